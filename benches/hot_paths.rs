@@ -0,0 +1,85 @@
+// Baselines for the paths a list request goes through, so changes such as streaming
+// or caching can be measured against them: `cargo bench`, and `cargo bench -- <name>`
+// for one group.
+
+use std::str::FromStr;
+
+use chrono::{TimeZone, Utc};
+use criterion::{criterion_group, criterion_main, BatchSize, Criterion, Throughput};
+use question_answer_api_rust::{
+    build_rocket, config::AppConfig, dto::AnswerResponse, empty_metadata, startup, AnswerDetail,
+    AnswerUuid, Question, QuestionUuid,
+};
+use rocket::local::asynchronous::Client;
+use tokio::runtime::Runtime;
+
+const QUESTIONS: usize = 10_000;
+const PER_PAGE: u64 = 200;
+const ANSWERS: usize = 5_000;
+
+// Through the router, the `GET /questions` handler and its JSON serialization, the way a
+// client lists questions, a full page at a time.
+fn list_questions(c: &mut Criterion) {
+    let runtime = Runtime::new().unwrap();
+    let client = runtime.block_on(async {
+        let state = startup::in_memory(AppConfig::from_env()).unwrap();
+        for n in 0..QUESTIONS {
+            state
+                .question_dao
+                .create_question(Question {
+                    title: format!("title {}", n),
+                    description: Some("description".to_owned()),
+                    metadata: empty_metadata(),
+                })
+                .await
+                .unwrap();
+        }
+        Client::untracked(build_rocket(state)).await.unwrap()
+    });
+    let uri = format!("/questions?per_page={}", PER_PAGE);
+
+    let mut group = c.benchmark_group("list_questions");
+    group.throughput(Throughput::Elements(PER_PAGE));
+    group.bench_function("in_memory_10k", |b| {
+        b.to_async(&runtime)
+            .iter(|| async { client.get(uri.as_str()).dispatch().await.into_bytes().await })
+    });
+    group.finish();
+}
+
+fn serialize_answers(c: &mut Criterion) {
+    let question_uuid = QuestionUuid::new_v4();
+    let answers: Vec<AnswerResponse> = (0..ANSWERS)
+        .map(|n| {
+            AnswerResponse::from(AnswerDetail {
+                answer_uuid: AnswerUuid::new_v4(),
+                question_uuid,
+                content: format!("answer {} {}", n, "c".repeat(200)),
+                created_at: Utc.with_ymd_and_hms(2023, 5, 20, 12, 0, 0).unwrap(),
+                updated_at: Utc.with_ymd_and_hms(2023, 5, 20, 12, 0, 0).unwrap(),
+                version: 1,
+            })
+        })
+        .collect();
+
+    let mut group = c.benchmark_group("serialize_answers");
+    group.throughput(Throughput::Elements(ANSWERS as u64));
+    group.bench_function("json_5k", |b| {
+        b.iter(|| serde_json::to_vec(&answers).unwrap())
+    });
+    group.finish();
+}
+
+fn parse_uuid(c: &mut Criterion) {
+    let uuid = QuestionUuid::new_v4().to_string();
+    c.bench_function("parse_uuid", |b| {
+        b.iter_batched(
+            || uuid.as_str(),
+            |uuid| QuestionUuid::from_str(uuid).unwrap(),
+            BatchSize::SmallInput,
+        )
+    });
+}
+
+criterion_group!(benches, list_questions, serialize_answers, parse_uuid);
+criterion_main!(benches);
@@ -0,0 +1,3 @@
+DROP TRIGGER IF EXISTS answers_cache_invalidation ON answers;
+DROP TRIGGER IF EXISTS questions_cache_invalidation ON questions;
+DROP FUNCTION IF EXISTS notify_cache_invalidation();
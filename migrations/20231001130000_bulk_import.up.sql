@@ -0,0 +1,24 @@
+-- Bulk imports set `app.skip_cache_invalidation` for their transaction and send a
+-- single notification instead of one per copied row.
+CREATE OR REPLACE FUNCTION notify_cache_invalidation() RETURNS trigger AS $$
+DECLARE
+    changed RECORD;
+BEGIN
+    IF current_setting('app.skip_cache_invalidation', true) = 'on' THEN
+        RETURN NULL;
+    END IF;
+
+    IF TG_OP = 'DELETE' THEN
+        changed := OLD;
+    ELSE
+        changed := NEW;
+    END IF;
+
+    PERFORM pg_notify(
+        'cache_invalidation',
+        json_build_object('table', TG_TABLE_NAME, 'question_uuid', changed.question_uuid)::text
+    );
+
+    RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
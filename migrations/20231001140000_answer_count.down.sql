@@ -0,0 +1,5 @@
+DROP TRIGGER IF EXISTS answers_decrement_answer_count ON answers;
+DROP TRIGGER IF EXISTS answers_increment_answer_count ON answers;
+DROP FUNCTION IF EXISTS decrement_answer_count();
+DROP FUNCTION IF EXISTS increment_answer_count();
+ALTER TABLE questions DROP COLUMN IF EXISTS answer_count;
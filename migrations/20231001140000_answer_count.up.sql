@@ -0,0 +1,43 @@
+-- Kept in sync by triggers so listing questions needs no COUNT per row. The triggers
+-- are per statement, so a bulk COPY of answers updates each question once.
+ALTER TABLE questions ADD COLUMN answer_count BIGINT NOT NULL DEFAULT 0;
+
+UPDATE questions SET answer_count = (
+    SELECT COUNT(*) FROM answers WHERE answers.question_uuid = questions.question_uuid
+);
+
+CREATE OR REPLACE FUNCTION increment_answer_count() RETURNS trigger AS $$
+BEGIN
+    UPDATE questions
+    SET answer_count = answer_count + inserted.count
+    FROM (
+        SELECT question_uuid, COUNT(*) AS count FROM new_answers GROUP BY question_uuid
+    ) AS inserted
+    WHERE questions.question_uuid = inserted.question_uuid;
+
+    RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE OR REPLACE FUNCTION decrement_answer_count() RETURNS trigger AS $$
+BEGIN
+    UPDATE questions
+    SET answer_count = answer_count - deleted.count
+    FROM (
+        SELECT question_uuid, COUNT(*) AS count FROM old_answers GROUP BY question_uuid
+    ) AS deleted
+    WHERE questions.question_uuid = deleted.question_uuid;
+
+    RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER answers_increment_answer_count
+    AFTER INSERT ON answers
+    REFERENCING NEW TABLE AS new_answers
+    FOR EACH STATEMENT EXECUTE FUNCTION increment_answer_count();
+
+CREATE TRIGGER answers_decrement_answer_count
+    AFTER DELETE ON answers
+    REFERENCING OLD TABLE AS old_answers
+    FOR EACH STATEMENT EXECUTE FUNCTION decrement_answer_count();
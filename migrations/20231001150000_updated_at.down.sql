@@ -0,0 +1,5 @@
+DROP TRIGGER IF EXISTS answers_set_updated_at ON answers;
+DROP TRIGGER IF EXISTS questions_set_updated_at ON questions;
+DROP FUNCTION IF EXISTS set_updated_at();
+ALTER TABLE answers DROP COLUMN IF EXISTS updated_at;
+ALTER TABLE questions DROP COLUMN IF EXISTS updated_at;
@@ -0,0 +1,25 @@
+-- Only edits to the content move updated_at; answer_count changes don't count as edits.
+ALTER TABLE questions ADD COLUMN updated_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP;
+ALTER TABLE answers ADD COLUMN updated_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP;
+
+UPDATE questions SET updated_at = created_at;
+UPDATE answers SET updated_at = created_at;
+
+CREATE OR REPLACE FUNCTION set_updated_at() RETURNS trigger AS $$
+BEGIN
+    NEW.updated_at := CURRENT_TIMESTAMP;
+    RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER questions_set_updated_at
+    BEFORE UPDATE ON questions
+    FOR EACH ROW
+    WHEN (OLD.title IS DISTINCT FROM NEW.title OR OLD.description IS DISTINCT FROM NEW.description)
+    EXECUTE FUNCTION set_updated_at();
+
+CREATE TRIGGER answers_set_updated_at
+    BEFORE UPDATE ON answers
+    FOR EACH ROW
+    WHEN (OLD.content IS DISTINCT FROM NEW.content)
+    EXECUTE FUNCTION set_updated_at();
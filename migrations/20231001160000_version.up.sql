@@ -0,0 +1,3 @@
+-- Bumped by every update; writers send the version they read and lose on a mismatch.
+ALTER TABLE questions ADD COLUMN version BIGINT NOT NULL DEFAULT 1;
+ALTER TABLE answers ADD COLUMN version BIGINT NOT NULL DEFAULT 1;
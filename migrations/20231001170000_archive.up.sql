@@ -0,0 +1,23 @@
+-- Questions without activity for a while are moved here, with their answers, to keep the
+-- live tables small. Archived rows are read-only.
+CREATE TABLE IF NOT EXISTS questions_archive (
+    question_uuid uuid PRIMARY KEY,
+    title VARCHAR(255) NOT NULL,
+    description VARCHAR(255) NOT NULL,
+    created_at TIMESTAMP NOT NULL,
+    updated_at TIMESTAMP NOT NULL,
+    answer_count BIGINT NOT NULL,
+    version BIGINT NOT NULL,
+    archived_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE TABLE IF NOT EXISTS answers_archive (
+    answer_uuid uuid PRIMARY KEY,
+    question_uuid uuid NOT NULL REFERENCES questions_archive (question_uuid) ON DELETE CASCADE,
+    content VARCHAR(255) NOT NULL,
+    created_at TIMESTAMP NOT NULL,
+    updated_at TIMESTAMP NOT NULL,
+    version BIGINT NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS answers_archive_question_uuid ON answers_archive (question_uuid);
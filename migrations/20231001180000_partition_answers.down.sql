@@ -0,0 +1,41 @@
+ALTER TABLE answers RENAME TO answers_partitioned;
+ALTER TABLE answers_partitioned RENAME CONSTRAINT answers_pkey TO answers_partitioned_pkey;
+ALTER TABLE answers_partitioned
+    RENAME CONSTRAINT answers_question_uuid_fkey TO answers_partitioned_question_uuid_fkey;
+ALTER INDEX answers_question_uuid RENAME TO answers_partitioned_question_uuid;
+
+CREATE TABLE answers (
+    answer_uuid uuid PRIMARY KEY DEFAULT gen_random_uuid(),
+    question_uuid uuid NOT NULL REFERENCES questions (question_uuid) ON DELETE CASCADE,
+    content VARCHAR(255) NOT NULL,
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    version BIGINT NOT NULL DEFAULT 1
+);
+
+INSERT INTO answers (answer_uuid, question_uuid, content, created_at, updated_at, version)
+SELECT answer_uuid, question_uuid, content, created_at, updated_at, version
+FROM answers_partitioned;
+
+DROP TABLE answers_partitioned;
+DROP FUNCTION IF EXISTS create_answer_partitions(TIMESTAMP, INT);
+
+CREATE TRIGGER answers_cache_invalidation
+    AFTER INSERT OR UPDATE OR DELETE ON answers
+    FOR EACH ROW EXECUTE FUNCTION notify_cache_invalidation();
+
+CREATE TRIGGER answers_increment_answer_count
+    AFTER INSERT ON answers
+    REFERENCING NEW TABLE AS new_answers
+    FOR EACH STATEMENT EXECUTE FUNCTION increment_answer_count();
+
+CREATE TRIGGER answers_decrement_answer_count
+    AFTER DELETE ON answers
+    REFERENCING OLD TABLE AS old_answers
+    FOR EACH STATEMENT EXECUTE FUNCTION decrement_answer_count();
+
+CREATE TRIGGER answers_set_updated_at
+    BEFORE UPDATE ON answers
+    FOR EACH ROW
+    WHEN (OLD.content IS DISTINCT FROM NEW.content)
+    EXECUTE FUNCTION set_updated_at();
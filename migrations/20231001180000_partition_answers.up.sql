@@ -0,0 +1,108 @@
+-- Answers are partitioned by the month they were created in. Postgres requires the
+-- partition key in the primary key, so answer_uuid alone is no longer enforced unique;
+-- it is still generated by gen_random_uuid().
+ALTER TABLE answers RENAME TO answers_unpartitioned;
+ALTER TABLE answers_unpartitioned RENAME CONSTRAINT answers_pkey TO answers_unpartitioned_pkey;
+ALTER TABLE answers_unpartitioned
+    RENAME CONSTRAINT answers_question_uuid_fkey TO answers_unpartitioned_question_uuid_fkey;
+
+-- created_at defaults to the statement time rather than the transaction start. An answer
+-- can only be inserted once its question is committed, so it is never older than its
+-- question, which lets reads skip the partitions from before the question existed.
+CREATE TABLE answers (
+    answer_uuid uuid NOT NULL DEFAULT gen_random_uuid(),
+    question_uuid uuid NOT NULL REFERENCES questions (question_uuid) ON DELETE CASCADE,
+    content VARCHAR(255) NOT NULL,
+    created_at TIMESTAMP NOT NULL DEFAULT statement_timestamp()::TIMESTAMP,
+    updated_at TIMESTAMP NOT NULL DEFAULT statement_timestamp()::TIMESTAMP,
+    version BIGINT NOT NULL DEFAULT 1,
+    PRIMARY KEY (answer_uuid, created_at)
+) PARTITION BY RANGE (created_at);
+
+CREATE INDEX answers_question_uuid ON answers (question_uuid, created_at);
+
+-- Creates the monthly partitions from `since` up to `months_ahead` months after the
+-- current one. Called by the API on a schedule so inserts always find a partition.
+CREATE OR REPLACE FUNCTION create_answer_partitions(since TIMESTAMP, months_ahead INT)
+RETURNS INT AS $$
+DECLARE
+    month TIMESTAMP := date_trunc('month', since);
+    last_month TIMESTAMP := date_trunc('month', LOCALTIMESTAMP) + make_interval(months => months_ahead);
+    created INT := 0;
+BEGIN
+    WHILE month <= last_month LOOP
+        IF to_regclass(format('answers_%s', to_char(month, 'YYYY_MM'))) IS NULL THEN
+            EXECUTE format(
+                'CREATE TABLE %I PARTITION OF answers FOR VALUES FROM (%L) TO (%L)',
+                format('answers_%s', to_char(month, 'YYYY_MM')),
+                month,
+                month + interval '1 month'
+            );
+            created := created + 1;
+        END IF;
+        month := month + interval '1 month';
+    END LOOP;
+
+    RETURN created;
+END;
+$$ LANGUAGE plpgsql;
+
+SELECT create_answer_partitions(
+    COALESCE((SELECT MIN(created_at) FROM answers_unpartitioned), LOCALTIMESTAMP),
+    2
+);
+
+-- Copied before the triggers exist so answer_count and the cache are left alone.
+INSERT INTO answers (answer_uuid, question_uuid, content, created_at, updated_at, version)
+SELECT answer_uuid, question_uuid, content, created_at, updated_at, version
+FROM answers_unpartitioned;
+
+DROP TABLE answers_unpartitioned;
+
+-- Row triggers are cloned onto every partition, where TG_TABLE_NAME is the partition's
+-- name; the table to report is passed as an argument instead.
+CREATE OR REPLACE FUNCTION notify_cache_invalidation() RETURNS trigger AS $$
+DECLARE
+    changed RECORD;
+BEGIN
+    IF current_setting('app.skip_cache_invalidation', true) = 'on' THEN
+        RETURN NULL;
+    END IF;
+
+    IF TG_OP = 'DELETE' THEN
+        changed := OLD;
+    ELSE
+        changed := NEW;
+    END IF;
+
+    PERFORM pg_notify(
+        'cache_invalidation',
+        json_build_object(
+            'table', COALESCE(TG_ARGV[0], TG_TABLE_NAME),
+            'question_uuid', changed.question_uuid
+        )::text
+    );
+
+    RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER answers_cache_invalidation
+    AFTER INSERT OR UPDATE OR DELETE ON answers
+    FOR EACH ROW EXECUTE FUNCTION notify_cache_invalidation('answers');
+
+CREATE TRIGGER answers_increment_answer_count
+    AFTER INSERT ON answers
+    REFERENCING NEW TABLE AS new_answers
+    FOR EACH STATEMENT EXECUTE FUNCTION increment_answer_count();
+
+CREATE TRIGGER answers_decrement_answer_count
+    AFTER DELETE ON answers
+    REFERENCING OLD TABLE AS old_answers
+    FOR EACH STATEMENT EXECUTE FUNCTION decrement_answer_count();
+
+CREATE TRIGGER answers_set_updated_at
+    BEFORE UPDATE ON answers
+    FOR EACH ROW
+    WHEN (OLD.content IS DISTINCT FROM NEW.content)
+    EXECUTE FUNCTION set_updated_at();
@@ -0,0 +1,17 @@
+-- Per-day activity for the dashboard, archived rows included. Refreshed by the API on a
+-- schedule and through `POST /admin/stats/refresh`; the unique index allows refreshing
+-- without blocking readers.
+CREATE MATERIALIZED VIEW IF NOT EXISTS daily_stats AS
+SELECT day, SUM(questions)::BIGINT AS questions, SUM(answers)::BIGINT AS answers
+FROM (
+    SELECT created_at::DATE AS day, 1 AS questions, 0 AS answers FROM questions
+    UNION ALL
+    SELECT created_at::DATE, 1, 0 FROM questions_archive
+    UNION ALL
+    SELECT created_at::DATE, 0, 1 FROM answers
+    UNION ALL
+    SELECT created_at::DATE, 0, 1 FROM answers_archive
+) AS activity
+GROUP BY day;
+
+CREATE UNIQUE INDEX IF NOT EXISTS daily_stats_day ON daily_stats (day);
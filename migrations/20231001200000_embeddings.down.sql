@@ -0,0 +1,2 @@
+DROP INDEX IF EXISTS questions_embedding;
+ALTER TABLE questions DROP COLUMN IF EXISTS embedding;
@@ -0,0 +1,14 @@
+-- Question embeddings for semantic search. pgvector is optional: without it the column
+-- is not created and the API leaves semantic search disabled.
+DO $$
+BEGIN
+    IF EXISTS (SELECT 1 FROM pg_available_extensions WHERE name = 'vector') THEN
+        CREATE EXTENSION IF NOT EXISTS vector;
+        EXECUTE 'ALTER TABLE questions ADD COLUMN IF NOT EXISTS embedding vector(1536)';
+        EXECUTE 'CREATE INDEX IF NOT EXISTS questions_embedding
+            ON questions USING hnsw (embedding vector_cosine_ops)';
+    ELSE
+        RAISE NOTICE 'pgvector is not installed, skipping question embeddings.';
+    END IF;
+END
+$$;
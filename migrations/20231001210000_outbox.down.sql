@@ -0,0 +1,6 @@
+DROP TRIGGER IF EXISTS answers_outbox_update ON answers;
+DROP TRIGGER IF EXISTS answers_outbox_insert_delete ON answers;
+DROP TRIGGER IF EXISTS questions_outbox_update ON questions;
+DROP TRIGGER IF EXISTS questions_outbox_insert_delete ON questions;
+DROP FUNCTION IF EXISTS write_outbox_event();
+DROP TABLE IF EXISTS outbox;
@@ -0,0 +1,63 @@
+-- Domain events, written by triggers in the same transaction as the change they describe,
+-- so an event exists exactly when its change was committed. The API relays them in `id`
+-- order and stamps `published_at`.
+CREATE TABLE IF NOT EXISTS outbox (
+    id BIGSERIAL PRIMARY KEY,
+    event_type TEXT NOT NULL,
+    aggregate_uuid UUID NOT NULL,
+    payload JSONB NOT NULL,
+    created_at TIMESTAMP NOT NULL DEFAULT LOCALTIMESTAMP,
+    published_at TIMESTAMP,
+    attempts INT NOT NULL DEFAULT 0,
+    last_error TEXT
+);
+
+CREATE INDEX IF NOT EXISTS outbox_pending ON outbox (id) WHERE published_at IS NULL;
+CREATE INDEX IF NOT EXISTS outbox_published_at ON outbox (published_at) WHERE published_at IS NOT NULL;
+
+-- TG_ARGV[0] names the entity ('question' or 'answer'); the row's `<entity>_uuid` is the
+-- aggregate. Rows removed by the archive job, which sets `app.archiving`, are reported as
+-- archived rather than deleted.
+CREATE OR REPLACE FUNCTION write_outbox_event() RETURNS trigger AS $$
+DECLARE
+    entity TEXT := TG_ARGV[0];
+    payload JSONB;
+    action TEXT;
+BEGIN
+    IF TG_OP = 'DELETE' THEN
+        payload := to_jsonb(OLD);
+        action := CASE WHEN current_setting('app.archiving', true) = 'on'
+            THEN 'archived' ELSE 'deleted' END;
+    ELSE
+        payload := to_jsonb(NEW);
+        action := CASE TG_OP WHEN 'INSERT' THEN 'created' ELSE 'updated' END;
+    END IF;
+
+    -- Embeddings are large and derived from the text, so they are left out.
+    payload := payload - 'embedding';
+
+    INSERT INTO outbox (event_type, aggregate_uuid, payload)
+    VALUES (entity || '_' || action, (payload ->> (entity || '_uuid'))::UUID, payload);
+
+    RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER questions_outbox_insert_delete
+    AFTER INSERT OR DELETE ON questions
+    FOR EACH ROW EXECUTE FUNCTION write_outbox_event('question');
+
+-- Only edits bump the version; answer counts and embeddings change without one.
+CREATE TRIGGER questions_outbox_update
+    AFTER UPDATE ON questions
+    FOR EACH ROW WHEN (OLD.version <> NEW.version)
+    EXECUTE FUNCTION write_outbox_event('question');
+
+CREATE TRIGGER answers_outbox_insert_delete
+    AFTER INSERT OR DELETE ON answers
+    FOR EACH ROW EXECUTE FUNCTION write_outbox_event('answer');
+
+CREATE TRIGGER answers_outbox_update
+    AFTER UPDATE ON answers
+    FOR EACH ROW WHEN (OLD.version <> NEW.version)
+    EXECUTE FUNCTION write_outbox_event('answer');
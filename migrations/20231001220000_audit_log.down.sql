@@ -0,0 +1,7 @@
+DROP TRIGGER IF EXISTS answers_audit_update ON answers;
+DROP TRIGGER IF EXISTS answers_audit_insert_delete ON answers;
+DROP TRIGGER IF EXISTS questions_audit_update ON questions;
+DROP TRIGGER IF EXISTS questions_audit_insert_delete ON questions;
+DROP FUNCTION IF EXISTS write_audit_log();
+DROP TABLE IF EXISTS audit_log;
+DROP FUNCTION IF EXISTS reject_audit_log_change();
@@ -0,0 +1,84 @@
+-- Every change to a question or answer, with who made it and the row before and after.
+-- Written by triggers in the same transaction as the change, and never updated or deleted.
+CREATE TABLE IF NOT EXISTS audit_log (
+    id BIGSERIAL PRIMARY KEY,
+    entity TEXT NOT NULL,
+    entity_uuid UUID NOT NULL,
+    action TEXT NOT NULL,
+    actor TEXT NOT NULL,
+    request_id TEXT,
+    before JSONB,
+    after JSONB,
+    created_at TIMESTAMP NOT NULL DEFAULT LOCALTIMESTAMP
+);
+
+CREATE INDEX IF NOT EXISTS audit_log_entity_uuid ON audit_log (entity_uuid, id);
+CREATE INDEX IF NOT EXISTS audit_log_actor ON audit_log (actor, id);
+
+-- The API sets `app.actor` and `app.request_id` on its connection before each write.
+-- Changes made by hand are attributed to the database user. Rows removed by the archive
+-- job are recorded as archived, as in the outbox.
+CREATE OR REPLACE FUNCTION write_audit_log() RETURNS trigger AS $$
+DECLARE
+    entity TEXT := TG_ARGV[0];
+    before JSONB;
+    after JSONB;
+    action TEXT;
+BEGIN
+    IF TG_OP <> 'INSERT' THEN
+        before := to_jsonb(OLD) - 'embedding';
+    END IF;
+    IF TG_OP <> 'DELETE' THEN
+        after := to_jsonb(NEW) - 'embedding';
+    END IF;
+
+    action := CASE TG_OP
+        WHEN 'INSERT' THEN 'created'
+        WHEN 'UPDATE' THEN 'updated'
+        WHEN 'DELETE' THEN CASE WHEN current_setting('app.archiving', true) = 'on'
+            THEN 'archived' ELSE 'deleted' END
+    END;
+
+    INSERT INTO audit_log (entity, entity_uuid, action, actor, request_id, before, after)
+    VALUES (
+        entity,
+        (COALESCE(after, before) ->> (entity || '_uuid'))::UUID,
+        action,
+        COALESCE(NULLIF(current_setting('app.actor', true), ''), 'db:' || current_user),
+        NULLIF(current_setting('app.request_id', true), ''),
+        before,
+        after
+    );
+
+    RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER questions_audit_insert_delete
+    AFTER INSERT OR DELETE ON questions
+    FOR EACH ROW EXECUTE FUNCTION write_audit_log('question');
+
+-- Answer counts and embeddings are derived and change without a version bump.
+CREATE TRIGGER questions_audit_update
+    AFTER UPDATE ON questions
+    FOR EACH ROW WHEN (OLD.version <> NEW.version)
+    EXECUTE FUNCTION write_audit_log('question');
+
+CREATE TRIGGER answers_audit_insert_delete
+    AFTER INSERT OR DELETE ON answers
+    FOR EACH ROW EXECUTE FUNCTION write_audit_log('answer');
+
+CREATE TRIGGER answers_audit_update
+    AFTER UPDATE ON answers
+    FOR EACH ROW WHEN (OLD.version <> NEW.version)
+    EXECUTE FUNCTION write_audit_log('answer');
+
+CREATE OR REPLACE FUNCTION reject_audit_log_change() RETURNS trigger AS $$
+BEGIN
+    RAISE EXCEPTION 'audit_log is append-only';
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER audit_log_append_only
+    BEFORE UPDATE OR DELETE OR TRUNCATE ON audit_log
+    FOR EACH STATEMENT EXECUTE FUNCTION reject_audit_log_change();
@@ -0,0 +1,4 @@
+UPDATE questions SET description = '' WHERE description IS NULL;
+UPDATE questions_archive SET description = '' WHERE description IS NULL;
+ALTER TABLE questions ALTER COLUMN description SET NOT NULL;
+ALTER TABLE questions_archive ALTER COLUMN description SET NOT NULL;
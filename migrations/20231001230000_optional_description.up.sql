@@ -0,0 +1,3 @@
+-- Questions may be asked with a title alone.
+ALTER TABLE questions ALTER COLUMN description DROP NOT NULL;
+ALTER TABLE questions_archive ALTER COLUMN description DROP NOT NULL;
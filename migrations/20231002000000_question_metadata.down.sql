@@ -0,0 +1,10 @@
+DROP TRIGGER IF EXISTS questions_set_updated_at ON questions;
+CREATE TRIGGER questions_set_updated_at
+    BEFORE UPDATE ON questions
+    FOR EACH ROW
+    WHEN (OLD.title IS DISTINCT FROM NEW.title OR OLD.description IS DISTINCT FROM NEW.description)
+    EXECUTE FUNCTION set_updated_at();
+
+DROP INDEX IF EXISTS questions_metadata;
+ALTER TABLE questions_archive DROP COLUMN IF EXISTS metadata;
+ALTER TABLE questions DROP COLUMN IF EXISTS metadata;
@@ -0,0 +1,18 @@
+-- A JSON object clients attach to questions, e.g. the system a question came from and its
+-- id there. The GIN index serves the `metadata @> ...` containment filter.
+ALTER TABLE questions ADD COLUMN metadata JSONB NOT NULL DEFAULT '{}';
+ALTER TABLE questions_archive ADD COLUMN metadata JSONB NOT NULL DEFAULT '{}';
+
+CREATE INDEX IF NOT EXISTS questions_metadata ON questions USING GIN (metadata jsonb_path_ops);
+
+-- Editing the metadata is an edit like any other.
+DROP TRIGGER IF EXISTS questions_set_updated_at ON questions;
+CREATE TRIGGER questions_set_updated_at
+    BEFORE UPDATE ON questions
+    FOR EACH ROW
+    WHEN (
+        OLD.title IS DISTINCT FROM NEW.title
+        OR OLD.description IS DISTINCT FROM NEW.description
+        OR OLD.metadata IS DISTINCT FROM NEW.metadata
+    )
+    EXECUTE FUNCTION set_updated_at();
@@ -0,0 +1,16 @@
+-- Who is emailed when a question gets an answer. There are no user accounts, so
+-- subscribers are email addresses; the author subscribes by creating the question with
+-- `author_email`. Subscriptions go with their question, also when it is archived.
+CREATE TABLE IF NOT EXISTS question_subscriptions (
+    question_uuid UUID NOT NULL REFERENCES questions (question_uuid) ON DELETE CASCADE,
+    email TEXT NOT NULL,
+    created_at TIMESTAMP NOT NULL DEFAULT LOCALTIMESTAMP,
+    PRIMARY KEY (question_uuid, email)
+);
+
+-- Addresses that asked for no emails at all. Their subscriptions are kept, so opting back
+-- in restores them.
+CREATE TABLE IF NOT EXISTS email_opt_outs (
+    email TEXT PRIMARY KEY,
+    created_at TIMESTAMP NOT NULL DEFAULT LOCALTIMESTAMP
+);
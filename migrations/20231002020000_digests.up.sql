@@ -0,0 +1,8 @@
+-- Addresses that asked for a daily or weekly summary of new answers on the questions they
+-- subscribe to, and of the questions that got the most answers. A digest covers the
+-- answers created after `last_sent_at`, which starts when the address signs up.
+CREATE TABLE IF NOT EXISTS digest_preferences (
+    email TEXT PRIMARY KEY,
+    frequency TEXT NOT NULL CHECK (frequency IN ('daily', 'weekly')),
+    last_sent_at TIMESTAMP NOT NULL DEFAULT LOCALTIMESTAMP
+);
@@ -0,0 +1,2 @@
+DROP TABLE IF EXISTS push_subscription_questions;
+DROP TABLE IF EXISTS push_subscriptions;
@@ -0,0 +1,18 @@
+-- Browsers pushed to when a question gets an answer. A subscription is the Push API
+-- endpoint with the keys its payloads are encrypted for; there are no user accounts, so
+-- each one lists the questions it follows itself.
+CREATE TABLE IF NOT EXISTS push_subscriptions (
+    endpoint TEXT PRIMARY KEY,
+    p256dh TEXT NOT NULL,
+    auth TEXT NOT NULL,
+    created_at TIMESTAMP NOT NULL DEFAULT LOCALTIMESTAMP
+);
+
+CREATE TABLE IF NOT EXISTS push_subscription_questions (
+    endpoint TEXT NOT NULL REFERENCES push_subscriptions (endpoint) ON DELETE CASCADE,
+    question_uuid UUID NOT NULL REFERENCES questions (question_uuid) ON DELETE CASCADE,
+    PRIMARY KEY (endpoint, question_uuid)
+);
+
+CREATE INDEX IF NOT EXISTS push_subscription_questions_question_uuid_idx
+    ON push_subscription_questions (question_uuid);
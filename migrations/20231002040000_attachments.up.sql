@@ -0,0 +1,20 @@
+-- Files attached to a question, or to one of its answers when answer_uuid is set. The
+-- bytes are in the object store under storage_key. Answers are partitioned, so
+-- answer_uuid cannot reference them; attachments go with their question, also when it
+-- is archived.
+CREATE TABLE IF NOT EXISTS attachments (
+    attachment_uuid UUID PRIMARY KEY,
+    question_uuid UUID NOT NULL REFERENCES questions (question_uuid) ON DELETE CASCADE,
+    answer_uuid UUID,
+    filename TEXT NOT NULL,
+    content_type TEXT NOT NULL,
+    size_bytes BIGINT NOT NULL,
+    storage_key TEXT NOT NULL,
+    -- Pending until a pre-signed upload is completed; multipart uploads are ready at once.
+    status TEXT NOT NULL DEFAULT 'pending' CHECK (status IN ('pending', 'ready')),
+    created_at TIMESTAMP NOT NULL DEFAULT LOCALTIMESTAMP
+);
+
+CREATE INDEX IF NOT EXISTS attachments_question_uuid_idx ON attachments (question_uuid, created_at);
+CREATE INDEX IF NOT EXISTS attachments_answer_uuid_idx ON attachments (answer_uuid)
+    WHERE answer_uuid IS NOT NULL;
@@ -0,0 +1,4 @@
+-- Resized copies of image attachments, e.g. thumbnails, as a JSON array of
+-- { name, width, height, content_type, size_bytes, storage_key }. Filled in by a
+-- background job after the upload, so it starts out empty.
+ALTER TABLE attachments ADD COLUMN IF NOT EXISTS variants JSONB NOT NULL DEFAULT '[]';
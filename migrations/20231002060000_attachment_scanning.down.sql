@@ -0,0 +1,4 @@
+DELETE FROM attachments WHERE status IN ('scanning', 'quarantined');
+ALTER TABLE attachments DROP CONSTRAINT IF EXISTS attachments_status_check;
+ALTER TABLE attachments ADD CONSTRAINT attachments_status_check
+    CHECK (status IN ('pending', 'ready'));
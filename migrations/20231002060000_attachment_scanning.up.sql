@@ -0,0 +1,5 @@
+-- Uploads wait in 'scanning' while a content scanner is configured; ones it flags are
+-- 'quarantined' and kept in the store for moderators, but never listed or served.
+ALTER TABLE attachments DROP CONSTRAINT IF EXISTS attachments_status_check;
+ALTER TABLE attachments ADD CONSTRAINT attachments_status_check
+    CHECK (status IN ('pending', 'scanning', 'ready', 'quarantined'));
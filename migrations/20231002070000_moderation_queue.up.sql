@@ -0,0 +1,12 @@
+-- Questions and answers a moderation provider flagged on create. They are not written to
+-- questions or answers until a moderator approves them; content is the create request
+-- as JSON, tagged with its kind.
+CREATE TABLE IF NOT EXISTS moderation_queue (
+    moderation_uuid UUID PRIMARY KEY,
+    content JSONB NOT NULL,
+    score REAL NOT NULL,
+    categories TEXT[] NOT NULL DEFAULT '{}',
+    created_at TIMESTAMP NOT NULL DEFAULT LOCALTIMESTAMP
+);
+
+CREATE INDEX IF NOT EXISTS moderation_queue_created_at_idx ON moderation_queue (created_at);
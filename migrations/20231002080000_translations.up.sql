@@ -0,0 +1,13 @@
+-- Machine translations of questions and answers, made on demand and kept so each is only
+-- paid for once. entity is 'question' or 'answer'; fields maps a field name such as
+-- title to its translation. source_version is the version of the row that was
+-- translated, so edited rows are translated again.
+CREATE TABLE IF NOT EXISTS translations (
+    entity TEXT NOT NULL,
+    entity_uuid UUID NOT NULL,
+    language TEXT NOT NULL,
+    source_version BIGINT NOT NULL,
+    fields JSONB NOT NULL,
+    created_at TIMESTAMP NOT NULL DEFAULT LOCALTIMESTAMP,
+    PRIMARY KEY (entity, entity_uuid, language)
+);
@@ -0,0 +1,10 @@
+-- OpenGraph metadata of URLs found in questions, fetched in the background so clients
+-- can render link cards. Shared by every question linking to the same URL.
+CREATE TABLE IF NOT EXISTS link_previews (
+    url TEXT PRIMARY KEY,
+    title TEXT,
+    description TEXT,
+    image_url TEXT,
+    site_name TEXT,
+    fetched_at TIMESTAMP NOT NULL DEFAULT LOCALTIMESTAMP
+);
@@ -0,0 +1,4 @@
+-- Full text search over questions, for `/questions/search` when no search engine is
+-- configured. The expression must match the one in the search queries.
+CREATE INDEX IF NOT EXISTS questions_search ON questions
+    USING GIN (to_tsvector('english', title || ' ' || coalesce(description, '')));
@@ -0,0 +1,7 @@
+-- Idempotency keys of questions received from message queues, so a redelivered or resent
+-- message does not create the question twice. Kept after the question is deleted.
+CREATE TABLE IF NOT EXISTS ingested_questions (
+    idempotency_key TEXT PRIMARY KEY,
+    question_uuid UUID NOT NULL,
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
@@ -0,0 +1,3 @@
+-- The sender's IP, user agent and referrer of content held as spam, reported along with
+-- the moderator's decision so the spam checker learns from it.
+ALTER TABLE moderation_queue ADD COLUMN IF NOT EXISTS spam_context JSONB;
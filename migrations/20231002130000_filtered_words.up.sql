@@ -0,0 +1,6 @@
+-- Words the word filter rejects or masks in new and edited questions and answers, managed
+-- by admins at runtime. Stored lowercased.
+CREATE TABLE IF NOT EXISTS filtered_words (
+    word TEXT PRIMARY KEY,
+    created_at TIMESTAMP NOT NULL DEFAULT LOCALTIMESTAMP
+);
@@ -0,0 +1,10 @@
+-- REST hook subscriptions, e.g. from Zapier or Make: each outbox event of `event_type`
+-- is POSTed to `target_url` as the relay publishes it.
+CREATE TABLE IF NOT EXISTS rest_hooks (
+    hook_uuid UUID PRIMARY KEY,
+    target_url TEXT NOT NULL,
+    event_type TEXT NOT NULL,
+    created_at TIMESTAMP NOT NULL DEFAULT LOCALTIMESTAMP
+);
+
+CREATE INDEX IF NOT EXISTS rest_hooks_event_type_idx ON rest_hooks (event_type);
@@ -0,0 +1,5 @@
+-- Lets autocomplete find the titles containing the typed text with an index scan, for
+-- text of three characters or more.
+CREATE EXTENSION IF NOT EXISTS pg_trgm;
+
+CREATE INDEX IF NOT EXISTS questions_title_trgm_idx ON questions USING GIN (lower(title) gin_trgm_ops);
@@ -0,0 +1,11 @@
+-- Accounts provisioned by an identity provider over SCIM. There is no sign in; the
+-- address in `user_name` stops getting email once the account is deactivated.
+CREATE TABLE IF NOT EXISTS users (
+    user_uuid UUID PRIMARY KEY,
+    user_name TEXT NOT NULL UNIQUE,
+    external_id TEXT,
+    display_name TEXT,
+    active BOOLEAN NOT NULL DEFAULT TRUE,
+    created_at TIMESTAMP NOT NULL DEFAULT LOCALTIMESTAMP,
+    updated_at TIMESTAMP NOT NULL DEFAULT LOCALTIMESTAMP
+);
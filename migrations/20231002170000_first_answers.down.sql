@@ -0,0 +1 @@
+DROP MATERIALIZED VIEW IF EXISTS first_answers;
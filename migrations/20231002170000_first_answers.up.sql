@@ -0,0 +1,19 @@
+-- How long each answered question waited for its first answer, archived rows included,
+-- for the admin dashboard. Refreshed together with `daily_stats`.
+CREATE MATERIALIZED VIEW IF NOT EXISTS first_answers AS
+SELECT asked.question_uuid,
+       asked.created_at::DATE AS day,
+       EXTRACT(EPOCH FROM MIN(answered.created_at) - asked.created_at)::DOUBLE PRECISION AS seconds
+FROM (
+    SELECT question_uuid, created_at FROM questions
+    UNION ALL
+    SELECT question_uuid, created_at FROM questions_archive
+) AS asked
+JOIN (
+    SELECT question_uuid, created_at FROM answers
+    UNION ALL
+    SELECT question_uuid, created_at FROM answers_archive
+) AS answered ON answered.question_uuid = asked.question_uuid
+GROUP BY asked.question_uuid, asked.created_at;
+
+CREATE UNIQUE INDEX IF NOT EXISTS first_answers_question_uuid ON first_answers (question_uuid);
@@ -0,0 +1,7 @@
+DROP TRIGGER IF EXISTS answers_update_last_activity_at ON answers;
+DROP TRIGGER IF EXISTS answers_insert_last_activity_at ON answers;
+DROP FUNCTION IF EXISTS answers_bump_last_activity_at();
+DROP TRIGGER IF EXISTS questions_set_last_activity_at ON questions;
+DROP FUNCTION IF EXISTS set_last_activity_at();
+ALTER TABLE questions_archive DROP COLUMN IF EXISTS last_activity_at;
+ALTER TABLE questions DROP COLUMN IF EXISTS last_activity_at;
@@ -0,0 +1,55 @@
+-- When a question last saw activity: it was asked or edited, or one of its answers was
+-- posted or edited. Kept by triggers in the same transaction as the change, for
+-- `?sort=activity`. Answers move it to their own timestamps, so restored answers keep
+-- the history they had.
+ALTER TABLE questions ADD COLUMN last_activity_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP;
+ALTER TABLE questions_archive ADD COLUMN last_activity_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP;
+
+UPDATE questions SET last_activity_at = GREATEST(
+    updated_at,
+    (SELECT MAX(updated_at) FROM answers WHERE answers.question_uuid = questions.question_uuid)
+);
+UPDATE questions_archive SET last_activity_at = GREATEST(
+    updated_at,
+    (
+        SELECT MAX(updated_at) FROM answers_archive
+        WHERE answers_archive.question_uuid = questions_archive.question_uuid
+    )
+);
+
+CREATE OR REPLACE FUNCTION set_last_activity_at() RETURNS trigger AS $$
+BEGIN
+    NEW.last_activity_at := CURRENT_TIMESTAMP;
+    RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER questions_set_last_activity_at
+    BEFORE UPDATE ON questions
+    FOR EACH ROW
+    WHEN (OLD.title IS DISTINCT FROM NEW.title OR OLD.description IS DISTINCT FROM NEW.description)
+    EXECUTE FUNCTION set_last_activity_at();
+
+-- Per statement like answer_count, so a bulk COPY of answers updates each question once.
+CREATE OR REPLACE FUNCTION answers_bump_last_activity_at() RETURNS trigger AS $$
+BEGIN
+    UPDATE questions
+    SET last_activity_at = GREATEST(last_activity_at, changed.latest)
+    FROM (
+        SELECT question_uuid, MAX(updated_at) AS latest FROM changed_answers GROUP BY question_uuid
+    ) AS changed
+    WHERE questions.question_uuid = changed.question_uuid;
+
+    RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER answers_insert_last_activity_at
+    AFTER INSERT ON answers
+    REFERENCING NEW TABLE AS changed_answers
+    FOR EACH STATEMENT EXECUTE FUNCTION answers_bump_last_activity_at();
+
+CREATE TRIGGER answers_update_last_activity_at
+    AFTER UPDATE ON answers
+    REFERENCING NEW TABLE AS changed_answers
+    FOR EACH STATEMENT EXECUTE FUNCTION answers_bump_last_activity_at();
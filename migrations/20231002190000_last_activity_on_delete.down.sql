@@ -0,0 +1,2 @@
+DROP TRIGGER IF EXISTS answers_delete_last_activity_at ON answers;
+DROP FUNCTION IF EXISTS answers_deleted_bump_last_activity_at();
@@ -0,0 +1,16 @@
+-- Deleting an answer is activity on its question too. Per statement like the other
+-- answer triggers; rows whose question is being deleted with them match nothing.
+CREATE OR REPLACE FUNCTION answers_deleted_bump_last_activity_at() RETURNS trigger AS $$
+BEGIN
+    UPDATE questions
+    SET last_activity_at = GREATEST(last_activity_at, CURRENT_TIMESTAMP)
+    WHERE question_uuid IN (SELECT question_uuid FROM deleted_answers);
+
+    RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER answers_delete_last_activity_at
+    AFTER DELETE ON answers
+    REFERENCING OLD TABLE AS deleted_answers
+    FOR EACH STATEMENT EXECUTE FUNCTION answers_deleted_bump_last_activity_at();
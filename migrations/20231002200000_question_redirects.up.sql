@@ -0,0 +1,9 @@
+-- Questions merged into another one, so links to them keep working. There is no foreign
+-- key to the target: it may be archived or deleted later, which ends the redirect.
+CREATE TABLE IF NOT EXISTS question_redirects (
+    source_uuid UUID PRIMARY KEY,
+    target_uuid UUID NOT NULL,
+    merged_at TIMESTAMP NOT NULL DEFAULT LOCALTIMESTAMP
+);
+
+CREATE INDEX IF NOT EXISTS question_redirects_target_uuid_idx ON question_redirects (target_uuid);
@@ -0,0 +1,14 @@
+-- Translations of questions people submitted, one per question and language, served by
+-- `?lang=` ahead of machine translations. language is lowercase, e.g. es or pt-br;
+-- question_version is the version of the question that was translated, so outdated
+-- translations can be told apart.
+CREATE TABLE IF NOT EXISTS question_translations (
+    question_uuid UUID NOT NULL REFERENCES questions (question_uuid) ON DELETE CASCADE,
+    language TEXT NOT NULL,
+    title TEXT NOT NULL,
+    description TEXT,
+    question_version BIGINT NOT NULL,
+    created_at TIMESTAMP NOT NULL DEFAULT LOCALTIMESTAMP,
+    updated_at TIMESTAMP NOT NULL DEFAULT LOCALTIMESTAMP,
+    PRIMARY KEY (question_uuid, language)
+);
@@ -0,0 +1,58 @@
+DROP MATERIALIZED VIEW IF EXISTS first_answers;
+DROP MATERIALIZED VIEW IF EXISTS daily_stats;
+
+CREATE OR REPLACE FUNCTION answers_bump_last_activity_at() RETURNS trigger AS $$
+BEGIN
+    UPDATE questions
+    SET last_activity_at = GREATEST(last_activity_at, changed.latest)
+    FROM (
+        SELECT question_uuid, MAX(updated_at) AS latest FROM changed_answers GROUP BY question_uuid
+    ) AS changed
+    WHERE questions.question_uuid = changed.question_uuid;
+
+    RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+ALTER TABLE questions_archive
+    ALTER COLUMN created_at TYPE TIMESTAMP USING created_at AT TIME ZONE 'UTC',
+    ALTER COLUMN updated_at TYPE TIMESTAMP USING updated_at AT TIME ZONE 'UTC',
+    ALTER COLUMN last_activity_at TYPE TIMESTAMP USING last_activity_at AT TIME ZONE 'UTC';
+
+ALTER TABLE questions
+    ALTER COLUMN created_at TYPE TIMESTAMP USING created_at AT TIME ZONE 'UTC',
+    ALTER COLUMN updated_at TYPE TIMESTAMP USING updated_at AT TIME ZONE 'UTC',
+    ALTER COLUMN last_activity_at TYPE TIMESTAMP USING last_activity_at AT TIME ZONE 'UTC';
+
+CREATE MATERIALIZED VIEW IF NOT EXISTS daily_stats AS
+SELECT day, SUM(questions)::BIGINT AS questions, SUM(answers)::BIGINT AS answers
+FROM (
+    SELECT created_at::DATE AS day, 1 AS questions, 0 AS answers FROM questions
+    UNION ALL
+    SELECT created_at::DATE, 1, 0 FROM questions_archive
+    UNION ALL
+    SELECT created_at::DATE, 0, 1 FROM answers
+    UNION ALL
+    SELECT created_at::DATE, 0, 1 FROM answers_archive
+) AS activity
+GROUP BY day;
+
+CREATE UNIQUE INDEX IF NOT EXISTS daily_stats_day ON daily_stats (day);
+
+CREATE MATERIALIZED VIEW IF NOT EXISTS first_answers AS
+SELECT asked.question_uuid,
+       asked.created_at::DATE AS day,
+       EXTRACT(EPOCH FROM MIN(answered.created_at) - asked.created_at)::DOUBLE PRECISION AS seconds
+FROM (
+    SELECT question_uuid, created_at FROM questions
+    UNION ALL
+    SELECT question_uuid, created_at FROM questions_archive
+) AS asked
+JOIN (
+    SELECT question_uuid, created_at FROM answers
+    UNION ALL
+    SELECT question_uuid, created_at FROM answers_archive
+) AS answered ON answered.question_uuid = asked.question_uuid
+GROUP BY asked.question_uuid, asked.created_at;
+
+CREATE UNIQUE INDEX IF NOT EXISTS first_answers_question_uuid ON first_answers (question_uuid);
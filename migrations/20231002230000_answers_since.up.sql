@@ -0,0 +1,4 @@
+-- Answer reads skip the partitions from before a question was created. Merges and imports
+-- can give a question answers older than itself, so they record here how far back its
+-- answers go; NULL when none predate the question.
+ALTER TABLE questions ADD COLUMN answers_since TIMESTAMPTZ;
@@ -0,0 +1 @@
+DROP INDEX IF EXISTS questions_unanswered_idx;
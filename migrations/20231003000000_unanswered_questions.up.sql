@@ -0,0 +1,5 @@
+-- Lists the questions waiting for an answer, oldest first, without reading the answered
+-- ones. `answer_count` is kept by the triggers of the answer_count migration.
+CREATE INDEX IF NOT EXISTS questions_unanswered_idx
+    ON questions (created_at, question_uuid)
+    WHERE answer_count = 0;
@@ -0,0 +1,3 @@
+DROP TRIGGER IF EXISTS answers_decrement_answer_count;
+DROP TRIGGER IF EXISTS answers_increment_answer_count;
+ALTER TABLE questions DROP COLUMN answer_count;
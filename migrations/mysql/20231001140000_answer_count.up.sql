@@ -0,0 +1,12 @@
+-- Kept in sync by triggers so listing questions needs no COUNT per row.
+ALTER TABLE questions ADD COLUMN answer_count BIGINT NOT NULL DEFAULT 0;
+
+UPDATE questions SET answer_count = (
+    SELECT COUNT(*) FROM answers WHERE answers.question_uuid = questions.question_uuid
+);
+
+CREATE TRIGGER answers_increment_answer_count AFTER INSERT ON answers FOR EACH ROW
+    UPDATE questions SET answer_count = answer_count + 1 WHERE question_uuid = NEW.question_uuid;
+
+CREATE TRIGGER answers_decrement_answer_count AFTER DELETE ON answers FOR EACH ROW
+    UPDATE questions SET answer_count = answer_count - 1 WHERE question_uuid = OLD.question_uuid;
@@ -0,0 +1,16 @@
+-- Only edits to the content move updated_at; answer_count changes don't count as edits.
+ALTER TABLE questions ADD COLUMN updated_at DATETIME(6) NOT NULL DEFAULT CURRENT_TIMESTAMP(6);
+ALTER TABLE answers ADD COLUMN updated_at DATETIME(6) NOT NULL DEFAULT CURRENT_TIMESTAMP(6);
+
+UPDATE questions SET updated_at = created_at;
+UPDATE answers SET updated_at = created_at;
+
+CREATE TRIGGER questions_set_updated_at BEFORE UPDATE ON questions FOR EACH ROW
+    SET NEW.updated_at = IF(
+        NEW.title <> OLD.title OR NEW.description <> OLD.description,
+        CURRENT_TIMESTAMP(6),
+        OLD.updated_at
+    );
+
+CREATE TRIGGER answers_set_updated_at BEFORE UPDATE ON answers FOR EACH ROW
+    SET NEW.updated_at = IF(NEW.content <> OLD.content, CURRENT_TIMESTAMP(6), OLD.updated_at);
@@ -0,0 +1,22 @@
+-- Questions without activity for a while are moved here, with their answers, to keep the
+-- live tables small. Archived rows are read-only.
+CREATE TABLE IF NOT EXISTS questions_archive (
+    question_uuid CHAR(36) PRIMARY KEY,
+    title VARCHAR(255) NOT NULL,
+    description VARCHAR(255) NOT NULL,
+    created_at DATETIME(6) NOT NULL,
+    updated_at DATETIME(6) NOT NULL,
+    answer_count BIGINT NOT NULL,
+    version BIGINT NOT NULL,
+    archived_at DATETIME(6) NOT NULL DEFAULT CURRENT_TIMESTAMP(6)
+);
+
+CREATE TABLE IF NOT EXISTS answers_archive (
+    answer_uuid CHAR(36) PRIMARY KEY,
+    question_uuid CHAR(36) NOT NULL,
+    content VARCHAR(255) NOT NULL,
+    created_at DATETIME(6) NOT NULL,
+    updated_at DATETIME(6) NOT NULL,
+    version BIGINT NOT NULL,
+    FOREIGN KEY (question_uuid) REFERENCES questions_archive (question_uuid) ON DELETE CASCADE
+);
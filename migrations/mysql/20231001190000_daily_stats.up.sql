@@ -0,0 +1,13 @@
+-- Computed on every read; only Postgres materializes it.
+CREATE OR REPLACE VIEW daily_stats AS
+SELECT day, CAST(SUM(questions) AS SIGNED) AS questions, CAST(SUM(answers) AS SIGNED) AS answers
+FROM (
+    SELECT DATE(created_at) AS day, 1 AS questions, 0 AS answers FROM questions
+    UNION ALL
+    SELECT DATE(created_at), 1, 0 FROM questions_archive
+    UNION ALL
+    SELECT DATE(created_at), 0, 1 FROM answers
+    UNION ALL
+    SELECT DATE(created_at), 0, 1 FROM answers_archive
+) AS activity
+GROUP BY day;
@@ -0,0 +1,12 @@
+DROP TRIGGER IF EXISTS questions_set_updated_at;
+CREATE TRIGGER questions_set_updated_at BEFORE UPDATE ON questions FOR EACH ROW
+    SET NEW.updated_at = IF(
+        NEW.title <> OLD.title OR NEW.description <> OLD.description,
+        CURRENT_TIMESTAMP(6),
+        OLD.updated_at
+    );
+
+UPDATE questions SET description = '' WHERE description IS NULL;
+UPDATE questions_archive SET description = '' WHERE description IS NULL;
+ALTER TABLE questions MODIFY description VARCHAR(255) NOT NULL;
+ALTER TABLE questions_archive MODIFY description VARCHAR(255) NOT NULL;
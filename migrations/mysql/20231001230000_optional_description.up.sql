@@ -0,0 +1,12 @@
+-- Questions may be asked with a title alone.
+ALTER TABLE questions MODIFY description VARCHAR(255) NULL;
+ALTER TABLE questions_archive MODIFY description VARCHAR(255) NULL;
+
+-- `<>` is never true against NULL, so compare null-safely.
+DROP TRIGGER IF EXISTS questions_set_updated_at;
+CREATE TRIGGER questions_set_updated_at BEFORE UPDATE ON questions FOR EACH ROW
+    SET NEW.updated_at = IF(
+        NEW.title <> OLD.title OR NOT (NEW.description <=> OLD.description),
+        CURRENT_TIMESTAMP(6),
+        OLD.updated_at
+    );
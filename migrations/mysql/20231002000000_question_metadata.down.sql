@@ -0,0 +1,10 @@
+DROP TRIGGER IF EXISTS questions_set_updated_at;
+CREATE TRIGGER questions_set_updated_at BEFORE UPDATE ON questions FOR EACH ROW
+    SET NEW.updated_at = IF(
+        NEW.title <> OLD.title OR NOT (NEW.description <=> OLD.description),
+        CURRENT_TIMESTAMP(6),
+        OLD.updated_at
+    );
+
+ALTER TABLE questions_archive DROP COLUMN metadata;
+ALTER TABLE questions DROP COLUMN metadata;
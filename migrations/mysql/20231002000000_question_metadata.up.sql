@@ -0,0 +1,14 @@
+-- A JSON object clients attach to questions.
+ALTER TABLE questions ADD COLUMN metadata JSON NOT NULL DEFAULT ('{}');
+ALTER TABLE questions_archive ADD COLUMN metadata JSON NOT NULL DEFAULT ('{}');
+
+-- Editing the metadata is an edit like any other.
+DROP TRIGGER IF EXISTS questions_set_updated_at;
+CREATE TRIGGER questions_set_updated_at BEFORE UPDATE ON questions FOR EACH ROW
+    SET NEW.updated_at = IF(
+        NEW.title <> OLD.title
+            OR NOT (NEW.description <=> OLD.description)
+            OR NOT (NEW.metadata <=> OLD.metadata),
+        CURRENT_TIMESTAMP(6),
+        OLD.updated_at
+    );
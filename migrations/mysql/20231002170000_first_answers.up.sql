@@ -0,0 +1,16 @@
+-- Computed on every read; only Postgres materializes it.
+CREATE OR REPLACE VIEW first_answers AS
+SELECT asked.question_uuid,
+       DATE(asked.created_at) AS day,
+       CAST(TIMESTAMPDIFF(MICROSECOND, asked.created_at, MIN(answered.created_at)) AS DOUBLE) / 1000000 AS seconds
+FROM (
+    SELECT question_uuid, created_at FROM questions
+    UNION ALL
+    SELECT question_uuid, created_at FROM questions_archive
+) AS asked
+JOIN (
+    SELECT question_uuid, created_at FROM answers
+    UNION ALL
+    SELECT question_uuid, created_at FROM answers_archive
+) AS answered ON answered.question_uuid = asked.question_uuid
+GROUP BY asked.question_uuid, asked.created_at;
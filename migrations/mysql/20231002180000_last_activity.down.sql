@@ -0,0 +1,5 @@
+DROP TRIGGER IF EXISTS answers_update_last_activity_at;
+DROP TRIGGER IF EXISTS answers_insert_last_activity_at;
+DROP TRIGGER IF EXISTS questions_set_last_activity_at;
+ALTER TABLE questions_archive DROP COLUMN last_activity_at;
+ALTER TABLE questions DROP COLUMN last_activity_at;
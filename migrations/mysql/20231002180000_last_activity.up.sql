@@ -0,0 +1,38 @@
+-- When a question last saw activity: it was asked or edited, or one of its answers was
+-- posted or edited. Triggers move it in the same transaction as the change.
+ALTER TABLE questions ADD COLUMN last_activity_at DATETIME(6) NOT NULL DEFAULT CURRENT_TIMESTAMP(6);
+ALTER TABLE questions_archive ADD COLUMN last_activity_at DATETIME(6) NOT NULL DEFAULT CURRENT_TIMESTAMP(6);
+
+UPDATE questions SET last_activity_at = GREATEST(
+    updated_at,
+    COALESCE(
+        (SELECT MAX(updated_at) FROM answers WHERE answers.question_uuid = questions.question_uuid),
+        updated_at
+    )
+);
+UPDATE questions_archive SET last_activity_at = GREATEST(
+    updated_at,
+    COALESCE(
+        (
+            SELECT MAX(updated_at) FROM answers_archive
+            WHERE answers_archive.question_uuid = questions_archive.question_uuid
+        ),
+        updated_at
+    )
+);
+
+-- Keeps the value an answer trigger just set when the question itself is unchanged.
+CREATE TRIGGER questions_set_last_activity_at BEFORE UPDATE ON questions FOR EACH ROW
+    SET NEW.last_activity_at = IF(
+        NEW.title <> OLD.title OR NEW.description <> OLD.description,
+        CURRENT_TIMESTAMP(6),
+        NEW.last_activity_at
+    );
+
+CREATE TRIGGER answers_insert_last_activity_at AFTER INSERT ON answers FOR EACH ROW
+    UPDATE questions SET last_activity_at = CURRENT_TIMESTAMP(6) WHERE question_uuid = NEW.question_uuid;
+
+CREATE TRIGGER answers_update_last_activity_at AFTER UPDATE ON answers FOR EACH ROW
+    UPDATE questions
+    SET last_activity_at = IF(NEW.content <> OLD.content, CURRENT_TIMESTAMP(6), last_activity_at)
+    WHERE question_uuid = NEW.question_uuid;
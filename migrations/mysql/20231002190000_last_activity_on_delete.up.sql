@@ -0,0 +1,3 @@
+-- Deleting an answer is activity on its question too.
+CREATE TRIGGER answers_delete_last_activity_at AFTER DELETE ON answers FOR EACH ROW
+    UPDATE questions SET last_activity_at = CURRENT_TIMESTAMP(6) WHERE question_uuid = OLD.question_uuid;
@@ -0,0 +1,4 @@
+DROP TRIGGER IF EXISTS answers_set_updated_at;
+DROP TRIGGER IF EXISTS questions_set_updated_at;
+ALTER TABLE answers DROP COLUMN updated_at;
+ALTER TABLE questions DROP COLUMN updated_at;
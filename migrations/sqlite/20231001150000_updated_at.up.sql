@@ -0,0 +1,17 @@
+-- SQLite can't add a column defaulting to CURRENT_TIMESTAMP, so inserts set updated_at
+-- explicitly. Only edits to the content move it afterwards.
+ALTER TABLE questions ADD COLUMN updated_at TIMESTAMP NOT NULL DEFAULT '1970-01-01 00:00:00';
+ALTER TABLE answers ADD COLUMN updated_at TIMESTAMP NOT NULL DEFAULT '1970-01-01 00:00:00';
+
+UPDATE questions SET updated_at = created_at;
+UPDATE answers SET updated_at = created_at;
+
+CREATE TRIGGER questions_set_updated_at AFTER UPDATE OF title, description ON questions
+BEGIN
+    UPDATE questions SET updated_at = CURRENT_TIMESTAMP WHERE question_uuid = NEW.question_uuid;
+END;
+
+CREATE TRIGGER answers_set_updated_at AFTER UPDATE OF content ON answers
+BEGIN
+    UPDATE answers SET updated_at = CURRENT_TIMESTAMP WHERE answer_uuid = NEW.answer_uuid;
+END;
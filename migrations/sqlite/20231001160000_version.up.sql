@@ -0,0 +1,18 @@
+-- Bumped by every update; writers send the version they read and lose on a mismatch.
+ALTER TABLE questions ADD COLUMN version INTEGER NOT NULL DEFAULT 1;
+ALTER TABLE answers ADD COLUMN version INTEGER NOT NULL DEFAULT 1;
+
+-- Updates always set every column, so only move updated_at when a value actually changed.
+DROP TRIGGER questions_set_updated_at;
+CREATE TRIGGER questions_set_updated_at AFTER UPDATE OF title, description ON questions
+WHEN OLD.title IS NOT NEW.title OR OLD.description IS NOT NEW.description
+BEGIN
+    UPDATE questions SET updated_at = CURRENT_TIMESTAMP WHERE question_uuid = NEW.question_uuid;
+END;
+
+DROP TRIGGER answers_set_updated_at;
+CREATE TRIGGER answers_set_updated_at AFTER UPDATE OF content ON answers
+WHEN OLD.content IS NOT NEW.content
+BEGIN
+    UPDATE answers SET updated_at = CURRENT_TIMESTAMP WHERE answer_uuid = NEW.answer_uuid;
+END;
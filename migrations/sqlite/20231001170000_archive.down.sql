@@ -0,0 +1,2 @@
+DROP TABLE IF EXISTS answers_archive;
+DROP TABLE IF EXISTS questions_archive;
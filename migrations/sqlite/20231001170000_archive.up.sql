@@ -0,0 +1,23 @@
+-- Questions without activity for a while are moved here, with their answers, to keep the
+-- live tables small. Archived rows are read-only.
+CREATE TABLE IF NOT EXISTS questions_archive (
+    question_uuid TEXT PRIMARY KEY NOT NULL,
+    title VARCHAR(255) NOT NULL,
+    description VARCHAR(255) NOT NULL,
+    created_at TIMESTAMP NOT NULL,
+    updated_at TIMESTAMP NOT NULL,
+    answer_count INTEGER NOT NULL,
+    version INTEGER NOT NULL,
+    archived_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE TABLE IF NOT EXISTS answers_archive (
+    answer_uuid TEXT PRIMARY KEY NOT NULL,
+    question_uuid TEXT NOT NULL REFERENCES questions_archive (question_uuid) ON DELETE CASCADE,
+    content VARCHAR(255) NOT NULL,
+    created_at TIMESTAMP NOT NULL,
+    updated_at TIMESTAMP NOT NULL,
+    version INTEGER NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS answers_archive_question_uuid ON answers_archive (question_uuid);
@@ -0,0 +1,13 @@
+-- Computed on every read; only Postgres materializes it.
+CREATE VIEW IF NOT EXISTS daily_stats AS
+SELECT day, SUM(questions) AS questions, SUM(answers) AS answers
+FROM (
+    SELECT date(created_at) AS day, 1 AS questions, 0 AS answers FROM questions
+    UNION ALL
+    SELECT date(created_at), 1, 0 FROM questions_archive
+    UNION ALL
+    SELECT date(created_at), 0, 1 FROM answers
+    UNION ALL
+    SELECT date(created_at), 0, 1 FROM answers_archive
+) AS activity
+GROUP BY day;
@@ -0,0 +1,8 @@
+DROP TRIGGER IF EXISTS questions_set_updated_at;
+CREATE TRIGGER questions_set_updated_at AFTER UPDATE OF title, description ON questions
+BEGIN
+    UPDATE questions SET updated_at = CURRENT_TIMESTAMP WHERE question_uuid = NEW.question_uuid;
+END;
+
+ALTER TABLE questions_archive DROP COLUMN metadata;
+ALTER TABLE questions DROP COLUMN metadata;
@@ -0,0 +1,10 @@
+-- A JSON object clients attach to questions, stored as text.
+ALTER TABLE questions ADD COLUMN metadata TEXT NOT NULL DEFAULT '{}';
+ALTER TABLE questions_archive ADD COLUMN metadata TEXT NOT NULL DEFAULT '{}';
+
+-- Editing the metadata is an edit like any other.
+DROP TRIGGER IF EXISTS questions_set_updated_at;
+CREATE TRIGGER questions_set_updated_at AFTER UPDATE OF title, description, metadata ON questions
+BEGIN
+    UPDATE questions SET updated_at = CURRENT_TIMESTAMP WHERE question_uuid = NEW.question_uuid;
+END;
@@ -0,0 +1,16 @@
+-- Computed on every read; only Postgres materializes it.
+CREATE VIEW IF NOT EXISTS first_answers AS
+SELECT asked.question_uuid,
+       date(asked.created_at) AS day,
+       (julianday(MIN(answered.created_at)) - julianday(asked.created_at)) * 86400.0 AS seconds
+FROM (
+    SELECT question_uuid, created_at FROM questions
+    UNION ALL
+    SELECT question_uuid, created_at FROM questions_archive
+) AS asked
+JOIN (
+    SELECT question_uuid, created_at FROM answers
+    UNION ALL
+    SELECT question_uuid, created_at FROM answers_archive
+) AS answered ON answered.question_uuid = asked.question_uuid
+GROUP BY asked.question_uuid, asked.created_at;
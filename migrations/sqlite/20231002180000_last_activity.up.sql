@@ -0,0 +1,38 @@
+-- When a question last saw activity: it was asked or edited, or one of its answers was
+-- posted or edited. SQLite can't add a column defaulting to CURRENT_TIMESTAMP, so
+-- inserts set it explicitly; triggers move it in the same transaction as the change.
+ALTER TABLE questions ADD COLUMN last_activity_at TIMESTAMP NOT NULL DEFAULT '1970-01-01 00:00:00';
+ALTER TABLE questions_archive ADD COLUMN last_activity_at TIMESTAMP NOT NULL DEFAULT '1970-01-01 00:00:00';
+
+UPDATE questions SET last_activity_at = MAX(
+    updated_at,
+    COALESCE(
+        (SELECT MAX(updated_at) FROM answers WHERE answers.question_uuid = questions.question_uuid),
+        updated_at
+    )
+);
+UPDATE questions_archive SET last_activity_at = MAX(
+    updated_at,
+    COALESCE(
+        (
+            SELECT MAX(updated_at) FROM answers_archive
+            WHERE answers_archive.question_uuid = questions_archive.question_uuid
+        ),
+        updated_at
+    )
+);
+
+CREATE TRIGGER questions_set_last_activity_at AFTER UPDATE OF title, description ON questions
+BEGIN
+    UPDATE questions SET last_activity_at = CURRENT_TIMESTAMP WHERE question_uuid = NEW.question_uuid;
+END;
+
+CREATE TRIGGER answers_insert_last_activity_at AFTER INSERT ON answers
+BEGIN
+    UPDATE questions SET last_activity_at = CURRENT_TIMESTAMP WHERE question_uuid = NEW.question_uuid;
+END;
+
+CREATE TRIGGER answers_update_last_activity_at AFTER UPDATE OF content ON answers
+BEGIN
+    UPDATE questions SET last_activity_at = CURRENT_TIMESTAMP WHERE question_uuid = NEW.question_uuid;
+END;
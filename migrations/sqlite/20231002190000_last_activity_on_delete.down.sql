@@ -0,0 +1 @@
+DROP TRIGGER IF EXISTS answers_delete_last_activity_at;
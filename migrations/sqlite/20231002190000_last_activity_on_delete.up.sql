@@ -0,0 +1,5 @@
+-- Deleting an answer is activity on its question too.
+CREATE TRIGGER answers_delete_last_activity_at AFTER DELETE ON answers
+BEGIN
+    UPDATE questions SET last_activity_at = CURRENT_TIMESTAMP WHERE question_uuid = OLD.question_uuid;
+END;
@@ -0,0 +1,75 @@
+use std::future::Future;
+
+use rocket::request::{self, FromRequest};
+use rocket::Request;
+
+use crate::{handlers::admin::Admin, request_id::RequestId};
+
+// Who a change is attributed to in the audit log. There are no user accounts, so
+// callers with the admin token are `admin` and everyone else is `anonymous`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Actor {
+    pub name: String,
+    pub request_id: Option<String>,
+}
+
+tokio::task_local! {
+    static CURRENT_ACTOR: Actor;
+}
+
+impl Actor {
+    // Background jobs run outside of any request.
+    pub fn system() -> Self {
+        Actor {
+            name: "system".to_owned(),
+            request_id: None,
+        }
+    }
+
+    // The actor of the request whose work is running, see `Deadline::run`.
+    pub fn current() -> Self {
+        CURRENT_ACTOR
+            .try_with(Actor::clone)
+            .unwrap_or_else(|_| Actor::system())
+    }
+
+    pub async fn scope<F: Future>(self, work: F) -> F::Output {
+        CURRENT_ACTOR.scope(self, work).await
+    }
+}
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for Actor {
+    type Error = ();
+
+    async fn from_request(req: &'r Request<'_>) -> request::Outcome<Self, Self::Error> {
+        let name = match req.guard::<Admin>().await {
+            request::Outcome::Success(_) => "admin",
+            _ => "anonymous",
+        };
+        let request_id = req.guard::<&RequestId>().await.unwrap();
+
+        request::Outcome::Success(Actor {
+            name: name.to_owned(),
+            request_id: Some(request_id.0.clone()),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn current_should_be_the_scoped_actor() {
+        let actor = Actor {
+            name: "admin".to_owned(),
+            request_id: Some("id".to_owned()),
+        };
+
+        let current = actor.clone().scope(async { Actor::current() }).await;
+
+        assert_eq!(current, actor);
+        assert_eq!(Actor::current(), Actor::system());
+    }
+}
@@ -0,0 +1,405 @@
+use std::{net::IpAddr, sync::Mutex};
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use log::{info, warn};
+use rocket::{
+    fairing::{Fairing, Info, Kind},
+    request::{self, FromRequest},
+    Request, Response,
+};
+use serde::Serialize;
+use serde_json::Value;
+use thiserror::Error;
+use tokio::sync::mpsc;
+
+use crate::{
+    backpressure,
+    config::{AnalyticsSinkKind, AppConfig},
+    fnv::Fnv1a,
+    startup::StartupError,
+    AppState,
+};
+
+const QUEUE_CAPACITY: usize = 1000;
+// Events sent to the sink at once, at most.
+const BATCH_SIZE: usize = 100;
+// Lets clients that already have an anonymous id, e.g. from Segment's browser library,
+// tie the API's events to theirs.
+pub const ANONYMOUS_ID_HEADER: &str = "X-Anonymous-Id";
+// Properties that are dropped whatever their value, at any depth.
+const SCRUBBED_KEYS: [&str; 7] = [
+    "email",
+    "author_email",
+    "ip",
+    "phone",
+    "name",
+    "token",
+    "password",
+];
+
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct AnalyticsEvent {
+    // `question_viewed`, `answer_created`, `search_performed`, ...
+    pub event: &'static str,
+    pub anonymous_id: String,
+    pub properties: Value,
+    pub timestamp: DateTime<Utc>,
+}
+
+#[derive(Error, Debug)]
+#[error("Sending analytics events failed: {0}")]
+pub struct AnalyticsError(pub String);
+
+#[async_trait]
+pub trait AnalyticsSink {
+    async fn send(&self, events: &[AnalyticsEvent]) -> Result<(), AnalyticsError>;
+}
+
+// Managed as `Option<Analytics>`; None while ANALYTICS_SINK is unset. Events are best
+// effort: a full queue or a failing sink drops them.
+pub struct Analytics {
+    queue: mpsc::Sender<AnalyticsEvent>,
+    sample_rate: f64,
+    // Made at startup, so hashed addresses cannot be matched across restarts or sites.
+    salt: u64,
+}
+
+impl Analytics {
+    pub fn spawn(sink: Box<dyn AnalyticsSink + Send + Sync>, sample_rate: f64) -> Self {
+        let (queue, mut events) = mpsc::channel(QUEUE_CAPACITY);
+
+        tokio::spawn(async move {
+            let mut batch = Vec::with_capacity(BATCH_SIZE);
+            while events.recv_many(&mut batch, BATCH_SIZE).await > 0 {
+                backpressure::record_queue_depth("analytics", events.len(), QUEUE_CAPACITY);
+                if let Err(err) = sink.send(&batch).await {
+                    warn!("Dropped {} analytics events: {}", batch.len(), err);
+                }
+                batch.clear();
+            }
+        });
+
+        Self {
+            queue,
+            sample_rate,
+            salt: rand::random(),
+        }
+    }
+
+    // Sampled, scrubbed and queued; never waits.
+    pub fn track(&self, event: &'static str, anonymous_id: String, properties: Value) {
+        if self.sample_rate < 1.0 && rand::random::<f64>() >= self.sample_rate {
+            return;
+        }
+
+        let event = AnalyticsEvent {
+            event,
+            anonymous_id,
+            properties: scrub(properties),
+            timestamp: Utc::now(),
+        };
+        if let Err(err) = self.queue.try_send(event) {
+            warn!("Dropped an analytics event: {}", err);
+        }
+        backpressure::record_queue("analytics", &self.queue);
+    }
+
+    // The client's own id when it sends a sane one, otherwise its address hashed.
+    fn anonymous_id(&self, header: Option<&str>, client_ip: Option<IpAddr>) -> String {
+        if let Some(id) = header.filter(|id| {
+            !id.is_empty()
+                && id.len() <= 64
+                && id
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+        }) {
+            return id.to_owned();
+        }
+
+        let mut hash = Fnv1a::new();
+        hash.write(&self.salt.to_le_bytes());
+        hash.write(
+            client_ip
+                .map_or_else(|| "unknown".to_owned(), |ip| ip.to_string())
+                .as_bytes(),
+        );
+        format!("{:016x}", hash.finish())
+    }
+}
+
+pub fn sink_from_config(
+    config: &AppConfig,
+) -> Result<Option<Box<dyn AnalyticsSink + Send + Sync>>, StartupError> {
+    match config.analytics_sink {
+        AnalyticsSinkKind::None => Ok(None),
+        AnalyticsSinkKind::Log => Ok(Some(Box::new(LogSink))),
+        #[cfg(feature = "segment")]
+        AnalyticsSinkKind::Segment => Ok(Some(Box::new(SegmentSink::new(
+            &config.segment_api_url,
+            config
+                .segment_write_key
+                .clone()
+                .ok_or(StartupError::MissingSetting {
+                    setting: "ANALYTICS_SINK=segment",
+                    needed: "SEGMENT_WRITE_KEY",
+                })?,
+        )))),
+        #[cfg(not(feature = "segment"))]
+        AnalyticsSinkKind::Segment => Err(StartupError::MissingFeature {
+            setting: "ANALYTICS_SINK=segment",
+            feature: "segment",
+        }),
+        #[cfg(feature = "kafka")]
+        AnalyticsSinkKind::Kafka => Ok(Some(Box::new(KafkaSink {
+            producer: crate::events::kafka_producer(
+                config
+                    .kafka_brokers
+                    .as_deref()
+                    .ok_or(StartupError::MissingSetting {
+                        setting: "ANALYTICS_SINK=kafka",
+                        needed: "KAFKA_BROKERS",
+                    })?,
+                config,
+            )?,
+            topic: config.analytics_topic.clone(),
+        }))),
+        #[cfg(not(feature = "kafka"))]
+        AnalyticsSinkKind::Kafka => Err(StartupError::MissingFeature {
+            setting: "ANALYTICS_SINK=kafka",
+            feature: "kafka",
+        }),
+    }
+}
+
+// Drops keys that name personal data and masks email addresses and long numbers, such
+// as phone numbers, in free text like search queries.
+pub fn scrub(value: Value) -> Value {
+    match value {
+        Value::Object(object) => Value::Object(
+            object
+                .into_iter()
+                .filter(|(key, _)| !SCRUBBED_KEYS.contains(&key.to_ascii_lowercase().as_str()))
+                .map(|(key, value)| (key, scrub(value)))
+                .collect(),
+        ),
+        Value::Array(values) => Value::Array(values.into_iter().map(scrub).collect()),
+        Value::String(text) => Value::String(scrub_text(&text)),
+        value => value,
+    }
+}
+
+fn scrub_text(text: &str) -> String {
+    text.split_inclusive(char::is_whitespace)
+        .map(|word| {
+            let trimmed = word.trim_end();
+            let is_email = trimmed
+                .split_once('@')
+                .is_some_and(|(user, domain)| !user.is_empty() && domain.contains('.'));
+            // Ids such as uuids have letters, so only numbers written like phone numbers match.
+            let is_number = trimmed.chars().filter(char::is_ascii_digit).count() >= 7
+                && trimmed
+                    .chars()
+                    .all(|c| c.is_ascii_digit() || "+-().".contains(c));
+            let mask = if is_email {
+                "[email]"
+            } else if is_number {
+                "[number]"
+            } else {
+                return word.to_owned();
+            };
+            format!("{}{}", mask, &word[trimmed.len()..])
+        })
+        .collect()
+}
+
+#[derive(Default)]
+struct Tracked(Mutex<Vec<(&'static str, Value)>>);
+
+// Records analytics events in a handler. They are only sent once the response turns out
+// successful, so failed requests never count.
+pub struct Tracker<'r>(&'r Tracked);
+
+impl Tracker<'_> {
+    pub fn track(&self, event: &'static str, properties: Value) {
+        self.0 .0.lock().unwrap().push((event, properties));
+    }
+}
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for Tracker<'r> {
+    type Error = ();
+
+    async fn from_request(req: &'r Request<'_>) -> request::Outcome<Self, Self::Error> {
+        request::Outcome::Success(Tracker(req.local_cache(Tracked::default)))
+    }
+}
+
+pub struct AnalyticsFairing;
+
+#[rocket::async_trait]
+impl Fairing for AnalyticsFairing {
+    fn info(&self) -> Info {
+        Info {
+            name: "Send the analytics events of successful requests",
+            kind: Kind::Response,
+        }
+    }
+
+    async fn on_response<'r>(&self, request: &'r Request<'_>, response: &mut Response<'r>) {
+        if !response.status().class().is_success() {
+            return;
+        }
+        let tracked = std::mem::take(&mut *request.local_cache(Tracked::default).0.lock().unwrap());
+        if tracked.is_empty() {
+            return;
+        }
+        let Some(analytics) = request
+            .rocket()
+            .state::<AppState>()
+            .and_then(|state| state.analytics.as_ref())
+        else {
+            return;
+        };
+
+        let anonymous_id = analytics.anonymous_id(
+            request.headers().get_one(ANONYMOUS_ID_HEADER),
+            request.client_ip(),
+        );
+        for (event, properties) in tracked {
+            analytics.track(event, anonymous_id.clone(), properties);
+        }
+    }
+}
+
+pub struct LogSink;
+
+#[async_trait]
+impl AnalyticsSink for LogSink {
+    async fn send(&self, events: &[AnalyticsEvent]) -> Result<(), AnalyticsError> {
+        for event in events {
+            info!(
+                "Analytics {} from {}: {}",
+                event.event, event.anonymous_id, event.properties
+            );
+        }
+        Ok(())
+    }
+}
+
+// Segment's `/v1/batch` tracking API, authenticated with a source's write key.
+#[cfg(feature = "segment")]
+pub struct SegmentSink {
+    client: reqwest::Client,
+    url: String,
+    write_key: String,
+}
+
+#[cfg(feature = "segment")]
+impl SegmentSink {
+    pub fn new(base_url: &str, write_key: String) -> Self {
+        let client = reqwest::Client::builder()
+            .timeout(std::time::Duration::from_secs(10))
+            .build()
+            .expect("HTTP client should build.");
+
+        Self {
+            client,
+            url: format!("{}/v1/batch", base_url.trim_end_matches('/')),
+            write_key,
+        }
+    }
+}
+
+#[cfg(feature = "segment")]
+#[async_trait]
+impl AnalyticsSink for SegmentSink {
+    async fn send(&self, events: &[AnalyticsEvent]) -> Result<(), AnalyticsError> {
+        let batch: Vec<Value> = events
+            .iter()
+            .map(|event| {
+                serde_json::json!({
+                    "type": "track",
+                    "event": event.event,
+                    "anonymousId": event.anonymous_id,
+                    "properties": event.properties,
+                    "timestamp": event.timestamp.to_rfc3339_opts(chrono::SecondsFormat::AutoSi, true),
+                    "messageId": uuid::Uuid::new_v4(),
+                })
+            })
+            .collect();
+
+        self.client
+            .post(&self.url)
+            .basic_auth(&self.write_key, None::<&str>)
+            .json(&serde_json::json!({ "batch": batch }))
+            .send()
+            .await
+            .and_then(|response| response.error_for_status())
+            .map_err(|err| AnalyticsError(err.to_string()))?;
+        Ok(())
+    }
+}
+
+// One JSON message per event, keyed by the anonymous id.
+#[cfg(feature = "kafka")]
+pub struct KafkaSink {
+    producer: rdkafka::producer::FutureProducer,
+    topic: String,
+}
+
+#[cfg(feature = "kafka")]
+#[async_trait]
+impl AnalyticsSink for KafkaSink {
+    async fn send(&self, events: &[AnalyticsEvent]) -> Result<(), AnalyticsError> {
+        for event in events {
+            let payload =
+                serde_json::to_vec(event).map_err(|err| AnalyticsError(err.to_string()))?;
+            let record = rdkafka::producer::FutureRecord::to(&self.topic)
+                .key(&event.anonymous_id)
+                .payload(&payload);
+            self.producer
+                .send(record, std::time::Duration::ZERO)
+                .await
+                .map_err(|(err, _)| AnalyticsError(err.to_string()))?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn scrub_should_drop_personal_keys_and_mask_text() {
+        let scrubbed = scrub(json!({
+            "query": "refund for jane@example.com call +1 555 0100123 or (555)010-0124 now",
+            "question_uuid": "3a94c0c4-ba65-4290-8414-02fa2131abe8",
+            "author_email": "jane@example.com",
+            "nested": [{ "Email": "jane@example.com", "results": 3 }],
+        }));
+
+        assert_eq!(
+            scrubbed,
+            json!({
+                "query": "refund for [email] call +1 555 [number] or [number] now",
+                "question_uuid": "3a94c0c4-ba65-4290-8414-02fa2131abe8",
+                "nested": [{ "results": 3 }],
+            })
+        );
+    }
+
+    #[tokio::test]
+    async fn anonymous_id_should_prefer_a_sane_header() {
+        let analytics = Analytics::spawn(Box::new(LogSink), 1.0);
+        let ip = Some(IpAddr::from([203, 0, 113, 7]));
+
+        assert_eq!(analytics.anonymous_id(Some("abc-123"), ip), "abc-123");
+        let hashed = analytics.anonymous_id(Some("not sane"), ip);
+        assert_eq!(hashed.len(), 16);
+        assert_eq!(analytics.anonymous_id(None, ip), hashed);
+        assert!(!hashed.contains("203"));
+    }
+}
@@ -0,0 +1,211 @@
+use async_trait::async_trait;
+use thiserror::Error;
+
+use crate::{
+    config::{AnswererKind, AppConfig},
+    models::{AnswerDetail, QuestionDetail},
+    rate_limit::RateLimiter,
+    startup::StartupError,
+};
+
+// Also needed in FEATURE_FLAGS, so suggestions can be switched off without a restart.
+pub const FEATURE_FLAG: &str = "suggest_answers";
+
+// Existing answers given to the model, newest first, so it does not repeat them.
+const CONTEXT_ANSWERS: usize = 5;
+
+#[cfg(feature = "openai")]
+const INSTRUCTIONS: &str = "You draft answers for a question and answer site. Answer the \
+question below helpfully and concisely, adding to the existing answers rather than repeating \
+them. Say so if you are unsure. Reply with the answer text only.";
+
+// Only remote answerers can fail.
+#[derive(Error, Debug)]
+pub enum AnswererError {
+    #[cfg(feature = "openai")]
+    #[error("Answer request failed: {0}")]
+    Request(String),
+}
+
+// A generated draft; it is returned to the caller and never stored.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Suggestion {
+    pub content: String,
+    pub model: String,
+}
+
+// Drafts an answer to a question from a language model.
+#[async_trait]
+pub trait AiAnswerer {
+    async fn suggest(
+        &self,
+        question: &QuestionDetail,
+        answers: &[AnswerDetail],
+    ) -> Result<Suggestion, AnswererError>;
+}
+
+// Managed as `Option<AnswerSuggestions>`; None while no answerer is configured.
+pub struct AnswerSuggestions {
+    pub answerer: Box<dyn AiAnswerer + Send + Sync>,
+    // Per client IP, since every suggestion costs a model call.
+    pub rate_limiter: RateLimiter,
+}
+
+pub fn answerer_from_config(
+    config: &AppConfig,
+) -> Result<Option<Box<dyn AiAnswerer + Send + Sync>>, StartupError> {
+    match config.answerer {
+        AnswererKind::None => Ok(None),
+        #[cfg(feature = "openai")]
+        AnswererKind::OpenAi => Ok(Some(Box::new(OpenAiAnswerer::new(
+            &config.answerer_api_url,
+            config.answerer_api_key.clone(),
+            config.answerer_model.clone(),
+        )))),
+        #[cfg(not(feature = "openai"))]
+        AnswererKind::OpenAi => Err(StartupError::MissingFeature {
+            setting: "ANSWERER=openai",
+            feature: "openai",
+        }),
+    }
+}
+
+// The question and its latest answers as the model is shown them.
+pub fn prompt(question: &QuestionDetail, answers: &[AnswerDetail]) -> String {
+    let mut prompt = format!("Question: {}", question.title);
+    if let Some(description) = &question.description {
+        prompt.push_str(&format!("\n\n{}", description));
+    }
+    let mut latest: Vec<&AnswerDetail> = answers.iter().collect();
+    latest.sort_by_key(|answer| std::cmp::Reverse(answer.created_at));
+    for (index, answer) in latest.into_iter().take(CONTEXT_ANSWERS).enumerate() {
+        prompt.push_str(&format!(
+            "\n\nExisting answer {}:\n{}",
+            index + 1,
+            answer.content
+        ));
+    }
+    prompt
+}
+
+// Any server implementing OpenAI's `/chat/completions` endpoint, which includes local
+// model servers such as Ollama or llama.cpp.
+#[cfg(feature = "openai")]
+pub struct OpenAiAnswerer {
+    client: reqwest::Client,
+    url: String,
+    api_key: Option<String>,
+    model: String,
+}
+
+#[cfg(feature = "openai")]
+impl OpenAiAnswerer {
+    pub fn new(base_url: &str, api_key: Option<String>, model: String) -> Self {
+        Self {
+            client: reqwest::Client::new(),
+            url: format!("{}/chat/completions", base_url.trim_end_matches('/')),
+            api_key,
+            model,
+        }
+    }
+}
+
+#[cfg(feature = "openai")]
+#[async_trait]
+impl AiAnswerer for OpenAiAnswerer {
+    async fn suggest(
+        &self,
+        question: &QuestionDetail,
+        answers: &[AnswerDetail],
+    ) -> Result<Suggestion, AnswererError> {
+        #[derive(serde::Deserialize)]
+        struct Response {
+            model: Option<String>,
+            choices: Vec<Choice>,
+        }
+
+        #[derive(serde::Deserialize)]
+        struct Choice {
+            message: Message,
+        }
+
+        #[derive(serde::Deserialize)]
+        struct Message {
+            content: Option<String>,
+        }
+
+        let mut request = self.client.post(&self.url).json(&serde_json::json!({
+            "model": self.model,
+            "messages": [
+                { "role": "system", "content": INSTRUCTIONS },
+                { "role": "user", "content": prompt(question, answers) },
+            ],
+        }));
+        if let Some(api_key) = &self.api_key {
+            request = request.bearer_auth(api_key);
+        }
+
+        let response: Response = request
+            .send()
+            .await
+            .and_then(|response| response.error_for_status())
+            .map_err(|err| AnswererError::Request(err.to_string()))?
+            .json()
+            .await
+            .map_err(|err| AnswererError::Request(err.to_string()))?;
+
+        let content = response
+            .choices
+            .into_iter()
+            .next()
+            .and_then(|choice| choice.message.content)
+            .map(|content| content.trim().to_owned())
+            .filter(|content| !content.is_empty())
+            .ok_or_else(|| AnswererError::Request("Response has no answer".to_owned()))?;
+        Ok(Suggestion {
+            content,
+            model: response.model.unwrap_or_else(|| self.model.clone()),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{Duration, Utc};
+
+    use super::*;
+    use crate::models::{empty_metadata, AnswerUuid, QuestionUuid};
+
+    #[test]
+    fn prompt_should_show_the_latest_answers() {
+        let now = Utc::now();
+        let question = QuestionDetail {
+            question_uuid: QuestionUuid::new_v4(),
+            title: "How do I borrow?".to_owned(),
+            description: Some("In Rust.".to_owned()),
+            metadata: empty_metadata(),
+            created_at: now,
+            updated_at: now,
+            last_activity_at: now,
+            answer_count: 7,
+            version: 1,
+        };
+        let answers: Vec<AnswerDetail> = (0..7)
+            .map(|minutes| AnswerDetail {
+                answer_uuid: AnswerUuid::new_v4(),
+                question_uuid: question.question_uuid,
+                content: format!("answer {}", minutes),
+                created_at: now + Duration::minutes(minutes),
+                updated_at: now,
+                version: 1,
+            })
+            .collect();
+
+        let prompt = prompt(&question, &answers);
+
+        assert!(prompt.starts_with("Question: How do I borrow?\n\nIn Rust."));
+        assert!(prompt.contains("Existing answer 1:\nanswer 6"));
+        assert!(prompt.contains("Existing answer 5:\nanswer 2"));
+        assert_eq!(prompt.matches("Existing answer").count(), CONTEXT_ANSWERS);
+    }
+}
@@ -0,0 +1,138 @@
+use sha2::{Digest, Sha256};
+
+// Gravatar serves at most this size.
+pub const MAX_SIZE: u32 = 2048;
+
+// Identicons are drawn on a 5x5 grid mirrored around its middle column.
+const GRID: usize = 5;
+
+// Where uploaded avatars are kept in the attachment store. The address is hashed so keys
+// never leak it, e.g. in the store's listings.
+pub fn storage_key(email_hash: &str) -> String {
+    format!("avatars/{}", email_hash)
+}
+
+// Gravatar identifies addresses by the hex SHA-256 of the trimmed, lowercased address,
+// which the identicon is drawn from too.
+pub fn email_hash(email: &str) -> String {
+    Sha256::digest(email.trim().to_lowercase().as_bytes())
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
+
+// `default` is what Gravatar shows for addresses without an avatar: one of its own
+// styles, e.g. `identicon`, or an image URL.
+pub fn gravatar_url(email_hash: &str, size: u32, default: &str) -> String {
+    format!(
+        "https://www.gravatar.com/avatar/{}?s={}&d={}",
+        email_hash,
+        size.clamp(1, MAX_SIZE),
+        percent_encode(default)
+    )
+}
+
+// Leaves only the unreserved characters as they are, so the result fits in a path
+// segment or query value.
+pub fn percent_encode(value: &str) -> String {
+    value
+        .bytes()
+        .map(|byte| match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => {
+                (byte as char).to_string()
+            }
+            _ => format!("%{:02X}", byte),
+        })
+        .collect()
+}
+
+// The media type of an uploaded avatar, from its first bytes; None for anything but
+// PNG, JPEG, GIF and WebP images.
+pub fn image_type(bytes: &[u8]) -> Option<&'static str> {
+    if bytes.starts_with(b"\x89PNG\r\n\x1a\n") {
+        Some("image/png")
+    } else if bytes.starts_with(b"\xff\xd8\xff") {
+        Some("image/jpeg")
+    } else if bytes.starts_with(b"GIF87a") || bytes.starts_with(b"GIF89a") {
+        Some("image/gif")
+    } else if bytes.len() >= 12 && bytes.starts_with(b"RIFF") && &bytes[8..12] == b"WEBP" {
+        Some("image/webp")
+    } else {
+        None
+    }
+}
+
+// A GitHub style identicon as SVG: cells filled in one color, picked along with the
+// pattern from `email_hash`, so an address always gets the same picture.
+pub fn identicon(email_hash: &str) -> String {
+    let nibbles: Vec<u8> = email_hash
+        .chars()
+        .filter_map(|c| c.to_digit(16))
+        .map(|digit| digit as u8)
+        .collect();
+    let nibble = |index: usize| nibbles.get(index).copied().unwrap_or(0);
+    let hue =
+        (u32::from(nibble(0)) << 8 | u32::from(nibble(1)) << 4 | u32::from(nibble(2))) * 360 / 4096;
+
+    let mut cells = String::new();
+    for row in 0..GRID {
+        for column in 0..GRID.div_ceil(2) {
+            if nibble(3 + row * 3 + column) % 2 == 0 {
+                continue;
+            }
+            for x in [column, GRID - 1 - column] {
+                cells.push_str(&format!(
+                    "<rect x=\"{}\" y=\"{}\" width=\"1\" height=\"1\"/>",
+                    x, row
+                ));
+                if x == GRID - 1 - x {
+                    break;
+                }
+            }
+        }
+    }
+
+    format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" viewBox=\"-0.5 -0.5 {size} {size}\" shape-rendering=\"crispEdges\"><rect x=\"-0.5\" y=\"-0.5\" width=\"{size}\" height=\"{size}\" fill=\"#f0f0f0\"/><g fill=\"hsl({hue}, 55%, 50%)\">{cells}</g></svg>",
+        size = GRID + 1,
+        hue = hue,
+        cells = cells
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn email_hash_should_ignore_case_and_surrounding_space() {
+        // The example from Gravatar's documentation.
+        assert_eq!(
+            email_hash(" MyEmailAddress@example.com "),
+            "84059b07d4be67b806386c0aad8070a23f18836bbaae342275dc0a83414c32ee"
+        );
+    }
+
+    #[test]
+    fn identicons_should_be_mirrored_and_differ_by_address() {
+        let svg = identicon(&email_hash("a@example.com"));
+        for column in 0..2 {
+            for row in 0..GRID {
+                let cell = |x| format!("<rect x=\"{}\" y=\"{}\" ", x, row);
+                assert_eq!(
+                    svg.contains(&cell(column)),
+                    svg.contains(&cell(GRID - 1 - column))
+                );
+            }
+        }
+        assert_eq!(svg, identicon(&email_hash("A@example.com")));
+        assert_ne!(svg, identicon(&email_hash("b@example.com")));
+    }
+
+    #[test]
+    fn image_type_should_sniff_supported_images() {
+        assert_eq!(image_type(b"\x89PNG\r\n\x1a\n...."), Some("image/png"));
+        assert_eq!(image_type(b"RIFF\0\0\0\0WEBPVP8 "), Some("image/webp"));
+        assert_eq!(image_type(b"<svg onload=alert(1)>"), None);
+    }
+}
@@ -0,0 +1,95 @@
+use std::{
+    sync::atomic::{AtomicU64, Ordering},
+    time::Duration,
+};
+
+use log::warn;
+use tokio::sync::mpsc;
+
+use crate::{config::AppConfig, metrics};
+
+// Past these the background work is logged as falling behind; zero disables each.
+static QUEUE_WARN_PERCENT: AtomicU64 = AtomicU64::new(80);
+static DELIVERY_LAG_WARN_MS: AtomicU64 = AtomicU64::new(60_000);
+static FANOUT_WARN_MS: AtomicU64 = AtomicU64::new(10_000);
+
+pub fn set_warn_thresholds(config: &AppConfig) {
+    QUEUE_WARN_PERCENT.store(config.queue_warn_percent.into(), Ordering::Relaxed);
+    DELIVERY_LAG_WARN_MS.store(
+        config.delivery_lag_warn_threshold.as_millis() as u64,
+        Ordering::Relaxed,
+    );
+    FANOUT_WARN_MS.store(
+        config.fanout_warn_threshold.as_millis() as u64,
+        Ordering::Relaxed,
+    );
+}
+
+// Records how many jobs wait in the in-process queue `name`, e.g. right after one was
+// queued or taken off it.
+pub fn record_queue_depth(name: &str, depth: usize, capacity: usize) {
+    metrics::QUEUE_DEPTH
+        .with_label_values(&[name])
+        .set(depth as i64);
+
+    if is_past_percent(depth, capacity, QUEUE_WARN_PERCENT.load(Ordering::Relaxed)) {
+        warn!(
+            "The {} queue holds {} of at most {} jobs; its worker is falling behind.",
+            name, depth, capacity
+        );
+    }
+}
+
+// The same from the sending side of the queue.
+pub fn record_queue<T>(name: &str, queue: &mpsc::Sender<T>) {
+    let capacity = queue.max_capacity();
+    record_queue_depth(name, capacity - queue.capacity(), capacity);
+}
+
+// Records how long after it was written an outbox event went out to the subscribers.
+pub fn record_delivery_lag(lag: Duration) {
+    metrics::OUTBOX_DELIVERY_LAG.observe(lag.as_secs_f64());
+
+    if is_past(lag, &DELIVERY_LAG_WARN_MS) {
+        warn!(
+            "An outbox event was delivered {:?} after it was written; subscribers are falling behind.",
+            lag
+        );
+    }
+}
+
+// Records how long the notifications about an answer took, from being queued until the
+// last email or push went out.
+pub fn record_fanout(latency: Duration) {
+    metrics::NOTIFICATION_FANOUT.observe(latency.as_secs_f64());
+
+    if is_past(latency, &FANOUT_WARN_MS) {
+        warn!(
+            "Notifying about an answer took {:?}; the mail or push service is slow.",
+            latency
+        );
+    }
+}
+
+fn is_past(elapsed: Duration, threshold_ms: &AtomicU64) -> bool {
+    let threshold = threshold_ms.load(Ordering::Relaxed);
+    threshold > 0 && elapsed > Duration::from_millis(threshold)
+}
+
+fn is_past_percent(depth: usize, capacity: usize, percent: u64) -> bool {
+    percent > 0 && capacity > 0 && depth as u64 * 100 >= capacity as u64 * percent
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn queue_should_be_past_threshold_from_the_percentage_on() {
+        assert!(!is_past_percent(79, 100, 80));
+        assert!(is_past_percent(80, 100, 80));
+        assert!(is_past_percent(9, 10, 80));
+        assert!(!is_past_percent(100, 100, 0));
+        assert!(!is_past_percent(0, 0, 80));
+    }
+}
@@ -0,0 +1,119 @@
+use async_trait::async_trait;
+use thiserror::Error;
+
+use crate::{
+    config::{AppConfig, CaptchaProviderKind},
+    startup::StartupError,
+};
+
+// Only remote verifiers can fail.
+#[derive(Error, Debug)]
+pub enum CaptchaError {
+    #[cfg(feature = "captcha")]
+    #[error("Captcha verification failed: {0}")]
+    Request(String),
+}
+
+// Checks the token a captcha widget gave the client.
+#[async_trait]
+pub trait CaptchaVerifier {
+    async fn verify(&self, token: &str, client_ip: Option<&str>) -> Result<bool, CaptchaError>;
+}
+
+// Managed as `Option<Captcha>`; None while CAPTCHA_PROVIDER is unset.
+pub struct Captcha {
+    pub verifier: Box<dyn CaptchaVerifier + Send + Sync>,
+    // Paths of the create routes that need a token, e.g. `/question`.
+    pub routes: Vec<String>,
+}
+
+impl Captcha {
+    pub fn protects(&self, route: &str) -> bool {
+        self.routes.iter().any(|protected| protected == route)
+    }
+}
+
+#[cfg_attr(not(feature = "captcha"), allow(unused_variables))]
+pub fn captcha_from_config(config: &AppConfig) -> Result<Option<Captcha>, StartupError> {
+    let default_url = match config.captcha_provider {
+        CaptchaProviderKind::None => return Ok(None),
+        CaptchaProviderKind::HCaptcha => "https://api.hcaptcha.com/siteverify",
+        CaptchaProviderKind::Turnstile => {
+            "https://challenges.cloudflare.com/turnstile/v0/siteverify"
+        }
+    };
+
+    #[cfg(feature = "captcha")]
+    return Ok(Some(Captcha {
+        verifier: Box::new(SiteverifyVerifier::new(
+            config.captcha_verify_url.as_deref().unwrap_or(default_url),
+            config
+                .captcha_secret
+                .clone()
+                .ok_or(StartupError::MissingSetting {
+                    setting: "CAPTCHA_PROVIDER",
+                    needed: "CAPTCHA_SECRET",
+                })?,
+        )),
+        routes: config.captcha_routes.clone(),
+    }));
+    #[cfg(not(feature = "captcha"))]
+    Err(StartupError::MissingFeature {
+        setting: "CAPTCHA_PROVIDER",
+        feature: "captcha",
+    })
+}
+
+// The `siteverify` endpoint hCaptcha and Cloudflare Turnstile both offer, taking the
+// secret and token as a form and answering `{"success": bool}`.
+#[cfg(feature = "captcha")]
+pub struct SiteverifyVerifier {
+    client: reqwest::Client,
+    url: String,
+    secret: String,
+}
+
+#[cfg(feature = "captcha")]
+impl SiteverifyVerifier {
+    pub fn new(url: &str, secret: String) -> Self {
+        let client = reqwest::Client::builder()
+            .timeout(std::time::Duration::from_secs(10))
+            .build()
+            .expect("HTTP client should build.");
+
+        Self {
+            client,
+            url: url.to_owned(),
+            secret,
+        }
+    }
+}
+
+#[cfg(feature = "captcha")]
+#[async_trait]
+impl CaptchaVerifier for SiteverifyVerifier {
+    async fn verify(&self, token: &str, client_ip: Option<&str>) -> Result<bool, CaptchaError> {
+        #[derive(serde::Deserialize)]
+        struct Response {
+            success: bool,
+        }
+
+        let mut form = vec![("secret", self.secret.as_str()), ("response", token)];
+        if let Some(client_ip) = client_ip {
+            form.push(("remoteip", client_ip));
+        }
+
+        let response: Response = self
+            .client
+            .post(&self.url)
+            .form(&form)
+            .send()
+            .await
+            .and_then(|response| response.error_for_status())
+            .map_err(|err| CaptchaError::Request(err.to_string()))?
+            .json()
+            .await
+            .map_err(|err| CaptchaError::Request(err.to_string()))?;
+        Ok(response.success)
+    }
+}
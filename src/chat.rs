@@ -0,0 +1,630 @@
+use async_trait::async_trait;
+use chrono::SecondsFormat;
+use log::warn;
+use serde_json::{json, Value};
+use thiserror::Error;
+use tokio::sync::mpsc;
+
+use crate::{
+    backpressure,
+    config::AppConfig,
+    models::{metadata_tags, AnswerDetail, QuestionDetail, QuestionUuid},
+    startup::StartupError,
+};
+
+// Notices waiting to be posted. Past this, new ones are dropped with a warning.
+const QUEUE_CAPACITY: usize = 1000;
+
+// Characters of a description quoted in a post.
+const EXCERPT_CHARS: usize = 200;
+
+// What chat integrations are told about. Questions cannot be flagged yet. `author` is
+// the actor who made the change, see `Actor`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Notice {
+    QuestionCreated {
+        question: QuestionDetail,
+        author: String,
+    },
+    AnswerCreated {
+        answer: AnswerDetail,
+        author: String,
+    },
+}
+
+impl Notice {
+    pub const EVENT_TYPES: [&'static str; 2] = ["question_created", "answer_created"];
+
+    // Named like the outbox events.
+    pub fn event_type(&self) -> &'static str {
+        match self {
+            Notice::QuestionCreated { .. } => "question_created",
+            Notice::AnswerCreated { .. } => "answer_created",
+        }
+    }
+}
+
+#[derive(Error, Debug)]
+#[error("Posting a notice failed: {0}")]
+pub struct NotifyError(pub String);
+
+// A chat integration new questions are posted to.
+#[async_trait]
+pub trait Notifier {
+    async fn notify(&self, notice: &Notice) -> Result<(), NotifyError>;
+}
+
+// Every integration configured in the environment; none when nothing is set.
+pub fn notifiers_from_config(
+    config: &AppConfig,
+) -> Result<Vec<Box<dyn Notifier + Send + Sync>>, StartupError> {
+    Ok([
+        slack_from_config(config)?,
+        discord_from_config(config)?,
+        telegram_from_config(config)?,
+    ]
+    .into_iter()
+    .flatten()
+    .collect())
+}
+
+type NotifierFromConfig = Result<Option<Box<dyn Notifier + Send + Sync>>, StartupError>;
+
+fn slack_from_config(config: &AppConfig) -> NotifierFromConfig {
+    match (&config.slack_webhook_url, config.slack_routes.is_empty()) {
+        (None, true) => Ok(None),
+        #[cfg(feature = "webhooks")]
+        _ => Ok(Some(Box::new(SlackNotifier::new(
+            config.slack_webhook_url.clone(),
+            config.slack_routes.clone(),
+            config.public_url.clone(),
+        )))),
+        #[cfg(not(feature = "webhooks"))]
+        _ => Err(StartupError::MissingFeature {
+            setting: "SLACK_WEBHOOK_URL or SLACK_ROUTES",
+            feature: "webhooks",
+        }),
+    }
+}
+
+fn discord_from_config(config: &AppConfig) -> NotifierFromConfig {
+    for (event_type, _) in &config.discord_webhooks {
+        if !Notice::EVENT_TYPES.contains(&event_type.as_str()) {
+            warn!(
+                "DISCORD_WEBHOOKS routes {:?}, which is not one of: {}.",
+                event_type,
+                Notice::EVENT_TYPES.join(", ")
+            );
+        }
+    }
+
+    match config.discord_webhooks.is_empty() {
+        true => Ok(None),
+        #[cfg(feature = "webhooks")]
+        false => Ok(Some(Box::new(DiscordNotifier::new(
+            config.discord_webhooks.clone(),
+            config.public_url.clone(),
+        )))),
+        #[cfg(not(feature = "webhooks"))]
+        false => Err(StartupError::MissingFeature {
+            setting: "DISCORD_WEBHOOKS",
+            feature: "webhooks",
+        }),
+    }
+}
+
+fn telegram_from_config(config: &AppConfig) -> NotifierFromConfig {
+    match &config.telegram_bot_token {
+        // A bot without chats still answers commands.
+        Some(_) if config.telegram_chat_ids.is_empty() => Ok(None),
+        None => Ok(None),
+        #[cfg(feature = "telegram")]
+        Some(token) => Ok(Some(Box::new(TelegramNotifier::new(
+            token,
+            config.telegram_chat_ids.clone(),
+            config.public_url.clone(),
+        )))),
+        #[cfg(not(feature = "telegram"))]
+        Some(_) => Err(StartupError::MissingFeature {
+            setting: "TELEGRAM_BOT_TOKEN",
+            feature: "telegram",
+        }),
+    }
+}
+
+// Posts notices to every notifier from a queue drained in the background, so a slow
+// chat service cannot hold up the requests that create questions. Like the emails,
+// delivery is best effort.
+#[derive(Clone)]
+pub struct ChatNotifiers {
+    queue: mpsc::Sender<Notice>,
+}
+
+impl ChatNotifiers {
+    pub fn spawn(notifiers: Vec<Box<dyn Notifier + Send + Sync>>) -> Self {
+        let (queue, mut notices) = mpsc::channel::<Notice>(QUEUE_CAPACITY);
+
+        tokio::spawn(async move {
+            while let Some(notice) = notices.recv().await {
+                backpressure::record_queue_depth("chat", notices.len(), QUEUE_CAPACITY);
+                for notifier in &notifiers {
+                    if let Err(err) = notifier.notify(&notice).await {
+                        warn!("{}", err);
+                    }
+                }
+            }
+        });
+
+        Self { queue }
+    }
+
+    pub fn enqueue(&self, notice: Notice) {
+        if let Err(err) = self.queue.try_send(notice) {
+            warn!("Dropped a chat notice: {}", err);
+        }
+        backpressure::record_queue("chat", &self.queue);
+    }
+}
+
+// The question's `tags` metadata, a list of strings or a single one.
+pub fn tags(question: &QuestionDetail) -> Vec<&str> {
+    metadata_tags(&question.metadata)
+}
+
+// The description cut at a word boundary, with an ellipsis when something was left out.
+pub fn excerpt(text: &str) -> String {
+    if text.chars().count() <= EXCERPT_CHARS {
+        return text.to_owned();
+    }
+
+    let cut: String = text.chars().take(EXCERPT_CHARS).collect();
+    let cut = match cut.rfind(char::is_whitespace) {
+        Some(end) => cut[..end].trim_end(),
+        None => &cut,
+    };
+    format!("{}…", cut)
+}
+
+// Where a question and its answers can be read: a URL under `PUBLIC_URL` when it is set.
+fn link(public_url: Option<&str>, question_uuid: QuestionUuid) -> Option<String> {
+    public_url.map(|base| format!("{}/answers/{}", base, question_uuid))
+}
+
+// The link, or the route to call for plain text without one.
+pub fn link_or_route(public_url: Option<&str>, question_uuid: QuestionUuid) -> String {
+    link(public_url, question_uuid).unwrap_or_else(|| format!("GET /answers/{}", question_uuid))
+}
+
+#[cfg(any(feature = "webhooks", feature = "telegram"))]
+fn http_client() -> reqwest::Client {
+    reqwest::Client::builder()
+        .timeout(std::time::Duration::from_secs(10))
+        .build()
+        .expect("HTTP client should build.")
+}
+
+// Anything but a 2xx response counts as a failure.
+#[cfg(any(feature = "webhooks", feature = "telegram"))]
+async fn post(
+    client: &reqwest::Client,
+    service: &str,
+    url: &str,
+    body: &Value,
+) -> Result<(), NotifyError> {
+    client
+        .post(url)
+        .json(body)
+        .send()
+        .await
+        .and_then(|response| response.error_for_status())
+        .map_err(|err| NotifyError(format!("{}: {}", service, err)))?;
+
+    Ok(())
+}
+
+// Posts new questions to Slack incoming webhooks. Each webhook belongs to one channel,
+// so routing a question is choosing webhooks: those of the routes matching its tags, or
+// the default one when none match.
+pub struct SlackNotifier {
+    default_url: Option<String>,
+    routes: Vec<(String, String)>,
+    public_url: Option<String>,
+    #[cfg(feature = "webhooks")]
+    client: reqwest::Client,
+}
+
+impl SlackNotifier {
+    pub fn new(
+        default_url: Option<String>,
+        routes: Vec<(String, String)>,
+        public_url: Option<String>,
+    ) -> Self {
+        Self {
+            default_url,
+            routes,
+            public_url,
+            #[cfg(feature = "webhooks")]
+            client: http_client(),
+        }
+    }
+
+    // Each webhook once, in route order.
+    pub fn webhooks(&self, question: &QuestionDetail) -> Vec<&str> {
+        let tags = tags(question);
+        let mut urls: Vec<&str> = vec![];
+        for (tag, url) in &self.routes {
+            if tags.contains(&tag.as_str()) && !urls.contains(&url.as_str()) {
+                urls.push(url);
+            }
+        }
+
+        if urls.is_empty() {
+            urls.extend(self.default_url.as_deref());
+        }
+        urls
+    }
+
+    // `text` is what notifications and clients without blocks show.
+    pub fn message(&self, question: &QuestionDetail) -> Value {
+        slack_message(question, self.public_url.as_deref())
+    }
+}
+
+#[cfg(feature = "webhooks")]
+#[async_trait]
+impl Notifier for SlackNotifier {
+    async fn notify(&self, notice: &Notice) -> Result<(), NotifyError> {
+        let Notice::QuestionCreated { question, .. } = notice else {
+            return Ok(());
+        };
+        let message = self.message(question);
+
+        for url in self.webhooks(question) {
+            post(&self.client, "Slack", url, &message).await?;
+        }
+        Ok(())
+    }
+}
+
+fn slack_message(question: &QuestionDetail, public_url: Option<&str>) -> Value {
+    let title = slack_escape(&question.title);
+    let mut text = match link(public_url, question.question_uuid) {
+        Some(link) => format!("*New question:* <{}|{}>", link, title),
+        None => format!("*New question:* {}", title),
+    };
+    if let Some(description) = &question.description {
+        text.push_str(&format!("\n>{}", slack_escape(&excerpt(description))));
+    }
+    let tags = tags(question);
+    if !tags.is_empty() {
+        text.push_str(&format!("\nTags: {}", slack_escape(&tags.join(", "))));
+    }
+    if public_url.is_none() {
+        text.push_str(&format!("\n`GET /answers/{}`", question.question_uuid));
+    }
+
+    json!({
+        "text": format!("New question: {}", question.title),
+        "blocks": [{
+            "type": "section",
+            "text": { "type": "mrkdwn", "text": text },
+        }],
+    })
+}
+
+// Slack reads `<...>` as links and mentions.
+fn slack_escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+// Posts to Discord webhooks, each event type to the webhooks routed for it; event types
+// without one are not posted.
+pub struct DiscordNotifier {
+    webhooks: Vec<(String, String)>,
+    public_url: Option<String>,
+    #[cfg(feature = "webhooks")]
+    client: reqwest::Client,
+}
+
+impl DiscordNotifier {
+    pub fn new(webhooks: Vec<(String, String)>, public_url: Option<String>) -> Self {
+        Self {
+            webhooks,
+            public_url,
+            #[cfg(feature = "webhooks")]
+            client: http_client(),
+        }
+    }
+
+    // Each webhook once, in `DISCORD_WEBHOOKS` order.
+    pub fn webhooks(&self, notice: &Notice) -> Vec<&str> {
+        let mut urls: Vec<&str> = vec![];
+        for (event_type, url) in &self.webhooks {
+            if event_type == notice.event_type() && !urls.contains(&url.as_str()) {
+                urls.push(url);
+            }
+        }
+        urls
+    }
+
+    // One embed. Discord shows markdown in descriptions but not in titles.
+    pub fn message(&self, notice: &Notice) -> Value {
+        discord_message(notice, self.public_url.as_deref())
+    }
+}
+
+#[cfg(feature = "webhooks")]
+#[async_trait]
+impl Notifier for DiscordNotifier {
+    async fn notify(&self, notice: &Notice) -> Result<(), NotifyError> {
+        let urls = self.webhooks(notice);
+        if urls.is_empty() {
+            return Ok(());
+        }
+
+        let message = self.message(notice);
+        for url in urls {
+            post(&self.client, "Discord", url, &message).await?;
+        }
+        Ok(())
+    }
+}
+
+fn discord_message(notice: &Notice, public_url: Option<&str>) -> Value {
+    let (title, text, author, question_uuid, created_at) = match notice {
+        Notice::QuestionCreated { question, author } => (
+            format!("New question: {}", question.title),
+            question.description.as_deref().unwrap_or_default(),
+            author,
+            question.question_uuid,
+            question.created_at,
+        ),
+        Notice::AnswerCreated { answer, author } => (
+            "New answer".to_owned(),
+            answer.content.as_str(),
+            author,
+            answer.question_uuid,
+            answer.created_at,
+        ),
+    };
+
+    let mut embed = json!({
+        "title": excerpt(&title),
+        "description": excerpt(text),
+        "author": { "name": author },
+        "footer": { "text": notice.event_type() },
+        "timestamp": created_at.to_rfc3339_opts(SecondsFormat::AutoSi, true),
+    });
+    if let Some(link) = link(public_url, question_uuid) {
+        embed["url"] = json!(link);
+    }
+
+    json!({ "embeds": [embed] })
+}
+
+// Posts new questions to Telegram chats through the Bot API, as plain text so titles
+// need no escaping. The bot must be a member of each chat.
+#[cfg(feature = "telegram")]
+pub struct TelegramNotifier {
+    send_message_url: String,
+    chat_ids: Vec<String>,
+    public_url: Option<String>,
+    client: reqwest::Client,
+}
+
+#[cfg(feature = "telegram")]
+impl TelegramNotifier {
+    pub fn new(token: &str, chat_ids: Vec<String>, public_url: Option<String>) -> Self {
+        Self {
+            send_message_url: format!("https://api.telegram.org/bot{}/sendMessage", token),
+            chat_ids,
+            public_url,
+            client: http_client(),
+        }
+    }
+}
+
+#[cfg(feature = "telegram")]
+#[async_trait]
+impl Notifier for TelegramNotifier {
+    async fn notify(&self, notice: &Notice) -> Result<(), NotifyError> {
+        let Notice::QuestionCreated { question, .. } = notice else {
+            return Ok(());
+        };
+
+        let text = telegram_text(question, self.public_url.as_deref());
+        for chat_id in &self.chat_ids {
+            // The URL holds the token, so it is left out of errors.
+            post(
+                &self.client,
+                "Telegram",
+                &self.send_message_url,
+                &telegram_message(chat_id, &text),
+            )
+            .await
+            .map_err(|err| NotifyError(err.0.replace(&self.send_message_url, "sendMessage")))?;
+        }
+        Ok(())
+    }
+}
+
+pub fn telegram_text(question: &QuestionDetail, public_url: Option<&str>) -> String {
+    let mut text = format!("New question: {}", question.title);
+    if let Some(description) = &question.description {
+        text.push_str(&format!("\n\n{}", excerpt(description)));
+    }
+    text.push_str(&format!(
+        "\n\n{}",
+        link_or_route(public_url, question.question_uuid)
+    ));
+    text
+}
+
+// The parameters of a `sendMessage` call. Numeric ids are sent as numbers, `@channel`
+// names as they are.
+pub fn telegram_message(chat_id: &str, text: &str) -> Value {
+    let chat_id = chat_id
+        .parse::<i64>()
+        .map_or_else(|_| json!(chat_id), |id| json!(id));
+    json!({
+        "chat_id": chat_id,
+        "text": text,
+        "disable_web_page_preview": true,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::DateTime;
+
+    use super::*;
+    use crate::models::QuestionUuid;
+
+    fn question(metadata: Value) -> QuestionDetail {
+        QuestionDetail {
+            question_uuid: QuestionUuid::new_v4(),
+            title: "Refund <urgent>".to_owned(),
+            description: Some(format!("{} tail", "word ".repeat(40))),
+            metadata,
+            created_at: DateTime::UNIX_EPOCH,
+            updated_at: DateTime::UNIX_EPOCH,
+            last_activity_at: DateTime::UNIX_EPOCH,
+            version: 1,
+            answer_count: 0,
+        }
+    }
+
+    #[test]
+    fn slack_should_route_by_tag_and_fall_back_to_the_default() {
+        let slack = SlackNotifier::new(
+            Some("https://default".to_owned()),
+            vec![
+                ("billing".to_owned(), "https://billing".to_owned()),
+                ("refunds".to_owned(), "https://billing".to_owned()),
+                ("api".to_owned(), "https://api".to_owned()),
+            ],
+            None,
+        );
+
+        assert_eq!(
+            slack.webhooks(&question(json!({ "tags": ["refunds", "billing", "api"] }))),
+            ["https://billing", "https://api"]
+        );
+        assert_eq!(
+            slack.webhooks(&question(json!({ "tags": "api" }))),
+            ["https://api"]
+        );
+        assert_eq!(
+            slack.webhooks(&question(json!({ "tags": ["mobile"] }))),
+            ["https://default"]
+        );
+        assert!(SlackNotifier::new(None, vec![], None)
+            .webhooks(&question(json!({})))
+            .is_empty());
+    }
+
+    #[test]
+    fn slack_message_should_escape_and_shorten() {
+        let question = question(json!({ "tags": ["billing"] }));
+
+        let message = slack_message(&question, None);
+
+        assert_eq!(message["text"], "New question: Refund <urgent>");
+        assert_eq!(
+            message["blocks"][0]["text"]["text"],
+            format!(
+                "*New question:* Refund &lt;urgent&gt;\n>{}…\nTags: billing\n`GET /answers/{}`",
+                "word ".repeat(40).trim_end(),
+                question.question_uuid
+            )
+        );
+    }
+
+    #[test]
+    fn slack_message_should_link_to_the_public_url() {
+        let question = question(json!({}));
+
+        let message = slack_message(&question, Some("https://qa.example.com"));
+
+        let text = message["blocks"][0]["text"]["text"].as_str().unwrap();
+        assert!(text.starts_with(&format!(
+            "*New question:* <https://qa.example.com/answers/{}|Refund &lt;urgent&gt;>\n>",
+            question.question_uuid
+        )));
+        assert!(!text.contains("GET /answers"));
+    }
+
+    #[test]
+    fn discord_should_post_each_event_type_to_its_webhooks() {
+        let question = question(json!({}));
+        let notice = Notice::QuestionCreated {
+            question: question.clone(),
+            author: "anonymous".to_owned(),
+        };
+        let answer = Notice::AnswerCreated {
+            answer: AnswerDetail {
+                answer_uuid: crate::models::AnswerUuid::new_v4(),
+                question_uuid: question.question_uuid,
+                content: "content".to_owned(),
+                created_at: DateTime::UNIX_EPOCH,
+                updated_at: DateTime::UNIX_EPOCH,
+                version: 1,
+            },
+            author: "admin".to_owned(),
+        };
+        let discord = DiscordNotifier::new(
+            vec![
+                (
+                    "question_created".to_owned(),
+                    "https://questions".to_owned(),
+                ),
+                ("question_created".to_owned(), "https://all".to_owned()),
+                ("answer_created".to_owned(), "https://all".to_owned()),
+            ],
+            Some("https://qa.example.com".to_owned()),
+        );
+
+        assert_eq!(
+            discord.webhooks(&notice),
+            ["https://questions", "https://all"]
+        );
+        assert_eq!(discord.webhooks(&answer), ["https://all"]);
+
+        let message = discord_message(&notice, Some("https://qa.example.com"));
+        assert_eq!(
+            message["embeds"][0],
+            json!({
+                "title": "New question: Refund <urgent>",
+                "description": format!("{}…", "word ".repeat(40).trim_end()),
+                "url": format!("https://qa.example.com/answers/{}", question.question_uuid),
+                "author": { "name": "anonymous" },
+                "footer": { "text": "question_created" },
+                "timestamp": "1970-01-01T00:00:00Z",
+            })
+        );
+        let message = discord_message(&answer, None);
+        assert_eq!(message["embeds"][0]["description"], "content");
+        assert_eq!(message["embeds"][0]["author"]["name"], "admin");
+        assert!(message["embeds"][0].get("url").is_none());
+    }
+
+    #[test]
+    fn telegram_messages_should_be_plain_text() {
+        let question = question(json!({}));
+
+        let text = telegram_text(&question, None);
+
+        assert_eq!(
+            text,
+            format!(
+                "New question: Refund <urgent>\n\n{}…\n\nGET /answers/{}",
+                "word ".repeat(40).trim_end(),
+                question.question_uuid
+            )
+        );
+        assert_eq!(telegram_message("-1001", &text)["chat_id"], -1001);
+        assert_eq!(telegram_message("@support", &text)["chat_id"], "@support");
+    }
+}
@@ -0,0 +1,278 @@
+use std::{
+    env,
+    fs::File,
+    io::{self, BufReader, BufWriter},
+    path::PathBuf,
+};
+
+use sqlx::{postgres::PgPoolOptions, PgPool};
+
+use crate::{
+    actor::Actor,
+    models::Export,
+    persistence::{
+        backup,
+        export::{self, TransferError},
+    },
+};
+#[cfg(feature = "elasticsearch")]
+use crate::{
+    persistence::question_dao::{QuestionDao, QuestionDaoImpl},
+    search::Elasticsearch,
+};
+
+pub const USAGE: &str = "\
+Usage:
+    question-answer-api-rust                  Serve the API
+    question-answer-api-rust backup [<file>]  Write a backup of the database
+    question-answer-api-rust restore <file>   Load a backup into an empty database
+    question-answer-api-rust export [--format json] [<file>]
+                                              Write questions and answers as JSON, to stdout without a file
+    question-answer-api-rust import <file>    Load an export next to the existing data
+    question-answer-api-rust reindex          Index every question in the search engine";
+
+// What the binary was started to do.
+#[derive(Debug, PartialEq)]
+pub enum Command {
+    Serve,
+    // Without a path the backup is written to the working directory.
+    Backup { path: Option<PathBuf> },
+    Restore { path: PathBuf },
+    // Without a path the export is written to stdout.
+    Export { path: Option<PathBuf> },
+    Import { path: PathBuf },
+    Reindex,
+}
+
+impl Command {
+    // `args` without the program name.
+    pub fn parse<I: IntoIterator<Item = String>>(args: I) -> Result<Self, String> {
+        let args: Vec<String> = args.into_iter().collect();
+        let args: Vec<&str> = args.iter().map(String::as_str).collect();
+
+        match args.as_slice() {
+            [] | ["serve"] => Ok(Command::Serve),
+            ["backup"] => Ok(Command::Backup { path: None }),
+            ["backup", path] => Ok(Command::Backup {
+                path: Some(PathBuf::from(path)),
+            }),
+            ["restore", path] => Ok(Command::Restore {
+                path: PathBuf::from(path),
+            }),
+            ["restore"] => Err("restore needs the backup file to load.".to_owned()),
+            ["export", rest @ ..] => parse_export(rest),
+            ["import", path] => Ok(Command::Import {
+                path: PathBuf::from(path),
+            }),
+            ["import"] => Err("import needs the export file to load.".to_owned()),
+            ["reindex"] => Ok(Command::Reindex),
+            [command, ..] => Err(format!("Unexpected arguments to {}.", command)),
+        }
+    }
+}
+
+// JSON is the only format so far; `--format` is accepted to leave room for others.
+fn parse_export(args: &[&str]) -> Result<Command, String> {
+    let (format, path) = match args {
+        [] => (None, None),
+        ["--format", format] => (Some(*format), None),
+        ["--format", format, path] | [path, "--format", format] => (Some(*format), Some(*path)),
+        [path] if !path.starts_with("--") => (None, Some(*path)),
+        _ => return Err("Usage: export [--format json] [<file>]".to_owned()),
+    };
+    if let Some(format) = format.filter(|format| *format != "json") {
+        return Err(format!("Unsupported export format {}; use json.", format));
+    }
+
+    Ok(Command::Export {
+        path: path.map(PathBuf::from),
+    })
+}
+
+pub async fn backup(path: Option<PathBuf>) -> Result<(), String> {
+    let path = path.unwrap_or_else(|| PathBuf::from(backup::file_name(chrono::Utc::now())));
+    let pool = connect().await?;
+
+    let summary = backup::backup(&pool, &path)
+        .await
+        .map_err(|err| format!("Backup failed: {}", err))?;
+    println!("Backed up {} to {}.", summary, path.display());
+    Ok(())
+}
+
+pub async fn restore(path: PathBuf) -> Result<(), String> {
+    let pool = connect().await?;
+    let actor = Actor {
+        name: "restore".to_owned(),
+        request_id: None,
+    };
+
+    let summary = actor
+        .scope(backup::restore(&pool, &path))
+        .await
+        .map_err(|err| format!("Restore failed, nothing was loaded: {}", err))?;
+    println!("Restored {} from {}.", summary, path.display());
+    Ok(())
+}
+
+pub async fn export(path: Option<PathBuf>) -> Result<(), String> {
+    let pool = connect().await?;
+
+    let exported = match &path {
+        Some(path) => {
+            let file = File::create(path).map_err(|err| format!("Export failed: {}", err))?;
+            export::export(&pool, &mut BufWriter::new(file)).await
+        }
+        None => export::export(&pool, &mut BufWriter::new(io::stdout())).await,
+    }
+    .map_err(|err| format!("Export failed: {}", err))?;
+
+    if let Some(path) = path {
+        println!("Exported {} questions to {}.", exported, path.display());
+    }
+    Ok(())
+}
+
+pub async fn import(path: PathBuf) -> Result<(), String> {
+    let file = File::open(&path).map_err(|err| format!("Import failed: {}", err))?;
+    let document: Export = serde_json::from_reader(BufReader::new(file)).map_err(|err| {
+        format!(
+            "Import failed, {} is not an export: {}",
+            path.display(),
+            err
+        )
+    })?;
+    let pool = connect().await?;
+    let actor = Actor {
+        name: "import".to_owned(),
+        request_id: None,
+    };
+
+    match actor.scope(export::import(&pool, document)).await {
+        Ok(summary) => {
+            println!(
+                "Imported {} questions and {} answers.",
+                summary.questions, summary.answers
+            );
+            Ok(())
+        }
+        Err(TransferError::Invalid(errors)) => {
+            for error in &errors {
+                eprintln!("{}", error);
+            }
+            Err(format!(
+                "Import failed, nothing was loaded: {} invalid records.",
+                errors.len()
+            ))
+        }
+        Err(err) => Err(format!("Import failed, nothing was loaded: {}", err)),
+    }
+}
+
+// Fills the search engine from the database, e.g. for a new index or after the outbox
+// was pruned before it caught up. Documents are overwritten, so it can run any time.
+#[cfg(feature = "elasticsearch")]
+pub async fn reindex() -> Result<(), String> {
+    const BATCH_SIZE: usize = 500;
+
+    let config = crate::config::AppConfig::from_env();
+    if config.search_backend != crate::config::SearchBackend::Elasticsearch {
+        return Err("reindex needs SEARCH_BACKEND=elasticsearch.".to_owned());
+    }
+    let pool = connect().await?;
+    let questions = QuestionDaoImpl::new(pool)
+        .get_questions()
+        .await
+        .map_err(|err| format!("Reindex failed: {}", err))?;
+
+    let elasticsearch = Elasticsearch::from_config(&config);
+    for batch in questions.chunks(BATCH_SIZE) {
+        elasticsearch
+            .index_questions(batch)
+            .await
+            .map_err(|err| format!("Reindex failed: {}", err))?;
+    }
+    println!("Indexed {} questions.", questions.len());
+    Ok(())
+}
+
+#[cfg(not(feature = "elasticsearch"))]
+pub async fn reindex() -> Result<(), String> {
+    Err("reindex needs the elasticsearch cargo feature.".to_owned())
+}
+
+// Backups, exports and imports are Postgres only.
+async fn connect() -> Result<PgPool, String> {
+    let database_url = env::var("DATABASE_URL").map_err(|_| "DATABASE_URL must be set.")?;
+    if !matches!(
+        database_url.split(':').next(),
+        Some("postgres" | "postgresql")
+    ) {
+        return Err("This command needs a Postgres DATABASE_URL.".to_owned());
+    }
+
+    PgPoolOptions::new()
+        .max_connections(1)
+        .connect(&database_url)
+        .await
+        .map_err(|err| format!("Connecting to the database failed: {}", err))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &[&str]) -> Result<Command, String> {
+        Command::parse(args.iter().map(|arg| arg.to_string()))
+    }
+
+    #[test]
+    fn parse_should_serve_without_arguments() {
+        assert_eq!(parse(&[]), Ok(Command::Serve));
+        assert_eq!(parse(&["serve"]), Ok(Command::Serve));
+    }
+
+    #[test]
+    fn parse_should_read_backup_and_restore_paths() {
+        assert_eq!(parse(&["backup"]), Ok(Command::Backup { path: None }));
+        assert_eq!(
+            parse(&["backup", "qa.ndjson.gz"]),
+            Ok(Command::Backup {
+                path: Some(PathBuf::from("qa.ndjson.gz"))
+            })
+        );
+        assert_eq!(
+            parse(&["restore", "qa.ndjson.gz"]),
+            Ok(Command::Restore {
+                path: PathBuf::from("qa.ndjson.gz")
+            })
+        );
+    }
+
+    #[test]
+    fn parse_should_read_export_format_and_import_path() {
+        assert_eq!(parse(&["export"]), Ok(Command::Export { path: None }));
+        assert_eq!(
+            parse(&["export", "--format", "json", "qa.json"]),
+            Ok(Command::Export {
+                path: Some(PathBuf::from("qa.json"))
+            })
+        );
+        assert_eq!(
+            parse(&["import", "qa.json"]),
+            Ok(Command::Import {
+                path: PathBuf::from("qa.json")
+            })
+        );
+        assert!(parse(&["export", "--format", "csv"]).is_err());
+        assert!(parse(&["import"]).is_err());
+        assert_eq!(parse(&["reindex"]), Ok(Command::Reindex));
+    }
+
+    #[test]
+    fn parse_should_reject_unknown_or_incomplete_commands() {
+        assert!(parse(&["restore"]).is_err());
+        assert!(parse(&["backup", "a", "b"]).is_err());
+        assert!(parse(&["migrate"]).is_err());
+    }
+}
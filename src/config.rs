@@ -0,0 +1,1066 @@
+use std::{
+    collections::HashSet,
+    env,
+    path::{Path, PathBuf},
+    str::FromStr,
+    sync::Arc,
+    time::{Duration, SystemTime},
+};
+
+use arc_swap::ArcSwap;
+use log::{info, warn};
+use rocket::data::{ByteUnit, Limits};
+use tokio::task::JoinHandle;
+
+#[cfg(feature = "chaos")]
+use crate::persistence::chaos::ChaosConfig;
+use crate::persistence::{question_dao::QuestionDeletion, retry::RetryPolicy};
+
+pub struct AppConfig {
+    pub storage: Storage,
+    pub body_limits: BodyLimits,
+    pub request_timeout: Duration,
+    pub db_max_connections: u32,
+    // Startup tries this many times to reach the database before giving up.
+    pub db_connect_attempts: u32,
+    pub db_connect_retry_delay: Duration,
+    // Both below `request_timeout`, so a stalled database trips the breaker before requests time out.
+    pub db_acquire_timeout: Duration,
+    pub breaker_call_timeout: Duration,
+    pub database_read_url: Option<String>,
+    pub replica_acquire_timeout: Duration,
+    pub replica_cooldown: Duration,
+    pub pool_acquire_warn_threshold: Duration,
+    // Requests are shed with 503 while the pool queue is this deep or its oldest caller
+    // has waited this long; zero disables either.
+    pub shed_queue_depth: usize,
+    pub shed_acquire_wait: Duration,
+    pub breaker_failure_threshold: u32,
+    pub breaker_open_duration: Duration,
+    pub retry_policy: RetryPolicy,
+    pub question_deletion: QuestionDeletion,
+    pub outbox_poll_interval: Duration,
+    pub outbox_batch_size: u32,
+    // Zero keeps published events forever.
+    pub outbox_retention_days: u32,
+    // Events are only logged while unset.
+    pub outbox_webhook_url: Option<String>,
+    // Warnings are logged while a background queue is this full, outbox events go out
+    // this late or answer notifications take this long; zero disables each.
+    pub queue_warn_percent: u8,
+    pub delivery_lag_warn_threshold: Duration,
+    pub fanout_warn_threshold: Duration,
+    // A nats: or amqp: URL questions are consumed from while set; needs the `nats` or
+    // `rabbitmq` cargo feature.
+    pub ingestion_url: Option<String>,
+    // The NATS subject or RabbitMQ queue.
+    pub ingestion_queue: String,
+    // Events are also produced to Kafka while set; needs the `kafka` cargo feature.
+    pub kafka_brokers: Option<String>,
+    #[cfg(feature = "kafka")]
+    pub kafka_question_topic: String,
+    #[cfg(feature = "kafka")]
+    pub kafka_answer_topic: String,
+    // Extra librdkafka producer properties, e.g. `security.protocol=SASL_SSL`.
+    #[cfg(feature = "kafka")]
+    pub kafka_properties: Vec<(String, String)>,
+    // Notification emails are only logged while unset.
+    pub smtp_url: Option<String>,
+    pub mail_from: String,
+    // Answer notifications can be replied to at addresses on this domain, see `replies`.
+    pub reply_email_domain: Option<String>,
+    pub reply_email_secret: Option<String>,
+    // Key of the tokens confirming subscription changes, see `confirmations`.
+    pub subscription_secret: Option<String>,
+    // How often due digests are looked for; zero stops sending them.
+    pub digest_check_interval: Duration,
+    // Where new questions are posted when no route matches their tags.
+    pub slack_webhook_url: Option<String>,
+    // Tag and the webhook its questions are posted to, in `SLACK_ROUTES` order.
+    pub slack_routes: Vec<(String, String)>,
+    // Event type and the Discord webhook it is posted to.
+    pub discord_webhooks: Vec<(String, String)>,
+    // Base URL the API is reached at, without a trailing slash, for links in chat posts.
+    pub public_url: Option<String>,
+    // New questions are posted to `telegram_chat_ids` while the token is set.
+    pub telegram_bot_token: Option<String>,
+    pub telegram_chat_ids: Vec<String>,
+    // Bot commands are answered while set; Telegram sends it with every update.
+    pub telegram_webhook_secret: Option<String>,
+    // Browsers are pushed to while both are set: the application server's P-256 key
+    // pair, base64url encoded as `web-push generate-vapid-keys` prints them.
+    pub vapid_private_key: Option<String>,
+    pub vapid_public_key: Option<String>,
+    // Who push services can contact about our pushes, a `mailto:` or `https:` URL.
+    pub vapid_subject: String,
+    // Where attachment bytes are kept.
+    pub object_store: ObjectStoreKind,
+    pub object_store_dir: PathBuf,
+    pub s3_bucket: Option<String>,
+    pub s3_region: String,
+    // An S3-compatible service such as MinIO, instead of AWS.
+    pub s3_endpoint: Option<String>,
+    pub s3_access_key_id: Option<String>,
+    pub s3_secret_access_key: Option<String>,
+    // How long pre-signed upload and download URLs work.
+    pub attachment_url_ttl: Duration,
+    // Longest edges, in pixels, of the thumbnails made of image attachments. Empty
+    // turns thumbnails off.
+    pub thumbnail_sizes: Vec<u32>,
+    // Users without an uploaded avatar are sent to Gravatar, which falls back to their
+    // identicon; otherwise they get the identicon straight away.
+    pub gravatar: bool,
+    // How long clients may reuse an avatar before asking again.
+    pub avatar_max_age: Duration,
+    // What uploads are checked with before they are served.
+    pub content_scanner: ScannerKind,
+    // clamd's TCP socket, `host:port`.
+    pub clamav_address: String,
+    // E.g. `icap://localhost:1344/avscan`; the path names the RESPMOD service.
+    pub icap_url: Option<String>,
+    pub scan_timeout: Duration,
+    // Told about quarantined attachments; they are only logged while empty.
+    pub moderator_emails: Vec<String>,
+    // Zero disables scheduled backups.
+    pub backup_interval: Duration,
+    pub backup_dir: PathBuf,
+    pub backup_keep: usize,
+    // Zero disables the query cache.
+    pub cache_ttl: Duration,
+    pub cors_allowed_origins: Vec<String>,
+    // How many levels of relations `?include=` may embed; zero turns expansion off.
+    pub max_include_depth: u32,
+    // Request bodies with fields the route does not know are rejected instead of ignored.
+    pub strict_json: bool,
+    pub feature_flags: HashSet<String>,
+    pub config_watch_interval: Duration,
+    // Admin endpoints are disabled while unset.
+    pub admin_token: Option<String>,
+    // Bearer token of the identity provider; SCIM provisioning is disabled while unset.
+    pub scim_token: Option<String>,
+    // Zero disables the archiving job.
+    pub archive_after_days: u32,
+    pub archive_interval: Duration,
+    pub stats_refresh_interval: Duration,
+    pub embedder: EmbedderKind,
+    #[cfg(feature = "openai")]
+    pub embedding_api_url: String,
+    #[cfg(feature = "openai")]
+    pub embedding_api_key: Option<String>,
+    #[cfg(feature = "openai")]
+    pub embedding_model: String,
+    // Where suggested answers come from; they also need the `suggest_answers` flag.
+    pub answerer: AnswererKind,
+    #[cfg(feature = "openai")]
+    pub answerer_api_url: String,
+    #[cfg(feature = "openai")]
+    pub answerer_api_key: Option<String>,
+    #[cfg(feature = "openai")]
+    pub answerer_model: String,
+    // Suggested answers each client IP may ask for per hour; zero lifts the limit.
+    pub suggestions_per_hour: u32,
+    // Checks new questions and answers; those scoring at least the threshold are held
+    // for a moderator instead of being published.
+    pub moderator: ModeratorKind,
+    pub moderation_threshold: f32,
+    // Words the heuristic moderator flags, matched case insensitively.
+    pub moderation_blocklist: Vec<String>,
+    #[cfg(any(feature = "openai", feature = "perspective"))]
+    pub moderation_api_url: Option<String>,
+    #[cfg(any(feature = "openai", feature = "perspective"))]
+    pub moderation_api_key: Option<String>,
+    // What happens to new and edited content containing a word admins put on the filter
+    // list at runtime.
+    pub word_filter: WordFilterPolicy,
+    // What happens to a new answer close to an existing answer to its question.
+    pub duplicate_answers: DuplicateAnswerPolicy,
+    // Trigram similarity from 0 to 1 from which answers count as close.
+    pub duplicate_answer_threshold: f32,
+    // Create routes anonymous callers need a captcha token for while a provider is set.
+    pub captcha_provider: CaptchaProviderKind,
+    pub captcha_routes: Vec<String>,
+    #[cfg(feature = "captcha")]
+    pub captcha_secret: Option<String>,
+    #[cfg(feature = "captcha")]
+    pub captcha_verify_url: Option<String>,
+    // Checks new questions and answers for spam; suspected spam is held for a moderator,
+    // whose decision is reported back.
+    pub spam_checker: SpamCheckerKind,
+    #[cfg(feature = "akismet")]
+    pub akismet_api_url: String,
+    #[cfg(feature = "akismet")]
+    pub akismet_api_key: Option<String>,
+    // Where tag suggestions come from; learned from the `tags` of existing questions.
+    pub tagger: TaggerKind,
+    #[cfg(feature = "openai")]
+    pub tagger_api_url: String,
+    #[cfg(feature = "openai")]
+    pub tagger_api_key: Option<String>,
+    #[cfg(feature = "openai")]
+    pub tagger_model: String,
+    pub max_suggested_tags: usize,
+    // New questions created without tags get the suggested ones.
+    pub auto_tag_questions: bool,
+    // How long the tagged questions suggestions are built from are reused.
+    pub tag_index_refresh: Duration,
+    // Whether links in questions get previews; needs the `link-previews` cargo feature.
+    pub link_previews: bool,
+    // Hosts, with their subdomains, links are fetched from; empty allows any public host.
+    pub link_preview_allowed_hosts: Vec<String>,
+    // Translates questions for `?lang=`; unset turns translation off.
+    pub translator: TranslatorKind,
+    #[cfg(feature = "translation")]
+    pub translator_api_url: Option<String>,
+    #[cfg(feature = "translation")]
+    pub translator_api_key: Option<String>,
+    // Where product analytics events go; unset sends none.
+    pub analytics_sink: AnalyticsSinkKind,
+    // Share of analytics events sent, from 0 to 1.
+    pub analytics_sample_rate: f64,
+    #[cfg(feature = "segment")]
+    pub segment_api_url: String,
+    #[cfg(feature = "segment")]
+    pub segment_write_key: Option<String>,
+    #[cfg(feature = "kafka")]
+    pub analytics_topic: String,
+    // Where `/questions/search` looks; a search engine is kept in sync from the outbox.
+    pub search_backend: SearchBackend,
+    // Deadline of `/questions/autocomplete`, far below `request_timeout` as a suggestion
+    // that comes after the next keystroke is no use.
+    pub autocomplete_timeout: Duration,
+    // Searches finding fewer results than this also suggest titles close to the query;
+    // zero turns suggestions off.
+    pub search_suggestions_below: usize,
+    // Trigram word similarity from 0 to 1 from which a title is suggested.
+    pub search_suggestion_threshold: f32,
+    // Credentials in the URL are sent as basic auth, which is how OpenSearch is secured.
+    #[cfg(feature = "elasticsearch")]
+    pub elasticsearch_url: String,
+    #[cfg(feature = "elasticsearch")]
+    pub elasticsearch_index: String,
+    #[cfg(feature = "elasticsearch")]
+    pub elasticsearch_api_key: Option<String>,
+    #[cfg(feature = "chaos")]
+    pub chaos: ChaosConfig,
+}
+
+impl AppConfig {
+    pub fn from_env() -> Self {
+        Self {
+            storage: from_env_or("STORAGE", Storage::Database),
+            body_limits: BodyLimits::from_env(),
+            request_timeout: secs_from_env("REQUEST_TIMEOUT_SECS", 10),
+            db_max_connections: from_env_or("DATABASE_MAX_CONNECTIONS", 5),
+            db_connect_attempts: from_env_or("DATABASE_CONNECT_ATTEMPTS", 5),
+            db_connect_retry_delay: secs_from_env("DATABASE_CONNECT_RETRY_SECS", 2),
+            db_acquire_timeout: millis_from_env("DATABASE_ACQUIRE_TIMEOUT_MS", 3000),
+            breaker_call_timeout: millis_from_env("BREAKER_CALL_TIMEOUT_MS", 5000),
+            database_read_url: env::var("DATABASE_READ_URL").ok(),
+            replica_acquire_timeout: millis_from_env("REPLICA_ACQUIRE_TIMEOUT_MS", 1000),
+            replica_cooldown: secs_from_env("REPLICA_COOLDOWN_SECS", 10),
+            pool_acquire_warn_threshold: millis_from_env("POOL_ACQUIRE_WARN_MS", 500),
+            shed_queue_depth: from_env_or("SHED_QUEUE_DEPTH", 0),
+            shed_acquire_wait: millis_from_env("SHED_ACQUIRE_WAIT_MS", 0),
+            breaker_failure_threshold: from_env_or("BREAKER_FAILURE_THRESHOLD", 5),
+            breaker_open_duration: secs_from_env("BREAKER_OPEN_SECS", 30),
+            cache_ttl: secs_from_env("CACHE_TTL_SECS", 30),
+            cors_allowed_origins: list_from_env("CORS_ALLOWED_ORIGINS", "*"),
+            max_include_depth: from_env_or("MAX_INCLUDE_DEPTH", 1),
+            strict_json: from_env_or("STRICT_JSON", false),
+            feature_flags: list_from_env("FEATURE_FLAGS", "").into_iter().collect(),
+            config_watch_interval: secs_from_env("CONFIG_WATCH_SECS", 5),
+            admin_token: env::var("ADMIN_TOKEN")
+                .ok()
+                .filter(|token| !token.is_empty()),
+            scim_token: env::var("SCIM_TOKEN")
+                .ok()
+                .filter(|token| !token.is_empty()),
+            archive_after_days: from_env_or("ARCHIVE_AFTER_DAYS", 0),
+            archive_interval: secs_from_env("ARCHIVE_INTERVAL_SECS", 3600),
+            stats_refresh_interval: secs_from_env("STATS_REFRESH_SECS", 300),
+            embedder: from_env_or("EMBEDDER", EmbedderKind::None),
+            #[cfg(feature = "openai")]
+            embedding_api_url: env::var("EMBEDDING_API_URL")
+                .unwrap_or_else(|_| "https://api.openai.com/v1".to_owned()),
+            #[cfg(feature = "openai")]
+            embedding_api_key: env::var("EMBEDDING_API_KEY")
+                .ok()
+                .filter(|key| !key.is_empty()),
+            #[cfg(feature = "openai")]
+            embedding_model: env::var("EMBEDDING_MODEL")
+                .unwrap_or_else(|_| "text-embedding-3-small".to_owned()),
+            answerer: from_env_or("ANSWERER", AnswererKind::None),
+            #[cfg(feature = "openai")]
+            answerer_api_url: env::var("ANSWERER_API_URL")
+                .unwrap_or_else(|_| "https://api.openai.com/v1".to_owned()),
+            #[cfg(feature = "openai")]
+            answerer_api_key: env::var("ANSWERER_API_KEY")
+                .ok()
+                .filter(|key| !key.is_empty()),
+            #[cfg(feature = "openai")]
+            answerer_model: env::var("ANSWERER_MODEL").unwrap_or_else(|_| "gpt-4o-mini".to_owned()),
+            suggestions_per_hour: from_env_or("SUGGESTIONS_PER_HOUR", 10),
+            moderator: from_env_or("MODERATION_PROVIDER", ModeratorKind::None),
+            moderation_threshold: from_env_or("MODERATION_THRESHOLD", 0.8),
+            moderation_blocklist: list_from_env("MODERATION_BLOCKLIST", ""),
+            #[cfg(any(feature = "openai", feature = "perspective"))]
+            moderation_api_url: env::var("MODERATION_API_URL")
+                .ok()
+                .filter(|url| !url.is_empty()),
+            #[cfg(any(feature = "openai", feature = "perspective"))]
+            moderation_api_key: env::var("MODERATION_API_KEY")
+                .ok()
+                .filter(|key| !key.is_empty()),
+            word_filter: from_env_or("WORD_FILTER", WordFilterPolicy::Off),
+            duplicate_answers: from_env_or("DUPLICATE_ANSWERS", DuplicateAnswerPolicy::Warn),
+            duplicate_answer_threshold: from_env_or("DUPLICATE_ANSWER_THRESHOLD", 0.6),
+            captcha_provider: from_env_or("CAPTCHA_PROVIDER", CaptchaProviderKind::None),
+            captcha_routes: list_from_env(
+                "CAPTCHA_ROUTES",
+                "/question,/question/with-answer,/answer",
+            ),
+            #[cfg(feature = "captcha")]
+            captcha_secret: env::var("CAPTCHA_SECRET")
+                .ok()
+                .filter(|secret| !secret.is_empty()),
+            #[cfg(feature = "captcha")]
+            captcha_verify_url: env::var("CAPTCHA_VERIFY_URL")
+                .ok()
+                .filter(|url| !url.is_empty()),
+            spam_checker: from_env_or("SPAM_CHECKER", SpamCheckerKind::None),
+            #[cfg(feature = "akismet")]
+            akismet_api_url: env::var("AKISMET_API_URL")
+                .unwrap_or_else(|_| "https://rest.akismet.com".to_owned()),
+            #[cfg(feature = "akismet")]
+            akismet_api_key: env::var("AKISMET_API_KEY")
+                .ok()
+                .filter(|key| !key.is_empty()),
+            tagger: from_env_or("TAG_SUGGESTER", TaggerKind::None),
+            #[cfg(feature = "openai")]
+            tagger_api_url: env::var("TAGGER_API_URL")
+                .unwrap_or_else(|_| "https://api.openai.com/v1".to_owned()),
+            #[cfg(feature = "openai")]
+            tagger_api_key: env::var("TAGGER_API_KEY")
+                .ok()
+                .filter(|key| !key.is_empty()),
+            #[cfg(feature = "openai")]
+            tagger_model: env::var("TAGGER_MODEL").unwrap_or_else(|_| "gpt-4o-mini".to_owned()),
+            max_suggested_tags: from_env_or("MAX_SUGGESTED_TAGS", 3),
+            auto_tag_questions: from_env_or("AUTO_TAG_QUESTIONS", false),
+            tag_index_refresh: secs_from_env("TAG_INDEX_REFRESH_SECS", 300),
+            link_previews: from_env_or("LINK_PREVIEWS", false),
+            link_preview_allowed_hosts: list_from_env("LINK_PREVIEW_ALLOWED_HOSTS", ""),
+            translator: from_env_or("TRANSLATOR", TranslatorKind::None),
+            #[cfg(feature = "translation")]
+            translator_api_url: env::var("TRANSLATOR_API_URL")
+                .ok()
+                .filter(|url| !url.is_empty()),
+            #[cfg(feature = "translation")]
+            translator_api_key: env::var("TRANSLATOR_API_KEY")
+                .ok()
+                .filter(|key| !key.is_empty()),
+            analytics_sink: from_env_or("ANALYTICS_SINK", AnalyticsSinkKind::None),
+            analytics_sample_rate: from_env_or("ANALYTICS_SAMPLE_RATE", 1.0_f64).clamp(0.0, 1.0),
+            #[cfg(feature = "segment")]
+            segment_api_url: env::var("SEGMENT_API_URL")
+                .unwrap_or_else(|_| "https://api.segment.io".to_owned()),
+            #[cfg(feature = "segment")]
+            segment_write_key: env::var("SEGMENT_WRITE_KEY")
+                .ok()
+                .filter(|key| !key.is_empty()),
+            #[cfg(feature = "kafka")]
+            analytics_topic: env::var("ANALYTICS_KAFKA_TOPIC")
+                .unwrap_or_else(|_| "qa.analytics".to_owned()),
+            search_backend: from_env_or("SEARCH_BACKEND", SearchBackend::Database),
+            autocomplete_timeout: millis_from_env("AUTOCOMPLETE_TIMEOUT_MS", 300),
+            search_suggestions_below: from_env_or("SEARCH_SUGGESTIONS_BELOW", 3),
+            search_suggestion_threshold: from_env_or("SEARCH_SUGGESTION_THRESHOLD", 0.3),
+            #[cfg(feature = "elasticsearch")]
+            elasticsearch_url: env::var("ELASTICSEARCH_URL")
+                .unwrap_or_else(|_| "http://localhost:9200".to_owned()),
+            #[cfg(feature = "elasticsearch")]
+            elasticsearch_index: env::var("ELASTICSEARCH_INDEX")
+                .unwrap_or_else(|_| "questions".to_owned()),
+            #[cfg(feature = "elasticsearch")]
+            elasticsearch_api_key: env::var("ELASTICSEARCH_API_KEY")
+                .ok()
+                .filter(|key| !key.is_empty()),
+            #[cfg(feature = "chaos")]
+            chaos: ChaosConfig {
+                latency: millis_from_env("CHAOS_LATENCY_MS", 0),
+                failure_rate: from_env_or("CHAOS_FAILURE_RATE", 0.0),
+            },
+            retry_policy: RetryPolicy {
+                max_attempts: from_env_or("DB_RETRY_ATTEMPTS", 3),
+                base_delay: millis_from_env("DB_RETRY_BASE_DELAY_MS", 50),
+                max_delay: millis_from_env("DB_RETRY_MAX_DELAY_MS", 1000),
+            },
+            question_deletion: from_env_or("QUESTION_DELETION", QuestionDeletion::Cascade),
+            outbox_poll_interval: millis_from_env("OUTBOX_POLL_MS", 1000),
+            outbox_batch_size: from_env_or("OUTBOX_BATCH_SIZE", 100),
+            outbox_retention_days: from_env_or("OUTBOX_RETENTION_DAYS", 7),
+            outbox_webhook_url: env::var("OUTBOX_WEBHOOK_URL")
+                .ok()
+                .filter(|url| !url.is_empty()),
+            queue_warn_percent: from_env_or("QUEUE_WARN_PERCENT", 80),
+            delivery_lag_warn_threshold: secs_from_env("OUTBOX_LAG_WARN_SECS", 60),
+            fanout_warn_threshold: millis_from_env("NOTIFICATION_FANOUT_WARN_MS", 10_000),
+            ingestion_url: env::var("INGESTION_URL").ok().filter(|url| !url.is_empty()),
+            ingestion_queue: env::var("INGESTION_QUEUE")
+                .unwrap_or_else(|_| "questions.ingest".to_owned()),
+            kafka_brokers: env::var("KAFKA_BROKERS")
+                .ok()
+                .filter(|brokers| !brokers.is_empty()),
+            #[cfg(feature = "kafka")]
+            kafka_question_topic: env::var("KAFKA_QUESTION_TOPIC")
+                .unwrap_or_else(|_| "qa.questions".to_owned()),
+            #[cfg(feature = "kafka")]
+            kafka_answer_topic: env::var("KAFKA_ANSWER_TOPIC")
+                .unwrap_or_else(|_| "qa.answers".to_owned()),
+            #[cfg(feature = "kafka")]
+            kafka_properties: pairs_from_env("KAFKA_PROPERTIES"),
+            smtp_url: env::var("SMTP_URL").ok().filter(|url| !url.is_empty()),
+            mail_from: env::var("MAIL_FROM")
+                .unwrap_or_else(|_| "Questions and Answers <noreply@localhost>".to_owned()),
+            reply_email_domain: env::var("REPLY_EMAIL_DOMAIN")
+                .ok()
+                .filter(|domain| !domain.is_empty()),
+            reply_email_secret: env::var("REPLY_EMAIL_SECRET")
+                .ok()
+                .filter(|secret| !secret.is_empty()),
+            subscription_secret: env::var("SUBSCRIPTION_SECRET")
+                .ok()
+                .filter(|secret| !secret.is_empty()),
+            digest_check_interval: secs_from_env("DIGEST_CHECK_SECS", 3600),
+            slack_webhook_url: env::var("SLACK_WEBHOOK_URL")
+                .ok()
+                .filter(|url| !url.is_empty()),
+            slack_routes: pairs_from_env("SLACK_ROUTES"),
+            discord_webhooks: pairs_from_env("DISCORD_WEBHOOKS"),
+            public_url: env::var("PUBLIC_URL")
+                .ok()
+                .map(|url| url.trim_end_matches('/').to_owned())
+                .filter(|url| !url.is_empty()),
+            telegram_bot_token: env::var("TELEGRAM_BOT_TOKEN")
+                .ok()
+                .filter(|token| !token.is_empty()),
+            telegram_chat_ids: list_from_env("TELEGRAM_CHAT_IDS", ""),
+            telegram_webhook_secret: env::var("TELEGRAM_WEBHOOK_SECRET")
+                .ok()
+                .filter(|secret| !secret.is_empty()),
+            vapid_private_key: env::var("VAPID_PRIVATE_KEY")
+                .ok()
+                .filter(|key| !key.is_empty()),
+            vapid_public_key: env::var("VAPID_PUBLIC_KEY")
+                .ok()
+                .filter(|key| !key.is_empty()),
+            vapid_subject: env::var("VAPID_SUBJECT")
+                .unwrap_or_else(|_| "mailto:noreply@localhost".to_owned()),
+            object_store: from_env_or("OBJECT_STORE", ObjectStoreKind::Local),
+            object_store_dir: env::var("OBJECT_STORE_DIR")
+                .unwrap_or_else(|_| "objects".to_owned())
+                .into(),
+            s3_bucket: env::var("S3_BUCKET")
+                .ok()
+                .filter(|bucket| !bucket.is_empty()),
+            s3_region: env::var("S3_REGION").unwrap_or_else(|_| "us-east-1".to_owned()),
+            s3_endpoint: env::var("S3_ENDPOINT")
+                .ok()
+                .map(|url| url.trim_end_matches('/').to_owned())
+                .filter(|url| !url.is_empty()),
+            s3_access_key_id: env::var("AWS_ACCESS_KEY_ID")
+                .ok()
+                .filter(|key| !key.is_empty()),
+            s3_secret_access_key: env::var("AWS_SECRET_ACCESS_KEY")
+                .ok()
+                .filter(|key| !key.is_empty()),
+            attachment_url_ttl: secs_from_env("ATTACHMENT_URL_SECS", 900),
+            thumbnail_sizes: list_from_env("THUMBNAIL_SIZES", "160,640")
+                .iter()
+                .filter_map(|size| size.parse().ok())
+                .filter(|&size| size > 0)
+                .collect(),
+            gravatar: from_env_or("AVATAR_GRAVATAR", true),
+            avatar_max_age: secs_from_env("AVATAR_MAX_AGE_SECS", 3600),
+            content_scanner: from_env_or("CONTENT_SCANNER", ScannerKind::None),
+            clamav_address: env::var("CLAMAV_ADDRESS")
+                .unwrap_or_else(|_| "localhost:3310".to_owned()),
+            icap_url: env::var("ICAP_URL").ok().filter(|url| !url.is_empty()),
+            scan_timeout: secs_from_env("SCAN_TIMEOUT_SECS", 60),
+            moderator_emails: list_from_env("MODERATOR_EMAILS", ""),
+            backup_interval: secs_from_env("BACKUP_INTERVAL_SECS", 0),
+            backup_dir: env::var("BACKUP_DIR")
+                .unwrap_or_else(|_| "backups".to_owned())
+                .into(),
+            backup_keep: from_env_or("BACKUP_KEEP", 7),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Storage {
+    Database,
+    // Process-local maps, for demos and frontend development without a database.
+    Memory,
+}
+
+impl FromStr for Storage {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.to_ascii_lowercase().as_str() {
+            "database" => Ok(Self::Database),
+            "memory" => Ok(Self::Memory),
+            _ => Err(format!("Unknown storage: {}", value)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ObjectStoreKind {
+    // Files under `object_store_dir`.
+    Local,
+    S3,
+}
+
+impl FromStr for ObjectStoreKind {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.to_ascii_lowercase().as_str() {
+            "local" => Ok(Self::Local),
+            "s3" => Ok(Self::S3),
+            _ => Err(format!("Unknown object store: {}", value)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ScannerKind {
+    // Uploads are ready at once.
+    None,
+    // Passes everything, to try out the scanning flow without a scanner.
+    Noop,
+    // A clamd daemon, see `clamav_address`.
+    ClamAv,
+    // An ICAP server such as c-icap, see `icap_url`.
+    Icap,
+}
+
+impl FromStr for ScannerKind {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.to_ascii_lowercase().as_str() {
+            "" | "none" => Ok(Self::None),
+            "noop" => Ok(Self::Noop),
+            "clamav" => Ok(Self::ClamAv),
+            "icap" => Ok(Self::Icap),
+            _ => Err(format!("Unknown content scanner: {}", value)),
+        }
+    }
+}
+
+// Where question embeddings for semantic search come from.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum EmbedderKind {
+    // Semantic search is disabled.
+    None,
+    // Word hashing in process, see `HashingEmbedder`.
+    Hashing,
+    // An OpenAI compatible `/embeddings` endpoint; needs the `openai` cargo feature.
+    OpenAi,
+}
+
+impl FromStr for EmbedderKind {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.to_ascii_lowercase().as_str() {
+            "" | "none" => Ok(Self::None),
+            "hashing" => Ok(Self::Hashing),
+            "openai" => Ok(Self::OpenAi),
+            _ => Err(format!("Unknown embedder: {}", value)),
+        }
+    }
+}
+
+// Where suggested draft answers come from.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum AnswererKind {
+    // Suggestions are disabled.
+    None,
+    // An OpenAI compatible `/chat/completions` endpoint; needs the `openai` cargo feature.
+    OpenAi,
+}
+
+impl FromStr for AnswererKind {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.to_ascii_lowercase().as_str() {
+            "" | "none" => Ok(Self::None),
+            "openai" => Ok(Self::OpenAi),
+            _ => Err(format!("Unknown answerer: {}", value)),
+        }
+    }
+}
+
+// Where tag suggestions come from.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TaggerKind {
+    // Suggestions are disabled.
+    None,
+    // TF-IDF over the words of existing tagged questions; runs in process.
+    TfIdf,
+    // An OpenAI compatible `/chat/completions` endpoint; needs the `openai` cargo feature.
+    OpenAi,
+}
+
+impl FromStr for TaggerKind {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.to_ascii_lowercase().as_str() {
+            "" | "none" => Ok(Self::None),
+            "tfidf" => Ok(Self::TfIdf),
+            "openai" => Ok(Self::OpenAi),
+            _ => Err(format!("Unknown tag suggester: {}", value)),
+        }
+    }
+}
+
+// Which machine translation service translates questions; all need the `translation`
+// cargo feature.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TranslatorKind {
+    // Translation is disabled.
+    None,
+    DeepL,
+    // Google Cloud Translation, basic edition.
+    Google,
+    LibreTranslate,
+}
+
+impl FromStr for TranslatorKind {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.to_ascii_lowercase().as_str() {
+            "" | "none" => Ok(Self::None),
+            "deepl" => Ok(Self::DeepL),
+            "google" => Ok(Self::Google),
+            "libretranslate" => Ok(Self::LibreTranslate),
+            _ => Err(format!("Unknown translator: {}", value)),
+        }
+    }
+}
+
+// Where product analytics events are sent.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum AnalyticsSinkKind {
+    // No events are recorded.
+    None,
+    Log,
+    // Segment's HTTP tracking API; needs the `segment` cargo feature.
+    Segment,
+    // ANALYTICS_KAFKA_TOPIC on KAFKA_BROKERS; needs the `kafka` cargo feature.
+    Kafka,
+}
+
+impl FromStr for AnalyticsSinkKind {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.to_ascii_lowercase().as_str() {
+            "" | "none" => Ok(Self::None),
+            "log" => Ok(Self::Log),
+            "segment" => Ok(Self::Segment),
+            "kafka" => Ok(Self::Kafka),
+            _ => Err(format!("Unknown analytics sink: {}", value)),
+        }
+    }
+}
+
+// What searches questions. Postgres full text search is used without a search engine and
+// while one fails.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SearchBackend {
+    Database,
+    // Elasticsearch or OpenSearch; needs the `elasticsearch` cargo feature.
+    Elasticsearch,
+}
+
+impl FromStr for SearchBackend {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.to_ascii_lowercase().as_str() {
+            "" | "database" | "postgres" => Ok(Self::Database),
+            "elasticsearch" | "opensearch" => Ok(Self::Elasticsearch),
+            _ => Err(format!("Unknown search backend: {}", value)),
+        }
+    }
+}
+
+// Who checks new content for toxicity and abuse.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ModeratorKind {
+    // Everything is published straight away.
+    None,
+    // Local rules: blocklisted words, link spam, shouting and repeated characters.
+    Heuristic,
+    // OpenAI's `/moderations` endpoint; needs the `openai` cargo feature.
+    OpenAi,
+    // Google's Perspective API; needs the `perspective` cargo feature.
+    Perspective,
+}
+
+impl FromStr for ModeratorKind {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.to_ascii_lowercase().as_str() {
+            "" | "none" => Ok(Self::None),
+            "heuristic" => Ok(Self::Heuristic),
+            "openai" => Ok(Self::OpenAi),
+            "perspective" => Ok(Self::Perspective),
+            _ => Err(format!("Unknown moderation provider: {}", value)),
+        }
+    }
+}
+
+// How the word filter treats content with a filtered word.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum WordFilterPolicy {
+    Off,
+    // Fails the request naming the offending fields.
+    Reject,
+    // Stores the content with the word's letters replaced by `*`.
+    Mask,
+}
+
+impl FromStr for WordFilterPolicy {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.to_ascii_lowercase().as_str() {
+            "" | "off" => Ok(Self::Off),
+            "reject" => Ok(Self::Reject),
+            "mask" => Ok(Self::Mask),
+            _ => Err(format!("Unknown word filter policy: {}", value)),
+        }
+    }
+}
+
+// How answer creates treat existing answers to the question that are close to the new one.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DuplicateAnswerPolicy {
+    Off,
+    // Creates the answer and lists the close ones in the response.
+    Warn,
+    // Fails the create with 409 unless the caller insists.
+    Reject,
+}
+
+impl FromStr for DuplicateAnswerPolicy {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.to_ascii_lowercase().as_str() {
+            "off" => Ok(Self::Off),
+            "" | "warn" => Ok(Self::Warn),
+            "reject" => Ok(Self::Reject),
+            _ => Err(format!("Unknown duplicate answer policy: {}", value)),
+        }
+    }
+}
+
+// Whose captcha widget clients solve; both need the `captcha` cargo feature.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CaptchaProviderKind {
+    None,
+    HCaptcha,
+    // Cloudflare Turnstile.
+    Turnstile,
+}
+
+impl FromStr for CaptchaProviderKind {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.to_ascii_lowercase().as_str() {
+            "" | "none" => Ok(Self::None),
+            "hcaptcha" => Ok(Self::HCaptcha),
+            "turnstile" => Ok(Self::Turnstile),
+            _ => Err(format!("Unknown captcha provider: {}", value)),
+        }
+    }
+}
+
+// Who checks new content for spam.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SpamCheckerKind {
+    None,
+    // Akismet's REST API, or a service speaking it; needs the `akismet` cargo feature.
+    Akismet,
+}
+
+impl FromStr for SpamCheckerKind {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.to_ascii_lowercase().as_str() {
+            "" | "none" => Ok(Self::None),
+            "akismet" => Ok(Self::Akismet),
+            _ => Err(format!("Unknown spam checker: {}", value)),
+        }
+    }
+}
+
+// Runtime view of the configuration. Settings read per request (timeouts, CORS
+// origins, feature flags) follow reloads; pools, limits and DAO policies are
+// built once at startup and need a restart.
+#[derive(Clone)]
+pub struct LiveConfig {
+    current: Arc<ArcSwap<AppConfig>>,
+    source: Option<PathBuf>,
+}
+
+impl LiveConfig {
+    pub fn new(config: AppConfig, source: Option<PathBuf>) -> Self {
+        Self {
+            current: Arc::new(ArcSwap::from_pointee(config)),
+            source,
+        }
+    }
+
+    pub fn load(&self) -> Arc<AppConfig> {
+        self.current.load_full()
+    }
+
+    pub fn reload(&self) -> Result<(), String> {
+        if let Some(source) = &self.source {
+            dotenvy::from_path_override(source)
+                .map_err(|err| format!("Failed to read {}: {}", source.display(), err))?;
+        }
+
+        // The pools and breaker keep their startup timeouts, which the new deadline must outlast.
+        let current = self.load();
+        let config = AppConfig {
+            db_acquire_timeout: current.db_acquire_timeout,
+            breaker_call_timeout: current.breaker_call_timeout,
+            ..AppConfig::from_env()
+        };
+        let longest = config.db_acquire_timeout.max(config.breaker_call_timeout);
+        if config.request_timeout <= longest {
+            return Err(format!(
+                "REQUEST_TIMEOUT_SECS of {:?} must stay above the {:?} database timeouts",
+                config.request_timeout, longest
+            ));
+        }
+
+        self.current.store(Arc::new(config));
+        info!("Configuration reloaded.");
+        Ok(())
+    }
+
+    // Polls the source file and reloads whenever its modification time changes.
+    pub fn spawn_watcher(&self, interval: Duration) -> Option<JoinHandle<()>> {
+        if interval.is_zero() {
+            return None;
+        }
+        let source = self.source.clone()?;
+        let live = self.clone();
+
+        Some(tokio::spawn(async move {
+            let mut last_modified = modified_at(&source);
+            loop {
+                tokio::time::sleep(interval).await;
+
+                let modified = modified_at(&source);
+                if modified != last_modified {
+                    last_modified = modified;
+                    if let Err(err) = live.reload() {
+                        warn!("{}", err);
+                    }
+                }
+            }
+        }))
+    }
+}
+
+fn modified_at(path: &Path) -> Option<SystemTime> {
+    std::fs::metadata(path)
+        .and_then(|meta| meta.modified())
+        .ok()
+}
+
+// Limits are looked up by Rocket as "json/<name>", falling back to "json".
+pub struct BodyLimits {
+    pub question: ByteUnit,
+    pub answer: ByteUnit,
+    pub import: ByteUnit,
+    // Multipart attachment uploads, and the largest file a pre-signed upload may hold.
+    pub attachment: ByteUnit,
+    pub avatar: ByteUnit,
+}
+
+impl BodyLimits {
+    pub fn from_env() -> Self {
+        Self {
+            question: from_env_or("QUESTION_BODY_LIMIT", ByteUnit::Kibibyte(16)),
+            answer: from_env_or("ANSWER_BODY_LIMIT", ByteUnit::Kibibyte(4)),
+            import: from_env_or("IMPORT_BODY_LIMIT", ByteUnit::Mebibyte(10)),
+            attachment: from_env_or("ATTACHMENT_BODY_LIMIT", ByteUnit::Mebibyte(10)),
+            avatar: from_env_or("AVATAR_BODY_LIMIT", ByteUnit::Mebibyte(1)),
+        }
+    }
+
+    pub fn to_limits(&self) -> Limits {
+        Limits::default()
+            .limit("json/question", self.question)
+            .limit("json/answer", self.answer)
+            .limit("json/import", self.import)
+            .limit("data-form", self.attachment)
+            .limit("file", self.attachment)
+            .limit("avatar", self.avatar)
+    }
+}
+
+fn from_env_or<T: std::str::FromStr>(key: &str, default: T) -> T {
+    parse_or_default(key, env::var(key).ok(), default)
+}
+
+fn secs_from_env(key: &str, default: u64) -> Duration {
+    Duration::from_secs(from_env_or(key, default))
+}
+
+fn millis_from_env(key: &str, default: u64) -> Duration {
+    Duration::from_millis(from_env_or(key, default))
+}
+
+fn list_from_env(key: &str, default: &str) -> Vec<String> {
+    env::var(key)
+        .unwrap_or_else(|_| default.to_owned())
+        .split(',')
+        .map(|item| item.trim().to_owned())
+        .filter(|item| !item.is_empty())
+        .collect()
+}
+
+// `key=value` pairs separated by commas; malformed ones are skipped with a warning.
+fn pairs_from_env(key: &str) -> Vec<(String, String)> {
+    list_from_env(key, "")
+        .into_iter()
+        .filter_map(|pair| match pair.split_once('=') {
+            Some((from, to)) if !from.trim().is_empty() && !to.trim().is_empty() => {
+                Some((from.trim().to_owned(), to.trim().to_owned()))
+            }
+            _ => {
+                warn!("Invalid entry in {}: {:?}, skipping it.", key, pair);
+                None
+            }
+        })
+        .collect()
+}
+
+fn parse_or_default<T: std::str::FromStr>(key: &str, value: Option<String>, default: T) -> T {
+    let Some(value) = value else {
+        return default;
+    };
+
+    value.trim().parse().unwrap_or_else(|_| {
+        warn!("Invalid value for {}: {:?}, using default.", key, value);
+        default
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_or_default_should_parse_byte_units() {
+        let result = parse_or_default("KEY", Some("8 KiB".to_owned()), ByteUnit::Kibibyte(4));
+        assert_eq!(result, ByteUnit::Kibibyte(8));
+    }
+
+    #[test]
+    fn parse_or_default_should_fallback_on_missing_value() {
+        let result = parse_or_default("KEY", None, ByteUnit::Kibibyte(4));
+        assert_eq!(result, ByteUnit::Kibibyte(4));
+    }
+
+    #[test]
+    fn parse_or_default_should_fallback_on_invalid_value() {
+        let result = parse_or_default("KEY", Some("lots".to_owned()), ByteUnit::Kibibyte(4));
+        assert_eq!(result, ByteUnit::Kibibyte(4));
+    }
+
+    #[test]
+    fn parse_or_default_should_parse_storage() {
+        let result = parse_or_default("STORAGE", Some("Memory".to_owned()), Storage::Database);
+        assert_eq!(result, Storage::Memory);
+    }
+
+    #[test]
+    fn live_config_should_swap_on_reload() {
+        let live = LiveConfig::new(AppConfig::from_env(), None);
+        let before = live.load();
+
+        live.reload().unwrap();
+
+        assert!(!Arc::ptr_eq(&before, &live.load()));
+    }
+
+    #[test]
+    fn live_config_should_keep_request_timeout_above_database_timeouts() {
+        let live = LiveConfig::new(
+            AppConfig {
+                db_acquire_timeout: Duration::from_secs(600),
+                ..AppConfig::from_env()
+            },
+            None,
+        );
+        let before = live.load();
+
+        assert!(live.reload().is_err());
+        assert!(Arc::ptr_eq(&before, &live.load()));
+    }
+
+    #[test]
+    fn live_config_should_report_unreadable_source() {
+        let live = LiveConfig::new(
+            AppConfig::from_env(),
+            Some(PathBuf::from("/nonexistent/.env")),
+        );
+
+        assert!(live.reload().is_err());
+    }
+
+    #[test]
+    fn body_limits_should_register_named_json_limits() {
+        let limits = BodyLimits {
+            question: ByteUnit::Kibibyte(16),
+            answer: ByteUnit::Kibibyte(4),
+            import: ByteUnit::Mebibyte(10),
+            attachment: ByteUnit::Mebibyte(5),
+            avatar: ByteUnit::Mebibyte(1),
+        }
+        .to_limits();
+
+        assert_eq!(limits.find(["json", "answer"]), Some(ByteUnit::Kibibyte(4)));
+        assert_eq!(
+            limits.find(["json", "question"]),
+            Some(ByteUnit::Kibibyte(16))
+        );
+        assert_eq!(
+            limits.find(["json", "import"]),
+            Some(ByteUnit::Mebibyte(10))
+        );
+        assert_eq!(limits.get("file"), Some(ByteUnit::Mebibyte(5)));
+        assert_eq!(limits.get("avatar"), Some(ByteUnit::Mebibyte(1)));
+        assert_eq!(limits.find(["json", "unknown"]), limits.get("json"));
+    }
+}
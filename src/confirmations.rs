@@ -0,0 +1,342 @@
+use chrono::{DateTime, Duration, Utc};
+use log::warn;
+use rand::Rng;
+
+use crate::{
+    avatars::percent_encode,
+    config::AppConfig,
+    handlers::admin::tokens_match,
+    models::{DigestFrequency, QuestionUuid},
+    replies::hmac_sha256,
+    startup::StartupError,
+};
+
+// How long the link in a confirmation email can be used.
+pub const TOKEN_DAYS: i64 = 7;
+
+// Where links point without PUBLIC_URL, which is only allowed while emails go to the log.
+pub const LOCAL_URL: &str = "http://localhost:8000";
+
+// A change to the emails an address gets. Anyone can ask for one, but it is only made
+// with a token mailed to the address, so no one is subscribed or unsubscribed by others.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SubscriptionChange {
+    Subscribe(QuestionUuid),
+    Unsubscribe(QuestionUuid),
+    OptOut,
+    OptIn,
+    // None stops the digests.
+    Digest(Option<DigestFrequency>),
+}
+
+impl SubscriptionChange {
+    // The change as the confirmation page's query names it.
+    pub fn query(self) -> String {
+        match self {
+            SubscriptionChange::Subscribe(question_uuid) => {
+                format!("change=subscribe&question={}", question_uuid.0)
+            }
+            SubscriptionChange::Unsubscribe(question_uuid) => {
+                format!("change=unsubscribe&question={}", question_uuid.0)
+            }
+            SubscriptionChange::OptOut => "change=opt-out".to_owned(),
+            SubscriptionChange::OptIn => "change=opt-in".to_owned(),
+            SubscriptionChange::Digest(Some(frequency)) => {
+                format!("change=digest&frequency={}", frequency.as_str())
+            }
+            SubscriptionChange::Digest(None) => "change=stop-digest".to_owned(),
+        }
+    }
+
+    // The reverse of `query`; None when a part is unknown or missing.
+    pub fn from_query(
+        change: &str,
+        question_uuid: Option<QuestionUuid>,
+        frequency: Option<DigestFrequency>,
+    ) -> Option<Self> {
+        match change {
+            "subscribe" => question_uuid.map(SubscriptionChange::Subscribe),
+            "unsubscribe" => question_uuid.map(SubscriptionChange::Unsubscribe),
+            "opt-out" => Some(SubscriptionChange::OptOut),
+            "opt-in" => Some(SubscriptionChange::OptIn),
+            "digest" => frequency.map(|frequency| SubscriptionChange::Digest(Some(frequency))),
+            "stop-digest" => Some(SubscriptionChange::Digest(None)),
+            _ => None,
+        }
+    }
+
+    // The request making the change; what tokens sign.
+    pub fn request(self, email: &str) -> String {
+        match self {
+            SubscriptionChange::Subscribe(question_uuid) => {
+                format!("PUT /question/{}/subscriptions/{}", question_uuid.0, email)
+            }
+            SubscriptionChange::Unsubscribe(question_uuid) => {
+                format!(
+                    "DELETE /question/{}/subscriptions/{}",
+                    question_uuid.0, email
+                )
+            }
+            SubscriptionChange::OptOut => format!("PUT /notifications/opt-outs/{}", email),
+            SubscriptionChange::OptIn => format!("DELETE /notifications/opt-outs/{}", email),
+            SubscriptionChange::Digest(Some(frequency)) => format!(
+                "PUT /notifications/digests/{}?frequency={}",
+                email,
+                frequency.as_str()
+            ),
+            SubscriptionChange::Digest(None) => format!("DELETE /notifications/digests/{}", email),
+        }
+    }
+
+    // What the confirmation email asks to be confirmed.
+    pub fn summary(self) -> String {
+        match self {
+            SubscriptionChange::Subscribe(question_uuid) => format!(
+                "get an email about each new answer to question {}",
+                question_uuid.0
+            ),
+            SubscriptionChange::Unsubscribe(question_uuid) => format!(
+                "stop the emails about new answers to question {}",
+                question_uuid.0
+            ),
+            SubscriptionChange::OptOut => "stop every email from this server".to_owned(),
+            SubscriptionChange::OptIn => "get emails from this server again".to_owned(),
+            SubscriptionChange::Digest(Some(frequency)) => {
+                format!("get a {} digest of new answers", frequency.as_str())
+            }
+            SubscriptionChange::Digest(None) => "stop the digests of new answers".to_owned(),
+        }
+    }
+}
+
+// What came of asking for a change.
+#[derive(Debug, PartialEq)]
+pub enum Confirmation {
+    Made,
+    // The token was mailed to the address.
+    Requested,
+}
+
+// Tokens confirming one change to one address, `<expiry>.<signature>` with the expiry in
+// seconds since the epoch. They are not kept anywhere, so they can be used more than once
+// until they expire; making the same change twice changes nothing.
+#[derive(Clone)]
+pub struct ConfirmationTokens {
+    secret: Vec<u8>,
+    public_url: String,
+}
+
+// Without SUBSCRIPTION_SECRET, tokens are signed with a key of this process only. Mailed
+// links need PUBLIC_URL to be absolute.
+pub fn tokens_from_config(config: &AppConfig) -> Result<ConfirmationTokens, StartupError> {
+    let public_url = match (&config.public_url, &config.smtp_url) {
+        (Some(public_url), _) => public_url.as_str(),
+        (None, Some(_)) => {
+            return Err(StartupError::MissingSetting {
+                setting: "SMTP_URL",
+                needed: "PUBLIC_URL",
+            })
+        }
+        (None, None) => LOCAL_URL,
+    };
+    Ok(match &config.subscription_secret {
+        Some(secret) => ConfirmationTokens::new(secret.as_bytes(), public_url),
+        None => {
+            warn!("SUBSCRIPTION_SECRET is unset; confirmation links only work on this instance until it restarts.");
+            ConfirmationTokens::new(&rand::thread_rng().gen::<[u8; 32]>(), public_url)
+        }
+    })
+}
+
+impl ConfirmationTokens {
+    pub fn new(secret: &[u8], public_url: &str) -> Self {
+        Self {
+            secret: secret.to_vec(),
+            public_url: public_url.trim_end_matches('/').to_owned(),
+        }
+    }
+
+    // The base of every link in emails, without a trailing slash.
+    pub fn public_url(&self) -> &str {
+        &self.public_url
+    }
+
+    pub fn token(&self, change: SubscriptionChange, email: &str, now: DateTime<Utc>) -> String {
+        let expiry = (now + Duration::days(TOKEN_DAYS)).timestamp();
+        format!("{}.{}", expiry, self.signature(change, email, expiry))
+    }
+
+    pub fn verify(
+        &self,
+        change: SubscriptionChange,
+        email: &str,
+        token: &str,
+        now: DateTime<Utc>,
+    ) -> bool {
+        let Some((expiry, signature)) = token.split_once('.') else {
+            return false;
+        };
+        let Ok(expiry) = expiry.parse::<i64>() else {
+            return false;
+        };
+        expiry >= now.timestamp()
+            && tokens_match(
+                &signature.to_lowercase(),
+                &self.signature(change, email, expiry),
+            )
+    }
+
+    // The page confirming the change, with a token, for emails to send as they are.
+    pub fn link(&self, change: SubscriptionChange, email: &str, now: DateTime<Utc>) -> String {
+        format!(
+            "{}/notifications/confirm?email={}&{}&token={}",
+            self.public_url,
+            percent_encode(email),
+            change.query(),
+            self.token(change, email, now)
+        )
+    }
+
+    fn signature(&self, change: SubscriptionChange, email: &str, expiry: i64) -> String {
+        let message = format!("{}\n{}", change.request(email), expiry);
+        hmac_sha256(&self.secret, message.as_bytes())
+            .iter()
+            .map(|byte| format!("{:02x}", byte))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tokens_should_only_confirm_their_change_until_they_expire() {
+        let tokens = ConfirmationTokens::new(b"secret", "https://qa.example.com");
+        let question_uuid = QuestionUuid::new_v4();
+        let change = SubscriptionChange::Subscribe(question_uuid);
+        let now = Utc::now();
+        let token = tokens.token(change, "reader@example.com", now);
+
+        assert!(tokens.verify(change, "reader@example.com", &token, now));
+        assert!(tokens.verify(
+            change,
+            "reader@example.com",
+            &token,
+            now + Duration::days(TOKEN_DAYS)
+        ));
+        assert!(!tokens.verify(
+            change,
+            "reader@example.com",
+            &token,
+            now + Duration::days(TOKEN_DAYS + 1)
+        ));
+        assert!(!tokens.verify(change, "other@example.com", &token, now));
+        assert!(!tokens.verify(
+            SubscriptionChange::Unsubscribe(question_uuid),
+            "reader@example.com",
+            &token,
+            now
+        ));
+        let (expiry, signature) = token.split_once('.').unwrap();
+        let extended = format!("{}.{}", expiry.parse::<i64>().unwrap() + 86400, signature);
+        assert!(!tokens.verify(change, "reader@example.com", &extended, now));
+        let other = ConfirmationTokens::new(b"other", "https://qa.example.com");
+        assert!(!other.verify(change, "reader@example.com", &token, now));
+    }
+
+    #[test]
+    fn digest_tokens_should_only_confirm_their_frequency() {
+        let tokens = ConfirmationTokens::new(b"secret", "https://qa.example.com");
+        let daily = SubscriptionChange::Digest(Some(DigestFrequency::Daily));
+        let weekly = SubscriptionChange::Digest(Some(DigestFrequency::Weekly));
+        let now = Utc::now();
+        let link = tokens.link(daily, "reader@example.com", now);
+
+        let (page, token) = link.split_once("&token=").unwrap();
+        assert_eq!(
+            page,
+            "https://qa.example.com/notifications/confirm?email=reader%40example.com&change=digest&frequency=daily"
+        );
+        assert!(tokens.verify(daily, "reader@example.com", token, now));
+        assert!(!tokens.verify(weekly, "reader@example.com", token, now));
+        assert!(!tokens.verify(
+            SubscriptionChange::Digest(None),
+            "reader@example.com",
+            token,
+            now
+        ));
+    }
+
+    #[test]
+    fn link_should_be_an_absolute_url_with_the_token() {
+        let tokens = ConfirmationTokens::new(b"secret", "https://qa.example.com/");
+        let now = Utc::now();
+        let link = tokens.link(SubscriptionChange::OptOut, "reader+qa@example.com", now);
+
+        let (page, token) = link.split_once("&token=").unwrap();
+        assert_eq!(
+            page,
+            "https://qa.example.com/notifications/confirm?email=reader%2Bqa%40example.com&change=opt-out"
+        );
+        assert!(tokens.verify(
+            SubscriptionChange::OptOut,
+            "reader+qa@example.com",
+            token,
+            now
+        ));
+    }
+
+    #[test]
+    fn mailed_links_should_need_a_public_url() {
+        let mut config = AppConfig::from_env();
+        config.public_url = None;
+        config.smtp_url = Some("smtp://mail.example.com".to_owned());
+        assert!(matches!(
+            tokens_from_config(&config),
+            Err(StartupError::MissingSetting {
+                needed: "PUBLIC_URL",
+                ..
+            })
+        ));
+
+        config.smtp_url = None;
+        assert_eq!(tokens_from_config(&config).unwrap().public_url(), LOCAL_URL);
+    }
+
+    #[test]
+    fn changes_should_round_trip_through_the_query() {
+        let question_uuid = QuestionUuid::new_v4();
+        for change in [
+            SubscriptionChange::Subscribe(question_uuid),
+            SubscriptionChange::Unsubscribe(question_uuid),
+            SubscriptionChange::OptOut,
+            SubscriptionChange::OptIn,
+            SubscriptionChange::Digest(Some(DigestFrequency::Weekly)),
+            SubscriptionChange::Digest(None),
+        ] {
+            let query = change.query();
+            let part = |name: &str| {
+                query
+                    .split('&')
+                    .find_map(|pair| pair.strip_prefix(name)?.strip_prefix('='))
+            };
+            assert_eq!(
+                SubscriptionChange::from_query(
+                    part("change").unwrap(),
+                    part("question").map(|uuid| QuestionUuid(uuid.parse().unwrap())),
+                    part("frequency").map(|frequency| frequency.parse().unwrap()),
+                ),
+                Some(change)
+            );
+        }
+        assert_eq!(
+            SubscriptionChange::from_query("subscribe", None, None),
+            None
+        );
+        assert_eq!(
+            SubscriptionChange::from_query("resubscribe", None, None),
+            None
+        );
+    }
+}
@@ -2,6 +2,19 @@ use rocket::fairing::{Fairing, Info, Kind};
 use rocket::http::{ContentType, Header, Method, Status};
 use rocket::{Request, Response};
 
+use crate::{request_id::REQUEST_ID_HEADER, AppState};
+
+// Response headers scripts on other origins may read, beyond the CORS-safelisted ones.
+const EXPOSED_HEADERS: &[&str] = &[
+    "ETag",
+    "X-RateLimit-Limit",
+    "X-RateLimit-Remaining",
+    "X-RateLimit-Reset",
+    "Retry-After",
+    REQUEST_ID_HEADER,
+];
+
+#[allow(clippy::upper_case_acronyms)]
 pub struct CORS;
 
 #[rocket::async_trait]
@@ -15,13 +28,32 @@ impl Fairing for CORS {
 
     // source: https://stackoverflow.com/questions/62412361/how-to-set-up-cors-or-options-for-rocket-rs
     async fn on_response<'r>(&self, request: &'r Request<'_>, response: &mut Response<'r>) {
-        response.set_header(Header::new("Access-Control-Allow-Origin", "*"));
+        let allowed_origins = request
+            .rocket()
+            .state::<AppState>()
+            .map(|state| state.live_config.load().cors_allowed_origins.clone())
+            .unwrap_or_else(|| vec!["*".to_owned()]);
+
+        if let Some(origin) = allowed_origin(&allowed_origins, request.headers().get_one("Origin"))
+        {
+            response.set_header(Header::new(
+                "Access-Control-Allow-Origin",
+                origin.to_owned(),
+            ));
+            if origin != "*" {
+                response.set_header(Header::new("Vary", "Origin"));
+            }
+        }
         response.set_header(Header::new(
             "Access-Control-Allow-Methods",
-            "POST, GET, PATCH, DELETE, OPTIONS",
+            "POST, GET, PUT, PATCH, DELETE, OPTIONS",
         ));
-        response.set_header(Header::new("Acess-Control-Allow-Headers", "*"));
-        response.set_header(Header::new("Acess-Control-Credentials", "true"));
+        response.set_header(Header::new("Access-Control-Allow-Headers", "*"));
+        response.set_header(Header::new(
+            "Access-Control-Expose-Headers",
+            EXPOSED_HEADERS.join(", "),
+        ));
+        response.set_header(Header::new("Access-Control-Allow-Credentials", "true"));
 
         // source: https://webprogramming.ninja/2022/08/25/handling-options-requests-in-rust-using-rocket-with-cors/
         if request.method() == Method::Options {
@@ -32,3 +64,34 @@ impl Fairing for CORS {
         }
     }
 }
+
+fn allowed_origin<'a>(allowed: &'a [String], origin: Option<&'a str>) -> Option<&'a str> {
+    if allowed.iter().any(|allowed| allowed == "*") {
+        return Some("*");
+    }
+
+    origin.filter(|origin| allowed.iter().any(|allowed| allowed == origin))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn allowed_origin_should_allow_any_origin_with_wildcard() {
+        let allowed = vec!["*".to_owned()];
+        assert_eq!(allowed_origin(&allowed, Some("https://a.com")), Some("*"));
+        assert_eq!(allowed_origin(&allowed, None), Some("*"));
+    }
+
+    #[test]
+    fn allowed_origin_should_echo_listed_origins_only() {
+        let allowed = vec!["https://a.com".to_owned()];
+        assert_eq!(
+            allowed_origin(&allowed, Some("https://a.com")),
+            Some("https://a.com")
+        );
+        assert_eq!(allowed_origin(&allowed, Some("https://b.com")), None);
+        assert_eq!(allowed_origin(&allowed, None), None);
+    }
+}
@@ -0,0 +1,749 @@
+// The JSON the API reads and writes. These mirror the models for now, but are kept
+// apart from what the DAOs take and return so the wire format can change without
+// touching the persistence layer; the `From` impls below are the only place the two
+// meet.
+
+use chrono::{DateTime, FixedOffset, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+use crate::{
+    handlers::ErrorCode,
+    models::{
+        empty_metadata, Answer, AnswerDetail, AnswerUpdate, AnswerUuid, Attachment, AttachmentUuid,
+        DailyStats, HeldContent, HookUuid, ModerationAction, ModerationItem, ModerationUuid,
+        ProvisionedUser, PushSubscription, Question, QuestionDetail, QuestionTranslation,
+        QuestionUpdate, QuestionUuid, QuestionWithAnswer, QuestionWithAnswerDetail, RestHook,
+        UserAttributes, UserUuid,
+    },
+    moderation::Published,
+    persistence::{
+        duplicate::SimilarAnswer,
+        link_preview::LinkPreview,
+        search::{Highlight, TitleMatch},
+    },
+};
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct CreateQuestionRequest {
+    pub title: String,
+    #[serde(default)]
+    pub description: Option<String>,
+    // Missing or null is stored as `{}`.
+    #[serde(default)]
+    pub metadata: Option<Value>,
+    // Subscribed to the question, so it is mailed about each answer.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub author_email: Option<String>,
+}
+
+// The version is optional here because it may come in an If-Match header instead.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct UpdateQuestionRequest {
+    pub title: String,
+    #[serde(default)]
+    pub description: Option<String>,
+    #[serde(default)]
+    pub metadata: Option<Value>,
+    #[serde(default)]
+    pub version: Option<i64>,
+}
+
+// A question posted together with its first answer.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct CreateQuestionWithAnswerRequest {
+    pub question: CreateQuestionRequest,
+    pub answer: String,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct CreateAnswerRequest {
+    // Also accepted as `questionUuid`, for clients reading camelCase responses.
+    #[serde(alias = "questionUuid")]
+    pub question_uuid: QuestionUuid,
+    pub content: String,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct UpdateAnswerRequest {
+    pub content: String,
+    #[serde(default)]
+    pub version: Option<i64>,
+}
+
+// A browser's `PushSubscription.toJSON()`, with the questions it wants pushes about.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct CreatePushSubscriptionRequest {
+    pub endpoint: String,
+    // Sent by browsers, but subscriptions are kept until the push service drops them.
+    #[serde(
+        default,
+        rename = "expirationTime",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub expiration_time: Option<f64>,
+    pub keys: PushSubscriptionKeys,
+    pub question_uuids: Vec<QuestionUuid>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct PushSubscriptionKeys {
+    pub p256dh: String,
+    pub auth: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "camel-case", serde(rename_all = "camelCase"))]
+pub struct QuestionResponse {
+    pub question_uuid: QuestionUuid,
+    pub title: String,
+    pub description: Option<String>,
+    pub metadata: Value,
+    pub created_at: DateTime<FixedOffset>,
+    pub updated_at: DateTime<FixedOffset>,
+    pub last_activity_at: DateTime<FixedOffset>,
+    pub answer_count: i64,
+    pub version: i64,
+    // Only with `?include=answers`, oldest first.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub answers: Option<Vec<AnswerResponse>>,
+    // Only with `?include=attachments`, oldest first.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub attachments: Option<Vec<AttachmentResponse>>,
+    // Only with `?lang=`: the language the title and description were translated into.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub language: Option<String>,
+    // Only from `GET /question/<uuid>` while link previews are enabled: the previews
+    // fetched so far of the links in the title and description.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub link_previews: Option<Vec<LinkPreviewResponse>>,
+    // Only from `GET /questions/search`: why the question matched.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub highlight: Option<HighlightResponse>,
+}
+
+// The title and description HTML escaped, with the matched words in `<mark>`. The
+// description is cut to up to two passages around them, joined by ` … `.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "camel-case", serde(rename_all = "camelCase"))]
+pub struct HighlightResponse {
+    pub title: String,
+    pub description: Option<String>,
+}
+
+// OpenGraph metadata of a link, for clients to render a link card.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "camel-case", serde(rename_all = "camelCase"))]
+pub struct LinkPreviewResponse {
+    pub url: String,
+    pub title: Option<String>,
+    pub description: Option<String>,
+    pub image_url: Option<String>,
+    pub site_name: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "camel-case", serde(rename_all = "camelCase"))]
+pub struct AnswerResponse {
+    pub answer_uuid: AnswerUuid,
+    pub question_uuid: QuestionUuid,
+    pub content: String,
+    pub created_at: DateTime<FixedOffset>,
+    pub updated_at: DateTime<FixedOffset>,
+    pub version: i64,
+    // Only with `?include=attachments`, or `answers.attachments` on questions.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub attachments: Option<Vec<AttachmentResponse>>,
+    // Only on creates, when the question already had answers close to this one.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub similar_answers: Option<Vec<SimilarAnswerResponse>>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "camel-case", serde(rename_all = "camelCase"))]
+pub struct SimilarAnswerResponse {
+    pub answer_uuid: AnswerUuid,
+    pub content: String,
+    // Trigram similarity from 0 to 1.
+    pub similarity: f32,
+}
+
+// Downloaded from `GET /attachments/<attachment_uuid>`.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "camel-case", serde(rename_all = "camelCase"))]
+pub struct AttachmentResponse {
+    pub attachment_uuid: AttachmentUuid,
+    pub question_uuid: QuestionUuid,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub answer_uuid: Option<AnswerUuid>,
+    pub filename: String,
+    pub content_type: String,
+    pub size_bytes: i64,
+    // `pending` until a pre-signed upload is completed, then `ready`, or `scanning` while
+    // a content scanner checks it first.
+    pub status: String,
+    // Thumbnails of images, once they have been made.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub variants: Vec<AttachmentVariantResponse>,
+    pub created_at: DateTime<FixedOffset>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "camel-case", serde(rename_all = "camelCase"))]
+pub struct AttachmentVariantResponse {
+    pub name: String,
+    pub width: u32,
+    pub height: u32,
+    pub content_type: String,
+    pub size_bytes: i64,
+    // `/attachments/<attachment_uuid>/variants/<name>`, relative to the API.
+    pub url: String,
+}
+
+// Announces a file to upload straight to the object store.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct CreateAttachmentRequest {
+    pub filename: String,
+    pub content_type: String,
+    pub size_bytes: i64,
+    // Attaches the file to this answer of the question instead of the question.
+    #[serde(default)]
+    pub answer_uuid: Option<AnswerUuid>,
+}
+
+// The pending attachment and where to PUT its bytes before completing it with
+// `POST /attachments/<attachment_uuid>/complete`.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "camel-case", serde(rename_all = "camelCase"))]
+pub struct AttachmentUploadResponse {
+    pub attachment: AttachmentResponse,
+    pub upload_url: String,
+}
+
+// A generated draft for a person to review and post with `POST /answer` themselves; it
+// is not stored.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "camel-case", serde(rename_all = "camelCase"))]
+pub struct SuggestedAnswerResponse {
+    pub question_uuid: QuestionUuid,
+    pub content: String,
+    // Always true, so clients cannot mistake the draft for a posted answer.
+    pub draft: bool,
+    pub ai_generated: bool,
+    pub model: String,
+    pub notice: String,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct SuggestTagsRequest {
+    pub title: String,
+    #[serde(default)]
+    pub description: Option<String>,
+}
+
+// A page of full text search results. While the search found few questions,
+// `suggestions` holds up to three titles close to the query, in case it was misspelled.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "camel-case", serde(rename_all = "camelCase"))]
+pub struct SearchResponse {
+    #[serde(flatten)]
+    pub page: Paginated<QuestionResponse>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub suggestions: Option<Vec<TitleMatchResponse>>,
+}
+
+// Only what a suggestion list shows, to keep as-you-type responses small.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "camel-case", serde(rename_all = "camelCase"))]
+pub struct TitleMatchResponse {
+    pub question_uuid: QuestionUuid,
+    pub title: String,
+}
+
+// Best first; may be empty when nothing similar is tagged yet.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "camel-case", serde(rename_all = "camelCase"))]
+pub struct SuggestedTagsResponse {
+    pub tags: Vec<String>,
+}
+
+// Sent with 202 instead of the created resource when moderation holds a create.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "camel-case", serde(rename_all = "camelCase"))]
+pub struct HeldContentResponse {
+    pub moderation_uuid: ModerationUuid,
+    pub message: String,
+}
+
+// The held create as it was sent, tagged with its `kind`.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "camel-case", serde(rename_all = "camelCase"))]
+pub struct ModerationItemResponse {
+    pub moderation_uuid: ModerationUuid,
+    pub content: HeldContent,
+    pub score: f32,
+    pub categories: Vec<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct BulkModerationRequest {
+    pub moderation_uuids: Vec<ModerationUuid>,
+    pub action: ModerationAction,
+}
+
+// One item of a bulk moderation, with the status and body its own route would have
+// answered: `published` for approvals, `code` and `message` for failures.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "camel-case", serde(rename_all = "camelCase"))]
+pub struct BulkModerationResult {
+    pub moderation_uuid: ModerationUuid,
+    pub status: u16,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub published: Option<PublishedResponse>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub code: Option<ErrorCode>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+}
+
+// A translation of a question's title and description, into the language in the path.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct TranslationRequest {
+    pub title: String,
+    #[serde(default)]
+    pub description: Option<String>,
+}
+
+// `question_version` is the version of the question that was translated; a lower one
+// than the question's own means it was edited since.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "camel-case", serde(rename_all = "camelCase"))]
+pub struct TranslationResponse {
+    pub question_uuid: QuestionUuid,
+    pub language: String,
+    pub title: String,
+    pub description: Option<String>,
+    pub question_version: i64,
+    pub updated_at: DateTime<Utc>,
+}
+
+// A REST hook subscription, e.g. from Zapier or Make. `event` is an outbox event type,
+// like `question_created`.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct CreateRestHookRequest {
+    pub target_url: String,
+    pub event: String,
+}
+
+// Zapier and Make keep `id` to unsubscribe with.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "camel-case", serde(rename_all = "camelCase"))]
+pub struct RestHookResponse {
+    pub id: HookUuid,
+    pub target_url: String,
+    pub event: String,
+    pub created_at: DateTime<Utc>,
+}
+
+// The admin dashboard over the days from `since` on. The rate and median cover the
+// questions asked in that time and are unset when there are none.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "camel-case", serde(rename_all = "camelCase"))]
+pub struct AdminStatsResponse {
+    pub since: String,
+    pub daily: Vec<DailyStats>,
+    pub active_users: Option<i64>,
+    pub answer_rate: Option<f64>,
+    pub median_first_answer_secs: Option<f64>,
+}
+
+pub const SCIM_USER_SCHEMA: &str = "urn:ietf:params:scim:schemas:core:2.0:User";
+pub const SCIM_LIST_SCHEMA: &str = "urn:ietf:params:scim:api:messages:2.0:ListResponse";
+
+// A SCIM User as identity providers send it, named in camelCase as SCIM has it. Only
+// the attributes below are kept; the rest, e.g. `name` or enterprise extensions, are
+// accepted and dropped.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ScimUserRequest {
+    pub user_name: String,
+    #[serde(default)]
+    pub external_id: Option<String>,
+    #[serde(default)]
+    pub display_name: Option<String>,
+    #[serde(default = "active_by_default")]
+    pub active: bool,
+    #[serde(flatten)]
+    pub other: Map<String, Value>,
+}
+
+fn active_by_default() -> bool {
+    true
+}
+
+// A SCIM PatchOp. Okta and Entra ID deactivate users with these.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ScimPatchRequest {
+    #[serde(rename = "Operations")]
+    pub operations: Vec<ScimPatchOperation>,
+    #[serde(flatten)]
+    pub other: Map<String, Value>,
+}
+
+// `value` is the new value of `path`, or without a path an object of attributes.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ScimPatchOperation {
+    pub op: String,
+    #[serde(default)]
+    pub path: Option<String>,
+    #[serde(default)]
+    pub value: Value,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ScimUserResponse {
+    pub schemas: Vec<String>,
+    pub id: UserUuid,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub external_id: Option<String>,
+    pub user_name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub display_name: Option<String>,
+    // The user name, which is the only address kept.
+    pub emails: Vec<ScimEmail>,
+    pub active: bool,
+    pub meta: ScimMeta,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ScimEmail {
+    pub value: String,
+    pub primary: bool,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ScimMeta {
+    pub resource_type: String,
+    pub created: DateTime<Utc>,
+    pub last_modified: DateTime<Utc>,
+    pub location: String,
+}
+
+// `start_index` counts from 1, as in SCIM.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ScimListResponse {
+    pub schemas: Vec<String>,
+    pub total_results: i64,
+    pub start_index: i64,
+    pub items_per_page: i64,
+    #[serde(rename = "Resources")]
+    pub resources: Vec<ScimUserResponse>,
+}
+
+// What approving a held item created; shaped like the response of the original create.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(untagged)]
+pub enum PublishedResponse {
+    QuestionWithAnswer(QuestionWithAnswerResponse),
+    Question(QuestionResponse),
+    Answer(AnswerResponse),
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "camel-case", serde(rename_all = "camelCase"))]
+pub struct QuestionWithAnswerResponse {
+    pub question: QuestionResponse,
+    pub answer: AnswerResponse,
+}
+
+impl From<CreateQuestionRequest> for Question {
+    fn from(request: CreateQuestionRequest) -> Self {
+        Question {
+            title: request.title,
+            description: request.description,
+            metadata: request.metadata.unwrap_or_else(empty_metadata),
+        }
+    }
+}
+
+impl From<UpdateQuestionRequest> for QuestionUpdate {
+    fn from(request: UpdateQuestionRequest) -> Self {
+        QuestionUpdate {
+            title: request.title,
+            description: request.description,
+            metadata: request.metadata.unwrap_or_else(empty_metadata),
+            version: request.version,
+        }
+    }
+}
+
+impl From<CreateQuestionWithAnswerRequest> for QuestionWithAnswer {
+    fn from(request: CreateQuestionWithAnswerRequest) -> Self {
+        QuestionWithAnswer {
+            question: request.question.into(),
+            answer: request.answer,
+        }
+    }
+}
+
+impl From<CreateAnswerRequest> for Answer {
+    fn from(request: CreateAnswerRequest) -> Self {
+        Answer {
+            question_uuid: request.question_uuid,
+            content: request.content,
+        }
+    }
+}
+
+impl From<UpdateAnswerRequest> for AnswerUpdate {
+    fn from(request: UpdateAnswerRequest) -> Self {
+        AnswerUpdate {
+            content: request.content,
+            version: request.version,
+        }
+    }
+}
+
+impl From<CreatePushSubscriptionRequest> for PushSubscription {
+    fn from(request: CreatePushSubscriptionRequest) -> Self {
+        PushSubscription {
+            endpoint: request.endpoint,
+            p256dh: request.keys.p256dh,
+            auth: request.keys.auth,
+        }
+    }
+}
+
+impl From<QuestionDetail> for QuestionResponse {
+    fn from(question: QuestionDetail) -> Self {
+        QuestionResponse {
+            question_uuid: question.question_uuid,
+            title: question.title,
+            description: question.description,
+            metadata: question.metadata,
+            created_at: question.created_at.fixed_offset(),
+            updated_at: question.updated_at.fixed_offset(),
+            last_activity_at: question.last_activity_at.fixed_offset(),
+            answer_count: question.answer_count,
+            version: question.version,
+            answers: None,
+            attachments: None,
+            language: None,
+            link_previews: None,
+            highlight: None,
+        }
+    }
+}
+
+impl From<LinkPreview> for LinkPreviewResponse {
+    fn from(preview: LinkPreview) -> Self {
+        LinkPreviewResponse {
+            url: preview.url,
+            title: preview.title,
+            description: preview.description,
+            image_url: preview.image_url,
+            site_name: preview.site_name,
+        }
+    }
+}
+
+impl From<AnswerDetail> for AnswerResponse {
+    fn from(answer: AnswerDetail) -> Self {
+        AnswerResponse {
+            answer_uuid: answer.answer_uuid,
+            question_uuid: answer.question_uuid,
+            content: answer.content,
+            created_at: answer.created_at.fixed_offset(),
+            updated_at: answer.updated_at.fixed_offset(),
+            version: answer.version,
+            attachments: None,
+            similar_answers: None,
+        }
+    }
+}
+
+impl From<Attachment> for AttachmentResponse {
+    fn from(attachment: Attachment) -> Self {
+        AttachmentResponse {
+            attachment_uuid: attachment.attachment_uuid,
+            question_uuid: attachment.question_uuid,
+            answer_uuid: attachment.answer_uuid,
+            filename: attachment.filename,
+            content_type: attachment.content_type,
+            size_bytes: attachment.size_bytes,
+            status: attachment.status.as_str().to_owned(),
+            variants: attachment
+                .variants
+                .into_iter()
+                .map(|variant| AttachmentVariantResponse {
+                    url: format!(
+                        "/attachments/{}/variants/{}",
+                        attachment.attachment_uuid, variant.name
+                    ),
+                    name: variant.name,
+                    width: variant.width,
+                    height: variant.height,
+                    content_type: variant.content_type,
+                    size_bytes: variant.size_bytes,
+                })
+                .collect(),
+            created_at: attachment.created_at.fixed_offset(),
+        }
+    }
+}
+
+impl From<ModerationItem> for ModerationItemResponse {
+    fn from(item: ModerationItem) -> Self {
+        ModerationItemResponse {
+            moderation_uuid: item.moderation_uuid,
+            content: item.content,
+            score: item.score,
+            categories: item.categories,
+            created_at: item.created_at,
+        }
+    }
+}
+
+impl From<SimilarAnswer> for SimilarAnswerResponse {
+    fn from(answer: SimilarAnswer) -> Self {
+        SimilarAnswerResponse {
+            answer_uuid: answer.answer_uuid,
+            content: answer.content,
+            similarity: answer.similarity,
+        }
+    }
+}
+
+impl From<Highlight> for HighlightResponse {
+    fn from(highlight: Highlight) -> Self {
+        HighlightResponse {
+            title: highlight.title,
+            description: highlight.description,
+        }
+    }
+}
+
+impl From<TitleMatch> for TitleMatchResponse {
+    fn from(found: TitleMatch) -> Self {
+        TitleMatchResponse {
+            question_uuid: found.question_uuid,
+            title: found.title,
+        }
+    }
+}
+
+impl From<RestHook> for RestHookResponse {
+    fn from(hook: RestHook) -> Self {
+        RestHookResponse {
+            id: hook.hook_uuid,
+            target_url: hook.target_url,
+            event: hook.event_type,
+            created_at: hook.created_at,
+        }
+    }
+}
+
+impl From<QuestionTranslation> for TranslationResponse {
+    fn from(translation: QuestionTranslation) -> Self {
+        TranslationResponse {
+            question_uuid: translation.question_uuid,
+            language: translation.language,
+            title: translation.title,
+            description: translation.description,
+            question_version: translation.question_version,
+            updated_at: translation.updated_at,
+        }
+    }
+}
+
+impl From<ScimUserRequest> for UserAttributes {
+    fn from(request: ScimUserRequest) -> Self {
+        UserAttributes {
+            user_name: request.user_name,
+            external_id: request.external_id,
+            display_name: request.display_name,
+            active: request.active,
+        }
+    }
+}
+
+impl From<ProvisionedUser> for ScimUserResponse {
+    fn from(user: ProvisionedUser) -> Self {
+        let attributes = user.attributes;
+        ScimUserResponse {
+            schemas: vec![SCIM_USER_SCHEMA.to_owned()],
+            id: user.user_uuid,
+            external_id: attributes.external_id,
+            emails: vec![ScimEmail {
+                value: attributes.user_name.clone(),
+                primary: true,
+            }],
+            user_name: attributes.user_name,
+            display_name: attributes.display_name,
+            active: attributes.active,
+            meta: ScimMeta {
+                resource_type: "User".to_owned(),
+                created: user.created_at,
+                last_modified: user.updated_at,
+                location: format!("/scim/v2/Users/{}", user.user_uuid),
+            },
+        }
+    }
+}
+
+impl From<Published> for PublishedResponse {
+    fn from(published: Published) -> Self {
+        match published {
+            Published::Question(question) => PublishedResponse::Question(question.into()),
+            Published::Answer(answer) => PublishedResponse::Answer(answer.into()),
+            Published::QuestionWithAnswer(detail) => {
+                PublishedResponse::QuestionWithAnswer(detail.into())
+            }
+        }
+    }
+}
+
+impl From<QuestionWithAnswerDetail> for QuestionWithAnswerResponse {
+    fn from(detail: QuestionWithAnswerDetail) -> Self {
+        QuestionWithAnswerResponse {
+            question: detail.question.into(),
+            answer: detail.answer.into(),
+        }
+    }
+}
+
+// One page of a list, the same for every paged endpoint. `total` is left out where
+// counting would cost another query, as for ranked search results; `next_cursor` is
+// null on the last page.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "camel-case", serde(rename_all = "camelCase"))]
+pub struct Paginated<T> {
+    pub items: Vec<T>,
+    pub page: u32,
+    pub per_page: u32,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub total: Option<u64>,
+    pub next_cursor: Option<String>,
+}
+
+impl<T> Paginated<T> {
+    // Maps the items, e.g. models to their response DTOs.
+    pub fn responses<R: From<T>>(self) -> Paginated<R> {
+        Paginated {
+            items: responses(self.items),
+            page: self.page,
+            per_page: self.per_page,
+            total: self.total,
+            next_cursor: self.next_cursor,
+        }
+    }
+}
+
+// Lists are mapped item by item.
+pub fn responses<T, R: From<T>>(items: Vec<T>) -> Vec<R> {
+    items.into_iter().map(R::from).collect()
+}
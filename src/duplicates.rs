@@ -0,0 +1,31 @@
+use std::sync::Arc;
+
+use crate::{
+    config::DuplicateAnswerPolicy,
+    models::{Answer, DBError},
+    persistence::duplicate::{DuplicateDao, SimilarAnswer},
+};
+
+// Enough to point at the answers that are already there without a long response.
+const MAX_SIMILAR: u32 = 3;
+
+// Managed as `Option<DuplicateAnswers>`; None while DUPLICATE_ANSWERS is off.
+pub struct DuplicateAnswers {
+    pub duplicate_dao: Arc<dyn DuplicateDao + Send + Sync>,
+    pub policy: DuplicateAnswerPolicy,
+    pub threshold: f32,
+}
+
+impl DuplicateAnswers {
+    // Checked before `answer` is stored, so it is not compared with itself.
+    pub async fn similar_to(&self, answer: &Answer) -> Result<Vec<SimilarAnswer>, DBError> {
+        self.duplicate_dao
+            .similar_answers(
+                answer.question_uuid,
+                &answer.content,
+                self.threshold,
+                MAX_SIMILAR,
+            )
+            .await
+    }
+}
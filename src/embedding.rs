@@ -0,0 +1,191 @@
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use thiserror::Error;
+
+use crate::{
+    config::{AppConfig, EmbedderKind},
+    fnv::Fnv1a,
+    persistence::embedding::EmbeddingDao,
+    startup::StartupError,
+};
+
+// Width of the `questions.embedding` column, see the embeddings migration.
+pub const EMBEDDING_DIMENSIONS: usize = 1536;
+
+// Only remote embedders can fail.
+#[derive(Error, Debug)]
+pub enum EmbeddingError {
+    #[cfg(feature = "openai")]
+    #[error("Embedding request failed: {0}")]
+    Request(String),
+    #[cfg(feature = "openai")]
+    #[error("Expected {EMBEDDING_DIMENSIONS} dimensions but got {0}")]
+    Dimensions(usize),
+}
+
+// Turns text into a vector whose cosine distance to others reflects how related they are.
+#[async_trait]
+pub trait Embedder {
+    async fn embed(&self, text: &str) -> Result<Vec<f32>, EmbeddingError>;
+}
+
+// Managed as `Option<SemanticSearch>`; None while no embedder is configured or the
+// backend cannot store embeddings.
+pub struct SemanticSearch {
+    pub embedder: Box<dyn Embedder + Send + Sync>,
+    pub embedding_dao: Arc<dyn EmbeddingDao + Send + Sync>,
+}
+
+pub fn embedder_from_config(
+    config: &AppConfig,
+) -> Result<Option<Box<dyn Embedder + Send + Sync>>, StartupError> {
+    match config.embedder {
+        EmbedderKind::None => Ok(None),
+        EmbedderKind::Hashing => Ok(Some(Box::new(HashingEmbedder))),
+        #[cfg(feature = "openai")]
+        EmbedderKind::OpenAi => Ok(Some(Box::new(OpenAiEmbedder::new(
+            &config.embedding_api_url,
+            config.embedding_api_key.clone(),
+            config.embedding_model.clone(),
+        )))),
+        #[cfg(not(feature = "openai"))]
+        EmbedderKind::OpenAi => Err(StartupError::MissingFeature {
+            setting: "EMBEDDER=openai",
+            feature: "openai",
+        }),
+    }
+}
+
+// What a question is embedded from, so stored and query vectors are built the same way.
+pub fn question_text(title: &str, description: Option<&str>) -> String {
+    match description {
+        Some(description) => format!("{}\n{}", title, description),
+        None => title.to_owned(),
+    }
+}
+
+// Hashes words into buckets. Runs in process and needs no model, but only matches shared
+// words, not meaning; useful for development and as a fallback.
+pub struct HashingEmbedder;
+
+#[async_trait]
+impl Embedder for HashingEmbedder {
+    async fn embed(&self, text: &str) -> Result<Vec<f32>, EmbeddingError> {
+        let mut embedding = vec![0.0_f32; EMBEDDING_DIMENSIONS];
+        for word in text
+            .split(|c: char| !c.is_alphanumeric())
+            .filter(|word| !word.is_empty())
+        {
+            let mut hash = Fnv1a::new();
+            hash.write(word.to_lowercase().as_bytes());
+            let hash = hash.finish();
+            // The sign bit keeps colliding words from always adding up.
+            let sign = if hash >> 63 == 0 { 1.0 } else { -1.0 };
+            embedding[(hash % EMBEDDING_DIMENSIONS as u64) as usize] += sign;
+        }
+
+        let norm = embedding
+            .iter()
+            .map(|value| value * value)
+            .sum::<f32>()
+            .sqrt();
+        if norm > 0.0 {
+            embedding.iter_mut().for_each(|value| *value /= norm);
+        }
+        Ok(embedding)
+    }
+}
+
+// Any server implementing OpenAI's `/embeddings` endpoint, which includes local model
+// servers such as Ollama or llama.cpp.
+#[cfg(feature = "openai")]
+pub struct OpenAiEmbedder {
+    client: reqwest::Client,
+    url: String,
+    api_key: Option<String>,
+    model: String,
+}
+
+#[cfg(feature = "openai")]
+impl OpenAiEmbedder {
+    pub fn new(base_url: &str, api_key: Option<String>, model: String) -> Self {
+        Self {
+            client: reqwest::Client::new(),
+            url: format!("{}/embeddings", base_url.trim_end_matches('/')),
+            api_key,
+            model,
+        }
+    }
+}
+
+#[cfg(feature = "openai")]
+#[async_trait]
+impl Embedder for OpenAiEmbedder {
+    async fn embed(&self, text: &str) -> Result<Vec<f32>, EmbeddingError> {
+        #[derive(serde::Deserialize)]
+        struct Response {
+            data: Vec<Item>,
+        }
+
+        #[derive(serde::Deserialize)]
+        struct Item {
+            embedding: Vec<f32>,
+        }
+
+        let mut request = self.client.post(&self.url).json(&serde_json::json!({
+            "model": self.model,
+            "input": text,
+            "dimensions": EMBEDDING_DIMENSIONS,
+        }));
+        if let Some(api_key) = &self.api_key {
+            request = request.bearer_auth(api_key);
+        }
+
+        let response: Response = request
+            .send()
+            .await
+            .and_then(|response| response.error_for_status())
+            .map_err(|err| EmbeddingError::Request(err.to_string()))?
+            .json()
+            .await
+            .map_err(|err| EmbeddingError::Request(err.to_string()))?;
+
+        let embedding = response
+            .data
+            .into_iter()
+            .next()
+            .ok_or_else(|| EmbeddingError::Request("Response has no embedding".to_owned()))?
+            .embedding;
+        if embedding.len() != EMBEDDING_DIMENSIONS {
+            return Err(EmbeddingError::Dimensions(embedding.len()));
+        }
+        Ok(embedding)
+    }
+}
+
+pub fn cosine_distance(a: &[f32], b: &[f32]) -> f32 {
+    let dot: f32 = a.iter().zip(b).map(|(a, b)| a * b).sum();
+    let norm = |v: &[f32]| v.iter().map(|value| value * value).sum::<f32>().sqrt();
+    let norms = norm(a) * norm(b);
+    if norms == 0.0 {
+        return 1.0;
+    }
+    1.0 - dot / norms
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn hashing_embedder_should_place_shared_words_closer() {
+        let embedder = HashingEmbedder;
+        let query = embedder.embed("How do I borrow in Rust?").await.unwrap();
+        let related = embedder.embed("Rust borrow checker errors").await.unwrap();
+        let unrelated = embedder.embed("Best pizza in Naples").await.unwrap();
+
+        assert_eq!(query.len(), EMBEDDING_DIMENSIONS);
+        assert!(cosine_distance(&query, &related) < cosine_distance(&query, &unrelated));
+    }
+}
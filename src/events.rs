@@ -0,0 +1,428 @@
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use chrono::{DateTime, TimeZone, Utc};
+use log::info;
+use serde::Serialize;
+use serde_json::json;
+use thiserror::Error;
+
+use crate::{
+    config::{AppConfig, SearchBackend},
+    persistence::rest_hook::RestHookDao,
+    startup::StartupError,
+};
+
+// Every `event_type` the outbox triggers write.
+pub const EVENT_TYPES: [&str; 8] = [
+    "question_created",
+    "question_updated",
+    "question_deleted",
+    "question_archived",
+    "answer_created",
+    "answer_updated",
+    "answer_deleted",
+    "answer_archived",
+];
+
+// A row of the `outbox` table as subscribers receive it. Delivery is at least once,
+// so consumers should skip ids they have already seen.
+#[derive(Debug, Clone, Serialize)]
+pub struct OutboxEvent {
+    pub id: i64,
+    // `question_created`, `answer_updated`, `question_archived`, ...
+    pub event_type: String,
+    pub aggregate_uuid: String,
+    // The row after the change, or before it for deletes.
+    pub payload: serde_json::Value,
+    pub created_at: DateTime<Utc>,
+}
+
+// What an event of `event_type` looks like, for REST hook clients to map fields from
+// before any has happened. None for unknown types.
+pub fn sample_event(event_type: &str) -> Option<OutboxEvent> {
+    if !EVENT_TYPES.contains(&event_type) {
+        return None;
+    }
+
+    let question_uuid = "6f1b6d3e-8a55-4c3a-9d47-2f0c1e9b7a10";
+    let answer_uuid = "0c9e7a4b-3d21-4f6e-8b5a-91d2c7e4f3a8";
+    let (aggregate_uuid, payload) = if event_type.starts_with("question_") {
+        (
+            question_uuid,
+            json!({
+                "question_uuid": question_uuid,
+                "title": "How do I reset my password?",
+                "description": "The reset link in the email has expired.",
+                "created_at": "2023-10-01T12:00:00",
+                "answer_count": 1,
+                "updated_at": "2023-10-01T12:00:00",
+                "last_activity_at": "2023-10-01T12:05:00",
+                "version": 1,
+                "metadata": {},
+            }),
+        )
+    } else {
+        (
+            answer_uuid,
+            json!({
+                "answer_uuid": answer_uuid,
+                "question_uuid": question_uuid,
+                "content": "Request a new link from the sign in page.",
+                "created_at": "2023-10-01T12:05:00",
+                "updated_at": "2023-10-01T12:05:00",
+                "version": 1,
+            }),
+        )
+    };
+
+    Some(OutboxEvent {
+        id: 1,
+        event_type: event_type.to_owned(),
+        aggregate_uuid: aggregate_uuid.to_owned(),
+        payload,
+        created_at: Utc.with_ymd_and_hms(2023, 10, 1, 12, 5, 0).unwrap(),
+    })
+}
+
+#[derive(Error, Debug)]
+#[error("Publishing event failed: {0}")]
+pub struct PublishError(pub String);
+
+#[async_trait]
+pub trait EventPublisher {
+    async fn publish(&self, event: &OutboxEvent) -> Result<(), PublishError>;
+}
+
+pub fn publisher_from_config(
+    config: &AppConfig,
+    rest_hook_dao: Arc<dyn RestHookDao + Send + Sync>,
+) -> Result<Box<dyn EventPublisher + Send + Sync>, StartupError> {
+    let mut publishers = vec![subscriber_from_config(config)?];
+    // Without the feature hooks cannot be registered, so there are none to call.
+    #[cfg(feature = "webhooks")]
+    publishers.push(Box::new(RestHookPublisher::new(rest_hook_dao)));
+    #[cfg(not(feature = "webhooks"))]
+    drop(rest_hook_dao);
+    match &config.kafka_brokers {
+        #[cfg(feature = "kafka")]
+        Some(brokers) => publishers.push(Box::new(KafkaPublisher::new(brokers, config)?)),
+        #[cfg(not(feature = "kafka"))]
+        Some(_) => {
+            return Err(StartupError::MissingFeature {
+                setting: "KAFKA_BROKERS",
+                feature: "kafka",
+            })
+        }
+        None => {}
+    }
+    match config.search_backend {
+        SearchBackend::Database => {}
+        #[cfg(feature = "elasticsearch")]
+        SearchBackend::Elasticsearch => publishers.push(Box::new(
+            crate::search::ElasticsearchIndexer(crate::search::Elasticsearch::from_config(config)),
+        )),
+        #[cfg(not(feature = "elasticsearch"))]
+        SearchBackend::Elasticsearch => {
+            return Err(StartupError::MissingFeature {
+                setting: "SEARCH_BACKEND=elasticsearch",
+                feature: "elasticsearch",
+            })
+        }
+    }
+
+    Ok(if publishers.len() == 1 {
+        publishers.remove(0)
+    } else {
+        Box::new(FanoutPublisher(publishers))
+    })
+}
+
+fn subscriber_from_config(
+    config: &AppConfig,
+) -> Result<Box<dyn EventPublisher + Send + Sync>, StartupError> {
+    match &config.outbox_webhook_url {
+        #[cfg(feature = "webhooks")]
+        Some(url) => Ok(Box::new(WebhookPublisher::new(url.clone()))),
+        #[cfg(not(feature = "webhooks"))]
+        Some(_) => Err(StartupError::MissingFeature {
+            setting: "OUTBOX_WEBHOOK_URL",
+            feature: "webhooks",
+        }),
+        None => Ok(Box::new(LogPublisher)),
+    }
+}
+
+// Publishes to each publisher in turn. An event that fails on one is retried on all, so
+// the ones before it see it again.
+pub struct FanoutPublisher(pub Vec<Box<dyn EventPublisher + Send + Sync>>);
+
+#[async_trait]
+impl EventPublisher for FanoutPublisher {
+    async fn publish(&self, event: &OutboxEvent) -> Result<(), PublishError> {
+        for publisher in &self.0 {
+            publisher.publish(event).await?;
+        }
+        Ok(())
+    }
+}
+
+// Used while no subscriber is configured, so the outbox still drains.
+pub struct LogPublisher;
+
+#[async_trait]
+impl EventPublisher for LogPublisher {
+    async fn publish(&self, event: &OutboxEvent) -> Result<(), PublishError> {
+        info!(
+            "Event {}: {} {}",
+            event.id, event.event_type, event.aggregate_uuid
+        );
+        Ok(())
+    }
+}
+
+// POSTs each event as JSON; anything but a 2xx response counts as a failure.
+#[cfg(feature = "webhooks")]
+pub struct WebhookPublisher {
+    client: reqwest::Client,
+    url: String,
+}
+
+#[cfg(feature = "webhooks")]
+impl WebhookPublisher {
+    pub fn new(url: String) -> Self {
+        let client = reqwest::Client::builder()
+            .timeout(std::time::Duration::from_secs(10))
+            .build()
+            .expect("HTTP client should build.");
+
+        Self { client, url }
+    }
+}
+
+#[cfg(feature = "webhooks")]
+#[async_trait]
+impl EventPublisher for WebhookPublisher {
+    async fn publish(&self, event: &OutboxEvent) -> Result<(), PublishError> {
+        self.client
+            .post(&self.url)
+            .json(event)
+            .send()
+            .await
+            .and_then(|response| response.error_for_status())
+            .map_err(|err| PublishError(err.to_string()))?;
+
+        Ok(())
+    }
+}
+
+// POSTs each event as JSON to the REST hooks subscribed to its type. A hook answering
+// 410 Gone is unsubscribed, as Zapier and Make expect; other failures are only logged,
+// so one broken target cannot hold up the outbox.
+#[cfg(feature = "webhooks")]
+pub struct RestHookPublisher {
+    client: reqwest::Client,
+    rest_hook_dao: Arc<dyn RestHookDao + Send + Sync>,
+}
+
+#[cfg(feature = "webhooks")]
+impl RestHookPublisher {
+    pub fn new(rest_hook_dao: Arc<dyn RestHookDao + Send + Sync>) -> Self {
+        let client = reqwest::Client::builder()
+            .timeout(std::time::Duration::from_secs(10))
+            .build()
+            .expect("HTTP client should build.");
+
+        Self {
+            client,
+            rest_hook_dao,
+        }
+    }
+}
+
+#[cfg(feature = "webhooks")]
+#[async_trait]
+impl EventPublisher for RestHookPublisher {
+    async fn publish(&self, event: &OutboxEvent) -> Result<(), PublishError> {
+        let hooks = self
+            .rest_hook_dao
+            .hooks_for(&event.event_type)
+            .await
+            .map_err(|err| PublishError(err.to_string()))?;
+
+        for hook in hooks {
+            match self.client.post(&hook.target_url).json(event).send().await {
+                Ok(response) if response.status() == reqwest::StatusCode::GONE => {
+                    info!(
+                        "REST hook {} answered 410, unsubscribing it",
+                        hook.hook_uuid
+                    );
+                    if let Err(err) = self.rest_hook_dao.delete_hook(hook.hook_uuid).await {
+                        log::warn!("REST hook {} not unsubscribed: {}", hook.hook_uuid, err);
+                    }
+                }
+                Ok(response) if response.status().is_success() => {}
+                Ok(response) => log::warn!(
+                    "REST hook {} answered {} to event {}",
+                    hook.hook_uuid,
+                    response.status(),
+                    event.id
+                ),
+                Err(err) => log::warn!(
+                    "REST hook {} not called for event {}: {}",
+                    hook.hook_uuid,
+                    event.id,
+                    err
+                ),
+            }
+        }
+        Ok(())
+    }
+}
+
+// Bumped when a field of `EventEnvelope` changes meaning or goes away; added fields keep
+// the version.
+#[cfg(feature = "kafka")]
+pub const EVENT_SCHEMA_VERSION: u32 = 1;
+
+// What is produced to Kafka. `data` is the row as in `OutboxEvent::payload`, whose
+// columns only grow through migrations.
+#[cfg(feature = "kafka")]
+#[derive(Debug, Serialize)]
+pub struct EventEnvelope<'a> {
+    pub schema_version: u32,
+    pub event_id: i64,
+    pub event_type: &'a str,
+    pub aggregate_uuid: &'a str,
+    pub occurred_at: DateTime<Utc>,
+    pub data: &'a serde_json::Value,
+}
+
+#[cfg(feature = "kafka")]
+impl<'a> From<&'a OutboxEvent> for EventEnvelope<'a> {
+    fn from(event: &'a OutboxEvent) -> Self {
+        Self {
+            schema_version: EVENT_SCHEMA_VERSION,
+            event_id: event.id,
+            event_type: &event.event_type,
+            aggregate_uuid: &event.aggregate_uuid,
+            occurred_at: event.created_at,
+            data: &event.payload,
+        }
+    }
+}
+
+// Shared by the event and analytics producers.
+#[cfg(feature = "kafka")]
+pub(crate) fn kafka_producer(
+    brokers: &str,
+    config: &AppConfig,
+) -> Result<rdkafka::producer::FutureProducer, StartupError> {
+    let mut client_config = rdkafka::ClientConfig::new();
+    client_config
+        .set("bootstrap.servers", brokers)
+        .set("message.timeout.ms", "10000")
+        .set("enable.idempotence", "true");
+    for (key, value) in &config.kafka_properties {
+        client_config.set(key, value);
+    }
+
+    client_config
+        .create()
+        .map_err(|err| StartupError::InvalidSetting {
+            setting: "KAFKA_BROKERS or KAFKA_PROPERTIES",
+            expected: "comma separated host:port brokers and librdkafka key=value properties",
+            reason: err.to_string(),
+        })
+}
+
+// Produces question events to one topic and answer events to another, keyed by the
+// aggregate uuid so each question's or answer's events stay in order on a partition.
+#[cfg(feature = "kafka")]
+pub struct KafkaPublisher {
+    producer: rdkafka::producer::FutureProducer,
+    question_topic: String,
+    answer_topic: String,
+}
+
+#[cfg(feature = "kafka")]
+impl KafkaPublisher {
+    pub fn new(brokers: &str, config: &AppConfig) -> Result<Self, StartupError> {
+        Ok(Self {
+            producer: kafka_producer(brokers, config)?,
+            question_topic: config.kafka_question_topic.clone(),
+            answer_topic: config.kafka_answer_topic.clone(),
+        })
+    }
+
+    fn topic(&self, event_type: &str) -> Option<&str> {
+        match event_type.split_once('_') {
+            Some(("question", _)) => Some(&self.question_topic),
+            Some(("answer", _)) => Some(&self.answer_topic),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(feature = "kafka")]
+#[async_trait]
+impl EventPublisher for KafkaPublisher {
+    async fn publish(&self, event: &OutboxEvent) -> Result<(), PublishError> {
+        use rdkafka::message::{Header, OwnedHeaders};
+
+        let Some(topic) = self.topic(&event.event_type) else {
+            return Ok(());
+        };
+        let payload = serde_json::to_vec(&EventEnvelope::from(event))
+            .map_err(|err| PublishError(err.to_string()))?;
+        let schema_version = EVENT_SCHEMA_VERSION.to_string();
+        let headers = OwnedHeaders::new()
+            .insert(Header {
+                key: "event_type",
+                value: Some(&event.event_type),
+            })
+            .insert(Header {
+                key: "schema_version",
+                value: Some(&schema_version),
+            });
+        let record = rdkafka::producer::FutureRecord::to(topic)
+            .key(&event.aggregate_uuid)
+            .payload(&payload)
+            .headers(headers);
+
+        self.producer
+            .send(record, std::time::Duration::ZERO)
+            .await
+            .map_err(|(err, _)| PublishError(err.to_string()))?;
+        Ok(())
+    }
+}
+
+#[cfg(all(test, feature = "kafka"))]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn envelope_should_carry_the_schema_version_and_row() {
+        let event = OutboxEvent {
+            id: 7,
+            event_type: "question_created".to_owned(),
+            aggregate_uuid: "4b1f4a6e-3c4c-4a3e-9a57-6a0c0e1f5b2d".to_owned(),
+            payload: json!({ "title": "title" }),
+            created_at: DateTime::UNIX_EPOCH,
+        };
+
+        assert_eq!(
+            serde_json::to_value(EventEnvelope::from(&event)).unwrap(),
+            json!({
+                "schema_version": 1,
+                "event_id": 7,
+                "event_type": "question_created",
+                "aggregate_uuid": "4b1f4a6e-3c4c-4a3e-9a57-6a0c0e1f5b2d",
+                "occurred_at": "1970-01-01T00:00:00Z",
+                "data": { "title": "title" },
+            })
+        );
+    }
+}
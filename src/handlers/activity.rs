@@ -0,0 +1,31 @@
+use rocket::{serde::json::Json, State};
+
+use super::{deadline::Deadline, pagination::KeysetPage, private, AppError};
+use crate::{
+    dto::Paginated, models::Activity, persistence::activity::ACTIVITY_MAX_LIMIT, AppState,
+};
+
+const DEFAULT_LIMIT: u32 = 20;
+
+// Newest first, for a "recent activity" widget; follow `next_cursor`, or pass the
+// smallest `id` seen as `before`, for the next page.
+#[get("/activity?<before>&<limit>&<cursor>")]
+pub async fn get_activity(
+    before: Option<i64>,
+    limit: Option<u32>,
+    cursor: Option<String>,
+    state: &State<AppState>,
+    deadline: Deadline<'_>,
+) -> Result<Json<Paginated<Activity>>, AppError> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).clamp(1, ACTIVITY_MAX_LIMIT);
+    let page = KeysetPage::from_params(before, limit, cursor.as_deref())?;
+    let result = deadline
+        .run(private::get_activity(
+            page.before,
+            page.per_page,
+            state.activity_dao.as_deref(),
+        ))
+        .await?;
+
+    Ok(Json(page.listed(result, |activity| activity.id)))
+}
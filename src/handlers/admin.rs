@@ -0,0 +1,74 @@
+use rocket::{
+    http::Status,
+    request::{self, FromRequest},
+    Request, State,
+};
+
+use crate::{config::AppConfig, AppState};
+
+use super::AppError;
+
+// Request guard for operator-only endpoints: `Authorization: Bearer <ADMIN_TOKEN>`.
+pub struct Admin;
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for Admin {
+    type Error = ();
+
+    async fn from_request(req: &'r Request<'_>) -> request::Outcome<Self, Self::Error> {
+        bearer_token(req, |config| config.admin_token.clone()).map(|()| Admin)
+    }
+}
+
+// The one bearer token check of the guards: 403 while `token` is unset, which disables
+// the endpoints, and 401 unless `Authorization: Bearer` carries it.
+pub(crate) fn bearer_token(
+    req: &Request<'_>,
+    token: impl FnOnce(&AppConfig) -> Option<String>,
+) -> request::Outcome<(), ()> {
+    let expected = req
+        .rocket()
+        .state::<AppState>()
+        .and_then(|state| token(&state.live_config.load()));
+    let Some(expected) = expected else {
+        return request::Outcome::Error((Status::Forbidden, ()));
+    };
+
+    let provided = req
+        .headers()
+        .get_one("Authorization")
+        .and_then(|value| value.strip_prefix("Bearer "));
+
+    match provided {
+        Some(provided) if tokens_match(provided, &expected) => request::Outcome::Success(()),
+        _ => request::Outcome::Error((Status::Unauthorized, ())),
+    }
+}
+
+// Compares without short-circuiting so response times don't leak the token.
+pub(crate) fn tokens_match(provided: &str, expected: &str) -> bool {
+    provided.len() == expected.len()
+        && provided
+            .bytes()
+            .zip(expected.bytes())
+            .fold(0, |diff, (a, b)| diff | (a ^ b))
+            == 0
+}
+
+#[post("/admin/config/reload")]
+pub fn reload_config(_admin: Admin, state: &State<AppState>) -> Result<(), AppError> {
+    state.live_config.reload().map_err(AppError::Internal)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tokens_match_should_compare_exactly() {
+        assert!(tokens_match("secret", "secret"));
+        assert!(!tokens_match("secreT", "secret"));
+        assert!(!tokens_match("secret-but-longer", "secret"));
+        assert!(!tokens_match("", "secret"));
+    }
+}
@@ -1,53 +1,244 @@
-use rocket::{serde::json::Json, State};
+use std::net::IpAddr;
 
-use crate::{models::*, persistence::answer_dao::AnswerDao};
+use rocket::{
+    form::{Errors, Form},
+    serde::json::Json,
+    Either, State,
+};
+
+use crate::{
+    analytics::Tracker, dto::*, models::*, moderation::Moderated, rate_limit::RateLimitSlot,
+    AppState,
+};
 
 use super::{
-    private::{self, HandlerError},
-    APIError,
+    captcha::CaptchaToken,
+    deadline::Deadline,
+    etag::{IfMatch, Tagged},
+    moderation::{moderated_json, ModeratedJson},
+    ndjson::{AcceptsNdjson, Ndjson},
+    pagination::DEFAULT_PER_PAGE,
+    payload::{JsonContent, LimitedJson},
+    private,
+    query::{AnswerListParams, Includes},
+    validation::FieldError,
+    AppError,
 };
 
-impl From<HandlerError> for APIError {
-    fn from(value: HandlerError) -> Self {
-        match value {
-            HandlerError::BadRequest(e) => Self::BadRequest(e),
-            HandlerError::InternalError(e) => Self::InternalError(e),
-        }
+// `allow_duplicate` posts the answer even when DUPLICATE_ANSWERS=reject finds it close to
+// existing answers.
+#[post("/answer?<allow_duplicate>", data = "<answer>")]
+#[allow(clippy::too_many_arguments)]
+pub async fn create_answer(
+    _content: JsonContent,
+    answer: LimitedJson<CreateAnswerRequest>,
+    allow_duplicate: Option<bool>,
+    spam_context: SpamContext,
+    captcha: CaptchaToken,
+    state: &State<AppState>,
+    deadline: Deadline<'_>,
+    tracker: Tracker<'_>,
+) -> Result<ModeratedJson<AnswerResponse>, AppError> {
+    deadline.run(captcha.verify(state.captcha.as_ref())).await?;
+    let (result, similar) = deadline
+        .run(private::create_answer(
+            answer.0.into(),
+            allow_duplicate.unwrap_or(false),
+            state.answer_dao.as_ref(),
+            private::WriteHooks::of(state),
+            &spam_context,
+        ))
+        .await?;
+    // Held answers are tracked once approved, if ever, which is not a user action.
+    if let Moderated::Published(answer) = &result {
+        tracker.track(
+            "answer_created",
+            serde_json::json!({
+                "answer_uuid": answer.answer_uuid,
+                "question_uuid": answer.question_uuid,
+            }),
+        );
+    }
+
+    let mut response = moderated_json::<_, AnswerResponse>(result);
+    if let (Either::Left(Json(answer)), false) = (&mut response, similar.is_empty()) {
+        answer.similar_answers = Some(similar.into_iter().map(Into::into).collect());
     }
+    Ok(response)
 }
 
-#[post("/answer", data = "<answer>")]
-pub async fn create_answer(
-    answer: Json<Answer>,
-    answer_dao: &State<Box<dyn AnswerDao + Sync + Send>>,
-) -> Result<Json<AnswerDetail>, APIError> {
-    let result = private::create_answer(answer.0, answer_dao)
-        .await
-        .map_err(|err| APIError::from(err))?;
+// The fields of an inbound email webhook: Mailgun's routes send `recipient` and
+// `body-plain`, SendGrid's Inbound Parse `to` and `text`. Attachments and the other
+// fields are ignored.
+#[derive(FromForm)]
+pub struct InboundEmail {
+    #[field(name = "recipient")]
+    #[field(name = "to")]
+    recipient: String,
+    #[field(name = "body-plain")]
+    #[field(name = "text")]
+    body: String,
+}
 
-    Ok(Json(result))
+// Replies to answer notifications, which are sent from a reply address of their question.
+#[post("/answer/email", data = "<email>")]
+pub async fn answer_by_email(
+    email: Form<InboundEmail>,
+    state: &State<AppState>,
+    deadline: Deadline<'_>,
+) -> Result<ModeratedJson<AnswerResponse>, AppError> {
+    let result = deadline
+        .run(private::answer_by_email(
+            &email.recipient,
+            &email.body,
+            state.replies.as_ref(),
+            state.answer_dao.as_ref(),
+            private::WriteHooks::of(state),
+        ))
+        .await?;
+
+    Ok(moderated_json(result))
 }
 
-#[get("/answers/<question_uuid>")]
-pub async fn get_answers(
-    question_uuid: String,
-    answer_dao: &State<Box<dyn AnswerDao + Send + Sync>>,
-) -> Result<Json<Vec<AnswerDetail>>, APIError> {
-    let result = private::get_answers(question_uuid, answer_dao)
-        .await
-        .map_err(|err| APIError::from(err))?;
+#[patch("/answer/<answer_uuid>", data = "<update>")]
+pub async fn update_answer(
+    answer_uuid: Result<AnswerUuid, uuid::Error>,
+    _content: JsonContent,
+    update: LimitedJson<UpdateAnswerRequest>,
+    if_match: IfMatch,
+    state: &State<AppState>,
+    deadline: Deadline<'_>,
+) -> Result<Json<AnswerResponse>, AppError> {
+    let result = deadline
+        .run(private::update_answer(
+            answer_uuid?,
+            update.0.into(),
+            if_match.0,
+            state.answer_dao.as_ref(),
+            state.word_filter.as_ref(),
+        ))
+        .await?;
 
-    Ok(Json(result))
+    Ok(Json(result.into()))
+}
+
+#[get("/answers/<question_uuid>?<params..>")]
+pub async fn get_answers<'r>(
+    question_uuid: Result<QuestionUuid, uuid::Error>,
+    params: Result<AnswerListParams, Errors<'_>>,
+    accepts_ndjson: AcceptsNdjson,
+    state: &'r State<AppState>,
+    deadline: Deadline<'_>,
+) -> Result<Either<Ndjson<'r, AnswerResponse>, Tagged<AnswerResponse>>, AppError> {
+    let question_uuid = question_uuid?;
+    let params = params?;
+    let archive_dao = params.include_archived.then(|| state.archive_dao.as_ref());
+    let includes = params.includes(state.live_config.load().max_include_depth)?;
+
+    if accepts_ndjson.0 {
+        if includes != Includes::default() {
+            return Err(AppError::InvalidQuery(vec![FieldError {
+                field: "include".to_owned(),
+                message: "is not supported by NDJSON lists".to_owned(),
+            }]));
+        }
+        let rows = deadline
+            .run(private::stream_answers(
+                question_uuid,
+                state.answer_dao.as_ref(),
+                archive_dao,
+            ))
+            .await?;
+        return Ok(Either::Left(Ndjson::mapped(rows)));
+    }
+
+    let page = params.page(DEFAULT_PER_PAGE)?;
+    let result = deadline
+        .run(private::get_answers(
+            question_uuid,
+            state.answer_dao.as_ref(),
+            params.include_archived,
+            params.sort,
+            page.window(),
+        ))
+        .await?;
+    let mut page = page.listed(result).responses::<AnswerResponse>();
+
+    if includes.answer_attachments {
+        embed_attachments(page.items.iter_mut().collect(), state, &deadline).await?;
+    }
+
+    Ok(Either::Right(Tagged::new(page)))
+}
+
+// Fills in the attachments of each answer, with one query for all of them.
+pub(super) async fn embed_attachments(
+    answers: Vec<&mut AnswerResponse>,
+    state: &AppState,
+    deadline: &Deadline<'_>,
+) -> Result<(), AppError> {
+    let answer_uuids: Vec<AnswerUuid> = answers.iter().map(|answer| answer.answer_uuid).collect();
+    let mut attachments = deadline
+        .run(private::get_attachments_of_answers(
+            &answer_uuids,
+            state.attachments.as_ref(),
+        ))
+        .await?;
+    for answer in answers {
+        let included = attachments.remove(&answer.answer_uuid).unwrap_or_default();
+        answer.attachments = Some(responses(included));
+    }
+
+    Ok(())
 }
 
 #[delete("/answer/<answer_uuid>")]
 pub async fn delete_answer(
-    answer_uuid: String,
-    answer_dao: &State<Box<dyn AnswerDao + Send + Sync>>,
-) -> Result<(), APIError> {
-    private::delete_answer(answer_uuid, answer_dao)
-        .await
-        .map_err(|err| APIError::from(err))?;
+    answer_uuid: Result<AnswerUuid, uuid::Error>,
+    state: &State<AppState>,
+    deadline: Deadline<'_>,
+) -> Result<(), AppError> {
+    deadline
+        .run(private::delete_answer(
+            answer_uuid?,
+            state.answer_dao.as_ref(),
+        ))
+        .await?;
 
     Ok(())
 }
+
+// A generated draft for the asker or an answerer to edit and post; it is never posted
+// by the API. Limited to SUGGESTIONS_PER_HOUR per client IP.
+#[post("/question/<question_uuid>/suggest-answer")]
+pub async fn suggest_answer(
+    question_uuid: Result<QuestionUuid, uuid::Error>,
+    client_ip: Option<IpAddr>,
+    rate_limit: &RateLimitSlot,
+    state: &State<AppState>,
+    deadline: Deadline<'_>,
+) -> Result<Json<SuggestedAnswerResponse>, AppError> {
+    let question_uuid = question_uuid?;
+    let client = client_ip.map_or_else(|| "unknown".to_owned(), |ip| ip.to_string());
+    let suggestion = deadline
+        .run(private::suggest_answer(
+            question_uuid,
+            &client,
+            rate_limit,
+            &state.live_config.load(),
+            state.question_dao.as_ref(),
+            state.answer_dao.as_ref(),
+            state.suggestions.as_ref(),
+        ))
+        .await?;
+
+    Ok(Json(SuggestedAnswerResponse {
+        question_uuid,
+        content: suggestion.content,
+        draft: true,
+        ai_generated: true,
+        model: suggestion.model,
+        notice: "AI-generated draft. Review it before posting; it has not been published."
+            .to_owned(),
+    }))
+}
@@ -0,0 +1,22 @@
+use rocket::{serde::json::Json, State};
+
+use super::{admin::Admin, deadline::Deadline, private, AppError};
+use crate::{models::ArchiveSummary, AppState};
+
+#[post("/admin/archive?<older_than_days>")]
+pub async fn archive(
+    _admin: Admin,
+    older_than_days: Option<u32>,
+    state: &State<AppState>,
+    deadline: Deadline<'_>,
+) -> Result<Json<ArchiveSummary>, AppError> {
+    let result = deadline
+        .run(private::archive(
+            older_than_days,
+            state.live_config.load().archive_after_days,
+            state.archive_dao.as_ref(),
+        ))
+        .await?;
+
+    Ok(Json(result))
+}
@@ -0,0 +1,251 @@
+use rocket::{
+    form::Form,
+    fs::TempFile,
+    http::{ContentType, Header},
+    response::Redirect,
+    serde::json::Json,
+    State,
+};
+use tokio::io::AsyncReadExt;
+
+use super::{
+    deadline::Deadline,
+    payload::{JsonContent, LimitedJson},
+    private, AppError,
+};
+use crate::{
+    dto::{AttachmentResponse, AttachmentUploadResponse, CreateAttachmentRequest},
+    models::{AnswerUuid, AttachmentUuid, QuestionUuid},
+    AppState,
+};
+
+// A multipart upload: the `file` part, and optionally the answer of the question it
+// belongs to.
+#[derive(FromForm)]
+pub struct AttachmentForm<'r> {
+    file: TempFile<'r>,
+    answer_uuid: Option<String>,
+}
+
+// Uploads through the API, buffered in memory up to ATTACHMENT_BODY_LIMIT.
+#[post(
+    "/question/<question_uuid>/attachments",
+    format = "multipart/form-data",
+    data = "<form>"
+)]
+pub async fn upload_attachment(
+    question_uuid: Result<QuestionUuid, uuid::Error>,
+    form: Form<AttachmentForm<'_>>,
+    state: &State<AppState>,
+    deadline: Deadline<'_>,
+) -> Result<Json<AttachmentResponse>, AppError> {
+    let question_uuid = question_uuid?;
+    let answer_uuid = form
+        .answer_uuid
+        .as_deref()
+        .map(str::parse::<AnswerUuid>)
+        .transpose()?;
+    let filename = form
+        .file
+        .raw_name()
+        .map(|name| name.dangerous_unsafe_unsanitized_raw().as_str().to_owned())
+        .unwrap_or_default();
+    let content_type = form
+        .file
+        .content_type()
+        .map(ContentType::to_string)
+        .unwrap_or_else(|| ContentType::Binary.to_string());
+
+    let unreadable =
+        |err: std::io::Error| AppError::Internal(format!("Could not read the upload: {}", err));
+    let mut bytes = Vec::with_capacity(form.file.len() as usize);
+    let reader = form.file.open().await.map_err(unreadable)?;
+    tokio::pin!(reader);
+    reader.read_to_end(&mut bytes).await.map_err(unreadable)?;
+
+    let result = deadline
+        .run(private::upload_attachment(
+            question_uuid,
+            answer_uuid,
+            filename,
+            content_type,
+            bytes,
+            &state.live_config.load(),
+            state.attachments.as_ref(),
+        ))
+        .await?;
+
+    Ok(Json(result.into()))
+}
+
+// Announces a file the client then PUTs to `upload_url` itself, for stores that
+// pre-sign uploads.
+#[post("/question/<question_uuid>/attachments", data = "<request>", rank = 2)]
+pub async fn create_attachment_upload(
+    question_uuid: Result<QuestionUuid, uuid::Error>,
+    _content: JsonContent,
+    request: LimitedJson<CreateAttachmentRequest>,
+    state: &State<AppState>,
+    deadline: Deadline<'_>,
+) -> Result<Json<AttachmentUploadResponse>, AppError> {
+    let request = request.0;
+    let (attachment, upload_url) = deadline
+        .run(private::create_attachment_upload(
+            question_uuid?,
+            request.answer_uuid,
+            request.filename,
+            request.content_type,
+            request.size_bytes,
+            &state.live_config.load(),
+            state.attachments.as_ref(),
+        ))
+        .await?;
+
+    Ok(Json(AttachmentUploadResponse {
+        attachment: attachment.into(),
+        upload_url,
+    }))
+}
+
+#[post("/attachments/<attachment_uuid>/complete")]
+pub async fn complete_attachment(
+    attachment_uuid: Result<AttachmentUuid, uuid::Error>,
+    state: &State<AppState>,
+    deadline: Deadline<'_>,
+) -> Result<Json<AttachmentResponse>, AppError> {
+    let result = deadline
+        .run(private::complete_attachment(
+            attachment_uuid?,
+            &state.live_config.load(),
+            state.attachments.as_ref(),
+        ))
+        .await?;
+
+    Ok(Json(result.into()))
+}
+
+#[derive(Responder)]
+pub enum AttachmentDownload {
+    // A pre-signed URL of the store, so the bytes do not pass through the API.
+    Redirect(Redirect),
+    Bytes(Vec<u8>, ContentType, Header<'static>, Header<'static>),
+}
+
+#[get("/attachments/<attachment_uuid>")]
+pub async fn download_attachment(
+    attachment_uuid: Result<AttachmentUuid, uuid::Error>,
+    state: &State<AppState>,
+    deadline: Deadline<'_>,
+) -> Result<AttachmentDownload, AppError> {
+    let attachment = deadline
+        .run(private::get_attachment(
+            attachment_uuid?,
+            state.attachments.as_ref(),
+        ))
+        .await?;
+
+    // Always as a download, so an uploaded page is never rendered on this origin.
+    download(
+        &attachment.storage_key,
+        &attachment.content_type,
+        format!("attachment; {}", filename_params(&attachment.filename)),
+        state,
+        &deadline,
+    )
+    .await
+}
+
+// Thumbnails are made by the API, so they can be shown inline.
+#[get("/attachments/<attachment_uuid>/variants/<name>")]
+pub async fn download_variant(
+    attachment_uuid: Result<AttachmentUuid, uuid::Error>,
+    name: &str,
+    state: &State<AppState>,
+    deadline: Deadline<'_>,
+) -> Result<AttachmentDownload, AppError> {
+    let variant = deadline
+        .run(private::get_attachment_variant(
+            attachment_uuid?,
+            name,
+            state.attachments.as_ref(),
+        ))
+        .await?;
+
+    download(
+        &variant.storage_key,
+        &variant.content_type,
+        format!(
+            "inline; {}",
+            filename_params(&format!("{}.png", variant.name))
+        ),
+        state,
+        &deadline,
+    )
+    .await
+}
+
+async fn download(
+    storage_key: &str,
+    content_type: &str,
+    disposition: String,
+    state: &AppState,
+    deadline: &Deadline<'_>,
+) -> Result<AttachmentDownload, AppError> {
+    let download_url = state.attachments.as_ref().and_then(|attachments| {
+        attachments
+            .object_store
+            .download_url(storage_key, state.live_config.load().attachment_url_ttl)
+    });
+    if let Some(download_url) = download_url {
+        return Ok(AttachmentDownload::Redirect(Redirect::temporary(
+            download_url,
+        )));
+    }
+
+    let bytes = deadline
+        .run(private::read_object(
+            storage_key,
+            state.attachments.as_ref(),
+        ))
+        .await?;
+    Ok(AttachmentDownload::Bytes(
+        bytes,
+        ContentType::parse_flexible(content_type).unwrap_or(ContentType::Binary),
+        Header::new("Content-Disposition", disposition),
+        Header::new("X-Content-Type-Options", "nosniff"),
+    ))
+}
+
+// An ASCII `filename` for old clients and the exact name in `filename*` (RFC 6266).
+fn filename_params(filename: &str) -> String {
+    let fallback: String = filename
+        .chars()
+        .map(|c| match c {
+            ' '..='~' if c != '"' && c != '\\' => c,
+            _ => '_',
+        })
+        .collect();
+    let encoded: String = filename
+        .bytes()
+        .map(|byte| match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'.' | b'-' | b'_' | b'~' => {
+                (byte as char).to_string()
+            }
+            _ => format!("%{:02X}", byte),
+        })
+        .collect();
+    format!("filename=\"{}\"; filename*=UTF-8''{}", fallback, encoded)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn filename_params_should_quote_and_encode_the_name() {
+        assert_eq!(
+            filename_params("résumé \"v2\".pdf"),
+            "filename=\"r_sum_ _v2_.pdf\"; filename*=UTF-8''r%C3%A9sum%C3%A9%20%22v2%22.pdf"
+        );
+    }
+}
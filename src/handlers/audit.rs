@@ -0,0 +1,44 @@
+use rocket::{serde::json::Json, State};
+
+use super::{admin::Admin, deadline::Deadline, pagination::KeysetPage, private, AppError};
+use crate::{
+    dto::Paginated,
+    models::AuditEntry,
+    persistence::audit::{AuditFilter, AUDIT_MAX_LIMIT},
+    AppState,
+};
+
+const DEFAULT_LIMIT: u32 = 50;
+
+// Newest first; follow `next_cursor`, or pass the smallest `id` seen as `before`, for
+// the next page.
+#[get("/admin/audit?<entity_uuid>&<actor>&<before>&<limit>&<cursor>")]
+#[allow(clippy::too_many_arguments)]
+pub async fn get_audit_entries(
+    _admin: Admin,
+    entity_uuid: Option<String>,
+    actor: Option<String>,
+    before: Option<i64>,
+    limit: Option<u32>,
+    cursor: Option<String>,
+    state: &State<AppState>,
+    deadline: Deadline<'_>,
+) -> Result<Json<Paginated<AuditEntry>>, AppError> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).clamp(1, AUDIT_MAX_LIMIT);
+    let page = KeysetPage::from_params(before, limit, cursor.as_deref())?;
+    let filter = AuditFilter {
+        entity_uuid,
+        actor,
+        before_id: page.before,
+        limit: page.per_page,
+    };
+
+    let result = deadline
+        .run(private::get_audit_entries(
+            filter,
+            state.audit_dao.as_deref(),
+        ))
+        .await?;
+
+    Ok(Json(page.listed(result, |entry| entry.id)))
+}
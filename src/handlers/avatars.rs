@@ -0,0 +1,151 @@
+use std::time::Duration;
+
+use rocket::{
+    data::{self, Data, FromData, ToByteUnit},
+    http::{ContentType, Header, Status},
+    response::{self, Redirect, Responder},
+    Request, Response, State,
+};
+
+use super::{
+    admin::Admin,
+    deadline::Deadline,
+    etag,
+    payload::ExceededLimit,
+    private::{self, Avatar},
+    AppError,
+};
+use crate::{fnv::Fnv1a, AppState};
+
+// Gravatar's default size.
+const DEFAULT_SIZE: u32 = 80;
+
+// The same URL serves every kind of avatar, so clients keep one per user. Uploads and
+// identicons are revalidated with their ETag once `max_age` is up.
+pub struct AvatarResponse {
+    avatar: Avatar,
+    max_age: Duration,
+}
+
+impl<'r> Responder<'r, 'static> for AvatarResponse {
+    fn respond_to(self, request: &'r Request<'_>) -> response::Result<'static> {
+        let cache_control = Header::new(
+            "Cache-Control",
+            format!("public, max-age={}", self.max_age.as_secs()),
+        );
+        let (bytes, content_type) = match self.avatar {
+            Avatar::Uploaded {
+                bytes,
+                content_type,
+            } => (
+                bytes,
+                ContentType::parse_flexible(content_type).unwrap_or(ContentType::Binary),
+            ),
+            Avatar::Identicon(svg) => (svg.into_bytes(), ContentType::SVG),
+            Avatar::Gravatar(url) => {
+                return Response::build_from(Redirect::found(url).respond_to(request)?)
+                    .header(cache_control)
+                    .ok()
+            }
+        };
+
+        let mut hash = Fnv1a::new();
+        hash.write(&bytes);
+        let etag = format!("\"{:016x}\"", hash.finish());
+        let mut response = Response::build();
+        response
+            .header(cache_control)
+            .header(Header::new("ETag", etag.clone()));
+        if etag::matches(request.headers().get_one("If-None-Match"), &etag) {
+            return response.status(Status::NotModified).ok();
+        }
+
+        response
+            .header(content_type)
+            .header(Header::new("X-Content-Type-Options", "nosniff"))
+            .sized_body(bytes.len(), std::io::Cursor::new(bytes))
+            .ok()
+    }
+}
+
+// The raw image, read up to AVATAR_BODY_LIMIT. Its type is told from its bytes, not
+// from `Content-Type`.
+pub struct AvatarImage(Vec<u8>);
+
+#[rocket::async_trait]
+impl<'r> FromData<'r> for AvatarImage {
+    type Error = String;
+
+    async fn from_data(req: &'r Request<'_>, data: Data<'r>) -> data::Outcome<'r, Self> {
+        let limit = req.limits().get("avatar").unwrap_or(1.mebibytes());
+
+        match data.open(limit).into_bytes().await {
+            Ok(bytes) if bytes.is_complete() => data::Outcome::Success(AvatarImage(bytes.value)),
+            Ok(_) => {
+                req.local_cache(|| ExceededLimit(Some(limit)));
+                data::Outcome::Error((
+                    Status::PayloadTooLarge,
+                    format!("Payload exceeds the {} limit", limit),
+                ))
+            }
+            Err(err) => data::Outcome::Error((Status::BadRequest, err.to_string())),
+        }
+    }
+}
+
+// The uploaded avatar of `email`, else a redirect to Gravatar, else its identicon.
+// `size` is passed on to Gravatar.
+#[get("/users/<email>/avatar?<size>&<gravatar>")]
+pub async fn get_avatar(
+    email: String,
+    size: Option<u32>,
+    gravatar: Option<bool>,
+    state: &State<AppState>,
+    deadline: Deadline<'_>,
+) -> Result<AvatarResponse, AppError> {
+    let config = state.live_config.load();
+    let avatar = deadline
+        .run(private::resolve_avatar(
+            email,
+            size.unwrap_or(DEFAULT_SIZE),
+            gravatar.unwrap_or(true),
+            &config,
+            state.attachments.as_ref(),
+        ))
+        .await?;
+
+    Ok(AvatarResponse {
+        avatar,
+        max_age: config.avatar_max_age,
+    })
+}
+
+// There are no user accounts to check who uploads, so only admins set avatars.
+#[put("/users/<email>/avatar", data = "<image>")]
+pub async fn set_avatar(
+    _admin: Admin,
+    email: String,
+    image: AvatarImage,
+    state: &State<AppState>,
+    deadline: Deadline<'_>,
+) -> Result<(), AppError> {
+    deadline
+        .run(private::set_avatar(
+            email,
+            image.0,
+            state.attachments.as_ref(),
+        ))
+        .await
+}
+
+#[delete("/users/<email>/avatar")]
+pub async fn delete_avatar(
+    _admin: Admin,
+    email: String,
+    state: &State<AppState>,
+    deadline: Deadline<'_>,
+) -> Result<(), AppError> {
+    deadline
+        .run(private::delete_avatar(email, state.attachments.as_ref()))
+        .await
+}
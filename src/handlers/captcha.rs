@@ -0,0 +1,79 @@
+use log::error;
+use rocket::{
+    request::{self, FromRequest},
+    Request,
+};
+
+use super::{admin::Admin, AppError};
+use crate::captcha::Captcha;
+
+// Where clients send the token their captcha widget gave them.
+pub const CAPTCHA_TOKEN_HEADER: &str = "X-Captcha-Token";
+
+// Request guard for create routes; `verify` decides whether the route needs a token.
+pub struct CaptchaToken {
+    route: Option<String>,
+    token: Option<String>,
+    client_ip: Option<String>,
+    // Callers with the admin token are trusted.
+    admin: bool,
+}
+
+impl CaptchaToken {
+    pub async fn verify(&self, captcha: Option<&Captcha>) -> Result<(), AppError> {
+        let Some(captcha) = captcha else {
+            return Ok(());
+        };
+        if self.admin
+            || !self
+                .route
+                .as_deref()
+                .is_some_and(|route| captcha.protects(route))
+        {
+            return Ok(());
+        }
+        let Some(token) = self.token.as_deref() else {
+            return Err(AppError::CaptchaRequired(format!(
+                "Solve the captcha and send its token in {}.",
+                CAPTCHA_TOKEN_HEADER
+            )));
+        };
+
+        match captcha
+            .verifier
+            .verify(token, self.client_ip.as_deref())
+            .await
+        {
+            Ok(true) => Ok(()),
+            Ok(false) => Err(AppError::CaptchaRequired(
+                "The captcha token is invalid or expired; solve the captcha again.".to_owned(),
+            )),
+            Err(err) => {
+                error!("Error on verifying a captcha token: {:?}", err);
+                Err(AppError::ServiceUnavailable(
+                    "Captcha verification is temporarily unavailable. Please try again later."
+                        .to_owned(),
+                ))
+            }
+        }
+    }
+}
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for CaptchaToken {
+    type Error = ();
+
+    async fn from_request(req: &'r Request<'_>) -> request::Outcome<Self, Self::Error> {
+        request::Outcome::Success(CaptchaToken {
+            route: req.route().map(|route| route.uri.path().to_string()),
+            token: req
+                .headers()
+                .get_one(CAPTCHA_TOKEN_HEADER)
+                .map(str::trim)
+                .filter(|token| !token.is_empty())
+                .map(str::to_owned),
+            client_ip: req.client_ip().map(|ip| ip.to_string()),
+            admin: req.guard::<Admin>().await.is_success(),
+        })
+    }
+}
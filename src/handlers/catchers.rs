@@ -0,0 +1,100 @@
+use rocket::{http::Status, Request};
+
+use super::{
+    deadline::Shed,
+    payload::{ExceededLimit, RejectedBody, UnsupportedMedia, SUPPORTED_MEDIA_TYPES},
+    ErrorBody, ErrorCode,
+};
+
+#[catch(400)]
+pub fn bad_request(req: &Request) -> ErrorBody {
+    let (code, message) = match &req.local_cache(|| RejectedBody(None)).0 {
+        Some(reason) => (
+            ErrorCode::MalformedBody,
+            format!("Request body is not valid JSON: {}", reason),
+        ),
+        None => (
+            ErrorCode::BadRequest,
+            "The request is malformed.".to_owned(),
+        ),
+    };
+
+    ErrorBody {
+        code,
+        ..ErrorBody::new(req, Status::BadRequest, message)
+    }
+}
+
+#[catch(404)]
+pub fn not_found(req: &Request) -> ErrorBody {
+    let message = format!("No route matches {} {}.", req.method(), req.uri().path());
+
+    ErrorBody::new(req, Status::NotFound, message)
+}
+
+#[catch(413)]
+pub fn payload_too_large(req: &Request) -> ErrorBody {
+    let message = match req.local_cache(|| ExceededLimit(None)).0 {
+        Some(limit) => format!("Request body exceeds the limit of {}.", limit),
+        None => "Request body is too large.".to_owned(),
+    };
+
+    ErrorBody::new(req, Status::PayloadTooLarge, message)
+}
+
+#[catch(415)]
+pub fn unsupported_media_type(req: &Request) -> ErrorBody {
+    let sent = match &req.local_cache(|| UnsupportedMedia(None)).0 {
+        Some(media_type) => format!("Content-Type {} is not supported", media_type),
+        None => "Content-Type is missing".to_owned(),
+    };
+    let message = format!(
+        "{}; send one of: {}.",
+        sent,
+        SUPPORTED_MEDIA_TYPES.join(", ")
+    );
+
+    ErrorBody::new(req, Status::UnsupportedMediaType, message)
+}
+
+// JSON bodies that parse but do not have the fields the route expects.
+#[catch(422)]
+pub fn unprocessable_entity(req: &Request) -> ErrorBody {
+    let message = match &req.local_cache(|| RejectedBody(None)).0 {
+        Some(reason) => format!(
+            "Request body does not match what this route expects: {}",
+            reason
+        ),
+        None => "The request could not be processed.".to_owned(),
+    };
+
+    ErrorBody::new(req, Status::UnprocessableEntity, message)
+}
+
+// Panics in handlers end up here; what went wrong is only logged.
+#[catch(500)]
+pub fn internal_error(req: &Request) -> ErrorBody {
+    let message = "Something went wrong on our side.".to_owned();
+
+    ErrorBody::new(req, Status::InternalServerError, message)
+}
+
+// Requests shed by the `Deadline` guard while the database is overloaded.
+#[catch(503)]
+pub fn service_unavailable(req: &Request) -> ErrorBody {
+    let message = if req.local_cache(|| Shed(false)).0 {
+        "The server is overloaded; please try again shortly.".to_owned()
+    } else {
+        Status::ServiceUnavailable.reason_lossy().to_owned()
+    };
+
+    ErrorBody::new(req, Status::ServiceUnavailable, message)
+}
+
+// Any other status a guard fails with, e.g. 401 and 403 from the admin guard.
+#[catch(default)]
+pub fn default(status: Status, req: &Request) -> ErrorBody {
+    let message = status.reason_lossy().to_owned();
+
+    ErrorBody::new(req, status, message)
+}
@@ -0,0 +1,191 @@
+use chrono::{
+    DateTime, Datelike, Duration, FixedOffset, LocalResult, NaiveDate, NaiveDateTime, NaiveTime,
+    TimeZone, Utc,
+};
+use chrono_tz::Tz;
+use rocket::request::{self, FromRequest};
+use rocket::Request;
+
+use super::{validation::FieldError, AppError};
+use crate::dto::QuestionResponse;
+
+// The caller's timezone as an `X-Timezone` header, for clients that set it once
+// instead of on every `?tz=`.
+pub struct TimezoneHeader(pub Option<String>);
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for TimezoneHeader {
+    type Error = ();
+
+    async fn from_request(req: &'r Request<'_>) -> request::Outcome<Self, Self::Error> {
+        let header = req.headers().get_one("X-Timezone").map(str::to_owned);
+        request::Outcome::Success(TimezoneHeader(header))
+    }
+}
+
+// An IANA name such as `Europe/Berlin`, from `?tz=` or else the header. None when the
+// caller sent neither, so dates stay in UTC.
+pub fn timezone(param: Option<&str>, header: &TimezoneHeader) -> Result<Option<Tz>, AppError> {
+    let (field, name) = match (param, header.0.as_deref()) {
+        (Some(name), _) => ("tz", name),
+        (None, Some(name)) => ("X-Timezone", name),
+        (None, None) => return Ok(None),
+    };
+
+    name.trim().parse().map(Some).map_err(|_| {
+        AppError::InvalidQuery(vec![FieldError {
+            field: field.to_owned(),
+            message: "must be an IANA timezone such as Europe/Berlin".to_owned(),
+        }])
+    })
+}
+
+// A bound of a date range as the instant it stands for. RFC 3339 timestamps carry their
+// own offset; dates and date-times without one, and the `today`, `yesterday`,
+// `this_week` (from Monday) and `this_month` shortcuts, which start at midnight, are
+// read in `tz`.
+pub fn bound(
+    field: &str,
+    value: &str,
+    tz: Tz,
+    now: DateTime<Utc>,
+) -> Result<DateTime<Utc>, FieldError> {
+    if let Ok(at) = DateTime::parse_from_rfc3339(value) {
+        return Ok(at.with_timezone(&Utc));
+    }
+
+    let today = now.with_timezone(&tz).date_naive();
+    let day = match value {
+        "today" => Some(today),
+        "yesterday" => today.pred_opt(),
+        "this_week" => today.checked_sub_signed(Duration::days(
+            today.weekday().num_days_from_monday().into(),
+        )),
+        "this_month" => today.with_day(1),
+        _ => NaiveDate::parse_from_str(value, "%Y-%m-%d").ok(),
+    };
+    let local = match day {
+        Some(day) => Some(day.and_time(NaiveTime::MIN)),
+        None => NaiveDateTime::parse_from_str(value, "%Y-%m-%dT%H:%M:%S%.f").ok(),
+    };
+
+    local
+        .and_then(|local| local_instant(local, tz))
+        .ok_or_else(|| FieldError {
+            field: field.to_owned(),
+            message: "must be a date, a timestamp, today, yesterday, this_week or this_month"
+                .to_owned(),
+        })
+}
+
+// Times a DST change skips are moved past the gap; ones it repeats take the first.
+fn local_instant(local: NaiveDateTime, tz: Tz) -> Option<DateTime<Utc>> {
+    let resolved = match tz.from_local_datetime(&local) {
+        LocalResult::Single(at) | LocalResult::Ambiguous(at, _) => at,
+        LocalResult::None => tz
+            .from_local_datetime(&(local + Duration::hours(1)))
+            .earliest()?,
+    };
+    Some(resolved.with_timezone(&Utc))
+}
+
+// The same instant at the offset `tz` has then.
+pub fn in_zone(at: DateTime<FixedOffset>, tz: Tz) -> DateTime<FixedOffset> {
+    at.with_timezone(&tz).fixed_offset()
+}
+
+// Shows the timestamps of a question, and of what it includes, in `tz`.
+pub fn localize(question: &mut QuestionResponse, tz: Tz) {
+    question.created_at = in_zone(question.created_at, tz);
+    question.updated_at = in_zone(question.updated_at, tz);
+    question.last_activity_at = in_zone(question.last_activity_at, tz);
+
+    for answer in question.answers.iter_mut().flatten() {
+        answer.created_at = in_zone(answer.created_at, tz);
+        answer.updated_at = in_zone(answer.updated_at, tz);
+        for attachment in answer.attachments.iter_mut().flatten() {
+            attachment.created_at = in_zone(attachment.created_at, tz);
+        }
+    }
+    for attachment in question.attachments.iter_mut().flatten() {
+        attachment.created_at = in_zone(attachment.created_at, tz);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bounds_should_start_at_midnight_in_the_timezone() {
+        // A Wednesday, already Thursday in Tokyo.
+        let now = Utc.with_ymd_and_hms(2023, 10, 4, 20, 0, 0).unwrap();
+        let berlin = chrono_tz::Europe::Berlin;
+        let tokyo = chrono_tz::Asia::Tokyo;
+        let bound = |value, tz| bound("created_after", value, tz, now).unwrap();
+
+        assert_eq!(
+            bound("today", berlin),
+            Utc.with_ymd_and_hms(2023, 10, 3, 22, 0, 0).unwrap()
+        );
+        assert_eq!(
+            bound("today", tokyo),
+            Utc.with_ymd_and_hms(2023, 10, 4, 15, 0, 0).unwrap()
+        );
+        assert_eq!(
+            bound("yesterday", berlin),
+            Utc.with_ymd_and_hms(2023, 10, 2, 22, 0, 0).unwrap()
+        );
+        assert_eq!(
+            bound("this_week", berlin),
+            Utc.with_ymd_and_hms(2023, 10, 1, 22, 0, 0).unwrap()
+        );
+        assert_eq!(
+            bound("this_month", tokyo),
+            Utc.with_ymd_and_hms(2023, 9, 30, 15, 0, 0).unwrap()
+        );
+        assert_eq!(
+            bound("2023-01-15", berlin),
+            Utc.with_ymd_and_hms(2023, 1, 14, 23, 0, 0).unwrap()
+        );
+        assert_eq!(
+            bound("2023-01-15T09:30:00", berlin),
+            Utc.with_ymd_and_hms(2023, 1, 15, 8, 30, 0).unwrap()
+        );
+        assert_eq!(
+            bound("2023-01-15T09:30:00Z", tokyo),
+            Utc.with_ymd_and_hms(2023, 1, 15, 9, 30, 0).unwrap()
+        );
+        assert!(super::bound("created_after", "tomorrow", berlin, now).is_err());
+    }
+
+    #[test]
+    fn skipped_local_times_should_move_past_the_gap() {
+        // Clocks in Berlin jumped from 02:00 to 03:00 that night.
+        let instant = local_instant(
+            NaiveDateTime::parse_from_str("2023-03-26T02:30:00", "%Y-%m-%dT%H:%M:%S").unwrap(),
+            chrono_tz::Europe::Berlin,
+        );
+        assert_eq!(
+            instant,
+            Some(Utc.with_ymd_and_hms(2023, 3, 26, 1, 30, 0).unwrap())
+        );
+    }
+
+    #[test]
+    fn instants_should_take_the_offset_of_their_date() {
+        let berlin = chrono_tz::Europe::Berlin;
+
+        let summer = Utc.with_ymd_and_hms(2023, 7, 1, 12, 0, 0).unwrap();
+        let winter = Utc.with_ymd_and_hms(2023, 12, 1, 12, 0, 0).unwrap();
+
+        assert_eq!(
+            in_zone(summer.fixed_offset(), berlin).offset(),
+            &FixedOffset::east_opt(2 * 3600).unwrap()
+        );
+        assert_eq!(
+            in_zone(winter.fixed_offset(), berlin).offset(),
+            &FixedOffset::east_opt(3600).unwrap()
+        );
+    }
+}
@@ -0,0 +1,135 @@
+use std::{future::Future, time::Duration};
+
+use log::warn;
+use rocket::http::Status;
+use rocket::request::{self, FromRequest};
+use rocket::Request;
+
+use crate::{actor::Actor, persistence::shedding, request_id::RequestId, AppState};
+
+use super::AppError;
+
+// Bounds the time a handler may spend on its work. Dropping the future on
+// timeout cancels the in-flight query and returns its connection to the pool.
+// The work runs as the request's `Actor`, which the audit log records. Requests arriving
+// while the connection pool is saturated are turned away with 503 before doing any work.
+pub struct Deadline<'r> {
+    timeout: Duration,
+    request_id: &'r RequestId,
+    actor: Actor,
+}
+
+impl<'r> Deadline<'r> {
+    // For routes with a tighter budget than REQUEST_TIMEOUT_SECS.
+    pub fn at_most(self, timeout: Duration) -> Self {
+        Deadline {
+            timeout: self.timeout.min(timeout),
+            ..self
+        }
+    }
+
+    pub async fn run<T, F>(&self, work: F) -> Result<T, AppError>
+    where
+        F: Future<Output = Result<T, AppError>>,
+    {
+        match tokio::time::timeout(self.timeout, self.actor.clone().scope(work)).await {
+            Ok(result) => result,
+            Err(_) => {
+                warn!(
+                    "Request {} timed out after {:?}",
+                    self.request_id, self.timeout
+                );
+                Err(AppError::GatewayTimeout(format!(
+                    "Request {} timed out.",
+                    self.request_id
+                )))
+            }
+        }
+    }
+}
+
+// Set on requests the guard shed, for the 503 catcher to explain.
+pub struct Shed(pub bool);
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for Deadline<'r> {
+    type Error = ();
+
+    async fn from_request(req: &'r Request<'_>) -> request::Outcome<Self, Self::Error> {
+        if shedding::should_shed() {
+            req.local_cache(|| Shed(true));
+            return request::Outcome::Error((Status::ServiceUnavailable, ()));
+        }
+        let timeout = req
+            .rocket()
+            .state::<AppState>()
+            .map(|state| state.live_config.load().request_timeout)
+            .unwrap_or(Duration::from_secs(10));
+        let request_id = req.guard::<&RequestId>().await.unwrap();
+        let actor = req.guard::<Actor>().await.unwrap();
+
+        request::Outcome::Success(Deadline {
+            timeout,
+            request_id,
+            actor,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn deadline(request_id: &RequestId) -> Deadline<'_> {
+        Deadline {
+            timeout: Duration::from_millis(20),
+            request_id,
+            actor: Actor::system(),
+        }
+    }
+
+    #[tokio::test]
+    async fn run_should_return_result_within_deadline() {
+        let request_id = RequestId("id".to_owned());
+        let result = deadline(&request_id).run(async { Ok(1) }).await;
+
+        assert!(matches!(result, Ok(1)));
+    }
+
+    #[tokio::test]
+    async fn run_should_pass_handler_errors_through() {
+        let request_id = RequestId("id".to_owned());
+        let result: Result<(), _> = deadline(&request_id)
+            .run(async { Err(AppError::BadRequest("bad".to_owned())) })
+            .await;
+
+        assert!(matches!(result, Err(AppError::BadRequest(_))));
+    }
+
+    #[tokio::test]
+    async fn run_should_run_as_the_request_actor() {
+        let request_id = RequestId("id".to_owned());
+        let mut deadline = deadline(&request_id);
+        deadline.actor.name = "admin".to_owned();
+
+        let result = deadline.run(async { Ok(Actor::current().name) }).await;
+
+        assert!(matches!(result, Ok(name) if name == "admin"));
+    }
+
+    #[tokio::test]
+    async fn run_should_time_out_slow_work() {
+        let request_id = RequestId("id".to_owned());
+        let result = deadline(&request_id)
+            .run(async {
+                tokio::time::sleep(Duration::from_secs(1)).await;
+                Ok(())
+            })
+            .await;
+
+        match result {
+            Err(AppError::GatewayTimeout(message)) => assert!(message.contains("id")),
+            _ => panic!("Expected GatewayTimeout"),
+        }
+    }
+}
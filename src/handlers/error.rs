@@ -0,0 +1,291 @@
+use std::mem;
+
+use log::error;
+use rocket::{
+    http::{ContentType, Header, Status},
+    response::{self, Responder, Response},
+    serde::{json::Json, Serialize},
+    Request,
+};
+use thiserror::Error;
+
+use super::{validation::FieldError, ErrorCode};
+use crate::{
+    models::{DBError, Entity},
+    rate_limit::RateLimit,
+    request_id::RequestId,
+};
+
+// What every handler returns. DAO failures come in through `From<DBError>`, so `?` is
+// enough at each call; the status, code and message a client sees are decided here.
+#[derive(Error, Debug)]
+pub enum AppError {
+    #[error("{0}")]
+    BadRequest(String),
+    #[error("{0}")]
+    InvalidUuid(String),
+    // Request bodies whose fields fail validation; every bad field is listed.
+    #[error("{} invalid fields", .0.len())]
+    Invalid(Vec<FieldError>),
+    // Query parameters that could not be parsed or are out of range.
+    #[error("{} invalid query parameters", .0.len())]
+    InvalidQuery(Vec<FieldError>),
+    // Rows named in a request body rather than in its path, such as the question of a
+    // new answer. The request is wrong, not the resource missing.
+    #[error("{1}")]
+    UnknownReference(Entity, String),
+    #[error("{0}")]
+    PreconditionRequired(String),
+    // Answers close to existing answers to the question, under DUPLICATE_ANSWERS=reject;
+    // each close answer is listed.
+    #[error("{} similar answers", .0.len())]
+    DuplicateAnswer(Vec<FieldError>),
+    // Anonymous creates without a captcha token that verifies.
+    #[error("{0}")]
+    CaptchaRequired(String),
+    // Changes made with a token that does not verify.
+    #[error("{0}")]
+    Forbidden(String),
+    // Clients past a rate limit; the limit they hit says when they may try again.
+    #[error("{0}")]
+    RateLimited(String, RateLimit),
+    #[error("{0}")]
+    NotImplemented(String),
+    #[error("{0}")]
+    ServiceUnavailable(String),
+    #[error("{0}")]
+    GatewayTimeout(String),
+    #[error("{0}")]
+    Internal(String),
+    #[error(transparent)]
+    Database(#[from] DBError),
+}
+
+impl AppError {
+    // For DAO calls made with a UUID the request body gave.
+    pub fn referenced_in_body(self) -> Self {
+        match self {
+            AppError::Database(DBError::NotFound(entity, message)) => {
+                AppError::UnknownReference(entity, message)
+            }
+            err => err,
+        }
+    }
+
+    pub fn status(&self) -> Status {
+        match self {
+            AppError::BadRequest(_)
+            | AppError::InvalidUuid(_)
+            | AppError::InvalidQuery(_)
+            | AppError::UnknownReference(..) => Status::BadRequest,
+            AppError::Invalid(_) => Status::UnprocessableEntity,
+            AppError::PreconditionRequired(_) => Status::PreconditionRequired,
+            AppError::DuplicateAnswer(_) => Status::Conflict,
+            AppError::CaptchaRequired(_) | AppError::Forbidden(_) => Status::Forbidden,
+            AppError::RateLimited(..) => Status::TooManyRequests,
+            AppError::NotImplemented(_) => Status::NotImplemented,
+            AppError::ServiceUnavailable(_) => Status::ServiceUnavailable,
+            AppError::GatewayTimeout(_) => Status::GatewayTimeout,
+            AppError::Internal(_) => Status::InternalServerError,
+            AppError::Database(DBError::InvalidUUID(_)) => Status::BadRequest,
+            AppError::Database(DBError::NotFound(..)) => Status::NotFound,
+            AppError::Database(DBError::Conflict(_)) => Status::Conflict,
+            AppError::Database(DBError::Unavailable) => Status::ServiceUnavailable,
+            AppError::Database(DBError::Other(_)) => Status::InternalServerError,
+        }
+    }
+
+    pub fn code(&self) -> ErrorCode {
+        match self {
+            AppError::BadRequest(_) => ErrorCode::BadRequest,
+            AppError::InvalidUuid(_) | AppError::Database(DBError::InvalidUUID(_)) => {
+                ErrorCode::InvalidUuid
+            }
+            AppError::Invalid(_) => ErrorCode::InvalidFields,
+            AppError::InvalidQuery(_) => ErrorCode::InvalidQuery,
+            AppError::PreconditionRequired(_) => ErrorCode::VersionRequired,
+            AppError::DuplicateAnswer(_) => ErrorCode::DuplicateAnswer,
+            AppError::CaptchaRequired(_) => ErrorCode::CaptchaRequired,
+            AppError::Forbidden(_) => ErrorCode::Forbidden,
+            AppError::RateLimited(..) => ErrorCode::RateLimited,
+            AppError::NotImplemented(_) => ErrorCode::NotEnabled,
+            AppError::ServiceUnavailable(_) => ErrorCode::ServiceUnavailable,
+            AppError::GatewayTimeout(_) => ErrorCode::Timeout,
+            AppError::Internal(_) | AppError::Database(DBError::Other(_)) => {
+                ErrorCode::InternalError
+            }
+            AppError::Database(DBError::NotFound(Entity::Question, _))
+            | AppError::UnknownReference(Entity::Question, _) => ErrorCode::QuestionNotFound,
+            AppError::Database(DBError::NotFound(Entity::Answer, _))
+            | AppError::UnknownReference(Entity::Answer, _) => ErrorCode::AnswerNotFound,
+            AppError::Database(DBError::NotFound(Entity::Attachment, _))
+            | AppError::UnknownReference(Entity::Attachment, _) => ErrorCode::AttachmentNotFound,
+            AppError::Database(DBError::NotFound(Entity::ModerationItem, _))
+            | AppError::UnknownReference(Entity::ModerationItem, _) => {
+                ErrorCode::ModerationItemNotFound
+            }
+            AppError::Database(DBError::NotFound(Entity::RestHook, _))
+            | AppError::UnknownReference(Entity::RestHook, _) => ErrorCode::HookNotFound,
+            AppError::Database(DBError::NotFound(Entity::User, _))
+            | AppError::UnknownReference(Entity::User, _) => ErrorCode::UserNotFound,
+            AppError::Database(DBError::NotFound(Entity::Translation, _))
+            | AppError::UnknownReference(Entity::Translation, _) => ErrorCode::TranslationNotFound,
+            AppError::Database(DBError::Conflict(_)) => ErrorCode::Conflict,
+            AppError::Database(DBError::Unavailable) => ErrorCode::DatabaseUnavailable,
+        }
+    }
+
+    // What the client is told. Database details stay in the log.
+    pub fn message(&self) -> String {
+        match self {
+            AppError::Invalid(_) => "The request has invalid fields, see errors.".to_owned(),
+            AppError::InvalidQuery(_) => "The query has invalid parameters, see errors.".to_owned(),
+            AppError::DuplicateAnswer(_) => {
+                "The question already has similar answers, see errors. Send ?allow_duplicate=true to post it anyway.".to_owned()
+            }
+            AppError::Database(
+                DBError::InvalidUUID(message)
+                | DBError::NotFound(_, message)
+                | DBError::Conflict(message),
+            ) => message.clone(),
+            AppError::Database(DBError::Unavailable) => {
+                "Service temporarily unavailable. Please try again later.".to_owned()
+            }
+            AppError::Database(DBError::Other(_)) => {
+                "Something went wrong! Please try again.".to_owned()
+            }
+            err => err.to_string(),
+        }
+    }
+}
+
+impl From<uuid::Error> for AppError {
+    fn from(err: uuid::Error) -> Self {
+        AppError::InvalidUuid(format!("Malformed UUID: {}", err))
+    }
+}
+
+// Errors are answered with the same `ErrorBody` as the catchers.
+impl<'r> Responder<'r, 'static> for AppError {
+    fn respond_to(mut self, req: &'r Request<'_>) -> response::Result<'static> {
+        let status = self.status();
+        let mut body = ErrorBody::new(req, status, self.message());
+        body.code = self.code();
+        if status.class().is_server_error() {
+            error!("Request {} failed: {:?}", body.request_id, self);
+        }
+        if let AppError::Invalid(errors)
+        | AppError::InvalidQuery(errors)
+        | AppError::DuplicateAnswer(errors) = &mut self
+        {
+            body.errors = mem::take(errors);
+        }
+        let retry_after = match &self {
+            AppError::RateLimited(_, limit) => {
+                body.rate_limit = Some(RateLimitFields {
+                    limit: limit.limit,
+                    window_secs: limit.window.as_secs(),
+                    retry_after_secs: limit.reset_secs(),
+                });
+                Some(limit.reset_secs())
+            }
+            _ => None,
+        };
+
+        let mut response = body.respond_to(req)?;
+        if let Some(secs) = retry_after {
+            response.set_header(Header::new("Retry-After", secs.to_string()));
+        }
+        Ok(response)
+    }
+}
+
+// An RFC 7807 problem document. There are no pages describing each problem, so `type`
+// is `about:blank` and `title` the status's reason; `code` tells problems apart.
+#[derive(Serialize)]
+#[cfg_attr(feature = "camel-case", serde(rename_all = "camelCase"))]
+pub struct ErrorBody {
+    #[serde(rename = "type")]
+    pub problem_type: &'static str,
+    pub title: &'static str,
+    pub status: u16,
+    pub detail: String,
+    // The path the request was made to.
+    pub instance: String,
+    pub code: ErrorCode,
+    // The `X-Request-Id` the response carries, to find the request in the logs.
+    pub request_id: String,
+    // Each rejected field of the request body or query, on 422s and 400s from validation,
+    // or the similar answers on a 409 for a duplicate answer.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub errors: Vec<FieldError>,
+    // The limit that was hit, on 429s.
+    #[serde(flatten)]
+    pub rate_limit: Option<RateLimitFields>,
+}
+
+#[derive(Serialize)]
+#[cfg_attr(feature = "camel-case", serde(rename_all = "camelCase"))]
+pub struct RateLimitFields {
+    // Requests allowed per window of `window_secs`.
+    pub limit: u32,
+    pub window_secs: u64,
+    // The same as the `Retry-After` header.
+    pub retry_after_secs: u64,
+}
+
+impl ErrorBody {
+    pub fn new(req: &Request<'_>, status: Status, detail: String) -> Self {
+        Self {
+            problem_type: "about:blank",
+            title: status.reason_lossy(),
+            status: status.code,
+            detail,
+            instance: req.uri().path().to_string(),
+            code: ErrorCode::for_status(status),
+            request_id: req.local_cache(RequestId::generate).0.clone(),
+            errors: vec![],
+            rate_limit: None,
+        }
+    }
+}
+
+// Served as `application/problem+json` with the status the body names.
+impl<'r> Responder<'r, 'static> for ErrorBody {
+    fn respond_to(self, req: &'r Request<'_>) -> response::Result<'static> {
+        let status = Status::new(self.status);
+        Response::build_from(Json(self).respond_to(req)?)
+            .status(status)
+            .header(ContentType::new("application", "problem+json"))
+            .ok()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn database_errors_should_map_by_kind() {
+        let unknown = AppError::from(DBError::NotFound(
+            Entity::Answer,
+            "Answer abc does not exist".to_owned(),
+        ));
+        assert_eq!(unknown.status(), Status::NotFound);
+        assert_eq!(unknown.code(), ErrorCode::AnswerNotFound);
+        assert_eq!(unknown.message(), "Answer abc does not exist");
+
+        let referenced = unknown.referenced_in_body();
+        assert_eq!(referenced.status(), Status::BadRequest);
+        assert_eq!(referenced.code(), ErrorCode::AnswerNotFound);
+        assert_eq!(referenced.message(), "Answer abc does not exist");
+
+        let unavailable = AppError::from(DBError::Unavailable);
+        assert_eq!(unavailable.status(), Status::ServiceUnavailable);
+        assert_eq!(unavailable.code(), ErrorCode::DatabaseUnavailable);
+
+        let other = AppError::from(DBError::Other("connection reset".into()));
+        assert_eq!(other.status(), Status::InternalServerError);
+        assert!(!other.message().contains("connection reset"));
+    }
+}
@@ -0,0 +1,226 @@
+use rocket::{
+    http::{Header, Status},
+    request::{self, FromRequest},
+    response::{self, Responder},
+    serde::{json::Json, Serialize},
+    Request, Response,
+};
+
+use crate::{
+    dto::{AnswerResponse, AttachmentResponse, Paginated, QuestionResponse},
+    fnv::Fnv1a,
+};
+
+// What makes a row look different to a client; changes whenever the row is edited.
+pub trait Versioned {
+    fn version(&self) -> String;
+}
+
+impl Versioned for QuestionResponse {
+    fn version(&self) -> String {
+        // Answering or editing an answer moves last_activity_at without touching the row.
+        let mut version = format!(
+            "{}/{}/{}/{}",
+            self.question_uuid, self.updated_at, self.answer_count, self.last_activity_at
+        );
+        // Editing an included answer leaves its question as it was.
+        for answer in self.answers.iter().flatten() {
+            version.push(';');
+            version.push_str(&answer.version());
+        }
+        push_attachments(&mut version, &self.attachments);
+        version
+    }
+}
+
+impl Versioned for AnswerResponse {
+    fn version(&self) -> String {
+        let mut version = format!("{}/{}", self.answer_uuid, self.updated_at);
+        push_attachments(&mut version, &self.attachments);
+        version
+    }
+}
+
+// Attachments never change once listed, so which ones there are is enough.
+fn push_attachments(version: &mut String, attachments: &Option<Vec<AttachmentResponse>>) {
+    for attachment in attachments.iter().flatten() {
+        version.push('+');
+        version.push_str(&attachment.attachment_uuid.to_string());
+    }
+}
+
+// A page of a JSON list sent with a weak ETag. Requests whose If-None-Match still
+// matches get a 304 without a body.
+pub struct Tagged<T> {
+    etag: String,
+    value: Paginated<T>,
+}
+
+impl<T: Versioned> Tagged<T> {
+    pub fn new(value: Paginated<T>) -> Self {
+        let mut hash = Fnv1a::new();
+        for item in &value.items {
+            hash.write(item.version().as_bytes());
+            hash.write(b"\n");
+        }
+        // Rows added or removed on other pages change the total.
+        hash.write(format!("{:?}", value.total).as_bytes());
+
+        Self {
+            etag: format!("W/\"{:016x}\"", hash.finish()),
+            value,
+        }
+    }
+}
+
+impl<'r, T: Serialize> Responder<'r, 'static> for Tagged<T> {
+    fn respond_to(self, request: &'r Request<'_>) -> response::Result<'static> {
+        let etag = Header::new("ETag", self.etag.clone());
+
+        if matches(request.headers().get_one("If-None-Match"), &self.etag) {
+            return Response::build()
+                .status(Status::NotModified)
+                .header(etag)
+                .ok();
+        }
+
+        Response::build_from(Json(self.value).respond_to(request)?)
+            .header(etag)
+            .ok()
+    }
+}
+
+pub fn matches(if_none_match: Option<&str>, etag: &str) -> bool {
+    let Some(if_none_match) = if_none_match else {
+        return false;
+    };
+
+    // If-None-Match uses the weak comparison, so the W/ prefix is ignored on both sides.
+    let opaque = |tag: &str| tag.trim().trim_start_matches("W/").to_owned();
+    if_none_match
+        .split(',')
+        .any(|tag| tag.trim() == "*" || opaque(tag) == opaque(etag))
+}
+
+// The version an update was made against, sent as `If-Match: "<version>"`.
+pub struct IfMatch(pub Option<i64>);
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for IfMatch {
+    type Error = ();
+
+    async fn from_request(req: &'r Request<'_>) -> request::Outcome<Self, Self::Error> {
+        match req.headers().get_one("If-Match").map(parse_version) {
+            None => request::Outcome::Success(IfMatch(None)),
+            Some(Some(version)) => request::Outcome::Success(IfMatch(Some(version))),
+            Some(None) => request::Outcome::Error((Status::BadRequest, ())),
+        }
+    }
+}
+
+// If-Match uses the strong comparison, so weak tags never match a version.
+fn parse_version(if_match: &str) -> Option<i64> {
+    let if_match = if_match.trim();
+    if_match
+        .strip_prefix('"')
+        .and_then(|tag| tag.strip_suffix('"'))
+        .unwrap_or(if_match)
+        .parse()
+        .ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{AnswerUuid, QuestionUuid};
+    use chrono::{DateTime, TimeZone, Utc};
+    use uuid::Uuid;
+
+    fn answer(updated_at: DateTime<Utc>) -> AnswerResponse {
+        AnswerResponse {
+            answer_uuid: AnswerUuid(Uuid::nil()),
+            question_uuid: QuestionUuid(Uuid::nil()),
+            content: "content".to_owned(),
+            created_at: DateTime::UNIX_EPOCH.fixed_offset(),
+            updated_at: updated_at.fixed_offset(),
+            version: 1,
+            attachments: None,
+            similar_answers: None,
+        }
+    }
+
+    fn page(items: Vec<AnswerResponse>, total: u64) -> Paginated<AnswerResponse> {
+        Paginated {
+            items,
+            page: 1,
+            per_page: 1,
+            total: Some(total),
+            next_cursor: None,
+        }
+    }
+
+    #[test]
+    fn etag_should_change_when_a_row_is_edited() {
+        let before = Tagged::new(page(
+            vec![answer(Utc.with_ymd_and_hms(2023, 10, 1, 10, 0, 0).unwrap())],
+            1,
+        ))
+        .etag;
+
+        assert_eq!(
+            Tagged::new(page(
+                vec![answer(Utc.with_ymd_and_hms(2023, 10, 1, 10, 0, 0).unwrap())],
+                1
+            ))
+            .etag,
+            before
+        );
+        assert_ne!(
+            Tagged::new(page(
+                vec![answer(Utc.with_ymd_and_hms(2023, 10, 1, 11, 0, 0).unwrap())],
+                1
+            ))
+            .etag,
+            before
+        );
+        assert_ne!(Tagged::new(page(vec![], 0)).etag, before);
+    }
+
+    #[test]
+    fn etag_should_change_when_other_pages_do() {
+        let before = Tagged::new(page(
+            vec![answer(Utc.with_ymd_and_hms(2023, 10, 1, 10, 0, 0).unwrap())],
+            1,
+        ))
+        .etag;
+
+        assert_ne!(
+            Tagged::new(page(
+                vec![answer(Utc.with_ymd_and_hms(2023, 10, 1, 10, 0, 0).unwrap())],
+                2
+            ))
+            .etag,
+            before
+        );
+    }
+
+    #[test]
+    fn matches_should_use_weak_comparison() {
+        let etag = "W/\"abc\"";
+
+        assert!(matches(Some("W/\"abc\""), etag));
+        assert!(matches(Some("\"abc\""), etag));
+        assert!(matches(Some("\"other\", W/\"abc\""), etag));
+        assert!(matches(Some("*"), etag));
+        assert!(!matches(Some("W/\"other\""), etag));
+        assert!(!matches(None, etag));
+    }
+
+    #[test]
+    fn parse_version_should_accept_quoted_and_bare_versions() {
+        assert_eq!(parse_version("\"3\""), Some(3));
+        assert_eq!(parse_version(" 3 "), Some(3));
+        assert_eq!(parse_version("W/\"3\""), None);
+        assert_eq!(parse_version("*"), None);
+    }
+}
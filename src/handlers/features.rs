@@ -0,0 +1,18 @@
+use rocket::{serde::json::Json, State};
+
+use crate::AppState;
+
+// Lets clients toggle UI for features enabled on this deployment.
+#[get("/features")]
+pub fn get_features(state: &State<AppState>) -> Json<Vec<String>> {
+    let mut features: Vec<String> = state
+        .live_config
+        .load()
+        .feature_flags
+        .iter()
+        .cloned()
+        .collect();
+    features.sort();
+
+    Json(features)
+}
@@ -0,0 +1,70 @@
+use rocket::{serde::json::Json, State};
+
+use super::{
+    admin::Admin,
+    deadline::Deadline,
+    payload::{JsonContent, LimitedJson},
+    private, AppError,
+};
+use crate::{
+    dto::{CreateRestHookRequest, RestHookResponse},
+    events::OutboxEvent,
+    models::HookUuid,
+    AppState,
+};
+
+// REST hooks as Zapier and Make subscribe them: events of the type are POSTed to
+// `target_url` until the hook is deleted, or the target answers 410.
+#[post("/hooks", data = "<request>")]
+pub async fn subscribe(
+    _admin: Admin,
+    _content: JsonContent,
+    request: LimitedJson<CreateRestHookRequest>,
+    state: &State<AppState>,
+    deadline: Deadline<'_>,
+) -> Result<Json<RestHookResponse>, AppError> {
+    let request = request.0;
+    let hook = deadline
+        .run(private::create_rest_hook(
+            request.target_url,
+            request.event,
+            state.rest_hook_dao.as_deref(),
+        ))
+        .await?;
+
+    Ok(Json(hook.into()))
+}
+
+#[delete("/hooks/<hook_uuid>")]
+pub async fn unsubscribe(
+    _admin: Admin,
+    hook_uuid: Result<HookUuid, uuid::Error>,
+    state: &State<AppState>,
+    deadline: Deadline<'_>,
+) -> Result<(), AppError> {
+    deadline
+        .run(private::delete_rest_hook(
+            hook_uuid?,
+            state.rest_hook_dao.as_deref(),
+        ))
+        .await
+}
+
+// Shaped like what the hooks are sent, newest first, for mapping fields while a zap or
+// scenario is set up.
+#[get("/hooks/samples/<event>")]
+pub async fn samples(
+    _admin: Admin,
+    event: &str,
+    state: &State<AppState>,
+    deadline: Deadline<'_>,
+) -> Result<Json<Vec<OutboxEvent>>, AppError> {
+    let result = deadline
+        .run(private::sample_events(
+            event,
+            state.rest_hook_dao.as_deref(),
+        ))
+        .await?;
+
+    Ok(Json(result))
+}
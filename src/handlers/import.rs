@@ -0,0 +1,27 @@
+use rocket::{serde::json::Json, State};
+
+use super::{
+    admin::Admin,
+    deadline::Deadline,
+    payload::{JsonContent, LimitedJson},
+    private, AppError,
+};
+use crate::{
+    models::{Import, ImportSummary},
+    AppState,
+};
+
+#[post("/admin/import", data = "<import>")]
+pub async fn import(
+    _admin: Admin,
+    _content: JsonContent,
+    import: LimitedJson<Import>,
+    state: &State<AppState>,
+    deadline: Deadline<'_>,
+) -> Result<Json<ImportSummary>, AppError> {
+    let result = deadline
+        .run(private::import(import.0, state.import_dao.as_ref()))
+        .await?;
+
+    Ok(Json(result))
+}
@@ -0,0 +1,26 @@
+use rocket::{serde::json::Json, State};
+
+use super::{admin::Admin, deadline::Deadline, private, AppError};
+use crate::{
+    models::{MergeSummary, QuestionUuid},
+    AppState,
+};
+
+#[post("/admin/questions/<source_uuid>/merge-into/<target_uuid>")]
+pub async fn merge_question(
+    _admin: Admin,
+    source_uuid: Result<QuestionUuid, uuid::Error>,
+    target_uuid: Result<QuestionUuid, uuid::Error>,
+    state: &State<AppState>,
+    deadline: Deadline<'_>,
+) -> Result<Json<MergeSummary>, AppError> {
+    let result = deadline
+        .run(private::merge_question(
+            source_uuid?,
+            target_uuid?,
+            state.merge_dao.as_deref(),
+        ))
+        .await?;
+
+    Ok(Json(result))
+}
@@ -0,0 +1,15 @@
+use rocket::{http::ContentType, State};
+
+use crate::{metrics, persistence::replica::REPLICA_POOL, AppState};
+
+#[get("/metrics")]
+pub fn get_metrics(state: &State<AppState>) -> (ContentType, String) {
+    if let Some(pool) = &state.pool {
+        pool.record_state();
+    }
+    if let Some(read_replica) = &state.read_replica {
+        metrics::record_pool_state(REPLICA_POOL, read_replica.pool());
+    }
+
+    (ContentType::Plain, metrics::render())
+}
@@ -1,11 +1,97 @@
+use rocket::{
+    http::Status,
+    serde::{Deserialize, Serialize},
+};
+
+pub mod activity;
+pub mod admin;
 pub mod answer;
+pub mod archive;
+pub mod attachments;
+pub mod audit;
+pub mod avatars;
+mod captcha;
+pub mod catchers;
+mod dates;
+mod deadline;
+mod error;
+mod etag;
+pub mod features;
+pub mod hooks;
+pub mod import;
+pub mod merge;
+pub mod metrics;
+pub mod moderation;
+mod ndjson;
+pub mod notifications;
+mod pagination;
+mod params;
+pub mod payload;
 mod private;
+mod query;
 pub mod question;
+pub mod scim;
+pub mod stats;
+pub mod telegram;
+pub mod translation;
+pub(crate) mod validation;
+
+pub use error::{AppError, ErrorBody};
+
+// Sent as `code` in every error body. Unlike the messages these are stable, so clients
+// can branch on them; new ones may be added, existing ones are not renamed.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum ErrorCode {
+    BadRequest,
+    MalformedBody,
+    UnprocessableBody,
+    InvalidFields,
+    InvalidQuery,
+    InvalidUuid,
+    QuestionNotFound,
+    AnswerNotFound,
+    AttachmentNotFound,
+    ModerationItemNotFound,
+    HookNotFound,
+    UserNotFound,
+    TranslationNotFound,
+    RouteNotFound,
+    Conflict,
+    VersionRequired,
+    DuplicateAnswer,
+    Unauthorized,
+    Forbidden,
+    CaptchaRequired,
+    PayloadTooLarge,
+    UnsupportedMediaType,
+    RateLimited,
+    NotEnabled,
+    ServiceUnavailable,
+    DatabaseUnavailable,
+    Timeout,
+    InternalError,
+}
 
-#[derive(Responder)]
-pub enum APIError {
-    #[response(status = 400)]
-    BadRequest(String),
-    #[response(status = 500)]
-    InternalError(String),
+impl ErrorCode {
+    // For errors that only have a status, such as those of the catchers.
+    pub fn for_status(status: Status) -> Self {
+        match status.code {
+            400 => ErrorCode::BadRequest,
+            401 => ErrorCode::Unauthorized,
+            403 => ErrorCode::Forbidden,
+            404 => ErrorCode::RouteNotFound,
+            409 => ErrorCode::Conflict,
+            413 => ErrorCode::PayloadTooLarge,
+            415 => ErrorCode::UnsupportedMediaType,
+            422 => ErrorCode::UnprocessableBody,
+            428 => ErrorCode::VersionRequired,
+            429 => ErrorCode::RateLimited,
+            501 => ErrorCode::NotEnabled,
+            503 => ErrorCode::ServiceUnavailable,
+            504 => ErrorCode::Timeout,
+            _ if status.class().is_client_error() => ErrorCode::BadRequest,
+            _ => ErrorCode::InternalError,
+        }
+    }
 }
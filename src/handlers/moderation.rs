@@ -0,0 +1,208 @@
+use log::error;
+use rocket::{
+    form::Errors,
+    http::Status,
+    request::{self, FromRequest},
+    response::status,
+    serde::json::Json,
+    Either, Request, State,
+};
+
+use super::{
+    admin::Admin,
+    deadline::Deadline,
+    pagination::{PageParams, DEFAULT_PER_PAGE},
+    payload::{JsonContent, LimitedJson},
+    private, AppError,
+};
+use crate::{
+    dto::*,
+    models::{ModerationUuid, SpamContext},
+    moderation::Moderated,
+    AppState,
+};
+
+// What the create routes answer: the created resource, or 202 when it was held.
+pub type ModeratedJson<T> = Either<Json<T>, status::Accepted<Json<HeldContentResponse>>>;
+
+pub fn moderated_json<D, T: From<D>>(created: Moderated<D>) -> ModeratedJson<T> {
+    match created {
+        Moderated::Published(created) => Either::Left(Json(created.into())),
+        Moderated::Held(item) => Either::Right(status::Accepted(Json(HeldContentResponse {
+            moderation_uuid: item.moderation_uuid,
+            message: "Held for review by a moderator.".to_owned(),
+        }))),
+    }
+}
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for SpamContext {
+    type Error = ();
+
+    async fn from_request(req: &'r Request<'_>) -> request::Outcome<Self, Self::Error> {
+        let header = |name| req.headers().get_one(name).map(str::to_owned);
+
+        request::Outcome::Success(SpamContext {
+            user_ip: req.client_ip().map(|ip| ip.to_string()),
+            user_agent: header("User-Agent"),
+            referrer: header("Referer"),
+        })
+    }
+}
+
+// Oldest first.
+#[get("/moderation/queue?<pagination..>")]
+pub async fn get_queue(
+    _admin: Admin,
+    pagination: Result<PageParams, Errors<'_>>,
+    state: &State<AppState>,
+    deadline: Deadline<'_>,
+) -> Result<Json<Paginated<ModerationItemResponse>>, AppError> {
+    let page = pagination?.page(DEFAULT_PER_PAGE)?;
+    let result = deadline
+        .run(private::held_items(
+            page.window(),
+            state.moderation.as_ref(),
+        ))
+        .await?;
+
+    Ok(Json(page.listed(result).responses()))
+}
+
+#[post("/moderation/queue/<moderation_uuid>/approve")]
+pub async fn approve(
+    _admin: Admin,
+    moderation_uuid: Result<ModerationUuid, uuid::Error>,
+    state: &State<AppState>,
+    deadline: Deadline<'_>,
+) -> Result<Json<PublishedResponse>, AppError> {
+    let result = deadline
+        .run(private::approve_held(
+            moderation_uuid?,
+            state.question_dao.as_ref(),
+            state.answer_dao.as_ref(),
+            state.unit_of_work.as_ref(),
+            private::WriteHooks::of(state),
+        ))
+        .await?;
+
+    Ok(Json(result.into()))
+}
+
+// Answers 200 whatever happened to each item; see the results.
+#[post("/moderation/bulk", data = "<request>")]
+pub async fn moderate_bulk(
+    _admin: Admin,
+    _content: JsonContent,
+    request: LimitedJson<BulkModerationRequest>,
+    state: &State<AppState>,
+    deadline: Deadline<'_>,
+) -> Result<Json<Vec<BulkModerationResult>>, AppError> {
+    let request = request.0;
+    let results = deadline
+        .run(private::moderate_held(
+            request.moderation_uuids,
+            request.action,
+            state.question_dao.as_ref(),
+            state.answer_dao.as_ref(),
+            state.unit_of_work.as_ref(),
+            private::WriteHooks::of(state),
+        ))
+        .await?;
+
+    Ok(Json(
+        results
+            .into_iter()
+            .map(|(moderation_uuid, result)| match result {
+                Ok(published) => BulkModerationResult {
+                    moderation_uuid,
+                    status: Status::Ok.code,
+                    published: published.map(Into::into),
+                    code: None,
+                    message: None,
+                },
+                // The items are named in the body, so unknown ones are bad requests.
+                Err(err) => {
+                    let err = err.referenced_in_body();
+                    if err.status().class().is_server_error() {
+                        error!("Bulk moderation of {} failed: {:?}", moderation_uuid, err);
+                    }
+                    BulkModerationResult {
+                        moderation_uuid,
+                        status: err.status().code,
+                        published: None,
+                        code: Some(err.code()),
+                        message: Some(err.message()),
+                    }
+                }
+            })
+            .collect(),
+    ))
+}
+
+#[delete("/moderation/queue/<moderation_uuid>")]
+pub async fn reject(
+    _admin: Admin,
+    moderation_uuid: Result<ModerationUuid, uuid::Error>,
+    state: &State<AppState>,
+    deadline: Deadline<'_>,
+) -> Result<(), AppError> {
+    deadline
+        .run(private::reject_held(
+            moderation_uuid?,
+            state.moderation.as_ref(),
+        ))
+        .await?;
+
+    Ok(())
+}
+
+// Alphabetical.
+#[get("/moderation/words")]
+pub async fn get_filtered_words(
+    _admin: Admin,
+    state: &State<AppState>,
+    deadline: Deadline<'_>,
+) -> Result<Json<Vec<String>>, AppError> {
+    let result = deadline
+        .run(private::filtered_words(state.word_filter.as_ref()))
+        .await?;
+
+    Ok(Json(result))
+}
+
+#[put("/moderation/words/<word>")]
+pub async fn add_filtered_word(
+    _admin: Admin,
+    word: &str,
+    state: &State<AppState>,
+    deadline: Deadline<'_>,
+) -> Result<(), AppError> {
+    deadline
+        .run(private::set_filtered_word(
+            word,
+            true,
+            state.word_filter.as_ref(),
+        ))
+        .await?;
+
+    Ok(())
+}
+
+#[delete("/moderation/words/<word>")]
+pub async fn remove_filtered_word(
+    _admin: Admin,
+    word: &str,
+    state: &State<AppState>,
+    deadline: Deadline<'_>,
+) -> Result<(), AppError> {
+    deadline
+        .run(private::set_filtered_word(
+            word,
+            false,
+            state.word_filter.as_ref(),
+        ))
+        .await?;
+
+    Ok(())
+}
@@ -0,0 +1,71 @@
+use async_stream::stream;
+use futures::{StreamExt, TryStreamExt};
+use log::error;
+use rocket::{
+    http::ContentType,
+    request::{self, FromRequest},
+    response::{self, stream::TextStream, Responder},
+    serde::Serialize,
+    Request,
+};
+
+use crate::persistence::RowStream;
+
+// Lists come as a single JSON array unless `application/x-ndjson` is the
+// preferred type in Accept.
+pub struct AcceptsNdjson(pub bool);
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for AcceptsNdjson {
+    type Error = ();
+
+    async fn from_request(req: &'r Request<'_>) -> request::Outcome<Self, Self::Error> {
+        let ndjson = req.accept().is_some_and(|accept| {
+            let preferred = accept.preferred().media_type();
+            preferred.top() == "application" && preferred.sub() == "x-ndjson"
+        });
+
+        request::Outcome::Success(AcceptsNdjson(ndjson))
+    }
+}
+
+// One JSON row per line, written as rows are read, so memory stays flat however
+// long the list is. The status has gone out with the first row; a read failing
+// after that can only cut the body short.
+pub struct Ndjson<'a, T>(pub RowStream<'a, T>);
+
+impl<'a, T: Send + 'a> Ndjson<'a, T> {
+    // Sends each row as `T`, e.g. a model as its response DTO.
+    pub fn mapped<S: Send + 'a>(rows: RowStream<'a, S>) -> Self
+    where
+        T: From<S>,
+    {
+        Ndjson(rows.map_ok(T::from).boxed())
+    }
+}
+
+impl<'r, T: Serialize + Send + 'r> Responder<'r, 'r> for Ndjson<'r, T> {
+    fn respond_to(self, request: &'r Request<'_>) -> response::Result<'r> {
+        let mut rows = self.0;
+        let lines = stream! {
+            while let Some(row) = rows.next().await {
+                let line = row
+                    .map_err(|err| err.to_string())
+                    .and_then(|row| serde_json::to_string(&row).map_err(|err| err.to_string()));
+                match line {
+                    Ok(line) => yield line + "\n",
+                    Err(err) => {
+                        error!("Streamed list cut short: {}", err);
+                        break;
+                    }
+                }
+            }
+        };
+
+        (
+            ContentType::new("application", "x-ndjson"),
+            TextStream(lines),
+        )
+            .respond_to(request)
+    }
+}
@@ -0,0 +1,260 @@
+use rocket::{form::Errors, http::Status, response::content::RawHtml, serde::json::Json, State};
+use serde_json::{json, Value};
+
+use super::{
+    deadline::Deadline,
+    payload::{JsonContent, LimitedJson},
+    private, AppError,
+};
+use crate::{
+    confirmations::{Confirmation, SubscriptionChange},
+    dto::CreatePushSubscriptionRequest,
+    models::QuestionUuid,
+    notifications::render,
+    AppState,
+};
+
+const CONFIRM_PAGE: &str = include_str!("../../templates/confirm_change.html");
+const CONFIRMED_PAGE: &str = include_str!("../../templates/change_confirmed.html");
+
+// Each change to what an address is emailed takes a `token` from a link mailed to it.
+// Without one the link is mailed and 202 answered; nothing changes until it is used.
+fn confirmed(confirmation: Confirmation) -> Status {
+    match confirmation {
+        Confirmation::Made => Status::Ok,
+        Confirmation::Requested => Status::Accepted,
+    }
+}
+
+// Mails `email` about each new answer to the question.
+#[put("/question/<question_uuid>/subscriptions/<email>?<token>")]
+pub async fn subscribe(
+    question_uuid: Result<QuestionUuid, uuid::Error>,
+    email: String,
+    token: Option<String>,
+    state: &State<AppState>,
+    deadline: Deadline<'_>,
+) -> Result<Status, AppError> {
+    let change = SubscriptionChange::Subscribe(question_uuid?);
+    deadline
+        .run(private::change_subscription(
+            email,
+            change,
+            token,
+            state.notifications.as_ref(),
+        ))
+        .await
+        .map(confirmed)
+}
+
+#[delete("/question/<question_uuid>/subscriptions/<email>?<token>")]
+pub async fn unsubscribe(
+    question_uuid: Result<QuestionUuid, uuid::Error>,
+    email: String,
+    token: Option<String>,
+    state: &State<AppState>,
+    deadline: Deadline<'_>,
+) -> Result<Status, AppError> {
+    let change = SubscriptionChange::Unsubscribe(question_uuid?);
+    deadline
+        .run(private::change_subscription(
+            email,
+            change,
+            token,
+            state.notifications.as_ref(),
+        ))
+        .await
+        .map(confirmed)
+}
+
+// Stops every email to `email` until the opt-out is deleted.
+#[put("/notifications/opt-outs/<email>?<token>")]
+pub async fn opt_out(
+    email: String,
+    token: Option<String>,
+    state: &State<AppState>,
+    deadline: Deadline<'_>,
+) -> Result<Status, AppError> {
+    deadline
+        .run(private::change_subscription(
+            email,
+            SubscriptionChange::OptOut,
+            token,
+            state.notifications.as_ref(),
+        ))
+        .await
+        .map(confirmed)
+}
+
+#[delete("/notifications/opt-outs/<email>?<token>")]
+pub async fn opt_in(
+    email: String,
+    token: Option<String>,
+    state: &State<AppState>,
+    deadline: Deadline<'_>,
+) -> Result<Status, AppError> {
+    deadline
+        .run(private::change_subscription(
+            email,
+            SubscriptionChange::OptIn,
+            token,
+            state.notifications.as_ref(),
+        ))
+        .await
+        .map(confirmed)
+}
+
+// Sends a daily or weekly digest to `email` instead of, or besides, its subscriptions'
+// emails.
+#[put("/notifications/digests/<email>?<frequency>&<token>")]
+pub async fn set_digest(
+    email: String,
+    frequency: Option<String>,
+    token: Option<String>,
+    state: &State<AppState>,
+    deadline: Deadline<'_>,
+) -> Result<Status, AppError> {
+    deadline
+        .run(private::set_digest_frequency(
+            email,
+            // A missing frequency is reported like an unknown one.
+            Some(frequency.unwrap_or_default()),
+            token,
+            state.notifications.as_ref(),
+        ))
+        .await
+        .map(confirmed)
+}
+
+#[delete("/notifications/digests/<email>?<token>")]
+pub async fn stop_digest(
+    email: String,
+    token: Option<String>,
+    state: &State<AppState>,
+    deadline: Deadline<'_>,
+) -> Result<Status, AppError> {
+    deadline
+        .run(private::set_digest_frequency(
+            email,
+            None,
+            token,
+            state.notifications.as_ref(),
+        ))
+        .await
+        .map(confirmed)
+}
+
+// The query of the links in confirmation emails, as `ConfirmationTokens::link` makes it.
+#[derive(FromForm)]
+pub struct ConfirmParams {
+    email: String,
+    change: String,
+    question: Option<String>,
+    frequency: Option<String>,
+    token: String,
+}
+
+impl ConfirmParams {
+    fn change(&self) -> Result<SubscriptionChange, AppError> {
+        private::linked_change(
+            &self.change,
+            self.question.as_deref(),
+            self.frequency.as_deref(),
+        )
+    }
+}
+
+// Where emailed links lead. Mail scanners open links on their own, so this only shows
+// the change; its button posts back to make it.
+#[get("/notifications/confirm?<params..>")]
+pub fn confirm_page(
+    params: Result<ConfirmParams, Errors<'_>>,
+) -> Result<RawHtml<String>, AppError> {
+    let params = params?;
+    let change = params.change()?;
+    Ok(RawHtml(render(
+        CONFIRM_PAGE,
+        &[
+            ("change", &html_escape(&change.summary())),
+            ("email", &html_escape(&params.email)),
+        ],
+    )))
+}
+
+#[post("/notifications/confirm?<params..>")]
+pub async fn confirm(
+    params: Result<ConfirmParams, Errors<'_>>,
+    state: &State<AppState>,
+    deadline: Deadline<'_>,
+) -> Result<RawHtml<String>, AppError> {
+    let params = params?;
+    let change = params.change()?;
+    deadline
+        .run(private::change_subscription(
+            params.email.clone(),
+            change,
+            Some(params.token),
+            state.notifications.as_ref(),
+        ))
+        .await?;
+    Ok(RawHtml(render(
+        CONFIRMED_PAGE,
+        &[
+            ("change", &html_escape(&change.summary())),
+            ("email", &html_escape(&params.email)),
+        ],
+    )))
+}
+
+fn html_escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+// Pushes to the calling browser about new answers to the questions in the body. There
+// are no accounts, so the subscription itself is who "me" is; posting it again
+// replaces the questions it follows.
+#[post("/me/push-subscriptions", data = "<subscription>")]
+pub async fn save_push_subscription(
+    _content: JsonContent,
+    subscription: LimitedJson<CreatePushSubscriptionRequest>,
+    state: &State<AppState>,
+    deadline: Deadline<'_>,
+) -> Result<(), AppError> {
+    let mut subscription = subscription.0;
+    let question_uuids = std::mem::take(&mut subscription.question_uuids);
+    deadline
+        .run(private::save_push_subscription(
+            subscription.into(),
+            question_uuids,
+            state.notifications.as_ref(),
+        ))
+        .await
+}
+
+#[delete("/me/push-subscriptions?<endpoint>")]
+pub async fn delete_push_subscription(
+    endpoint: String,
+    state: &State<AppState>,
+    deadline: Deadline<'_>,
+) -> Result<(), AppError> {
+    deadline
+        .run(private::delete_push_subscription(
+            endpoint,
+            state.notifications.as_ref(),
+        ))
+        .await
+}
+
+// The `applicationServerKey` pages pass to `pushManager.subscribe()`.
+#[get("/me/push-subscriptions/vapid-public-key")]
+pub fn get_vapid_public_key(state: &State<AppState>) -> Result<Json<Value>, AppError> {
+    match &state.live_config.load().vapid_public_key {
+        Some(key) => Ok(Json(json!({ "public_key": key }))),
+        None => Err(AppError::NotImplemented(
+            "Web push needs VAPID_PUBLIC_KEY and VAPID_PRIVATE_KEY.".to_owned(),
+        )),
+    }
+}
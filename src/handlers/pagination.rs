@@ -0,0 +1,267 @@
+use rocket::form;
+
+use crate::{
+    dto::Paginated,
+    models::{Listed, Window},
+};
+
+use super::AppError;
+
+pub const DEFAULT_PER_PAGE: u32 = 50;
+pub const MAX_PER_PAGE: u32 = 200;
+
+// `?page=&per_page=`, with pages counted from 1, or `?cursor=` with the `next_cursor`
+// of the page before, which carries both.
+#[derive(FromForm, Debug, Default)]
+pub struct PageParams {
+    #[field(validate = page_bounds())]
+    pub page: Option<u32>,
+    #[field(validate = per_page_bounds())]
+    pub per_page: Option<u32>,
+    pub cursor: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Page {
+    pub number: u32,
+    pub per_page: u32,
+}
+
+impl PageParams {
+    pub fn page(&self, default_per_page: u32) -> Result<Page, AppError> {
+        Page::from_params(
+            self.page,
+            self.per_page,
+            self.cursor.as_deref(),
+            default_per_page,
+        )
+    }
+}
+
+pub fn page_bounds<'v>(page: &Option<u32>) -> form::Result<'v, ()> {
+    if *page == Some(0) {
+        Err(form::Error::validation("must be at least 1"))?;
+    }
+    Ok(())
+}
+
+pub fn per_page_bounds<'v>(per_page: &Option<u32>) -> form::Result<'v, ()> {
+    if per_page.is_some_and(|per_page| !(1..=MAX_PER_PAGE).contains(&per_page)) {
+        Err(form::Error::validation(format!(
+            "must be between 1 and {}",
+            MAX_PER_PAGE
+        )))?;
+    }
+    Ok(())
+}
+
+impl Page {
+    // The cursor wins over `page` and `per_page`, which the form has already checked.
+    pub fn from_params(
+        page: Option<u32>,
+        per_page: Option<u32>,
+        cursor: Option<&str>,
+        default_per_page: u32,
+    ) -> Result<Self, AppError> {
+        let Some(cursor) = cursor else {
+            return Ok(Page {
+                number: page.unwrap_or(1),
+                per_page: per_page.unwrap_or(default_per_page),
+            });
+        };
+
+        let page = Page::from_cursor(cursor)?;
+        if page.number == 0 || !(1..=MAX_PER_PAGE).contains(&page.per_page) {
+            return Err(AppError::BadRequest(format!(
+                "Malformed cursor: {}",
+                cursor
+            )));
+        }
+        Ok(page)
+    }
+
+    pub fn offset(&self) -> usize {
+        (self.number as usize - 1).saturating_mul(self.per_page as usize)
+    }
+
+    // How many items the pages up to this one hold.
+    pub fn end(&self) -> usize {
+        self.offset().saturating_add(self.per_page as usize)
+    }
+
+    // Opaque to clients, so endpoints can move to keyset cursors without a new field.
+    fn next_cursor(&self) -> String {
+        format!("{}:{}", self.number.saturating_add(1), self.per_page)
+    }
+
+    fn from_cursor(cursor: &str) -> Result<Self, AppError> {
+        cursor
+            .split_once(':')
+            .and_then(|(number, per_page)| {
+                Some(Page {
+                    number: number.parse().ok()?,
+                    per_page: per_page.parse().ok()?,
+                })
+            })
+            .ok_or_else(|| AppError::BadRequest(format!("Malformed cursor: {}", cursor)))
+    }
+
+    // The rows of this page, for the DAO to cut out of the list.
+    pub fn window(&self) -> Window {
+        Window {
+            offset: self.offset() as u64,
+            limit: u64::from(self.per_page),
+        }
+    }
+
+    // This page as the DAO listed it through `window`.
+    pub fn listed<T>(&self, listed: Listed<T>) -> Paginated<T> {
+        Paginated {
+            items: listed.items,
+            page: self.number,
+            per_page: self.per_page,
+            total: Some(listed.total),
+            next_cursor: ((self.end() as u64) < listed.total).then(|| self.next_cursor()),
+        }
+    }
+
+    // Cuts this page out of a list that holds at most the pages up to it, as ranked
+    // results do; whether more follow is up to the caller, and the total is unknown.
+    pub fn of_first<T>(&self, items: Vec<T>, more: bool) -> Paginated<T> {
+        Paginated {
+            items: items
+                .into_iter()
+                .skip(self.offset())
+                .take(self.per_page as usize)
+                .collect(),
+            page: self.number,
+            per_page: self.per_page,
+            total: None,
+            next_cursor: more.then(|| self.next_cursor()),
+        }
+    }
+}
+
+// A page of a list read newest first before an id, as the activity feed and the audit
+// log are. Rows added meanwhile do not shift later pages, since the cursor carries the
+// smallest id sent. A full page has a cursor even when nothing older is left.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct KeysetPage {
+    pub number: u32,
+    pub per_page: u32,
+    pub before: Option<i64>,
+}
+
+impl KeysetPage {
+    // The cursor wins over `before` and `per_page`.
+    pub fn from_params(
+        before: Option<i64>,
+        per_page: u32,
+        cursor: Option<&str>,
+    ) -> Result<Self, AppError> {
+        let Some(cursor) = cursor else {
+            return Ok(KeysetPage {
+                number: 1,
+                per_page,
+                before,
+            });
+        };
+
+        let mut parts = cursor.splitn(3, ':').map(str::parse::<i64>);
+        match (parts.next(), parts.next(), parts.next()) {
+            (Some(Ok(number)), Some(Ok(per_page)), Some(Ok(before))) if number > 0 => {
+                Ok(KeysetPage {
+                    number: u32::try_from(number).unwrap_or(u32::MAX),
+                    per_page: u32::try_from(per_page).unwrap_or(u32::MAX),
+                    before: Some(before),
+                })
+            }
+            _ => Err(AppError::BadRequest(format!(
+                "Malformed cursor: {}",
+                cursor
+            ))),
+        }
+    }
+
+    pub fn listed<T>(&self, items: Vec<T>, id: impl Fn(&T) -> i64) -> Paginated<T> {
+        let next_cursor = match items.last() {
+            Some(last) if items.len() >= self.per_page as usize => Some(format!(
+                "{}:{}:{}",
+                self.number.saturating_add(1),
+                self.per_page,
+                id(last)
+            )),
+            _ => None,
+        };
+        Paginated {
+            items,
+            page: self.number,
+            per_page: self.per_page,
+            total: None,
+            next_cursor,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pages_should_follow_their_cursors_to_the_end() {
+        let params = PageParams {
+            per_page: Some(2),
+            ..PageParams::default()
+        };
+        let page = params.page(DEFAULT_PER_PAGE).unwrap();
+        let first = page.listed(page.window().of(vec![1, 2, 3]));
+        assert_eq!(first.items, vec![1, 2]);
+        assert_eq!(first.total, Some(3));
+
+        let params = PageParams {
+            cursor: first.next_cursor,
+            ..PageParams::default()
+        };
+        let page = params.page(DEFAULT_PER_PAGE).unwrap();
+        let second = page.listed(page.window().of(vec![1, 2, 3]));
+        assert_eq!((second.page, second.per_page), (2, 2));
+        assert_eq!(second.items, vec![3]);
+        assert_eq!(second.next_cursor, None);
+    }
+
+    #[test]
+    fn bad_cursors_should_be_rejected() {
+        for cursor in ["next", "0:50", "2:0", "2:1000"] {
+            let params = PageParams {
+                cursor: Some(cursor.to_owned()),
+                ..PageParams::default()
+            };
+            let err = params.page(DEFAULT_PER_PAGE).unwrap_err();
+            assert_eq!(err.status(), rocket::http::Status::BadRequest);
+        }
+    }
+
+    #[test]
+    fn keyset_pages_should_continue_before_the_last_id() {
+        let page = KeysetPage::from_params(None, 2, None).unwrap();
+        let first = page.listed(vec![9, 7], |id| *id);
+        assert_eq!(first.next_cursor.as_deref(), Some("2:2:7"));
+
+        let page = KeysetPage::from_params(None, 20, first.next_cursor.as_deref()).unwrap();
+        assert_eq!(
+            page,
+            KeysetPage {
+                number: 2,
+                per_page: 2,
+                before: Some(7)
+            }
+        );
+        let second = page.listed(vec![4], |id| *id);
+        assert_eq!(second.page, 2);
+        assert_eq!(second.next_cursor, None);
+
+        for cursor in ["7", "0:2:7", "2:2:x"] {
+            assert!(KeysetPage::from_params(None, 2, Some(cursor)).is_err());
+        }
+    }
+}
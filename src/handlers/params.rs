@@ -0,0 +1,53 @@
+use rocket::request::FromParam;
+
+use crate::models::{AnswerUuid, AttachmentUuid, HookUuid, ModerationUuid, QuestionUuid, UserUuid};
+
+// Routes take these as `Result<_, uuid::Error>` and answer a malformed id with 400,
+// where a failed parameter would otherwise fall through to a 422.
+impl<'a> FromParam<'a> for QuestionUuid {
+    type Error = uuid::Error;
+
+    fn from_param(param: &'a str) -> Result<Self, Self::Error> {
+        param.parse()
+    }
+}
+
+impl<'a> FromParam<'a> for AnswerUuid {
+    type Error = uuid::Error;
+
+    fn from_param(param: &'a str) -> Result<Self, Self::Error> {
+        param.parse()
+    }
+}
+
+impl<'a> FromParam<'a> for AttachmentUuid {
+    type Error = uuid::Error;
+
+    fn from_param(param: &'a str) -> Result<Self, Self::Error> {
+        param.parse()
+    }
+}
+
+impl<'a> FromParam<'a> for ModerationUuid {
+    type Error = uuid::Error;
+
+    fn from_param(param: &'a str) -> Result<Self, Self::Error> {
+        param.parse()
+    }
+}
+
+impl<'a> FromParam<'a> for HookUuid {
+    type Error = uuid::Error;
+
+    fn from_param(param: &'a str) -> Result<Self, Self::Error> {
+        param.parse()
+    }
+}
+
+impl<'a> FromParam<'a> for UserUuid {
+    type Error = uuid::Error;
+
+    fn from_param(param: &'a str) -> Result<Self, Self::Error> {
+        param.parse()
+    }
+}
@@ -0,0 +1,229 @@
+use rocket::{
+    data::{self, ByteUnit, Data, FromData, Limits},
+    http::Status,
+    request::{self, FromRequest},
+    serde::DeserializeOwned,
+    Request,
+};
+
+use crate::{
+    dto::{
+        BulkModerationRequest, CreateAnswerRequest, CreateAttachmentRequest,
+        CreatePushSubscriptionRequest, CreateQuestionRequest, CreateQuestionWithAnswerRequest,
+        CreateRestHookRequest, ScimPatchRequest, ScimUserRequest, SuggestTagsRequest,
+        TranslationRequest, UpdateAnswerRequest, UpdateQuestionRequest,
+    },
+    models::Import,
+    AppState,
+};
+
+// Name of the Rocket limit applied to a payload, looked up as "json/<LIMIT>".
+pub trait BodyLimit {
+    const LIMIT: &'static str;
+}
+
+impl BodyLimit for CreateQuestionRequest {
+    const LIMIT: &'static str = "question";
+}
+
+impl BodyLimit for UpdateQuestionRequest {
+    const LIMIT: &'static str = "question";
+}
+
+impl BodyLimit for CreateQuestionWithAnswerRequest {
+    const LIMIT: &'static str = "question";
+}
+
+impl BodyLimit for TranslationRequest {
+    const LIMIT: &'static str = "question";
+}
+
+// A question that is still being written.
+impl BodyLimit for SuggestTagsRequest {
+    const LIMIT: &'static str = "question";
+}
+
+// These are never near the question limit, so they do not get one of their own.
+impl BodyLimit for CreatePushSubscriptionRequest {
+    const LIMIT: &'static str = "question";
+}
+
+impl BodyLimit for CreateRestHookRequest {
+    const LIMIT: &'static str = "question";
+}
+
+impl BodyLimit for ScimUserRequest {
+    const LIMIT: &'static str = "question";
+}
+
+impl BodyLimit for ScimPatchRequest {
+    const LIMIT: &'static str = "question";
+}
+
+impl BodyLimit for BulkModerationRequest {
+    const LIMIT: &'static str = "question";
+}
+
+// Only describes the file, which is uploaded to the store.
+impl BodyLimit for CreateAttachmentRequest {
+    const LIMIT: &'static str = "question";
+}
+
+impl BodyLimit for Import {
+    const LIMIT: &'static str = "import";
+}
+
+impl BodyLimit for CreateAnswerRequest {
+    const LIMIT: &'static str = "answer";
+}
+
+impl BodyLimit for UpdateAnswerRequest {
+    const LIMIT: &'static str = "answer";
+}
+
+// Cached on the request when a body is rejected so the 413 catcher can report it.
+pub struct ExceededLimit(pub Option<ByteUnit>);
+
+// Cached on the request when a body cannot be read or parsed, for the 400 and 422 catchers.
+pub struct RejectedBody(pub Option<String>);
+
+// Media types write routes accept bodies in, listed in the 415 error body.
+pub const SUPPORTED_MEDIA_TYPES: &[&str] = &["application/json"];
+
+// Cached on the request when its `Content-Type` is refused, for the 415 catcher.
+pub struct UnsupportedMedia(pub Option<String>);
+
+// Request guard for routes that take a body: the `Content-Type` must be one of
+// `SUPPORTED_MEDIA_TYPES`, parameters such as `charset` aside.
+pub struct JsonContent;
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for JsonContent {
+    type Error = ();
+
+    async fn from_request(req: &'r Request<'_>) -> request::Outcome<Self, Self::Error> {
+        match req.content_type() {
+            Some(content_type) if content_type.is_json() => request::Outcome::Success(JsonContent),
+            content_type => {
+                req.local_cache(|| {
+                    UnsupportedMedia(content_type.map(|ct| format!("{}/{}", ct.top(), ct.sub())))
+                });
+                request::Outcome::Error((Status::UnsupportedMediaType, ()))
+            }
+        }
+    }
+}
+
+// Like rocket's `Json<T>`, but reads the body with the per-route limit of `T`.
+pub struct LimitedJson<T>(pub T);
+
+#[rocket::async_trait]
+impl<'r, T: DeserializeOwned + BodyLimit> FromData<'r> for LimitedJson<T> {
+    type Error = String;
+
+    async fn from_data(req: &'r Request<'_>, data: Data<'r>) -> data::Outcome<'r, Self> {
+        let limit = req
+            .limits()
+            .find(["json", T::LIMIT])
+            .unwrap_or(Limits::JSON);
+
+        let body = match data.open(limit).into_string().await {
+            Ok(body) if body.is_complete() => body.into_inner(),
+            Ok(_) => {
+                req.local_cache(|| ExceededLimit(Some(limit)));
+                return data::Outcome::Error((
+                    Status::PayloadTooLarge,
+                    format!("Payload exceeds the {} limit", limit),
+                ));
+            }
+            Err(err) => return reject(req, Status::BadRequest, err.to_string()),
+        };
+
+        let strict = req
+            .rocket()
+            .state::<AppState>()
+            .is_some_and(|state| state.live_config.load().strict_json);
+
+        match parse::<T>(&body, strict) {
+            Ok((_, Some(field))) => reject(
+                req,
+                Status::UnprocessableEntity,
+                format!("unknown field `{}`", field),
+            ),
+            Ok((payload, None)) => data::Outcome::Success(LimitedJson(payload)),
+            Err(err) if err.is_data() => reject(req, Status::UnprocessableEntity, err.to_string()),
+            Err(err) => reject(req, Status::BadRequest, err.to_string()),
+        }
+    }
+}
+
+// Deserializes `body`, and when `strict` also returns the path of the first field serde skipped.
+// Optional fields that were sent, even as null, and metadata keys are not skipped.
+fn parse<T: DeserializeOwned>(body: &str, strict: bool) -> serde_json::Result<(T, Option<String>)> {
+    let mut unknown = None;
+    let mut de = serde_json::Deserializer::from_str(body);
+    let payload = serde_ignored::deserialize(&mut de, |path| {
+        if strict && unknown.is_none() {
+            unknown = Some(field_path(&path));
+        }
+    })?;
+    de.end()?;
+    Ok((payload, unknown))
+}
+
+// `questions`, `[0]` and `title` give `questions[0].title`.
+fn field_path(path: &serde_ignored::Path) -> String {
+    match path {
+        serde_ignored::Path::Root => String::new(),
+        serde_ignored::Path::Seq { parent, index } => format!("{}[{}]", field_path(parent), index),
+        serde_ignored::Path::Map { parent, key } => match field_path(parent) {
+            parent if parent.is_empty() => key.clone(),
+            parent => format!("{}.{}", parent, key),
+        },
+        serde_ignored::Path::Some { parent }
+        | serde_ignored::Path::NewtypeStruct { parent }
+        | serde_ignored::Path::NewtypeVariant { parent } => field_path(parent),
+    }
+}
+
+fn reject<'r, T>(
+    req: &'r Request<'_>,
+    status: Status,
+    reason: String,
+) -> data::Outcome<'r, T, String> {
+    req.local_cache(|| RejectedBody(Some(reason.clone())));
+    data::Outcome::Error((status, reason))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn optional_fields_sent_as_null_should_be_known() {
+        let body = r#"{ "title": "title", "description": null, "author_email": null }"#;
+        let (question, unknown) = parse::<CreateQuestionRequest>(body, true).unwrap();
+        assert_eq!(question.author_email, None);
+        assert_eq!(unknown, None);
+
+        let body = r#"{ "title": "title", "author_mail": null }"#;
+        let (_, unknown) = parse::<CreateQuestionRequest>(body, true).unwrap();
+        assert_eq!(unknown.as_deref(), Some("author_mail"));
+    }
+
+    #[test]
+    fn browser_push_subscriptions_should_be_known() {
+        let body = r#"{
+            "endpoint": "https://push.example.com/send/abc",
+            "expirationTime": null,
+            "keys": { "p256dh": "key", "auth": "secret" },
+            "question_uuids": []
+        }"#;
+        let (_, unknown) = parse::<CreatePushSubscriptionRequest>(body, true).unwrap();
+        assert_eq!(unknown, None);
+
+        let body = body.replace("\"auth\"", "\"uath\": \"secret\", \"auth\"");
+        let (_, unknown) = parse::<CreatePushSubscriptionRequest>(&body, true).unwrap();
+        assert_eq!(unknown.as_deref(), Some("keys.uath"));
+    }
+}
@@ -1,452 +1,3326 @@
-use log::error;
+use std::collections::HashMap;
 
+use chrono::Utc;
+use futures::{stream, StreamExt};
+use log::{error, warn};
+use serde_json::Value;
+
+use super::{
+    pagination::{DEFAULT_PER_PAGE, MAX_PER_PAGE},
+    validation::{self, FieldError},
+    AppError,
+};
 use crate::{
-    models::{Answer, AnswerDetail, DBError, Question, QuestionDetail},
-    persistence::{answer_dao::AnswerDao, question_dao::QuestionDao},
+    actor::Actor,
+    answerer::{self, AnswerSuggestions, Suggestion},
+    attachments::{self, Attachments, StoreError},
+    avatars,
+    chat::{ChatNotifiers, Notice},
+    config::{AppConfig, DuplicateAnswerPolicy, WordFilterPolicy},
+    confirmations::{Confirmation, SubscriptionChange},
+    dto::{AdminStatsResponse, ScimPatchOperation},
+    duplicates::DuplicateAnswers,
+    embedding::{self, SemanticSearch},
+    events::{self, OutboxEvent},
+    link_previews::LinkPreviews,
+    models::{
+        empty_metadata, Activity, Answer, AnswerDetail, AnswerSort, AnswerUpdate, AnswerUuid,
+        ArchiveSummary, Attachment, AttachmentStatus, AttachmentUuid, AttachmentVariant,
+        AuditEntry, DBError, DailyStats, DigestFrequency, Entity, HeldContent, HookUuid, Import,
+        ImportSummary, Listed, MergeSummary, ModerationAction, ModerationItem, ModerationUuid,
+        NewAttachment, NewModerationItem, ProvisionedUser, PushSubscription, Question,
+        QuestionDetail, QuestionFilter, QuestionSort, QuestionTranslation, QuestionUpdate,
+        QuestionUuid, QuestionWithAnswer, QuestionWithAnswerDetail, RestHook, SpamContext,
+        UserAttributes, UserUuid, Window,
+    },
+    moderation::{Moderated, Moderation, Published},
+    notifications::Notifications,
+    persistence::{
+        activity::ActivityDao,
+        answer_dao::AnswerDao,
+        archive::ArchiveDao,
+        audit::{AuditDao, AuditFilter},
+        duplicate::SimilarAnswer,
+        import::ImportDao,
+        link_preview::LinkPreview,
+        merge::MergeDao,
+        question_dao::QuestionDao,
+        rest_hook::RestHookDao,
+        search::{Highlight, TitleMatch},
+        stats::StatsDao,
+        subscription::SubscriptionDao,
+        translation::TranslationDao,
+        unit_of_work::UnitOfWorkFactory,
+        user::UserDao,
+        RowStream,
+    },
+    rate_limit::RateLimitSlot,
+    replies::{self, ReplyAddresses},
+    search::Search,
+    tagging::TagSuggestions,
+    translation::{self, Translations},
+    word_filter::WordFilter,
+    AppState,
 };
 
-#[derive(Debug, PartialEq)]
-pub enum HandlerError {
-    BadRequest(String),
-    InternalError(String),
+// Updates must say which version they were made against, in If-Match or in the body.
+fn expected_version(if_match: Option<i64>, body: Option<i64>) -> Result<i64, AppError> {
+    match (if_match, body) {
+        (Some(header), Some(body)) if header != body => Err(AppError::BadRequest(
+            "If-Match and version disagree.".to_owned(),
+        )),
+        (Some(version), _) | (None, Some(version)) => Ok(version),
+        (None, None) => Err(AppError::PreconditionRequired(
+            "Send the version being updated in If-Match or in the body.".to_owned(),
+        )),
+    }
+}
+
+// Questions are saved even when embedding them fails; they are then left out of
+// semantic search until their next edit.
+async fn index_question(semantic_search: Option<&SemanticSearch>, question: &QuestionDetail) {
+    let Some(semantic_search) = semantic_search else {
+        return;
+    };
+
+    let text = embedding::question_text(&question.title, question.description.as_deref());
+    let result = match semantic_search.embedder.embed(&text).await {
+        Ok(embedding) => semantic_search
+            .embedding_dao
+            .store_question_embedding(question.question_uuid, embedding)
+            .await
+            .map_err(|err| err.to_string()),
+        Err(err) => Err(err.to_string()),
+    };
+
+    if let Err(err) = result {
+        warn!(
+            "Failed to embed question {}: {}",
+            question.question_uuid, err
+        );
+    }
+}
+
+// Authors are only subscribed once they confirm, like anyone else; the address could be
+// someone else's.
+async fn subscribe_author(
+    notifications: Option<&Notifications>,
+    question: &QuestionDetail,
+    author_email: Option<String>,
+) {
+    let (Some(notifications), Some(email)) = (notifications, author_email) else {
+        return;
+    };
+
+    let change = SubscriptionChange::Subscribe(question.question_uuid);
+    notifications.request_confirmation(&email, change).await;
+}
+
+// Questions created without tags get the suggested ones, when enabled. They are still
+// created when suggesting fails.
+async fn auto_tag(tag_suggestions: Option<&TagSuggestions>, question: &mut Question) {
+    let Some(tag_suggestions) = tag_suggestions.filter(|suggestions| suggestions.auto_apply) else {
+        return;
+    };
+    let Value::Object(metadata) = &mut question.metadata else {
+        return;
+    };
+    if metadata.contains_key("tags") {
+        return;
+    }
+
+    match tag_suggestions
+        .suggest(&question.title, question.description.as_deref())
+        .await
+    {
+        Ok(tags) if !tags.is_empty() => {
+            metadata.insert("tags".to_owned(), Value::from(tags));
+        }
+        Ok(_) => {}
+        Err(err) => warn!("Error on suggesting tags: {:?}", err),
+    }
+}
+
+// Held content is stored as the create it came from, so approving it replays that create.
+async fn hold_if_flagged(
+    moderation: Option<&Moderation>,
+    spam_context: &SpamContext,
+    content: impl FnOnce() -> HeldContent,
+) -> Result<Option<ModerationItem>, AppError> {
+    match moderation {
+        Some(moderation) => Ok(moderation.hold_if_flagged(&content(), spam_context).await?),
+        None => Ok(None),
+    }
+}
+
+// `prefix` names the fields of a question nested in the body.
+fn question_fields<'a>(
+    prefix: &'static str,
+    question: &'a mut Question,
+) -> Vec<(&'static str, &'a mut String)> {
+    let (title, description) = match prefix {
+        "" => ("title", "description"),
+        _ => ("question.title", "question.description"),
+    };
+    let mut fields = vec![(title, &mut question.title)];
+    if let Some(text) = &mut question.description {
+        fields.push((description, text));
+    }
+    fields
+}
+
+// Under `Reject` content with a filtered word fails validation; under `Mask` it is
+// stored with the words masked.
+async fn filter_words(
+    word_filter: Option<&WordFilter>,
+    fields: Vec<(&'static str, &mut String)>,
+) -> Result<(), AppError> {
+    let Some(word_filter) = word_filter else {
+        return Ok(());
+    };
+
+    let filtered = word_filter.apply(fields).await?;
+    if word_filter.policy == WordFilterPolicy::Reject && !filtered.is_empty() {
+        return Err(AppError::Invalid(
+            filtered
+                .into_iter()
+                .map(|field| FieldError {
+                    field: field.to_owned(),
+                    message: "contains a blocked word".to_owned(),
+                })
+                .collect(),
+        ));
+    }
+    Ok(())
+}
+
+fn preview_links(link_previews: Option<&LinkPreviews>, question: &QuestionDetail) {
+    if let Some(link_previews) = link_previews {
+        link_previews.enqueue(&embedding::question_text(
+            &question.title,
+            question.description.as_deref(),
+        ));
+    }
+}
+
+fn post_to_chat(chat: Option<&ChatNotifiers>, notice: impl FnOnce() -> Notice) {
+    if let Some(chat) = chat {
+        chat.enqueue(notice());
+    }
+}
+
+// What is done around writing questions and answers, each step skipped while its feature is off.
+#[derive(Clone, Copy, Default)]
+pub struct WriteHooks<'a> {
+    pub semantic_search: Option<&'a SemanticSearch>,
+    pub notifications: Option<&'a Notifications>,
+    pub chat: Option<&'a ChatNotifiers>,
+    pub word_filter: Option<&'a WordFilter>,
+    pub moderation: Option<&'a Moderation>,
+    pub duplicate_answers: Option<&'a DuplicateAnswers>,
+    pub tag_suggestions: Option<&'a TagSuggestions>,
+    pub link_previews: Option<&'a LinkPreviews>,
+}
+
+impl<'a> WriteHooks<'a> {
+    pub fn of(state: &'a AppState) -> Self {
+        WriteHooks {
+            semantic_search: state.semantic_search.as_ref(),
+            notifications: state.notifications.as_ref(),
+            chat: state.chat.as_ref(),
+            word_filter: state.word_filter.as_ref(),
+            moderation: state.moderation.as_ref(),
+            duplicate_answers: state.duplicate_answers.as_ref(),
+            tag_suggestions: state.tag_suggestions.as_ref(),
+            link_previews: state.link_previews.as_ref(),
+        }
+    }
+
+    // Held content already went through the word filter and moderation, and approving it
+    // publishes it even when it is close to existing answers.
+    fn approved(self) -> Self {
+        WriteHooks {
+            word_filter: None,
+            moderation: None,
+            duplicate_answers: None,
+            ..self
+        }
+    }
+}
+
+pub async fn create_question<Q: QuestionDao + Sync + Send + ?Sized>(
+    question: Question,
+    author_email: Option<String>,
+    questions_dao: &Q,
+    hooks: WriteHooks<'_>,
+    spam_context: &SpamContext,
+) -> Result<Moderated<QuestionDetail>, AppError> {
+    let (mut question, author_email) = validation::question(question, author_email)?;
+    filter_words(hooks.word_filter, question_fields("", &mut question)).await?;
+    let held = hold_if_flagged(hooks.moderation, spam_context, || HeldContent::Question {
+        question: question.clone(),
+        author_email: author_email.clone(),
+    });
+    if let Some(item) = held.await? {
+        return Ok(Moderated::Held(Box::new(item)));
+    }
+    auto_tag(hooks.tag_suggestions, &mut question).await;
+    let question = questions_dao.create_question(question).await?;
+
+    index_question(hooks.semantic_search, &question).await;
+    preview_links(hooks.link_previews, &question);
+    subscribe_author(hooks.notifications, &question, author_email).await;
+    post_to_chat(hooks.chat, || Notice::QuestionCreated {
+        question: question.clone(),
+        author: Actor::current().name,
+    });
+    Ok(Moderated::Published(question))
+}
+
+// Neither the question nor the answer is kept unless both are written.
+pub async fn create_question_with_answer(
+    payload: QuestionWithAnswer,
+    author_email: Option<String>,
+    unit_of_work: &(dyn UnitOfWorkFactory + Sync + Send),
+    hooks: WriteHooks<'_>,
+    spam_context: &SpamContext,
+) -> Result<Moderated<QuestionWithAnswerDetail>, AppError> {
+    let (mut payload, author_email) = validation::question_with_answer(payload, author_email)?;
+    let mut fields = question_fields("question.", &mut payload.question);
+    fields.push(("answer", &mut payload.answer));
+    filter_words(hooks.word_filter, fields).await?;
+    let held = hold_if_flagged(hooks.moderation, spam_context, || {
+        HeldContent::QuestionWithAnswer {
+            question: payload.question.clone(),
+            answer: payload.answer.clone(),
+            author_email: author_email.clone(),
+        }
+    });
+    if let Some(item) = held.await? {
+        return Ok(Moderated::Held(Box::new(item)));
+    }
+    auto_tag(hooks.tag_suggestions, &mut payload.question).await;
+    let mut work = unit_of_work.begin().await?;
+    let question = work.create_question(payload.question).await?;
+    let answer = work
+        .create_answer(Answer {
+            question_uuid: question.question_uuid,
+            content: payload.answer,
+        })
+        .await?;
+    work.commit().await?;
+    let result = QuestionWithAnswerDetail { question, answer };
+
+    index_question(hooks.semantic_search, &result.question).await;
+    preview_links(hooks.link_previews, &result.question);
+    subscribe_author(hooks.notifications, &result.question, author_email).await;
+    post_to_chat(hooks.chat, || Notice::QuestionCreated {
+        question: result.question.clone(),
+        author: Actor::current().name,
+    });
+    Ok(Moderated::Published(result))
+}
+
+pub async fn update_question<Q: QuestionDao + Sync + Send + ?Sized>(
+    question_uuid: QuestionUuid,
+    update: QuestionUpdate,
+    if_match: Option<i64>,
+    question_dao: &Q,
+    hooks: WriteHooks<'_>,
+) -> Result<QuestionDetail, AppError> {
+    let update = validation::question_update(update)?;
+    let version = expected_version(if_match, update.version)?;
+    let mut question = Question {
+        title: update.title,
+        description: update.description,
+        metadata: update.metadata,
+    };
+    filter_words(hooks.word_filter, question_fields("", &mut question)).await?;
+
+    let question = question_dao
+        .update_question(question_uuid, question, version)
+        .await?;
+
+    index_question(hooks.semantic_search, &question).await;
+    preview_links(hooks.link_previews, &question);
+    Ok(question)
+}
+
+pub const SEARCH_MAX_LIMIT: u32 = 100;
+
+pub async fn search_questions(
+    query: String,
+    limit: u32,
+    search: Option<&Search>,
+) -> Result<Vec<QuestionDetail>, AppError> {
+    let Some(search) = search else {
+        return Err(AppError::NotImplemented(
+            "Search is not enabled on this server.".to_owned(),
+        ));
+    };
+    if query.trim().is_empty() {
+        return Err(AppError::BadRequest("q must not be empty.".to_owned()));
+    }
+
+    search
+        .search(&query, limit.clamp(1, SEARCH_MAX_LIMIT))
+        .await
+        .map_err(|err| {
+            error!("Error on searching questions: {:?}", err);
+            AppError::ServiceUnavailable(
+                "Search is temporarily unavailable. Please try again later.".to_owned(),
+            )
+        })
+}
+
+// Results are still worth showing without their highlights, so a failure only leaves
+// them out.
+pub async fn highlight(
+    query: &str,
+    questions: &[QuestionDetail],
+    search: Option<&Search>,
+) -> Result<Option<Vec<Highlight>>, AppError> {
+    let Some(search) = search else {
+        return Ok(None);
+    };
+    if questions.is_empty() {
+        return Ok(Some(vec![]));
+    }
+
+    match search.highlight(query, questions).await {
+        Ok(highlights) => Ok(Some(highlights)),
+        Err(err) => {
+            warn!("Error on highlighting search results: {:?}", err);
+            Ok(None)
+        }
+    }
+}
+
+pub const SEARCH_SUGGESTIONS: u32 = 3;
+
+// "Did you mean" titles for a search that found fewer than `below` questions, None
+// while suggestions are off. Like highlights they are left out should they fail.
+pub async fn suggest_titles(
+    query: &str,
+    found: &[QuestionDetail],
+    below: usize,
+    threshold: f32,
+    search: Option<&Search>,
+) -> Result<Option<Vec<TitleMatch>>, AppError> {
+    let Some(search) = search else {
+        return Ok(None);
+    };
+    if found.len() >= below {
+        return Ok(None);
+    }
+
+    let excluded: Vec<QuestionUuid> = found.iter().map(|q| q.question_uuid).collect();
+    match search
+        .similar_titles(query, threshold, &excluded, SEARCH_SUGGESTIONS)
+        .await
+    {
+        Ok(titles) => Ok(Some(titles)),
+        Err(err) => {
+            warn!("Error on suggesting titles for a search: {:?}", err);
+            Ok(None)
+        }
+    }
+}
+
+pub const AUTOCOMPLETE_MAX_LIMIT: u32 = 10;
+
+// Shorter text matches too many titles to help, and is too short for the trigram index.
+const AUTOCOMPLETE_MIN_CHARS: usize = 3;
+
+// Nothing until enough is typed, rather than an error on every early keystroke.
+pub async fn complete_titles(
+    text: String,
+    limit: u32,
+    search: Option<&Search>,
+) -> Result<Vec<TitleMatch>, AppError> {
+    let Some(search) = search else {
+        return Err(AppError::NotImplemented(
+            "Search is not enabled on this server.".to_owned(),
+        ));
+    };
+    let text = text.trim();
+    if text.chars().count() < AUTOCOMPLETE_MIN_CHARS {
+        return Ok(vec![]);
+    }
+
+    Ok(search
+        .complete_titles(text, limit.clamp(1, AUTOCOMPLETE_MAX_LIMIT))
+        .await?)
+}
+
+pub const SEMANTIC_SEARCH_MAX_LIMIT: u32 = 50;
+
+pub async fn semantic_search(
+    query: String,
+    limit: u32,
+    semantic_search: Option<&SemanticSearch>,
+) -> Result<Vec<QuestionDetail>, AppError> {
+    let Some(semantic_search) = semantic_search else {
+        return Err(AppError::NotImplemented(
+            "Semantic search is not enabled on this server.".to_owned(),
+        ));
+    };
+    if query.trim().is_empty() {
+        return Err(AppError::BadRequest("q must not be empty.".to_owned()));
+    }
+
+    let embedding = semantic_search
+        .embedder
+        .embed(&query)
+        .await
+        .map_err(|err| {
+            error!("Error on embedding a search query: {:?}", err);
+            AppError::ServiceUnavailable(
+                "Search is temporarily unavailable. Please try again later.".to_owned(),
+            )
+        })?;
+
+    Ok(semantic_search
+        .embedding_dao
+        .nearest_questions(embedding, limit.clamp(1, SEMANTIC_SEARCH_MAX_LIMIT))
+        .await?)
+}
+
+// One window of the questions, archived ones included when `include_archived` is set.
+// Filtering, ordering and paging are left to the DAO.
+pub async fn get_questions<Q: QuestionDao + Sync + Send + ?Sized>(
+    question_dao: &Q,
+    include_archived: bool,
+    sort: QuestionSort,
+    filter: &QuestionFilter,
+    window: Window,
+) -> Result<Listed<QuestionDetail>, AppError> {
+    Ok(question_dao
+        .list_questions(filter, include_archived, sort, window)
+        .await?)
+}
+
+// With `lang`, the title and description come back translated into it, along with the
+// language they are in: a translation people submitted comes first, then one from the
+// translator, and without either the question is returned as written. The previews
+// fetched so far of its links come too while link previews are enabled.
+pub async fn get_question(
+    question_uuid: QuestionUuid,
+    lang: Option<String>,
+    question_dao: &(dyn QuestionDao + Send + Sync),
+    translations: Option<&Translations>,
+    translation_dao: Option<&(dyn TranslationDao + Send + Sync)>,
+    link_previews: Option<&LinkPreviews>,
+) -> Result<(QuestionDetail, Option<String>, Option<Vec<LinkPreview>>), AppError> {
+    let language = lang
+        .map(|lang| {
+            translation::parse_language(&lang).ok_or_else(|| {
+                AppError::InvalidQuery(vec![FieldError {
+                    field: "lang".to_owned(),
+                    message: "must be a language code such as es or pt-BR".to_owned(),
+                }])
+            })
+        })
+        .transpose()?;
+    let question = question_dao.get_question(question_uuid).await?;
+    // From the original text, which a translator may have changed the links in.
+    let previews = match link_previews {
+        Some(link_previews) => Some(
+            link_previews
+                .previews(&embedding::question_text(
+                    &question.title,
+                    question.description.as_deref(),
+                ))
+                .await?,
+        ),
+        None => None,
+    };
+    let Some(language) = language else {
+        return Ok((question, None, previews));
+    };
+    if let Some(translation_dao) = translation_dao {
+        if let Some(translation) =
+            submitted_translation(question_uuid, &language, translation_dao).await?
+        {
+            let language = translation.language.clone();
+            return Ok((translated(question, translation), Some(language), previews));
+        }
+    }
+    let Some(translations) = translations else {
+        return Ok((question, None, previews));
+    };
+
+    let question = translations
+        .translate_question(question, &language)
+        .await
+        .map_err(|err| {
+            error!(
+                "Error on translating question {}: {:?}",
+                question_uuid.0, err
+            );
+            AppError::ServiceUnavailable(
+                "Translation is temporarily unavailable. Please try again later.".to_owned(),
+            )
+        })?;
+    Ok((question, Some(language), previews))
+}
+
+// The translation people submitted into `language`, or else into its primary language,
+// so `pt-br` finds a `pt` one.
+async fn submitted_translation(
+    question_uuid: QuestionUuid,
+    language: &str,
+    translation_dao: &(dyn TranslationDao + Send + Sync),
+) -> Result<Option<QuestionTranslation>, DBError> {
+    if let Some(translation) = translation_dao
+        .question_translation(question_uuid, language)
+        .await?
+    {
+        return Ok(Some(translation));
+    }
+    match language.split_once('-') {
+        Some((primary, _)) => {
+            translation_dao
+                .question_translation(question_uuid, primary)
+                .await
+        }
+        None => Ok(None),
+    }
+}
+
+fn translated(question: QuestionDetail, translation: QuestionTranslation) -> QuestionDetail {
+    QuestionDetail {
+        title: translation.title,
+        description: translation.description,
+        ..question
+    }
+}
+
+fn submitted_translations(
+    translation_dao: Option<&(dyn TranslationDao + Send + Sync)>,
+) -> Result<&(dyn TranslationDao + Send + Sync), AppError> {
+    translation_dao.ok_or_else(|| {
+        AppError::NotImplemented(
+            "Submitted translations need Postgres or STORAGE=memory.".to_owned(),
+        )
+    })
+}
+
+fn path_language(language: &str) -> Result<String, AppError> {
+    translation::parse_language(language).ok_or_else(|| {
+        AppError::BadRequest(format!(
+            "{} is not a language code such as es or pt-BR.",
+            language
+        ))
+    })
+}
+
+// Replaces the question's translation into `language`; anyone may submit one. The word
+// filter applies as to the question itself.
+pub async fn submit_translation(
+    question_uuid: QuestionUuid,
+    language: &str,
+    title: String,
+    description: Option<String>,
+    translation_dao: Option<&(dyn TranslationDao + Send + Sync)>,
+    word_filter: Option<&WordFilter>,
+) -> Result<QuestionTranslation, AppError> {
+    let translation_dao = submitted_translations(translation_dao)?;
+    let language = path_language(language)?;
+    let (mut title, mut description) = validation::translation(title, description)?;
+    let mut fields = vec![("title", &mut title)];
+    if let Some(text) = &mut description {
+        fields.push(("description", text));
+    }
+    filter_words(word_filter, fields).await?;
+
+    Ok(translation_dao
+        .submit_question_translation(question_uuid, &language, title, description)
+        .await?)
+}
+
+// The translations people submitted, by language. NotFound for unknown questions.
+pub async fn get_translations(
+    question_uuid: QuestionUuid,
+    question_dao: &(dyn QuestionDao + Send + Sync),
+    translation_dao: Option<&(dyn TranslationDao + Send + Sync)>,
+) -> Result<Vec<QuestionTranslation>, AppError> {
+    let translation_dao = submitted_translations(translation_dao)?;
+    question_dao.get_question(question_uuid).await?;
+
+    Ok(translation_dao.question_translations(question_uuid).await?)
+}
+
+pub async fn delete_translation(
+    question_uuid: QuestionUuid,
+    language: &str,
+    translation_dao: Option<&(dyn TranslationDao + Send + Sync)>,
+) -> Result<(), AppError> {
+    let translation_dao = submitted_translations(translation_dao)?;
+    let language = path_language(language)?;
+
+    Ok(translation_dao
+        .delete_question_translation(question_uuid, &language)
+        .await?)
+}
+
+// A draft from the configured answerer. Nothing is stored: it only becomes an answer if
+// a person posts it. Clients are rate limited by `client`, e.g. their IP, and where they
+// stand is recorded in `rate_limit`.
+pub async fn suggest_answer(
+    question_uuid: QuestionUuid,
+    client: &str,
+    rate_limit: &RateLimitSlot,
+    config: &AppConfig,
+    question_dao: &(dyn QuestionDao + Send + Sync),
+    answer_dao: &(dyn AnswerDao + Send + Sync),
+    suggestions: Option<&AnswerSuggestions>,
+) -> Result<Suggestion, AppError> {
+    let suggestions = suggestions
+        .filter(|_| config.feature_flags.contains(answerer::FEATURE_FLAG))
+        .ok_or_else(|| {
+            AppError::NotImplemented("Suggested answers are not enabled on this server.".to_owned())
+        })?;
+    let question = question_dao.get_question(question_uuid).await?;
+    match suggestions.rate_limiter.check(client) {
+        Ok(limit) => {
+            if let Some(limit) = limit {
+                rate_limit.record(limit);
+            }
+        }
+        Err(limit) => {
+            rate_limit.record(limit);
+            return Err(AppError::RateLimited(
+                format!(
+                    "Too many suggested answers; try again in {} seconds.",
+                    limit.reset_secs()
+                ),
+                limit,
+            ));
+        }
+    }
+
+    let answers = answer_dao.get_answers(question_uuid).await?;
+    suggestions
+        .answerer
+        .suggest(&question, &answers)
+        .await
+        .map_err(|err| {
+            error!("Error on suggesting an answer: {:?}", err);
+            AppError::ServiceUnavailable(
+                "Suggested answers are temporarily unavailable. Please try again later.".to_owned(),
+            )
+        })
 }
 
-impl HandlerError {
-    pub fn default_internal_error() -> Self {
-        HandlerError::InternalError("Something went wrong! Please try again.".to_owned())
+// Tags for a question that is being written; the title and description are checked like
+// those of a new question.
+pub async fn suggest_tags(
+    title: String,
+    description: Option<String>,
+    tag_suggestions: Option<&TagSuggestions>,
+) -> Result<Vec<String>, AppError> {
+    let tag_suggestions = tag_suggestions.ok_or_else(|| {
+        AppError::NotImplemented("Tag suggestions are not enabled on this server.".to_owned())
+    })?;
+    let (question, _) = validation::question(
+        Question {
+            title,
+            description,
+            metadata: empty_metadata(),
+        },
+        None,
+    )?;
+
+    tag_suggestions
+        .suggest(&question.title, question.description.as_deref())
+        .await
+        .map_err(|err| {
+            error!("Error on suggesting tags: {:?}", err);
+            AppError::ServiceUnavailable(
+                "Tag suggestions are temporarily unavailable. Please try again later.".to_owned(),
+            )
+        })
+}
+
+// One window of the questions without answers, oldest first, so the questions that
+// have waited longest come first.
+pub async fn get_unanswered_questions<Q: QuestionDao + Sync + Send + ?Sized>(
+    question_dao: &Q,
+    window: Window,
+) -> Result<Listed<QuestionDetail>, AppError> {
+    Ok(question_dao.list_unanswered_questions(window).await?)
+}
+
+// The questions `filter` matches, passed on row by row as they are read, then the
+// archived ones it matches.
+pub async fn stream_questions<'a, Q: QuestionDao + Sync + Send + ?Sized>(
+    question_dao: &'a Q,
+    archive_dao: Option<&'a (dyn ArchiveDao + Sync + Send)>,
+    filter: QuestionFilter,
+) -> Result<RowStream<'a, QuestionDetail>, AppError> {
+    let questions = started(question_dao.stream_questions(filter.clone())).await?;
+
+    Ok(match archive_dao {
+        Some(archive_dao) => questions
+            .chain(archive_dao.stream_archived_questions(filter))
+            .boxed(),
+        None => questions,
+    })
+}
+
+// Waits for the first row, so a read that cannot even start still gets an error status.
+async fn started<'a, T: Send + 'a>(
+    mut rows: RowStream<'a, T>,
+) -> Result<RowStream<'a, T>, DBError> {
+    match rows.next().await {
+        Some(Err(err)) => Err(err),
+        Some(Ok(first)) => Ok(stream::once(async { Ok(first) }).chain(rows).boxed()),
+        None => Ok(rows),
+    }
+}
+
+pub async fn delete_question<Q: QuestionDao + Sync + Send + ?Sized>(
+    question_uuid: QuestionUuid,
+    question_dao: &Q,
+) -> Result<(), AppError> {
+    Ok(question_dao.delete_question(question_uuid).await?)
+}
+
+// Also returns the existing answers close to the created one, for the client to warn about.
+pub async fn create_answer<A: AnswerDao + Sync + Send + ?Sized>(
+    answer: Answer,
+    allow_duplicate: bool,
+    answer_dao: &A,
+    hooks: WriteHooks<'_>,
+    spam_context: &SpamContext,
+) -> Result<(Moderated<AnswerDetail>, Vec<SimilarAnswer>), AppError> {
+    let mut answer = validation::answer(answer)?;
+    filter_words(hooks.word_filter, vec![("content", &mut answer.content)]).await?;
+    let similar = similar_answers(hooks.duplicate_answers, &answer, allow_duplicate).await?;
+    let held = hold_if_flagged(hooks.moderation, spam_context, || HeldContent::Answer {
+        answer: answer.clone(),
+    });
+    if let Some(item) = held.await? {
+        return Ok((Moderated::Held(Box::new(item)), similar));
+    }
+    let answer = answer_dao
+        .create_answer(answer)
+        .await
+        .map_err(|err| AppError::from(err).referenced_in_body())?;
+
+    if let Some(notifications) = hooks.notifications {
+        notifications.enqueue(answer.clone());
+    }
+    post_to_chat(hooks.chat, || Notice::AnswerCreated {
+        answer: answer.clone(),
+        author: Actor::current().name,
+    });
+    Ok((Moderated::Published(answer), similar))
+}
+
+// Answers the question `recipient` is the reply address of with what the email says
+// above the quoted notification.
+pub async fn answer_by_email<A: AnswerDao + Sync + Send + ?Sized>(
+    recipient: &str,
+    body: &str,
+    replies: Option<&ReplyAddresses>,
+    answer_dao: &A,
+    hooks: WriteHooks<'_>,
+) -> Result<Moderated<AnswerDetail>, AppError> {
+    let replies = replies.ok_or_else(|| {
+        AppError::NotImplemented("Replying by email is not enabled on this server.".to_owned())
+    })?;
+    let Some(question_uuid) = replies.question_for(recipient) else {
+        return Err(AppError::Invalid(vec![FieldError {
+            field: "recipient".to_owned(),
+            message: "is not a reply address of this server".to_owned(),
+        }]));
+    };
+
+    let answer = Answer {
+        question_uuid,
+        content: replies::reply_text(body),
+    };
+    // The sender's network details are not known, only the mail provider's.
+    let (created, _) =
+        create_answer(answer, false, answer_dao, hooks, &SpamContext::default()).await?;
+    Ok(created)
+}
+
+// Under `Reject` close answers fail the create, unless the caller insists.
+async fn similar_answers(
+    duplicate_answers: Option<&DuplicateAnswers>,
+    answer: &Answer,
+    allow_duplicate: bool,
+) -> Result<Vec<SimilarAnswer>, AppError> {
+    let Some(duplicate_answers) = duplicate_answers else {
+        return Ok(vec![]);
+    };
+    let similar = duplicate_answers.similar_to(answer).await?;
+    if similar.is_empty()
+        || allow_duplicate
+        || duplicate_answers.policy != DuplicateAnswerPolicy::Reject
+    {
+        return Ok(similar);
+    }
+
+    Err(AppError::DuplicateAnswer(
+        similar
+            .iter()
+            .map(|similar| FieldError {
+                field: "content".to_owned(),
+                message: format!(
+                    "is {:.0}% alike answer {}",
+                    similar.similarity * 100.0,
+                    similar.answer_uuid
+                ),
+            })
+            .collect(),
+    ))
+}
+
+fn moderation(moderation: Option<&Moderation>) -> Result<&Moderation, AppError> {
+    moderation.ok_or_else(|| {
+        AppError::NotImplemented("Moderation is not enabled on this server.".to_owned())
+    })
+}
+
+fn held_item_not_found(moderation_uuid: ModerationUuid) -> AppError {
+    AppError::Database(DBError::NotFound(
+        Entity::ModerationItem,
+        format!("Moderation item {} does not exist", moderation_uuid),
+    ))
+}
+
+pub async fn held_items(
+    window: Window,
+    moderation: Option<&Moderation>,
+) -> Result<Listed<ModerationItem>, AppError> {
+    Ok(self::moderation(moderation)?
+        .moderation_dao
+        .held_items(window)
+        .await?)
+}
+
+pub async fn approve_held(
+    moderation_uuid: ModerationUuid,
+    question_dao: &(dyn QuestionDao + Sync + Send),
+    answer_dao: &(dyn AnswerDao + Sync + Send),
+    unit_of_work: &(dyn UnitOfWorkFactory + Sync + Send),
+    hooks: WriteHooks<'_>,
+) -> Result<Published, AppError> {
+    let moderation = self::moderation(hooks.moderation)?;
+    let item = moderation
+        .moderation_dao
+        .take_held(moderation_uuid)
+        .await?
+        .ok_or_else(|| held_item_not_found(moderation_uuid))?;
+
+    publish_held(
+        item,
+        moderation,
+        question_dao,
+        answer_dao,
+        unit_of_work,
+        hooks,
+    )
+    .await
+}
+
+// Replays the held create, already taken off the queue, without moderation or the word
+// filter, which it passed before being held. Should it fail, e.g. because the question
+// of an answer was deleted meanwhile, the item is put back so it can be rejected instead.
+async fn publish_held(
+    item: ModerationItem,
+    moderation: &Moderation,
+    question_dao: &(dyn QuestionDao + Sync + Send),
+    answer_dao: &(dyn AnswerDao + Sync + Send),
+    unit_of_work: &(dyn UnitOfWorkFactory + Sync + Send),
+    hooks: WriteHooks<'_>,
+) -> Result<Published, AppError> {
+    let moderation_uuid = item.moderation_uuid;
+    let hooks = hooks.approved();
+    let published = match item.content.clone() {
+        HeldContent::Question {
+            question,
+            author_email,
+        } => create_question(
+            question,
+            author_email,
+            question_dao,
+            hooks,
+            &SpamContext::default(),
+        )
+        .await
+        .map(|created| Published::Question(unmoderated(created))),
+        HeldContent::Answer { answer } => {
+            { create_answer(answer, true, answer_dao, hooks, &SpamContext::default()).await }
+                .map(|(created, _)| Published::Answer(unmoderated(created)))
+        }
+        HeldContent::QuestionWithAnswer {
+            question,
+            answer,
+            author_email,
+        } => create_question_with_answer(
+            QuestionWithAnswer { question, answer },
+            author_email,
+            unit_of_work,
+            hooks,
+            &SpamContext::default(),
+        )
+        .await
+        .map(|created| Published::QuestionWithAnswer(unmoderated(created))),
+    };
+
+    if published.is_err() {
+        let requeued = moderation
+            .moderation_dao
+            .hold(NewModerationItem {
+                moderation_uuid: item.moderation_uuid,
+                content: item.content,
+                score: item.score,
+                categories: item.categories,
+                spam_context: item.spam_context,
+            })
+            .await;
+        if let Err(err) = requeued {
+            error!("Error on requeueing {}: {:?}", moderation_uuid, err);
+        }
+    } else {
+        moderation.report_decision(&item, false).await;
+    }
+    published
+}
+
+fn unmoderated<T>(created: Moderated<T>) -> T {
+    match created {
+        Moderated::Published(created) => created,
+        Moderated::Held(_) => unreachable!("creates without moderation are never held"),
+    }
+}
+
+pub async fn reject_held(
+    moderation_uuid: ModerationUuid,
+    moderation: Option<&Moderation>,
+) -> Result<(), AppError> {
+    let moderation = self::moderation(moderation)?;
+    let item = moderation
+        .moderation_dao
+        .take_held(moderation_uuid)
+        .await?
+        .ok_or_else(|| held_item_not_found(moderation_uuid))?;
+    moderation.report_decision(&item, true).await;
+    Ok(())
+}
+
+const MODERATION_BULK_MAX_ITEMS: usize = 100;
+
+// Takes all the items off the queue in one statement, so moderators acting on the same
+// items at once never both get one, then approves or deletes each. Every item gets what
+// its own route would have answered, in the order given; duplicates are acted on once.
+pub async fn moderate_held(
+    moderation_uuids: Vec<ModerationUuid>,
+    action: ModerationAction,
+    question_dao: &(dyn QuestionDao + Sync + Send),
+    answer_dao: &(dyn AnswerDao + Sync + Send),
+    unit_of_work: &(dyn UnitOfWorkFactory + Sync + Send),
+    hooks: WriteHooks<'_>,
+) -> Result<Vec<(ModerationUuid, Result<Option<Published>, AppError>)>, AppError> {
+    let moderation = self::moderation(hooks.moderation)?;
+    let mut unique = Vec::with_capacity(moderation_uuids.len());
+    for moderation_uuid in moderation_uuids {
+        if !unique.contains(&moderation_uuid) {
+            unique.push(moderation_uuid);
+        }
+    }
+    if unique.is_empty() || unique.len() > MODERATION_BULK_MAX_ITEMS {
+        return Err(AppError::Invalid(vec![FieldError {
+            field: "moderation_uuids".to_owned(),
+            message: format!("must list 1 to {} items", MODERATION_BULK_MAX_ITEMS),
+        }]));
+    }
+
+    let mut taken: HashMap<ModerationUuid, ModerationItem> = moderation
+        .moderation_dao
+        .take_held_many(&unique)
+        .await?
+        .into_iter()
+        .map(|item| (item.moderation_uuid, item))
+        .collect();
+
+    let mut results = Vec::with_capacity(unique.len());
+    for moderation_uuid in unique {
+        let result = match (taken.remove(&moderation_uuid), action) {
+            (None, _) => Err(held_item_not_found(moderation_uuid)),
+            (Some(item), ModerationAction::Approve) => publish_held(
+                item,
+                moderation,
+                question_dao,
+                answer_dao,
+                unit_of_work,
+                hooks,
+            )
+            .await
+            .map(Some),
+            (Some(item), ModerationAction::Delete) => {
+                moderation.report_decision(&item, true).await;
+                Ok(None)
+            }
+        };
+        results.push((moderation_uuid, result));
+    }
+    Ok(results)
+}
+
+const MAX_FILTERED_WORD_CHARS: usize = 64;
+
+fn word_filter(word_filter: Option<&WordFilter>) -> Result<&WordFilter, AppError> {
+    word_filter.ok_or_else(|| {
+        AppError::NotImplemented("The word filter is not enabled on this server.".to_owned())
+    })
+}
+
+pub async fn filtered_words(word_filter: Option<&WordFilter>) -> Result<Vec<String>, AppError> {
+    Ok(self::word_filter(word_filter)?.words().await?)
+}
+
+// Words are matched whole, so only single words of letters and digits can be listed.
+pub async fn set_filtered_word(
+    word: &str,
+    filtered: bool,
+    word_filter: Option<&WordFilter>,
+) -> Result<(), AppError> {
+    let word_filter = self::word_filter(word_filter)?;
+    let word = word.trim().to_lowercase();
+    if word.is_empty()
+        || word.chars().count() > MAX_FILTERED_WORD_CHARS
+        || !word.chars().all(char::is_alphanumeric)
+    {
+        return Err(AppError::BadRequest(format!(
+            "The word must be letters and digits only, at most {} of them.",
+            MAX_FILTERED_WORD_CHARS
+        )));
+    }
+
+    Ok(word_filter.set_filtered(word, filtered).await?)
+}
+
+pub async fn get_answers<A: AnswerDao + Sync + Send + ?Sized>(
+    question_uuid: QuestionUuid,
+    answer_dao: &A,
+    include_archived: bool,
+    sort: AnswerSort,
+    window: Window,
+) -> Result<Listed<AnswerDetail>, AppError> {
+    Ok(answer_dao
+        .list_answers(question_uuid, include_archived, sort, window)
+        .await?)
+}
+
+// The answers of many questions from one query per table, for `?include=answers`; archived
+// answers are added when `archive_dao` is given. Each question's answers are oldest first.
+pub async fn get_answers_of_questions<A: AnswerDao + Sync + Send + ?Sized>(
+    question_uuids: &[QuestionUuid],
+    answer_dao: &A,
+    archive_dao: Option<&(dyn ArchiveDao + Sync + Send)>,
+) -> Result<HashMap<QuestionUuid, Vec<AnswerDetail>>, AppError> {
+    if question_uuids.is_empty() {
+        return Ok(HashMap::new());
+    }
+    let mut answers = answer_dao.get_answers_of_questions(question_uuids).await?;
+
+    if let Some(archive_dao) = archive_dao {
+        let archived = archive_dao
+            .get_archived_answers_of_questions(question_uuids)
+            .await?;
+        answers.extend(archived);
+    }
+    AnswerSort::Oldest.sort(&mut answers);
+
+    let mut by_question: HashMap<QuestionUuid, Vec<AnswerDetail>> = HashMap::new();
+    for answer in answers {
+        by_question
+            .entry(answer.question_uuid)
+            .or_default()
+            .push(answer);
+    }
+    Ok(by_question)
+}
+
+pub async fn stream_answers<'a, A: AnswerDao + Sync + Send + ?Sized>(
+    question_uuid: QuestionUuid,
+    answer_dao: &'a A,
+    archive_dao: Option<&'a (dyn ArchiveDao + Sync + Send)>,
+) -> Result<RowStream<'a, AnswerDetail>, AppError> {
+    let answers = started(answer_dao.stream_answers(question_uuid)).await?;
+
+    Ok(match archive_dao {
+        Some(archive_dao) => answers
+            .chain(archive_dao.stream_archived_answers(question_uuid))
+            .boxed(),
+        None => answers,
+    })
+}
+
+pub async fn delete_answer<A: AnswerDao + Sync + Send + ?Sized>(
+    answer_uuid: AnswerUuid,
+    answer_dao: &A,
+) -> Result<(), AppError> {
+    Ok(answer_dao.delete_answer(answer_uuid).await?)
+}
+
+pub async fn update_answer<A: AnswerDao + Sync + Send + ?Sized>(
+    answer_uuid: AnswerUuid,
+    update: AnswerUpdate,
+    if_match: Option<i64>,
+    answer_dao: &A,
+    word_filter: Option<&WordFilter>,
+) -> Result<AnswerDetail, AppError> {
+    let mut update = validation::answer_update(update)?;
+    let version = expected_version(if_match, update.version)?;
+    filter_words(word_filter, vec![("content", &mut update.content)]).await?;
+
+    Ok(answer_dao
+        .update_answer(answer_uuid, update.content, version)
+        .await?)
+}
+
+pub async fn import(
+    import: Import,
+    import_dao: &(dyn ImportDao + Sync + Send),
+) -> Result<ImportSummary, AppError> {
+    let import = validation::import(import)?;
+    Ok(import_dao.import(import.questions).await?)
+}
+
+// `older_than_days` overrides ARCHIVE_AFTER_DAYS for a single run.
+pub async fn archive(
+    older_than_days: Option<u32>,
+    configured_days: u32,
+    archive_dao: &(dyn ArchiveDao + Sync + Send),
+) -> Result<ArchiveSummary, AppError> {
+    let older_than_days = older_than_days.unwrap_or(configured_days);
+    if older_than_days == 0 {
+        return Err(AppError::BadRequest(
+            "Pass older_than_days or set ARCHIVE_AFTER_DAYS.".to_owned(),
+        ));
+    }
+
+    Ok(archive_dao.archive(older_than_days).await?)
+}
+
+pub async fn merge_question(
+    source_uuid: QuestionUuid,
+    target_uuid: QuestionUuid,
+    merge_dao: Option<&(dyn MergeDao + Sync + Send)>,
+) -> Result<MergeSummary, AppError> {
+    let Some(merge_dao) = merge_dao else {
+        return Err(AppError::NotImplemented(
+            "Merging questions needs Postgres or STORAGE=memory.".to_owned(),
+        ));
+    };
+    if source_uuid == target_uuid {
+        return Err(AppError::BadRequest(
+            "A question cannot be merged into itself.".to_owned(),
+        ));
+    }
+
+    Ok(merge_dao.merge_question(source_uuid, target_uuid).await?)
+}
+
+// Where a question merged into another one went; None where merging is off.
+pub async fn find_redirect(
+    question_uuid: QuestionUuid,
+    merge_dao: Option<&(dyn MergeDao + Sync + Send)>,
+) -> Result<Option<QuestionUuid>, AppError> {
+    match merge_dao {
+        Some(merge_dao) => Ok(merge_dao.redirect(question_uuid).await?),
+        None => Ok(None),
+    }
+}
+
+pub async fn get_daily_stats(
+    stats_dao: &(dyn StatsDao + Sync + Send),
+) -> Result<Vec<DailyStats>, AppError> {
+    Ok(stats_dao.get_daily_stats().await?)
+}
+
+const DEFAULT_ADMIN_STATS_DAYS: i64 = 30;
+const MAX_ADMIN_STATS_DAYS: i64 = 366;
+
+// `days` counts today, so 1 is today only.
+pub async fn get_admin_stats(
+    days: Option<i64>,
+    stats_dao: &(dyn StatsDao + Sync + Send),
+) -> Result<AdminStatsResponse, AppError> {
+    let days = days.unwrap_or(DEFAULT_ADMIN_STATS_DAYS);
+    if !(1..=MAX_ADMIN_STATS_DAYS).contains(&days) {
+        return Err(AppError::InvalidQuery(vec![FieldError {
+            field: "days".to_owned(),
+            message: format!("must be between 1 and {}", MAX_ADMIN_STATS_DAYS),
+        }]));
+    }
+    let since = Utc::now().date_naive() - chrono::Duration::days(days - 1);
+
+    let daily: Vec<DailyStats> = stats_dao
+        .get_daily_stats()
+        .await?
+        .into_iter()
+        .filter(|stats| stats.day >= since.to_string())
+        .collect();
+    let response = stats_dao.get_response_stats(since).await?;
+
+    Ok(AdminStatsResponse {
+        since: since.to_string(),
+        daily,
+        active_users: response.active_users,
+        answer_rate: (response.questions > 0)
+            .then(|| response.answered as f64 / response.questions as f64),
+        median_first_answer_secs: response.median_first_answer_secs,
+    })
+}
+
+pub async fn refresh_stats(stats_dao: &(dyn StatsDao + Sync + Send)) -> Result<(), AppError> {
+    Ok(stats_dao.refresh().await?)
+}
+
+pub async fn get_audit_entries(
+    filter: AuditFilter,
+    audit_dao: Option<&(dyn AuditDao + Sync + Send)>,
+) -> Result<Vec<AuditEntry>, AppError> {
+    let Some(audit_dao) = audit_dao else {
+        return Err(AppError::NotImplemented(
+            "The audit log needs Postgres.".to_owned(),
+        ));
+    };
+
+    Ok(audit_dao.get_entries(filter).await?)
+}
+
+pub async fn get_activity(
+    before_id: Option<i64>,
+    limit: u32,
+    activity_dao: Option<&(dyn ActivityDao + Sync + Send)>,
+) -> Result<Vec<Activity>, AppError> {
+    let Some(activity_dao) = activity_dao else {
+        return Err(AppError::NotImplemented(
+            "The activity feed needs Postgres or STORAGE=memory.".to_owned(),
+        ));
+    };
+
+    Ok(activity_dao.recent_activity(before_id, limit).await?)
+}
+
+fn notifications(notifications: Option<&Notifications>) -> Result<&Notifications, AppError> {
+    notifications.ok_or_else(|| {
+        AppError::NotImplemented("Notifications need Postgres or STORAGE=memory.".to_owned())
+    })
+}
+
+fn subscription_dao(
+    notifications: Option<&Notifications>,
+) -> Result<&(dyn SubscriptionDao + Send + Sync), AppError> {
+    Ok(self::notifications(notifications)?
+        .subscription_dao
+        .as_ref())
+}
+
+// Makes `change` when `token` confirms it for `email`. Without a token one is mailed to the
+// address, so only its owner can make the change.
+pub async fn change_subscription(
+    email: String,
+    change: SubscriptionChange,
+    token: Option<String>,
+    notifications: Option<&Notifications>,
+) -> Result<Confirmation, AppError> {
+    let notifications = self::notifications(notifications)?;
+    let email = validation::email(email)?;
+    let Some(token) = token else {
+        notifications.request_confirmation(&email, change).await;
+        return Ok(Confirmation::Requested);
+    };
+    if !notifications
+        .tokens
+        .verify(change, &email, &token, Utc::now())
+    {
+        return Err(AppError::Forbidden(
+            "The token is invalid or expired. Leave it out to be sent a new one.".to_owned(),
+        ));
+    }
+
+    let subscription_dao = notifications.subscription_dao.as_ref();
+    match change {
+        SubscriptionChange::Subscribe(question_uuid) => {
+            subscription_dao.subscribe(question_uuid, email).await?
+        }
+        SubscriptionChange::Unsubscribe(question_uuid) => {
+            subscription_dao.unsubscribe(question_uuid, email).await?
+        }
+        SubscriptionChange::OptOut => subscription_dao.set_opted_out(email, true).await?,
+        SubscriptionChange::OptIn => subscription_dao.set_opted_out(email, false).await?,
+        SubscriptionChange::Digest(frequency) => {
+            subscription_dao
+                .set_digest_frequency(email, frequency)
+                .await?
+        }
+    }
+    Ok(Confirmation::Made)
+}
+
+// `frequency` is `daily` or `weekly`; None stops the digests. Confirmed like other
+// subscription changes.
+pub async fn set_digest_frequency(
+    email: String,
+    frequency: Option<String>,
+    token: Option<String>,
+    notifications: Option<&Notifications>,
+) -> Result<Confirmation, AppError> {
+    self::notifications(notifications)?;
+    let frequency = frequency.as_deref().map(digest_frequency).transpose()?;
+    let change = SubscriptionChange::Digest(frequency);
+    change_subscription(email, change, token, notifications).await
+}
+
+fn digest_frequency(frequency: &str) -> Result<DigestFrequency, AppError> {
+    frequency.parse().map_err(|_| {
+        AppError::InvalidQuery(vec![FieldError {
+            field: "frequency".to_owned(),
+            message: "must be one of: daily, weekly".to_owned(),
+        }])
+    })
+}
+
+// The change a confirmation link names, in the query `SubscriptionChange::query` makes.
+pub fn linked_change(
+    change: &str,
+    question: Option<&str>,
+    frequency: Option<&str>,
+) -> Result<SubscriptionChange, AppError> {
+    let question_uuid = question.map(str::parse::<QuestionUuid>).transpose()?;
+    let frequency = frequency.map(digest_frequency).transpose()?;
+    SubscriptionChange::from_query(change, question_uuid, frequency).ok_or_else(|| {
+        AppError::InvalidQuery(vec![FieldError {
+            field: "change".to_owned(),
+            message: "is not a change confirmation links make".to_owned(),
+        }])
+    })
+}
+
+pub async fn save_push_subscription(
+    subscription: PushSubscription,
+    question_uuids: Vec<QuestionUuid>,
+    notifications: Option<&Notifications>,
+) -> Result<(), AppError> {
+    let subscription_dao = subscription_dao(notifications)?;
+    let (subscription, question_uuids) =
+        validation::push_subscription(subscription, question_uuids)?;
+    subscription_dao
+        .save_push_subscription(subscription, question_uuids)
+        .await
+        .map_err(|err| AppError::from(err).referenced_in_body())
+}
+
+pub async fn delete_push_subscription(
+    endpoint: String,
+    notifications: Option<&Notifications>,
+) -> Result<(), AppError> {
+    let subscription_dao = subscription_dao(notifications)?;
+    Ok(subscription_dao.delete_push_subscription(endpoint).await?)
+}
+
+// Zapier shows a few to pick fields from.
+const SAMPLE_EVENTS: i64 = 3;
+
+fn rest_hook_dao(
+    rest_hook_dao: Option<&(dyn RestHookDao + Sync + Send)>,
+) -> Result<&(dyn RestHookDao + Sync + Send), AppError> {
+    rest_hook_dao.ok_or_else(|| {
+        AppError::NotImplemented(
+            "REST hooks need Postgres and the webhooks cargo feature.".to_owned(),
+        )
+    })
+}
+
+pub async fn create_rest_hook(
+    target_url: String,
+    event: String,
+    rest_hook_dao: Option<&(dyn RestHookDao + Sync + Send)>,
+) -> Result<RestHook, AppError> {
+    let rest_hook_dao = self::rest_hook_dao(rest_hook_dao)?;
+    let (target_url, event) = validation::rest_hook(target_url, event)?;
+    Ok(rest_hook_dao
+        .create_hook(HookUuid::new_v4(), target_url, event)
+        .await?)
+}
+
+pub async fn delete_rest_hook(
+    hook_uuid: HookUuid,
+    rest_hook_dao: Option<&(dyn RestHookDao + Sync + Send)>,
+) -> Result<(), AppError> {
+    let rest_hook_dao = self::rest_hook_dao(rest_hook_dao)?;
+    Ok(rest_hook_dao.delete_hook(hook_uuid).await?)
+}
+
+// The latest events still in the outbox, so sample fields carry real values, or the
+// built-in sample before there are any.
+pub async fn sample_events(
+    event: &str,
+    rest_hook_dao: Option<&(dyn RestHookDao + Sync + Send)>,
+) -> Result<Vec<OutboxEvent>, AppError> {
+    let rest_hook_dao = self::rest_hook_dao(rest_hook_dao)?;
+    let sample = events::sample_event(event).ok_or_else(|| {
+        AppError::BadRequest(format!(
+            "Unknown event {:?}, expected one of {}.",
+            event,
+            events::EVENT_TYPES.join(", ")
+        ))
+    })?;
+
+    let recent = rest_hook_dao.recent_events(event, SAMPLE_EVENTS).await?;
+    Ok(if recent.is_empty() {
+        vec![sample]
+    } else {
+        recent
+    })
+}
+
+fn user_dao(
+    user_dao: Option<&(dyn UserDao + Sync + Send)>,
+) -> Result<&(dyn UserDao + Sync + Send), AppError> {
+    user_dao.ok_or_else(|| {
+        AppError::NotImplemented("SCIM provisioning needs Postgres or STORAGE=memory.".to_owned())
+    })
+}
+
+// Lists users a page at a time from the 1-based `start_index`, as SCIM pages them.
+// `filter` may only be `userName eq "<name>"`, which identity providers use to look
+// for an account before creating it.
+pub async fn scim_users(
+    filter: Option<&str>,
+    start_index: Option<i64>,
+    count: Option<i64>,
+    user_dao: Option<&(dyn UserDao + Sync + Send)>,
+) -> Result<(Vec<ProvisionedUser>, i64, i64), AppError> {
+    let user_dao = self::user_dao(user_dao)?;
+    let user_name = match filter {
+        Some(filter) => Some(user_name_filter(filter).ok_or_else(|| {
+            AppError::InvalidQuery(vec![FieldError {
+                field: "filter".to_owned(),
+                message: "only `userName eq \"<name>\"` is supported".to_owned(),
+            }])
+        })?),
+        None => None,
+    };
+    let start_index = start_index.unwrap_or(1).max(1);
+    let count = count
+        .unwrap_or(DEFAULT_PER_PAGE.into())
+        .clamp(0, MAX_PER_PAGE.into());
+
+    let (users, total) = user_dao
+        .users(user_name.as_deref(), start_index - 1, count)
+        .await?;
+    Ok((users, total, start_index))
+}
+
+fn user_name_filter(filter: &str) -> Option<String> {
+    let mut parts = filter.trim().splitn(3, ' ');
+    let (attribute, operator, value) = (parts.next()?, parts.next()?, parts.next()?);
+    if !attribute.eq_ignore_ascii_case("userName") || !operator.eq_ignore_ascii_case("eq") {
+        return None;
+    }
+    let value = value.trim().strip_prefix('"')?.strip_suffix('"')?;
+    // Stored trimmed and lowercased, like every address.
+    Some(value.trim().to_lowercase())
+}
+
+pub async fn get_scim_user(
+    user_uuid: UserUuid,
+    user_dao: Option<&(dyn UserDao + Sync + Send)>,
+) -> Result<ProvisionedUser, AppError> {
+    Ok(self::user_dao(user_dao)?.get_user(user_uuid).await?)
+}
+
+pub async fn create_scim_user(
+    attributes: UserAttributes,
+    user_dao: Option<&(dyn UserDao + Sync + Send)>,
+    notifications: Option<&Notifications>,
+) -> Result<ProvisionedUser, AppError> {
+    let user_dao = self::user_dao(user_dao)?;
+    let attributes = validation::user(attributes)?;
+    let user = user_dao.create_user(UserUuid::new_v4(), attributes).await?;
+    stop_emails_if_inactive(&user, notifications).await?;
+    Ok(user)
+}
+
+pub async fn replace_scim_user(
+    user_uuid: UserUuid,
+    attributes: UserAttributes,
+    user_dao: Option<&(dyn UserDao + Sync + Send)>,
+    notifications: Option<&Notifications>,
+) -> Result<ProvisionedUser, AppError> {
+    let user_dao = self::user_dao(user_dao)?;
+    let attributes = validation::user(attributes)?;
+    let user = user_dao.update_user(user_uuid, attributes).await?;
+    stop_emails_if_inactive(&user, notifications).await?;
+    Ok(user)
+}
+
+pub async fn patch_scim_user(
+    user_uuid: UserUuid,
+    operations: Vec<ScimPatchOperation>,
+    user_dao: Option<&(dyn UserDao + Sync + Send)>,
+    notifications: Option<&Notifications>,
+) -> Result<ProvisionedUser, AppError> {
+    let user_dao = self::user_dao(user_dao)?;
+    let user = user_dao.get_user(user_uuid).await?;
+    let attributes = validation::user(patched(user.attributes, operations)?)?;
+    let user = user_dao.update_user(user_uuid, attributes).await?;
+    stop_emails_if_inactive(&user, notifications).await?;
+    Ok(user)
+}
+
+// Applies `add`, `replace` and `remove` operations on the kept attributes. Paths of
+// other attributes, e.g. `name.givenName`, are ignored like they are on creates.
+fn patched(
+    mut attributes: UserAttributes,
+    operations: Vec<ScimPatchOperation>,
+) -> Result<UserAttributes, AppError> {
+    let mut errors = vec![];
+    for (i, operation) in operations.into_iter().enumerate() {
+        let op = operation.op.to_lowercase();
+        let values = match (op.as_str(), operation.path) {
+            ("add" | "replace", Some(path)) => vec![(path, operation.value)],
+            ("add" | "replace", None) => match operation.value {
+                Value::Object(values) => values.into_iter().collect(),
+                _ => {
+                    errors.push(FieldError {
+                        field: format!("Operations[{}].value", i),
+                        message: "must be an object when there is no path".to_owned(),
+                    });
+                    continue;
+                }
+            },
+            ("remove", Some(path)) => vec![(path, Value::Null)],
+            _ => {
+                errors.push(FieldError {
+                    field: format!("Operations[{}].op", i),
+                    message: "must be add, replace, or remove with a path".to_owned(),
+                });
+                continue;
+            }
+        };
+
+        for (path, value) in values {
+            if !set_attribute(&mut attributes, &path, value) {
+                errors.push(FieldError {
+                    field: format!("Operations[{}].value", i),
+                    message: format!("is not a valid {}", path),
+                });
+            }
+        }
+    }
+
+    match errors.is_empty() {
+        true => Ok(attributes),
+        false => Err(AppError::Invalid(errors)),
+    }
+}
+
+// False when `value` does not fit the attribute at `path`.
+fn set_attribute(attributes: &mut UserAttributes, path: &str, value: Value) -> bool {
+    let text = match &value {
+        Value::String(text) => Some(Some(text.clone())),
+        Value::Null => Some(None),
+        _ => None,
+    };
+    match (path.to_lowercase().as_str(), text) {
+        ("active", _) => {
+            let active = match value {
+                Value::Bool(active) => Some(active),
+                // Entra ID sends booleans as strings.
+                Value::String(active) => active.to_lowercase().parse().ok(),
+                _ => None,
+            };
+            active.map(|active| attributes.active = active).is_some()
+        }
+        ("username", Some(Some(user_name))) => {
+            attributes.user_name = user_name;
+            true
+        }
+        ("externalid", Some(external_id)) => {
+            attributes.external_id = external_id;
+            true
+        }
+        ("displayname", Some(display_name)) => {
+            attributes.display_name = display_name;
+            true
+        }
+        ("username" | "externalid" | "displayname", _) => false,
+        _ => true,
+    }
+}
+
+// Deprovisioned users get no more email, as if they had opted out themselves; being
+// activated again does not opt them back in.
+async fn stop_emails_if_inactive(
+    user: &ProvisionedUser,
+    notifications: Option<&Notifications>,
+) -> Result<(), AppError> {
+    if let (false, Some(notifications)) = (user.attributes.active, notifications) {
+        notifications
+            .subscription_dao
+            .set_opted_out(user.attributes.user_name.clone(), true)
+            .await?;
+    }
+    Ok(())
+}
+
+fn attachments(attachments: Option<&Attachments>) -> Result<&Attachments, AppError> {
+    attachments.ok_or_else(|| {
+        AppError::NotImplemented("Attachments need Postgres or STORAGE=memory.".to_owned())
+    })
+}
+
+// Store failures are logged here; clients are only told to retry.
+fn store_unavailable(err: StoreError) -> AppError {
+    error!("Attachment store error: {}", err);
+    AppError::ServiceUnavailable(
+        "The attachment store is unavailable. Please try again later.".to_owned(),
+    )
+}
+
+fn new_attachment(
+    question_uuid: QuestionUuid,
+    answer_uuid: Option<AnswerUuid>,
+    (filename, content_type): (String, String),
+    size_bytes: i64,
+) -> NewAttachment {
+    let attachment_uuid = AttachmentUuid::new_v4();
+    NewAttachment {
+        attachment_uuid,
+        question_uuid,
+        answer_uuid,
+        filename,
+        content_type,
+        size_bytes,
+        storage_key: attachments::storage_key(attachment_uuid),
+        status: AttachmentStatus::Pending,
+    }
+}
+
+// The row is written first, so an upload to an unknown question never reaches the
+// store, and removed again if storing the bytes fails.
+pub async fn upload_attachment(
+    question_uuid: QuestionUuid,
+    answer_uuid: Option<AnswerUuid>,
+    filename: String,
+    content_type: String,
+    bytes: Vec<u8>,
+    config: &AppConfig,
+    attachments: Option<&Attachments>,
+) -> Result<Attachment, AppError> {
+    let attachments = self::attachments(attachments)?;
+    let size_bytes = bytes.len() as i64;
+    let max_bytes = config.body_limits.attachment.as_u64();
+    let checked = validation::attachment(filename, content_type, size_bytes, max_bytes)?;
+    let attachment = attachments
+        .attachment_dao
+        .create_attachment(new_attachment(
+            question_uuid,
+            answer_uuid,
+            checked,
+            size_bytes,
+        ))
+        .await?;
+
+    let stored = attachments
+        .object_store
+        .put(&attachment.storage_key, &attachment.content_type, bytes)
+        .await;
+    if let Err(err) = stored {
+        if let Err(err) = attachments
+            .attachment_dao
+            .delete_attachment(attachment.attachment_uuid)
+            .await
+        {
+            warn!(
+                "Could not remove attachment {} after its upload failed: {}",
+                attachment.attachment_uuid, err
+            );
+        }
+        return Err(store_unavailable(err));
+    }
+
+    mark_uploaded(attachments, attachment.attachment_uuid, size_bytes).await
+}
+
+// Scanned first while a content scanner is configured, which then makes the thumbnails
+// of clean images.
+async fn mark_uploaded(
+    attachments: &Attachments,
+    attachment_uuid: AttachmentUuid,
+    size_bytes: i64,
+) -> Result<Attachment, AppError> {
+    let status = match attachments.scans {
+        Some(_) => AttachmentStatus::Scanning,
+        None => AttachmentStatus::Ready,
+    };
+    let attachment = attachments
+        .attachment_dao
+        .mark_uploaded(attachment_uuid, size_bytes, status)
+        .await?;
+    match (&attachments.scans, &attachments.thumbnails) {
+        (Some(scans), _) => scans.wake(),
+        (None, Some(thumbnails)) => thumbnails.enqueue(attachment.clone()),
+        (None, None) => {}
+    }
+    Ok(attachment)
+}
+
+// A pending attachment and the URL the client PUTs its bytes to; it is listed once
+// `complete_attachment` has checked the upload.
+pub async fn create_attachment_upload(
+    question_uuid: QuestionUuid,
+    answer_uuid: Option<AnswerUuid>,
+    filename: String,
+    content_type: String,
+    size_bytes: i64,
+    config: &AppConfig,
+    attachments: Option<&Attachments>,
+) -> Result<(Attachment, String), AppError> {
+    let attachments = self::attachments(attachments)?;
+    let max_bytes = config.body_limits.attachment.as_u64();
+    let checked = validation::attachment(filename, content_type, size_bytes, max_bytes)?;
+    let new_attachment = new_attachment(question_uuid, answer_uuid, checked, size_bytes);
+    let Some(upload_url) = attachments
+        .object_store
+        .upload_url(&new_attachment.storage_key, config.attachment_url_ttl)
+    else {
+        return Err(AppError::NotImplemented(
+            "Pre-signed uploads need OBJECT_STORE=s3; send the file as multipart/form-data instead."
+                .to_owned(),
+        ));
+    };
+
+    let attachment = attachments
+        .attachment_dao
+        .create_attachment(new_attachment)
+        .await?;
+    Ok((attachment, upload_url))
+}
+
+// The size is read back from the store, since the client could have uploaded anything
+// to the URL. Oversized objects are deleted along with their attachment.
+pub async fn complete_attachment(
+    attachment_uuid: AttachmentUuid,
+    config: &AppConfig,
+    attachments: Option<&Attachments>,
+) -> Result<Attachment, AppError> {
+    let attachments = self::attachments(attachments)?;
+    let max_bytes = config.body_limits.attachment.as_u64();
+    let pending = attachments
+        .attachment_dao
+        .get_attachment(attachment_uuid)
+        .await?
+        .filter(|attachment| attachment.status == AttachmentStatus::Pending)
+        .ok_or_else(|| {
+            DBError::NotFound(
+                Entity::Attachment,
+                format!("No upload of attachment {} is pending", attachment_uuid),
+            )
+        })?;
+
+    let size = attachments
+        .object_store
+        .size(&pending.storage_key)
+        .await
+        .map_err(store_unavailable)?;
+    match size {
+        None => Err(AppError::BadRequest(format!(
+            "Nothing has been uploaded for attachment {} yet.",
+            attachment_uuid
+        ))),
+        Some(size) if size > max_bytes => {
+            attachments
+                .object_store
+                .delete(&pending.storage_key)
+                .await
+                .map_err(store_unavailable)?;
+            attachments
+                .attachment_dao
+                .delete_attachment(attachment_uuid)
+                .await?;
+            Err(AppError::BadRequest(format!(
+                "The upload is {} bytes, past the limit of {}; it has been discarded.",
+                size, max_bytes
+            )))
+        }
+        Some(size) => mark_uploaded(attachments, attachment_uuid, size as i64).await,
+    }
+}
+
+// Only ready attachments are served: pending ones may not have their bytes yet, and
+// scanning or quarantined ones are not known to be safe.
+pub async fn get_attachment(
+    attachment_uuid: AttachmentUuid,
+    attachments: Option<&Attachments>,
+) -> Result<Attachment, AppError> {
+    let attachments = self::attachments(attachments)?;
+    attachments
+        .attachment_dao
+        .get_attachment(attachment_uuid)
+        .await?
+        .filter(|attachment| attachment.status == AttachmentStatus::Ready)
+        .ok_or_else(|| {
+            AppError::Database(DBError::NotFound(
+                Entity::Attachment,
+                format!("Attachment {} does not exist", attachment_uuid),
+            ))
+        })
+}
+
+// E.g. a thumbnail; only images that have been thumbnailed have any.
+pub async fn get_attachment_variant(
+    attachment_uuid: AttachmentUuid,
+    name: &str,
+    attachments: Option<&Attachments>,
+) -> Result<AttachmentVariant, AppError> {
+    let attachment = get_attachment(attachment_uuid, attachments).await?;
+    attachment
+        .variants
+        .into_iter()
+        .find(|variant| variant.name == name)
+        .ok_or_else(|| {
+            AppError::Database(DBError::NotFound(
+                Entity::Attachment,
+                format!("Attachment {} has no variant {}", attachment_uuid, name),
+            ))
+        })
+}
+
+pub async fn read_object(
+    storage_key: &str,
+    attachments: Option<&Attachments>,
+) -> Result<Vec<u8>, AppError> {
+    let attachments = self::attachments(attachments)?;
+    attachments
+        .object_store
+        .get(storage_key)
+        .await
+        .map_err(store_unavailable)
+}
+
+// What a user's avatar resolves to, first match wins.
+pub enum Avatar {
+    Uploaded {
+        bytes: Vec<u8>,
+        content_type: &'static str,
+    },
+    Gravatar(String),
+    Identicon(String),
+}
+
+// There are no user accounts, so users are their email addresses. `gravatar` is false
+// when Gravatar itself falls back here, so it is not asked again.
+pub async fn resolve_avatar(
+    email: String,
+    size: u32,
+    gravatar: bool,
+    config: &AppConfig,
+    attachments: Option<&Attachments>,
+) -> Result<Avatar, AppError> {
+    let email = validation::email(email)?;
+    let email_hash = avatars::email_hash(&email);
+
+    if let Some(attachments) = attachments {
+        match attachments
+            .object_store
+            .get(&avatars::storage_key(&email_hash))
+            .await
+        {
+            Ok(bytes) => {
+                return Ok(Avatar::Uploaded {
+                    content_type: avatars::image_type(&bytes).unwrap_or("application/octet-stream"),
+                    bytes,
+                })
+            }
+            Err(StoreError::NotFound(_)) => {}
+            Err(err) => return Err(store_unavailable(err)),
+        }
+    }
+
+    if gravatar && config.gravatar {
+        // Without a public URL Gravatar cannot reach this API, so it draws its own.
+        let default = match &config.public_url {
+            Some(public_url) => format!(
+                "{}/users/{}/avatar?gravatar=false",
+                public_url.trim_end_matches('/'),
+                avatars::percent_encode(&email)
+            ),
+            None => "identicon".to_owned(),
+        };
+        return Ok(Avatar::Gravatar(avatars::gravatar_url(
+            &email_hash,
+            size,
+            &default,
+        )));
+    }
+
+    Ok(Avatar::Identicon(avatars::identicon(&email_hash)))
+}
+
+pub async fn set_avatar(
+    email: String,
+    bytes: Vec<u8>,
+    attachments: Option<&Attachments>,
+) -> Result<(), AppError> {
+    let attachments = self::attachments(attachments)?;
+    let email = validation::email(email)?;
+    let Some(content_type) = avatars::image_type(&bytes) else {
+        return Err(AppError::BadRequest(
+            "The avatar must be a PNG, JPEG, GIF or WebP image.".to_owned(),
+        ));
+    };
+
+    attachments
+        .object_store
+        .put(
+            &avatars::storage_key(&avatars::email_hash(&email)),
+            content_type,
+            bytes,
+        )
+        .await
+        .map_err(store_unavailable)
+}
+
+// Back to Gravatar or the identicon.
+pub async fn delete_avatar(
+    email: String,
+    attachments: Option<&Attachments>,
+) -> Result<(), AppError> {
+    let attachments = self::attachments(attachments)?;
+    let email = validation::email(email)?;
+
+    attachments
+        .object_store
+        .delete(&avatars::storage_key(&avatars::email_hash(&email)))
+        .await
+        .map_err(store_unavailable)
+}
+
+// For `?include=attachments`, one query for the page. Each question's attachments are
+// oldest first.
+pub async fn get_attachments_of_questions(
+    question_uuids: &[QuestionUuid],
+    attachments: Option<&Attachments>,
+) -> Result<HashMap<QuestionUuid, Vec<Attachment>>, AppError> {
+    let attachments = self::attachments(attachments)?;
+    let mut by_question: HashMap<QuestionUuid, Vec<Attachment>> = HashMap::new();
+    if question_uuids.is_empty() {
+        return Ok(by_question);
+    }
+
+    for attachment in attachments
+        .attachment_dao
+        .attachments_of_questions(question_uuids)
+        .await?
+    {
+        by_question
+            .entry(attachment.question_uuid)
+            .or_default()
+            .push(attachment);
+    }
+    Ok(by_question)
+}
+
+pub async fn get_attachments_of_answers(
+    answer_uuids: &[AnswerUuid],
+    attachments: Option<&Attachments>,
+) -> Result<HashMap<AnswerUuid, Vec<Attachment>>, AppError> {
+    let attachments = self::attachments(attachments)?;
+    let mut by_answer: HashMap<AnswerUuid, Vec<Attachment>> = HashMap::new();
+    if answer_uuids.is_empty() {
+        return Ok(by_answer);
+    }
+
+    for attachment in attachments
+        .attachment_dao
+        .attachments_of_answers(answer_uuids)
+        .await?
+    {
+        if let Some(answer_uuid) = attachment.answer_uuid {
+            by_answer.entry(answer_uuid).or_default().push(attachment);
+        }
+    }
+    Ok(by_answer)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        confirmations::ConfirmationTokens,
+        embedding::HashingEmbedder,
+        models::{empty_metadata, ActivityKind, ImportedQuestion, ResponseStats},
+        moderation::HeuristicModerator,
+        notifications::ConsoleMailer,
+        persistence::{
+            embedding::EmbeddingDao,
+            memory::{
+                InMemoryActivityDao, InMemoryAnswerDao, InMemoryMergeDao, InMemoryModerationDao,
+                InMemoryQuestionDao, InMemorySubscriptionDao, InMemoryUserDao, MemoryStore,
+            },
+            unit_of_work::UnitOfWork,
+        },
+        testing::{AnswerDaoMock, QuestionDaoMock},
+    };
+    use chrono::{DateTime, Duration, NaiveDate, TimeZone, Utc};
+    use rocket::http::Status;
+    use std::sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    };
+    use tokio::sync::Mutex;
+
+    #[tokio::test]
+    async fn create_question_should_return_question() {
+        let title = "title".to_owned();
+        let description = Some("description".to_owned());
+        let question = Question {
+            title: title.clone(),
+            description: description.clone(),
+            metadata: empty_metadata(),
+        };
+        let question_detail = QuestionDetail {
+            title,
+            description,
+            metadata: empty_metadata(),
+            question_uuid: QuestionUuid::new_v4(),
+            created_at: DateTime::UNIX_EPOCH,
+            updated_at: DateTime::UNIX_EPOCH,
+            last_activity_at: DateTime::UNIX_EPOCH,
+            version: 1,
+            answer_count: 0,
+        };
+
+        let mut question_dao = QuestionDaoMock::new();
+        question_dao.mock_create_question_response(Ok(question_detail.clone()));
+
+        let question_dao: Box<dyn QuestionDao + Sync + Send> = Box::new(question_dao);
+
+        let result = create_question(
+            question,
+            None,
+            question_dao.as_ref(),
+            WriteHooks::default(),
+            &SpamContext::default(),
+        )
+        .await;
+        assert!(result.is_ok());
+        assert_eq!(result.unwrap(), Moderated::Published(question_detail));
+    }
+
+    #[tokio::test]
+    async fn create_question_should_return_error() {
+        let question = Question {
+            title: "title".to_owned(),
+            description: Some("description".to_owned()),
+            metadata: empty_metadata(),
+        };
+        let mut question_dao = QuestionDaoMock::new();
+        question_dao.mock_create_question_response(Err(DBError::Other("".into())));
+        let question_dao: Box<dyn QuestionDao + Sync + Send> = Box::new(question_dao);
+
+        let result = create_question(
+            question,
+            None,
+            question_dao.as_ref(),
+            WriteHooks::default(),
+            &SpamContext::default(),
+        )
+        .await;
+        assert!(result.is_err());
+        assert_eq!(result.unwrap_err().status(), Status::InternalServerError);
+    }
+
+    #[tokio::test]
+    async fn create_question_should_reject_blank_fields_before_the_dao() {
+        let question = Question {
+            title: " ".to_owned(),
+            description: Some("description".to_owned()),
+            metadata: empty_metadata(),
+        };
+        // Nothing is mocked, so reaching the DAO would panic.
+        let question_dao = QuestionDaoMock::new();
+
+        let result = create_question(
+            question,
+            None,
+            &question_dao,
+            WriteHooks::default(),
+            &SpamContext::default(),
+        )
+        .await;
+
+        match result {
+            Err(AppError::Invalid(errors)) => assert_eq!(
+                errors,
+                vec![FieldError {
+                    field: "title".to_owned(),
+                    message: "must not be empty".to_owned(),
+                }]
+            ),
+            _ => panic!("Expected Invalid"),
+        }
+    }
+
+    #[tokio::test]
+    async fn create_question_should_hold_flagged_content_before_the_dao() {
+        let moderation = Moderation {
+            provider: Some(Box::new(HeuristicModerator::new(vec!["scam".to_owned()]))),
+            spam_checker: None,
+            moderation_dao: Arc::new(InMemoryModerationDao::new(MemoryStore::new())),
+            threshold: 0.8,
+        };
+        let question = Question {
+            title: "A scam".to_owned(),
+            description: None,
+            metadata: empty_metadata(),
+        };
+        // Nothing is mocked, so reaching the DAO would panic.
+        let question_dao = QuestionDaoMock::new();
+
+        let result = create_question(
+            question,
+            None,
+            &question_dao,
+            WriteHooks {
+                moderation: Some(&moderation),
+                ..Default::default()
+            },
+            &SpamContext::default(),
+        )
+        .await
+        .unwrap();
+
+        let Moderated::Held(item) = result else {
+            panic!("Expected Held");
+        };
+        assert_eq!(item.score, 1.0);
+        assert_eq!(
+            moderation
+                .moderation_dao
+                .held_items(Window {
+                    offset: 0,
+                    limit: 10
+                })
+                .await
+                .unwrap()
+                .items,
+            [*item]
+        );
+    }
+
+    #[tokio::test]
+    async fn create_question_should_subscribe_its_author_once_confirmed() {
+        let store = MemoryStore::new();
+        let subscription_dao = Arc::new(InMemorySubscriptionDao::new(store.clone()));
+        let notifications = Notifications::spawn(
+            subscription_dao.clone(),
+            Arc::new(ConsoleMailer),
+            None,
+            None,
+            ConfirmationTokens::new(b"secret", "https://qa.example.com"),
+        );
+        let question = Question {
+            title: "title".to_owned(),
+            description: None,
+            metadata: empty_metadata(),
+        };
+
+        let question = create_question(
+            question,
+            Some(" Author@Example.com".to_owned()),
+            &InMemoryQuestionDao::new(store),
+            WriteHooks {
+                notifications: Some(&notifications),
+                ..Default::default()
+            },
+            &SpamContext::default(),
+        )
+        .await
+        .unwrap();
+        let question_uuid = unmoderated(question).question_uuid;
+        let subscribers = || async {
+            subscription_dao
+                .subscribers(question_uuid)
+                .await
+                .unwrap()
+                .unwrap()
+                .emails
+        };
+        assert!(subscribers().await.is_empty());
+
+        let change = SubscriptionChange::Subscribe(question_uuid);
+        let forged = ConfirmationTokens::new(b"forged", "https://qa.example.com").token(
+            change,
+            "author@example.com",
+            Utc::now(),
+        );
+        let result = change_subscription(
+            "author@example.com".to_owned(),
+            change,
+            Some(forged),
+            Some(&notifications),
+        )
+        .await;
+        assert_eq!(result.unwrap_err().status(), Status::Forbidden);
+        assert!(subscribers().await.is_empty());
+
+        let token = notifications
+            .tokens
+            .token(change, "author@example.com", Utc::now());
+        let result = change_subscription(
+            "Author@Example.com".to_owned(),
+            change,
+            Some(token),
+            Some(&notifications),
+        )
+        .await;
+        assert_eq!(result.unwrap(), Confirmation::Made);
+        assert_eq!(subscribers().await, vec!["author@example.com".to_owned()]);
+    }
+
+    #[tokio::test]
+    async fn digests_should_start_once_confirmed() {
+        let subscription_dao = Arc::new(InMemorySubscriptionDao::new(MemoryStore::new()));
+        let notifications = Notifications::spawn(
+            subscription_dao.clone(),
+            Arc::new(ConsoleMailer),
+            None,
+            None,
+            ConfirmationTokens::new(b"secret", "https://qa.example.com"),
+        );
+        let later = Utc::now() + Duration::weeks(2);
+
+        let result = set_digest_frequency(
+            "reader@example.com".to_owned(),
+            Some("weekly".to_owned()),
+            None,
+            Some(&notifications),
+        )
+        .await;
+        assert_eq!(result.unwrap(), Confirmation::Requested);
+        assert!(subscription_dao
+            .claim_due_digests(later)
+            .await
+            .unwrap()
+            .is_empty());
+
+        let change = SubscriptionChange::Digest(Some(DigestFrequency::Weekly));
+        let token = notifications
+            .tokens
+            .token(change, "reader@example.com", Utc::now());
+        let result = set_digest_frequency(
+            "reader@example.com".to_owned(),
+            Some("daily".to_owned()),
+            Some(token.clone()),
+            Some(&notifications),
+        )
+        .await;
+        assert_eq!(result.unwrap_err().status(), Status::Forbidden);
+
+        let result = set_digest_frequency(
+            "reader@example.com".to_owned(),
+            Some("weekly".to_owned()),
+            Some(token),
+            Some(&notifications),
+        )
+        .await;
+        assert_eq!(result.unwrap(), Confirmation::Made);
+        let due = subscription_dao.claim_due_digests(later).await.unwrap();
+        assert_eq!(due.len(), 1);
+        assert_eq!(due[0].email, "reader@example.com");
+    }
+
+    #[tokio::test]
+    async fn subscriptions_should_need_notifications() {
+        let result = change_subscription(
+            "a@example.com".to_owned(),
+            SubscriptionChange::Subscribe(QuestionUuid::new_v4()),
+            None,
+            None,
+        )
+        .await;
+
+        assert_eq!(result.unwrap_err().status(), Status::NotImplemented);
+    }
+
+    #[tokio::test]
+    async fn deactivated_users_should_stop_getting_email() {
+        let store = MemoryStore::new();
+        let subscription_dao = Arc::new(InMemorySubscriptionDao::new(store.clone()));
+        let notifications = Notifications::spawn(
+            subscription_dao.clone(),
+            Arc::new(ConsoleMailer),
+            None,
+            None,
+            ConfirmationTokens::new(b"secret", "https://qa.example.com"),
+        );
+        let question = InMemoryQuestionDao::new(store.clone())
+            .create_question(Question {
+                title: "title".to_owned(),
+                description: None,
+                metadata: empty_metadata(),
+            })
+            .await
+            .unwrap();
+        subscription_dao
+            .subscribe(question.question_uuid, "ada@example.com".to_owned())
+            .await
+            .unwrap();
+        let user_dao = InMemoryUserDao::new(store);
+        let user = create_scim_user(
+            UserAttributes {
+                user_name: " Ada@Example.com".to_owned(),
+                external_id: None,
+                display_name: Some("Ada".to_owned()),
+                active: true,
+            },
+            Some(&user_dao),
+            Some(&notifications),
+        )
+        .await
+        .unwrap();
+        assert_eq!(user.attributes.user_name, "ada@example.com");
+
+        let operation = |op: &str, path: Option<&str>, value: Value| ScimPatchOperation {
+            op: op.to_owned(),
+            path: path.map(str::to_owned),
+            value,
+        };
+        let result = patch_scim_user(
+            user.user_uuid,
+            vec![
+                operation("move", Some("active"), Value::Null),
+                operation("replace", Some("active"), serde_json::json!(0)),
+            ],
+            Some(&user_dao),
+            Some(&notifications),
+        )
+        .await;
+        let Err(AppError::Invalid(errors)) = result else {
+            panic!("Expected Invalid");
+        };
+        assert_eq!(errors[0].field, "Operations[0].op");
+        assert_eq!(errors[1].field, "Operations[1].value");
+
+        // As Entra ID sends it.
+        let user = patch_scim_user(
+            user.user_uuid,
+            vec![operation(
+                "Replace",
+                None,
+                serde_json::json!({ "active": "False", "name.givenName": "Ada" }),
+            )],
+            Some(&user_dao),
+            Some(&notifications),
+        )
+        .await
+        .unwrap();
+        assert!(!user.attributes.active);
+        assert_eq!(user.attributes.display_name.as_deref(), Some("Ada"));
+        let subscribers = subscription_dao
+            .subscribers(question.question_uuid)
+            .await
+            .unwrap()
+            .unwrap();
+        assert!(subscribers.emails.is_empty());
+    }
+
+    #[test]
+    fn user_name_filter_should_only_take_user_name_equality() {
+        assert_eq!(
+            user_name_filter(r#"userName eq "Ada@Example.com""#).as_deref(),
+            Some("ada@example.com")
+        );
+        assert_eq!(
+            user_name_filter(r#"USERNAME EQ "a b@example.com""#).as_deref(),
+            Some("a b@example.com")
+        );
+        assert_eq!(user_name_filter(r#"userName sw "ada""#), None);
+        assert_eq!(user_name_filter(r#"emails eq "ada@example.com""#), None);
+        assert_eq!(user_name_filter("userName eq ada@example.com"), None);
+    }
+
+    #[tokio::test]
+    async fn get_questions_should_return_questions() {
+        let questions = vec![QuestionDetail {
+            title: "title".to_owned(),
+            description: Some("description".to_owned()),
+            metadata: empty_metadata(),
+            question_uuid: QuestionUuid::new_v4(),
+            created_at: DateTime::UNIX_EPOCH,
+            updated_at: DateTime::UNIX_EPOCH,
+            last_activity_at: DateTime::UNIX_EPOCH,
+            version: 1,
+            answer_count: 0,
+        }];
+        let mut question_dao = QuestionDaoMock::new();
+        question_dao.mock_get_questions_response(Ok(questions.clone()));
+        let question_dao: Box<dyn QuestionDao + Sync + Send> = Box::new(question_dao);
+
+        let result = get_questions(
+            question_dao.as_ref(),
+            false,
+            QuestionSort::Oldest,
+            &QuestionFilter::default(),
+            Window {
+                offset: 0,
+                limit: 50,
+            },
+        )
+        .await;
+        assert!(result.is_ok());
+        assert_eq!(result.unwrap().items, questions);
+    }
+
+    #[tokio::test]
+    async fn get_questions_should_return_error() {
+        let mut question_dao = QuestionDaoMock::new();
+        question_dao.mock_get_questions_response(Err(DBError::Other("".into())));
+        let question_dao: Box<dyn QuestionDao + Sync + Send> = Box::new(question_dao);
+        let result = get_questions(
+            question_dao.as_ref(),
+            false,
+            QuestionSort::Oldest,
+            &QuestionFilter::default(),
+            Window {
+                offset: 0,
+                limit: 50,
+            },
+        )
+        .await;
+        assert!(result.is_err());
+        assert_eq!(result.unwrap_err().status(), Status::InternalServerError);
+    }
+
+    #[tokio::test]
+    async fn get_questions_should_return_service_unavailable() {
+        let mut question_dao = QuestionDaoMock::new();
+        question_dao.mock_get_questions_response(Err(DBError::Unavailable));
+        let question_dao: Box<dyn QuestionDao + Sync + Send> = Box::new(question_dao);
+        let result = get_questions(
+            question_dao.as_ref(),
+            false,
+            QuestionSort::Oldest,
+            &QuestionFilter::default(),
+            Window {
+                offset: 0,
+                limit: 50,
+            },
+        )
+        .await;
+        assert!(result.is_err());
+        assert_eq!(result.unwrap_err().status(), Status::ServiceUnavailable);
+    }
+
+    #[tokio::test]
+    async fn stream_questions_should_return_questions() {
+        let questions = vec![QuestionDetail {
+            title: "title".to_owned(),
+            description: Some("description".to_owned()),
+            metadata: empty_metadata(),
+            question_uuid: QuestionUuid::new_v4(),
+            created_at: DateTime::UNIX_EPOCH,
+            updated_at: DateTime::UNIX_EPOCH,
+            last_activity_at: DateTime::UNIX_EPOCH,
+            version: 1,
+            answer_count: 0,
+        }];
+        let mut question_dao = QuestionDaoMock::new();
+        question_dao.mock_get_questions_response(Ok(questions.clone()));
+        let question_dao: Box<dyn QuestionDao + Sync + Send> = Box::new(question_dao);
+
+        let rows = stream_questions(question_dao.as_ref(), None, QuestionFilter::default())
+            .await
+            .unwrap();
+        let result: Vec<_> = rows.collect().await;
+        assert_eq!(result.len(), 1);
+        assert_eq!(result[0].as_ref().unwrap(), &questions[0]);
+    }
+
+    #[tokio::test]
+    async fn stream_questions_should_return_service_unavailable() {
+        let mut question_dao = QuestionDaoMock::new();
+        question_dao.mock_get_questions_response(Err(DBError::Unavailable));
+        let question_dao: Box<dyn QuestionDao + Sync + Send> = Box::new(question_dao);
+
+        let result = stream_questions(question_dao.as_ref(), None, QuestionFilter::default()).await;
+        assert!(result.is_err());
+        assert_eq!(result.err().unwrap().status(), Status::ServiceUnavailable);
+    }
+
+    #[tokio::test]
+    async fn delete_question_should_succeed() {
+        let mut question_dao = QuestionDaoMock::new();
+        question_dao.mock_delete_question_response(Ok(()));
+        let question_dao: Box<dyn QuestionDao + Sync + Send> = Box::new(question_dao);
+
+        let result = delete_question(QuestionUuid::new_v4(), question_dao.as_ref()).await;
+        assert!(result.is_ok());
+        assert_eq!(result.unwrap(), ());
+    }
+
+    #[tokio::test]
+    async fn delete_question_should_return_error() {
+        let mut question_dao = QuestionDaoMock::new();
+        question_dao.mock_delete_question_response(Err(DBError::InvalidUUID("".to_owned())));
+        let question_dao: Box<dyn QuestionDao + Sync + Send> = Box::new(question_dao);
+        let result = delete_question(QuestionUuid::new_v4(), question_dao.as_ref()).await;
+        assert!(result.is_err());
+        assert_eq!(result.unwrap_err().status(), Status::BadRequest);
+    }
+
+    #[tokio::test]
+    async fn create_answer_should_return_answer() {
+        let mut answer_dao = AnswerDaoMock::new();
+        let answer = AnswerDetail {
+            answer_uuid: AnswerUuid::new_v4(),
+            question_uuid: QuestionUuid::new_v4(),
+            content: "content".to_owned(),
+            created_at: DateTime::UNIX_EPOCH,
+            updated_at: DateTime::UNIX_EPOCH,
+            version: 1,
+        };
+        answer_dao.mock_create_answer(Ok(answer.clone()));
+        let answer_dao: Box<dyn AnswerDao + Sync + Send> = Box::new(answer_dao);
+
+        let result = create_answer(
+            Answer {
+                question_uuid: QuestionUuid::new_v4(),
+                content: "content".to_owned(),
+            },
+            false,
+            answer_dao.as_ref(),
+            WriteHooks::default(),
+            &SpamContext::default(),
+        )
+        .await;
+        assert!(result.is_ok());
+        assert_eq!(result.unwrap(), (Moderated::Published(answer), vec![]));
+    }
+
+    #[tokio::test]
+    async fn create_answer_should_return_bad_request_error() {
+        let mut answer_dao = AnswerDaoMock::new();
+        answer_dao.mock_create_answer(Err(DBError::InvalidUUID("".to_owned())));
+        let answer_dao: Box<dyn AnswerDao + Sync + Send> = Box::new(answer_dao);
+        let result = create_answer(
+            Answer {
+                question_uuid: QuestionUuid::new_v4(),
+                content: "content".to_owned(),
+            },
+            false,
+            answer_dao.as_ref(),
+            WriteHooks::default(),
+            &SpamContext::default(),
+        )
+        .await;
+
+        assert!(result.is_err());
+        assert_eq!(result.unwrap_err().status(), Status::BadRequest);
+    }
+
+    #[tokio::test]
+    async fn create_answer_should_return_internal_error() {
+        let mut answer_dao = AnswerDaoMock::new();
+        answer_dao.mock_create_answer(Err(DBError::Other(Box::new(std::io::Error::other(
+            "Oh no!",
+        )))));
+        let answer_dao: Box<dyn AnswerDao + Sync + Send> = Box::new(answer_dao);
+        let result = create_answer(
+            Answer {
+                question_uuid: QuestionUuid::new_v4(),
+                content: "content".to_owned(),
+            },
+            false,
+            answer_dao.as_ref(),
+            WriteHooks::default(),
+            &SpamContext::default(),
+        )
+        .await;
+        assert!(result.is_err());
+        assert_eq!(result.unwrap_err().status(), Status::InternalServerError);
+    }
+
+    #[tokio::test]
+    async fn get_answers_should_return_answers() {
+        let answers = vec![AnswerDetail {
+            answer_uuid: AnswerUuid::new_v4(),
+            question_uuid: QuestionUuid::new_v4(),
+            content: "content".to_owned(),
+            created_at: DateTime::UNIX_EPOCH,
+            updated_at: DateTime::UNIX_EPOCH,
+            version: 1,
+        }];
+
+        let mut answer_dao = AnswerDaoMock::new();
+        answer_dao.mock_get_answers(Ok(answers.clone()));
+        let answer_dao: Box<dyn AnswerDao + Sync + Send> = Box::new(answer_dao);
+
+        let result = get_answers(
+            QuestionUuid::new_v4(),
+            answer_dao.as_ref(),
+            false,
+            AnswerSort::Oldest,
+            Window {
+                offset: 0,
+                limit: 50,
+            },
+        )
+        .await;
+        assert!(result.is_ok());
+        assert_eq!(result.unwrap().items, answers);
+    }
+
+    #[tokio::test]
+    async fn get_answers_should_return_error() {
+        let mut answer_dao = AnswerDaoMock::new();
+        answer_dao.mock_get_answers(Err(DBError::InvalidUUID("".to_owned())));
+        let answer_dao: Box<dyn AnswerDao + Sync + Send> = Box::new(answer_dao);
+
+        let result = get_answers(
+            QuestionUuid::new_v4(),
+            answer_dao.as_ref(),
+            false,
+            AnswerSort::Oldest,
+            Window {
+                offset: 0,
+                limit: 50,
+            },
+        )
+        .await;
+        assert!(result.is_err());
+        assert_eq!(result.unwrap_err().status(), Status::BadRequest);
+    }
+
+    #[tokio::test]
+    async fn get_answers_of_questions_should_group_oldest_first() {
+        let question_uuid = QuestionUuid::new_v4();
+        let answer = |question_uuid: QuestionUuid, created_at: DateTime<Utc>| AnswerDetail {
+            answer_uuid: AnswerUuid::new_v4(),
+            question_uuid,
+            content: "content".to_owned(),
+            created_at,
+            updated_at: created_at,
+            version: 1,
+        };
+        let newer = answer(question_uuid, DateTime::UNIX_EPOCH + Duration::days(1));
+        let older = answer(question_uuid, DateTime::UNIX_EPOCH);
+        let other = answer(QuestionUuid::new_v4(), DateTime::UNIX_EPOCH);
+
+        let mut answer_dao = AnswerDaoMock::new();
+        answer_dao.mock_get_answers(Ok(vec![newer.clone(), other.clone(), older.clone()]));
+
+        let result =
+            get_answers_of_questions(&[question_uuid, other.question_uuid], &answer_dao, None)
+                .await
+                .unwrap();
+
+        assert_eq!(result[&question_uuid], vec![older, newer]);
+        assert_eq!(result[&other.question_uuid], vec![other]);
+    }
+
+    #[tokio::test]
+    async fn stream_answers_should_return_error() {
+        let mut answer_dao = AnswerDaoMock::new();
+        answer_dao.mock_get_answers(Err(DBError::InvalidUUID("".to_owned())));
+        let answer_dao: Box<dyn AnswerDao + Sync + Send> = Box::new(answer_dao);
+
+        let result = stream_answers(QuestionUuid::new_v4(), answer_dao.as_ref(), None).await;
+        assert!(result.is_err());
+        assert_eq!(result.err().unwrap().status(), Status::BadRequest);
+    }
+
+    #[tokio::test]
+    async fn delete_answer_should_succeed() {
+        let mut answer_dao = AnswerDaoMock::new();
+        answer_dao.mock_delete_answer(Ok(()));
+        let answer_dao: Box<dyn AnswerDao + Sync + Send> = Box::new(answer_dao);
+
+        let result = delete_answer(AnswerUuid::new_v4(), answer_dao.as_ref()).await;
+        assert!(result.is_ok());
+    }
+
+    #[tokio::test]
+    async fn delete_answer_should_return_error() {
+        let mut answer_dao = AnswerDaoMock::new();
+        answer_dao.mock_delete_answer(Err(DBError::InvalidUUID("".to_owned())));
+        let answer_dao: Box<dyn AnswerDao + Sync + Send> = Box::new(answer_dao);
+
+        let result = delete_answer(AnswerUuid::new_v4(), answer_dao.as_ref()).await;
+        assert!(result.is_err());
+        assert_eq!(result.unwrap_err().status(), Status::BadRequest);
+    }
+
+    fn question_update(version: Option<i64>) -> QuestionUpdate {
+        QuestionUpdate {
+            title: "title".to_owned(),
+            description: Some("description".to_owned()),
+            metadata: empty_metadata(),
+            version,
+        }
+    }
+
+    #[tokio::test]
+    async fn update_question_should_return_question() {
+        let question = QuestionDetail {
+            question_uuid: QuestionUuid::new_v4(),
+            title: "title".to_owned(),
+            description: Some("description".to_owned()),
+            metadata: empty_metadata(),
+            created_at: DateTime::UNIX_EPOCH,
+            updated_at: Utc.with_ymd_and_hms(2023, 10, 1, 10, 0, 0).unwrap(),
+            last_activity_at: Utc.with_ymd_and_hms(2023, 10, 1, 10, 0, 0).unwrap(),
+            answer_count: 0,
+            version: 2,
+        };
+        let mut question_dao = QuestionDaoMock::new();
+        question_dao.mock_update_question_response(Ok(question.clone()));
+        let question_dao: Box<dyn QuestionDao + Sync + Send> = Box::new(question_dao);
+
+        let result = update_question(
+            QuestionUuid::new_v4(),
+            question_update(None),
+            Some(1),
+            question_dao.as_ref(),
+            WriteHooks::default(),
+        )
+        .await;
+
+        assert_eq!(result.unwrap(), question);
+    }
+
+    #[tokio::test]
+    async fn update_question_should_return_conflict_on_stale_version() {
+        let mut question_dao = QuestionDaoMock::new();
+        question_dao.mock_update_question_response(Err(DBError::Conflict("".to_owned())));
+        let question_dao: Box<dyn QuestionDao + Sync + Send> = Box::new(question_dao);
+
+        let result = update_question(
+            QuestionUuid::new_v4(),
+            question_update(Some(1)),
+            None,
+            question_dao.as_ref(),
+            WriteHooks::default(),
+        )
+        .await;
+
+        assert_eq!(result.unwrap_err().status(), Status::Conflict);
+    }
+
+    #[tokio::test]
+    async fn update_question_should_require_a_version() {
+        let question_dao: Box<dyn QuestionDao + Sync + Send> = Box::new(QuestionDaoMock::new());
+
+        let result = update_question(
+            QuestionUuid::new_v4(),
+            question_update(None),
+            None,
+            question_dao.as_ref(),
+            WriteHooks::default(),
+        )
+        .await;
+
+        assert_eq!(result.unwrap_err().status(), Status::PreconditionRequired);
+    }
+
+    #[tokio::test]
+    async fn update_answer_should_reject_disagreeing_versions() {
+        let answer_dao: Box<dyn AnswerDao + Sync + Send> = Box::new(AnswerDaoMock::new());
+
+        let result = update_answer(
+            AnswerUuid::new_v4(),
+            AnswerUpdate {
+                content: "content".to_owned(),
+                version: Some(1),
+            },
+            Some(2),
+            answer_dao.as_ref(),
+            None,
+        )
+        .await;
+
+        assert_eq!(result.unwrap_err().status(), Status::BadRequest);
     }
-}
 
-pub async fn create_question(
-    question: Question,
-    questions_dao: &Box<dyn QuestionDao + Sync + Send>,
-) -> Result<QuestionDetail, HandlerError> {
-    let question = questions_dao.create_question(question).await;
+    #[tokio::test]
+    async fn update_answer_should_return_bad_request_for_unknown_answer() {
+        let mut answer_dao = AnswerDaoMock::new();
+        answer_dao.mock_update_answer(Err(DBError::InvalidUUID("".to_owned())));
+        let answer_dao: Box<dyn AnswerDao + Sync + Send> = Box::new(answer_dao);
 
-    match question {
-        Ok(question) => Ok(question),
-        Err(err) => {
-            error!("Unexpected error found on create_question: {:?}", err);
-            Err(HandlerError::default_internal_error())
-        }
+        let result = update_answer(
+            AnswerUuid::new_v4(),
+            AnswerUpdate {
+                content: "content".to_owned(),
+                version: Some(1),
+            },
+            None,
+            answer_dao.as_ref(),
+            None,
+        )
+        .await;
+
+        assert_eq!(result.unwrap_err().status(), Status::BadRequest);
     }
-}
 
-pub async fn get_questions(
-    question_dao: &Box<dyn QuestionDao + Sync + Send>,
-) -> Result<Vec<QuestionDetail>, HandlerError> {
-    let questions = question_dao.get_questions().await.map_err(|err| {
-        error!("Failed to read questions, err: {:?}", err);
-        HandlerError::default_internal_error()
-    })?;
+    struct UnitOfWorkMock {
+        create_answer_response: Option<Result<AnswerDetail, DBError>>,
+        committed: Arc<AtomicBool>,
+    }
 
-    Ok(questions)
-}
+    #[async_trait]
+    impl UnitOfWork for UnitOfWorkMock {
+        async fn create_question(&mut self, question: Question) -> Result<QuestionDetail, DBError> {
+            Ok(QuestionDetail {
+                question_uuid: QuestionUuid::new_v4(),
+                title: question.title,
+                description: question.description,
+                metadata: empty_metadata(),
+                created_at: DateTime::UNIX_EPOCH,
+                updated_at: DateTime::UNIX_EPOCH,
+                last_activity_at: DateTime::UNIX_EPOCH,
+                version: 1,
+                answer_count: 0,
+            })
+        }
+
+        async fn create_answer(&mut self, _: Answer) -> Result<AnswerDetail, DBError> {
+            self.create_answer_response
+                .take()
+                .expect("create_answer_response should not be None.")
+        }
 
-pub async fn delete_question(
-    question_uuid: String,
-    question_dao: &Box<dyn QuestionDao + Sync + Send>,
-) -> Result<(), HandlerError> {
-    let result = question_dao.delete_question(question_uuid).await;
+        async fn commit(self: Box<Self>) -> Result<(), DBError> {
+            self.committed.store(true, Ordering::SeqCst);
+            Ok(())
+        }
+    }
 
-    match result {
-        Ok(_) => Ok(()),
-        Err(err) => {
-            error!("Error on deleting question: {}", err);
+    struct UnitOfWorkFactoryMock {
+        create_answer_response: Mutex<Option<Result<AnswerDetail, DBError>>>,
+        committed: Arc<AtomicBool>,
+    }
 
-            if let DBError::InvalidUUID(s) = err {
-                return Err(HandlerError::BadRequest(s));
+    impl UnitOfWorkFactoryMock {
+        fn new(create_answer_response: Result<AnswerDetail, DBError>) -> Self {
+            Self {
+                create_answer_response: Mutex::new(Some(create_answer_response)),
+                committed: Arc::new(AtomicBool::new(false)),
             }
+        }
+    }
 
-            Err(HandlerError::default_internal_error())
+    #[async_trait]
+    impl UnitOfWorkFactory for UnitOfWorkFactoryMock {
+        async fn begin(&self) -> Result<Box<dyn UnitOfWork>, DBError> {
+            Ok(Box::new(UnitOfWorkMock {
+                create_answer_response: self.create_answer_response.lock().await.take(),
+                committed: self.committed.clone(),
+            }))
         }
     }
-}
 
-pub async fn create_answer(
-    answer: Answer,
-    answer_dao: &Box<dyn AnswerDao + Sync + Send>,
-) -> Result<AnswerDetail, HandlerError> {
-    let result = answer_dao.create_answer(answer).await;
+    fn question_with_answer() -> QuestionWithAnswer {
+        QuestionWithAnswer {
+            question: Question {
+                title: "title".to_owned(),
+                description: Some("description".to_owned()),
+                metadata: empty_metadata(),
+            },
+            answer: "content".to_owned(),
+        }
+    }
 
-    match result {
-        Ok(answer) => Ok(answer),
-        Err(err) => {
-            error!("Something wents wrong during create_answer: {:?}", err);
-            if let DBError::InvalidUUID(s) = err {
-                return Err(HandlerError::BadRequest(s));
-            }
+    #[tokio::test]
+    async fn create_question_with_answer_should_commit_both() {
+        let answer = AnswerDetail {
+            answer_uuid: AnswerUuid::new_v4(),
+            question_uuid: QuestionUuid::new_v4(),
+            content: "content".to_owned(),
+            created_at: DateTime::UNIX_EPOCH,
+            updated_at: DateTime::UNIX_EPOCH,
+            version: 1,
+        };
+        let unit_of_work = UnitOfWorkFactoryMock::new(Ok(answer.clone()));
 
-            Err(HandlerError::default_internal_error())
-        }
+        let result = create_question_with_answer(
+            question_with_answer(),
+            None,
+            &unit_of_work,
+            WriteHooks::default(),
+            &SpamContext::default(),
+        )
+        .await;
+
+        assert_eq!(unmoderated(result.unwrap()).answer, answer);
+        assert!(unit_of_work.committed.load(Ordering::SeqCst));
     }
-}
 
-pub async fn get_answers(
-    question_uuid: String,
-    answer_dao: &Box<dyn AnswerDao + Sync + Send>,
-) -> Result<Vec<AnswerDetail>, HandlerError> {
-    let result = answer_dao.get_answers(question_uuid).await;
+    #[tokio::test]
+    async fn create_question_with_answer_should_not_commit_on_failure() {
+        let unit_of_work = UnitOfWorkFactoryMock::new(Err(DBError::Unavailable));
 
-    match result {
-        Ok(answers) => Ok(answers),
-        Err(err) => {
-            error!("Error on get_answers: {:?}", err);
+        let result = create_question_with_answer(
+            question_with_answer(),
+            None,
+            &unit_of_work,
+            WriteHooks::default(),
+            &SpamContext::default(),
+        )
+        .await;
 
-            if let DBError::InvalidUUID(s) = err {
-                return Err(HandlerError::BadRequest(s));
-            }
+        assert_eq!(result.unwrap_err().status(), Status::ServiceUnavailable);
+        assert!(!unit_of_work.committed.load(Ordering::SeqCst));
+    }
 
-            Err(HandlerError::default_internal_error())
+    struct ImportDaoMock {
+        response: Mutex<Option<Result<ImportSummary, DBError>>>,
+    }
+
+    #[async_trait]
+    impl ImportDao for ImportDaoMock {
+        async fn import(&self, _: Vec<ImportedQuestion>) -> Result<ImportSummary, DBError> {
+            self.response
+                .lock()
+                .await
+                .take()
+                .expect("response should not be None.")
         }
     }
-}
 
-pub async fn delete_answer(
-    answer_uuid: String,
-    answer_dao: &Box<dyn AnswerDao + Sync + Send>,
-) -> Result<(), HandlerError> {
-    answer_dao.delete_answer(answer_uuid).await.map_err(|err| {
-        error!("Error on delete answer: {:?}", err);
+    #[tokio::test]
+    async fn import_should_return_summary() {
+        let summary = ImportSummary {
+            questions: 1,
+            answers: 2,
+        };
+        let import_dao = ImportDaoMock {
+            response: Mutex::new(Some(Ok(summary.clone()))),
+        };
+
+        let result = import(Import { questions: vec![] }, &import_dao).await;
 
-        if let DBError::InvalidUUID(s) = err {
-            return HandlerError::BadRequest(s);
-        }
+        assert_eq!(result.unwrap(), summary);
+    }
 
-        return HandlerError::default_internal_error();
-    })?;
+    #[tokio::test]
+    async fn import_should_return_error() {
+        let import_dao = ImportDaoMock {
+            response: Mutex::new(Some(Err(DBError::Unavailable))),
+        };
 
-    Ok(())
-}
+        let result = import(Import { questions: vec![] }, &import_dao).await;
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use tokio::sync::Mutex;
+        assert_eq!(result.unwrap_err().status(), Status::ServiceUnavailable);
+    }
 
-    struct QuestionDaoMock {
-        create_question_response: Mutex<Option<Result<QuestionDetail, DBError>>>,
-        delete_question_response: Mutex<Option<Result<(), DBError>>>,
-        get_questions_response: Mutex<Option<Result<Vec<QuestionDetail>, DBError>>>,
+    struct ArchiveDaoMock {
+        questions: Vec<QuestionDetail>,
+        older_than_days: Mutex<Option<u32>>,
     }
 
-    impl QuestionDaoMock {
-        fn new() -> Self {
-            Self {
-                create_question_response: Mutex::new(None),
-                delete_question_response: Mutex::new(None),
-                get_questions_response: Mutex::new(None),
+    impl ArchiveDaoMock {
+        fn new(questions: Vec<QuestionDetail>) -> Self {
+            ArchiveDaoMock {
+                questions,
+                older_than_days: Mutex::new(None),
             }
         }
+    }
 
-        fn mock_create_question_response(&mut self, response: Result<QuestionDetail, DBError>) {
-            self.create_question_response = Mutex::new(Some(response));
+    #[async_trait]
+    impl ArchiveDao for ArchiveDaoMock {
+        async fn archive(&self, older_than_days: u32) -> Result<ArchiveSummary, DBError> {
+            *self.older_than_days.lock().await = Some(older_than_days);
+            Ok(ArchiveSummary {
+                questions: 0,
+                answers: 0,
+            })
         }
 
-        fn mock_delete_question_response(&mut self, response: Result<(), DBError>) {
-            self.delete_question_response = Mutex::new(Some(response));
+        fn stream_archived_questions(
+            &self,
+            filter: QuestionFilter,
+        ) -> RowStream<'_, QuestionDetail> {
+            let questions: Vec<_> = self
+                .questions
+                .iter()
+                .filter(|question| filter.matches(question))
+                .cloned()
+                .collect();
+            stream::iter(questions.into_iter().map(Ok)).boxed()
         }
 
-        fn mock_get_questions_response(&mut self, response: Result<Vec<QuestionDetail>, DBError>) {
-            self.get_questions_response = Mutex::new(Some(response));
+        fn stream_archived_answers(&self, _: QuestionUuid) -> RowStream<'_, AnswerDetail> {
+            stream::empty().boxed()
         }
-    }
 
-    #[async_trait]
-    impl QuestionDao for QuestionDaoMock {
-        async fn create_question(&self, _: Question) -> Result<QuestionDetail, DBError> {
-            self.create_question_response
-                .lock()
-                .await
-                .take()
-                .expect("create_question_response should not be None.")
+        async fn get_archived_answers_of_questions(
+            &self,
+            _: &[QuestionUuid],
+        ) -> Result<Vec<AnswerDetail>, DBError> {
+            Ok(vec![])
         }
+    }
 
-        async fn delete_question(&self, _: String) -> Result<(), DBError> {
-            self.delete_question_response
-                .lock()
-                .await
-                .take()
-                .expect("delete_question_response should not be None.")
-        }
+    #[tokio::test]
+    async fn archive_should_prefer_the_requested_age() {
+        let archive_dao = ArchiveDaoMock::new(vec![]);
 
-        async fn get_questions(&self) -> Result<Vec<QuestionDetail>, DBError> {
-            self.get_questions_response
-                .lock()
-                .await
-                .take()
-                .expect("get_questions_response should not be None.")
-        }
+        archive(Some(7), 90, &archive_dao).await.unwrap();
+
+        assert_eq!(*archive_dao.older_than_days.lock().await, Some(7));
     }
 
-    struct AnswerDaoMock {
-        create_answer_response: Mutex<Option<Result<AnswerDetail, DBError>>>,
-        delete_answer_response: Mutex<Option<Result<(), DBError>>>,
-        get_answers_response: Mutex<Option<Result<Vec<AnswerDetail>, DBError>>>,
+    #[tokio::test]
+    async fn archive_should_reject_a_zero_age() {
+        let archive_dao = ArchiveDaoMock::new(vec![]);
+
+        let result = archive(None, 0, &archive_dao).await;
+
+        assert_eq!(result.unwrap_err().status(), Status::BadRequest);
+        assert_eq!(*archive_dao.older_than_days.lock().await, None);
     }
 
-    impl AnswerDaoMock {
-        fn new() -> Self {
-            AnswerDaoMock {
-                create_answer_response: Mutex::new(None),
-                delete_answer_response: Mutex::new(None),
-                get_answers_response: Mutex::new(None),
-            }
-        }
-        fn mock_create_answer(&mut self, response: Result<AnswerDetail, DBError>) {
-            self.create_answer_response = Mutex::new(Some(response));
-        }
-        fn mock_delete_answer(&mut self, response: Result<(), DBError>) {
-            self.delete_answer_response = Mutex::new(Some(response));
-        }
-        fn mock_get_answers(&mut self, response: Result<Vec<AnswerDetail>, DBError>) {
-            self.get_answers_response = Mutex::new(Some(response));
-        }
+    struct StatsDaoMock {
+        stats: Vec<DailyStats>,
     }
 
     #[async_trait]
-    impl AnswerDao for AnswerDaoMock {
-        async fn create_answer(&self, _: Answer) -> Result<AnswerDetail, DBError> {
-            self.create_answer_response
-                .lock()
-                .await
-                .take()
-                .expect("create_answer_response should not be None.")
+    impl StatsDao for StatsDaoMock {
+        async fn get_daily_stats(&self) -> Result<Vec<DailyStats>, DBError> {
+            Ok(self.stats.clone())
         }
-        async fn delete_answer(&self, _: String) -> Result<(), DBError> {
-            self.delete_answer_response
-                .lock()
-                .await
-                .take()
-                .expect("delete_answer_response should not be None.")
+
+        async fn get_response_stats(&self, _: NaiveDate) -> Result<ResponseStats, DBError> {
+            Ok(ResponseStats {
+                questions: 4,
+                answered: 3,
+                median_first_answer_secs: Some(90.0),
+                active_users: None,
+            })
         }
-        async fn get_answers(&self, _: String) -> Result<Vec<AnswerDetail>, DBError> {
-            self.get_answers_response
-                .lock()
-                .await
-                .take()
-                .expect("get_answers_response should not be None.")
+
+        async fn refresh(&self) -> Result<(), DBError> {
+            Err(DBError::Unavailable)
         }
     }
 
     #[tokio::test]
-    async fn create_question_should_return_question() {
-        let title = "title".to_owned();
-        let description = "description".to_owned();
-        let question = Question {
-            title: title.clone(),
-            description: description.clone(),
-        };
-        let question_detail = QuestionDetail {
-            title,
-            description,
-            question_uuid: "uuid".to_owned(),
-            created_at: "some-date".to_owned(),
+    async fn get_daily_stats_should_return_stats() {
+        let stats = vec![DailyStats {
+            day: "2023-10-01".to_owned(),
+            questions: 2,
+            answers: 3,
+        }];
+        let stats_dao = StatsDaoMock {
+            stats: stats.clone(),
         };
 
-        let mut question_dao = QuestionDaoMock::new();
-        question_dao.mock_create_question_response(Ok(question_detail.clone()));
-
-        let question_dao: Box<dyn QuestionDao + Sync + Send> = Box::new(question_dao);
+        let result = get_daily_stats(&stats_dao).await;
 
-        let result = create_question(question, &question_dao).await;
-        assert!(result.is_ok());
-        assert_eq!(result.unwrap(), question_detail);
+        assert_eq!(result.unwrap(), stats);
     }
 
     #[tokio::test]
-    async fn create_question_should_return_error() {
-        let question = Question {
-            title: "title".to_owned(),
-            description: "description".to_owned(),
+    async fn get_admin_stats_should_cover_the_last_days() {
+        let today = Utc::now().date_naive();
+        let day = |date: NaiveDate| DailyStats {
+            day: date.to_string(),
+            questions: 2,
+            answers: 2,
+        };
+        let stats_dao = StatsDaoMock {
+            stats: vec![day(today - Duration::days(7)), day(today)],
         };
-        let mut question_dao = QuestionDaoMock::new();
-        question_dao.mock_create_question_response(Err(DBError::InvalidUUID("".to_owned())));
-        let question_dao: Box<dyn QuestionDao + Sync + Send> = Box::new(question_dao);
 
-        let result = create_question(question, &question_dao).await;
-        assert!(result.is_err());
-        assert_eq!(
-            std::mem::discriminant(&result.unwrap_err()),
-            std::mem::discriminant(&HandlerError::InternalError("".to_owned()))
-        );
+        let result = get_admin_stats(Some(7), &stats_dao).await.unwrap();
+
+        assert_eq!(result.since, (today - Duration::days(6)).to_string());
+        assert_eq!(result.daily, vec![day(today)]);
+        assert_eq!(result.answer_rate, Some(0.75));
+        assert_eq!(result.median_first_answer_secs, Some(90.0));
+        assert_eq!(result.active_users, None);
+        for days in [0, 367] {
+            let err = get_admin_stats(Some(days), &stats_dao).await.unwrap_err();
+            assert_eq!(err.status(), Status::BadRequest);
+        }
     }
 
     #[tokio::test]
-    async fn get_questions_should_return_questions() {
-        let questions = vec![QuestionDetail {
-            title: "title".to_owned(),
-            description: "description".to_owned(),
-            question_uuid: "uuid".to_owned(),
-            created_at: "some-date".to_owned(),
-        }];
-        let mut question_dao = QuestionDaoMock::new();
-        question_dao.mock_get_questions_response(Ok(questions.clone()));
-        let question_dao: Box<dyn QuestionDao + Sync + Send> = Box::new(question_dao);
+    async fn refresh_stats_should_return_error() {
+        let stats_dao = StatsDaoMock { stats: vec![] };
 
-        let result = get_questions(&question_dao).await;
-        assert!(result.is_ok());
-        assert_eq!(result.unwrap(), questions);
+        let result = refresh_stats(&stats_dao).await;
+
+        assert_eq!(result.unwrap_err().status(), Status::ServiceUnavailable);
     }
 
-    #[tokio::test]
-    async fn get_questions_should_return_error() {
-        let mut question_dao = QuestionDaoMock::new();
-        question_dao.mock_get_questions_response(Err(DBError::InvalidUUID("".to_owned())));
-        let question_dao: Box<dyn QuestionDao + Sync + Send> = Box::new(question_dao);
-        let result = get_questions(&question_dao).await;
-        assert!(result.is_err());
-        assert_eq!(
-            std::mem::discriminant(&result.unwrap_err()),
-            std::mem::discriminant(&HandlerError::InternalError("".to_owned()))
-        );
+    struct EmbeddingDaoMock {
+        stored: Arc<Mutex<Vec<QuestionUuid>>>,
     }
 
-    #[tokio::test]
-    async fn delete_question_should_succeed() {
-        let mut question_dao = QuestionDaoMock::new();
-        question_dao.mock_delete_question_response(Ok(()));
-        let question_dao: Box<dyn QuestionDao + Sync + Send> = Box::new(question_dao);
+    #[async_trait]
+    impl EmbeddingDao for EmbeddingDaoMock {
+        async fn store_question_embedding(
+            &self,
+            question_uuid: QuestionUuid,
+            _: Vec<f32>,
+        ) -> Result<(), DBError> {
+            self.stored.lock().await.push(question_uuid);
+            Ok(())
+        }
 
-        let result = delete_question("question_uuid".to_owned(), &question_dao).await;
-        assert!(result.is_ok());
-        assert_eq!(result.unwrap(), ());
+        async fn nearest_questions(
+            &self,
+            _: Vec<f32>,
+            limit: u32,
+        ) -> Result<Vec<QuestionDetail>, DBError> {
+            assert_eq!(limit, SEMANTIC_SEARCH_MAX_LIMIT);
+            Ok(vec![])
+        }
     }
 
-    #[tokio::test]
-    async fn delete_question_should_return_error() {
-        let mut question_dao = QuestionDaoMock::new();
-        question_dao.mock_delete_question_response(Err(DBError::InvalidUUID("".to_owned())));
-        let question_dao: Box<dyn QuestionDao + Sync + Send> = Box::new(question_dao);
-        let result = delete_question("question_uuid".to_owned(), &question_dao).await;
-        assert!(result.is_err());
-        assert_eq!(
-            std::mem::discriminant(&result.unwrap_err()),
-            std::mem::discriminant(&HandlerError::BadRequest("".to_owned()))
-        );
+    fn semantic_search_with(stored: Arc<Mutex<Vec<QuestionUuid>>>) -> SemanticSearch {
+        SemanticSearch {
+            embedder: Box::new(HashingEmbedder),
+            embedding_dao: Arc::new(EmbeddingDaoMock { stored }),
+        }
     }
 
     #[tokio::test]
-    async fn create_answer_should_return_answer() {
-        let mut answer_dao = AnswerDaoMock::new();
-        let answer = AnswerDetail {
-            answer_uuid: "some".to_owned(),
-            question_uuid: "question_uuid".to_owned(),
-            content: "content".to_owned(),
-            created_at: "created".to_owned(),
+    async fn create_question_should_store_its_embedding() {
+        let question_uuid = QuestionUuid::new_v4();
+        let question = QuestionDetail {
+            question_uuid,
+            title: "title".to_owned(),
+            description: Some("description".to_owned()),
+            metadata: empty_metadata(),
+            created_at: DateTime::UNIX_EPOCH,
+            updated_at: DateTime::UNIX_EPOCH,
+            last_activity_at: DateTime::UNIX_EPOCH,
+            version: 1,
+            answer_count: 0,
         };
-        answer_dao.mock_create_answer(Ok(answer.clone()));
-        let answer_dao: Box<dyn AnswerDao + Sync + Send> = Box::new(answer_dao);
+        let mut question_dao = QuestionDaoMock::new();
+        question_dao.mock_create_question_response(Ok(question));
+        let stored = Arc::new(Mutex::new(vec![]));
+        let search = semantic_search_with(stored.clone());
 
-        let result = create_answer(
-            Answer {
-                question_uuid: "question_id".to_owned(),
-                content: "content".to_owned(),
+        let result = create_question(
+            Question {
+                title: "title".to_owned(),
+                description: Some("description".to_owned()),
+                metadata: empty_metadata(),
             },
-            &answer_dao,
+            None,
+            &question_dao,
+            WriteHooks {
+                semantic_search: Some(&search),
+                ..Default::default()
+            },
+            &SpamContext::default(),
         )
         .await;
+
         assert!(result.is_ok());
-        assert_eq!(result.unwrap(), answer);
+        assert_eq!(*stored.lock().await, vec![question_uuid]);
     }
 
     #[tokio::test]
-    async fn create_answer_should_return_bad_request_error() {
-        let mut answer_dao = AnswerDaoMock::new();
-        answer_dao.mock_create_answer(Err(DBError::InvalidUUID("".to_owned())));
-        let answer_dao: Box<dyn AnswerDao + Sync + Send> = Box::new(answer_dao);
-        let result = create_answer(
-            Answer {
-                question_uuid: "question_id".to_owned(),
-                content: "content".to_owned(),
-            },
-            &answer_dao,
-        )
-        .await;
+    async fn semantic_search_should_cap_the_limit() {
+        let search = semantic_search_with(Arc::new(Mutex::new(vec![])));
 
-        assert!(result.is_err());
-        assert_eq!(
-            std::mem::discriminant(&result.unwrap_err()),
-            std::mem::discriminant(&HandlerError::BadRequest("".to_owned()))
-        );
-    }
+        let result = semantic_search("borrow checker".to_owned(), 1000, Some(&search)).await;
 
-    #[tokio::test]
-    async fn create_answer_should_return_internal_error() {
-        let mut answer_dao = AnswerDaoMock::new();
-        answer_dao.mock_create_answer(Err(DBError::Other(Box::new(std::io::Error::new(
-            std::io::ErrorKind::Other,
-            "Oh no!",
-        )))));
-        let answer_dao: Box<dyn AnswerDao + Sync + Send> = Box::new(answer_dao);
-        let result = create_answer(
-            Answer {
-                question_uuid: "question_id".to_owned(),
-                content: "content".to_owned(),
-            },
-            &answer_dao,
-        )
-        .await;
-        assert!(result.is_err());
-        assert_eq!(
-            std::mem::discriminant(&result.unwrap_err()),
-            std::mem::discriminant(&HandlerError::InternalError("".to_owned()))
-        );
+        assert_eq!(result.unwrap(), vec![]);
     }
 
     #[tokio::test]
-    async fn get_answers_should_return_answers() {
-        let answers = vec![AnswerDetail {
-            answer_uuid: "some".to_owned(),
-            question_uuid: "question_uuid".to_owned(),
-            content: "content".to_owned(),
-            created_at: "created".to_owned(),
-        }];
-
-        let mut answer_dao = AnswerDaoMock::new();
-        answer_dao.mock_get_answers(Ok(answers.clone()));
-        let answer_dao: Box<dyn AnswerDao + Sync + Send> = Box::new(answer_dao);
+    async fn semantic_search_should_fail_when_disabled() {
+        let result = semantic_search("borrow checker".to_owned(), 10, None).await;
 
-        let result = get_answers("question_uuid".to_owned(), &answer_dao).await;
-        assert!(result.is_ok());
-        assert_eq!(result.unwrap(), answers);
+        assert_eq!(result.unwrap_err().status(), Status::NotImplemented);
     }
 
     #[tokio::test]
-    async fn get_answers_should_return_error() {
-        let mut answer_dao = AnswerDaoMock::new();
-        answer_dao.mock_get_answers(Err(DBError::InvalidUUID("".to_owned())));
-        let answer_dao: Box<dyn AnswerDao + Sync + Send> = Box::new(answer_dao);
+    async fn get_audit_entries_should_fail_without_audit_log() {
+        let result = get_audit_entries(AuditFilter::default(), None).await;
 
-        let result = get_answers("question_uuid".to_owned(), &answer_dao).await;
-        assert!(result.is_err());
-        assert_eq!(
-            std::mem::discriminant(&result.unwrap_err()),
-            std::mem::discriminant(&HandlerError::BadRequest("".to_owned()))
-        );
+        assert_eq!(result.unwrap_err().status(), Status::NotImplemented);
     }
 
     #[tokio::test]
-    async fn delete_answer_should_succeed() {
-        let mut answer_dao = AnswerDaoMock::new();
-        answer_dao.mock_delete_answer(Ok(()));
-        let answer_dao: Box<dyn AnswerDao + Sync + Send> = Box::new(answer_dao);
+    async fn get_activity_should_list_answers_with_their_question() {
+        let store = MemoryStore::new();
+        let question_dao = InMemoryQuestionDao::new(store.clone());
+        let question = question_dao
+            .create_question(Question {
+                title: "title".to_owned(),
+                description: None,
+                metadata: empty_metadata(),
+            })
+            .await
+            .unwrap();
+        InMemoryAnswerDao::new(store.clone())
+            .create_answer(Answer {
+                question_uuid: question.question_uuid,
+                content: "content".to_owned(),
+            })
+            .await
+            .unwrap();
+        let activity_dao = InMemoryActivityDao::new(store);
 
-        let result = delete_answer("answer_uuid".to_owned(), &answer_dao).await;
-        assert!(result.is_ok());
+        let activity = get_activity(None, 1, Some(&activity_dao)).await.unwrap();
+
+        assert_eq!(activity.len(), 1);
+        assert_eq!(activity[0].kind, ActivityKind::AnswerPosted);
+        assert_eq!(activity[0].question_title, "title");
+        let older = get_activity(Some(activity[0].id), 10, Some(&activity_dao))
+            .await
+            .unwrap();
+        assert_eq!(older.len(), 1);
+        assert_eq!(older[0].kind, ActivityKind::QuestionAsked);
+        let result = get_activity(None, 10, None).await;
+        assert_eq!(result.unwrap_err().status(), Status::NotImplemented);
     }
 
     #[tokio::test]
-    async fn delete_answer_should_return_error() {
-        let mut answer_dao = AnswerDaoMock::new();
-        answer_dao.mock_delete_answer(Err(DBError::InvalidUUID("".to_owned())));
-        let answer_dao: Box<dyn AnswerDao + Sync + Send> = Box::new(answer_dao);
+    async fn merge_question_should_reject_merging_into_itself() {
+        let store = MemoryStore::new();
+        let question = InMemoryQuestionDao::new(store.clone())
+            .create_question(Question {
+                title: "title".to_owned(),
+                description: None,
+                metadata: empty_metadata(),
+            })
+            .await
+            .unwrap();
+        let merge_dao = InMemoryMergeDao::new(store);
 
-        let result = delete_answer("answer_uuid".to_owned(), &answer_dao).await;
-        assert!(result.is_err());
-        assert_eq!(
-            std::mem::discriminant(&result.unwrap_err()),
-            std::mem::discriminant(&HandlerError::BadRequest("".to_owned()))
-        );
+        let result = merge_question(
+            question.question_uuid,
+            question.question_uuid,
+            Some(&merge_dao),
+        )
+        .await;
+
+        assert_eq!(result.unwrap_err().status(), Status::BadRequest);
+        let result = merge_question(question.question_uuid, QuestionUuid::new_v4(), None).await;
+        assert_eq!(result.unwrap_err().status(), Status::NotImplemented);
     }
 }
@@ -0,0 +1,317 @@
+use std::collections::HashMap;
+
+use chrono::{DateTime, Utc};
+use chrono_tz::Tz;
+use rocket::form::Errors;
+
+use super::{
+    dates,
+    pagination::{page_bounds, per_page_bounds, Page},
+    validation::FieldError,
+    AppError,
+};
+use crate::models::{AnswerSort, DateRange, MetadataFilter, QuestionSort};
+
+#[derive(FromForm, Debug)]
+pub struct QuestionListParams {
+    pub include_archived: bool,
+    #[field(default = QuestionSort::Oldest)]
+    pub sort: QuestionSort,
+    #[field(validate = page_bounds())]
+    pub page: Option<u32>,
+    #[field(validate = per_page_bounds())]
+    pub per_page: Option<u32>,
+    pub cursor: Option<String>,
+    // `?metadata.source=zendesk&metadata.ticket=42`.
+    pub metadata: HashMap<String, String>,
+    // `?include=answers`, comma separated or repeated.
+    pub include: Vec<String>,
+    // `?created_after=today&tz=Europe/Berlin`, see `dates::bound`.
+    pub created_after: Option<String>,
+    pub created_before: Option<String>,
+    pub tz: Option<String>,
+}
+
+// Relations `?include=` can embed in listed questions.
+pub const INCLUDABLE: &[&str] = &["answers", "attachments", "answers.attachments"];
+
+// Relations `?include=` can embed in listed answers.
+pub const ANSWER_INCLUDABLE: &[&str] = &["attachments"];
+
+// What `?include=` asked to embed. `answers.attachments` implies `answers`.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct Includes {
+    pub answers: bool,
+    pub attachments: bool,
+    pub answer_attachments: bool,
+}
+
+#[derive(FromForm, Debug)]
+pub struct AnswerListParams {
+    pub include_archived: bool,
+    #[field(default = AnswerSort::Oldest)]
+    pub sort: AnswerSort,
+    #[field(validate = page_bounds())]
+    pub page: Option<u32>,
+    #[field(validate = per_page_bounds())]
+    pub per_page: Option<u32>,
+    pub cursor: Option<String>,
+    // `?include=attachments`.
+    pub include: Vec<String>,
+}
+
+// The paths asked for, each one of `includable`. Nested paths such as
+// `answers.attachments` count one level per segment; `max_depth` is checked before the
+// relation names, so none is looked up past it.
+fn included_paths<'a>(
+    include: &'a [String],
+    includable: &[&str],
+    max_depth: u32,
+) -> Result<Vec<&'a str>, AppError> {
+    let mut included = vec![];
+    let mut errors = vec![];
+    let paths = include
+        .iter()
+        .flat_map(|include| include.split(','))
+        .map(str::trim)
+        .filter(|path| !path.is_empty());
+
+    for path in paths {
+        let depth = path.split('.').count();
+        let message = if depth > max_depth as usize {
+            format!(
+                "`{}` goes {} deep, past the limit of {}",
+                path, depth, max_depth
+            )
+        } else if includable.contains(&path) {
+            included.push(path);
+            continue;
+        } else {
+            format!("`{}` is not one of: {}", path, includable.join(", "))
+        };
+        errors.push(FieldError {
+            field: "include".to_owned(),
+            message,
+        });
+    }
+
+    match errors.is_empty() {
+        true => Ok(included),
+        false => Err(AppError::InvalidQuery(errors)),
+    }
+}
+
+impl QuestionListParams {
+    pub fn page(&self, default_per_page: u32) -> Result<Page, AppError> {
+        Page::from_params(
+            self.page,
+            self.per_page,
+            self.cursor.as_deref(),
+            default_per_page,
+        )
+    }
+
+    pub fn includes(&self, max_depth: u32) -> Result<Includes, AppError> {
+        let mut includes = Includes::default();
+        for path in included_paths(&self.include, INCLUDABLE, max_depth)? {
+            match path {
+                "answers" => includes.answers = true,
+                "attachments" => includes.attachments = true,
+                _ => {
+                    includes.answers = true;
+                    includes.answer_attachments = true;
+                }
+            }
+        }
+        Ok(includes)
+    }
+
+    pub fn metadata_filter(&self) -> MetadataFilter {
+        MetadataFilter(
+            self.metadata
+                .iter()
+                .map(|(key, value)| (key.clone(), value.clone()))
+                .collect(),
+        )
+    }
+
+    pub fn created(&self, tz: Option<Tz>, now: DateTime<Utc>) -> Result<DateRange, AppError> {
+        let tz = tz.unwrap_or(Tz::UTC);
+        let mut errors = Vec::new();
+        let mut bound = |field: &str, value: &Option<String>| {
+            let value = value.as_deref()?;
+            dates::bound(field, value, tz, now)
+                .map_err(|err| errors.push(err))
+                .ok()
+        };
+        let range = DateRange {
+            after: bound("created_after", &self.created_after),
+            before: bound("created_before", &self.created_before),
+        };
+
+        if errors.is_empty() {
+            Ok(range)
+        } else {
+            Err(AppError::InvalidQuery(errors))
+        }
+    }
+}
+
+impl AnswerListParams {
+    pub fn page(&self, default_per_page: u32) -> Result<Page, AppError> {
+        Page::from_params(
+            self.page,
+            self.per_page,
+            self.cursor.as_deref(),
+            default_per_page,
+        )
+    }
+
+    // Only `attachments`, so `answer_attachments` is what is set.
+    pub fn includes(&self, max_depth: u32) -> Result<Includes, AppError> {
+        let paths = included_paths(&self.include, ANSWER_INCLUDABLE, max_depth)?;
+        Ok(Includes {
+            answer_attachments: !paths.is_empty(),
+            ..Includes::default()
+        })
+    }
+}
+
+// Routes take their parameters as `Result<_, Errors>` so that a bad one is answered
+// with 400 and the offending fields, where Rocket would otherwise forward to a 422.
+impl From<Errors<'_>> for AppError {
+    fn from(errors: Errors<'_>) -> Self {
+        AppError::InvalidQuery(
+            errors
+                .iter()
+                .map(|error| FieldError {
+                    field: error
+                        .name
+                        .as_ref()
+                        .map(|name| name.to_string())
+                        .unwrap_or_default(),
+                    message: error.kind.to_string(),
+                })
+                .collect(),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{empty_metadata, QuestionDetail, QuestionUuid};
+    use chrono::{DateTime, TimeZone, Utc};
+
+    fn question(created_at: DateTime<Utc>, answer_count: i64) -> QuestionDetail {
+        QuestionDetail {
+            question_uuid: QuestionUuid::new_v4(),
+            title: created_at.date_naive().to_string(),
+            description: Some("description".to_owned()),
+            metadata: empty_metadata(),
+            created_at,
+            updated_at: created_at,
+            last_activity_at: created_at,
+            answer_count,
+            version: 1,
+        }
+    }
+
+    fn params(include: &[&str]) -> QuestionListParams {
+        QuestionListParams {
+            include_archived: false,
+            sort: QuestionSort::Oldest,
+            page: None,
+            per_page: None,
+            cursor: None,
+            metadata: HashMap::new(),
+            include: include.iter().map(|include| include.to_string()).collect(),
+            created_after: None,
+            created_before: None,
+            tz: None,
+        }
+    }
+
+    #[test]
+    fn includes_should_be_known_and_shallow_enough() {
+        assert_eq!(params(&[]).includes(1).unwrap(), Includes::default());
+        assert_eq!(
+            params(&["answers", " answers,"]).includes(1).unwrap(),
+            Includes {
+                answers: true,
+                ..Includes::default()
+            }
+        );
+        assert_eq!(
+            params(&["attachments,answers.attachments"])
+                .includes(2)
+                .unwrap(),
+            Includes {
+                answers: true,
+                attachments: true,
+                answer_attachments: true,
+            }
+        );
+
+        let messages = |include: &str, max_depth: u32| match params(&[include]).includes(max_depth)
+        {
+            Err(AppError::InvalidQuery(errors)) => errors
+                .into_iter()
+                .map(|error| error.message)
+                .collect::<Vec<_>>(),
+            other => panic!("Expected InvalidQuery but got {:?}", other.map(|_| ())),
+        };
+        assert_eq!(
+            messages("answers,tags", 1),
+            ["`tags` is not one of: answers, attachments, answers.attachments"]
+        );
+        assert_eq!(
+            messages("answers.question", 1),
+            ["`answers.question` goes 2 deep, past the limit of 1"]
+        );
+        assert_eq!(
+            messages("answers.question", 2),
+            ["`answers.question` is not one of: answers, attachments, answers.attachments"]
+        );
+        assert_eq!(
+            messages("answers", 0),
+            ["`answers` goes 1 deep, past the limit of 0"]
+        );
+    }
+
+    #[test]
+    fn question_sorts_should_order_by_their_key() {
+        let titles = |sort: QuestionSort| {
+            let mut questions = vec![
+                question(Utc.with_ymd_and_hms(2023, 2, 1, 0, 0, 0).unwrap(), 0),
+                QuestionDetail {
+                    last_activity_at: Utc.with_ymd_and_hms(2023, 4, 1, 0, 0, 0).unwrap(),
+                    ..question(Utc.with_ymd_and_hms(2023, 1, 1, 0, 0, 0).unwrap(), 1)
+                },
+                question(Utc.with_ymd_and_hms(2023, 3, 1, 0, 0, 0).unwrap(), 2),
+            ];
+            sort.sort(&mut questions);
+            questions
+                .into_iter()
+                .map(|question| question.title)
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(
+            titles(QuestionSort::Oldest),
+            ["2023-01-01", "2023-02-01", "2023-03-01"]
+        );
+        assert_eq!(
+            titles(QuestionSort::Newest),
+            ["2023-03-01", "2023-02-01", "2023-01-01"]
+        );
+        assert_eq!(
+            titles(QuestionSort::Answers),
+            ["2023-03-01", "2023-01-01", "2023-02-01"]
+        );
+        assert_eq!(
+            titles(QuestionSort::Activity),
+            ["2023-01-01", "2023-03-01", "2023-02-01"]
+        );
+    }
+}
@@ -1,44 +1,421 @@
 use super::{
-    private::{self},
-    APIError,
+    answer::embed_attachments,
+    captcha::CaptchaToken,
+    dates::{self, TimezoneHeader},
+    deadline::Deadline,
+    etag::{IfMatch, Tagged},
+    moderation::{moderated_json, ModeratedJson},
+    ndjson::{AcceptsNdjson, Ndjson},
+    pagination::{PageParams, DEFAULT_PER_PAGE},
+    payload::{JsonContent, LimitedJson},
+    private::{self, SEARCH_MAX_LIMIT, SEMANTIC_SEARCH_MAX_LIMIT},
+    query::{Includes, QuestionListParams},
+    validation::FieldError,
+    AppError,
 };
+use crate::analytics::Tracker;
+use crate::dto::*;
 use crate::models::*;
-use crate::persistence::question_dao::QuestionDao;
-use rocket::{serde::json::Json, State};
+use crate::AppState;
+use chrono::Utc;
+use chrono_tz::Tz;
+use futures::{StreamExt, TryStreamExt};
+use rocket::{form::Errors, http::RawStr, response::Redirect, serde::json::Json, Either, State};
 
 #[post("/question", data = "<question>")]
 pub async fn create_question(
-    question: Json<Question>,
-    question_dao: &State<Box<dyn QuestionDao + Sync + Send>>,
-) -> Result<Json<QuestionDetail>, APIError> {
-    // let now = SystemTime::now();
-    // let now: DateTime<Local> = now.into();
-    let result = private::create_question(question.0, question_dao)
-        .await
-        .map_err(|err| APIError::from(err))?;
+    _content: JsonContent,
+    question: LimitedJson<CreateQuestionRequest>,
+    spam_context: SpamContext,
+    captcha: CaptchaToken,
+    state: &State<AppState>,
+    deadline: Deadline<'_>,
+) -> Result<ModeratedJson<QuestionResponse>, AppError> {
+    deadline.run(captcha.verify(state.captcha.as_ref())).await?;
+    let mut question = question.0;
+    let author_email = question.author_email.take();
+    let result = deadline
+        .run(private::create_question(
+            question.into(),
+            author_email,
+            state.question_dao.as_ref(),
+            private::WriteHooks::of(state),
+            &spam_context,
+        ))
+        .await?;
 
-    Ok(Json(result))
+    Ok(moderated_json(result))
 }
 
-#[get("/questions")]
-pub async fn get_questions(
-    question_dao: &State<Box<dyn QuestionDao + Sync + Send>>,
-) -> Result<Json<Vec<QuestionDetail>>, APIError> {
-    let result = private::get_questions(question_dao)
-        .await
-        .map_err(|err| APIError::from(err))?;
+#[post("/question/with-answer", data = "<payload>")]
+pub async fn create_question_with_answer(
+    _content: JsonContent,
+    payload: LimitedJson<CreateQuestionWithAnswerRequest>,
+    spam_context: SpamContext,
+    captcha: CaptchaToken,
+    state: &State<AppState>,
+    deadline: Deadline<'_>,
+) -> Result<ModeratedJson<QuestionWithAnswerResponse>, AppError> {
+    deadline.run(captcha.verify(state.captcha.as_ref())).await?;
+    let mut payload = payload.0;
+    let author_email = payload.question.author_email.take();
+    let result = deadline
+        .run(private::create_question_with_answer(
+            payload.into(),
+            author_email,
+            state.unit_of_work.as_ref(),
+            private::WriteHooks::of(state),
+            &spam_context,
+        ))
+        .await?;
 
-    Ok(Json(result))
+    Ok(moderated_json(result))
+}
+
+#[patch("/question/<question_uuid>", data = "<update>")]
+pub async fn update_question(
+    question_uuid: Result<QuestionUuid, uuid::Error>,
+    _content: JsonContent,
+    update: LimitedJson<UpdateQuestionRequest>,
+    if_match: IfMatch,
+    state: &State<AppState>,
+    deadline: Deadline<'_>,
+) -> Result<Json<QuestionResponse>, AppError> {
+    let result = deadline
+        .run(private::update_question(
+            question_uuid?,
+            update.0.into(),
+            if_match.0,
+            state.question_dao.as_ref(),
+            private::WriteHooks::of(state),
+        ))
+        .await?;
+
+    Ok(Json(result.into()))
+}
+
+// Streamed as NDJSON when asked for, whole and without an ETag since the rows are not
+// known up front.
+#[get("/questions?<params..>")]
+pub async fn get_questions<'r>(
+    params: Result<QuestionListParams, Errors<'_>>,
+    tz_header: TimezoneHeader,
+    accepts_ndjson: AcceptsNdjson,
+    state: &'r State<AppState>,
+    deadline: Deadline<'_>,
+) -> Result<Either<Ndjson<'r, QuestionResponse>, Tagged<QuestionResponse>>, AppError> {
+    let params = params?;
+    let archive_dao = params.include_archived.then(|| state.archive_dao.as_ref());
+    let tz = dates::timezone(params.tz.as_deref(), &tz_header)?;
+    let filter = QuestionFilter {
+        metadata: params.metadata_filter(),
+        created: params.created(tz, Utc::now())?,
+    };
+    let includes = params.includes(state.live_config.load().max_include_depth)?;
+
+    if accepts_ndjson.0 {
+        if includes != Includes::default() {
+            return Err(AppError::InvalidQuery(vec![FieldError {
+                field: "include".to_owned(),
+                message: "is not supported by NDJSON lists".to_owned(),
+            }]));
+        }
+        let rows = deadline
+            .run(private::stream_questions(
+                state.question_dao.as_ref(),
+                archive_dao,
+                filter,
+            ))
+            .await?;
+        return Ok(Either::Left(match tz {
+            Some(tz) => Ndjson(
+                rows.map_ok(move |question| {
+                    let mut question = QuestionResponse::from(question);
+                    dates::localize(&mut question, tz);
+                    question
+                })
+                .boxed(),
+            ),
+            None => Ndjson::mapped(rows),
+        }));
+    }
+
+    let page = params.page(DEFAULT_PER_PAGE)?;
+    let result = deadline
+        .run(private::get_questions(
+            state.question_dao.as_ref(),
+            params.include_archived,
+            params.sort,
+            &filter,
+            page.window(),
+        ))
+        .await?;
+    let mut page = page.listed(result).responses::<QuestionResponse>();
+    let question_uuids: Vec<QuestionUuid> = page
+        .items
+        .iter()
+        .map(|question| question.question_uuid)
+        .collect();
+
+    if includes.answers {
+        let mut answers = deadline
+            .run(private::get_answers_of_questions(
+                &question_uuids,
+                state.answer_dao.as_ref(),
+                archive_dao,
+            ))
+            .await?;
+        for question in &mut page.items {
+            let included = answers.remove(&question.question_uuid).unwrap_or_default();
+            question.answers = Some(responses(included));
+        }
+    }
+    if includes.attachments {
+        let mut attachments = deadline
+            .run(private::get_attachments_of_questions(
+                &question_uuids,
+                state.attachments.as_ref(),
+            ))
+            .await?;
+        for question in &mut page.items {
+            let included = attachments
+                .remove(&question.question_uuid)
+                .unwrap_or_default();
+            question.attachments = Some(responses(included));
+        }
+    }
+    if includes.answer_attachments {
+        let answers = page
+            .items
+            .iter_mut()
+            .flat_map(|question| question.answers.iter_mut().flatten())
+            .collect();
+        embed_attachments(answers, state, &deadline).await?;
+    }
+    if let Some(tz) = tz {
+        for question in &mut page.items {
+            dates::localize(question, tz);
+        }
+    }
+
+    Ok(Either::Right(Tagged::new(page)))
+}
+
+// Best matches first, up to `SEARCH_MAX_LIMIT` over all pages. `q` takes web search
+// syntax: quoted phrases, `or` and `-word`.
+#[get("/questions/search?<q>&<pagination..>")]
+pub async fn search_questions(
+    q: String,
+    pagination: Result<PageParams, Errors<'_>>,
+    state: &State<AppState>,
+    deadline: Deadline<'_>,
+    tracker: Tracker<'_>,
+) -> Result<Json<SearchResponse>, AppError> {
+    let page = pagination?.page(10)?;
+    let wanted = page.end().min(SEARCH_MAX_LIMIT as usize);
+    let result = deadline
+        .run(private::search_questions(
+            q.clone(),
+            wanted as u32,
+            state.search.as_ref(),
+        ))
+        .await?;
+
+    tracker.track(
+        "search_performed",
+        serde_json::json!({ "kind": "full_text", "query": q, "results": result.len() }),
+    );
+    let more = result.len() == wanted && wanted < SEARCH_MAX_LIMIT as usize;
+    let below = state.live_config.load().search_suggestions_below;
+    let threshold = state.live_config.load().search_suggestion_threshold;
+    let suggestions = deadline
+        .run(private::suggest_titles(
+            &q,
+            &result,
+            below,
+            threshold,
+            state.search.as_ref(),
+        ))
+        .await?;
+    let page = page.of_first(result, more);
+    let highlights = deadline
+        .run(private::highlight(&q, &page.items, state.search.as_ref()))
+        .await?;
+
+    let mut page = page.responses::<QuestionResponse>();
+    for (question, highlight) in page.items.iter_mut().zip(highlights.into_iter().flatten()) {
+        question.highlight = Some(highlight.into());
+    }
+    Ok(Json(SearchResponse {
+        page,
+        suggestions: suggestions.map(responses),
+    }))
+}
+
+// Titles completing `q` as it is typed, at most `AUTOCOMPLETE_MAX_LIMIT` of them. Not
+// tracked like searches, as it runs on every keystroke.
+#[get("/questions/autocomplete?<q>&<limit>")]
+pub async fn autocomplete(
+    q: String,
+    limit: Option<u32>,
+    state: &State<AppState>,
+    deadline: Deadline<'_>,
+) -> Result<Json<Vec<TitleMatchResponse>>, AppError> {
+    let timeout = state.live_config.load().autocomplete_timeout;
+    let result = deadline
+        .at_most(timeout)
+        .run(private::complete_titles(
+            q,
+            limit.unwrap_or(5),
+            state.search.as_ref(),
+        ))
+        .await?;
+
+    Ok(Json(result.into_iter().map(Into::into).collect()))
+}
+
+// Most similar questions first, up to `SEMANTIC_SEARCH_MAX_LIMIT` over all pages.
+#[get("/questions/semantic-search?<q>&<pagination..>")]
+pub async fn semantic_search(
+    q: String,
+    pagination: Result<PageParams, Errors<'_>>,
+    state: &State<AppState>,
+    deadline: Deadline<'_>,
+    tracker: Tracker<'_>,
+) -> Result<Json<Paginated<QuestionResponse>>, AppError> {
+    let page = pagination?.page(10)?;
+    let wanted = page.end().min(SEMANTIC_SEARCH_MAX_LIMIT as usize);
+    let result = deadline
+        .run(private::semantic_search(
+            q.clone(),
+            wanted as u32,
+            state.semantic_search.as_ref(),
+        ))
+        .await?;
+
+    tracker.track(
+        "search_performed",
+        serde_json::json!({ "kind": "semantic", "query": q, "results": result.len() }),
+    );
+    let more = result.len() == wanted && wanted < SEMANTIC_SEARCH_MAX_LIMIT as usize;
+    Ok(Json(page.of_first(result, more).responses()))
+}
+
+#[post("/questions/suggest-tags", data = "<request>")]
+pub async fn suggest_tags(
+    _content: JsonContent,
+    request: LimitedJson<SuggestTagsRequest>,
+    state: &State<AppState>,
+    deadline: Deadline<'_>,
+) -> Result<Json<SuggestedTagsResponse>, AppError> {
+    let request = request.0;
+    let tags = deadline
+        .run(private::suggest_tags(
+            request.title,
+            request.description,
+            state.tag_suggestions.as_ref(),
+        ))
+        .await?;
+
+    Ok(Json(SuggestedTagsResponse { tags }))
+}
+
+#[get("/question/<question_uuid>?<lang>&<tz>")]
+pub async fn get_question(
+    question_uuid: Result<QuestionUuid, uuid::Error>,
+    lang: Option<String>,
+    tz: Option<String>,
+    tz_header: TimezoneHeader,
+    state: &State<AppState>,
+    deadline: Deadline<'_>,
+    tracker: Tracker<'_>,
+) -> Result<Either<Redirect, Json<QuestionResponse>>, AppError> {
+    let question_uuid = question_uuid?;
+    let zone = dates::timezone(tz.as_deref(), &tz_header)?;
+    let result = deadline
+        .run(private::get_question(
+            question_uuid,
+            lang.clone(),
+            state.question_dao.as_ref(),
+            state.translations.as_ref(),
+            state.translation_dao.as_deref(),
+            state.link_previews.as_ref(),
+        ))
+        .await;
+    let (question, language, link_previews) = match result {
+        // Questions merged into another one point at it for good. `lang` was checked
+        // before the lookup, so it is a plain language code; `tz` goes on as the
+        // zone it named.
+        Err(AppError::Database(DBError::NotFound(Entity::Question, message))) => {
+            let redirect = deadline
+                .run(private::find_redirect(
+                    question_uuid,
+                    state.merge_dao.as_deref(),
+                ))
+                .await?;
+            return match redirect {
+                Some(target_uuid) => {
+                    let query = redirect_query(lang, tz.and(zone));
+                    Ok(Either::Left(Redirect::permanent(format!(
+                        "/question/{target_uuid}{query}"
+                    ))))
+                }
+                None => Err(DBError::NotFound(Entity::Question, message).into()),
+            };
+        }
+        result => result?,
+    };
+
+    tracker.track(
+        "question_viewed",
+        serde_json::json!({
+            "question_uuid": question.question_uuid,
+            "language": language,
+        }),
+    );
+    let mut question = QuestionResponse {
+        language,
+        link_previews: link_previews.map(|previews| {
+            previews
+                .into_iter()
+                .map(LinkPreviewResponse::from)
+                .collect()
+        }),
+        ..question.into()
+    };
+    if let Some(zone) = zone {
+        dates::localize(&mut question, zone);
+    }
+    Ok(Either::Right(Json(question)))
 }
 
 #[delete("/question/<question_uuid>")]
 pub async fn delete_question(
-    question_uuid: String,
-    question_dao: &State<Box<dyn QuestionDao + Sync + Send>>,
-) -> Result<(), APIError> {
-    private::delete_question(question_uuid, question_dao)
-        .await
-        .map_err(|err| APIError::from(err))?;
+    question_uuid: Result<QuestionUuid, uuid::Error>,
+    state: &State<AppState>,
+    deadline: Deadline<'_>,
+) -> Result<(), AppError> {
+    deadline
+        .run(private::delete_question(
+            question_uuid?,
+            state.question_dao.as_ref(),
+        ))
+        .await?;
 
     Ok(())
 }
+
+// `?lang=` and `?tz=` as they go on to the question a merged one points at.
+fn redirect_query(lang: Option<String>, zone: Option<Tz>) -> String {
+    let mut query: Vec<String> = lang
+        .into_iter()
+        .map(|lang| format!("lang={lang}"))
+        .collect();
+    query.extend(zone.map(|zone| format!("tz={}", RawStr::new(zone.name()).percent_encode())));
+
+    if query.is_empty() {
+        String::new()
+    } else {
+        format!("?{}", query.join("&"))
+    }
+}
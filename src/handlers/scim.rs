@@ -0,0 +1,178 @@
+use rocket::{
+    http::{ContentType, Status},
+    request::{self, FromRequest},
+    response::status,
+    serde::json::Json,
+    Request, State,
+};
+
+use super::{
+    admin::bearer_token,
+    deadline::Deadline,
+    payload::{LimitedJson, UnsupportedMedia},
+    private, AppError,
+};
+use crate::{
+    dto::{
+        ScimListResponse, ScimPatchRequest, ScimUserRequest, ScimUserResponse, SCIM_LIST_SCHEMA,
+    },
+    models::UserUuid,
+    AppState,
+};
+
+// SCIM's media type.
+fn scim_json<T>(body: T) -> (ContentType, Json<T>) {
+    (ContentType::new("application", "scim+json"), Json(body))
+}
+
+// Request guard for the identity provider: `Authorization: Bearer <SCIM_TOKEN>`.
+pub struct ScimClient;
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for ScimClient {
+    type Error = ();
+
+    async fn from_request(req: &'r Request<'_>) -> request::Outcome<Self, Self::Error> {
+        bearer_token(req, |config| config.scim_token.clone()).map(|()| ScimClient)
+    }
+}
+
+// Like `JsonContent`, but identity providers may also send `application/scim+json`.
+pub struct ScimContent;
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for ScimContent {
+    type Error = ();
+
+    async fn from_request(req: &'r Request<'_>) -> request::Outcome<Self, Self::Error> {
+        match req.content_type() {
+            Some(content_type)
+                if content_type.is_json()
+                    || (content_type.top() == "application"
+                        && content_type.sub() == "scim+json") =>
+            {
+                request::Outcome::Success(ScimContent)
+            }
+            content_type => {
+                req.local_cache(|| {
+                    UnsupportedMedia(content_type.map(|ct| format!("{}/{}", ct.top(), ct.sub())))
+                });
+                request::Outcome::Error((Status::UnsupportedMediaType, ()))
+            }
+        }
+    }
+}
+
+#[derive(FromForm, Debug)]
+pub struct ScimListParams {
+    filter: Option<String>,
+    #[field(name = "startIndex")]
+    start_index: Option<i64>,
+    count: Option<i64>,
+}
+
+#[get("/scim/v2/Users?<params..>")]
+pub async fn list_users(
+    _client: ScimClient,
+    params: ScimListParams,
+    state: &State<AppState>,
+    deadline: Deadline<'_>,
+) -> Result<(ContentType, Json<ScimListResponse>), AppError> {
+    let (users, total, start_index) = deadline
+        .run(private::scim_users(
+            params.filter.as_deref(),
+            params.start_index,
+            params.count,
+            state.user_dao.as_deref(),
+        ))
+        .await?;
+
+    Ok(scim_json(ScimListResponse {
+        schemas: vec![SCIM_LIST_SCHEMA.to_owned()],
+        total_results: total,
+        start_index,
+        items_per_page: users.len() as i64,
+        resources: users.into_iter().map(ScimUserResponse::from).collect(),
+    }))
+}
+
+#[get("/scim/v2/Users/<user_uuid>")]
+pub async fn get_user(
+    _client: ScimClient,
+    user_uuid: Result<UserUuid, uuid::Error>,
+    state: &State<AppState>,
+    deadline: Deadline<'_>,
+) -> Result<(ContentType, Json<ScimUserResponse>), AppError> {
+    let user = deadline
+        .run(private::get_scim_user(
+            user_uuid?,
+            state.user_dao.as_deref(),
+        ))
+        .await?;
+
+    Ok(scim_json(user.into()))
+}
+
+// SCIM clients expect `201` here, unlike the API's other creates.
+#[post("/scim/v2/Users", data = "<user>")]
+pub async fn create_user(
+    _client: ScimClient,
+    _content: ScimContent,
+    user: LimitedJson<ScimUserRequest>,
+    state: &State<AppState>,
+    deadline: Deadline<'_>,
+) -> Result<status::Created<(ContentType, Json<ScimUserResponse>)>, AppError> {
+    let user = deadline
+        .run(private::create_scim_user(
+            user.0.into(),
+            state.user_dao.as_deref(),
+            state.notifications.as_ref(),
+        ))
+        .await?;
+
+    let response = ScimUserResponse::from(user);
+    Ok(status::Created::new(response.meta.location.clone()).body(scim_json(response)))
+}
+
+#[put("/scim/v2/Users/<user_uuid>", data = "<user>")]
+pub async fn replace_user(
+    _client: ScimClient,
+    _content: ScimContent,
+    user_uuid: Result<UserUuid, uuid::Error>,
+    user: LimitedJson<ScimUserRequest>,
+    state: &State<AppState>,
+    deadline: Deadline<'_>,
+) -> Result<(ContentType, Json<ScimUserResponse>), AppError> {
+    let user = deadline
+        .run(private::replace_scim_user(
+            user_uuid?,
+            user.0.into(),
+            state.user_dao.as_deref(),
+            state.notifications.as_ref(),
+        ))
+        .await?;
+
+    Ok(scim_json(user.into()))
+}
+
+// How Okta and Entra ID deactivate users: `replace` on `active` with false.
+#[patch("/scim/v2/Users/<user_uuid>", data = "<patch>")]
+pub async fn patch_user(
+    _client: ScimClient,
+    _content: ScimContent,
+    user_uuid: Result<UserUuid, uuid::Error>,
+    patch: LimitedJson<ScimPatchRequest>,
+    state: &State<AppState>,
+    deadline: Deadline<'_>,
+) -> Result<(ContentType, Json<ScimUserResponse>), AppError> {
+    let user = deadline
+        .run(private::patch_scim_user(
+            user_uuid?,
+            patch.0.operations,
+            state.user_dao.as_deref(),
+            state.notifications.as_ref(),
+        ))
+        .await?;
+
+    Ok(scim_json(user.into()))
+}
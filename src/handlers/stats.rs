@@ -0,0 +1,44 @@
+use rocket::{serde::json::Json, State};
+
+use super::{admin::Admin, deadline::Deadline, private, AppError};
+use crate::{dto::AdminStatsResponse, models::DailyStats, AppState};
+
+#[get("/stats/daily")]
+pub async fn get_daily_stats(
+    state: &State<AppState>,
+    deadline: Deadline<'_>,
+) -> Result<Json<Vec<DailyStats>>, AppError> {
+    let result = deadline
+        .run(private::get_daily_stats(state.stats_dao.as_ref()))
+        .await?;
+
+    Ok(Json(result))
+}
+
+// For an ops or community dashboard; see `AdminStatsResponse`.
+#[get("/admin/stats?<days>")]
+pub async fn get_admin_stats(
+    _admin: Admin,
+    days: Option<i64>,
+    state: &State<AppState>,
+    deadline: Deadline<'_>,
+) -> Result<Json<AdminStatsResponse>, AppError> {
+    let result = deadline
+        .run(private::get_admin_stats(days, state.stats_dao.as_ref()))
+        .await?;
+
+    Ok(Json(result))
+}
+
+#[post("/admin/stats/refresh")]
+pub async fn refresh_stats(
+    _admin: Admin,
+    state: &State<AppState>,
+    deadline: Deadline<'_>,
+) -> Result<(), AppError> {
+    deadline
+        .run(private::refresh_stats(state.stats_dao.as_ref()))
+        .await?;
+
+    Ok(())
+}
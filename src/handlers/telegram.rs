@@ -0,0 +1,113 @@
+use rocket::{
+    http::Status,
+    request::{self, FromRequest},
+    serde::json::Json,
+    Either, Request, State,
+};
+use serde::Deserialize;
+use serde_json::Value;
+
+use super::{admin::tokens_match, deadline::Deadline, private, AppError};
+use crate::{chat, models::Window, AppState};
+
+// Questions listed in a reply to `/unanswered`.
+const UNANSWERED_LIMIT: u64 = 10;
+
+// Request guard for Telegram's webhook calls, which carry the secret passed to
+// `setWebhook` as `X-Telegram-Bot-Api-Secret-Token`.
+pub struct TelegramWebhook;
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for TelegramWebhook {
+    type Error = ();
+
+    async fn from_request(req: &'r Request<'_>) -> request::Outcome<Self, Self::Error> {
+        let expected = req
+            .rocket()
+            .state::<AppState>()
+            .and_then(|state| state.live_config.load().telegram_webhook_secret.clone());
+        let Some(expected) = expected else {
+            return request::Outcome::Error((Status::Forbidden, ()));
+        };
+
+        match req.headers().get_one("X-Telegram-Bot-Api-Secret-Token") {
+            Some(secret) if tokens_match(secret, &expected) => {
+                request::Outcome::Success(TelegramWebhook)
+            }
+            _ => request::Outcome::Error((Status::Unauthorized, ())),
+        }
+    }
+}
+
+// The parts of an update the commands read; other updates are acknowledged and ignored.
+#[derive(Deserialize, Debug)]
+pub struct Update {
+    message: Option<Message>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct Message {
+    chat: Chat,
+    text: Option<String>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct Chat {
+    id: i64,
+}
+
+// Replies go back in the response as a `sendMessage` call, so answering commands needs
+// no access to the Bot API.
+#[post("/telegram/updates", data = "<update>")]
+pub async fn receive_update(
+    _webhook: TelegramWebhook,
+    update: Json<Update>,
+    state: &State<AppState>,
+    deadline: Deadline<'_>,
+) -> Result<Either<Json<Value>, ()>, AppError> {
+    let Some(message) = update.0.message else {
+        return Ok(Either::Right(()));
+    };
+
+    // In groups commands may be addressed as `/unanswered@SomeBot`.
+    let command = message
+        .text
+        .as_deref()
+        .and_then(|text| text.split_whitespace().next())
+        .and_then(|word| word.split('@').next());
+    if command != Some("/unanswered") {
+        return Ok(Either::Right(()));
+    }
+
+    let questions = deadline
+        .run(private::get_unanswered_questions(
+            state.question_dao.as_ref(),
+            Window {
+                offset: 0,
+                limit: UNANSWERED_LIMIT,
+            },
+        ))
+        .await?
+        .items;
+
+    let public_url = state.live_config.load().public_url.clone();
+    let text = if questions.is_empty() {
+        "Every question has an answer.".to_owned()
+    } else {
+        let lines: Vec<_> = questions
+            .iter()
+            .map(|question| {
+                format!(
+                    "- {}\n  {}",
+                    question.title,
+                    chat::link_or_route(public_url.as_deref(), question.question_uuid)
+                )
+            })
+            .collect();
+        format!("Waiting for an answer, oldest first:\n{}", lines.join("\n"))
+    };
+
+    let mut reply = chat::telegram_message(&message.chat.id.to_string(), &text);
+    reply["method"] = "sendMessage".into();
+    Ok(Either::Left(Json(reply)))
+}
@@ -0,0 +1,79 @@
+use rocket::{serde::json::Json, State};
+
+use super::{
+    admin::Admin,
+    deadline::Deadline,
+    payload::{JsonContent, LimitedJson},
+    private, AppError,
+};
+use crate::{
+    dto::{TranslationRequest, TranslationResponse},
+    models::QuestionUuid,
+    AppState,
+};
+
+// Translations of a question people submitted, which `GET /question/<uuid>?lang=`
+// serves ahead of machine translations.
+#[get("/question/<question_uuid>/translations")]
+pub async fn get_translations(
+    question_uuid: Result<QuestionUuid, uuid::Error>,
+    state: &State<AppState>,
+    deadline: Deadline<'_>,
+) -> Result<Json<Vec<TranslationResponse>>, AppError> {
+    let result = deadline
+        .run(private::get_translations(
+            question_uuid?,
+            state.question_dao.as_ref(),
+            state.translation_dao.as_deref(),
+        ))
+        .await?;
+
+    Ok(Json(
+        result.into_iter().map(TranslationResponse::from).collect(),
+    ))
+}
+
+#[put(
+    "/question/<question_uuid>/translations/<language>",
+    data = "<request>"
+)]
+pub async fn submit_translation(
+    question_uuid: Result<QuestionUuid, uuid::Error>,
+    language: &str,
+    _content: JsonContent,
+    request: LimitedJson<TranslationRequest>,
+    state: &State<AppState>,
+    deadline: Deadline<'_>,
+) -> Result<Json<TranslationResponse>, AppError> {
+    let request = request.0;
+    let result = deadline
+        .run(private::submit_translation(
+            question_uuid?,
+            language,
+            request.title,
+            request.description,
+            state.translation_dao.as_deref(),
+            state.word_filter.as_ref(),
+        ))
+        .await?;
+
+    Ok(Json(result.into()))
+}
+
+// For taking down a bad translation; a new one can be submitted in its place too.
+#[delete("/question/<question_uuid>/translations/<language>")]
+pub async fn delete_translation(
+    _admin: Admin,
+    question_uuid: Result<QuestionUuid, uuid::Error>,
+    language: &str,
+    state: &State<AppState>,
+    deadline: Deadline<'_>,
+) -> Result<(), AppError> {
+    deadline
+        .run(private::delete_translation(
+            question_uuid?,
+            language,
+            state.translation_dao.as_deref(),
+        ))
+        .await
+}
@@ -0,0 +1,597 @@
+use rocket::serde::Serialize;
+use serde_json::Value;
+
+use crate::{
+    events::EVENT_TYPES,
+    models::{
+        Answer, AnswerUpdate, Import, ImportedQuestion, PushSubscription, Question, QuestionUpdate,
+        QuestionUuid, QuestionWithAnswer, UserAttributes, MAX_EMAIL_BYTES, MAX_HOOK_URL_BYTES,
+        MAX_METADATA_BYTES, MAX_PUSH_ENDPOINT_BYTES, MAX_PUSH_QUESTIONS, MAX_TEXT_CHARS,
+    },
+};
+
+use super::AppError;
+
+// One rejected field of a request body, named by its path, e.g. `questions[2].title`.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "camel-case", serde(rename_all = "camelCase"))]
+pub struct FieldError {
+    pub field: String,
+    pub message: String,
+}
+
+// Collects what is wrong with a body's text fields while trimming them, so every
+// problem is reported at once.
+#[derive(Default)]
+struct Fields(Vec<FieldError>);
+
+impl Fields {
+    // Surrounding whitespace is dropped before the checks and is not stored.
+    fn text(&mut self, field: String, value: String) -> String {
+        let value = value.trim();
+        let problem = if value.is_empty() {
+            Some("must not be empty".to_owned())
+        } else if value.chars().count() > MAX_TEXT_CHARS {
+            Some(format!("must be at most {} characters", MAX_TEXT_CHARS))
+        } else {
+            None
+        };
+
+        if let Some(message) = problem {
+            self.0.push(FieldError { field, message });
+        }
+        value.to_owned()
+    }
+
+    // Like `text`, but a missing or blank value is fine and becomes `None`.
+    fn optional_text(&mut self, field: String, value: Option<String>) -> Option<String> {
+        let value = value.filter(|value| !value.trim().is_empty())?;
+        Some(self.text(field, value))
+    }
+
+    // Metadata is stored as sent, but must be an object and small enough to index.
+    fn metadata(&mut self, field: String, value: Value) -> Value {
+        let problem = if !value.is_object() {
+            Some("must be an object".to_owned())
+        } else if value.to_string().len() > MAX_METADATA_BYTES {
+            Some(format!("must be at most {} bytes", MAX_METADATA_BYTES))
+        } else {
+            None
+        };
+
+        if let Some(message) = problem {
+            self.0.push(FieldError { field, message });
+        }
+        value
+    }
+
+    // Trimmed and lowercased, so an address subscribes once however it is typed. Only
+    // the shape is checked; whether mail arrives is up to the mail server.
+    fn email(&mut self, field: String, value: String) -> String {
+        let value = value.trim().to_lowercase();
+        let valid = value.len() <= MAX_EMAIL_BYTES
+            && !value.contains(char::is_whitespace)
+            && value.split_once('@').is_some_and(|(local, domain)| {
+                !local.is_empty() && !domain.contains('@') && domain.contains('.')
+            });
+
+        if !valid {
+            self.0.push(FieldError {
+                field,
+                message: "must be an email address".to_owned(),
+            });
+        }
+        value
+    }
+
+    // A key from the browser, which must decode to `bytes` bytes. Only the encoding is
+    // checked; whether it is a usable key shows when pushing.
+    fn push_key(&mut self, field: String, value: String, bytes: usize) -> String {
+        let unpadded = value.trim_end_matches('=');
+        let valid = unpadded.len() % 4 != 1
+            && unpadded.len() * 3 / 4 == bytes
+            && unpadded
+                .bytes()
+                .all(|byte| byte.is_ascii_alphanumeric() || byte == b'-' || byte == b'_');
+
+        if !valid {
+            self.0.push(FieldError {
+                field,
+                message: format!("must be {} bytes of base64url", bytes),
+            });
+        }
+        value
+    }
+
+    fn question(&mut self, prefix: &str, question: Question) -> Question {
+        Question {
+            title: self.text(format!("{}title", prefix), question.title),
+            description: self.optional_text(format!("{}description", prefix), question.description),
+            metadata: self.metadata(format!("{}metadata", prefix), question.metadata),
+        }
+    }
+
+    fn finish<T>(self, checked: T) -> Result<T, AppError> {
+        if self.0.is_empty() {
+            Ok(checked)
+        } else {
+            Err(AppError::Invalid(self.0))
+        }
+    }
+}
+
+pub fn question(
+    question: Question,
+    author_email: Option<String>,
+) -> Result<(Question, Option<String>), AppError> {
+    let mut fields = Fields::default();
+    let question = fields.question("", question);
+    let author_email = author_email.map(|email| fields.email("author_email".to_owned(), email));
+    fields.finish((question, author_email))
+}
+
+// Like the title and description of a question.
+pub fn translation(
+    title: String,
+    description: Option<String>,
+) -> Result<(String, Option<String>), AppError> {
+    let mut fields = Fields::default();
+    let title = fields.text("title".to_owned(), title);
+    let description = fields.optional_text("description".to_owned(), description);
+    fields.finish((title, description))
+}
+
+pub fn email(email: String) -> Result<String, AppError> {
+    let mut fields = Fields::default();
+    let email = fields.email("email".to_owned(), email);
+    fields.finish(email)
+}
+
+// Users are known by their email address, so the user name must be one. Fields are
+// named as SCIM names them.
+pub fn user(attributes: UserAttributes) -> Result<UserAttributes, AppError> {
+    let mut fields = Fields::default();
+    let attributes = UserAttributes {
+        user_name: fields.email("userName".to_owned(), attributes.user_name),
+        external_id: fields.optional_text("externalId".to_owned(), attributes.external_id),
+        display_name: fields.optional_text("displayName".to_owned(), attributes.display_name),
+        active: attributes.active,
+    };
+    fields.finish(attributes)
+}
+
+// The keys are a P-256 public key and a 16 byte secret, as the Push API makes them.
+pub fn push_subscription(
+    subscription: PushSubscription,
+    question_uuids: Vec<QuestionUuid>,
+) -> Result<(PushSubscription, Vec<QuestionUuid>), AppError> {
+    let mut fields = Fields::default();
+    if !subscription.endpoint.starts_with("https://")
+        || subscription.endpoint.len() > MAX_PUSH_ENDPOINT_BYTES
+    {
+        fields.0.push(FieldError {
+            field: "endpoint".to_owned(),
+            message: format!(
+                "must be an https URL of at most {} bytes",
+                MAX_PUSH_ENDPOINT_BYTES
+            ),
+        });
+    }
+    let subscription = PushSubscription {
+        p256dh: fields.push_key("keys.p256dh".to_owned(), subscription.p256dh, 65),
+        auth: fields.push_key("keys.auth".to_owned(), subscription.auth, 16),
+        endpoint: subscription.endpoint,
+    };
+    if question_uuids.is_empty() || question_uuids.len() > MAX_PUSH_QUESTIONS {
+        fields.0.push(FieldError {
+            field: "question_uuids".to_owned(),
+            message: format!("must list 1 to {} questions", MAX_PUSH_QUESTIONS),
+        });
+    }
+    fields.finish((subscription, question_uuids))
+}
+
+// Hooks are only POSTed to over https, as they may carry anything that was written.
+pub fn rest_hook(target_url: String, event: String) -> Result<(String, String), AppError> {
+    let mut fields = Fields::default();
+    let target_url = target_url.trim().to_owned();
+    if !target_url.starts_with("https://") || target_url.len() > MAX_HOOK_URL_BYTES {
+        fields.0.push(FieldError {
+            field: "target_url".to_owned(),
+            message: format!(
+                "must be an https URL of at most {} bytes",
+                MAX_HOOK_URL_BYTES
+            ),
+        });
+    }
+    if !EVENT_TYPES.contains(&event.as_str()) {
+        fields.0.push(FieldError {
+            field: "event".to_owned(),
+            message: format!("must be one of {}", EVENT_TYPES.join(", ")),
+        });
+    }
+    fields.finish((target_url, event))
+}
+
+// Only the last segment of a path is kept, as browsers may send the whole one. The
+// content type must have the `type/subtype` shape and is lowercased; nothing checks
+// that the bytes match it.
+pub fn attachment(
+    filename: String,
+    content_type: String,
+    size_bytes: i64,
+    max_bytes: u64,
+) -> Result<(String, String), AppError> {
+    let mut fields = Fields::default();
+    let filename = filename
+        .rsplit(['/', '\\'])
+        .next()
+        .unwrap_or_default()
+        .to_owned();
+    let filename = fields.text("filename".to_owned(), filename);
+    if filename.chars().any(char::is_control) {
+        fields.0.push(FieldError {
+            field: "filename".to_owned(),
+            message: "must not contain control characters".to_owned(),
+        });
+    }
+
+    let content_type = content_type.trim().to_lowercase();
+    let token = |part: &str| {
+        !part.is_empty()
+            && part
+                .bytes()
+                .all(|byte| byte.is_ascii_alphanumeric() || b"!#$&^_.+-".contains(&byte))
+    };
+    let valid = content_type.len() <= MAX_TEXT_CHARS
+        && content_type
+            .split_once('/')
+            .is_some_and(|(kind, subtype)| token(kind) && token(subtype));
+    if !valid {
+        fields.0.push(FieldError {
+            field: "content_type".to_owned(),
+            message: "must be a media type such as image/png".to_owned(),
+        });
+    }
+
+    if size_bytes < 1 || size_bytes as u64 > max_bytes {
+        fields.0.push(FieldError {
+            field: "size_bytes".to_owned(),
+            message: format!("must be 1 to {} bytes", max_bytes),
+        });
+    }
+    fields.finish((filename, content_type))
+}
+
+pub fn question_update(update: QuestionUpdate) -> Result<QuestionUpdate, AppError> {
+    let mut fields = Fields::default();
+    let update = QuestionUpdate {
+        title: fields.text("title".to_owned(), update.title),
+        description: fields.optional_text("description".to_owned(), update.description),
+        metadata: fields.metadata("metadata".to_owned(), update.metadata),
+        version: update.version,
+    };
+    fields.finish(update)
+}
+
+pub fn question_with_answer(
+    payload: QuestionWithAnswer,
+    author_email: Option<String>,
+) -> Result<(QuestionWithAnswer, Option<String>), AppError> {
+    let mut fields = Fields::default();
+    let payload = QuestionWithAnswer {
+        question: fields.question("question.", payload.question),
+        answer: fields.text("answer".to_owned(), payload.answer),
+    };
+    let author_email =
+        author_email.map(|email| fields.email("question.author_email".to_owned(), email));
+    fields.finish((payload, author_email))
+}
+
+pub fn answer(answer: Answer) -> Result<Answer, AppError> {
+    let mut fields = Fields::default();
+    let answer = Answer {
+        question_uuid: answer.question_uuid,
+        content: fields.text("content".to_owned(), answer.content),
+    };
+    fields.finish(answer)
+}
+
+pub fn answer_update(update: AnswerUpdate) -> Result<AnswerUpdate, AppError> {
+    let mut fields = Fields::default();
+    let update = AnswerUpdate {
+        content: fields.text("content".to_owned(), update.content),
+        version: update.version,
+    };
+    fields.finish(update)
+}
+
+pub fn import(import: Import) -> Result<Import, AppError> {
+    let mut fields = Fields::default();
+    let questions = import
+        .questions
+        .into_iter()
+        .enumerate()
+        .map(|(index, question)| {
+            let prefix = format!("questions[{}].", index);
+            let checked = fields.question(
+                &prefix,
+                Question {
+                    title: question.title,
+                    description: question.description,
+                    metadata: question.metadata,
+                },
+            );
+            let answers = question
+                .answers
+                .into_iter()
+                .enumerate()
+                .map(|(answer_index, answer)| {
+                    fields.text(format!("{}answers[{}]", prefix, answer_index), answer)
+                })
+                .collect();
+
+            ImportedQuestion {
+                title: checked.title,
+                description: checked.description,
+                metadata: checked.metadata,
+                answers,
+            }
+        })
+        .collect();
+    fields.finish(Import { questions })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{empty_metadata, QuestionUuid};
+    use serde_json::json;
+
+    fn field_errors(result: Result<impl Sized, AppError>) -> Vec<(String, String)> {
+        match result {
+            Err(AppError::Invalid(errors)) => errors
+                .into_iter()
+                .map(|error| (error.field, error.message))
+                .collect(),
+            _ => panic!("expected field errors"),
+        }
+    }
+
+    #[test]
+    fn question_should_be_trimmed() {
+        let question = question(
+            Question {
+                title: "  title ".to_owned(),
+                description: Some("\tdescription\n".to_owned()),
+                metadata: json!({ "source": " zendesk " }),
+            },
+            None,
+        );
+
+        assert_eq!(
+            question.unwrap().0,
+            Question {
+                title: "title".to_owned(),
+                description: Some("description".to_owned()),
+                metadata: json!({ "source": " zendesk " }),
+            }
+        );
+    }
+
+    #[test]
+    fn blank_description_should_be_missing() {
+        let question = question(
+            Question {
+                title: "title".to_owned(),
+                description: Some(" ".to_owned()),
+                metadata: empty_metadata(),
+            },
+            None,
+        );
+
+        assert_eq!(question.unwrap().0.description, None);
+    }
+
+    #[test]
+    fn question_should_report_every_bad_field() {
+        let errors = field_errors(question(
+            Question {
+                title: "   ".to_owned(),
+                description: Some("d".repeat(MAX_TEXT_CHARS + 1)),
+                metadata: json!(["zendesk"]),
+            },
+            Some("author".to_owned()),
+        ));
+
+        assert_eq!(
+            errors,
+            vec![
+                ("title".to_owned(), "must not be empty".to_owned()),
+                (
+                    "description".to_owned(),
+                    "must be at most 255 characters".to_owned()
+                ),
+                ("metadata".to_owned(), "must be an object".to_owned()),
+                (
+                    "author_email".to_owned(),
+                    "must be an email address".to_owned()
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn emails_should_be_normalized_and_look_like_addresses() {
+        assert_eq!(
+            email(" Author@Example.COM ".to_owned()).unwrap(),
+            "author@example.com"
+        );
+        for bad in [
+            "",
+            "author",
+            "@example.com",
+            "a@b@example.com",
+            "a b@example.com",
+            "a@localhost",
+        ] {
+            assert!(
+                email(bad.to_owned()).is_err(),
+                "{:?} should be rejected",
+                bad
+            );
+        }
+    }
+
+    #[test]
+    fn push_subscriptions_should_have_keys_of_the_right_size() {
+        let subscription = |endpoint: &str, p256dh: String, auth: &str| PushSubscription {
+            endpoint: endpoint.to_owned(),
+            p256dh,
+            auth: auth.to_owned(),
+        };
+        let p256dh = format!("B{}", "A".repeat(86));
+
+        assert!(push_subscription(
+            subscription(
+                "https://push.example.net/1",
+                p256dh.clone(),
+                "c2VjcmV0c2VjcmV0c2VjcQ=="
+            ),
+            vec![QuestionUuid::new_v4()],
+        )
+        .is_ok());
+        assert_eq!(
+            field_errors(push_subscription(
+                subscription(
+                    "http://push.example.net/1",
+                    p256dh[1..].to_owned(),
+                    "c2VjcmV0+2VjcmV0c2VjcQ"
+                ),
+                vec![],
+            )),
+            vec![
+                (
+                    "endpoint".to_owned(),
+                    "must be an https URL of at most 2048 bytes".to_owned()
+                ),
+                (
+                    "keys.p256dh".to_owned(),
+                    "must be 65 bytes of base64url".to_owned()
+                ),
+                (
+                    "keys.auth".to_owned(),
+                    "must be 16 bytes of base64url".to_owned()
+                ),
+                (
+                    "question_uuids".to_owned(),
+                    "must list 1 to 100 questions".to_owned()
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn attachments_should_keep_the_file_name_only() {
+        assert_eq!(
+            attachment(
+                "C:\\Users\\me\\photo.png ".to_owned(),
+                "Image/PNG".to_owned(),
+                10,
+                10
+            )
+            .unwrap(),
+            ("photo.png".to_owned(), "image/png".to_owned())
+        );
+        assert_eq!(
+            field_errors(attachment(
+                "../\u{7}.png".to_owned(),
+                "image".to_owned(),
+                11,
+                10
+            )),
+            vec![
+                (
+                    "filename".to_owned(),
+                    "must not contain control characters".to_owned()
+                ),
+                (
+                    "content_type".to_owned(),
+                    "must be a media type such as image/png".to_owned()
+                ),
+                ("size_bytes".to_owned(), "must be 1 to 10 bytes".to_owned()),
+            ]
+        );
+    }
+
+    #[test]
+    fn metadata_should_be_at_most_max_bytes() {
+        // `{"k":""}` is 8 bytes around the value.
+        let metadata = |len: usize| json!({ "k": "x".repeat(len - 8) });
+        let question = |metadata| Question {
+            title: "title".to_owned(),
+            description: None,
+            metadata,
+        };
+
+        assert!(super::question(question(metadata(MAX_METADATA_BYTES)), None).is_ok());
+        assert_eq!(
+            field_errors(super::question(
+                question(metadata(MAX_METADATA_BYTES + 1)),
+                None
+            )),
+            vec![(
+                "metadata".to_owned(),
+                "must be at most 4096 bytes".to_owned()
+            )]
+        );
+    }
+
+    #[test]
+    fn length_should_count_characters_not_bytes() {
+        let answer = answer(Answer {
+            question_uuid: QuestionUuid::new_v4(),
+            content: "é".repeat(MAX_TEXT_CHARS),
+        });
+
+        assert!(answer.is_ok());
+    }
+
+    #[test]
+    fn import_should_name_fields_by_their_path() {
+        let errors = field_errors(import(Import {
+            questions: vec![
+                ImportedQuestion {
+                    title: "title".to_owned(),
+                    description: Some("description".to_owned()),
+                    metadata: empty_metadata(),
+                    answers: vec!["answer".to_owned()],
+                },
+                ImportedQuestion {
+                    title: "".to_owned(),
+                    description: Some("d".repeat(MAX_TEXT_CHARS + 1)),
+                    metadata: empty_metadata(),
+                    answers: vec!["answer".to_owned(), " ".to_owned()],
+                },
+            ],
+        }));
+
+        assert_eq!(
+            errors,
+            vec![
+                (
+                    "questions[1].title".to_owned(),
+                    "must not be empty".to_owned()
+                ),
+                (
+                    "questions[1].description".to_owned(),
+                    "must be at most 255 characters".to_owned()
+                ),
+                (
+                    "questions[1].answers[1]".to_owned(),
+                    "must not be empty".to_owned()
+                ),
+            ]
+        );
+    }
+}
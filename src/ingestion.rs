@@ -0,0 +1,320 @@
+use std::sync::Arc;
+
+#[cfg(any(feature = "nats", feature = "rabbitmq"))]
+use log::{info, warn};
+use serde::Deserialize;
+use serde_json::Value;
+use thiserror::Error;
+use tokio::task::JoinHandle;
+
+use crate::{
+    actor::Actor,
+    config::AppConfig,
+    handlers::{validation, AppError},
+    models::{empty_metadata, DBError, Question},
+    persistence::ingestion::{Ingested, IngestionDao},
+};
+
+// Longer keys are rejected rather than truncated, which could merge distinct ones.
+const MAX_IDEMPOTENCY_KEY_BYTES: usize = 200;
+// How long the consumer waits before reconnecting, and before a message that failed on
+// the database is redelivered.
+#[cfg(any(feature = "nats", feature = "rabbitmq"))]
+const RETRY_DELAY: std::time::Duration = std::time::Duration::from_secs(5);
+// Names the durable NATS consumer and the RabbitMQ consumer tag.
+#[cfg(any(feature = "nats", feature = "rabbitmq"))]
+const CONSUMER_NAME: &str = "question-answer-api";
+
+// A question sent by another system. `idempotency_key` identifies it across redeliveries
+// and resends; without one the transport's message id is used.
+#[derive(Deserialize, Debug)]
+pub struct IngestMessage {
+    #[serde(default)]
+    pub idempotency_key: Option<String>,
+    pub title: String,
+    #[serde(default)]
+    pub description: Option<String>,
+    #[serde(default)]
+    pub metadata: Option<Value>,
+}
+
+#[derive(Error, Debug)]
+pub enum IngestError {
+    // The message can never be ingested, so it is dropped or dead-lettered.
+    #[error("Invalid message: {0}")]
+    Invalid(String),
+    // Redelivered later.
+    #[error(transparent)]
+    Database(#[from] DBError),
+}
+
+// Creates the question in `payload` unless its key was ingested before.
+pub async fn ingest(
+    payload: &[u8],
+    message_id: Option<&str>,
+    ingestion_dao: &(dyn IngestionDao + Send + Sync),
+) -> Result<Ingested, IngestError> {
+    let message: IngestMessage =
+        serde_json::from_slice(payload).map_err(|err| IngestError::Invalid(err.to_string()))?;
+    let key = message
+        .idempotency_key
+        .as_deref()
+        .or(message_id)
+        .map(str::trim)
+        .filter(|key| !key.is_empty())
+        .ok_or_else(|| IngestError::Invalid("idempotency_key is missing".to_owned()))?;
+    if key.len() > MAX_IDEMPOTENCY_KEY_BYTES {
+        return Err(IngestError::Invalid(format!(
+            "idempotency_key is longer than {} bytes",
+            MAX_IDEMPOTENCY_KEY_BYTES
+        )));
+    }
+
+    let question = Question {
+        title: message.title,
+        description: message.description,
+        metadata: message.metadata.unwrap_or_else(empty_metadata),
+    };
+    let (question, _) = validation::question(question, None).map_err(|err| match err {
+        AppError::Invalid(fields) => IngestError::Invalid(
+            fields
+                .iter()
+                .map(|field| format!("{}: {}", field.field, field.message))
+                .collect::<Vec<_>>()
+                .join(", "),
+        ),
+        err => IngestError::Invalid(err.to_string()),
+    })?;
+
+    let actor = Actor {
+        name: "ingestion".to_owned(),
+        request_id: Some(key.to_owned()),
+    };
+    Ok(actor
+        .scope(ingestion_dao.create_question_once(key, question))
+        .await?)
+}
+
+#[cfg(any(feature = "nats", feature = "rabbitmq"))]
+fn log_outcome(result: &Result<Ingested, IngestError>) {
+    match result {
+        Ok(Ingested::Created(question)) => {
+            info!("Ingested question {}", question.question_uuid)
+        }
+        Ok(Ingested::Duplicate(question_uuid)) => {
+            info!("Skipped a duplicate of ingested question {}", question_uuid)
+        }
+        Err(err) => warn!("Error on ingesting a question: {}", err),
+    }
+}
+
+// Consumes INGESTION_URL, reconnecting after failures, while it is set.
+#[cfg_attr(
+    not(any(feature = "nats", feature = "rabbitmq")),
+    allow(unused_variables)
+)]
+pub fn spawn_consumer(
+    config: &AppConfig,
+    ingestion_dao: Arc<dyn IngestionDao + Send + Sync>,
+) -> Option<JoinHandle<()>> {
+    let url = config.ingestion_url.clone()?;
+    let queue = config.ingestion_queue.clone();
+
+    match url.split(':').next() {
+        #[cfg(feature = "nats")]
+        Some("nats" | "tls") => Some(tokio::spawn(async move {
+            loop {
+                if let Err(err) = consume_nats(&url, &queue, ingestion_dao.as_ref()).await {
+                    warn!("NATS ingestion stopped: {}", err);
+                }
+                tokio::time::sleep(RETRY_DELAY).await;
+            }
+        })),
+        #[cfg(feature = "rabbitmq")]
+        Some("amqp" | "amqps") => Some(tokio::spawn(async move {
+            loop {
+                if let Err(err) = consume_rabbitmq(&url, &queue, ingestion_dao.as_ref()).await {
+                    warn!("RabbitMQ ingestion stopped: {}", err);
+                }
+                tokio::time::sleep(RETRY_DELAY).await;
+            }
+        })),
+        _ => panic!(
+            "INGESTION_URL needs a nats: URL and the nats cargo feature, or an amqp: URL and the rabbitmq cargo feature."
+        ),
+    }
+}
+
+// Reads the subject `queue` through a JetStream stream and durable consumer, made on
+// first use, so messages are kept while the API is down and acknowledged once stored.
+#[cfg(feature = "nats")]
+async fn consume_nats(
+    url: &str,
+    queue: &str,
+    ingestion_dao: &(dyn IngestionDao + Send + Sync),
+) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    use async_nats::jetstream::{self, consumer::pull, AckKind};
+    use futures::StreamExt;
+
+    let client = async_nats::connect(url).await?;
+    let stream = jetstream::new(client)
+        .get_or_create_stream(jetstream::stream::Config {
+            // Stream names cannot contain dots.
+            name: queue.replace(['.', '*', '>'], "_").to_uppercase(),
+            subjects: vec![queue.to_owned()],
+            ..Default::default()
+        })
+        .await?;
+    let consumer = stream
+        .get_or_create_consumer(
+            CONSUMER_NAME,
+            pull::Config {
+                durable_name: Some(CONSUMER_NAME.to_owned()),
+                ..Default::default()
+            },
+        )
+        .await?;
+    let mut messages = consumer.messages().await?;
+    info!("Consuming questions from NATS subject {}", queue);
+
+    while let Some(message) = messages.next().await {
+        let message = message?;
+        let message_id = message
+            .headers
+            .as_ref()
+            .and_then(|headers| headers.get("Nats-Msg-Id"))
+            .map(|id| id.as_str().to_owned());
+        let result = ingest(&message.payload, message_id.as_deref(), ingestion_dao).await;
+        log_outcome(&result);
+
+        let ack = match result {
+            Ok(_) => AckKind::Ack,
+            Err(IngestError::Invalid(_)) => AckKind::Term,
+            Err(IngestError::Database(_)) => AckKind::Nak(Some(RETRY_DELAY)),
+        };
+        message.ack_with(ack).await?;
+    }
+    Ok(())
+}
+
+// Reads the durable queue `queue`, declared on first use. Invalid messages are rejected
+// without requeueing, so a dead letter exchange set by policy receives them.
+#[cfg(feature = "rabbitmq")]
+async fn consume_rabbitmq(
+    url: &str,
+    queue: &str,
+    ingestion_dao: &(dyn IngestionDao + Send + Sync),
+) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    use futures::StreamExt;
+    use lapin::{
+        options::{
+            BasicAckOptions, BasicConsumeOptions, BasicNackOptions, BasicQosOptions,
+            QueueDeclareOptions,
+        },
+        types::FieldTable,
+        Connection, ConnectionProperties,
+    };
+
+    let connection = Connection::connect(url, ConnectionProperties::default()).await?;
+    let channel = connection.create_channel().await?;
+    channel
+        .queue_declare(
+            queue,
+            QueueDeclareOptions {
+                durable: true,
+                ..Default::default()
+            },
+            FieldTable::default(),
+        )
+        .await?;
+    channel.basic_qos(10, BasicQosOptions::default()).await?;
+    let mut consumer = channel
+        .basic_consume(
+            queue,
+            CONSUMER_NAME,
+            BasicConsumeOptions::default(),
+            FieldTable::default(),
+        )
+        .await?;
+    info!("Consuming questions from RabbitMQ queue {}", queue);
+
+    while let Some(delivery) = consumer.next().await {
+        let delivery = delivery?;
+        let message_id = delivery
+            .properties
+            .message_id()
+            .as_ref()
+            .map(|id| id.as_str().to_owned());
+        let result = ingest(&delivery.data, message_id.as_deref(), ingestion_dao).await;
+        log_outcome(&result);
+
+        match result {
+            Ok(_) => delivery.ack(BasicAckOptions::default()).await?,
+            Err(IngestError::Invalid(_)) => {
+                delivery
+                    .nack(BasicNackOptions {
+                        requeue: false,
+                        ..Default::default()
+                    })
+                    .await?
+            }
+            Err(IngestError::Database(_)) => {
+                tokio::time::sleep(RETRY_DELAY).await;
+                delivery
+                    .nack(BasicNackOptions {
+                        requeue: true,
+                        ..Default::default()
+                    })
+                    .await?
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::persistence::memory::{InMemoryIngestionDao, MemoryStore};
+    use serde_json::json;
+
+    #[tokio::test]
+    async fn ingest_should_create_each_key_once() {
+        let dao = InMemoryIngestionDao::new(MemoryStore::new());
+        let payload = json!({ "idempotency_key": "crm-42", "title": " title " }).to_string();
+
+        let Ok(Ingested::Created(created)) = ingest(payload.as_bytes(), None, &dao).await else {
+            panic!("The first delivery should create the question");
+        };
+        assert_eq!(created.title, "title");
+        assert!(matches!(
+            ingest(payload.as_bytes(), None, &dao).await,
+            Ok(Ingested::Duplicate(question_uuid)) if question_uuid == created.question_uuid
+        ));
+
+        let payload = json!({ "title": "title" }).to_string();
+        assert!(matches!(
+            ingest(payload.as_bytes(), Some("msg-1"), &dao).await,
+            Ok(Ingested::Created(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn ingest_should_reject_invalid_messages() {
+        let dao = InMemoryIngestionDao::new(MemoryStore::new());
+
+        for (payload, message_id) in [
+            (json!({ "title": "title" }), None),
+            (json!({ "idempotency_key": "crm-42", "title": " " }), None),
+            (json!({ "idempotency_key": "crm-42" }), None),
+            (json!({ "title": "title" }), Some("x".repeat(201))),
+        ] {
+            let result = ingest(payload.to_string().as_bytes(), message_id.as_deref(), &dao).await;
+            assert!(
+                matches!(result, Err(IngestError::Invalid(_))),
+                "{} should be invalid",
+                payload
+            );
+        }
+    }
+}
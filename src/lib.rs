@@ -0,0 +1,243 @@
+#[macro_use]
+extern crate rocket;
+
+pub mod actor;
+pub mod analytics;
+pub mod answerer;
+pub mod attachments;
+pub mod avatars;
+mod backpressure;
+pub mod captcha;
+pub mod chat;
+pub mod cli;
+pub mod config;
+pub mod confirmations;
+mod cors;
+pub mod dto;
+pub mod duplicates;
+pub mod embedding;
+mod events;
+mod fnv;
+mod handlers;
+pub mod ingestion;
+pub mod link_previews;
+mod metrics;
+pub mod models;
+pub mod moderation;
+pub mod notifications;
+pub mod persistence;
+pub mod push;
+pub mod rate_limit;
+pub mod replies;
+mod request_id;
+pub mod scanning;
+pub mod search;
+pub mod spam;
+pub mod startup;
+pub mod tagging;
+#[cfg(any(test, feature = "test-support"))]
+pub mod testing;
+pub mod thumbnails;
+pub mod translation;
+pub mod word_filter;
+
+use std::{path::PathBuf, sync::Arc};
+
+use analytics::{Analytics, AnalyticsFairing};
+use answerer::AnswerSuggestions;
+use attachments::Attachments;
+use captcha::Captcha;
+use chat::ChatNotifiers;
+use config::{AppConfig, LiveConfig};
+use cors::*;
+use duplicates::DuplicateAnswers;
+use embedding::SemanticSearch;
+use handlers::*;
+use link_previews::LinkPreviews;
+use moderation::Moderation;
+use notifications::Notifications;
+use persistence::{
+    activity::ActivityDao, archive::ArchiveDao, audit::AuditDao, import::ImportDao,
+    merge::MergeDao, replica::ReadReplica, rest_hook::RestHookDao, stats::StatsDao,
+    translation::TranslationDao, unit_of_work::UnitOfWorkFactory, user::UserDao,
+};
+use rate_limit::RateLimitHeaders;
+use replies::ReplyAddresses;
+use request_id::RequestIdFairing;
+use rocket::{data::Limits, Build, Rocket};
+use search::Search;
+use tagging::TagSuggestions;
+use translation::Translations;
+use word_filter::WordFilter;
+
+// The persistence layer, for services that embed it instead of calling the API. The
+// traits document what every implementation guarantees; `QuestionDaoImpl` and
+// `AnswerDaoImpl` are the Postgres ones, and `persistence::memory` has stand-ins.
+pub use models::{
+    empty_metadata, Answer, AnswerDetail, AnswerUuid, DBError, DateRange, Entity, MetadataFilter,
+    Question, QuestionDetail, QuestionFilter, QuestionUuid,
+};
+pub use persistence::{
+    answer_dao::{AnswerDao, AnswerDaoImpl},
+    question_dao::{QuestionDao, QuestionDaoImpl, QuestionDeletion},
+    DatabasePool, RowStream,
+};
+
+// Everything the routes and guards use, managed once as the only state. `startup`
+// builds it from the environment; tests and embedders can fill it with their own DAOs.
+// A new service is a new field here. The DAOs are `Arc`s so a background task can be
+// handed a clone of the same decorated DAO the routes use, cache and breaker included.
+pub struct AppState {
+    pub live_config: LiveConfig,
+    pub pool: Option<DatabasePool>,
+    pub question_dao: Arc<dyn QuestionDao + Send + Sync>,
+    pub answer_dao: Arc<dyn AnswerDao + Send + Sync>,
+    pub unit_of_work: Arc<dyn UnitOfWorkFactory + Send + Sync>,
+    pub import_dao: Arc<dyn ImportDao + Send + Sync>,
+    pub archive_dao: Arc<dyn ArchiveDao + Send + Sync>,
+    pub stats_dao: Arc<dyn StatsDao + Send + Sync>,
+    pub read_replica: Option<ReadReplica>,
+    pub semantic_search: Option<SemanticSearch>,
+    // None when the storage has no full text search.
+    pub search: Option<Search>,
+    // None while ANALYTICS_SINK is unset; tracked events are then discarded.
+    pub analytics: Option<Analytics>,
+    pub suggestions: Option<AnswerSuggestions>,
+    // None while CAPTCHA_PROVIDER is unset; anonymous creates then need no token.
+    pub captcha: Option<Captcha>,
+    // None while no moderation provider is configured; creates are then published as is.
+    pub moderation: Option<Moderation>,
+    // None while WORD_FILTER is off.
+    pub word_filter: Option<WordFilter>,
+    // None while DUPLICATE_ANSWERS is off.
+    pub duplicate_answers: Option<DuplicateAnswers>,
+    // None when REST hooks cannot be called, e.g. without the webhooks feature.
+    pub rest_hook_dao: Option<Arc<dyn RestHookDao + Send + Sync>>,
+    // None when the storage cannot keep provisioned users; SCIM then answers 501.
+    pub user_dao: Option<Arc<dyn UserDao + Send + Sync>>,
+    // None when the storage keeps no history of writes; `/activity` then answers 501.
+    pub activity_dao: Option<Arc<dyn ActivityDao + Send + Sync>>,
+    // None when the storage cannot merge questions; merging then answers 501.
+    pub merge_dao: Option<Arc<dyn MergeDao + Send + Sync>>,
+    pub tag_suggestions: Option<TagSuggestions>,
+    // None while no translator is configured; `?lang=` then only finds the translations
+    // people submitted.
+    pub translations: Option<Translations>,
+    // None when the storage cannot keep translations; submitting one then answers 501.
+    pub translation_dao: Option<Arc<dyn TranslationDao + Send + Sync>>,
+    // None while LINK_PREVIEWS is off.
+    pub link_previews: Option<LinkPreviews>,
+    pub audit_dao: Option<Arc<dyn AuditDao + Send + Sync>>,
+    pub notifications: Option<Notifications>,
+    // None while REPLY_EMAIL_DOMAIN is unset; replying to notifications then gets a 501.
+    pub replies: Option<ReplyAddresses>,
+    // None when no chat integration is configured.
+    pub chat: Option<ChatNotifiers>,
+    // None when the storage cannot keep attachment rows.
+    pub attachments: Option<Attachments>,
+}
+
+// Serves a state built up front, e.g. `startup::in_memory` in tests.
+pub fn build_rocket(state: AppState) -> Rocket<Build> {
+    let limits = state.live_config.load().body_limits.to_limits();
+    routes(limits).manage(state)
+}
+
+// Serves the storage configured in the environment, connected to while igniting.
+pub fn rocket_from_env(env_file: Option<PathBuf>) -> Rocket<Build> {
+    let limits = AppConfig::from_env().body_limits.to_limits();
+    routes(limits).attach(startup::database(env_file))
+}
+
+fn routes(limits: Limits) -> Rocket<Build> {
+    let figment = rocket::Config::figment().merge(("limits", limits));
+
+    rocket::custom(figment)
+        .mount(
+            "/",
+            routes![
+                question::create_question,
+                question::create_question_with_answer,
+                question::update_question,
+                question::get_questions,
+                question::search_questions,
+                question::autocomplete,
+                question::semantic_search,
+                question::suggest_tags,
+                question::get_question,
+                question::delete_question,
+                answer::create_answer,
+                answer::answer_by_email,
+                answer::update_answer,
+                answer::get_answers,
+                answer::delete_answer,
+                answer::suggest_answer,
+                handlers::metrics::get_metrics,
+                admin::reload_config,
+                features::get_features,
+                import::import,
+                handlers::archive::archive,
+                handlers::merge::merge_question,
+                handlers::translation::get_translations,
+                handlers::translation::submit_translation,
+                handlers::translation::delete_translation,
+                handlers::stats::get_daily_stats,
+                handlers::stats::get_admin_stats,
+                handlers::stats::refresh_stats,
+                handlers::audit::get_audit_entries,
+                handlers::activity::get_activity,
+                handlers::notifications::subscribe,
+                handlers::notifications::unsubscribe,
+                handlers::notifications::opt_out,
+                handlers::notifications::opt_in,
+                handlers::notifications::set_digest,
+                handlers::notifications::stop_digest,
+                handlers::notifications::confirm_page,
+                handlers::notifications::confirm,
+                handlers::notifications::save_push_subscription,
+                handlers::notifications::delete_push_subscription,
+                handlers::notifications::get_vapid_public_key,
+                handlers::telegram::receive_update,
+                handlers::attachments::upload_attachment,
+                handlers::attachments::create_attachment_upload,
+                handlers::attachments::complete_attachment,
+                handlers::attachments::download_attachment,
+                handlers::attachments::download_variant,
+                handlers::avatars::get_avatar,
+                handlers::avatars::set_avatar,
+                handlers::avatars::delete_avatar,
+                handlers::moderation::get_queue,
+                handlers::moderation::approve,
+                handlers::moderation::moderate_bulk,
+                handlers::moderation::reject,
+                handlers::moderation::get_filtered_words,
+                handlers::moderation::add_filtered_word,
+                handlers::moderation::remove_filtered_word,
+                handlers::hooks::subscribe,
+                handlers::hooks::unsubscribe,
+                handlers::hooks::samples,
+                handlers::scim::list_users,
+                handlers::scim::get_user,
+                handlers::scim::create_user,
+                handlers::scim::replace_user,
+                handlers::scim::patch_user,
+            ],
+        )
+        .register(
+            "/",
+            catchers![
+                catchers::bad_request,
+                catchers::not_found,
+                catchers::payload_too_large,
+                catchers::unsupported_media_type,
+                catchers::unprocessable_entity,
+                catchers::internal_error,
+                catchers::service_unavailable,
+                catchers::default,
+            ],
+        )
+        .attach(CORS)
+        .attach(RequestIdFairing)
+        .attach(RateLimitHeaders)
+        .attach(AnalyticsFairing)
+}
@@ -0,0 +1,599 @@
+use std::{net::IpAddr, sync::Arc};
+
+use async_trait::async_trait;
+use log::warn;
+use thiserror::Error;
+use tokio::sync::mpsc;
+
+use crate::{
+    backpressure,
+    config::AppConfig,
+    models::DBError,
+    persistence::link_preview::{LinkPreview, LinkPreviewDao},
+    startup::StartupError,
+};
+
+// Texts waiting for their links to be fetched. Past this, new ones are dropped with a
+// warning and their links get no preview.
+const QUEUE_CAPACITY: usize = 100;
+
+// Links previewed per text, so a list of links cannot make the worker crawl.
+const MAX_URLS: usize = 5;
+const MAX_URL_LENGTH: usize = 2048;
+
+const MAX_TITLE_CHARS: usize = 300;
+const MAX_DESCRIPTION_CHARS: usize = 1000;
+
+#[derive(Error, Debug)]
+pub enum PreviewError {
+    // The URL or where it resolves or redirects to may not be fetched.
+    #[error("Link may not be fetched: {0}")]
+    Blocked(String),
+    #[error(transparent)]
+    Database(#[from] DBError),
+    #[cfg(feature = "link-previews")]
+    #[error("Link request failed: {0}")]
+    Request(String),
+}
+
+// Fetches what a page says about itself.
+#[async_trait]
+pub trait PreviewFetcher {
+    // None for pages that are not HTML.
+    async fn fetch(&self, url: &str) -> Result<Option<LinkPreview>, PreviewError>;
+}
+
+// Fetches the previews of links in new and edited questions from a queue drained in the
+// background. Best effort like thumbnails: failures are logged and nothing is retried.
+#[derive(Clone)]
+pub struct LinkPreviews {
+    queue: mpsc::Sender<Vec<String>>,
+    pub link_preview_dao: Arc<dyn LinkPreviewDao + Send + Sync>,
+}
+
+impl LinkPreviews {
+    // Starts the worker, which fetches one link at a time.
+    pub fn spawn(
+        fetcher: Box<dyn PreviewFetcher + Send + Sync>,
+        link_preview_dao: Arc<dyn LinkPreviewDao + Send + Sync>,
+    ) -> Self {
+        let (queue, mut jobs) = mpsc::channel::<Vec<String>>(QUEUE_CAPACITY);
+
+        let dao = link_preview_dao.clone();
+        tokio::spawn(async move {
+            while let Some(urls) = jobs.recv().await {
+                backpressure::record_queue_depth("link_previews", jobs.len(), QUEUE_CAPACITY);
+                fetch_previews(&urls, fetcher.as_ref(), dao.as_ref()).await;
+            }
+        });
+
+        Self {
+            queue,
+            link_preview_dao,
+        }
+    }
+
+    // Never waits for the queue; texts without links are skipped.
+    pub fn enqueue(&self, text: &str) {
+        let urls = extract_urls(text);
+        if urls.is_empty() {
+            return;
+        }
+        if let Err(err) = self.queue.try_send(urls) {
+            warn!("Dropped a link preview job: {}", err);
+        }
+        backpressure::record_queue("link_previews", &self.queue);
+    }
+
+    // The previews fetched so far of the links in `text`, in the order they appear.
+    pub async fn previews(&self, text: &str) -> Result<Vec<LinkPreview>, DBError> {
+        let urls = extract_urls(text);
+        if urls.is_empty() {
+            return Ok(vec![]);
+        }
+        self.link_preview_dao.get_link_previews(&urls).await
+    }
+}
+
+pub fn fetcher_from_config(
+    config: &AppConfig,
+) -> Result<Option<Box<dyn PreviewFetcher + Send + Sync>>, StartupError> {
+    config
+        .link_previews
+        .then(|| http_fetcher(config))
+        .transpose()
+}
+
+#[cfg(feature = "link-previews")]
+fn http_fetcher(config: &AppConfig) -> Result<Box<dyn PreviewFetcher + Send + Sync>, StartupError> {
+    Ok(Box::new(HttpFetcher::new(
+        config.link_preview_allowed_hosts.clone(),
+    )))
+}
+
+#[cfg(not(feature = "link-previews"))]
+fn http_fetcher(
+    _config: &AppConfig,
+) -> Result<Box<dyn PreviewFetcher + Send + Sync>, StartupError> {
+    Err(StartupError::MissingFeature {
+        setting: "LINK_PREVIEWS",
+        feature: "link-previews",
+    })
+}
+
+// Links that already have a preview are not fetched again, so popular links are only
+// fetched once.
+pub async fn fetch_previews(
+    urls: &[String],
+    fetcher: &(dyn PreviewFetcher + Send + Sync),
+    link_preview_dao: &(dyn LinkPreviewDao + Send + Sync),
+) {
+    let known = match link_preview_dao.get_link_previews(urls).await {
+        Ok(known) => known,
+        Err(err) => {
+            warn!("Error on reading link previews: {:?}", err);
+            return;
+        }
+    };
+
+    for url in urls {
+        if known.iter().any(|preview| preview.url == *url) {
+            continue;
+        }
+        let stored = match fetcher.fetch(url).await {
+            Ok(Some(preview)) if preview.title.is_some() || preview.description.is_some() => {
+                link_preview_dao
+                    .store_link_preview(preview)
+                    .await
+                    .map_err(PreviewError::from)
+            }
+            Ok(_) => Ok(()),
+            Err(err) => Err(err),
+        };
+        if let Err(err) = stored {
+            warn!("Preview of {} failed: {}", url, err);
+        }
+    }
+}
+
+// The http and https links in `text`, first seen first and without the punctuation that
+// usually follows a link in prose.
+pub fn extract_urls(text: &str) -> Vec<String> {
+    let mut urls: Vec<String> = vec![];
+    let mut rest = text;
+    while let Some(start) = ["http://", "https://"]
+        .iter()
+        .filter_map(|scheme| rest.find(scheme))
+        .min()
+    {
+        let candidate = &rest[start..];
+        let end = candidate
+            .find(|c: char| c.is_whitespace() || "<>\"'`".contains(c))
+            .unwrap_or(candidate.len());
+        let mut url = candidate[..end].trim_end_matches(['.', ',', ';', ':', '!', '?']);
+        if url.ends_with(')') && !url.contains('(') {
+            url = &url[..url.len() - 1];
+        }
+        rest = &candidate[end..];
+
+        let has_host = url
+            .split_once("://")
+            .is_some_and(|(_, host)| !host.is_empty());
+        if has_host && url.len() <= MAX_URL_LENGTH && !urls.iter().any(|known| known == url) {
+            urls.push(url.to_owned());
+            if urls.len() == MAX_URLS {
+                break;
+            }
+        }
+    }
+    urls
+}
+
+// Whether `host` is one of `allowed_hosts` or a subdomain of one; any host is while the
+// list is empty.
+pub fn host_allowed(host: &str, allowed_hosts: &[String]) -> bool {
+    let host = host.to_ascii_lowercase();
+    allowed_hosts.is_empty()
+        || allowed_hosts.iter().any(|allowed| {
+            let allowed = allowed.to_ascii_lowercase();
+            host == allowed || host.ends_with(&format!(".{}", allowed))
+        })
+}
+
+// Whether an address is on the public internet, so links cannot reach the server's own
+// network, e.g. a cloud metadata endpoint.
+pub fn is_public(address: IpAddr) -> bool {
+    match address {
+        IpAddr::V4(address) => {
+            let [first, second, ..] = address.octets();
+            !(address.is_private()
+                || address.is_loopback()
+                || address.is_link_local()
+                || address.is_unspecified()
+                || address.is_broadcast()
+                || address.is_documentation()
+                || address.is_multicast()
+                // Shared address space used by carrier-grade NAT.
+                || (first == 100 && (64..128).contains(&second))
+                // Reserved, and "this network".
+                || first >= 240
+                || first == 0)
+        }
+        IpAddr::V6(address) => match address.to_ipv4_mapped() {
+            Some(mapped) => is_public(IpAddr::V4(mapped)),
+            None => {
+                let first = address.segments()[0];
+                !(address.is_loopback()
+                    || address.is_unspecified()
+                    || address.is_multicast()
+                    // Unique local and link-local addresses.
+                    || (first & 0xfe00) == 0xfc00
+                    || (first & 0xffc0) == 0xfe80)
+            }
+        },
+    }
+}
+
+// Reads the OpenGraph `og:` tags of a page, falling back to its `<title>` and description.
+// Relative image URLs are resolved against `url`.
+pub fn parse_page(url: &str, html: &str) -> LinkPreview {
+    let lower = html.to_ascii_lowercase();
+    let mut og = std::collections::HashMap::new();
+    let mut title = None;
+    let mut description = None;
+
+    let mut position = 0;
+    while let Some(offset) = lower[position..].find('<') {
+        let start = position + offset + 1;
+        let name_end = lower[start..]
+            .find(|c: char| c.is_whitespace() || c == '>' || c == '/')
+            .map_or(lower.len(), |end| start + end);
+        let tag_end = lower[start..]
+            .find('>')
+            .map_or(lower.len(), |end| start + end);
+        match &lower[start..name_end] {
+            "meta" => {
+                let attributes = attributes(&html[name_end..tag_end]);
+                let key = attributes
+                    .iter()
+                    .find(|(name, _)| name == "property" || name == "name")
+                    .map(|(_, value)| value.to_ascii_lowercase());
+                let content = attributes
+                    .iter()
+                    .find(|(name, _)| name == "content")
+                    .map(|(_, value)| value.clone());
+                if let (Some(key), Some(content)) = (key, content) {
+                    if key == "description" {
+                        description.get_or_insert(content);
+                    } else if let Some(property) = key.strip_prefix("og:") {
+                        og.entry(property.to_owned()).or_insert(content);
+                    }
+                }
+            }
+            "title" if title.is_none() => {
+                let text_start = (tag_end + 1).min(lower.len());
+                let text_end = lower[text_start..]
+                    .find("</title")
+                    .map_or(lower.len(), |end| text_start + end);
+                title = Some(decode_entities(&html[text_start..text_end]));
+            }
+            "/head" | "body" => break,
+            _ => {}
+        }
+        position = tag_end.max(start);
+    }
+
+    let clean = |text: Option<String>, max_chars: usize| {
+        text.map(|text| {
+            let text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+            text.chars().take(max_chars).collect::<String>()
+        })
+        .filter(|text| !text.is_empty())
+    };
+    LinkPreview {
+        url: url.to_owned(),
+        title: clean(og.remove("title").or(title), MAX_TITLE_CHARS),
+        description: clean(
+            og.remove("description").or(description),
+            MAX_DESCRIPTION_CHARS,
+        ),
+        image_url: og
+            .remove("image")
+            .and_then(|image| resolve(url, image.trim())),
+        site_name: clean(og.remove("site_name"), MAX_TITLE_CHARS),
+    }
+}
+
+// The attributes of a tag, names lowercased and values decoded.
+fn attributes(tag: &str) -> Vec<(String, String)> {
+    let mut attributes = vec![];
+    let mut rest = tag.trim_start();
+    while !rest.is_empty() && !rest.starts_with(['>', '/']) {
+        let name_end = rest
+            .find(|c: char| c.is_whitespace() || c == '=' || c == '>')
+            .unwrap_or(rest.len());
+        let name = rest[..name_end].to_ascii_lowercase();
+        rest = rest[name_end..].trim_start();
+
+        let mut value = String::new();
+        if let Some(after) = rest.strip_prefix('=') {
+            let after = after.trim_start();
+            let (raw, remaining) = match after.chars().next() {
+                Some(quote @ ('"' | '\'')) => {
+                    let end = after[1..].find(quote).map_or(after.len(), |end| end + 1);
+                    (&after[1..end], after.get(end + 1..).unwrap_or(""))
+                }
+                _ => {
+                    let end = after
+                        .find(|c: char| c.is_whitespace() || c == '>')
+                        .unwrap_or(after.len());
+                    (&after[..end], &after[end..])
+                }
+            };
+            value = decode_entities(raw);
+            rest = remaining;
+        }
+        if name.is_empty() {
+            // Stray characters, e.g. a lone `/`; skip one so parsing moves on.
+            rest = rest.get(1..).unwrap_or("");
+        } else {
+            attributes.push((name, value));
+        }
+        rest = rest.trim_start();
+    }
+    attributes
+}
+
+// The entities pages commonly use in titles and descriptions.
+fn decode_entities(text: &str) -> String {
+    let mut decoded = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find('&') {
+        decoded.push_str(&rest[..start]);
+        rest = &rest[start..];
+        let entity = rest
+            .find(';')
+            .filter(|end| *end <= 10)
+            .map(|end| &rest[1..end]);
+        let character = entity.and_then(|entity| match entity {
+            "amp" => Some('&'),
+            "lt" => Some('<'),
+            "gt" => Some('>'),
+            "quot" => Some('"'),
+            "apos" => Some('\''),
+            "nbsp" => Some(' '),
+            _ => {
+                let number = entity.strip_prefix('#')?;
+                let code = match number.strip_prefix(['x', 'X']) {
+                    Some(hex) => u32::from_str_radix(hex, 16).ok()?,
+                    None => number.parse().ok()?,
+                };
+                char::from_u32(code)
+            }
+        });
+        match (character, entity) {
+            (Some(character), Some(entity)) => {
+                decoded.push(character);
+                rest = &rest[entity.len() + 2..];
+            }
+            _ => {
+                decoded.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    decoded.push_str(rest);
+    decoded
+}
+
+// `link` as an absolute http or https URL, relative to the page at `base`.
+fn resolve(base: &str, link: &str) -> Option<String> {
+    let (scheme, after_scheme) = base.split_once("://")?;
+    let origin_end = after_scheme.find('/').unwrap_or(after_scheme.len());
+    let resolved = if link.starts_with("http://") || link.starts_with("https://") {
+        link.to_owned()
+    } else if let Some(rest) = link.strip_prefix("//") {
+        format!("{}://{}", scheme, rest)
+    } else if link.starts_with('/') {
+        format!("{}://{}{}", scheme, &after_scheme[..origin_end], link)
+    } else {
+        return None;
+    };
+    (resolved.len() <= MAX_URL_LENGTH).then_some(resolved)
+}
+
+// Fetches pages with the SSRF protections links from users need: only http and https on
+// their default ports, hosts in LINK_PREVIEW_ALLOWED_HOSTS when set, and only public
+// addresses. The checked address is the one connected to, so a second DNS answer cannot
+// point elsewhere, and every redirect is checked again.
+#[cfg(feature = "link-previews")]
+pub struct HttpFetcher {
+    allowed_hosts: Vec<String>,
+}
+
+#[cfg(feature = "link-previews")]
+impl HttpFetcher {
+    const MAX_REDIRECTS: usize = 3;
+    // Only the head is read, which is where the tags are.
+    const MAX_BODY_BYTES: usize = 512 * 1024;
+    const TIMEOUT: std::time::Duration = std::time::Duration::from_secs(5);
+
+    pub fn new(allowed_hosts: Vec<String>) -> Self {
+        Self { allowed_hosts }
+    }
+
+    async fn check(&self, url: &reqwest::Url) -> Result<std::net::SocketAddr, PreviewError> {
+        let blocked = |reason: &str| PreviewError::Blocked(format!("{} ({})", reason, url));
+        if !matches!(url.scheme(), "http" | "https") {
+            return Err(blocked("not http or https"));
+        }
+        if !url.username().is_empty() || url.password().is_some() {
+            return Err(blocked("has credentials"));
+        }
+        if url
+            .port()
+            .is_some_and(|port| Some(port) != url.port_or_known_default())
+        {
+            return Err(blocked("not on the default port"));
+        }
+        let (Some(host), Some(port)) = (url.host_str(), url.port_or_known_default()) else {
+            return Err(blocked("has no host"));
+        };
+        // IPv6 addresses come in brackets.
+        let host = host.trim_start_matches('[').trim_end_matches(']');
+        if !host_allowed(host, &self.allowed_hosts) {
+            return Err(blocked("host is not allowed"));
+        }
+
+        let addresses: Vec<_> = tokio::net::lookup_host((host, port))
+            .await
+            .map_err(|err| PreviewError::Request(err.to_string()))?
+            .collect();
+        match addresses.first() {
+            Some(_) if addresses.iter().any(|address| !is_public(address.ip())) => {
+                Err(blocked("resolves to a private address"))
+            }
+            Some(address) => Ok(*address),
+            None => Err(blocked("does not resolve")),
+        }
+    }
+}
+
+#[cfg(feature = "link-previews")]
+#[async_trait]
+impl PreviewFetcher for HttpFetcher {
+    async fn fetch(&self, url: &str) -> Result<Option<LinkPreview>, PreviewError> {
+        use reqwest::header::{ACCEPT, CONTENT_TYPE, LOCATION};
+
+        let request_error = |err: reqwest::Error| PreviewError::Request(err.to_string());
+        let mut url =
+            reqwest::Url::parse(url).map_err(|err| PreviewError::Blocked(err.to_string()))?;
+        for _ in 0..=Self::MAX_REDIRECTS {
+            let address = self.check(&url).await?;
+            let mut client = reqwest::Client::builder()
+                .redirect(reqwest::redirect::Policy::none())
+                .timeout(Self::TIMEOUT)
+                .user_agent("question-answer-api link preview");
+            if let Some(domain) = url.domain() {
+                client = client.resolve(domain, address);
+            }
+            let mut response = client
+                .build()
+                .map_err(request_error)?
+                .get(url.clone())
+                .header(ACCEPT, "text/html")
+                .send()
+                .await
+                .map_err(request_error)?;
+
+            if response.status().is_redirection() {
+                let location = response
+                    .headers()
+                    .get(LOCATION)
+                    .and_then(|location| location.to_str().ok())
+                    .ok_or_else(|| PreviewError::Blocked(format!("bad redirect ({})", url)))?;
+                url = url
+                    .join(location)
+                    .map_err(|err| PreviewError::Blocked(err.to_string()))?;
+                continue;
+            }
+            response = response.error_for_status().map_err(request_error)?;
+            let is_html = response
+                .headers()
+                .get(CONTENT_TYPE)
+                .and_then(|content_type| content_type.to_str().ok())
+                .is_some_and(|content_type| {
+                    content_type.starts_with("text/html")
+                        || content_type.starts_with("application/xhtml+xml")
+                });
+            if !is_html {
+                return Ok(None);
+            }
+
+            let mut body = vec![];
+            while let Some(chunk) = response.chunk().await.map_err(request_error)? {
+                body.extend_from_slice(&chunk);
+                if body.len() >= Self::MAX_BODY_BYTES {
+                    body.truncate(Self::MAX_BODY_BYTES);
+                    break;
+                }
+            }
+            return Ok(Some(parse_page(
+                url.as_str(),
+                &String::from_utf8_lossy(&body),
+            )));
+        }
+        Err(PreviewError::Blocked(format!(
+            "too many redirects ({})",
+            url
+        )))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn extract_urls_should_find_links_in_prose() {
+        let text = "See https://example.com/a, (or http://example.org/b?x=1). Again: \
+                    https://example.com/a and <https://wiki.example/Rust_(language)> or https://";
+
+        assert_eq!(
+            extract_urls(text),
+            [
+                "https://example.com/a",
+                "http://example.org/b?x=1",
+                "https://wiki.example/Rust_(language)",
+            ]
+        );
+    }
+
+    #[test]
+    fn only_public_addresses_should_be_fetched() {
+        for private in [
+            "127.0.0.1",
+            "10.1.2.3",
+            "172.16.0.1",
+            "192.168.1.1",
+            "169.254.169.254",
+            "100.64.0.1",
+            "0.0.0.0",
+            "::1",
+            "fd00::1",
+            "fe80::1",
+            "::ffff:127.0.0.1",
+        ] {
+            assert!(!is_public(private.parse().unwrap()), "{}", private);
+        }
+        assert!(is_public("93.184.216.34".parse().unwrap()));
+        assert!(is_public("2606:2800:220:1::1".parse().unwrap()));
+
+        let allowed = ["example.com".to_owned()];
+        assert!(host_allowed("docs.Example.com", &allowed));
+        assert!(!host_allowed("notexample.com", &allowed));
+        assert!(host_allowed("anything.test", &[]));
+    }
+
+    #[test]
+    fn parse_page_should_prefer_open_graph_tags() {
+        let html = r#"<!doctype html><html><head>
+            <title>Fallback &amp; title</title>
+            <meta name="description" content="Plain description">
+            <meta property="og:title" content="Rust &quot;Book&quot;" />
+            <meta content='/cover.png' property='og:image'>
+            <meta property=og:site_name content=Docs>
+            </head><body><meta property="og:description" content="ignored"></body></html>"#;
+
+        assert_eq!(
+            parse_page("https://docs.example/book/intro", html),
+            LinkPreview {
+                url: "https://docs.example/book/intro".to_owned(),
+                title: Some("Rust \"Book\"".to_owned()),
+                description: Some("Plain description".to_owned()),
+                image_url: Some("https://docs.example/cover.png".to_owned()),
+                site_name: Some("Docs".to_owned()),
+            }
+        );
+    }
+}
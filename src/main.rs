@@ -1,47 +1,34 @@
-#[macro_use]
-extern crate rocket;
+use std::{env, process};
 
-mod cors;
-mod handlers;
-mod models;
-mod persistence;
-
-use cors::*;
-use handlers::*;
-use persistence::{
-    answer_dao::{AnswerDao, AnswerDaoImpl},
-    question_dao::{QuestionDao, QuestionDaoImpl},
+use question_answer_api_rust::{
+    cli::{self, Command},
+    rocket_from_env,
 };
-use sqlx::postgres::PgPoolOptions;
-use std::env;
 
-#[launch]
-async fn rocket() -> _ {
+#[rocket::main]
+async fn main() {
     pretty_env_logger::init();
-    dotenvy::dotenv().ok();
-
-    let pool = PgPoolOptions::new()
-        .max_connections(5)
-        .connect(&env::var("DATABASE_URL").expect("DATABASE_URL must be set."))
-        .await
-        .unwrap();
+    let env_file = dotenvy::dotenv().ok();
 
-    let question_dao = QuestionDaoImpl::new(pool.clone());
-    let answer_dao = AnswerDaoImpl::new(pool.clone());
+    let command = Command::parse(env::args().skip(1)).unwrap_or_else(|err| {
+        eprintln!("{}\n\n{}", err, cli::USAGE);
+        process::exit(2);
+    });
+    let result = match command {
+        Command::Serve => rocket_from_env(env_file)
+            .launch()
+            .await
+            .map(|_| ())
+            .map_err(|err| err.to_string()),
+        Command::Backup { path } => cli::backup(path).await,
+        Command::Restore { path } => cli::restore(path).await,
+        Command::Export { path } => cli::export(path).await,
+        Command::Import { path } => cli::import(path).await,
+        Command::Reindex => cli::reindex().await,
+    };
 
-    rocket::build()
-        .mount(
-            "/",
-            routes![
-                question::create_question,
-                question::get_questions,
-                question::delete_question,
-                answer::create_answer,
-                answer::get_answers,
-                answer::delete_answer,
-            ],
-        )
-        .attach(CORS)
-        .manage(Box::new(question_dao) as Box<dyn QuestionDao + Send + Sync>)
-        .manage(Box::new(answer_dao) as Box<dyn AnswerDao + Send + Sync>)
+    if let Err(err) = result {
+        eprintln!("{}", err);
+        process::exit(1);
+    }
 }
@@ -0,0 +1,173 @@
+use once_cell::sync::Lazy;
+use prometheus::{
+    histogram_opts, opts, Encoder, Histogram, HistogramVec, IntCounter, IntCounterVec, IntGauge,
+    IntGaugeVec, Registry, TextEncoder,
+};
+use sqlx::{Database, Pool};
+
+pub static REGISTRY: Lazy<Registry> = Lazy::new(Registry::new);
+
+pub static POOL_SIZE: Lazy<IntGaugeVec> = Lazy::new(|| {
+    register(IntGaugeVec::new(
+        opts!("db_pool_size", "Open connections in the pool"),
+        &["pool"],
+    ))
+});
+
+pub static POOL_IDLE: Lazy<IntGaugeVec> = Lazy::new(|| {
+    register(IntGaugeVec::new(
+        opts!("db_pool_idle", "Idle connections in the pool"),
+        &["pool"],
+    ))
+});
+
+pub static POOL_IN_USE: Lazy<IntGaugeVec> = Lazy::new(|| {
+    register(IntGaugeVec::new(
+        opts!("db_pool_in_use", "Connections currently checked out"),
+        &["pool"],
+    ))
+});
+
+pub static POOL_MAX_SIZE: Lazy<IntGaugeVec> = Lazy::new(|| {
+    register(IntGaugeVec::new(
+        opts!("db_pool_max_size", "Configured max_connections of the pool"),
+        &["pool"],
+    ))
+});
+
+pub static POOL_ACQUIRE_WAIT: Lazy<HistogramVec> = Lazy::new(|| {
+    register(HistogramVec::new(
+        histogram_opts!(
+            "db_pool_acquire_wait_seconds",
+            "Time spent waiting for a pool connection",
+            vec![0.001, 0.005, 0.01, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0]
+        ),
+        &["pool"],
+    ))
+});
+
+pub static POOL_WAITING: Lazy<IntGauge> = Lazy::new(|| {
+    register(IntGauge::new(
+        "db_pool_waiting",
+        "Callers waiting for a primary pool connection",
+    ))
+});
+
+pub static REQUESTS_SHED: Lazy<IntCounter> = Lazy::new(|| {
+    register(IntCounter::new(
+        "http_requests_shed_total",
+        "Requests turned away with 503 while the primary pool was saturated",
+    ))
+});
+
+pub static CIRCUIT_BREAKER_OPEN: Lazy<IntGauge> = Lazy::new(|| {
+    register(IntGauge::new(
+        "db_circuit_breaker_open",
+        "1 while the database circuit breaker rejects calls",
+    ))
+});
+
+pub static DB_RETRIES: Lazy<IntCounterVec> = Lazy::new(|| {
+    register(IntCounterVec::new(
+        opts!(
+            "db_retries_total",
+            "DAO calls retried after a transient error"
+        ),
+        &["operation"],
+    ))
+});
+
+pub static REPLICA_FALLBACKS: Lazy<IntCounter> = Lazy::new(|| {
+    register(IntCounter::new(
+        "db_replica_fallbacks_total",
+        "Reads sent to the primary because the read replica was unavailable",
+    ))
+});
+
+pub static DAO_CALL_DURATION: Lazy<HistogramVec> = Lazy::new(|| {
+    register(HistogramVec::new(
+        histogram_opts!(
+            "db_dao_call_duration_seconds",
+            "Duration of DAO calls, including retries",
+            vec![0.001, 0.005, 0.01, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0]
+        ),
+        &["dao", "method", "outcome"],
+    ))
+});
+
+pub static CACHE_LOOKUPS: Lazy<IntCounterVec> = Lazy::new(|| {
+    register(IntCounterVec::new(
+        opts!("cache_lookups_total", "Query cache lookups by outcome"),
+        &["cache", "result"],
+    ))
+});
+
+pub static OUTBOX_EVENTS: Lazy<IntCounterVec> = Lazy::new(|| {
+    register(IntCounterVec::new(
+        opts!(
+            "outbox_events_total",
+            "Outbox events handed to the publisher by outcome"
+        ),
+        &["outcome"],
+    ))
+});
+
+pub static QUEUE_DEPTH: Lazy<IntGaugeVec> = Lazy::new(|| {
+    register(IntGaugeVec::new(
+        opts!(
+            "background_queue_depth",
+            "Jobs waiting in an in-process background queue"
+        ),
+        &["queue"],
+    ))
+});
+
+pub static OUTBOX_DELIVERY_LAG: Lazy<Histogram> = Lazy::new(|| {
+    register(Histogram::with_opts(histogram_opts!(
+        "outbox_delivery_lag_seconds",
+        "Time from an outbox event being written to its delivery to subscribers",
+        vec![0.1, 0.5, 1.0, 2.5, 5.0, 10.0, 30.0, 60.0, 300.0, 900.0]
+    )))
+});
+
+pub static NOTIFICATION_FANOUT: Lazy<Histogram> = Lazy::new(|| {
+    register(Histogram::with_opts(histogram_opts!(
+        "notification_fanout_seconds",
+        "Time from an answer being queued until its emails and pushes were sent",
+        vec![0.01, 0.05, 0.1, 0.5, 1.0, 2.5, 5.0, 10.0, 30.0, 60.0]
+    )))
+});
+
+fn register<T: prometheus::core::Collector + Clone + 'static>(
+    collector: prometheus::Result<T>,
+) -> T {
+    let collector = collector.expect("metric definition should be valid");
+    REGISTRY
+        .register(Box::new(collector.clone()))
+        .expect("metric should only be registered once");
+    collector
+}
+
+pub fn record_pool_state<DB: Database>(name: &str, pool: &Pool<DB>) {
+    let size = pool.size() as i64;
+    let idle = pool.num_idle() as i64;
+
+    POOL_SIZE.with_label_values(&[name]).set(size);
+    POOL_IDLE.with_label_values(&[name]).set(idle);
+    POOL_IN_USE.with_label_values(&[name]).set(size - idle);
+}
+
+pub fn record_pool_max_size(name: &str, max_connections: u32) {
+    POOL_MAX_SIZE
+        .with_label_values(&[name])
+        .set(max_connections as i64);
+}
+
+pub fn render() -> String {
+    let mut buffer = vec![];
+    TextEncoder::new()
+        .encode(&REGISTRY.gather(), &mut buffer)
+        .expect("metrics should encode as text");
+
+    String::from_utf8(buffer).expect("metrics text should be utf-8")
+}
@@ -1,38 +1,877 @@
+use std::{cmp::Reverse, collections::BTreeMap, fmt, str::FromStr};
+
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
 use thiserror::Error;
+use uuid::Uuid;
 
-#[derive(Serialize, Deserialize)]
+// Question and answer ids are parsed where they enter the API, in paths and bodies, so
+// the layers below never see a malformed one. They serialize as plain UUID strings.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[serde(transparent)]
+pub struct QuestionUuid(pub Uuid);
+
+impl QuestionUuid {
+    pub fn new_v4() -> Self {
+        Self(Uuid::new_v4())
+    }
+}
+
+impl fmt::Display for QuestionUuid {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+impl FromStr for QuestionUuid {
+    type Err = uuid::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Uuid::parse_str(s).map(Self)
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[serde(transparent)]
+pub struct AnswerUuid(pub Uuid);
+
+impl AnswerUuid {
+    pub fn new_v4() -> Self {
+        Self(Uuid::new_v4())
+    }
+}
+
+impl fmt::Display for AnswerUuid {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+impl FromStr for AnswerUuid {
+    type Err = uuid::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Uuid::parse_str(s).map(Self)
+    }
+}
+
+// Longest title, description or answer the columns hold.
+pub const MAX_TEXT_CHARS: usize = 255;
+
+// Longest address SMTP allows.
+pub const MAX_EMAIL_BYTES: usize = 254;
+
+// Push endpoints are URLs the browser's push service picks; theirs are far shorter.
+pub const MAX_PUSH_ENDPOINT_BYTES: usize = 2048;
+
+// Zapier and Make hand out hook URLs far shorter than this.
+pub const MAX_HOOK_URL_BYTES: usize = 2048;
+
+// Questions one push subscription may follow.
+pub const MAX_PUSH_QUESTIONS: usize = 100;
+
+// Largest metadata object a question may carry, in bytes of compact JSON.
+pub const MAX_METADATA_BYTES: usize = 4096;
+
+// What a question carries when the client attached no metadata.
+pub fn empty_metadata() -> Value {
+    Value::Object(Map::new())
+}
+
+// The `tags` of a question's metadata, a list of strings or a single one.
+pub fn metadata_tags(metadata: &Value) -> Vec<&str> {
+    match metadata.get("tags") {
+        Some(Value::Array(tags)) => tags.iter().filter_map(Value::as_str).collect(),
+        Some(Value::String(tag)) => vec![tag],
+        _ => vec![],
+    }
+}
+
+// The description is optional; a blank one is stored as missing. `metadata` is a JSON
+// object the client owns, e.g. the system a question came from and its id there.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 pub struct Question {
     pub title: String,
-    pub description: String,
+    pub description: Option<String>,
+    pub metadata: Value,
 }
 
 #[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
 pub struct QuestionDetail {
-    pub question_uuid: String,
+    pub question_uuid: QuestionUuid,
     pub title: String,
-    pub description: String,
-    pub created_at: String,
+    pub description: Option<String>,
+    pub metadata: Value,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+    // Moved by edits and by answers being posted, edited or deleted.
+    pub last_activity_at: DateTime<Utc>,
+    pub answer_count: i64,
+    pub version: i64,
 }
 
-#[derive(Serialize, Deserialize)]
+// An edit with the version it was made against, if the client sent one. The metadata
+// is replaced as a whole, like the other fields.
+pub struct QuestionUpdate {
+    pub title: String,
+    pub description: Option<String>,
+    pub metadata: Value,
+    pub version: Option<i64>,
+}
+
+// Questions whose metadata has every one of these keys, set to that string. Sent as
+// `?metadata.key=value`; Postgres answers it with a JSONB containment query.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct MetadataFilter(pub BTreeMap<String, String>);
+
+impl MetadataFilter {
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    // The object the metadata must contain.
+    pub fn to_json(&self) -> Value {
+        Value::Object(
+            self.0
+                .iter()
+                .map(|(key, value)| (key.clone(), Value::String(value.clone())))
+                .collect(),
+        )
+    }
+
+    // The same test in process, for backends and lists the filter is not pushed into.
+    pub fn matches(&self, metadata: &Value) -> bool {
+        self.0
+            .iter()
+            .all(|(key, value)| metadata.get(key).and_then(Value::as_str) == Some(value))
+    }
+}
+
+// Questions created in `[after, before)`, either end open. Sent as `?created_after=` and
+// `?created_before=`, read in the caller's timezone.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct DateRange {
+    pub after: Option<DateTime<Utc>>,
+    pub before: Option<DateTime<Utc>>,
+}
+
+impl DateRange {
+    pub fn is_empty(&self) -> bool {
+        self.after.is_none() && self.before.is_none()
+    }
+
+    pub fn contains(&self, at: DateTime<Utc>) -> bool {
+        self.after.is_none_or(|after| at >= after) && self.before.is_none_or(|before| at < before)
+    }
+}
+
+// What `GET /questions` narrows the list to. Backends put both in their WHERE clause.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct QuestionFilter {
+    pub metadata: MetadataFilter,
+    pub created: DateRange,
+}
+
+impl QuestionFilter {
+    pub fn is_empty(&self) -> bool {
+        self.metadata.is_empty() && self.created.is_empty()
+    }
+
+    // The same test in process, like `MetadataFilter::matches`.
+    pub fn matches(&self, question: &QuestionDetail) -> bool {
+        self.metadata.matches(&question.metadata) && self.created.contains(question.created_at)
+    }
+}
+
+// How `GET /questions` is ordered, as `?sort=`. Ties fall back to the uuid, so pages
+// hold the same rows however often they are fetched.
+#[derive(FromFormField, Debug, Clone, Copy, PartialEq, Default)]
+pub enum QuestionSort {
+    #[default]
+    Oldest,
+    Newest,
+    // Most recently edited first.
+    Updated,
+    // Most answered first.
+    Answers,
+    // Most recently asked, edited or answered first.
+    Activity,
+}
+
+impl QuestionSort {
+    // As `?sort=` spells it, for the queries that order by it.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Oldest => "oldest",
+            Self::Newest => "newest",
+            Self::Updated => "updated",
+            Self::Answers => "answers",
+            Self::Activity => "activity",
+        }
+    }
+
+    // The same order in process, for backends without a query to put it in.
+    pub fn sort(self, questions: &mut [QuestionDetail]) {
+        match self {
+            Self::Oldest => questions.sort_by(|a, b| {
+                (&a.created_at, a.question_uuid.0).cmp(&(&b.created_at, b.question_uuid.0))
+            }),
+            Self::Newest => questions.sort_by(|a, b| {
+                (&b.created_at, b.question_uuid.0).cmp(&(&a.created_at, a.question_uuid.0))
+            }),
+            Self::Updated => questions.sort_by(|a, b| {
+                (&b.updated_at, b.question_uuid.0).cmp(&(&a.updated_at, a.question_uuid.0))
+            }),
+            Self::Answers => questions
+                .sort_by_key(|question| (Reverse(question.answer_count), question.question_uuid.0)),
+            Self::Activity => questions.sort_by(|a, b| {
+                (&b.last_activity_at, b.question_uuid.0)
+                    .cmp(&(&a.last_activity_at, a.question_uuid.0))
+            }),
+        }
+    }
+}
+
+#[derive(FromFormField, Debug, Clone, Copy, PartialEq, Default)]
+pub enum AnswerSort {
+    #[default]
+    Oldest,
+    Newest,
+    Updated,
+}
+
+impl AnswerSort {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Oldest => "oldest",
+            Self::Newest => "newest",
+            Self::Updated => "updated",
+        }
+    }
+
+    pub fn sort(self, answers: &mut [AnswerDetail]) {
+        match self {
+            Self::Oldest => answers.sort_by(|a, b| {
+                (&a.created_at, a.answer_uuid.0).cmp(&(&b.created_at, b.answer_uuid.0))
+            }),
+            Self::Newest => answers.sort_by(|a, b| {
+                (&b.created_at, b.answer_uuid.0).cmp(&(&a.created_at, a.answer_uuid.0))
+            }),
+            Self::Updated => answers.sort_by(|a, b| {
+                (&b.updated_at, b.answer_uuid.0).cmp(&(&a.updated_at, a.answer_uuid.0))
+            }),
+        }
+    }
+}
+
+// The rows of a list a page holds: `limit` of them after skipping `offset`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Window {
+    pub offset: u64,
+    pub limit: u64,
+}
+
+impl Window {
+    // Cuts the window out of a whole list, for backends that read it all anyway.
+    pub fn of<T>(self, rows: Vec<T>) -> Listed<T> {
+        let total = rows.len() as u64;
+        Listed {
+            items: rows
+                .into_iter()
+                .skip(self.offset as usize)
+                .take(self.limit as usize)
+                .collect(),
+            total,
+        }
+    }
+}
+
+// The rows in a window of a list, and how many the whole list has.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Listed<T> {
+    pub items: Vec<T>,
+    pub total: u64,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 pub struct Answer {
-    pub question_uuid: String,
+    pub question_uuid: QuestionUuid,
     pub content: String,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 pub struct AnswerDetail {
-    pub answer_uuid: String,
+    pub answer_uuid: AnswerUuid,
+    pub question_uuid: QuestionUuid,
+    pub content: String,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+    pub version: i64,
+}
+
+pub struct AnswerUpdate {
+    pub content: String,
+    pub version: Option<i64>,
+}
+
+// A question written together with its first answer.
+pub struct QuestionWithAnswer {
+    pub question: Question,
+    pub answer: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct QuestionWithAnswerDetail {
+    pub question: QuestionDetail,
+    pub answer: AnswerDetail,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct ImportedQuestion {
+    pub title: String,
+    #[serde(default)]
+    pub description: Option<String>,
+    #[serde(default = "empty_metadata")]
+    pub metadata: Value,
+    #[serde(default)]
+    pub answers: Vec<String>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct Import {
+    pub questions: Vec<ImportedQuestion>,
+}
+
+// A question as `export` writes it, with its identity, history and answers, so it can be
+// loaded elsewhere as it was.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ExportedQuestion {
     pub question_uuid: String,
+    pub title: String,
+    #[serde(default)]
+    pub description: Option<String>,
+    #[serde(default = "empty_metadata")]
+    pub metadata: Value,
+    pub created_at: String,
+    pub updated_at: String,
+    pub version: i64,
+    #[serde(default)]
+    pub answers: Vec<ExportedAnswer>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ExportedAnswer {
+    pub answer_uuid: String,
     pub content: String,
     pub created_at: String,
+    pub updated_at: String,
+    pub version: i64,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Export {
+    pub questions: Vec<ExportedQuestion>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "camel-case", serde(rename_all = "camelCase"))]
+pub struct ImportSummary {
+    pub questions: u64,
+    pub answers: u64,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "camel-case", serde(rename_all = "camelCase"))]
+pub struct ArchiveSummary {
+    pub questions: u64,
+    pub answers: u64,
+}
+
+// What merging a duplicate question moved to the question it was merged into. Followers
+// are email and push subscriptions, counted when the target had no such one yet.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "camel-case", serde(rename_all = "camelCase"))]
+pub struct MergeSummary {
+    pub source_uuid: QuestionUuid,
+    pub target_uuid: QuestionUuid,
+    pub answers: u64,
+    pub followers: u64,
+}
+
+// A translation of a question someone submitted. `language` is lowercase, such as `es`
+// or `pt-br`; `question_version` is the version of the question that was translated.
+#[derive(Debug, Clone, PartialEq)]
+pub struct QuestionTranslation {
+    pub question_uuid: QuestionUuid,
+    pub language: String,
+    pub title: String,
+    pub description: Option<String>,
+    pub question_version: i64,
+    pub updated_at: DateTime<Utc>,
+}
+
+// A question's title and who to tell about its new answers.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Subscribers {
+    pub question_title: String,
+    pub emails: Vec<String>,
+    pub push_subscriptions: Vec<PushSubscription>,
+}
+
+// A browser's Push API subscription: where to send pushes, and the base64url keys
+// their payloads are encrypted for.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PushSubscription {
+    pub endpoint: String,
+    pub p256dh: String,
+    pub auth: String,
+}
+
+// How often an address gets a digest of new answers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DigestFrequency {
+    Daily,
+    Weekly,
+}
+
+impl DigestFrequency {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            DigestFrequency::Daily => "daily",
+            DigestFrequency::Weekly => "weekly",
+        }
+    }
+
+    pub fn period(self) -> chrono::Duration {
+        match self {
+            DigestFrequency::Daily => chrono::Duration::days(1),
+            DigestFrequency::Weekly => chrono::Duration::weeks(1),
+        }
+    }
+}
+
+impl FromStr for DigestFrequency {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.to_ascii_lowercase().as_str() {
+            "daily" => Ok(Self::Daily),
+            "weekly" => Ok(Self::Weekly),
+            _ => Err(format!("Unknown digest frequency: {}", value)),
+        }
+    }
+}
+
+// A digest claimed for sending. It covers what was created after `since` and up to
+// `until`, where the next one starts.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DueDigest {
+    pub email: String,
+    pub frequency: DigestFrequency,
+    pub since: DateTime<Utc>,
+    pub until: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct DigestAnswer {
+    pub question_uuid: QuestionUuid,
+    pub question_title: String,
+    pub content: String,
+}
+
+// A question by how many answers it got during a digest's period.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TrendingQuestion {
+    pub question_uuid: QuestionUuid,
+    pub title: String,
+    pub new_answers: i64,
+}
+
+// New answers on the subscribed questions, oldest first, and the most answered
+// questions, whether subscribed or not.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Digest {
+    pub answers: Vec<DigestAnswer>,
+    pub trending: Vec<TrendingQuestion>,
+}
+
+impl Digest {
+    pub fn is_empty(&self) -> bool {
+        self.answers.is_empty() && self.trending.is_empty()
+    }
+}
+
+// Questions and answers created on one day, archived ones included.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "camel-case", serde(rename_all = "camelCase"))]
+pub struct DailyStats {
+    pub day: String,
+    pub questions: i64,
+    pub answers: i64,
+}
+
+// How questions asked since some day fared. `active_users` is unset on backends without
+// a user directory.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ResponseStats {
+    pub questions: i64,
+    pub answered: i64,
+    pub median_first_answer_secs: Option<f64>,
+    pub active_users: Option<i64>,
+}
+
+// One change from the audit log. `before` is unset for creates and `after` for deletes.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "camel-case", serde(rename_all = "camelCase"))]
+pub struct AuditEntry {
+    pub id: i64,
+    pub entity: String,
+    pub entity_uuid: String,
+    pub action: String,
+    pub actor: String,
+    pub request_id: Option<String>,
+    pub before: Option<serde_json::Value>,
+    pub after: Option<serde_json::Value>,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ActivityKind {
+    QuestionAsked,
+    AnswerPosted,
+}
+
+// An entry of the public activity feed. Titles and content are as they are now, not as
+// first written; `answer_uuid` and `content` are set for answers.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "camel-case", serde(rename_all = "camelCase"))]
+pub struct Activity {
+    pub id: i64,
+    pub kind: ActivityKind,
+    pub question_uuid: QuestionUuid,
+    pub question_title: String,
+    pub answer_uuid: Option<AnswerUuid>,
+    pub content: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[serde(transparent)]
+pub struct AttachmentUuid(pub Uuid);
+
+impl AttachmentUuid {
+    pub fn new_v4() -> Self {
+        Self(Uuid::new_v4())
+    }
+}
+
+impl fmt::Display for AttachmentUuid {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+impl FromStr for AttachmentUuid {
+    type Err = uuid::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Uuid::parse_str(s).map(Self)
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[serde(transparent)]
+pub struct ModerationUuid(pub Uuid);
+
+impl ModerationUuid {
+    pub fn new_v4() -> Self {
+        Self(Uuid::new_v4())
+    }
+}
+
+impl fmt::Display for ModerationUuid {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+impl FromStr for ModerationUuid {
+    type Err = uuid::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Uuid::parse_str(s).map(Self)
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[serde(transparent)]
+pub struct HookUuid(pub Uuid);
+
+impl HookUuid {
+    pub fn new_v4() -> Self {
+        Self(Uuid::new_v4())
+    }
+}
+
+impl fmt::Display for HookUuid {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+impl FromStr for HookUuid {
+    type Err = uuid::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Uuid::parse_str(s).map(Self)
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[serde(transparent)]
+pub struct UserUuid(pub Uuid);
+
+impl UserUuid {
+    pub fn new_v4() -> Self {
+        Self(Uuid::new_v4())
+    }
+}
+
+impl fmt::Display for UserUuid {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+impl FromStr for UserUuid {
+    type Err = uuid::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Uuid::parse_str(s).map(Self)
+    }
+}
+
+// A create that was held for a moderator instead of being written, with everything
+// needed to write it once approved.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum HeldContent {
+    Question {
+        question: Question,
+        author_email: Option<String>,
+    },
+    Answer {
+        answer: Answer,
+    },
+    QuestionWithAnswer {
+        question: Question,
+        answer: String,
+        author_email: Option<String>,
+    },
+}
+
+impl HeldContent {
+    // What moderation providers are shown.
+    pub fn text(&self) -> String {
+        let question_text = |question: &Question| match &question.description {
+            Some(description) => format!("{}\n{}", question.title, description),
+            None => question.title.clone(),
+        };
+        match self {
+            HeldContent::Question { question, .. } => question_text(question),
+            HeldContent::Answer { answer } => answer.content.clone(),
+            HeldContent::QuestionWithAnswer {
+                question, answer, ..
+            } => format!("{}\n{}", question_text(question), answer),
+        }
+    }
+}
+
+// Who sent a create, as spam checkers want it. Kept with content held as spam only, so
+// the moderator's decision can be reported with it.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct SpamContext {
+    pub user_ip: Option<String>,
+    pub user_agent: Option<String>,
+    pub referrer: Option<String>,
+}
+
+// An entry of the moderation queue.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ModerationItem {
+    pub moderation_uuid: ModerationUuid,
+    pub content: HeldContent,
+    // The provider's highest score, from 0 to 1.
+    pub score: f32,
+    // The provider's categories that reached the threshold, e.g. `toxicity`, or `spam`.
+    pub categories: Vec<String>,
+    pub spam_context: Option<SpamContext>,
+    pub created_at: DateTime<Utc>,
+}
+
+// What a moderator does with held items in bulk: publish them, or drop them.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ModerationAction {
+    Approve,
+    Delete,
+}
+
+// What a `ModerationDao` stores; the database adds the creation time.
+#[derive(Debug, Clone, PartialEq)]
+pub struct NewModerationItem {
+    pub moderation_uuid: ModerationUuid,
+    pub content: HeldContent,
+    pub score: f32,
+    pub categories: Vec<String>,
+    pub spam_context: Option<SpamContext>,
+}
+
+// A REST hook subscription: outbox events of `event_type` are POSTed to `target_url`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RestHook {
+    pub hook_uuid: HookUuid,
+    pub target_url: String,
+    pub event_type: String,
+    pub created_at: DateTime<Utc>,
+}
+
+// What an identity provider sets on a provisioned account. `user_name` is the email
+// address the account is known by.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UserAttributes {
+    pub user_name: String,
+    pub external_id: Option<String>,
+    pub display_name: Option<String>,
+    pub active: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProvisionedUser {
+    pub user_uuid: UserUuid,
+    pub attributes: UserAttributes,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AttachmentStatus {
+    // Created for a pre-signed upload the client has not completed yet.
+    Pending,
+    // Uploaded, and waiting for the content scanner.
+    Scanning,
+    Ready,
+    // Flagged by the content scanner; kept for moderators but never served.
+    Quarantined,
+}
+
+impl AttachmentStatus {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            AttachmentStatus::Pending => "pending",
+            AttachmentStatus::Scanning => "scanning",
+            AttachmentStatus::Ready => "ready",
+            AttachmentStatus::Quarantined => "quarantined",
+        }
+    }
+}
+
+impl FromStr for AttachmentStatus {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "pending" => Ok(Self::Pending),
+            "scanning" => Ok(Self::Scanning),
+            "ready" => Ok(Self::Ready),
+            "quarantined" => Ok(Self::Quarantined),
+            _ => Err(format!("Unknown attachment status: {}", value)),
+        }
+    }
+}
+
+// A file attached to a question, or to one of its answers. The bytes are in the object
+// store under `storage_key`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Attachment {
+    pub attachment_uuid: AttachmentUuid,
+    pub question_uuid: QuestionUuid,
+    pub answer_uuid: Option<AnswerUuid>,
+    pub filename: String,
+    pub content_type: String,
+    pub size_bytes: i64,
+    pub storage_key: String,
+    pub status: AttachmentStatus,
+    // Empty until the thumbnails of an image have been made.
+    pub variants: Vec<AttachmentVariant>,
+    pub created_at: DateTime<Utc>,
+}
+
+// A resized copy of an image attachment, stored next to it.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct AttachmentVariant {
+    // Unique per attachment, e.g. `thumbnail-160`.
+    pub name: String,
+    pub width: u32,
+    pub height: u32,
+    pub content_type: String,
+    pub size_bytes: i64,
+    pub storage_key: String,
+}
+
+// What an `AttachmentDao` stores; the database adds the creation time.
+#[derive(Debug, Clone, PartialEq)]
+pub struct NewAttachment {
+    pub attachment_uuid: AttachmentUuid,
+    pub question_uuid: QuestionUuid,
+    pub answer_uuid: Option<AnswerUuid>,
+    pub filename: String,
+    pub content_type: String,
+    pub size_bytes: i64,
+    pub storage_key: String,
+    pub status: AttachmentStatus,
+}
+
+// What a `DBError::NotFound` was looking for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Entity {
+    Question,
+    Answer,
+    Attachment,
+    ModerationItem,
+    RestHook,
+    User,
+    Translation,
+}
+
+impl fmt::Display for Entity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Entity::Question => "Question",
+            Entity::Answer => "Answer",
+            Entity::Attachment => "Attachment",
+            Entity::ModerationItem => "Moderation item",
+            Entity::RestHook => "REST hook",
+            Entity::User => "User",
+            Entity::Translation => "Translation",
+        })
+    }
 }
 
 #[derive(Error, Debug)]
 pub enum DBError {
     #[error("Invalid UUID provided: {0}")]
     InvalidUUID(String),
+    // No row with the given UUID, e.g. an edit to a deleted answer or an answer to an
+    // unknown question.
+    #[error("{1}")]
+    NotFound(Entity, String),
+    #[error("Stale version: {0}")]
+    Conflict(String),
+    #[error("Database is temporarily unavailable")]
+    Unavailable,
     #[error("Database error ocorred")]
     Other(#[from] Box<dyn std::error::Error + Send + Sync>),
 }
@@ -40,4 +879,21 @@ pub enum DBError {
 // source: https://www.postgresql.org/docs/current/errcodes-appendix.html
 pub mod postgres_error_code {
     pub const FOREIGN_KEY_VIOLATION: &str = "23503";
+    pub const UNIQUE_VIOLATION: &str = "23505";
+    pub const SERIALIZATION_FAILURE: &str = "40001";
+    pub const DEADLOCK_DETECTED: &str = "40P01";
+    pub const CONNECTION_EXCEPTION_CLASS: &str = "08";
+}
+
+// source: https://www.sqlite.org/rescode.html
+#[cfg(feature = "sqlite")]
+pub mod sqlite_error_code {
+    pub const FOREIGN_KEY_VIOLATION: &str = "787";
+}
+
+// MySQL shares SQLSTATE 23000 between several constraint errors, so its error numbers are used.
+// source: https://dev.mysql.com/doc/mysql-errors/8.0/en/server-error-reference.html
+#[cfg(feature = "mysql")]
+pub mod mysql_error_number {
+    pub const NO_REFERENCED_ROW: u16 = 1452;
 }
@@ -0,0 +1,547 @@
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use log::warn;
+use thiserror::Error;
+
+use crate::{
+    config::{AppConfig, ModeratorKind},
+    models::{
+        AnswerDetail, DBError, HeldContent, ModerationItem, ModerationUuid, NewModerationItem,
+        QuestionDetail, QuestionWithAnswerDetail, SpamContext,
+    },
+    persistence::moderation::ModerationDao,
+    spam::{SpamChecker, SPAM_CATEGORY},
+    startup::StartupError,
+};
+
+// Past this many links a post reads as link spam.
+const MAX_LINKS: usize = 5;
+// Shorter posts in capitals, e.g. "FAQ" or "RTFM", are not shouting.
+const MIN_SHOUTING_LETTERS: usize = 20;
+const REPEATED_RUN: usize = 12;
+
+// Only remote providers can fail.
+#[derive(Error, Debug)]
+pub enum ModerationError {
+    #[cfg(any(feature = "openai", feature = "perspective"))]
+    #[error("Moderation request failed: {0}")]
+    Request(String),
+}
+
+// Scores from 0 to 1 per category, e.g. `toxicity` or `harassment`.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Assessment {
+    pub scores: Vec<(String, f32)>,
+}
+
+impl Assessment {
+    pub fn score(&self) -> f32 {
+        self.scores
+            .iter()
+            .map(|(_, score)| *score)
+            .fold(0.0, f32::max)
+    }
+
+    pub fn categories_over(&self, threshold: f32) -> Vec<String> {
+        self.scores
+            .iter()
+            .filter(|(_, score)| *score >= threshold)
+            .map(|(category, _)| category.clone())
+            .collect()
+    }
+}
+
+// Rates how toxic or abusive a text is.
+#[async_trait]
+pub trait ModerationProvider {
+    async fn assess(&self, text: &str) -> Result<Assessment, ModerationError>;
+}
+
+// Managed as `Option<Moderation>`; None while neither a provider nor a spam checker is
+// configured, or the backend has no moderation queue.
+pub struct Moderation {
+    pub provider: Option<Box<dyn ModerationProvider + Send + Sync>>,
+    pub spam_checker: Option<Box<dyn SpamChecker + Send + Sync>>,
+    pub moderation_dao: Arc<dyn ModerationDao + Send + Sync>,
+    pub threshold: f32,
+}
+
+// What a create returns when it passed through moderation.
+#[derive(Debug, PartialEq)]
+pub enum Moderated<T> {
+    Published(T),
+    Held(Box<ModerationItem>),
+}
+
+// What approving a held item created.
+#[derive(Debug)]
+pub enum Published {
+    Question(QuestionDetail),
+    Answer(AnswerDetail),
+    QuestionWithAnswer(QuestionWithAnswerDetail),
+}
+
+impl Moderation {
+    // Queues the content when it is spam or scores at least the threshold. A provider or
+    // checker that cannot be reached lets the content through rather than blocking every post.
+    pub async fn hold_if_flagged(
+        &self,
+        content: &HeldContent,
+        spam_context: &SpamContext,
+    ) -> Result<Option<ModerationItem>, DBError> {
+        if let Some(item) = self.hold_if_spam(content, spam_context).await? {
+            return Ok(Some(item));
+        }
+        let Some(provider) = &self.provider else {
+            return Ok(None);
+        };
+
+        let assessment = match provider.assess(&content.text()).await {
+            Ok(assessment) => assessment,
+            Err(err) => {
+                warn!("Publishing unmoderated content: {}", err);
+                return Ok(None);
+            }
+        };
+        if assessment.score() < self.threshold {
+            return Ok(None);
+        }
+
+        let item = self
+            .moderation_dao
+            .hold(NewModerationItem {
+                moderation_uuid: ModerationUuid::new_v4(),
+                content: content.clone(),
+                score: assessment.score(),
+                categories: assessment.categories_over(self.threshold),
+                spam_context: None,
+            })
+            .await?;
+        Ok(Some(item))
+    }
+
+    async fn hold_if_spam(
+        &self,
+        content: &HeldContent,
+        spam_context: &SpamContext,
+    ) -> Result<Option<ModerationItem>, DBError> {
+        let Some(spam_checker) = &self.spam_checker else {
+            return Ok(None);
+        };
+        match spam_checker.is_spam(content, spam_context).await {
+            Ok(true) => {}
+            Ok(false) => return Ok(None),
+            Err(err) => {
+                warn!("Publishing content unchecked for spam: {}", err);
+                return Ok(None);
+            }
+        }
+
+        let item = self
+            .moderation_dao
+            .hold(NewModerationItem {
+                moderation_uuid: ModerationUuid::new_v4(),
+                content: content.clone(),
+                score: 1.0,
+                categories: vec![SPAM_CATEGORY.to_owned()],
+                spam_context: Some(spam_context.clone()),
+            })
+            .await?;
+        Ok(Some(item))
+    }
+
+    // Tells the spam checker whether content it held was spam after all. The decision
+    // stands even when the checker cannot be reached.
+    pub async fn report_decision(&self, item: &ModerationItem, spam: bool) {
+        let (Some(spam_checker), Some(spam_context)) = (&self.spam_checker, &item.spam_context)
+        else {
+            return;
+        };
+        if let Err(err) = spam_checker.report(&item.content, spam_context, spam).await {
+            warn!(
+                "Error on reporting moderation item {} to the spam checker: {}",
+                item.moderation_uuid, err
+            );
+        }
+    }
+}
+
+pub fn provider_from_config(
+    config: &AppConfig,
+) -> Result<Option<Box<dyn ModerationProvider + Send + Sync>>, StartupError> {
+    match config.moderator {
+        ModeratorKind::None => Ok(None),
+        ModeratorKind::Heuristic => Ok(Some(Box::new(HeuristicModerator::new(
+            config.moderation_blocklist.clone(),
+        )))),
+        #[cfg(feature = "openai")]
+        ModeratorKind::OpenAi => Ok(Some(Box::new(OpenAiModerator::new(
+            config
+                .moderation_api_url
+                .as_deref()
+                .unwrap_or("https://api.openai.com/v1"),
+            config.moderation_api_key.clone(),
+        )))),
+        #[cfg(not(feature = "openai"))]
+        ModeratorKind::OpenAi => Err(StartupError::MissingFeature {
+            setting: "MODERATION_PROVIDER=openai",
+            feature: "openai",
+        }),
+        #[cfg(feature = "perspective")]
+        ModeratorKind::Perspective => Ok(Some(Box::new(PerspectiveModerator::new(
+            config
+                .moderation_api_url
+                .as_deref()
+                .unwrap_or("https://commentanalyzer.googleapis.com/v1alpha1"),
+            config
+                .moderation_api_key
+                .clone()
+                .ok_or(StartupError::MissingSetting {
+                    setting: "MODERATION_PROVIDER=perspective",
+                    needed: "MODERATION_API_KEY",
+                })?,
+        )))),
+        #[cfg(not(feature = "perspective"))]
+        ModeratorKind::Perspective => Err(StartupError::MissingFeature {
+            setting: "MODERATION_PROVIDER=perspective",
+            feature: "perspective",
+        }),
+    }
+}
+
+// Local rules that need no service: blocklisted words, link spam, shouting and long runs
+// of one character. Catches the blatant cases only.
+pub struct HeuristicModerator {
+    blocklist: Vec<String>,
+}
+
+impl HeuristicModerator {
+    pub fn new(blocklist: Vec<String>) -> Self {
+        Self {
+            blocklist: blocklist
+                .into_iter()
+                .map(|word| word.to_lowercase())
+                .collect(),
+        }
+    }
+}
+
+#[async_trait]
+impl ModerationProvider for HeuristicModerator {
+    async fn assess(&self, text: &str) -> Result<Assessment, ModerationError> {
+        let mut scores = Vec::new();
+
+        let lowercase = text.to_lowercase();
+        if lowercase
+            .split(|c: char| !c.is_alphanumeric())
+            .any(|word| self.blocklist.iter().any(|blocked| blocked == word))
+        {
+            scores.push(("blocklist".to_owned(), 1.0));
+        }
+
+        if lowercase.matches("http://").count() + lowercase.matches("https://").count() > MAX_LINKS
+        {
+            scores.push(("links".to_owned(), 0.9));
+        }
+
+        let letters: Vec<char> = text.chars().filter(|c| c.is_alphabetic()).collect();
+        let uppercase = letters.iter().filter(|c| c.is_uppercase()).count();
+        if letters.len() >= MIN_SHOUTING_LETTERS && uppercase * 5 >= letters.len() * 4 {
+            scores.push(("shouting".to_owned(), 0.5));
+        }
+
+        let mut run = 0;
+        let mut previous = None;
+        for c in text.chars().filter(|c| !c.is_whitespace()) {
+            run = if previous == Some(c) { run + 1 } else { 1 };
+            previous = Some(c);
+            if run >= REPEATED_RUN {
+                scores.push(("repetition".to_owned(), 0.5));
+                break;
+            }
+        }
+
+        Ok(Assessment { scores })
+    }
+}
+
+// OpenAI's `/moderations` endpoint, or a server implementing it.
+#[cfg(feature = "openai")]
+pub struct OpenAiModerator {
+    client: reqwest::Client,
+    url: String,
+    api_key: Option<String>,
+}
+
+#[cfg(feature = "openai")]
+impl OpenAiModerator {
+    pub fn new(base_url: &str, api_key: Option<String>) -> Self {
+        Self {
+            client: reqwest::Client::new(),
+            url: format!("{}/moderations", base_url.trim_end_matches('/')),
+            api_key,
+        }
+    }
+}
+
+#[cfg(feature = "openai")]
+#[async_trait]
+impl ModerationProvider for OpenAiModerator {
+    async fn assess(&self, text: &str) -> Result<Assessment, ModerationError> {
+        #[derive(serde::Deserialize)]
+        struct Response {
+            results: Vec<ModerationResult>,
+        }
+
+        #[derive(serde::Deserialize)]
+        struct ModerationResult {
+            category_scores: std::collections::BTreeMap<String, f32>,
+        }
+
+        let mut request = self.client.post(&self.url).json(&serde_json::json!({
+            "model": "omni-moderation-latest",
+            "input": text,
+        }));
+        if let Some(api_key) = &self.api_key {
+            request = request.bearer_auth(api_key);
+        }
+
+        let response: Response = request
+            .send()
+            .await
+            .and_then(|response| response.error_for_status())
+            .map_err(|err| ModerationError::Request(err.to_string()))?
+            .json()
+            .await
+            .map_err(|err| ModerationError::Request(err.to_string()))?;
+
+        let result = response
+            .results
+            .into_iter()
+            .next()
+            .ok_or_else(|| ModerationError::Request("Response has no result".to_owned()))?;
+        Ok(Assessment {
+            scores: result.category_scores.into_iter().collect(),
+        })
+    }
+}
+
+// Google's Perspective API. Texts are sent with `doNotStore`.
+#[cfg(feature = "perspective")]
+pub struct PerspectiveModerator {
+    client: reqwest::Client,
+    url: String,
+}
+
+#[cfg(feature = "perspective")]
+impl PerspectiveModerator {
+    const ATTRIBUTES: [&'static str; 5] = [
+        "TOXICITY",
+        "SEVERE_TOXICITY",
+        "INSULT",
+        "THREAT",
+        "IDENTITY_ATTACK",
+    ];
+
+    pub fn new(base_url: &str, api_key: String) -> Self {
+        Self {
+            client: reqwest::Client::new(),
+            url: format!(
+                "{}/comments:analyze?key={}",
+                base_url.trim_end_matches('/'),
+                api_key
+            ),
+        }
+    }
+}
+
+#[cfg(feature = "perspective")]
+#[async_trait]
+impl ModerationProvider for PerspectiveModerator {
+    async fn assess(&self, text: &str) -> Result<Assessment, ModerationError> {
+        #[derive(serde::Deserialize)]
+        #[serde(rename_all = "camelCase")]
+        struct Response {
+            attribute_scores: std::collections::BTreeMap<String, AttributeScore>,
+        }
+
+        #[derive(serde::Deserialize)]
+        #[serde(rename_all = "camelCase")]
+        struct AttributeScore {
+            summary_score: SummaryScore,
+        }
+
+        #[derive(serde::Deserialize)]
+        struct SummaryScore {
+            value: f32,
+        }
+
+        let attributes: serde_json::Map<String, serde_json::Value> = Self::ATTRIBUTES
+            .iter()
+            .map(|attribute| (attribute.to_string(), serde_json::json!({})))
+            .collect();
+        let response: Response = self
+            .client
+            .post(&self.url)
+            .json(&serde_json::json!({
+                "comment": { "text": text },
+                "requestedAttributes": attributes,
+                "doNotStore": true,
+            }))
+            .send()
+            .await
+            .and_then(|response| response.error_for_status())
+            .map_err(|err| ModerationError::Request(err.to_string()))?
+            .json()
+            .await
+            .map_err(|err| ModerationError::Request(err.to_string()))?;
+
+        Ok(Assessment {
+            scores: response
+                .attribute_scores
+                .into_iter()
+                .map(|(attribute, score)| (attribute.to_lowercase(), score.summary_score.value))
+                .collect(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        models::{empty_metadata, Question, Window},
+        persistence::memory::{InMemoryModerationDao, MemoryStore},
+    };
+
+    fn question(title: &str) -> HeldContent {
+        HeldContent::Question {
+            question: Question {
+                title: title.to_owned(),
+                description: None,
+                metadata: empty_metadata(),
+            },
+            author_email: None,
+        }
+    }
+
+    // Flags titles mentioning pills and records what it is told.
+    #[derive(Default)]
+    struct FakeSpamChecker {
+        reports: Arc<std::sync::Mutex<Vec<(SpamContext, bool)>>>,
+    }
+
+    #[async_trait]
+    impl SpamChecker for FakeSpamChecker {
+        async fn is_spam(
+            &self,
+            content: &HeldContent,
+            _: &SpamContext,
+        ) -> Result<bool, crate::spam::SpamError> {
+            Ok(content.text().contains("pills"))
+        }
+
+        async fn report(
+            &self,
+            _: &HeldContent,
+            context: &SpamContext,
+            spam: bool,
+        ) -> Result<(), crate::spam::SpamError> {
+            self.reports.lock().unwrap().push((context.clone(), spam));
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn heuristic_moderator_should_score_abuse() {
+        let moderator = HeuristicModerator::new(vec!["Scam".to_owned()]);
+        let score = |text: &'static str| {
+            let moderator = &moderator;
+            async move { moderator.assess(text).await.unwrap().score() }
+        };
+
+        assert_eq!(score("How do I borrow twice in Rust?").await, 0.0);
+        assert_eq!(score("This is a scam, obviously").await, 1.0);
+        assert_eq!(score("Scammer is not a blocked word").await, 0.0);
+        assert_eq!(score("WHY DOES NOTHING EVER COMPILE HERE").await, 0.5);
+        assert_eq!(score("FAQ").await, 0.0);
+        assert_eq!(score("noooooooooooooooo").await, 0.5);
+        assert_eq!(
+            score("https://a https://b https://c https://d https://e http://f").await,
+            0.9
+        );
+    }
+
+    #[tokio::test]
+    async fn moderation_should_hold_content_over_the_threshold() {
+        let moderation = Moderation {
+            provider: Some(Box::new(HeuristicModerator::new(vec!["scam".to_owned()]))),
+            spam_checker: None,
+            moderation_dao: Arc::new(InMemoryModerationDao::new(MemoryStore::new())),
+            threshold: 0.8,
+        };
+
+        let held = moderation
+            .hold_if_flagged(&question("A scam"), &SpamContext::default())
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(held.categories, ["blocklist"]);
+        assert_eq!(held.content, question("A scam"));
+
+        assert!(moderation
+            .hold_if_flagged(
+                &question("WHY DOES NOTHING EVER COMPILE HERE"),
+                &SpamContext::default(),
+            )
+            .await
+            .unwrap()
+            .is_none());
+        assert_eq!(
+            moderation
+                .moderation_dao
+                .held_items(Window {
+                    offset: 0,
+                    limit: 10
+                })
+                .await
+                .unwrap()
+                .items,
+            [held]
+        );
+    }
+
+    #[tokio::test]
+    async fn spam_should_be_held_with_its_context_and_reported() {
+        let spam_checker = FakeSpamChecker::default();
+        let reports = spam_checker.reports.clone();
+        let moderation = Moderation {
+            provider: None,
+            spam_checker: Some(Box::new(spam_checker)),
+            moderation_dao: Arc::new(InMemoryModerationDao::new(MemoryStore::new())),
+            threshold: 0.8,
+        };
+        let context = SpamContext {
+            user_ip: Some("203.0.113.7".to_owned()),
+            user_agent: Some("curl/8.0".to_owned()),
+            referrer: None,
+        };
+
+        let held = moderation
+            .hold_if_flagged(&question("Cheap pills"), &context)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(held.categories, [SPAM_CATEGORY]);
+        assert_eq!(held.spam_context.as_ref(), Some(&context));
+        assert!(moderation
+            .hold_if_flagged(&question("A scam"), &context)
+            .await
+            .unwrap()
+            .is_none());
+
+        moderation.report_decision(&held, true).await;
+        assert_eq!(*reports.lock().unwrap(), [(context, true)]);
+    }
+}
@@ -0,0 +1,156 @@
+use async_trait::async_trait;
+use sqlx::PgPool;
+
+use super::acquire;
+use crate::models::{Activity, ActivityKind, AnswerUuid, DBError, QuestionUuid};
+
+pub const ACTIVITY_MAX_LIMIT: u32 = 100;
+
+// The questions asked and answers posted, newest first. Deleted ones are left out and
+// archived ones kept. Postgres reads it from the audit log.
+#[async_trait]
+pub trait ActivityDao {
+    // Only entries older than `before_id`, to page through the feed.
+    async fn recent_activity(
+        &self,
+        before_id: Option<i64>,
+        limit: u32,
+    ) -> Result<Vec<Activity>, DBError>;
+}
+
+pub struct PgActivityDao {
+    db: PgPool,
+}
+
+impl PgActivityDao {
+    pub fn new(db: PgPool) -> Self {
+        Self { db }
+    }
+}
+
+#[async_trait]
+impl ActivityDao for PgActivityDao {
+    async fn recent_activity(
+        &self,
+        before_id: Option<i64>,
+        limit: u32,
+    ) -> Result<Vec<Activity>, DBError> {
+        let mut conn = acquire(&self.db).await?;
+
+        // Both snapshots carry `question_uuid`. Joining the live and archived rows drops
+        // whatever was deleted since, and answers with their question.
+        let rows = sqlx::query!(
+            r#"
+                SELECT
+                    log.id, log.entity, log.created_at,
+                    question.question_uuid AS "question_uuid!",
+                    question.title AS "question_title!",
+                    answer.answer_uuid AS "answer_uuid?",
+                    answer.content AS "content?"
+                FROM audit_log AS log
+                JOIN (
+                    SELECT question_uuid, title FROM questions
+                    UNION ALL
+                    SELECT question_uuid, title FROM questions_archive
+                ) AS question ON question.question_uuid = (log.after ->> 'question_uuid')::UUID
+                LEFT JOIN (
+                    SELECT answer_uuid, content FROM answers
+                    UNION ALL
+                    SELECT answer_uuid, content FROM answers_archive
+                ) AS answer ON log.entity = 'answer' AND answer.answer_uuid = log.entity_uuid
+                WHERE log.action = 'created'
+                AND (log.entity = 'question' OR answer.answer_uuid IS NOT NULL)
+                AND ($1::BIGINT IS NULL OR log.id < $1)
+                ORDER BY log.id DESC
+                LIMIT $2
+            "#,
+            before_id,
+            i64::from(limit.clamp(1, ACTIVITY_MAX_LIMIT)),
+        )
+        .fetch_all(&mut conn)
+        .await
+        .map_err(|err| DBError::Other(Box::new(err)))?;
+
+        Ok(rows
+            .into_iter()
+            .map(|row| Activity {
+                id: row.id,
+                kind: if row.entity == "answer" {
+                    ActivityKind::AnswerPosted
+                } else {
+                    ActivityKind::QuestionAsked
+                },
+                question_uuid: QuestionUuid(row.question_uuid),
+                question_title: row.question_title,
+                answer_uuid: row.answer_uuid.map(AnswerUuid),
+                content: row.content,
+                created_at: row.created_at.and_utc(),
+            })
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        models::{empty_metadata, Answer, Question},
+        persistence::{
+            answer_dao::{AnswerDao, AnswerDaoImpl},
+            question_dao::{QuestionDao, QuestionDaoImpl},
+        },
+    };
+
+    #[sqlx::test]
+    async fn recent_activity_should_skip_deleted_answers(pool: PgPool) -> Result<(), String> {
+        let dao = PgActivityDao::new(pool.clone());
+        let answer_dao = AnswerDaoImpl::new(pool.clone());
+        let question = QuestionDaoImpl::new(pool)
+            .create_question(Question {
+                title: "title".to_owned(),
+                description: None,
+                metadata: empty_metadata(),
+            })
+            .await
+            .map_err(|err| err.to_string())?;
+        let mut answers = vec![];
+        for content in ["first", "second"] {
+            let answer = answer_dao
+                .create_answer(Answer {
+                    question_uuid: question.question_uuid,
+                    content: content.to_owned(),
+                })
+                .await
+                .map_err(|err| err.to_string())?;
+            answers.push(answer);
+        }
+        answer_dao
+            .delete_answer(answers[0].answer_uuid)
+            .await
+            .map_err(|err| err.to_string())?;
+
+        let activity = dao
+            .recent_activity(None, 10)
+            .await
+            .map_err(|err| err.to_string())?;
+        let kinds: Vec<_> = activity
+            .iter()
+            .map(|entry| (entry.kind, entry.content.as_deref()))
+            .collect();
+        assert_eq!(
+            kinds,
+            vec![
+                (ActivityKind::AnswerPosted, Some("second")),
+                (ActivityKind::QuestionAsked, None)
+            ]
+        );
+        assert_eq!(activity[1].question_title, "title");
+
+        let older = dao
+            .recent_activity(Some(activity[0].id), 10)
+            .await
+            .map_err(|err| err.to_string())?;
+        assert_eq!(older, activity[1..]);
+        Ok(())
+    }
+}
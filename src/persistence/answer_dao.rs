@@ -1,147 +1,370 @@
+use async_stream::try_stream;
 use async_trait::async_trait;
-use sqlx::{types::Uuid, PgPool};
-
-use crate::models::{postgres_error_code, Answer, AnswerDetail, DBError};
-
+use futures::TryStreamExt;
+use sqlx::{
+    types::{chrono::NaiveDateTime, Uuid},
+    PgConnection, PgPool,
+};
+
+use super::{
+    acquire_read, audit::acquire_as_actor, missed_update, replica::ReadReplica, RowStream,
+};
+use crate::models::{
+    postgres_error_code, Answer, AnswerDetail, AnswerSort, AnswerUuid, DBError, Entity, Listed,
+    QuestionUuid, Window,
+};
+
+// Answers as stored, with the same conventions as `QuestionDao`. A question's
+// `answer_count` is kept in step with its answers by the database.
 #[async_trait]
 pub trait AnswerDao {
+    // Fails with `DBError::NotFound` for `Entity::Question` if the question does not exist.
     async fn create_answer(&self, answer: Answer) -> Result<AnswerDetail, DBError>;
-    async fn delete_answer(&self, answer_uuid: String) -> Result<(), DBError>;
-    async fn get_answers(&self, question_uuid: String) -> Result<Vec<AnswerDetail>, DBError>;
+    // Succeeds for unknown answers, like `QuestionDao::delete_question`.
+    async fn delete_answer(&self, answer_uuid: AnswerUuid) -> Result<(), DBError>;
+    // Fails with `DBError::Conflict` unless the stored answer is still at `version`,
+    // and with `DBError::NotFound` if there is none.
+    async fn update_answer(
+        &self,
+        answer_uuid: AnswerUuid,
+        content: String,
+        version: i64,
+    ) -> Result<AnswerDetail, DBError>;
+    // The question's answers, in no particular order; none for an unknown question.
+    async fn get_answers(&self, question_uuid: QuestionUuid) -> Result<Vec<AnswerDetail>, DBError>;
+    // Same rows as `get_answers`, fetched as they are consumed.
+    fn stream_answers(&self, question_uuid: QuestionUuid) -> RowStream<'_, AnswerDetail>;
+    // The answers of all the questions in one query, in no particular order.
+    async fn get_answers_of_questions(
+        &self,
+        question_uuids: &[QuestionUuid],
+    ) -> Result<Vec<AnswerDetail>, DBError>;
+    // A window of the question's answers in `sort` order, and how many it has in all,
+    // like `QuestionDao::list_questions`. With `include_archived`, archived answers are
+    // listed too; a question is either live or archived, so only one kind has any.
+    async fn list_answers(
+        &self,
+        question_uuid: QuestionUuid,
+        include_archived: bool,
+        sort: AnswerSort,
+        window: Window,
+    ) -> Result<Listed<AnswerDetail>, DBError>;
+}
+
+// An `answers` row as the queries below select it.
+pub(super) struct AnswerRow {
+    pub answer_uuid: Uuid,
+    pub question_uuid: Uuid,
+    pub content: String,
+    pub created_at: NaiveDateTime,
+    pub updated_at: NaiveDateTime,
+    pub version: i64,
+}
+
+impl From<AnswerRow> for AnswerDetail {
+    fn from(row: AnswerRow) -> Self {
+        AnswerDetail {
+            answer_uuid: AnswerUuid(row.answer_uuid),
+            question_uuid: QuestionUuid(row.question_uuid),
+            content: row.content,
+            created_at: row.created_at.and_utc(),
+            updated_at: row.updated_at.and_utc(),
+            version: row.version,
+        }
+    }
 }
 
 pub struct AnswerDaoImpl {
     db: PgPool,
+    read_replica: Option<ReadReplica>,
 }
 
 impl AnswerDaoImpl {
     pub fn new(db: PgPool) -> Self {
-        Self { db }
+        Self {
+            db,
+            read_replica: None,
+        }
+    }
+
+    pub fn with_read_replica(mut self, read_replica: ReadReplica) -> Self {
+        self.read_replica = Some(read_replica);
+        self
     }
 }
 
 #[async_trait]
 impl AnswerDao for AnswerDaoImpl {
     async fn create_answer(&self, answer: Answer) -> Result<AnswerDetail, DBError> {
-        let question_uuid = Uuid::parse_str(&answer.question_uuid)
-            .map_err(|err| DBError::InvalidUUID(err.to_string()))?;
+        let mut conn = acquire_as_actor(&self.db).await?;
+        insert_answer(&mut conn, answer).await
+    }
+
+    async fn delete_answer(&self, answer_uuid: AnswerUuid) -> Result<(), DBError> {
+        let answer_uuid = answer_uuid.0;
+
+        let mut conn = acquire_as_actor(&self.db).await?;
 
-        let result = sqlx::query!(
+        // The creation month isn't known from the uuid alone, so this probes the
+        // primary key index of every partition.
+        sqlx::query!(
             "--sql
-                INSERT INTO answers ( question_uuid, content )
-                VALUES ( $1, $2 )
-                RETURNING *
+                DELETE from answers
+                WHERE answer_uuid = $1
             ",
-            &question_uuid,
-            &answer.content,
+            answer_uuid
         )
-        .fetch_one(&self.db)
+        .execute(&mut conn)
         .await
-        .map_err(|err: sqlx::Error| match err {
-            sqlx::Error::Database(err) => {
-                let Some(code) = err.code() else {
-                    return DBError::Other(Box::new(err))
-                };
-
-                if code.eq(postgres_error_code::FOREIGN_KEY_VIOLATION) {
-                    return DBError::InvalidUUID(err.to_string());
-                }
+        .map_err(|err| DBError::Other(Box::new(err)))?;
 
-                return DBError::Other(Box::new(err));
-            }
-            err => DBError::Other(Box::new(err)),
-        })?;
-
-        Ok(AnswerDetail {
-            answer_uuid: result.answer_uuid.to_string(),
-            question_uuid: result.question_uuid.to_string(),
-            content: result.content,
-            created_at: result.created_at.to_string(),
-        })
+        Ok(())
     }
 
-    async fn delete_answer(&self, answer_uuid: String) -> Result<(), DBError> {
-        let answer_uuid =
-            Uuid::parse_str(&answer_uuid).map_err(|e| DBError::InvalidUUID(e.to_string()))?;
+    async fn update_answer(
+        &self,
+        answer_uuid: AnswerUuid,
+        content: String,
+        version: i64,
+    ) -> Result<AnswerDetail, DBError> {
+        let answer_uuid = answer_uuid.0;
 
-        sqlx::query!(
+        let mut conn = acquire_as_actor(&self.db).await?;
+
+        let result = sqlx::query_as!(
+            AnswerRow,
             "--sql
-                DELETE from answers
-                WHERE answer_uuid = $1
+                UPDATE answers
+                SET content = $1, version = version + 1
+                WHERE answer_uuid = $2 AND version = $3
+                RETURNING answer_uuid, question_uuid, content, created_at, updated_at, version
             ",
-            answer_uuid
+            &content,
+            answer_uuid,
+            version,
         )
-        .execute(&self.db)
+        .fetch_optional(&mut conn)
         .await
         .map_err(|err| DBError::Other(Box::new(err)))?;
 
-        Ok(())
+        let Some(result) = result else {
+            let current_version = sqlx::query_scalar!(
+                "SELECT version FROM answers WHERE answer_uuid = $1",
+                answer_uuid,
+            )
+            .fetch_optional(&mut conn)
+            .await
+            .map_err(|err| DBError::Other(Box::new(err)))?;
+
+            return Err(missed_update(
+                Entity::Answer,
+                &answer_uuid.to_string(),
+                current_version,
+                version,
+            ));
+        };
+
+        Ok(result.into())
     }
 
-    async fn get_answers(&self, question_uuid: String) -> Result<Vec<AnswerDetail>, DBError> {
-        let question_uuid =
-            Uuid::parse_str(&question_uuid).map_err(|e| DBError::InvalidUUID(e.to_string()))?;
+    async fn get_answers(&self, question_uuid: QuestionUuid) -> Result<Vec<AnswerDetail>, DBError> {
+        let question_uuid = question_uuid.0;
 
-        let result = sqlx::query!(
+        let mut conn = acquire_read(&self.db, self.read_replica.as_ref()).await?;
+
+        let result = sqlx::query_as!(
+            AnswerRow,
             "--sql
-                SELECT * from answers
+                SELECT answer_uuid, question_uuid, content, created_at, updated_at, version
+                FROM answers
                 WHERE question_uuid = $1
+                -- Answers are never older than their question, or than the answers a merge or import
+                -- brought in, so earlier partitions are skipped.
+                AND created_at >= (SELECT LEAST(created_at, answers_since) AT TIME ZONE 'UTC' FROM questions WHERE question_uuid = $1)
             ",
             question_uuid
         )
-        .fetch_all(&self.db)
+        .fetch_all(&mut conn)
         .await
         .map_err(|err| DBError::Other(Box::new(err)))?;
 
-        let answers = result
-            .iter()
-            .map(|val| AnswerDetail {
-                question_uuid: val.question_uuid.to_string(),
-                answer_uuid: val.answer_uuid.to_string(),
-                content: val.content.clone(),
-                created_at: val.created_at.to_string(),
-            })
-            .collect();
+        Ok(result.into_iter().map(AnswerDetail::from).collect())
+    }
+
+    fn stream_answers(&self, question_uuid: QuestionUuid) -> RowStream<'_, AnswerDetail> {
+        Box::pin(try_stream! {
+            let question_uuid = question_uuid.0;
+
+            let mut conn = acquire_read(&self.db, self.read_replica.as_ref()).await?;
+
+            let mut rows = sqlx::query_as!(
+                AnswerRow,
+                "--sql
+                    SELECT answer_uuid, question_uuid, content, created_at, updated_at, version
+                    FROM answers
+                    WHERE question_uuid = $1
+                    AND created_at >= (SELECT LEAST(created_at, answers_since) AT TIME ZONE 'UTC' FROM questions WHERE question_uuid = $1)
+                ",
+                question_uuid
+            )
+            .fetch(&mut conn);
+
+            while let Some(row) = rows
+                .try_next()
+                .await
+                .map_err(|err| DBError::Other(Box::new(err)))?
+            {
+                yield row.into();
+            }
+        })
+    }
+
+    async fn get_answers_of_questions(
+        &self,
+        question_uuids: &[QuestionUuid],
+    ) -> Result<Vec<AnswerDetail>, DBError> {
+        let question_uuids: Vec<Uuid> = question_uuids.iter().map(|uuid| uuid.0).collect();
+
+        let mut conn = acquire_read(&self.db, self.read_replica.as_ref()).await?;
+
+        let result = sqlx::query_as!(
+            AnswerRow,
+            "--sql
+                SELECT answer_uuid, question_uuid, content, created_at, updated_at, version
+                FROM answers
+                WHERE question_uuid = ANY($1)
+            ",
+            &question_uuids[..]
+        )
+        .fetch_all(&mut conn)
+        .await
+        .map_err(|err| DBError::Other(Box::new(err)))?;
+
+        Ok(result.into_iter().map(AnswerDetail::from).collect())
+    }
+
+    async fn list_answers(
+        &self,
+        question_uuid: QuestionUuid,
+        include_archived: bool,
+        sort: AnswerSort,
+        window: Window,
+    ) -> Result<Listed<AnswerDetail>, DBError> {
+        let question_uuid = question_uuid.0;
+
+        let mut conn = acquire_read(&self.db, self.read_replica.as_ref()).await?;
+
+        let items = sqlx::query_as!(
+            AnswerRow,
+            r#"
+                SELECT
+                    answer_uuid AS "answer_uuid!",
+                    question_uuid AS "question_uuid!",
+                    content AS "content!",
+                    created_at AS "created_at!",
+                    updated_at AS "updated_at!",
+                    version AS "version!"
+                FROM (
+                    SELECT answer_uuid, question_uuid, content, created_at, updated_at, version
+                    FROM answers
+                    WHERE question_uuid = $1
+                    AND created_at >= (SELECT LEAST(created_at, answers_since) AT TIME ZONE 'UTC' FROM questions WHERE question_uuid = $1)
+                    UNION ALL
+                    SELECT answer_uuid, question_uuid, content, created_at, updated_at, version
+                    FROM answers_archive
+                    WHERE $2 AND question_uuid = $1
+                ) AS listed
+                ORDER BY
+                    CASE WHEN $3 = 'oldest' THEN created_at END,
+                    CASE WHEN $3 = 'newest' THEN created_at END DESC,
+                    CASE WHEN $3 = 'updated' THEN updated_at END DESC,
+                    CASE WHEN $3 = 'oldest' THEN answer_uuid END,
+                    answer_uuid DESC
+                OFFSET $4
+                LIMIT $5
+            "#,
+            question_uuid,
+            include_archived,
+            sort.as_str(),
+            window.offset as i64,
+            window.limit as i64,
+        )
+        .fetch_all(&mut conn)
+        .await
+        .map_err(|err| DBError::Other(Box::new(err)))?;
+
+        let total = sqlx::query_scalar!(
+            r#"
+                SELECT
+                    (
+                        SELECT COUNT(*) FROM answers
+                        WHERE question_uuid = $1
+                        AND created_at >= (SELECT LEAST(created_at, answers_since) AT TIME ZONE 'UTC' FROM questions WHERE question_uuid = $1)
+                    ) + (
+                        SELECT COUNT(*) FROM answers_archive WHERE $2 AND question_uuid = $1
+                    ) AS "total!"
+            "#,
+            question_uuid,
+            include_archived,
+        )
+        .fetch_one(&mut conn)
+        .await
+        .map_err(|err| DBError::Other(Box::new(err)))?;
 
-        Ok(answers)
+        Ok(Listed {
+            items: items.into_iter().map(AnswerDetail::from).collect(),
+            total: total as u64,
+        })
     }
 }
 
+// Shared with `PgUnitOfWork` so the statement is the same in and out of a transaction.
+pub(super) async fn insert_answer(
+    conn: &mut PgConnection,
+    answer: Answer,
+) -> Result<AnswerDetail, DBError> {
+    let question_uuid = answer.question_uuid.0;
+
+    let result = sqlx::query_as!(
+        AnswerRow,
+        "--sql
+            INSERT INTO answers ( question_uuid, content )
+            VALUES ( $1, $2 )
+            RETURNING answer_uuid, question_uuid, content, created_at, updated_at, version
+        ",
+        &question_uuid,
+        &answer.content,
+    )
+    .fetch_one(conn)
+    .await
+    .map_err(|err: sqlx::Error| match err {
+        sqlx::Error::Database(err) => {
+            let Some(code) = err.code() else {
+                return DBError::Other(Box::new(err));
+            };
+
+            if code.eq(postgres_error_code::FOREIGN_KEY_VIOLATION) {
+                return DBError::NotFound(Entity::Question, err.to_string());
+            }
+
+            DBError::Other(Box::new(err))
+        }
+        err => DBError::Other(Box::new(err)),
+    })?;
+
+    Ok(result.into())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use sqlx::PgPool;
 
     use crate::{
-        models::{Answer, DBError, Question},
+        models::{empty_metadata, Answer, DBError, Question},
         persistence::question_dao::{QuestionDao, QuestionDaoImpl},
     };
 
-    #[sqlx::test]
-    async fn create_answer_should_fail_with_malformed_uuid(pool: PgPool) -> Result<(), String> {
-        let dao = AnswerDaoImpl::new(pool.clone());
-        let result = dao
-            .create_answer(Answer {
-                question_uuid: "invalid-uuid".to_owned(),
-                content: "content".to_owned(),
-            })
-            .await;
-
-        if result.is_ok() {
-            return Err(format!("Expected Err but found Ok"));
-        }
-
-        let err = result.err().unwrap();
-        if let DBError::InvalidUUID(_) = err {
-            return Ok(());
-        }
-
-        Err(format!(
-            "Expected InvalidUUID but got: {} ",
-            err.to_string()
-        ))
-    }
-
     #[sqlx::test]
     async fn create_answer_should_fail_with_non_existent_uuid(pool: PgPool) -> Result<(), String> {
         let dao = AnswerDaoImpl::new(pool.clone());
@@ -149,19 +372,19 @@ mod tests {
         let some_uuid = "a22abcd2-22ab-2222-a22b-2abc2a2b22cc";
         let result = dao
             .create_answer(Answer {
-                question_uuid: some_uuid.to_owned(),
+                question_uuid: some_uuid.parse().unwrap(),
                 content: "content".to_owned(),
             })
             .await;
 
         if result.is_ok() {
-            return Err(format!("Expected err but found ok"));
+            return Err("Expected err but found ok".to_string());
         }
         let err = result.err().unwrap();
 
         match err {
-            DBError::InvalidUUID(_) => Ok(()),
-            err => Err(format!("Expected InvalidUUID but got: {}", err)),
+            DBError::NotFound(Entity::Question, _) => Ok(()),
+            err => Err(format!("Expected NotFound but got: {}", err)),
         }
     }
 
@@ -175,13 +398,13 @@ mod tests {
         let some_uuid = "a22abcd2-22ab-2222-a22b-2abc2a2b22cc";
         let result = dao
             .create_answer(Answer {
-                question_uuid: some_uuid.to_owned(),
+                question_uuid: some_uuid.parse().unwrap(),
                 content: "content".to_owned(),
             })
             .await;
 
         if result.is_ok() {
-            return Err(format!("Expected Err but got Ok"));
+            return Err("Expected Err but got Ok".to_string());
         };
 
         match result.err().unwrap() {
@@ -198,14 +421,15 @@ mod tests {
         let question = question_dao
             .create_question(Question {
                 title: "title".to_owned(),
-                description: "desc".to_owned(),
+                description: Some("desc".to_owned()),
+                metadata: empty_metadata(),
             })
             .await
             .unwrap();
 
         let result = dao
             .create_answer(Answer {
-                question_uuid: question.question_uuid.clone(),
+                question_uuid: question.question_uuid,
                 content: "content".to_owned(),
             })
             .await;
@@ -220,21 +444,6 @@ mod tests {
         }
     }
 
-    #[sqlx::test]
-    async fn delete_answer_should_fail_with_malformed_uuid(pool: PgPool) -> Result<(), String> {
-        let dao = AnswerDaoImpl::new(pool);
-        let result = dao
-            .delete_answer("invalid_uuid".to_owned())
-            .await
-            .err()
-            .unwrap();
-
-        match result {
-            DBError::InvalidUUID(_) => Ok(()),
-            err => Err(format!("Expected InvalidUUID but got: {}", err)),
-        }
-    }
-
     #[sqlx::test]
     async fn delete_answer_should_fail_if_database_error_occurs(
         pool: PgPool,
@@ -242,7 +451,11 @@ mod tests {
         let dao = AnswerDaoImpl::new(pool.clone());
         pool.close().await;
         let some_uuid = "a22abcd2-22ab-2222-a22b-2abc2a2b22cc";
-        let err = dao.delete_answer(some_uuid.to_owned()).await.err().unwrap();
+        let err = dao
+            .delete_answer(some_uuid.parse().unwrap())
+            .await
+            .err()
+            .unwrap();
 
         match err {
             DBError::Other(_) => Ok(()),
@@ -258,7 +471,8 @@ mod tests {
         let question = question_dao
             .create_question(Question {
                 title: "title".to_owned(),
-                description: "desc".to_owned(),
+                description: Some("desc".to_owned()),
+                metadata: empty_metadata(),
             })
             .await
             .unwrap();
@@ -280,18 +494,111 @@ mod tests {
     }
 
     #[sqlx::test]
-    async fn get_answers_should_fail_with_malformed_uuid(pool: PgPool) -> Result<(), String> {
-        let dao = AnswerDaoImpl::new(pool);
-        let result = dao
-            .get_answers("invalid_uuid".to_owned())
+    async fn answer_count_should_follow_answer_writes(pool: PgPool) -> Result<(), String> {
+        let question_dao = QuestionDaoImpl::new(pool.clone());
+        let dao = AnswerDaoImpl::new(pool.clone());
+
+        let question = question_dao
+            .create_question(Question {
+                title: "title".to_owned(),
+                description: Some("desc".to_owned()),
+                metadata: empty_metadata(),
+            })
             .await
-            .unwrap_err();
+            .map_err(|err| err.to_string())?;
+
+        let mut answers = vec![];
+        for _ in 0..2 {
+            let answer = dao
+                .create_answer(Answer {
+                    question_uuid: question.question_uuid,
+                    content: "content".to_owned(),
+                })
+                .await
+                .map_err(|err| err.to_string())?;
+            answers.push(answer);
+        }
+        dao.delete_answer(answers[0].answer_uuid)
+            .await
+            .map_err(|err| err.to_string())?;
 
-        if let DBError::InvalidUUID(_) = result {
-            return Ok(());
+        let questions = question_dao
+            .get_questions()
+            .await
+            .map_err(|err| err.to_string())?;
+        assert_eq!(questions[0].answer_count, 1);
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn last_activity_at_should_follow_every_write(pool: PgPool) -> Result<(), String> {
+        let question_dao = QuestionDaoImpl::new(pool.clone());
+        let dao = AnswerDaoImpl::new(pool.clone());
+        // Rewinds the question's activity, runs a write, then checks it moved again.
+        async fn moved(pool: &PgPool) -> Result<bool, String> {
+            sqlx::query_scalar("SELECT last_activity_at > '2000-01-02' FROM questions")
+                .fetch_one(pool)
+                .await
+                .map_err(|err| err.to_string())
         }
+        let rewind = || async {
+            sqlx::query("UPDATE questions SET last_activity_at = '2000-01-01'")
+                .execute(&pool)
+                .await
+                .map_err(|err| err.to_string())
+        };
+
+        let question = question_dao
+            .create_question(Question {
+                title: "title".to_owned(),
+                description: Some("desc".to_owned()),
+                metadata: empty_metadata(),
+            })
+            .await
+            .map_err(|err| err.to_string())?;
 
-        Err(format!("Expected InvalidUUID but got: {}", result))
+        rewind().await?;
+        let answer = dao
+            .create_answer(Answer {
+                question_uuid: question.question_uuid,
+                content: "content".to_owned(),
+            })
+            .await
+            .map_err(|err| err.to_string())?;
+        assert!(moved(&pool).await?, "creating an answer");
+
+        rewind().await?;
+        dao.update_answer(answer.answer_uuid, "edited".to_owned(), answer.version)
+            .await
+            .map_err(|err| err.to_string())?;
+        assert!(moved(&pool).await?, "editing an answer");
+
+        rewind().await?;
+        dao.delete_answer(answer.answer_uuid)
+            .await
+            .map_err(|err| err.to_string())?;
+        assert!(moved(&pool).await?, "deleting an answer");
+
+        rewind().await?;
+        let question = question_dao
+            .get_questions()
+            .await
+            .map_err(|err| err.to_string())?
+            .remove(0);
+        question_dao
+            .update_question(
+                question.question_uuid,
+                Question {
+                    title: "edited".to_owned(),
+                    description: question.description,
+                    metadata: question.metadata,
+                },
+                question.version,
+            )
+            .await
+            .map_err(|err| err.to_string())?;
+        assert!(moved(&pool).await?, "editing the question");
+        Ok(())
     }
 
     #[sqlx::test]
@@ -300,7 +607,10 @@ mod tests {
         pool.close().await;
 
         let some_uuid = "a22abcd2-22ab-2222-a22b-2abc2a2b22cc";
-        let err = dao.get_answers(some_uuid.to_owned()).await.unwrap_err();
+        let err = dao
+            .get_answers(some_uuid.parse().unwrap())
+            .await
+            .unwrap_err();
 
         if let DBError::Other(_) = err {
             return Ok(());
@@ -316,14 +626,15 @@ mod tests {
         let question = question_dao
             .create_question(Question {
                 title: "title".to_owned(),
-                description: "quest".to_owned(),
+                description: Some("quest".to_owned()),
+                metadata: empty_metadata(),
             })
             .await
             .unwrap();
 
         let answer1 = dao
             .create_answer(Answer {
-                question_uuid: question.question_uuid.clone(),
+                question_uuid: question.question_uuid,
                 content: "content".to_owned(),
             })
             .await
@@ -331,7 +642,7 @@ mod tests {
 
         let answer2 = dao
             .create_answer(Answer {
-                question_uuid: question.question_uuid.clone(),
+                question_uuid: question.question_uuid,
                 content: "content".to_owned(),
             })
             .await
@@ -342,4 +653,82 @@ mod tests {
         assert_eq!(result, vec![answer1, answer2]);
         Ok(())
     }
+
+    #[sqlx::test]
+    async fn get_answers_of_questions_should_only_return_theirs(
+        pool: PgPool,
+    ) -> Result<(), String> {
+        let question_dao = QuestionDaoImpl::new(pool.clone());
+        let dao = AnswerDaoImpl::new(pool);
+        let mut answers = vec![];
+        for title in ["first", "second", "third"] {
+            let question = question_dao
+                .create_question(Question {
+                    title: title.to_owned(),
+                    description: None,
+                    metadata: empty_metadata(),
+                })
+                .await
+                .unwrap();
+            let answer = dao
+                .create_answer(Answer {
+                    question_uuid: question.question_uuid,
+                    content: title.to_owned(),
+                })
+                .await
+                .unwrap();
+            answers.push(answer);
+        }
+
+        let mut result = dao
+            .get_answers_of_questions(&[answers[0].question_uuid, answers[2].question_uuid])
+            .await
+            .unwrap();
+        result.sort_by(|a, b| a.content.cmp(&b.content));
+
+        assert_eq!(result, vec![answers[0].clone(), answers[2].clone()]);
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn list_answers_should_order_and_page_in_the_query(pool: PgPool) -> Result<(), String> {
+        let question_dao = QuestionDaoImpl::new(pool.clone());
+        let dao = AnswerDaoImpl::new(pool);
+        let question = question_dao
+            .create_question(Question {
+                title: "title".to_owned(),
+                description: None,
+                metadata: empty_metadata(),
+            })
+            .await
+            .unwrap();
+        let mut answers = vec![];
+        for content in ["first", "second", "third"] {
+            let answer = dao
+                .create_answer(Answer {
+                    question_uuid: question.question_uuid,
+                    content: content.to_owned(),
+                })
+                .await
+                .unwrap();
+            answers.push(answer);
+        }
+
+        let listed = dao
+            .list_answers(
+                question.question_uuid,
+                true,
+                AnswerSort::Newest,
+                Window {
+                    offset: 0,
+                    limit: 2,
+                },
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(listed.total, 3);
+        assert_eq!(listed.items, vec![answers[2].clone(), answers[1].clone()]);
+        Ok(())
+    }
 }
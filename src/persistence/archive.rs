@@ -0,0 +1,363 @@
+use std::{sync::Arc, time::Duration};
+
+use async_stream::try_stream;
+use async_trait::async_trait;
+use futures::TryStreamExt;
+use log::{info, warn};
+use sqlx::{types::Uuid, PgPool};
+use tokio::task::JoinHandle;
+
+use super::{
+    acquire,
+    answer_dao::AnswerRow,
+    audit::set_actor,
+    invalidation::{CACHE_INVALIDATION_CHANNEL, INVALIDATE_ALL},
+    locks::{self, Lock},
+    question_dao::QuestionRow,
+    RowStream,
+};
+use crate::{
+    config::LiveConfig,
+    models::{AnswerDetail, ArchiveSummary, DBError, QuestionDetail, QuestionFilter, QuestionUuid},
+};
+
+// Questions without activity for a while, with their answers, live in separate
+// read-only tables so the hot ones stay small.
+#[async_trait]
+pub trait ArchiveDao {
+    // A question is inactive when neither it nor any of its answers changed
+    // in the last `older_than_days` days.
+    async fn archive(&self, older_than_days: u32) -> Result<ArchiveSummary, DBError>;
+    // The archived questions `filter` matches, fetched as they are consumed.
+    fn stream_archived_questions(&self, filter: QuestionFilter) -> RowStream<'_, QuestionDetail>;
+    // The archived answers of a question, fetched as they are consumed.
+    fn stream_archived_answers(&self, question_uuid: QuestionUuid) -> RowStream<'_, AnswerDetail>;
+    // Like `AnswerDao::get_answers_of_questions`.
+    async fn get_archived_answers_of_questions(
+        &self,
+        question_uuids: &[QuestionUuid],
+    ) -> Result<Vec<AnswerDetail>, DBError>;
+}
+
+pub struct PgArchiveDao {
+    db: PgPool,
+}
+
+impl PgArchiveDao {
+    pub fn new(db: PgPool) -> Self {
+        Self { db }
+    }
+
+    // None when another instance is archiving.
+    async fn move_inactive(
+        &self,
+        older_than_days: i32,
+    ) -> Result<Option<ArchiveSummary>, sqlx::Error> {
+        let mut tx = self.db.begin().await?;
+        if !locks::try_lock(&mut tx, Lock::Archive).await? {
+            return Ok(None);
+        }
+        set_actor(&mut tx).await?;
+        // One notification for the whole batch, as for bulk imports.
+        sqlx::query("SET LOCAL app.skip_cache_invalidation = 'on'")
+            .execute(&mut tx)
+            .await?;
+        // Makes the outbox triggers report the removed rows as archived, not deleted.
+        sqlx::query("SET LOCAL app.archiving = 'on'")
+            .execute(&mut tx)
+            .await?;
+
+        // Locking the questions makes concurrent answer inserts wait for this
+        // transaction and then fail their foreign key check.
+        let question_uuids = sqlx::query_scalar!(
+            r#"
+                SELECT question_uuid FROM questions
+                WHERE updated_at < CURRENT_TIMESTAMP - make_interval(days => $1)
+                AND NOT EXISTS (
+                    SELECT 1 FROM answers
+                    WHERE answers.question_uuid = questions.question_uuid
+                    AND answers.created_at >= LEAST(questions.created_at, questions.answers_since) AT TIME ZONE 'UTC'
+                    AND answers.updated_at >= LOCALTIMESTAMP - make_interval(days => $1)
+                )
+                FOR UPDATE
+            "#,
+            older_than_days,
+        )
+        .fetch_all(&mut tx)
+        .await?;
+
+        if question_uuids.is_empty() {
+            return Ok(Some(ArchiveSummary {
+                questions: 0,
+                answers: 0,
+            }));
+        }
+
+        let questions = sqlx::query!(
+            r#"
+                INSERT INTO questions_archive
+                    ( question_uuid, title, description, metadata, created_at, updated_at, last_activity_at, answer_count, version )
+                SELECT question_uuid, title, description, metadata, created_at, updated_at, last_activity_at, answer_count, version
+                FROM questions
+                WHERE question_uuid = ANY($1)
+            "#,
+            &question_uuids,
+        )
+        .execute(&mut tx)
+        .await?
+        .rows_affected();
+
+        let answers = sqlx::query!(
+            r#"
+                INSERT INTO answers_archive
+                    ( answer_uuid, question_uuid, content, created_at, updated_at, version )
+                SELECT answer_uuid, question_uuid, content, created_at, updated_at, version
+                FROM answers
+                WHERE question_uuid = ANY($1)
+                AND created_at >= (SELECT MIN(LEAST(created_at, answers_since)) AT TIME ZONE 'UTC' FROM questions WHERE question_uuid = ANY($1))
+            "#,
+            &question_uuids,
+        )
+        .execute(&mut tx)
+        .await?
+        .rows_affected();
+
+        sqlx::query!(
+            "DELETE FROM questions WHERE question_uuid = ANY($1)",
+            &question_uuids,
+        )
+        .execute(&mut tx)
+        .await?;
+
+        sqlx::query("SELECT pg_notify($1, $2)")
+            .bind(CACHE_INVALIDATION_CHANNEL)
+            .bind(INVALIDATE_ALL)
+            .execute(&mut tx)
+            .await?;
+        tx.commit().await?;
+
+        Ok(Some(ArchiveSummary { questions, answers }))
+    }
+}
+
+#[async_trait]
+impl ArchiveDao for PgArchiveDao {
+    async fn archive(&self, older_than_days: u32) -> Result<ArchiveSummary, DBError> {
+        let older_than_days =
+            i32::try_from(older_than_days).map_err(|err| DBError::Other(Box::new(err)))?;
+
+        self.move_inactive(older_than_days)
+            .await
+            .map_err(|err| DBError::Other(Box::new(err)))?
+            .ok_or_else(|| locks::lock_held(Lock::Archive))
+    }
+
+    fn stream_archived_questions(&self, filter: QuestionFilter) -> RowStream<'_, QuestionDetail> {
+        Box::pin(try_stream! {
+            let mut conn = acquire(&self.db).await?;
+
+            let mut rows = sqlx::query_as!(
+                QuestionRow,
+                r#"
+                    SELECT question_uuid, title, description, metadata, created_at, updated_at, last_activity_at, answer_count, version
+                    FROM questions_archive
+                    WHERE metadata @> $1
+                    AND ($2::TIMESTAMPTZ IS NULL OR created_at >= $2)
+                    AND ($3::TIMESTAMPTZ IS NULL OR created_at < $3)
+                "#,
+                filter.metadata.to_json(),
+                filter.created.after,
+                filter.created.before,
+            )
+            .fetch(&mut conn);
+
+            while let Some(row) = rows
+                .try_next()
+                .await
+                .map_err(|err| DBError::Other(Box::new(err)))?
+            {
+                yield row.into();
+            }
+        })
+    }
+
+    fn stream_archived_answers(&self, question_uuid: QuestionUuid) -> RowStream<'_, AnswerDetail> {
+        Box::pin(try_stream! {
+            let mut conn = acquire(&self.db).await?;
+
+            let mut rows = sqlx::query_as!(
+                AnswerRow,
+                r#"
+                    SELECT answer_uuid, question_uuid, content, created_at, updated_at, version
+                    FROM answers_archive
+                    WHERE question_uuid = $1
+                "#,
+                question_uuid.0,
+            )
+            .fetch(&mut conn);
+
+            while let Some(row) = rows
+                .try_next()
+                .await
+                .map_err(|err| DBError::Other(Box::new(err)))?
+            {
+                yield row.into();
+            }
+        })
+    }
+
+    async fn get_archived_answers_of_questions(
+        &self,
+        question_uuids: &[QuestionUuid],
+    ) -> Result<Vec<AnswerDetail>, DBError> {
+        let question_uuids: Vec<Uuid> = question_uuids.iter().map(|uuid| uuid.0).collect();
+
+        let mut conn = acquire(&self.db).await?;
+
+        let result = sqlx::query_as!(
+            AnswerRow,
+            r#"
+                SELECT answer_uuid, question_uuid, content, created_at, updated_at, version
+                FROM answers_archive
+                WHERE question_uuid = ANY($1)
+            "#,
+            &question_uuids[..],
+        )
+        .fetch_all(&mut conn)
+        .await
+        .map_err(|err| DBError::Other(Box::new(err)))?;
+
+        Ok(result.into_iter().map(AnswerDetail::from).collect())
+    }
+}
+
+// Archives periodically. Settings are read on every run, so they follow config reloads;
+// `ARCHIVE_AFTER_DAYS=0` pauses the job.
+pub fn spawn_archiver(
+    archive_dao: Arc<dyn ArchiveDao + Send + Sync>,
+    config: LiveConfig,
+) -> JoinHandle<()> {
+    tokio::spawn(async move {
+        loop {
+            let interval = config.load().archive_interval.max(Duration::from_secs(1));
+            tokio::time::sleep(interval).await;
+
+            let older_than_days = config.load().archive_after_days;
+            if older_than_days == 0 {
+                continue;
+            }
+
+            match archive_dao.archive(older_than_days).await {
+                Ok(summary) if summary.questions > 0 => info!(
+                    "Archived {} questions and {} answers.",
+                    summary.questions, summary.answers
+                ),
+                Ok(_) => {}
+                // Another instance is archiving.
+                Err(DBError::Conflict(reason)) => info!("Archiving skipped: {}", reason),
+                Err(err) => warn!("Archiving failed: {:?}", err),
+            }
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        models::{empty_metadata, Answer, Question},
+        persistence::{
+            answer_dao::{AnswerDao, AnswerDaoImpl},
+            question_dao::{QuestionDao, QuestionDaoImpl},
+        },
+    };
+
+    async fn backdate(pool: &PgPool, question_uuid: &QuestionUuid) -> Result<(), String> {
+        for table in ["questions", "answers"] {
+            sqlx::query(&format!(
+                "UPDATE {} SET updated_at = updated_at - interval '100 days' WHERE question_uuid = $1",
+                table
+            ))
+            .bind(question_uuid.0)
+            .execute(pool)
+            .await
+            .map_err(|err| err.to_string())?;
+        }
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn archive_should_move_inactive_questions_with_their_answers(
+        pool: PgPool,
+    ) -> Result<(), String> {
+        let question_dao = QuestionDaoImpl::new(pool.clone());
+        let answer_dao = AnswerDaoImpl::new(pool.clone());
+        let dao = PgArchiveDao::new(pool.clone());
+
+        let mut questions = vec![];
+        for title in ["inactive", "active"] {
+            let question = question_dao
+                .create_question(Question {
+                    title: title.to_owned(),
+                    description: Some("description".to_owned()),
+                    metadata: empty_metadata(),
+                })
+                .await
+                .map_err(|err| err.to_string())?;
+            answer_dao
+                .create_answer(Answer {
+                    question_uuid: question.question_uuid,
+                    content: "content".to_owned(),
+                })
+                .await
+                .map_err(|err| err.to_string())?;
+            questions.push(question);
+        }
+        backdate(&pool, &questions[0].question_uuid).await?;
+
+        let summary = dao.archive(30).await.map_err(|err| err.to_string())?;
+        assert_eq!(
+            summary,
+            ArchiveSummary {
+                questions: 1,
+                answers: 1
+            }
+        );
+
+        let live = question_dao
+            .get_questions()
+            .await
+            .map_err(|err| err.to_string())?;
+        assert_eq!(live.len(), 1);
+        assert_eq!(live[0].question_uuid, questions[1].question_uuid);
+
+        let archived: Vec<_> = dao
+            .stream_archived_questions(QuestionFilter::default())
+            .try_collect()
+            .await
+            .map_err(|err| err.to_string())?;
+        assert_eq!(archived.len(), 1);
+        assert_eq!(archived[0].question_uuid, questions[0].question_uuid);
+
+        let answers: Vec<_> = dao
+            .stream_archived_answers(questions[0].question_uuid)
+            .try_collect()
+            .await
+            .map_err(|err| err.to_string())?;
+        assert_eq!(answers.len(), 1);
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn archive_should_not_run_twice_at_once(pool: PgPool) -> Result<(), String> {
+        let mut other_instance = pool.begin().await.map_err(|err| err.to_string())?;
+        locks::try_lock(&mut other_instance, Lock::Archive)
+            .await
+            .map_err(|err| err.to_string())?;
+
+        match PgArchiveDao::new(pool).archive(30).await {
+            Err(DBError::Conflict(_)) => Ok(()),
+            result => Err(format!("Expected Conflict but got: {:?}", result)),
+        }
+    }
+}
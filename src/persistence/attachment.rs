@@ -0,0 +1,476 @@
+use async_trait::async_trait;
+use serde_json::Value;
+use sqlx::{types::chrono::NaiveDateTime, PgPool};
+use uuid::Uuid;
+
+use super::acquire;
+use crate::models::{
+    postgres_error_code, AnswerUuid, Attachment, AttachmentStatus, AttachmentUuid,
+    AttachmentVariant, DBError, Entity, NewAttachment, QuestionUuid,
+};
+
+// The rows describing attachments; their bytes are in an `ObjectStore`. Listings only
+// return ready attachments, oldest first.
+#[async_trait]
+pub trait AttachmentDao {
+    // Fails with `DBError::NotFound` for `Entity::Question` if the question does not
+    // exist, and for `Entity::Answer` if `answer_uuid` is not one of its answers.
+    async fn create_attachment(&self, attachment: NewAttachment) -> Result<Attachment, DBError>;
+    // None for unknown attachments.
+    async fn get_attachment(
+        &self,
+        attachment_uuid: AttachmentUuid,
+    ) -> Result<Option<Attachment>, DBError>;
+    // Records the uploaded size of a pending attachment and moves it to `status`, ready
+    // or scanning. Fails with `DBError::NotFound` for `Entity::Attachment` if no pending
+    // one has the uuid.
+    async fn mark_uploaded(
+        &self,
+        attachment_uuid: AttachmentUuid,
+        size_bytes: i64,
+        status: AttachmentStatus,
+    ) -> Result<Attachment, DBError>;
+    // Moves a scanning attachment to `status`, ready or quarantined. None if it is not
+    // scanning, e.g. because it went with its question meanwhile.
+    async fn finish_scan(
+        &self,
+        attachment_uuid: AttachmentUuid,
+        status: AttachmentStatus,
+    ) -> Result<Option<Attachment>, DBError>;
+    // Attachments waiting for the content scanner, oldest first.
+    async fn scanning_attachments(&self, limit: u32) -> Result<Vec<Attachment>, DBError>;
+    // Succeeds for unknown attachments.
+    async fn delete_attachment(&self, attachment_uuid: AttachmentUuid) -> Result<(), DBError>;
+    // Replaces the variants of an attachment. Succeeds for unknown attachments, which
+    // may have gone with their question while the variants were made.
+    async fn set_variants(
+        &self,
+        attachment_uuid: AttachmentUuid,
+        variants: Vec<AttachmentVariant>,
+    ) -> Result<(), DBError>;
+    // Attachments of the questions themselves, not of their answers.
+    async fn attachments_of_questions(
+        &self,
+        question_uuids: &[QuestionUuid],
+    ) -> Result<Vec<Attachment>, DBError>;
+    async fn attachments_of_answers(
+        &self,
+        answer_uuids: &[AnswerUuid],
+    ) -> Result<Vec<Attachment>, DBError>;
+}
+
+struct AttachmentRow {
+    attachment_uuid: Uuid,
+    question_uuid: Uuid,
+    answer_uuid: Option<Uuid>,
+    filename: String,
+    content_type: String,
+    size_bytes: i64,
+    storage_key: String,
+    status: String,
+    variants: Value,
+    created_at: NaiveDateTime,
+}
+
+impl TryFrom<AttachmentRow> for Attachment {
+    type Error = DBError;
+
+    fn try_from(row: AttachmentRow) -> Result<Self, Self::Error> {
+        Ok(Attachment {
+            attachment_uuid: AttachmentUuid(row.attachment_uuid),
+            question_uuid: QuestionUuid(row.question_uuid),
+            answer_uuid: row.answer_uuid.map(AnswerUuid),
+            filename: row.filename,
+            content_type: row.content_type,
+            size_bytes: row.size_bytes,
+            storage_key: row.storage_key,
+            status: row
+                .status
+                .parse()
+                .map_err(|err: String| DBError::Other(err.into()))?,
+            variants: serde_json::from_value(row.variants)
+                .map_err(|err| DBError::Other(Box::new(err)))?,
+            created_at: row.created_at.and_utc(),
+        })
+    }
+}
+
+fn attachments(rows: Vec<AttachmentRow>) -> Result<Vec<Attachment>, DBError> {
+    rows.into_iter().map(Attachment::try_from).collect()
+}
+
+pub struct PgAttachmentDao {
+    db: PgPool,
+}
+
+impl PgAttachmentDao {
+    pub fn new(db: PgPool) -> Self {
+        Self { db }
+    }
+}
+
+#[async_trait]
+impl AttachmentDao for PgAttachmentDao {
+    async fn create_attachment(&self, attachment: NewAttachment) -> Result<Attachment, DBError> {
+        let mut conn = acquire(&self.db).await?;
+
+        if let Some(answer_uuid) = attachment.answer_uuid {
+            let answered = sqlx::query_scalar!(
+                r#"
+                    SELECT EXISTS (
+                        SELECT 1 FROM answers WHERE answer_uuid = $1 AND question_uuid = $2
+                    ) AS "answered!"
+                "#,
+                answer_uuid.0,
+                attachment.question_uuid.0,
+            )
+            .fetch_one(&mut conn)
+            .await
+            .map_err(|err| DBError::Other(Box::new(err)))?;
+            if !answered {
+                return Err(DBError::NotFound(
+                    Entity::Answer,
+                    format!(
+                        "Answer {} of question {} does not exist",
+                        answer_uuid.0, attachment.question_uuid.0
+                    ),
+                ));
+            }
+        }
+
+        let row = sqlx::query_as!(
+            AttachmentRow,
+            r#"
+                INSERT INTO attachments
+                    ( attachment_uuid, question_uuid, answer_uuid, filename, content_type, size_bytes, storage_key, status )
+                VALUES ( $1, $2, $3, $4, $5, $6, $7, $8 )
+                RETURNING *
+            "#,
+            attachment.attachment_uuid.0,
+            attachment.question_uuid.0,
+            attachment.answer_uuid.map(|answer_uuid| answer_uuid.0),
+            attachment.filename,
+            attachment.content_type,
+            attachment.size_bytes,
+            attachment.storage_key,
+            attachment.status.as_str(),
+        )
+        .fetch_one(&mut conn)
+        .await
+        .map_err(|err: sqlx::Error| match err {
+            sqlx::Error::Database(err)
+                if err
+                    .code()
+                    .is_some_and(|code| code == postgres_error_code::FOREIGN_KEY_VIOLATION) =>
+            {
+                DBError::NotFound(
+                    Entity::Question,
+                    format!("Question {} does not exist", attachment.question_uuid.0),
+                )
+            }
+            err => DBError::Other(Box::new(err)),
+        })?;
+
+        row.try_into()
+    }
+
+    async fn get_attachment(
+        &self,
+        attachment_uuid: AttachmentUuid,
+    ) -> Result<Option<Attachment>, DBError> {
+        let mut conn = acquire(&self.db).await?;
+
+        let row = sqlx::query_as!(
+            AttachmentRow,
+            "SELECT * FROM attachments WHERE attachment_uuid = $1",
+            attachment_uuid.0,
+        )
+        .fetch_optional(&mut conn)
+        .await
+        .map_err(|err| DBError::Other(Box::new(err)))?;
+
+        row.map(Attachment::try_from).transpose()
+    }
+
+    async fn mark_uploaded(
+        &self,
+        attachment_uuid: AttachmentUuid,
+        size_bytes: i64,
+        status: AttachmentStatus,
+    ) -> Result<Attachment, DBError> {
+        let mut conn = acquire(&self.db).await?;
+
+        let row = sqlx::query_as!(
+            AttachmentRow,
+            r#"
+                UPDATE attachments SET status = $3, size_bytes = $2
+                WHERE attachment_uuid = $1 AND status = 'pending'
+                RETURNING *
+            "#,
+            attachment_uuid.0,
+            size_bytes,
+            status.as_str(),
+        )
+        .fetch_optional(&mut conn)
+        .await
+        .map_err(|err| DBError::Other(Box::new(err)))?;
+
+        match row {
+            Some(row) => row.try_into(),
+            None => Err(DBError::NotFound(
+                Entity::Attachment,
+                format!("No upload of attachment {} is pending", attachment_uuid.0),
+            )),
+        }
+    }
+
+    async fn finish_scan(
+        &self,
+        attachment_uuid: AttachmentUuid,
+        status: AttachmentStatus,
+    ) -> Result<Option<Attachment>, DBError> {
+        let mut conn = acquire(&self.db).await?;
+
+        let row = sqlx::query_as!(
+            AttachmentRow,
+            r#"
+                UPDATE attachments SET status = $2
+                WHERE attachment_uuid = $1 AND status = 'scanning'
+                RETURNING *
+            "#,
+            attachment_uuid.0,
+            status.as_str(),
+        )
+        .fetch_optional(&mut conn)
+        .await
+        .map_err(|err| DBError::Other(Box::new(err)))?;
+
+        row.map(Attachment::try_from).transpose()
+    }
+
+    async fn scanning_attachments(&self, limit: u32) -> Result<Vec<Attachment>, DBError> {
+        let mut conn = acquire(&self.db).await?;
+
+        let rows = sqlx::query_as!(
+            AttachmentRow,
+            r#"
+                SELECT * FROM attachments WHERE status = 'scanning'
+                ORDER BY created_at, attachment_uuid
+                LIMIT $1
+            "#,
+            i64::from(limit),
+        )
+        .fetch_all(&mut conn)
+        .await
+        .map_err(|err| DBError::Other(Box::new(err)))?;
+
+        attachments(rows)
+    }
+
+    async fn delete_attachment(&self, attachment_uuid: AttachmentUuid) -> Result<(), DBError> {
+        let mut conn = acquire(&self.db).await?;
+
+        sqlx::query!(
+            "DELETE FROM attachments WHERE attachment_uuid = $1",
+            attachment_uuid.0,
+        )
+        .execute(&mut conn)
+        .await
+        .map_err(|err| DBError::Other(Box::new(err)))?;
+
+        Ok(())
+    }
+
+    async fn set_variants(
+        &self,
+        attachment_uuid: AttachmentUuid,
+        variants: Vec<AttachmentVariant>,
+    ) -> Result<(), DBError> {
+        let mut conn = acquire(&self.db).await?;
+        let variants =
+            serde_json::to_value(variants).map_err(|err| DBError::Other(Box::new(err)))?;
+
+        sqlx::query!(
+            "UPDATE attachments SET variants = $2 WHERE attachment_uuid = $1",
+            attachment_uuid.0,
+            variants,
+        )
+        .execute(&mut conn)
+        .await
+        .map_err(|err| DBError::Other(Box::new(err)))?;
+
+        Ok(())
+    }
+
+    async fn attachments_of_questions(
+        &self,
+        question_uuids: &[QuestionUuid],
+    ) -> Result<Vec<Attachment>, DBError> {
+        let mut conn = acquire(&self.db).await?;
+        let question_uuids: Vec<Uuid> = question_uuids.iter().map(|uuid| uuid.0).collect();
+
+        let rows = sqlx::query_as!(
+            AttachmentRow,
+            r#"
+                SELECT * FROM attachments
+                WHERE question_uuid = ANY($1) AND answer_uuid IS NULL AND status = 'ready'
+                ORDER BY created_at, attachment_uuid
+            "#,
+            &question_uuids,
+        )
+        .fetch_all(&mut conn)
+        .await
+        .map_err(|err| DBError::Other(Box::new(err)))?;
+
+        attachments(rows)
+    }
+
+    async fn attachments_of_answers(
+        &self,
+        answer_uuids: &[AnswerUuid],
+    ) -> Result<Vec<Attachment>, DBError> {
+        let mut conn = acquire(&self.db).await?;
+        let answer_uuids: Vec<Uuid> = answer_uuids.iter().map(|uuid| uuid.0).collect();
+
+        let rows = sqlx::query_as!(
+            AttachmentRow,
+            r#"
+                SELECT * FROM attachments
+                WHERE answer_uuid = ANY($1) AND status = 'ready'
+                ORDER BY created_at, attachment_uuid
+            "#,
+            &answer_uuids,
+        )
+        .fetch_all(&mut conn)
+        .await
+        .map_err(|err| DBError::Other(Box::new(err)))?;
+
+        attachments(rows)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        models::{empty_metadata, Answer, Question},
+        persistence::{
+            answer_dao::{AnswerDao, AnswerDaoImpl},
+            question_dao::{QuestionDao, QuestionDaoImpl},
+        },
+    };
+
+    #[sqlx::test]
+    async fn attachments_should_be_listed_once_ready(pool: PgPool) -> Result<(), String> {
+        let question = QuestionDaoImpl::new(pool.clone())
+            .create_question(Question {
+                title: "title".to_owned(),
+                description: None,
+                metadata: empty_metadata(),
+            })
+            .await
+            .unwrap();
+        let answer = AnswerDaoImpl::new(pool.clone())
+            .create_answer(Answer {
+                question_uuid: question.question_uuid,
+                content: "content".to_owned(),
+            })
+            .await
+            .unwrap();
+        let dao = PgAttachmentDao::new(pool);
+        let new_attachment = |answer_uuid: Option<AnswerUuid>, status| {
+            let attachment_uuid = AttachmentUuid::new_v4();
+            NewAttachment {
+                attachment_uuid,
+                question_uuid: question.question_uuid,
+                answer_uuid,
+                filename: "photo.png".to_owned(),
+                content_type: "image/png".to_owned(),
+                size_bytes: 0,
+                storage_key: format!("attachments/{}", attachment_uuid),
+                status,
+            }
+        };
+
+        let on_question = dao
+            .create_attachment(new_attachment(None, AttachmentStatus::Ready))
+            .await
+            .unwrap();
+        let pending = dao
+            .create_attachment(new_attachment(
+                Some(answer.answer_uuid),
+                AttachmentStatus::Pending,
+            ))
+            .await
+            .unwrap();
+        let result = dao
+            .create_attachment(new_attachment(
+                Some(AnswerUuid::new_v4()),
+                AttachmentStatus::Ready,
+            ))
+            .await;
+        assert!(matches!(result, Err(DBError::NotFound(Entity::Answer, _))));
+
+        assert_eq!(
+            dao.attachments_of_questions(&[question.question_uuid])
+                .await
+                .unwrap(),
+            [on_question]
+        );
+        assert!(dao
+            .attachments_of_answers(&[answer.answer_uuid])
+            .await
+            .unwrap()
+            .is_empty());
+
+        let ready = dao
+            .mark_uploaded(pending.attachment_uuid, 42, AttachmentStatus::Ready)
+            .await
+            .unwrap();
+        assert_eq!(ready.status, AttachmentStatus::Ready);
+        assert_eq!(ready.size_bytes, 42);
+        assert_eq!(
+            dao.attachments_of_answers(&[answer.answer_uuid])
+                .await
+                .unwrap(),
+            [ready]
+        );
+        assert!(matches!(
+            dao.mark_uploaded(pending.attachment_uuid, 42, AttachmentStatus::Ready)
+                .await,
+            Err(DBError::NotFound(Entity::Attachment, _))
+        ));
+
+        let scanning = dao
+            .create_attachment(new_attachment(None, AttachmentStatus::Scanning))
+            .await
+            .unwrap();
+        assert_eq!(
+            dao.scanning_attachments(10).await.unwrap(),
+            std::slice::from_ref(&scanning)
+        );
+        let quarantined = dao
+            .finish_scan(scanning.attachment_uuid, AttachmentStatus::Quarantined)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(quarantined.status, AttachmentStatus::Quarantined);
+        assert_eq!(
+            dao.finish_scan(scanning.attachment_uuid, AttachmentStatus::Ready)
+                .await
+                .unwrap(),
+            None,
+            "only scanning attachments are moved"
+        );
+        assert_eq!(
+            dao.attachments_of_questions(&[question.question_uuid])
+                .await
+                .unwrap()
+                .len(),
+            1,
+            "quarantined attachments are not listed"
+        );
+
+        Ok(())
+    }
+}
@@ -0,0 +1,225 @@
+use async_trait::async_trait;
+use sqlx::{pool::PoolConnection, types::Uuid, PgConnection, PgPool, Postgres};
+
+use super::acquire;
+use crate::{
+    actor::Actor,
+    models::{AuditEntry, DBError},
+};
+
+pub const AUDIT_MAX_LIMIT: u32 = 500;
+
+// Narrows the trail; unset fields match every entry.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct AuditFilter {
+    pub entity_uuid: Option<String>,
+    pub actor: Option<String>,
+    // Only entries older than this id, to page through the trail.
+    pub before_id: Option<i64>,
+    pub limit: u32,
+}
+
+// Reads the `audit_log` the triggers of the audit_log migration write. Postgres only.
+#[async_trait]
+pub trait AuditDao {
+    // Newest first.
+    async fn get_entries(&self, filter: AuditFilter) -> Result<Vec<AuditEntry>, DBError>;
+}
+
+// Tells the audit triggers who makes the writes that follow on `conn`. The setting
+// lives as long as the connection, so every write sets it again before running.
+pub async fn set_actor(conn: &mut PgConnection) -> Result<(), sqlx::Error> {
+    let actor = Actor::current();
+
+    sqlx::query!(
+        r#"
+            SELECT
+                set_config('app.actor', $1, false) AS actor,
+                set_config('app.request_id', $2, false) AS request_id
+        "#,
+        actor.name,
+        actor.request_id.unwrap_or_default(),
+    )
+    .fetch_one(conn)
+    .await?;
+
+    Ok(())
+}
+
+// A connection for writes, with the current actor set.
+pub async fn acquire_as_actor(pool: &PgPool) -> Result<PoolConnection<Postgres>, DBError> {
+    let mut conn = acquire(pool).await?;
+    set_actor(&mut conn)
+        .await
+        .map_err(|err| DBError::Other(Box::new(err)))?;
+
+    Ok(conn)
+}
+
+pub struct PgAuditDao {
+    db: PgPool,
+}
+
+impl PgAuditDao {
+    pub fn new(db: PgPool) -> Self {
+        Self { db }
+    }
+}
+
+#[async_trait]
+impl AuditDao for PgAuditDao {
+    async fn get_entries(&self, filter: AuditFilter) -> Result<Vec<AuditEntry>, DBError> {
+        let entity_uuid = filter
+            .entity_uuid
+            .map(|uuid| Uuid::parse_str(&uuid))
+            .transpose()
+            .map_err(|error| DBError::InvalidUUID(error.to_string()))?;
+
+        let mut conn = acquire(&self.db).await?;
+
+        let rows = sqlx::query!(
+            r#"
+                SELECT
+                    id, entity, entity_uuid, action, actor, request_id,
+                    before::TEXT AS before, after::TEXT AS after, created_at
+                FROM audit_log
+                WHERE ($1::UUID IS NULL OR entity_uuid = $1)
+                AND ($2::TEXT IS NULL OR actor = $2)
+                AND ($3::BIGINT IS NULL OR id < $3)
+                ORDER BY id DESC
+                LIMIT $4
+            "#,
+            entity_uuid,
+            filter.actor,
+            filter.before_id,
+            i64::from(filter.limit.clamp(1, AUDIT_MAX_LIMIT)),
+        )
+        .fetch_all(&mut conn)
+        .await
+        .map_err(|err| DBError::Other(Box::new(err)))?;
+
+        rows.into_iter()
+            .map(|row| {
+                Ok(AuditEntry {
+                    id: row.id,
+                    entity: row.entity,
+                    entity_uuid: row.entity_uuid.to_string(),
+                    action: row.action,
+                    actor: row.actor,
+                    request_id: row.request_id,
+                    before: parse_snapshot(row.before)?,
+                    after: parse_snapshot(row.after)?,
+                    created_at: row.created_at.and_utc(),
+                })
+            })
+            .collect()
+    }
+}
+
+fn parse_snapshot(snapshot: Option<String>) -> Result<Option<serde_json::Value>, DBError> {
+    snapshot
+        .map(|snapshot| serde_json::from_str(&snapshot))
+        .transpose()
+        .map_err(|err| DBError::Other(Box::new(err)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        models::{empty_metadata, Question},
+        persistence::question_dao::{QuestionDao, QuestionDaoImpl},
+    };
+
+    #[sqlx::test]
+    async fn trail_should_record_changes_with_their_actor(pool: PgPool) -> Result<(), String> {
+        let dao = QuestionDaoImpl::new(pool.clone());
+        let actor = Actor {
+            name: "admin".to_owned(),
+            request_id: Some("request".to_owned()),
+        };
+
+        let question = actor
+            .scope(async {
+                let question = dao
+                    .create_question(Question {
+                        title: "title".to_owned(),
+                        description: Some("description".to_owned()),
+                        metadata: empty_metadata(),
+                    })
+                    .await?;
+                dao.update_question(
+                    question.question_uuid,
+                    Question {
+                        title: "edited".to_owned(),
+                        description: Some("description".to_owned()),
+                        metadata: empty_metadata(),
+                    },
+                    question.version,
+                )
+                .await
+            })
+            .await
+            .map_err(|err| err.to_string())?;
+        dao.delete_question(question.question_uuid)
+            .await
+            .map_err(|err| err.to_string())?;
+
+        let audit_dao = PgAuditDao::new(pool);
+        let entries = audit_dao
+            .get_entries(AuditFilter {
+                entity_uuid: Some(question.question_uuid.to_string()),
+                limit: 10,
+                ..Default::default()
+            })
+            .await
+            .map_err(|err| err.to_string())?;
+
+        let actions: Vec<_> = entries
+            .iter()
+            .map(|entry| (entry.action.as_str(), entry.actor.as_str()))
+            .collect();
+        assert_eq!(
+            actions,
+            [
+                ("deleted", "system"),
+                ("updated", "admin"),
+                ("created", "admin")
+            ]
+        );
+        let updated = &entries[1];
+        assert_eq!(updated.request_id.as_deref(), Some("request"));
+        assert_eq!(updated.before.as_ref().unwrap()["title"], "title");
+        assert_eq!(updated.after.as_ref().unwrap()["title"], "edited");
+        assert!(entries[0].after.is_none());
+
+        let by_admin = audit_dao
+            .get_entries(AuditFilter {
+                actor: Some("admin".to_owned()),
+                limit: 1,
+                ..Default::default()
+            })
+            .await
+            .map_err(|err| err.to_string())?;
+        assert_eq!(by_admin.len(), 1);
+        assert_eq!(by_admin[0].action, "updated");
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn trail_should_be_append_only(pool: PgPool) -> Result<(), String> {
+        QuestionDaoImpl::new(pool.clone())
+            .create_question(Question {
+                title: "title".to_owned(),
+                description: Some("description".to_owned()),
+                metadata: empty_metadata(),
+            })
+            .await
+            .map_err(|err| err.to_string())?;
+
+        let result = sqlx::query("DELETE FROM audit_log").execute(&pool).await;
+
+        assert!(result.is_err());
+        Ok(())
+    }
+}
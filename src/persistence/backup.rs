@@ -0,0 +1,448 @@
+use std::{
+    fs::{self, File},
+    io::{self, BufRead, BufReader, BufWriter, Write},
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use flate2::{read::GzDecoder, write::GzEncoder, Compression};
+use futures::TryStreamExt;
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+use sqlx::{PgConnection, PgPool};
+use thiserror::Error;
+use tokio::task::JoinHandle;
+
+use super::{
+    audit::set_actor,
+    invalidation::{CACHE_INVALIDATION_CHANNEL, INVALIDATE_ALL},
+    locks::{self, Lock},
+};
+use crate::config::AppConfig;
+
+const FORMAT: &str = "question-answer-backup";
+const VERSION: u32 = 1;
+const FILE_PREFIX: &str = "qa-backup-";
+const FILE_SUFFIX: &str = ".ndjson.gz";
+
+// Tables in the order they are written and restored, with the order of their rows.
+// Answers come oldest first, so the first one tells which partitions are needed.
+// The outbox is left out: its events were either relayed already or are replayed as
+// the restored rows are inserted. So are cached translations, which are made again on
+// demand, and link previews, which are fetched again when their question is edited.
+const TABLES: [(&str, &str); 16] = [
+    ("audit_log", "id"),
+    ("questions", "created_at"),
+    ("answers", "created_at"),
+    ("questions_archive", "created_at"),
+    ("answers_archive", "created_at"),
+    ("question_subscriptions", "created_at"),
+    ("email_opt_outs", "created_at"),
+    ("digest_preferences", "email"),
+    ("push_subscriptions", "created_at"),
+    ("push_subscription_questions", "endpoint"),
+    ("attachments", "created_at"),
+    ("question_translations", "created_at"),
+    ("moderation_queue", "created_at"),
+    ("ingested_questions", "created_at"),
+    ("filtered_words", "created_at"),
+    ("rest_hooks", "created_at"),
+];
+
+#[derive(Error, Debug)]
+pub enum BackupError {
+    #[error("Database error: {0}")]
+    Database(#[from] sqlx::Error),
+    #[error("File error: {0}")]
+    Io(#[from] io::Error),
+    #[error("Line {line}: {reason}")]
+    Invalid { line: usize, reason: String },
+    #[error("The database is not empty; restore into a new one")]
+    NotEmpty,
+    #[error("Another backup is running")]
+    Busy,
+}
+
+// First line of every backup.
+#[derive(Serialize, Deserialize)]
+struct Header {
+    format: String,
+    version: u32,
+}
+
+#[derive(Deserialize)]
+struct Line {
+    table: String,
+    row: serde_json::Value,
+}
+
+// Rows written or restored per table, in `TABLES` order.
+#[derive(Debug, Default, PartialEq)]
+pub struct BackupSummary {
+    pub tables: Vec<(&'static str, u64)>,
+}
+
+impl std::fmt::Display for BackupSummary {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let tables: Vec<_> = self
+            .tables
+            .iter()
+            .map(|(table, rows)| format!("{} {}", rows, table))
+            .collect();
+        f.write_str(&tables.join(", "))
+    }
+}
+
+pub fn file_name(at: chrono::DateTime<chrono::Utc>) -> String {
+    format!(
+        "{}{}{}",
+        FILE_PREFIX,
+        at.format("%Y%m%dT%H%M%SZ"),
+        FILE_SUFFIX
+    )
+}
+
+// Writes every table to `path` as gzipped NDJSON, one `{"table", "row"}` object per
+// line, from a single snapshot. Rows are streamed from the database, so memory does
+// not grow with its size. The file only appears once it is complete.
+pub async fn backup(pool: &PgPool, path: &Path) -> Result<BackupSummary, BackupError> {
+    let mut tx = pool.begin().await?;
+    sqlx::query("SET TRANSACTION ISOLATION LEVEL REPEATABLE READ, READ ONLY")
+        .execute(&mut tx)
+        .await?;
+    if !locks::try_lock(&mut tx, Lock::Backup).await? {
+        return Err(BackupError::Busy);
+    }
+
+    let partial = partial_path(path);
+    let result = write_tables(&mut tx, &partial).await;
+    if result.is_err() {
+        let _ = fs::remove_file(&partial);
+    }
+    let summary = result?;
+    fs::rename(&partial, path)?;
+
+    Ok(summary)
+}
+
+async fn write_tables(conn: &mut PgConnection, path: &Path) -> Result<BackupSummary, BackupError> {
+    let mut out = GzEncoder::new(BufWriter::new(File::create(path)?), Compression::default());
+    let header = Header {
+        format: FORMAT.to_owned(),
+        version: VERSION,
+    };
+    writeln!(out, "{}", serde_json::to_string(&header).unwrap())?;
+
+    let mut summary = BackupSummary::default();
+    for (table, order) in TABLES {
+        let query = format!(
+            "SELECT to_jsonb(t)::TEXT FROM {} t ORDER BY {}",
+            table, order
+        );
+        let mut rows = sqlx::query_scalar::<_, String>(&query).fetch(&mut *conn);
+
+        let mut count = 0;
+        while let Some(row) = rows.try_next().await? {
+            writeln!(out, r#"{{"table":"{}","row":{}}}"#, table, row)?;
+            count += 1;
+        }
+        summary.tables.push((table, count));
+    }
+
+    out.finish()?.flush()?;
+    Ok(summary)
+}
+
+// Loads a backup into a database with no questions, archived questions or audit log,
+// in one transaction. UUIDs, timestamps and versions are kept; answer counts are
+// rebuilt by the triggers as the answers are inserted.
+pub async fn restore(pool: &PgPool, path: &Path) -> Result<BackupSummary, BackupError> {
+    let lines = BufReader::new(GzDecoder::new(File::open(path)?)).lines();
+
+    let mut tx = pool.begin().await?;
+    set_actor(&mut tx).await?;
+    // Running instances are told once, on commit, see the bulk_import migration.
+    sqlx::query("SET LOCAL app.skip_cache_invalidation = 'on'")
+        .execute(&mut tx)
+        .await?;
+
+    let empty = sqlx::query_scalar!(
+        r#"
+            SELECT NOT EXISTS (SELECT 1 FROM questions)
+                AND NOT EXISTS (SELECT 1 FROM questions_archive)
+                AND NOT EXISTS (SELECT 1 FROM audit_log) AS "empty!"
+        "#
+    )
+    .fetch_one(&mut tx)
+    .await?;
+    if !empty {
+        return Err(BackupError::NotEmpty);
+    }
+
+    let mut summary = BackupSummary {
+        tables: TABLES.iter().map(|(table, _)| (*table, 0)).collect(),
+    };
+    let mut header_seen = false;
+    let mut current = 0;
+    for (index, line) in lines.enumerate() {
+        let number = index + 1;
+        let line = line?;
+        let invalid = |reason: String| BackupError::Invalid {
+            line: number,
+            reason,
+        };
+
+        if number == 1 {
+            let header: Header = serde_json::from_str(&line)
+                .map_err(|_| invalid("not a backup of this API".to_owned()))?;
+            if header.format != FORMAT || header.version != VERSION {
+                return Err(invalid(format!(
+                    "unsupported backup {} version {}",
+                    header.format, header.version
+                )));
+            }
+            header_seen = true;
+            continue;
+        }
+
+        let line: Line = serde_json::from_str(&line).map_err(|err| invalid(err.to_string()))?;
+        let position = TABLES
+            .iter()
+            .position(|(table, _)| *table == line.table)
+            .ok_or_else(|| invalid(format!("unknown table {}", line.table)))?;
+        if position < current {
+            return Err(invalid(format!("{} rows out of order", line.table)));
+        }
+
+        if summary.tables[position].1 == 0 {
+            prepare_table(&mut tx, TABLES[position].0, &line.row)
+                .await
+                .map_err(|err| invalid(err.to_string()))?;
+        }
+        current = position;
+
+        insert_row(&mut tx, TABLES[position].0, &line.row)
+            .await
+            .map_err(|err| invalid(err.to_string()))?;
+        summary.tables[position].1 += 1;
+    }
+
+    if !header_seen {
+        return Err(BackupError::Invalid {
+            line: 1,
+            reason: "the backup is empty".to_owned(),
+        });
+    }
+
+    advance_audit_log_ids(&mut tx).await?;
+    sqlx::query("SELECT pg_notify($1, $2)")
+        .bind(CACHE_INVALIDATION_CHANNEL)
+        .bind(INVALIDATE_ALL)
+        .execute(&mut tx)
+        .await?;
+    tx.commit().await?;
+
+    Ok(summary)
+}
+
+// Runs before the first row of `table` is restored.
+async fn prepare_table(
+    conn: &mut PgConnection,
+    table: &str,
+    first_row: &serde_json::Value,
+) -> Result<(), sqlx::Error> {
+    match table {
+        // The changes the triggers are about to log must get ids after the restored ones.
+        "questions" => advance_audit_log_ids(conn).await?,
+        "answers" => {
+            sqlx::query(
+                "SELECT create_answer_partitions(($1::JSONB ->> 'created_at')::TIMESTAMP, 2)",
+            )
+            .bind(first_row.to_string())
+            .execute(conn)
+            .await?;
+        }
+        _ => {}
+    }
+
+    Ok(())
+}
+
+async fn advance_audit_log_ids(conn: &mut PgConnection) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        r#"
+            SELECT setval(pg_get_serial_sequence('audit_log', 'id'), COALESCE(MAX(id), 0) + 1, false)
+            FROM audit_log
+        "#,
+    )
+    .execute(conn)
+    .await?;
+
+    Ok(())
+}
+
+async fn insert_row(
+    conn: &mut PgConnection,
+    table: &str,
+    row: &serde_json::Value,
+) -> Result<(), sqlx::Error> {
+    // Counted again by the triggers as the answers come in.
+    let overrides = if table == "questions" {
+        r#"{"answer_count": 0}"#
+    } else {
+        "{}"
+    };
+    let query = format!(
+        "INSERT INTO {table} SELECT * FROM jsonb_populate_record(NULL::{table}, $1::JSONB || $2::JSONB)",
+        table = table
+    );
+
+    sqlx::query(&query)
+        .bind(row.to_string())
+        .bind(overrides)
+        .execute(conn)
+        .await?;
+
+    Ok(())
+}
+
+fn partial_path(path: &Path) -> PathBuf {
+    let mut partial = path.as_os_str().to_owned();
+    partial.push(".partial");
+    PathBuf::from(partial)
+}
+
+// Backs up into BACKUP_DIR every BACKUP_INTERVAL_SECS and keeps the BACKUP_KEEP most
+// recent backups. With several instances only one writes each backup.
+pub fn spawn_backup_scheduler(pool: PgPool, config: &AppConfig) -> JoinHandle<()> {
+    let interval = config.backup_interval.max(Duration::from_secs(1));
+    let dir = config.backup_dir.clone();
+    let keep = config.backup_keep;
+
+    tokio::spawn(async move {
+        loop {
+            tokio::time::sleep(interval).await;
+
+            let path = dir.join(file_name(chrono::Utc::now()));
+            let result = match fs::create_dir_all(&dir) {
+                Ok(()) => backup(&pool, &path).await,
+                Err(err) => Err(err.into()),
+            };
+            match result {
+                Ok(summary) => info!("Backed up {} to {}.", summary, path.display()),
+                Err(BackupError::Busy) => {}
+                Err(BackupError::Database(sqlx::Error::PoolClosed)) => return,
+                Err(err) => warn!("Backup failed: {}", err),
+            }
+
+            if let Err(err) = prune(&dir, keep) {
+                warn!("Deleting old backups failed: {}", err);
+            }
+        }
+    })
+}
+
+// Deletes all but the `keep` newest backups in `dir`; their names sort by time.
+fn prune(dir: &Path, keep: usize) -> Result<(), io::Error> {
+    let mut backups: Vec<_> = fs::read_dir(dir)?
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| {
+            path.file_name()
+                .and_then(|name| name.to_str())
+                .is_some_and(|name| name.starts_with(FILE_PREFIX) && name.ends_with(FILE_SUFFIX))
+        })
+        .collect();
+    backups.sort();
+
+    let excess = backups.len().saturating_sub(keep);
+    for path in &backups[..excess] {
+        fs::remove_file(path)?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+
+    use sqlx::types::Uuid;
+
+    use super::*;
+    use crate::{
+        models::{empty_metadata, Answer, Question},
+        persistence::{
+            answer_dao::{AnswerDao, AnswerDaoImpl},
+            question_dao::{QuestionDao, QuestionDaoImpl},
+        },
+    };
+
+    async fn snapshot(pool: &PgPool) -> Result<Vec<String>, sqlx::Error> {
+        sqlx::query_scalar(
+            r#"
+                SELECT to_jsonb(t)::TEXT FROM (
+                    SELECT question_uuid, title, created_at, updated_at, answer_count, version
+                    FROM questions
+                    UNION ALL
+                    SELECT answer_uuid, content, created_at, updated_at, 0, version FROM answers
+                ) t
+                ORDER BY 1
+            "#,
+        )
+        .fetch_all(pool)
+        .await
+    }
+
+    #[sqlx::test]
+    async fn restore_should_bring_back_what_backup_wrote(pool: PgPool) -> Result<(), String> {
+        let question = QuestionDaoImpl::new(pool.clone())
+            .create_question(Question {
+                title: "title".to_owned(),
+                description: Some("description".to_owned()),
+                metadata: empty_metadata(),
+            })
+            .await
+            .map_err(|err| err.to_string())?;
+        AnswerDaoImpl::new(pool.clone())
+            .create_answer(Answer {
+                question_uuid: question.question_uuid,
+                content: "content".to_owned(),
+            })
+            .await
+            .map_err(|err| err.to_string())?;
+        let before = snapshot(&pool).await.map_err(|err| err.to_string())?;
+
+        let path =
+            env::temp_dir().join(file_name(chrono::Utc::now()) + &Uuid::new_v4().to_string());
+        let written = backup(&pool, &path).await.map_err(|err| err.to_string())?;
+        assert_eq!(
+            written.tables[..3],
+            [("audit_log", 2), ("questions", 1), ("answers", 1)]
+        );
+
+        let not_empty = restore(&pool, &path).await;
+        assert!(matches!(not_empty, Err(BackupError::NotEmpty)));
+
+        // Empties the database, audit log included, as a new one would be.
+        for statement in [
+            "DELETE FROM questions",
+            "ALTER TABLE audit_log DISABLE TRIGGER audit_log_append_only",
+            "DELETE FROM audit_log",
+        ] {
+            sqlx::query(statement)
+                .execute(&pool)
+                .await
+                .map_err(|err| err.to_string())?;
+        }
+
+        let restored = restore(&pool, &path).await.map_err(|err| err.to_string())?;
+        fs::remove_file(&path).map_err(|err| err.to_string())?;
+        assert_eq!(restored, written);
+        assert_eq!(
+            snapshot(&pool).await.map_err(|err| err.to_string())?,
+            before
+        );
+        Ok(())
+    }
+}
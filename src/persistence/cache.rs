@@ -0,0 +1,476 @@
+use std::{
+    collections::HashMap,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, RwLock,
+    },
+    time::{Duration, Instant},
+};
+
+use async_trait::async_trait;
+use sqlx::types::Uuid;
+
+use super::{
+    answer_dao::AnswerDao,
+    archive::ArchiveDao,
+    import::ImportDao,
+    question_dao::QuestionDao,
+    unit_of_work::{UnitOfWork, UnitOfWorkFactory},
+    RowStream,
+};
+use crate::{
+    metrics,
+    models::{
+        Answer, AnswerDetail, AnswerSort, AnswerUuid, ArchiveSummary, DBError, ImportSummary,
+        ImportedQuestion, Listed, Question, QuestionDetail, QuestionFilter, QuestionSort,
+        QuestionUuid, Window,
+    },
+};
+
+struct Entry<T> {
+    value: T,
+    fetched_at: Instant,
+}
+
+// In-process cache for list reads. Entries expire after `ttl` as a safety net,
+// but are normally dropped as soon as a write is observed (see `invalidation`).
+pub struct QueryCache {
+    ttl: Duration,
+    // Bumped on every invalidation so reads that raced a write don't store stale rows.
+    generation: AtomicU64,
+    questions: RwLock<Option<Entry<Vec<QuestionDetail>>>>,
+    answers: RwLock<HashMap<Uuid, Entry<Vec<AnswerDetail>>>>,
+}
+
+impl QueryCache {
+    pub fn new(ttl: Duration) -> Self {
+        Self {
+            ttl,
+            generation: AtomicU64::new(0),
+            questions: RwLock::new(None),
+            answers: RwLock::new(HashMap::new()),
+        }
+    }
+
+    pub fn generation(&self) -> u64 {
+        self.generation.load(Ordering::SeqCst)
+    }
+
+    pub fn questions(&self) -> Option<Vec<QuestionDetail>> {
+        let questions = self.questions.read().unwrap();
+        let cached = questions
+            .as_ref()
+            .filter(|entry| entry.fetched_at.elapsed() < self.ttl)
+            .map(|entry| entry.value.clone());
+
+        record_lookup("questions", cached.is_some());
+        cached
+    }
+
+    pub fn store_questions(&self, generation: u64, value: Vec<QuestionDetail>) {
+        let mut questions = self.questions.write().unwrap();
+        if generation == self.generation() {
+            *questions = Some(Entry {
+                value,
+                fetched_at: Instant::now(),
+            });
+        }
+    }
+
+    pub fn answers(&self, question_uuid: &Uuid) -> Option<Vec<AnswerDetail>> {
+        let answers = self.answers.read().unwrap();
+        let cached = answers
+            .get(question_uuid)
+            .filter(|entry| entry.fetched_at.elapsed() < self.ttl)
+            .map(|entry| entry.value.clone());
+
+        record_lookup("answers", cached.is_some());
+        cached
+    }
+
+    pub fn store_answers(&self, generation: u64, question_uuid: Uuid, value: Vec<AnswerDetail>) {
+        let mut answers = self.answers.write().unwrap();
+        if generation == self.generation() {
+            answers.insert(
+                question_uuid,
+                Entry {
+                    value,
+                    fetched_at: Instant::now(),
+                },
+            );
+        }
+    }
+
+    pub fn invalidate_questions(&self) {
+        self.generation.fetch_add(1, Ordering::SeqCst);
+        *self.questions.write().unwrap() = None;
+    }
+
+    pub fn invalidate_answers(&self, question_uuid: &Uuid) {
+        self.generation.fetch_add(1, Ordering::SeqCst);
+        self.answers.write().unwrap().remove(question_uuid);
+    }
+
+    pub fn clear(&self) {
+        self.generation.fetch_add(1, Ordering::SeqCst);
+        *self.questions.write().unwrap() = None;
+        self.answers.write().unwrap().clear();
+    }
+}
+
+fn record_lookup(cache: &str, hit: bool) {
+    let result = if hit { "hit" } else { "miss" };
+    metrics::CACHE_LOOKUPS
+        .with_label_values(&[cache, result])
+        .inc();
+}
+
+pub struct CachedQuestionDao<T> {
+    inner: T,
+    cache: Arc<QueryCache>,
+}
+
+impl<T> CachedQuestionDao<T> {
+    pub fn new(inner: T, cache: Arc<QueryCache>) -> Self {
+        Self { inner, cache }
+    }
+}
+
+#[async_trait]
+impl<T: QuestionDao + Send + Sync> QuestionDao for CachedQuestionDao<T> {
+    async fn create_question(&self, question: Question) -> Result<QuestionDetail, DBError> {
+        let result = self.inner.create_question(question).await;
+        self.cache.invalidate_questions();
+        result
+    }
+
+    async fn delete_question(&self, question_uuid: QuestionUuid) -> Result<(), DBError> {
+        let result = self.inner.delete_question(question_uuid).await;
+        self.cache.invalidate_questions();
+        self.cache.invalidate_answers(&question_uuid.0);
+        result
+    }
+
+    async fn update_question(
+        &self,
+        question_uuid: QuestionUuid,
+        question: Question,
+        version: i64,
+    ) -> Result<QuestionDetail, DBError> {
+        let result = self
+            .inner
+            .update_question(question_uuid, question, version)
+            .await;
+        self.cache.invalidate_questions();
+        result
+    }
+
+    async fn get_questions(&self) -> Result<Vec<QuestionDetail>, DBError> {
+        if let Some(questions) = self.cache.questions() {
+            return Ok(questions);
+        }
+
+        let generation = self.cache.generation();
+        let questions = self.inner.get_questions().await?;
+        self.cache.store_questions(generation, questions.clone());
+
+        Ok(questions)
+    }
+
+    // Served from the cached list while there is one.
+    async fn get_question(&self, question_uuid: QuestionUuid) -> Result<QuestionDetail, DBError> {
+        let cached = self.cache.questions().and_then(|questions| {
+            questions
+                .into_iter()
+                .find(|question| question.question_uuid == question_uuid)
+        });
+        match cached {
+            Some(question) => Ok(question),
+            None => self.inner.get_question(question_uuid).await,
+        }
+    }
+
+    // Only the full list is kept; filtered ones are read through.
+    async fn get_questions_matching(
+        &self,
+        filter: &QuestionFilter,
+    ) -> Result<Vec<QuestionDetail>, DBError> {
+        self.inner.get_questions_matching(filter).await
+    }
+
+    // Streamed reads are the ones too large to keep, so they bypass the cache.
+    fn stream_questions(&self, filter: QuestionFilter) -> RowStream<'_, QuestionDetail> {
+        self.inner.stream_questions(filter)
+    }
+
+    // Pages are read through, like filtered lists.
+    async fn list_questions(
+        &self,
+        filter: &QuestionFilter,
+        include_archived: bool,
+        sort: QuestionSort,
+        window: Window,
+    ) -> Result<Listed<QuestionDetail>, DBError> {
+        self.inner
+            .list_questions(filter, include_archived, sort, window)
+            .await
+    }
+
+    async fn list_unanswered_questions(
+        &self,
+        window: Window,
+    ) -> Result<Listed<QuestionDetail>, DBError> {
+        self.inner.list_unanswered_questions(window).await
+    }
+}
+
+pub struct CachedAnswerDao<T> {
+    inner: T,
+    cache: Arc<QueryCache>,
+}
+
+impl<T> CachedAnswerDao<T> {
+    pub fn new(inner: T, cache: Arc<QueryCache>) -> Self {
+        Self { inner, cache }
+    }
+}
+
+#[async_trait]
+impl<T: AnswerDao + Send + Sync> AnswerDao for CachedAnswerDao<T> {
+    async fn create_answer(&self, answer: Answer) -> Result<AnswerDetail, DBError> {
+        let question_uuid = answer.question_uuid;
+        let result = self.inner.create_answer(answer).await;
+        // The question list carries the answer count, so it goes too.
+        self.cache.invalidate_questions();
+        self.cache.invalidate_answers(&question_uuid.0);
+        result
+    }
+
+    async fn delete_answer(&self, answer_uuid: AnswerUuid) -> Result<(), DBError> {
+        // The owning question is unknown here; the NOTIFY from the trigger
+        // narrows it down for other instances, this one just starts over.
+        let result = self.inner.delete_answer(answer_uuid).await;
+        self.cache.clear();
+        result
+    }
+
+    async fn update_answer(
+        &self,
+        answer_uuid: AnswerUuid,
+        content: String,
+        version: i64,
+    ) -> Result<AnswerDetail, DBError> {
+        let result = self
+            .inner
+            .update_answer(answer_uuid, content, version)
+            .await;
+        match &result {
+            Ok(answer) => self.cache.invalidate_answers(&answer.question_uuid.0),
+            Err(_) => self.cache.clear(),
+        }
+        result
+    }
+
+    async fn get_answers(&self, question_uuid: QuestionUuid) -> Result<Vec<AnswerDetail>, DBError> {
+        let key = question_uuid.0;
+        if let Some(answers) = self.cache.answers(&key) {
+            return Ok(answers);
+        }
+
+        let generation = self.cache.generation();
+        let answers = self.inner.get_answers(question_uuid).await?;
+        self.cache.store_answers(generation, key, answers.clone());
+
+        Ok(answers)
+    }
+
+    fn stream_answers(&self, question_uuid: QuestionUuid) -> RowStream<'_, AnswerDetail> {
+        self.inner.stream_answers(question_uuid)
+    }
+
+    async fn get_answers_of_questions(
+        &self,
+        question_uuids: &[QuestionUuid],
+    ) -> Result<Vec<AnswerDetail>, DBError> {
+        self.inner.get_answers_of_questions(question_uuids).await
+    }
+
+    async fn list_answers(
+        &self,
+        question_uuid: QuestionUuid,
+        include_archived: bool,
+        sort: AnswerSort,
+        window: Window,
+    ) -> Result<Listed<AnswerDetail>, DBError> {
+        self.inner
+            .list_answers(question_uuid, include_archived, sort, window)
+            .await
+    }
+}
+
+pub struct CachedUnitOfWorkFactory<T> {
+    inner: T,
+    cache: Arc<QueryCache>,
+}
+
+impl<T> CachedUnitOfWorkFactory<T> {
+    pub fn new(inner: T, cache: Arc<QueryCache>) -> Self {
+        Self { inner, cache }
+    }
+}
+
+#[async_trait]
+impl<T: UnitOfWorkFactory + Send + Sync> UnitOfWorkFactory for CachedUnitOfWorkFactory<T> {
+    async fn begin(&self) -> Result<Box<dyn UnitOfWork>, DBError> {
+        Ok(Box::new(CachedUnitOfWork {
+            inner: self.inner.begin().await?,
+            cache: self.cache.clone(),
+        }))
+    }
+}
+
+struct CachedUnitOfWork {
+    inner: Box<dyn UnitOfWork>,
+    cache: Arc<QueryCache>,
+}
+
+#[async_trait]
+impl UnitOfWork for CachedUnitOfWork {
+    async fn create_question(&mut self, question: Question) -> Result<QuestionDetail, DBError> {
+        self.inner.create_question(question).await
+    }
+
+    async fn create_answer(&mut self, answer: Answer) -> Result<AnswerDetail, DBError> {
+        self.inner.create_answer(answer).await
+    }
+
+    async fn commit(self: Box<Self>) -> Result<(), DBError> {
+        let result = self.inner.commit().await;
+        self.cache.clear();
+        result
+    }
+}
+
+pub struct CachedImportDao<T> {
+    inner: T,
+    cache: Arc<QueryCache>,
+}
+
+impl<T> CachedImportDao<T> {
+    pub fn new(inner: T, cache: Arc<QueryCache>) -> Self {
+        Self { inner, cache }
+    }
+}
+
+#[async_trait]
+impl<T: ImportDao + Send + Sync> ImportDao for CachedImportDao<T> {
+    async fn import(&self, questions: Vec<ImportedQuestion>) -> Result<ImportSummary, DBError> {
+        let result = self.inner.import(questions).await;
+        self.cache.clear();
+        result
+    }
+}
+
+pub struct CachedArchiveDao<T> {
+    inner: T,
+    cache: Arc<QueryCache>,
+}
+
+impl<T> CachedArchiveDao<T> {
+    pub fn new(inner: T, cache: Arc<QueryCache>) -> Self {
+        Self { inner, cache }
+    }
+}
+
+// Archived rows are never edited, so only moving rows needs to touch the cache.
+#[async_trait]
+impl<T: ArchiveDao + Send + Sync> ArchiveDao for CachedArchiveDao<T> {
+    async fn archive(&self, older_than_days: u32) -> Result<ArchiveSummary, DBError> {
+        let result = self.inner.archive(older_than_days).await;
+        self.cache.clear();
+        result
+    }
+
+    fn stream_archived_questions(&self, filter: QuestionFilter) -> RowStream<'_, QuestionDetail> {
+        self.inner.stream_archived_questions(filter)
+    }
+
+    fn stream_archived_answers(&self, question_uuid: QuestionUuid) -> RowStream<'_, AnswerDetail> {
+        self.inner.stream_archived_answers(question_uuid)
+    }
+
+    async fn get_archived_answers_of_questions(
+        &self,
+        question_uuids: &[QuestionUuid],
+    ) -> Result<Vec<AnswerDetail>, DBError> {
+        self.inner
+            .get_archived_answers_of_questions(question_uuids)
+            .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::empty_metadata;
+    use chrono::DateTime;
+
+    fn question() -> QuestionDetail {
+        QuestionDetail {
+            question_uuid: QuestionUuid(Uuid::nil()),
+            title: "title".to_owned(),
+            description: Some("description".to_owned()),
+            metadata: empty_metadata(),
+            created_at: DateTime::UNIX_EPOCH,
+            updated_at: DateTime::UNIX_EPOCH,
+            last_activity_at: DateTime::UNIX_EPOCH,
+            version: 1,
+            answer_count: 0,
+        }
+    }
+
+    #[test]
+    fn store_questions_should_be_served_until_invalidated() {
+        let cache = QueryCache::new(Duration::from_secs(60));
+
+        cache.store_questions(cache.generation(), vec![question()]);
+        assert_eq!(cache.questions(), Some(vec![question()]));
+
+        cache.invalidate_questions();
+        assert_eq!(cache.questions(), None);
+    }
+
+    #[test]
+    fn store_questions_should_skip_reads_that_raced_an_invalidation() {
+        let cache = QueryCache::new(Duration::from_secs(60));
+
+        let generation = cache.generation();
+        cache.invalidate_questions();
+        cache.store_questions(generation, vec![question()]);
+
+        assert_eq!(cache.questions(), None);
+    }
+
+    #[test]
+    fn questions_should_expire_after_ttl() {
+        let cache = QueryCache::new(Duration::ZERO);
+
+        cache.store_questions(cache.generation(), vec![question()]);
+
+        assert_eq!(cache.questions(), None);
+    }
+
+    #[test]
+    fn invalidate_answers_should_only_drop_that_question() {
+        let cache = QueryCache::new(Duration::from_secs(60));
+        let first = Uuid::new_v4();
+        let second = Uuid::new_v4();
+
+        cache.store_answers(cache.generation(), first, vec![]);
+        cache.store_answers(cache.generation(), second, vec![]);
+        cache.invalidate_answers(&first);
+
+        assert_eq!(cache.answers(&first), None);
+        assert_eq!(cache.answers(&second), Some(vec![]));
+    }
+}
@@ -0,0 +1,252 @@
+use std::{io, time::Duration};
+
+use async_stream::stream;
+use async_trait::async_trait;
+use futures::StreamExt;
+use rand::Rng;
+
+use super::{answer_dao::AnswerDao, question_dao::QuestionDao, RowStream};
+use crate::models::{
+    Answer, AnswerDetail, AnswerSort, AnswerUuid, DBError, Listed, Question, QuestionDetail,
+    QuestionFilter, QuestionSort, QuestionUuid, Window,
+};
+
+// What `ChaosDao` does to each call: waits `latency`, then fails with probability
+// `failure_rate` (0 to 1) before the call reaches the database.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ChaosConfig {
+    pub latency: Duration,
+    pub failure_rate: f64,
+}
+
+impl ChaosConfig {
+    pub fn is_enabled(&self) -> bool {
+        !self.latency.is_zero() || self.failure_rate > 0.0
+    }
+
+    async fn disrupt(&self) -> Result<(), DBError> {
+        if !self.latency.is_zero() {
+            tokio::time::sleep(self.latency).await;
+        }
+        if self.failure_rate > 0.0 && rand::thread_rng().gen_bool(self.failure_rate.min(1.0)) {
+            return Err(injected_failure());
+        }
+        Ok(())
+    }
+}
+
+// An I/O error, so retries and the circuit breaker treat it as the database going away.
+fn injected_failure() -> DBError {
+    DBError::Other(Box::new(sqlx::Error::Io(io::Error::new(
+        io::ErrorKind::ConnectionReset,
+        "failure injected by ChaosDao",
+    ))))
+}
+
+// Injects latency and failures in front of a question or answer DAO, to see retries,
+// the circuit breaker and the clients' error handling at work. Sits under the retry
+// layer, where the backend's own failures would come from.
+pub struct ChaosDao<T> {
+    inner: T,
+    config: ChaosConfig,
+}
+
+impl<T> ChaosDao<T> {
+    pub fn new(inner: T, config: ChaosConfig) -> Self {
+        Self { inner, config }
+    }
+}
+
+impl<T: Sync> ChaosDao<T> {
+    // Streams are disrupted before their first row.
+    fn stream<'a, R: Send + 'a>(&'a self, rows: RowStream<'a, R>) -> RowStream<'a, R> {
+        Box::pin(stream! {
+            if let Err(err) = self.config.disrupt().await {
+                yield Err(err);
+                return;
+            }
+
+            let mut rows = rows;
+            while let Some(row) = rows.next().await {
+                yield row;
+            }
+        })
+    }
+}
+
+#[async_trait]
+impl<T: QuestionDao + Send + Sync> QuestionDao for ChaosDao<T> {
+    async fn create_question(&self, question: Question) -> Result<QuestionDetail, DBError> {
+        self.config.disrupt().await?;
+        self.inner.create_question(question).await
+    }
+
+    async fn delete_question(&self, question_uuid: QuestionUuid) -> Result<(), DBError> {
+        self.config.disrupt().await?;
+        self.inner.delete_question(question_uuid).await
+    }
+
+    async fn update_question(
+        &self,
+        question_uuid: QuestionUuid,
+        question: Question,
+        version: i64,
+    ) -> Result<QuestionDetail, DBError> {
+        self.config.disrupt().await?;
+        self.inner
+            .update_question(question_uuid, question, version)
+            .await
+    }
+
+    async fn get_questions(&self) -> Result<Vec<QuestionDetail>, DBError> {
+        self.config.disrupt().await?;
+        self.inner.get_questions().await
+    }
+
+    async fn get_question(&self, question_uuid: QuestionUuid) -> Result<QuestionDetail, DBError> {
+        self.config.disrupt().await?;
+        self.inner.get_question(question_uuid).await
+    }
+
+    async fn get_questions_matching(
+        &self,
+        filter: &QuestionFilter,
+    ) -> Result<Vec<QuestionDetail>, DBError> {
+        self.config.disrupt().await?;
+        self.inner.get_questions_matching(filter).await
+    }
+
+    fn stream_questions(&self, filter: QuestionFilter) -> RowStream<'_, QuestionDetail> {
+        self.stream(self.inner.stream_questions(filter))
+    }
+
+    async fn list_questions(
+        &self,
+        filter: &QuestionFilter,
+        include_archived: bool,
+        sort: QuestionSort,
+        window: Window,
+    ) -> Result<Listed<QuestionDetail>, DBError> {
+        self.config.disrupt().await?;
+        self.inner
+            .list_questions(filter, include_archived, sort, window)
+            .await
+    }
+
+    async fn list_unanswered_questions(
+        &self,
+        window: Window,
+    ) -> Result<Listed<QuestionDetail>, DBError> {
+        self.config.disrupt().await?;
+        self.inner.list_unanswered_questions(window).await
+    }
+}
+
+#[async_trait]
+impl<T: AnswerDao + Send + Sync> AnswerDao for ChaosDao<T> {
+    async fn create_answer(&self, answer: Answer) -> Result<AnswerDetail, DBError> {
+        self.config.disrupt().await?;
+        self.inner.create_answer(answer).await
+    }
+
+    async fn delete_answer(&self, answer_uuid: AnswerUuid) -> Result<(), DBError> {
+        self.config.disrupt().await?;
+        self.inner.delete_answer(answer_uuid).await
+    }
+
+    async fn update_answer(
+        &self,
+        answer_uuid: AnswerUuid,
+        content: String,
+        version: i64,
+    ) -> Result<AnswerDetail, DBError> {
+        self.config.disrupt().await?;
+        self.inner
+            .update_answer(answer_uuid, content, version)
+            .await
+    }
+
+    async fn get_answers(&self, question_uuid: QuestionUuid) -> Result<Vec<AnswerDetail>, DBError> {
+        self.config.disrupt().await?;
+        self.inner.get_answers(question_uuid).await
+    }
+
+    fn stream_answers(&self, question_uuid: QuestionUuid) -> RowStream<'_, AnswerDetail> {
+        self.stream(self.inner.stream_answers(question_uuid))
+    }
+
+    async fn get_answers_of_questions(
+        &self,
+        question_uuids: &[QuestionUuid],
+    ) -> Result<Vec<AnswerDetail>, DBError> {
+        self.config.disrupt().await?;
+        self.inner.get_answers_of_questions(question_uuids).await
+    }
+
+    async fn list_answers(
+        &self,
+        question_uuid: QuestionUuid,
+        include_archived: bool,
+        sort: AnswerSort,
+        window: Window,
+    ) -> Result<Listed<AnswerDetail>, DBError> {
+        self.config.disrupt().await?;
+        self.inner
+            .list_answers(question_uuid, include_archived, sort, window)
+            .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::*;
+    use crate::persistence::{
+        circuit_breaker::{CircuitBreaker, CircuitBreakerQuestionDao},
+        memory::{InMemoryQuestionDao, MemoryStore},
+        retry::{is_transient, RetryPolicy, RetryQuestionDao},
+    };
+
+    fn chaos(failure_rate: f64) -> ChaosDao<InMemoryQuestionDao> {
+        ChaosDao::new(
+            InMemoryQuestionDao::new(MemoryStore::new()),
+            ChaosConfig {
+                latency: Duration::ZERO,
+                failure_rate,
+            },
+        )
+    }
+
+    #[tokio::test]
+    async fn injected_failures_should_look_transient() {
+        let err = chaos(1.0).get_questions().await.unwrap_err();
+
+        assert!(is_transient(&err));
+    }
+
+    #[tokio::test]
+    async fn breaker_should_open_under_injected_failures() {
+        let breaker = Arc::new(CircuitBreaker::new(2, Duration::from_secs(60)));
+        let policy = RetryPolicy {
+            max_attempts: 2,
+            base_delay: Duration::ZERO,
+            max_delay: Duration::ZERO,
+        };
+        let dao =
+            CircuitBreakerQuestionDao::new(RetryQuestionDao::new(chaos(1.0), policy), breaker);
+
+        for _ in 0..2 {
+            assert!(matches!(dao.get_questions().await, Err(DBError::Other(_))));
+        }
+        assert!(matches!(
+            dao.get_questions().await,
+            Err(DBError::Unavailable)
+        ));
+    }
+
+    #[tokio::test]
+    async fn calls_should_pass_through_without_failures() {
+        assert!(chaos(0.0).get_questions().await.unwrap().is_empty());
+    }
+}
@@ -0,0 +1,394 @@
+use std::{
+    future::Future,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use async_stream::stream;
+use async_trait::async_trait;
+use futures::StreamExt;
+use log::{info, warn};
+
+use super::{answer_dao::AnswerDao, question_dao::QuestionDao, RowStream};
+use crate::{
+    metrics,
+    models::{
+        Answer, AnswerDetail, AnswerSort, AnswerUuid, DBError, Listed, Question, QuestionDetail,
+        QuestionFilter, QuestionSort, QuestionUuid, Window,
+    },
+};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum State {
+    Closed { failures: u32 },
+    Open { since: Instant },
+    // A single trial call is in flight; everyone else keeps failing fast.
+    HalfOpen { since: Instant },
+}
+
+pub struct CircuitBreaker {
+    state: Mutex<State>,
+    failure_threshold: u32,
+    open_for: Duration,
+    call_timeout: Option<Duration>,
+}
+
+impl CircuitBreaker {
+    pub fn new(failure_threshold: u32, open_for: Duration) -> Self {
+        Self {
+            state: Mutex::new(State::Closed { failures: 0 }),
+            failure_threshold: failure_threshold.max(1),
+            open_for,
+            call_timeout: None,
+        }
+    }
+
+    // A call still waiting when the timeout fires is given up on and counted as a failure, so a
+    // database that hangs opens the breaker instead of holding every request to its deadline.
+    pub fn with_call_timeout(mut self, call_timeout: Duration) -> Self {
+        self.call_timeout = Some(call_timeout);
+        self
+    }
+
+    pub async fn call<T, F>(&self, operation: F) -> Result<T, DBError>
+    where
+        F: Future<Output = Result<T, DBError>>,
+    {
+        self.try_acquire()?;
+
+        let result = self.bounded(operation).await;
+        self.record(&result);
+
+        result
+    }
+
+    // Only the start of a stream is judged: its first row, error or end.
+    pub fn call_stream<'a, T: Send + 'a>(&'a self, rows: RowStream<'a, T>) -> RowStream<'a, T> {
+        Box::pin(stream! {
+            if let Err(err) = self.try_acquire() {
+                yield Err(err);
+                return;
+            }
+
+            let mut rows = rows;
+            let first = self.bounded(async { rows.next().await.transpose() }).await.transpose();
+            match first {
+                Some(first) => {
+                    self.record(&first);
+                    yield first;
+                }
+                None => {
+                    self.record(&Ok::<(), DBError>(()));
+                    return;
+                }
+            }
+            while let Some(row) = rows.next().await {
+                yield row;
+            }
+        })
+    }
+
+    async fn bounded<T, F>(&self, operation: F) -> Result<T, DBError>
+    where
+        F: Future<Output = Result<T, DBError>>,
+    {
+        match self.call_timeout {
+            Some(call_timeout) => tokio::time::timeout(call_timeout, operation)
+                .await
+                .unwrap_or_else(|_| {
+                    Err(DBError::Other(
+                        format!("database call timed out after {call_timeout:?}").into(),
+                    ))
+                }),
+            None => operation.await,
+        }
+    }
+
+    fn try_acquire(&self) -> Result<(), DBError> {
+        let mut state = self.state.lock().unwrap();
+
+        match *state {
+            State::Closed { .. } => Ok(()),
+            State::Open { since } | State::HalfOpen { since }
+                if since.elapsed() >= self.open_for =>
+            {
+                *state = State::HalfOpen {
+                    since: Instant::now(),
+                };
+                Ok(())
+            }
+            State::Open { .. } | State::HalfOpen { .. } => Err(DBError::Unavailable),
+        }
+    }
+
+    fn record<T>(&self, result: &Result<T, DBError>) {
+        let mut state = self.state.lock().unwrap();
+
+        // Only infrastructure failures count; bad input says nothing about the database.
+        let failed = matches!(result, Err(DBError::Other(_)));
+
+        *state = match (*state, failed) {
+            (State::HalfOpen { .. }, false) => {
+                info!("Database recovered, closing circuit breaker.");
+                State::Closed { failures: 0 }
+            }
+            (_, false) => State::Closed { failures: 0 },
+            (State::Closed { failures }, true) if failures + 1 < self.failure_threshold => {
+                State::Closed {
+                    failures: failures + 1,
+                }
+            }
+            (State::Open { since }, true) => State::Open { since },
+            (_, true) => {
+                warn!(
+                    "Opening database circuit breaker for {:?} after consecutive failures.",
+                    self.open_for
+                );
+                State::Open {
+                    since: Instant::now(),
+                }
+            }
+        };
+
+        metrics::CIRCUIT_BREAKER_OPEN.set(!matches!(*state, State::Closed { .. }) as i64);
+    }
+}
+
+pub struct CircuitBreakerQuestionDao<T> {
+    inner: T,
+    breaker: Arc<CircuitBreaker>,
+}
+
+impl<T> CircuitBreakerQuestionDao<T> {
+    pub fn new(inner: T, breaker: Arc<CircuitBreaker>) -> Self {
+        Self { inner, breaker }
+    }
+}
+
+#[async_trait]
+impl<T: QuestionDao + Send + Sync> QuestionDao for CircuitBreakerQuestionDao<T> {
+    async fn create_question(&self, question: Question) -> Result<QuestionDetail, DBError> {
+        self.breaker
+            .call(self.inner.create_question(question))
+            .await
+    }
+
+    async fn delete_question(&self, question_uuid: QuestionUuid) -> Result<(), DBError> {
+        self.breaker
+            .call(self.inner.delete_question(question_uuid))
+            .await
+    }
+
+    async fn update_question(
+        &self,
+        question_uuid: QuestionUuid,
+        question: Question,
+        version: i64,
+    ) -> Result<QuestionDetail, DBError> {
+        self.breaker
+            .call(self.inner.update_question(question_uuid, question, version))
+            .await
+    }
+
+    async fn get_questions(&self) -> Result<Vec<QuestionDetail>, DBError> {
+        self.breaker.call(self.inner.get_questions()).await
+    }
+
+    async fn get_question(&self, question_uuid: QuestionUuid) -> Result<QuestionDetail, DBError> {
+        self.breaker
+            .call(self.inner.get_question(question_uuid))
+            .await
+    }
+
+    async fn get_questions_matching(
+        &self,
+        filter: &QuestionFilter,
+    ) -> Result<Vec<QuestionDetail>, DBError> {
+        self.breaker
+            .call(self.inner.get_questions_matching(filter))
+            .await
+    }
+
+    fn stream_questions(&self, filter: QuestionFilter) -> RowStream<'_, QuestionDetail> {
+        self.breaker
+            .call_stream(self.inner.stream_questions(filter))
+    }
+
+    async fn list_questions(
+        &self,
+        filter: &QuestionFilter,
+        include_archived: bool,
+        sort: QuestionSort,
+        window: Window,
+    ) -> Result<Listed<QuestionDetail>, DBError> {
+        self.breaker
+            .call(
+                self.inner
+                    .list_questions(filter, include_archived, sort, window),
+            )
+            .await
+    }
+
+    async fn list_unanswered_questions(
+        &self,
+        window: Window,
+    ) -> Result<Listed<QuestionDetail>, DBError> {
+        self.breaker
+            .call(self.inner.list_unanswered_questions(window))
+            .await
+    }
+}
+
+pub struct CircuitBreakerAnswerDao<T> {
+    inner: T,
+    breaker: Arc<CircuitBreaker>,
+}
+
+impl<T> CircuitBreakerAnswerDao<T> {
+    pub fn new(inner: T, breaker: Arc<CircuitBreaker>) -> Self {
+        Self { inner, breaker }
+    }
+}
+
+#[async_trait]
+impl<T: AnswerDao + Send + Sync> AnswerDao for CircuitBreakerAnswerDao<T> {
+    async fn create_answer(&self, answer: Answer) -> Result<AnswerDetail, DBError> {
+        self.breaker.call(self.inner.create_answer(answer)).await
+    }
+
+    async fn delete_answer(&self, answer_uuid: AnswerUuid) -> Result<(), DBError> {
+        self.breaker
+            .call(self.inner.delete_answer(answer_uuid))
+            .await
+    }
+
+    async fn update_answer(
+        &self,
+        answer_uuid: AnswerUuid,
+        content: String,
+        version: i64,
+    ) -> Result<AnswerDetail, DBError> {
+        self.breaker
+            .call(self.inner.update_answer(answer_uuid, content, version))
+            .await
+    }
+
+    async fn get_answers(&self, question_uuid: QuestionUuid) -> Result<Vec<AnswerDetail>, DBError> {
+        self.breaker
+            .call(self.inner.get_answers(question_uuid))
+            .await
+    }
+
+    fn stream_answers(&self, question_uuid: QuestionUuid) -> RowStream<'_, AnswerDetail> {
+        self.breaker
+            .call_stream(self.inner.stream_answers(question_uuid))
+    }
+
+    async fn get_answers_of_questions(
+        &self,
+        question_uuids: &[QuestionUuid],
+    ) -> Result<Vec<AnswerDetail>, DBError> {
+        self.breaker
+            .call(self.inner.get_answers_of_questions(question_uuids))
+            .await
+    }
+
+    async fn list_answers(
+        &self,
+        question_uuid: QuestionUuid,
+        include_archived: bool,
+        sort: AnswerSort,
+        window: Window,
+    ) -> Result<Listed<AnswerDetail>, DBError> {
+        self.breaker
+            .call(
+                self.inner
+                    .list_answers(question_uuid, include_archived, sort, window),
+            )
+            .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn db_failure() -> Result<(), DBError> {
+        Err(DBError::Other(Box::new(std::io::Error::other("down"))))
+    }
+
+    #[tokio::test]
+    async fn breaker_should_open_after_consecutive_failures() {
+        let breaker = CircuitBreaker::new(2, Duration::from_secs(60));
+
+        let _ = breaker.call(async { db_failure() }).await;
+        let _ = breaker.call(async { db_failure() }).await;
+        let result = breaker.call(async { Ok(()) }).await;
+
+        assert!(matches!(result, Err(DBError::Unavailable)));
+    }
+
+    #[tokio::test]
+    async fn breaker_should_reset_failures_on_success() {
+        let breaker = CircuitBreaker::new(2, Duration::from_secs(60));
+
+        let _ = breaker.call(async { db_failure() }).await;
+        let _ = breaker.call(async { Ok(()) }).await;
+        let _ = breaker.call(async { db_failure() }).await;
+        let result = breaker.call(async { Ok(()) }).await;
+
+        assert!(result.is_ok());
+    }
+
+    #[tokio::test]
+    async fn breaker_should_ignore_invalid_input_errors() {
+        let breaker = CircuitBreaker::new(1, Duration::from_secs(60));
+
+        let _: Result<(), _> = breaker
+            .call(async { Err(DBError::InvalidUUID("".to_owned())) })
+            .await;
+        let result = breaker.call(async { Ok(()) }).await;
+
+        assert!(result.is_ok());
+    }
+
+    #[tokio::test]
+    async fn breaker_should_close_after_successful_trial() {
+        let breaker = CircuitBreaker::new(1, Duration::from_millis(10));
+
+        let _ = breaker.call(async { db_failure() }).await;
+        tokio::time::sleep(Duration::from_millis(20)).await;
+
+        assert!(breaker.call(async { Ok(()) }).await.is_ok());
+        assert!(breaker.call(async { Ok(()) }).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn breaker_should_reopen_after_failed_trial() {
+        let breaker = CircuitBreaker::new(1, Duration::from_millis(10));
+
+        let _ = breaker.call(async { db_failure() }).await;
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        let _ = breaker.call(async { db_failure() }).await;
+        let result = breaker.call(async { Ok(()) }).await;
+
+        assert!(matches!(result, Err(DBError::Unavailable)));
+    }
+
+    #[tokio::test]
+    async fn breaker_should_count_timed_out_calls_as_failures() {
+        let breaker = CircuitBreaker::new(1, Duration::from_secs(60))
+            .with_call_timeout(Duration::from_millis(10));
+
+        let slow = breaker
+            .call(async {
+                tokio::time::sleep(Duration::from_secs(5)).await;
+                Ok(())
+            })
+            .await;
+        let result = breaker.call(async { Ok(()) }).await;
+
+        assert!(matches!(slow, Err(DBError::Other(_))));
+        assert!(matches!(result, Err(DBError::Unavailable)));
+    }
+}
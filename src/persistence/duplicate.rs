@@ -0,0 +1,140 @@
+use async_trait::async_trait;
+use sqlx::PgPool;
+
+use super::acquire;
+use crate::models::{AnswerUuid, DBError, QuestionUuid};
+
+// An existing answer close to one being posted, with their trigram similarity from 0 to 1.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SimilarAnswer {
+    pub answer_uuid: AnswerUuid,
+    pub content: String,
+    pub similarity: f32,
+}
+
+#[async_trait]
+pub trait DuplicateDao {
+    // The answers to the question at least `threshold` alike `content`, closest first.
+    async fn similar_answers(
+        &self,
+        question_uuid: QuestionUuid,
+        content: &str,
+        threshold: f32,
+        limit: u32,
+    ) -> Result<Vec<SimilarAnswer>, DBError>;
+}
+
+pub struct PgDuplicateDao {
+    db: PgPool,
+}
+
+impl PgDuplicateDao {
+    pub fn new(db: PgPool) -> Self {
+        Self { db }
+    }
+}
+
+#[async_trait]
+impl DuplicateDao for PgDuplicateDao {
+    async fn similar_answers(
+        &self,
+        question_uuid: QuestionUuid,
+        content: &str,
+        threshold: f32,
+        limit: u32,
+    ) -> Result<Vec<SimilarAnswer>, DBError> {
+        let mut conn = acquire(&self.db).await?;
+
+        // A question has few answers, so they are compared one by one rather than
+        // through a trigram index.
+        let rows = sqlx::query!(
+            r#"
+                SELECT answer_uuid, content, similarity(content, $2) AS "similarity!"
+                FROM answers
+                WHERE question_uuid = $1 AND similarity(content, $2) >= $3
+                ORDER BY 3 DESC, created_at
+                LIMIT $4
+            "#,
+            question_uuid.0,
+            content,
+            threshold,
+            i64::from(limit),
+        )
+        .fetch_all(&mut conn)
+        .await
+        .map_err(|err| DBError::Other(Box::new(err)))?;
+
+        Ok(rows
+            .into_iter()
+            .map(|row| SimilarAnswer {
+                answer_uuid: AnswerUuid(row.answer_uuid),
+                content: row.content,
+                similarity: row.similarity,
+            })
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        models::{empty_metadata, Answer, Question},
+        persistence::{
+            answer_dao::{AnswerDao, AnswerDaoImpl},
+            question_dao::{QuestionDao, QuestionDaoImpl},
+        },
+    };
+
+    #[sqlx::test]
+    async fn similar_answers_should_skip_other_questions_and_distant_answers(
+        pool: PgPool,
+    ) -> Result<(), String> {
+        let question_dao = QuestionDaoImpl::new(pool.clone());
+        let answer_dao = AnswerDaoImpl::new(pool.clone());
+        let mut question_uuids = vec![];
+        for title in ["first", "second"] {
+            let question = question_dao
+                .create_question(Question {
+                    title: title.to_owned(),
+                    description: None,
+                    metadata: empty_metadata(),
+                })
+                .await
+                .unwrap();
+            question_uuids.push(question.question_uuid);
+        }
+        let mut answers = vec![];
+        for (question_uuid, content) in [
+            (question_uuids[0], "Restart the router, then reconnect."),
+            (question_uuids[0], "Check the billing page."),
+            (question_uuids[1], "Restart the router, then reconnect."),
+        ] {
+            answers.push(
+                answer_dao
+                    .create_answer(Answer {
+                        question_uuid,
+                        content: content.to_owned(),
+                    })
+                    .await
+                    .unwrap(),
+            );
+        }
+        let dao = PgDuplicateDao::new(pool);
+
+        let similar = dao
+            .similar_answers(
+                question_uuids[0],
+                "restart the router and reconnect",
+                0.5,
+                5,
+            )
+            .await
+            .unwrap();
+        assert_eq!(similar.len(), 1);
+        assert_eq!(similar[0].answer_uuid, answers[0].answer_uuid);
+        assert!(similar[0].similarity < 1.0);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,124 @@
+use async_trait::async_trait;
+use sqlx::PgPool;
+
+use super::{acquire, question_dao::QuestionRow};
+use crate::models::{DBError, QuestionDetail, QuestionUuid};
+
+// Stores question embeddings and finds the questions closest to a query embedding.
+#[async_trait]
+pub trait EmbeddingDao {
+    async fn store_question_embedding(
+        &self,
+        question_uuid: QuestionUuid,
+        embedding: Vec<f32>,
+    ) -> Result<(), DBError>;
+    // Closest first, by cosine distance. Questions without an embedding are left out.
+    async fn nearest_questions(
+        &self,
+        embedding: Vec<f32>,
+        limit: u32,
+    ) -> Result<Vec<QuestionDetail>, DBError>;
+}
+
+// Uses the pgvector column from the embeddings migration. Its queries are checked at
+// runtime, since the column only exists where pgvector is installed.
+pub struct PgEmbeddingDao {
+    db: PgPool,
+}
+
+impl PgEmbeddingDao {
+    // None when the database has no embedding column.
+    pub async fn connect(db: PgPool) -> Result<Option<Self>, sqlx::Error> {
+        let has_column: bool = sqlx::query_scalar(
+            r#"
+                SELECT EXISTS (
+                    SELECT 1 FROM information_schema.columns
+                    WHERE table_name = 'questions' AND column_name = 'embedding'
+                )
+            "#,
+        )
+        .fetch_one(&db)
+        .await?;
+
+        Ok(has_column.then_some(Self { db }))
+    }
+}
+
+// pgvector accepts its text form, so no client-side type is needed.
+fn to_vector(embedding: &[f32]) -> String {
+    let values: Vec<String> = embedding.iter().map(f32::to_string).collect();
+    format!("[{}]", values.join(","))
+}
+
+#[async_trait]
+impl EmbeddingDao for PgEmbeddingDao {
+    async fn store_question_embedding(
+        &self,
+        question_uuid: QuestionUuid,
+        embedding: Vec<f32>,
+    ) -> Result<(), DBError> {
+        let question_uuid = question_uuid.0;
+
+        let mut conn = acquire(&self.db).await?;
+
+        sqlx::query("UPDATE questions SET embedding = $1::vector WHERE question_uuid = $2")
+            .bind(to_vector(&embedding))
+            .bind(question_uuid)
+            .execute(&mut conn)
+            .await
+            .map_err(|err| DBError::Other(Box::new(err)))?;
+
+        Ok(())
+    }
+
+    async fn nearest_questions(
+        &self,
+        embedding: Vec<f32>,
+        limit: u32,
+    ) -> Result<Vec<QuestionDetail>, DBError> {
+        let mut conn = acquire(&self.db).await?;
+
+        let result = sqlx::query_as::<_, QuestionRow>(
+            r#"
+                SELECT question_uuid, title, description, metadata, created_at, updated_at, last_activity_at, answer_count, version
+                FROM questions
+                WHERE embedding IS NOT NULL
+                ORDER BY embedding <=> $1::vector
+                LIMIT $2
+            "#,
+        )
+        .bind(to_vector(&embedding))
+        .bind(i64::from(limit))
+        .fetch_all(&mut conn)
+        .await
+        .map_err(|err| DBError::Other(Box::new(err)))?;
+
+        Ok(result.into_iter().map(QuestionDetail::from).collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn to_vector_should_use_pgvector_text_format() {
+        assert_eq!(to_vector(&[1.0, -0.5, 0.25]), "[1,-0.5,0.25]");
+    }
+
+    #[sqlx::test]
+    async fn connect_should_follow_the_embedding_column(pool: PgPool) -> Result<(), String> {
+        let has_column: bool = sqlx::query_scalar(
+            "SELECT EXISTS (SELECT 1 FROM pg_extension WHERE extname = 'vector')",
+        )
+        .fetch_one(&pool)
+        .await
+        .map_err(|err| err.to_string())?;
+
+        let dao = PgEmbeddingDao::connect(pool)
+            .await
+            .map_err(|err| err.to_string())?;
+        assert_eq!(dao.is_some(), has_column);
+        Ok(())
+    }
+}
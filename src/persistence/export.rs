@@ -0,0 +1,537 @@
+use std::{collections::HashSet, fmt, io::Write};
+
+use chrono::NaiveDateTime;
+use futures::TryStreamExt;
+use sqlx::{types::Uuid, PgPool};
+use thiserror::Error;
+
+use super::{
+    audit::set_actor,
+    invalidation::{CACHE_INVALIDATION_CHANNEL, INVALIDATE_ALL},
+    locks::{self, Lock},
+};
+use crate::models::{
+    Export, ExportedAnswer, ExportedQuestion, ImportSummary, MAX_METADATA_BYTES, MAX_TEXT_CHARS,
+};
+
+#[derive(Error, Debug)]
+pub enum TransferError {
+    #[error("Database error: {0}")]
+    Database(#[from] sqlx::Error),
+    #[error("File error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("{} invalid records", .0.len())]
+    Invalid(Vec<RecordError>),
+    #[error("An import is already running")]
+    Busy,
+}
+
+// What is wrong with one question or answer of an export, e.g. `questions[2].answers[0]`.
+#[derive(Debug, PartialEq)]
+pub struct RecordError {
+    pub record: String,
+    pub message: String,
+}
+
+impl fmt::Display for RecordError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.record, self.message)
+    }
+}
+
+// Writes the live questions with their answers as one `Export` JSON document, oldest
+// first. Questions are written as they are read, so memory does not grow with the
+// database. Archived questions are not included; `backup` covers those.
+pub async fn export<W: Write>(pool: &PgPool, out: &mut W) -> Result<u64, TransferError> {
+    let mut tx = pool.begin().await?;
+    sqlx::query("SET TRANSACTION ISOLATION LEVEL REPEATABLE READ, READ ONLY")
+        .execute(&mut tx)
+        .await?;
+
+    let mut questions = sqlx::query_scalar!(
+        r#"
+            SELECT json_build_object(
+                'question_uuid', q.question_uuid,
+                'title', q.title,
+                'description', q.description,
+                'metadata', q.metadata,
+                'created_at', q.created_at AT TIME ZONE 'UTC',
+                'updated_at', q.updated_at AT TIME ZONE 'UTC',
+                'version', q.version,
+                'answers', COALESCE(
+                    (
+                        SELECT json_agg(
+                            json_build_object(
+                                'answer_uuid', a.answer_uuid,
+                                'content', a.content,
+                                'created_at', a.created_at,
+                                'updated_at', a.updated_at,
+                                'version', a.version
+                            )
+                            ORDER BY a.created_at
+                        )
+                        FROM answers a
+                        WHERE a.question_uuid = q.question_uuid
+                        AND a.created_at >= LEAST(q.created_at, q.answers_since) AT TIME ZONE 'UTC'
+                    ),
+                    '[]'
+                )
+            )::TEXT AS "question!"
+            FROM questions q
+            ORDER BY q.created_at
+        "#
+    )
+    .fetch(&mut tx);
+
+    write!(out, r#"{{"questions":["#)?;
+    let mut count = 0;
+    while let Some(question) = questions.try_next().await? {
+        if count > 0 {
+            write!(out, ",")?;
+        }
+        write!(out, "\n{}", question)?;
+        count += 1;
+    }
+    writeln!(out, "\n]}}")?;
+    out.flush()?;
+
+    Ok(count)
+}
+
+// Loads an export into the database alongside what is already there, keeping UUIDs,
+// timestamps and versions. Every record is checked first; if any is invalid, or
+// already exists, all problems are returned and nothing is loaded.
+pub async fn import(pool: &PgPool, export: Export) -> Result<ImportSummary, TransferError> {
+    let mut errors = validate(&export);
+
+    let mut tx = pool.begin().await?;
+    if !locks::try_lock(&mut tx, Lock::Import).await? {
+        return Err(TransferError::Busy);
+    }
+    set_actor(&mut tx).await?;
+    // One notification for the whole import, see the bulk_import migration.
+    sqlx::query("SET LOCAL app.skip_cache_invalidation = 'on'")
+        .execute(&mut tx)
+        .await?;
+
+    let question_uuids: Vec<Uuid> = export
+        .questions
+        .iter()
+        .filter_map(|question| Uuid::parse_str(&question.question_uuid).ok())
+        .collect();
+    let answer_uuids: Vec<Uuid> = exported_answers(&export)
+        .filter_map(|(_, _, answer)| Uuid::parse_str(&answer.answer_uuid).ok())
+        .collect();
+
+    let existing: HashSet<Uuid> = sqlx::query_scalar!(
+        r#"
+            SELECT question_uuid AS "uuid!" FROM questions WHERE question_uuid = ANY($1)
+            UNION ALL
+            SELECT question_uuid FROM questions_archive WHERE question_uuid = ANY($1)
+            UNION ALL
+            SELECT answer_uuid FROM answers WHERE answer_uuid = ANY($2)
+            UNION ALL
+            SELECT answer_uuid FROM answers_archive WHERE answer_uuid = ANY($2)
+        "#,
+        &question_uuids,
+        &answer_uuids,
+    )
+    .fetch_all(&mut tx)
+    .await?
+    .into_iter()
+    .collect();
+
+    for (index, question) in export.questions.iter().enumerate() {
+        if parses_into(&question.question_uuid, &existing) {
+            errors.push(record_error(
+                format!("questions[{}]", index),
+                "question_uuid already exists",
+            ));
+        }
+    }
+    for (index, answer_index, answer) in exported_answers(&export) {
+        if parses_into(&answer.answer_uuid, &existing) {
+            errors.push(record_error(
+                format!("questions[{}].answers[{}]", index, answer_index),
+                "answer_uuid already exists",
+            ));
+        }
+    }
+    if !errors.is_empty() {
+        return Err(TransferError::Invalid(errors));
+    }
+    // From here on every UUID parsed, so the lists above line up with the records.
+
+    if let Some(oldest) = exported_answers(&export)
+        .map(|(_, _, answer)| answer.created_at.as_str())
+        .min_by_key(|created_at| timestamp(created_at))
+    {
+        sqlx::query!(
+            "SELECT create_answer_partitions($1::TEXT::TIMESTAMP, 2)",
+            oldest
+        )
+        .fetch_one(&mut tx)
+        .await?;
+    }
+
+    let questions = sqlx::query!(
+        r#"
+            INSERT INTO questions (question_uuid, title, description, metadata, created_at, updated_at, last_activity_at, version, answers_since)
+            SELECT question_uuid, title, NULLIF(description, ''), metadata::JSONB, created_at::TIMESTAMP AT TIME ZONE 'UTC', updated_at::TIMESTAMP AT TIME ZONE 'UTC', updated_at::TIMESTAMP AT TIME ZONE 'UTC', version, NULLIF(answers_since, '')::TIMESTAMP AT TIME ZONE 'UTC'
+            FROM UNNEST($1::UUID[], $2::TEXT[], $3::TEXT[], $4::TEXT[], $5::TEXT[], $6::TEXT[], $7::BIGINT[], $8::TEXT[])
+                AS imported (question_uuid, title, description, metadata, created_at, updated_at, version, answers_since)
+        "#,
+        &question_uuids,
+        &export.questions.iter().map(|q| q.title.clone()).collect::<Vec<_>>(),
+        // Missing descriptions go in as '' and are turned back into NULL above.
+        &export.questions.iter().map(|q| q.description.clone().unwrap_or_default()).collect::<Vec<_>>(),
+        &export.questions.iter().map(|q| q.metadata.to_string()).collect::<Vec<_>>(),
+        &export.questions.iter().map(|q| q.created_at.clone()).collect::<Vec<_>>(),
+        &export.questions.iter().map(|q| q.updated_at.clone()).collect::<Vec<_>>(),
+        &export.questions.iter().map(|q| q.version).collect::<Vec<_>>(),
+        // Likewise for questions with no answers older than themselves.
+        &export.questions.iter().map(|q| answers_since(q).unwrap_or_default()).collect::<Vec<_>>(),
+    )
+    .execute(&mut tx)
+    .await?
+    .rows_affected();
+
+    let answer_questions: Vec<Uuid> = exported_answers(&export)
+        .map(|(index, _, _)| question_uuids[index])
+        .collect();
+    let answers = sqlx::query!(
+        r#"
+            INSERT INTO answers (answer_uuid, question_uuid, content, created_at, updated_at, version)
+            SELECT answer_uuid, question_uuid, content, created_at::TIMESTAMP, updated_at::TIMESTAMP, version
+            FROM UNNEST($1::UUID[], $2::UUID[], $3::TEXT[], $4::TEXT[], $5::TEXT[], $6::BIGINT[])
+                AS imported (answer_uuid, question_uuid, content, created_at, updated_at, version)
+        "#,
+        &answer_uuids,
+        &answer_questions,
+        &exported_answers(&export).map(|(_, _, a)| a.content.clone()).collect::<Vec<_>>(),
+        &exported_answers(&export).map(|(_, _, a)| a.created_at.clone()).collect::<Vec<_>>(),
+        &exported_answers(&export).map(|(_, _, a)| a.updated_at.clone()).collect::<Vec<_>>(),
+        &exported_answers(&export).map(|(_, _, a)| a.version).collect::<Vec<_>>(),
+    )
+    .execute(&mut tx)
+    .await?
+    .rows_affected();
+
+    sqlx::query("SELECT pg_notify($1, $2)")
+        .bind(CACHE_INVALIDATION_CHANNEL)
+        .bind(INVALIDATE_ALL)
+        .execute(&mut tx)
+        .await?;
+    tx.commit().await?;
+
+    Ok(ImportSummary { questions, answers })
+}
+
+// The answers of every question, with the index of their question and their own.
+fn exported_answers(export: &Export) -> impl Iterator<Item = (usize, usize, &ExportedAnswer)> {
+    export
+        .questions
+        .iter()
+        .enumerate()
+        .flat_map(|(index, question)| {
+            question
+                .answers
+                .iter()
+                .enumerate()
+                .map(move |(answer_index, answer)| (index, answer_index, answer))
+        })
+}
+
+// The oldest answer of a question when it is older than the question, as merged answers
+// can be, so answer reads do not skip its partition.
+fn answers_since(question: &ExportedQuestion) -> Option<String> {
+    let created = timestamp(&question.created_at);
+    question
+        .answers
+        .iter()
+        .map(|answer| answer.created_at.as_str())
+        .filter(|created_at| timestamp(created_at) < created)
+        .min_by_key(|created_at| timestamp(created_at))
+        .map(str::to_owned)
+}
+
+fn parses_into(uuid: &str, uuids: &HashSet<Uuid>) -> bool {
+    Uuid::parse_str(uuid).is_ok_and(|uuid| uuids.contains(&uuid))
+}
+
+fn timestamp(value: &str) -> Option<NaiveDateTime> {
+    value.parse().ok()
+}
+
+fn record_error(record: String, message: &str) -> RecordError {
+    RecordError {
+        record,
+        message: message.to_owned(),
+    }
+}
+
+// Checks what can be checked without the database.
+fn validate(export: &Export) -> Vec<RecordError> {
+    let mut errors = vec![];
+    let mut seen = HashSet::new();
+    let mut report = |record: &str, messages: Vec<String>| {
+        errors.extend(messages.into_iter().map(|message| RecordError {
+            record: record.to_owned(),
+            message,
+        }))
+    };
+
+    for (index, question) in export.questions.iter().enumerate() {
+        let record = format!("questions[{}]", index);
+        let mut messages = problems(
+            &mut seen,
+            ("question_uuid", &question.question_uuid),
+            &[
+                ("title", &question.title),
+                (
+                    "description",
+                    question.description.as_deref().unwrap_or_default(),
+                ),
+            ],
+            (&question.created_at, &question.updated_at),
+            question.version,
+        );
+        if !question.metadata.is_object() {
+            messages.push("metadata is not an object".to_owned());
+        } else if question.metadata.to_string().len() > MAX_METADATA_BYTES {
+            messages.push(format!(
+                "metadata is larger than {} bytes",
+                MAX_METADATA_BYTES
+            ));
+        }
+        report(&record, messages);
+
+        for (answer_index, answer) in question.answers.iter().enumerate() {
+            let messages = problems(
+                &mut seen,
+                ("answer_uuid", &answer.answer_uuid),
+                &[("content", &answer.content)],
+                (&answer.created_at, &answer.updated_at),
+                answer.version,
+            );
+            report(&format!("{}.answers[{}]", record, answer_index), messages);
+        }
+    }
+
+    errors
+}
+
+// Checks shared by questions and answers. UUIDs go into `seen`, to find repeats.
+fn problems(
+    seen: &mut HashSet<Uuid>,
+    (uuid_field, uuid): (&str, &str),
+    texts: &[(&str, &str)],
+    (created_at, updated_at): (&str, &str),
+    version: i64,
+) -> Vec<String> {
+    let mut messages = vec![];
+
+    match Uuid::parse_str(uuid) {
+        Ok(uuid) if !seen.insert(uuid) => {
+            messages.push(format!("{} appears more than once", uuid_field))
+        }
+        Ok(_) => {}
+        Err(err) => messages.push(format!("{} is invalid: {}", uuid_field, err)),
+    }
+    for (field, text) in texts {
+        if text.chars().count() > MAX_TEXT_CHARS {
+            messages.push(format!(
+                "{} is longer than {} characters",
+                field, MAX_TEXT_CHARS
+            ));
+        }
+    }
+    match (timestamp(created_at), timestamp(updated_at)) {
+        (Some(created), Some(updated)) if updated < created => {
+            messages.push("updated_at is before created_at".to_owned())
+        }
+        (Some(_), Some(_)) => {}
+        (None, _) => messages.push(format!("created_at is not a timestamp: {}", created_at)),
+        (_, None) => messages.push(format!("updated_at is not a timestamp: {}", updated_at)),
+    }
+    if version < 1 {
+        messages.push("version must be at least 1".to_owned());
+    }
+
+    messages
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        models::{empty_metadata, Answer, ExportedQuestion, Question},
+        persistence::{
+            answer_dao::{AnswerDao, AnswerDaoImpl},
+            question_dao::{QuestionDao, QuestionDaoImpl},
+        },
+    };
+
+    async fn snapshot(pool: &PgPool) -> Result<Vec<String>, sqlx::Error> {
+        sqlx::query_scalar(
+            r#"
+                SELECT to_jsonb(t)::TEXT FROM (
+                    SELECT question_uuid, question_uuid AS parent, title, metadata, created_at, updated_at, answer_count, version
+                    FROM questions
+                    UNION ALL
+                    SELECT answer_uuid, question_uuid, content, NULL, created_at, updated_at, 0, version FROM answers
+                ) t
+                ORDER BY 1
+            "#,
+        )
+        .fetch_all(pool)
+        .await
+    }
+
+    #[sqlx::test]
+    async fn import_should_bring_back_what_export_wrote(pool: PgPool) -> Result<(), String> {
+        let question = QuestionDaoImpl::new(pool.clone())
+            .create_question(Question {
+                title: "title".to_owned(),
+                description: Some("description".to_owned()),
+                metadata: serde_json::json!({ "source": "zendesk", "ticket": 42 }),
+            })
+            .await
+            .map_err(|err| err.to_string())?;
+        AnswerDaoImpl::new(pool.clone())
+            .create_answer(Answer {
+                question_uuid: question.question_uuid,
+                content: "content".to_owned(),
+            })
+            .await
+            .map_err(|err| err.to_string())?;
+        let before = snapshot(&pool).await.map_err(|err| err.to_string())?;
+
+        let mut written = vec![];
+        let exported = export(&pool, &mut written)
+            .await
+            .map_err(|err| err.to_string())?;
+        assert_eq!(exported, 1);
+        let document: Export = serde_json::from_slice(&written).map_err(|err| err.to_string())?;
+
+        let duplicate = import(&pool, document).await;
+        let Err(TransferError::Invalid(errors)) = duplicate else {
+            return Err(format!("expected duplicates, got {:?}", duplicate));
+        };
+        assert_eq!(
+            errors,
+            [
+                record_error("questions[0]".to_owned(), "question_uuid already exists"),
+                record_error(
+                    "questions[0].answers[0]".to_owned(),
+                    "answer_uuid already exists"
+                ),
+            ]
+        );
+
+        sqlx::query("DELETE FROM questions")
+            .execute(&pool)
+            .await
+            .map_err(|err| err.to_string())?;
+        let document: Export = serde_json::from_slice(&written).map_err(|err| err.to_string())?;
+        let summary = import(&pool, document)
+            .await
+            .map_err(|err| err.to_string())?;
+        assert_eq!((summary.questions, summary.answers), (1, 1));
+
+        let after = snapshot(&pool).await.map_err(|err| err.to_string())?;
+        assert_eq!(before, after);
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn imported_answers_older_than_their_question_should_be_listed(
+        pool: PgPool,
+    ) -> Result<(), String> {
+        // As a merge leaves them: the answer was posted to a question asked earlier.
+        let question_uuid = "5b3f0e6c-0e0a-4a8a-9d8e-2f6d1c3b9a10";
+        let document = Export {
+            questions: vec![ExportedQuestion {
+                question_uuid: question_uuid.to_owned(),
+                title: "title".to_owned(),
+                description: None,
+                metadata: empty_metadata(),
+                created_at: "2023-10-01T09:00:00".to_owned(),
+                updated_at: "2023-10-01T09:00:00".to_owned(),
+                version: 1,
+                answers: vec![ExportedAnswer {
+                    answer_uuid: "a6d0d1ae-1c5b-4a55-a3b2-8b7b0f0e7f61".to_owned(),
+                    content: "content".to_owned(),
+                    created_at: "2023-08-01T10:00:00".to_owned(),
+                    updated_at: "2023-08-01T10:00:00".to_owned(),
+                    version: 2,
+                }],
+            }],
+        };
+
+        import(&pool, document)
+            .await
+            .map_err(|err| err.to_string())?;
+
+        let answers = AnswerDaoImpl::new(pool.clone())
+            .get_answers(question_uuid.parse().unwrap())
+            .await
+            .map_err(|err| err.to_string())?;
+        assert_eq!(answers.len(), 1);
+        Ok(())
+    }
+
+    #[test]
+    fn validate_should_report_each_bad_record() {
+        let answer = ExportedAnswer {
+            answer_uuid: "a6d0d1ae-1c5b-4a55-a3b2-8b7b0f0e7f61".to_owned(),
+            content: "content".to_owned(),
+            created_at: "2023-10-01T10:00:00".to_owned(),
+            updated_at: "2023-10-01T10:00:00".to_owned(),
+            version: 1,
+        };
+        let question = ExportedQuestion {
+            question_uuid: "5b3f0e6c-0e0a-4a8a-9d8e-2f6d1c3b9a10".to_owned(),
+            title: "title".to_owned(),
+            description: Some("description".to_owned()),
+            metadata: empty_metadata(),
+            created_at: "2023-10-01T09:00:00".to_owned(),
+            updated_at: "2023-10-01T09:00:00".to_owned(),
+            version: 1,
+            answers: vec![answer.clone()],
+        };
+        let export = Export {
+            questions: vec![
+                question.clone(),
+                ExportedQuestion {
+                    question_uuid: "not a uuid".to_owned(),
+                    title: "t".repeat(MAX_TEXT_CHARS + 1),
+                    metadata: serde_json::json!("zendesk"),
+                    updated_at: "2023-10-01T08:00:00".to_owned(),
+                    version: 0,
+                    answers: vec![ExportedAnswer {
+                        created_at: "yesterday".to_owned(),
+                        ..answer
+                    }],
+                    ..question
+                },
+            ],
+        };
+
+        let errors: Vec<String> = validate(&export).iter().map(ToString::to_string).collect();
+
+        assert_eq!(errors.len(), 7);
+        assert!(errors[0].starts_with("questions[1]: question_uuid is invalid"));
+        assert_eq!(
+            errors[1..],
+            [
+                "questions[1]: title is longer than 255 characters",
+                "questions[1]: updated_at is before created_at",
+                "questions[1]: version must be at least 1",
+                "questions[1]: metadata is not an object",
+                "questions[1].answers[0]: answer_uuid appears more than once",
+                "questions[1].answers[0]: created_at is not a timestamp: yesterday",
+            ]
+        );
+    }
+}
@@ -0,0 +1,291 @@
+use async_trait::async_trait;
+use sqlx::{postgres::PgCopyIn, types::Uuid, PgConnection, PgPool};
+
+use super::{
+    audit::set_actor,
+    invalidation::{CACHE_INVALIDATION_CHANNEL, INVALIDATE_ALL},
+    locks::{self, Lock},
+    unit_of_work::UnitOfWorkFactory,
+};
+use crate::models::{Answer, DBError, ImportSummary, ImportedQuestion, Question};
+
+// Rows are buffered up to this size before being sent to the server.
+const COPY_CHUNK_SIZE: usize = 64 * 1024;
+
+// Loads questions with their answers; either everything is imported or nothing is.
+#[async_trait]
+pub trait ImportDao {
+    async fn import(&self, questions: Vec<ImportedQuestion>) -> Result<ImportSummary, DBError>;
+}
+
+// Streams rows through `COPY ... FROM STDIN` instead of issuing one INSERT per row.
+pub struct PgCopyImportDao {
+    db: PgPool,
+}
+
+impl PgCopyImportDao {
+    pub fn new(db: PgPool) -> Self {
+        Self { db }
+    }
+
+    // None when another import is running.
+    async fn copy(
+        &self,
+        questions: Vec<ImportedQuestion>,
+    ) -> Result<Option<ImportSummary>, sqlx::Error> {
+        let question_uuids: Vec<String> = questions
+            .iter()
+            .map(|_| Uuid::new_v4().to_string())
+            .collect();
+
+        let mut tx = self.db.begin().await?;
+        if !locks::try_lock(&mut tx, Lock::Import).await? {
+            return Ok(None);
+        }
+        set_actor(&mut tx).await?;
+        // One notification for the whole import instead of one per row, see the bulk_import migration.
+        sqlx::query("SET LOCAL app.skip_cache_invalidation = 'on'")
+            .execute(&mut tx)
+            .await?;
+
+        let mut copy = CsvCopy::start(
+            &mut tx,
+            "questions (question_uuid, title, description, metadata)",
+        )
+        .await?;
+        for (question, question_uuid) in questions.iter().zip(&question_uuids) {
+            copy.row(&[
+                Some(question_uuid),
+                Some(&question.title),
+                question.description.as_deref(),
+                Some(&question.metadata.to_string()),
+            ])
+            .await?;
+        }
+        let question_count = copy.finish().await?;
+
+        let mut copy =
+            CsvCopy::start(&mut tx, "answers (answer_uuid, question_uuid, content)").await?;
+        for (question, question_uuid) in questions.iter().zip(&question_uuids) {
+            for content in &question.answers {
+                copy.row(&[
+                    Some(&Uuid::new_v4().to_string()),
+                    Some(question_uuid),
+                    Some(content),
+                ])
+                .await?;
+            }
+        }
+        let answer_count = copy.finish().await?;
+
+        sqlx::query("SELECT pg_notify($1, $2)")
+            .bind(CACHE_INVALIDATION_CHANNEL)
+            .bind(INVALIDATE_ALL)
+            .execute(&mut tx)
+            .await?;
+        tx.commit().await?;
+
+        Ok(Some(ImportSummary {
+            questions: question_count,
+            answers: answer_count,
+        }))
+    }
+}
+
+#[async_trait]
+impl ImportDao for PgCopyImportDao {
+    async fn import(&self, questions: Vec<ImportedQuestion>) -> Result<ImportSummary, DBError> {
+        self.copy(questions)
+            .await
+            .map_err(|err| DBError::Other(Box::new(err)))?
+            .ok_or_else(|| locks::lock_held(Lock::Import))
+    }
+}
+
+// A COPY in CSV format. Dropping it before `finish` aborts the copy.
+struct CsvCopy<'c> {
+    copy: PgCopyIn<&'c mut PgConnection>,
+    buffer: Vec<u8>,
+}
+
+impl<'c> CsvCopy<'c> {
+    async fn start(conn: &'c mut PgConnection, target: &str) -> Result<Self, sqlx::Error> {
+        let copy = conn
+            .copy_in_raw(&format!("COPY {} FROM STDIN WITH (FORMAT csv)", target))
+            .await?;
+
+        Ok(Self {
+            copy,
+            buffer: Vec::with_capacity(COPY_CHUNK_SIZE),
+        })
+    }
+
+    async fn row(&mut self, fields: &[Option<&str>]) -> Result<(), sqlx::Error> {
+        write_csv_row(&mut self.buffer, fields);
+        if self.buffer.len() >= COPY_CHUNK_SIZE {
+            self.flush().await?;
+        }
+        Ok(())
+    }
+
+    async fn flush(&mut self) -> Result<(), sqlx::Error> {
+        self.copy.send(self.buffer.as_slice()).await?;
+        self.buffer.clear();
+        Ok(())
+    }
+
+    async fn finish(mut self) -> Result<u64, sqlx::Error> {
+        if !self.buffer.is_empty() {
+            self.flush().await?;
+        }
+        self.copy.finish().await
+    }
+}
+
+// Quotes every field, so commas, quotes and newlines in the text survive. A `None` is
+// left empty and unquoted, which COPY reads as NULL.
+fn write_csv_row(buffer: &mut Vec<u8>, fields: &[Option<&str>]) {
+    for (index, field) in fields.iter().enumerate() {
+        if index > 0 {
+            buffer.push(b',');
+        }
+        let Some(field) = field else {
+            continue;
+        };
+        buffer.push(b'"');
+        buffer.extend_from_slice(field.replace('"', "\"\"").as_bytes());
+        buffer.push(b'"');
+    }
+    buffer.push(b'\n');
+}
+
+// Per-row inserts inside a single unit of work, for backends without COPY.
+pub struct UnitOfWorkImportDao<T> {
+    unit_of_work: T,
+}
+
+impl<T> UnitOfWorkImportDao<T> {
+    pub fn new(unit_of_work: T) -> Self {
+        Self { unit_of_work }
+    }
+}
+
+#[async_trait]
+impl<T: UnitOfWorkFactory + Send + Sync> ImportDao for UnitOfWorkImportDao<T> {
+    async fn import(&self, questions: Vec<ImportedQuestion>) -> Result<ImportSummary, DBError> {
+        let mut work = self.unit_of_work.begin().await?;
+        let mut summary = ImportSummary {
+            questions: 0,
+            answers: 0,
+        };
+
+        for question in questions {
+            let created = work
+                .create_question(Question {
+                    title: question.title,
+                    description: question.description,
+                    metadata: question.metadata,
+                })
+                .await?;
+            summary.questions += 1;
+
+            for content in question.answers {
+                work.create_answer(Answer {
+                    question_uuid: created.question_uuid,
+                    content,
+                })
+                .await?;
+                summary.answers += 1;
+            }
+        }
+
+        work.commit().await?;
+        Ok(summary)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::empty_metadata;
+    use crate::persistence::{
+        answer_dao::{AnswerDao, AnswerDaoImpl},
+        memory::{MemoryStore, MemoryUnitOfWorkFactory},
+        question_dao::{QuestionDao, QuestionDaoImpl},
+    };
+
+    fn questions() -> Vec<ImportedQuestion> {
+        vec![
+            ImportedQuestion {
+                title: "first".to_owned(),
+                description: Some("with \"quotes\", commas\nand newlines".to_owned()),
+                metadata: serde_json::json!({ "source": "csv, \"quoted\"" }),
+                answers: vec!["one".to_owned(), "two".to_owned()],
+            },
+            ImportedQuestion {
+                title: "second".to_owned(),
+                description: Some("description".to_owned()),
+                metadata: empty_metadata(),
+                answers: vec![],
+            },
+        ]
+    }
+
+    #[test]
+    fn write_csv_row_should_quote_fields() {
+        let mut buffer = vec![];
+
+        write_csv_row(&mut buffer, &[Some("a"), Some("b,\"c\""), None, Some("")]);
+
+        assert_eq!(buffer, b"\"a\",\"b,\"\"c\"\"\",,\"\"\n");
+    }
+
+    #[sqlx::test]
+    async fn copy_import_should_load_questions_and_answers(pool: PgPool) -> Result<(), String> {
+        let summary = PgCopyImportDao::new(pool.clone())
+            .import(questions())
+            .await
+            .map_err(|err| err.to_string())?;
+        assert_eq!(
+            summary,
+            ImportSummary {
+                questions: 2,
+                answers: 2
+            }
+        );
+
+        let stored = QuestionDaoImpl::new(pool.clone())
+            .get_questions()
+            .await
+            .map_err(|err| err.to_string())?;
+        let first = stored
+            .iter()
+            .find(|question| question.title == "first")
+            .ok_or("Expected the first question to be imported")?;
+        assert_eq!(first.description, questions()[0].description);
+        assert_eq!(first.metadata, questions()[0].metadata);
+        assert_eq!(first.answer_count, 2);
+
+        let answers = AnswerDaoImpl::new(pool)
+            .get_answers(first.question_uuid)
+            .await
+            .map_err(|err| err.to_string())?;
+        assert_eq!(answers.len(), 2);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn unit_of_work_import_should_load_questions_and_answers() {
+        let dao = UnitOfWorkImportDao::new(MemoryUnitOfWorkFactory::new(MemoryStore::new()));
+
+        let summary = dao.import(questions()).await.unwrap();
+
+        assert_eq!(
+            summary,
+            ImportSummary {
+                questions: 2,
+                answers: 2
+            }
+        );
+    }
+}
@@ -0,0 +1,118 @@
+use async_trait::async_trait;
+use sqlx::PgPool;
+
+use super::{audit::set_actor, question_dao::insert_question};
+use crate::models::{DBError, Question, QuestionDetail, QuestionUuid};
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Ingested {
+    Created(QuestionDetail),
+    // A question was created for the key before; it may have been deleted since.
+    Duplicate(QuestionUuid),
+}
+
+// Creates questions received from other systems at most once per idempotency key.
+#[async_trait]
+pub trait IngestionDao {
+    async fn create_question_once(
+        &self,
+        idempotency_key: &str,
+        question: Question,
+    ) -> Result<Ingested, DBError>;
+}
+
+pub struct PgIngestionDao {
+    db: PgPool,
+}
+
+impl PgIngestionDao {
+    pub fn new(db: PgPool) -> Self {
+        Self { db }
+    }
+}
+
+#[async_trait]
+impl IngestionDao for PgIngestionDao {
+    // The question and its key commit together. A concurrent delivery of the same key
+    // waits on the key's row and then rolls its question back.
+    async fn create_question_once(
+        &self,
+        idempotency_key: &str,
+        question: Question,
+    ) -> Result<Ingested, DBError> {
+        let mut tx = self
+            .db
+            .begin()
+            .await
+            .map_err(|err| DBError::Other(Box::new(err)))?;
+        set_actor(&mut tx)
+            .await
+            .map_err(|err| DBError::Other(Box::new(err)))?;
+
+        let question = insert_question(&mut tx, question).await?;
+        let inserted = sqlx::query!(
+            r#"
+                INSERT INTO ingested_questions ( idempotency_key, question_uuid )
+                VALUES ( $1, $2 )
+                ON CONFLICT ( idempotency_key ) DO NOTHING
+            "#,
+            idempotency_key,
+            question.question_uuid.0,
+        )
+        .execute(&mut tx)
+        .await
+        .map_err(|err| DBError::Other(Box::new(err)))?;
+
+        if inserted.rows_affected() == 1 {
+            tx.commit()
+                .await
+                .map_err(|err| DBError::Other(Box::new(err)))?;
+            return Ok(Ingested::Created(question));
+        }
+        tx.rollback()
+            .await
+            .map_err(|err| DBError::Other(Box::new(err)))?;
+
+        let question_uuid = sqlx::query_scalar!(
+            "SELECT question_uuid FROM ingested_questions WHERE idempotency_key = $1",
+            idempotency_key,
+        )
+        .fetch_one(&self.db)
+        .await
+        .map_err(|err| DBError::Other(Box::new(err)))?;
+        Ok(Ingested::Duplicate(QuestionUuid(question_uuid)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        models::empty_metadata,
+        persistence::question_dao::{QuestionDao, QuestionDaoImpl},
+    };
+
+    #[sqlx::test]
+    async fn create_question_once_should_skip_known_keys(pool: PgPool) -> Result<(), String> {
+        let dao = PgIngestionDao::new(pool.clone());
+        let question = Question {
+            title: "title".to_owned(),
+            description: None,
+            metadata: empty_metadata(),
+        };
+
+        let Ingested::Created(created) = dao
+            .create_question_once("crm-42", question.clone())
+            .await
+            .unwrap()
+        else {
+            panic!("The first delivery should create the question");
+        };
+        let again = dao.create_question_once("crm-42", question).await.unwrap();
+        assert_eq!(again, Ingested::Duplicate(created.question_uuid));
+
+        let questions = QuestionDaoImpl::new(pool).get_questions().await.unwrap();
+        assert_eq!(questions, vec![created]);
+        Ok(())
+    }
+}
@@ -0,0 +1,298 @@
+use std::{future::Future, time::Instant};
+
+use async_stream::stream;
+use async_trait::async_trait;
+use futures::StreamExt;
+use log::{error, info};
+use tracing::Instrument;
+
+use super::{answer_dao::AnswerDao, question_dao::QuestionDao, RowStream};
+use crate::{
+    metrics,
+    models::{
+        Answer, AnswerDetail, AnswerSort, AnswerUuid, DBError, Listed, Question, QuestionDetail,
+        QuestionFilter, QuestionSort, QuestionUuid, Window,
+    },
+};
+
+// Runs `call` in a `dao` span, records its duration and logs failures, so handlers
+// only have to map errors.
+async fn instrument<T>(
+    dao: &'static str,
+    method: &'static str,
+    call: impl Future<Output = Result<T, DBError>>,
+) -> Result<T, DBError> {
+    let span = tracing::info_span!("dao", dao, method);
+    let started = Instant::now();
+    let result = call.instrument(span).await;
+    record(dao, method, started, &result);
+
+    result
+}
+
+// A stream counts as a single call, lasting until its last row or its first error.
+fn instrument_stream<'a, T: Send + 'a>(
+    dao: &'static str,
+    method: &'static str,
+    mut rows: RowStream<'a, T>,
+) -> RowStream<'a, T> {
+    Box::pin(stream! {
+        let started = Instant::now();
+        while let Some(row) = rows.next().await {
+            if row.is_err() {
+                record(dao, method, started, &row);
+                yield row;
+                return;
+            }
+            yield row;
+        }
+        record(dao, method, started, &Ok::<(), DBError>(()));
+    })
+}
+
+fn record<T>(
+    dao: &'static str,
+    method: &'static str,
+    started: Instant,
+    result: &Result<T, DBError>,
+) {
+    let outcome = match result {
+        Ok(_) => "ok",
+        // Caused by the request rather than the database.
+        Err(err @ (DBError::InvalidUUID(_) | DBError::NotFound(..) | DBError::Conflict(_))) => {
+            info!("{}.{} rejected: {}", dao, method, err);
+            "rejected"
+        }
+        Err(err) => {
+            error!("{}.{} failed: {:?}", dao, method, err);
+            "error"
+        }
+    };
+    metrics::DAO_CALL_DURATION
+        .with_label_values(&[dao, method, outcome])
+        .observe(started.elapsed().as_secs_f64());
+}
+
+pub struct InstrumentedQuestionDao<T> {
+    inner: T,
+}
+
+impl<T> InstrumentedQuestionDao<T> {
+    pub fn new(inner: T) -> Self {
+        Self { inner }
+    }
+}
+
+#[async_trait]
+impl<T: QuestionDao + Send + Sync> QuestionDao for InstrumentedQuestionDao<T> {
+    async fn create_question(&self, question: Question) -> Result<QuestionDetail, DBError> {
+        instrument(
+            "question",
+            "create_question",
+            self.inner.create_question(question),
+        )
+        .await
+    }
+
+    async fn delete_question(&self, question_uuid: QuestionUuid) -> Result<(), DBError> {
+        instrument(
+            "question",
+            "delete_question",
+            self.inner.delete_question(question_uuid),
+        )
+        .await
+    }
+
+    async fn update_question(
+        &self,
+        question_uuid: QuestionUuid,
+        question: Question,
+        version: i64,
+    ) -> Result<QuestionDetail, DBError> {
+        instrument(
+            "question",
+            "update_question",
+            self.inner.update_question(question_uuid, question, version),
+        )
+        .await
+    }
+
+    async fn get_questions(&self) -> Result<Vec<QuestionDetail>, DBError> {
+        instrument("question", "get_questions", self.inner.get_questions()).await
+    }
+
+    async fn get_question(&self, question_uuid: QuestionUuid) -> Result<QuestionDetail, DBError> {
+        instrument(
+            "question",
+            "get_question",
+            self.inner.get_question(question_uuid),
+        )
+        .await
+    }
+
+    async fn get_questions_matching(
+        &self,
+        filter: &QuestionFilter,
+    ) -> Result<Vec<QuestionDetail>, DBError> {
+        instrument(
+            "question",
+            "get_questions_matching",
+            self.inner.get_questions_matching(filter),
+        )
+        .await
+    }
+
+    fn stream_questions(&self, filter: QuestionFilter) -> RowStream<'_, QuestionDetail> {
+        instrument_stream(
+            "question",
+            "stream_questions",
+            self.inner.stream_questions(filter),
+        )
+    }
+
+    async fn list_questions(
+        &self,
+        filter: &QuestionFilter,
+        include_archived: bool,
+        sort: QuestionSort,
+        window: Window,
+    ) -> Result<Listed<QuestionDetail>, DBError> {
+        instrument(
+            "question",
+            "list_questions",
+            self.inner
+                .list_questions(filter, include_archived, sort, window),
+        )
+        .await
+    }
+
+    async fn list_unanswered_questions(
+        &self,
+        window: Window,
+    ) -> Result<Listed<QuestionDetail>, DBError> {
+        instrument(
+            "question",
+            "list_unanswered_questions",
+            self.inner.list_unanswered_questions(window),
+        )
+        .await
+    }
+}
+
+pub struct InstrumentedAnswerDao<T> {
+    inner: T,
+}
+
+impl<T> InstrumentedAnswerDao<T> {
+    pub fn new(inner: T) -> Self {
+        Self { inner }
+    }
+}
+
+#[async_trait]
+impl<T: AnswerDao + Send + Sync> AnswerDao for InstrumentedAnswerDao<T> {
+    async fn create_answer(&self, answer: Answer) -> Result<AnswerDetail, DBError> {
+        instrument("answer", "create_answer", self.inner.create_answer(answer)).await
+    }
+
+    async fn delete_answer(&self, answer_uuid: AnswerUuid) -> Result<(), DBError> {
+        instrument(
+            "answer",
+            "delete_answer",
+            self.inner.delete_answer(answer_uuid),
+        )
+        .await
+    }
+
+    async fn update_answer(
+        &self,
+        answer_uuid: AnswerUuid,
+        content: String,
+        version: i64,
+    ) -> Result<AnswerDetail, DBError> {
+        instrument(
+            "answer",
+            "update_answer",
+            self.inner.update_answer(answer_uuid, content, version),
+        )
+        .await
+    }
+
+    async fn get_answers(&self, question_uuid: QuestionUuid) -> Result<Vec<AnswerDetail>, DBError> {
+        instrument(
+            "answer",
+            "get_answers",
+            self.inner.get_answers(question_uuid),
+        )
+        .await
+    }
+
+    fn stream_answers(&self, question_uuid: QuestionUuid) -> RowStream<'_, AnswerDetail> {
+        instrument_stream(
+            "answer",
+            "stream_answers",
+            self.inner.stream_answers(question_uuid),
+        )
+    }
+
+    async fn get_answers_of_questions(
+        &self,
+        question_uuids: &[QuestionUuid],
+    ) -> Result<Vec<AnswerDetail>, DBError> {
+        instrument(
+            "answer",
+            "get_answers_of_questions",
+            self.inner.get_answers_of_questions(question_uuids),
+        )
+        .await
+    }
+
+    async fn list_answers(
+        &self,
+        question_uuid: QuestionUuid,
+        include_archived: bool,
+        sort: AnswerSort,
+        window: Window,
+    ) -> Result<Listed<AnswerDetail>, DBError> {
+        instrument(
+            "answer",
+            "list_answers",
+            self.inner
+                .list_answers(question_uuid, include_archived, sort, window),
+        )
+        .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        models::{empty_metadata, Question},
+        persistence::memory::{InMemoryQuestionDao, MemoryStore},
+    };
+
+    #[tokio::test]
+    async fn calls_should_be_recorded_by_outcome() {
+        let dao = InstrumentedQuestionDao::new(InMemoryQuestionDao::new(MemoryStore::new()));
+        let count = |outcome: &str| {
+            metrics::DAO_CALL_DURATION
+                .with_label_values(&["question", "update_question", outcome])
+                .get_sample_count()
+        };
+        let (ok, rejected) = (count("ok"), count("rejected"));
+
+        dao.get_questions().await.unwrap();
+        let question = Question {
+            title: "title".to_owned(),
+            description: Some("description".to_owned()),
+            metadata: empty_metadata(),
+        };
+        dao.update_question(QuestionUuid::new_v4(), question, 1)
+            .await
+            .unwrap_err();
+
+        assert_eq!(count("ok"), ok);
+        assert_eq!(count("rejected"), rejected + 1);
+    }
+}
@@ -0,0 +1,145 @@
+use std::{sync::Arc, time::Duration};
+
+use log::{error, warn};
+use serde::Deserialize;
+use sqlx::{postgres::PgListener, types::Uuid, PgPool};
+use tokio::task::JoinHandle;
+
+use super::cache::QueryCache;
+
+// Channel written to by the `notify_cache_invalidation` trigger.
+pub const CACHE_INVALIDATION_CHANNEL: &str = "cache_invalidation";
+
+// Sent once by bulk writes that skip the per-row trigger.
+pub const INVALIDATE_ALL: &str = r#"{"table":"*"}"#;
+
+#[derive(Deserialize)]
+struct Invalidation {
+    table: String,
+    #[serde(default)]
+    question_uuid: Option<Uuid>,
+}
+
+pub fn apply(cache: &QueryCache, payload: &str) {
+    let invalidation: Invalidation = match serde_json::from_str(payload) {
+        Ok(invalidation) => invalidation,
+        Err(err) => {
+            warn!("Unreadable cache invalidation {:?}: {}", payload, err);
+            cache.clear();
+            return;
+        }
+    };
+
+    match (invalidation.table.as_str(), invalidation.question_uuid) {
+        ("questions", Some(question_uuid)) => {
+            cache.invalidate_questions();
+            cache.invalidate_answers(&question_uuid);
+        }
+        ("answers", Some(question_uuid)) => cache.invalidate_answers(&question_uuid),
+        _ => cache.clear(),
+    }
+}
+
+// Keeps the cache of this instance in sync with writes made by any instance.
+pub fn spawn_listener(pool: PgPool, cache: Arc<QueryCache>) -> JoinHandle<()> {
+    tokio::spawn(async move {
+        loop {
+            match listen(&pool, &cache).await {
+                Err(sqlx::Error::PoolClosed) => return,
+                Err(err) => error!("Cache invalidation listener failed: {}", err),
+                Ok(()) => {}
+            }
+
+            // Notifications may have been missed while disconnected.
+            cache.clear();
+            tokio::time::sleep(Duration::from_secs(1)).await;
+        }
+    })
+}
+
+// Returns once the connection is lost so the caller can resync.
+async fn listen(pool: &PgPool, cache: &QueryCache) -> Result<(), sqlx::Error> {
+    let mut listener = PgListener::connect_with(pool).await?;
+    listener.listen(CACHE_INVALIDATION_CHANNEL).await?;
+
+    while let Some(notification) = listener.try_recv().await? {
+        apply(cache, notification.payload());
+    }
+
+    warn!("Lost the cache invalidation connection, reconnecting.");
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        models::{empty_metadata, Question},
+        persistence::question_dao::{QuestionDao, QuestionDaoImpl},
+    };
+
+    #[test]
+    fn apply_should_invalidate_answers_of_question() {
+        let cache = QueryCache::new(Duration::from_secs(60));
+        let question_uuid = Uuid::new_v4();
+        cache.store_answers(cache.generation(), question_uuid, vec![]);
+
+        apply(
+            &cache,
+            &format!(
+                r#"{{"table":"answers","question_uuid":"{}"}}"#,
+                question_uuid
+            ),
+        );
+
+        assert_eq!(cache.answers(&question_uuid), None);
+    }
+
+    #[test]
+    fn apply_should_clear_everything_on_unknown_payload() {
+        let cache = QueryCache::new(Duration::from_secs(60));
+        cache.store_questions(cache.generation(), vec![]);
+
+        apply(&cache, "garbage");
+
+        assert_eq!(cache.questions(), None);
+    }
+
+    #[test]
+    fn apply_should_clear_everything_on_invalidate_all() {
+        let cache = QueryCache::new(Duration::from_secs(60));
+        cache.store_questions(cache.generation(), vec![]);
+
+        apply(&cache, INVALIDATE_ALL);
+
+        assert_eq!(cache.questions(), None);
+    }
+
+    #[sqlx::test]
+    async fn listener_should_invalidate_on_writes(pool: PgPool) -> Result<(), String> {
+        let cache = Arc::new(QueryCache::new(Duration::from_secs(60)));
+        let listener = spawn_listener(pool.clone(), cache.clone());
+        tokio::time::sleep(Duration::from_millis(200)).await;
+
+        cache.store_questions(cache.generation(), vec![]);
+        QuestionDaoImpl::new(pool.clone())
+            .create_question(Question {
+                title: "title".to_owned(),
+                description: Some("description".to_owned()),
+                metadata: empty_metadata(),
+            })
+            .await
+            .map_err(|err| err.to_string())?;
+
+        for _ in 0..50 {
+            if cache.questions().is_none() {
+                listener.abort();
+                return Ok(());
+            }
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+
+        listener.abort();
+        Err("Expected the question list to be invalidated".to_owned())
+    }
+}
@@ -0,0 +1,130 @@
+use async_trait::async_trait;
+use sqlx::PgPool;
+
+use super::acquire;
+use crate::models::DBError;
+
+// What a page says about itself in its OpenGraph tags, or its title and description.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LinkPreview {
+    pub url: String,
+    pub title: Option<String>,
+    pub description: Option<String>,
+    pub image_url: Option<String>,
+    pub site_name: Option<String>,
+}
+
+// Previews by URL, shared by every question linking to it.
+#[async_trait]
+pub trait LinkPreviewDao {
+    // The stored previews of `urls`, in their order; URLs without one are left out.
+    async fn get_link_previews(&self, urls: &[String]) -> Result<Vec<LinkPreview>, DBError>;
+    // Replaces the preview of the same URL.
+    async fn store_link_preview(&self, preview: LinkPreview) -> Result<(), DBError>;
+}
+
+pub struct PgLinkPreviewDao {
+    db: PgPool,
+}
+
+impl PgLinkPreviewDao {
+    pub fn new(db: PgPool) -> Self {
+        Self { db }
+    }
+}
+
+#[async_trait]
+impl LinkPreviewDao for PgLinkPreviewDao {
+    async fn get_link_previews(&self, urls: &[String]) -> Result<Vec<LinkPreview>, DBError> {
+        let mut conn = acquire(&self.db).await?;
+
+        let rows = sqlx::query_as!(
+            LinkPreview,
+            r#"
+                SELECT url, title, description, image_url, site_name FROM link_previews
+                WHERE url = ANY($1)
+            "#,
+            urls,
+        )
+        .fetch_all(&mut conn)
+        .await
+        .map_err(|err| DBError::Other(Box::new(err)))?;
+
+        let mut previews = rows;
+        previews.sort_by_key(|preview| urls.iter().position(|url| *url == preview.url));
+        Ok(previews)
+    }
+
+    async fn store_link_preview(&self, preview: LinkPreview) -> Result<(), DBError> {
+        let mut conn = acquire(&self.db).await?;
+
+        sqlx::query!(
+            r#"
+                INSERT INTO link_previews ( url, title, description, image_url, site_name )
+                VALUES ( $1, $2, $3, $4, $5 )
+                ON CONFLICT ( url ) DO UPDATE
+                SET title = EXCLUDED.title, description = EXCLUDED.description,
+                    image_url = EXCLUDED.image_url, site_name = EXCLUDED.site_name,
+                    fetched_at = LOCALTIMESTAMP
+            "#,
+            preview.url,
+            preview.title,
+            preview.description,
+            preview.image_url,
+            preview.site_name,
+        )
+        .execute(&mut conn)
+        .await
+        .map_err(|err| DBError::Other(Box::new(err)))?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn preview(url: &str, title: &str) -> LinkPreview {
+        LinkPreview {
+            url: url.to_owned(),
+            title: Some(title.to_owned()),
+            description: None,
+            image_url: None,
+            site_name: None,
+        }
+    }
+
+    #[sqlx::test]
+    async fn link_previews_should_come_in_the_order_asked(pool: PgPool) -> Result<(), String> {
+        let dao = PgLinkPreviewDao::new(pool);
+        dao.store_link_preview(preview("https://a.example", "old"))
+            .await
+            .unwrap();
+        dao.store_link_preview(preview("https://a.example", "A"))
+            .await
+            .unwrap();
+        dao.store_link_preview(preview("https://b.example", "B"))
+            .await
+            .unwrap();
+
+        let previews = dao
+            .get_link_previews(&[
+                "https://b.example".to_owned(),
+                "https://unknown.example".to_owned(),
+                "https://a.example".to_owned(),
+            ])
+            .await
+            .unwrap();
+
+        assert_eq!(
+            previews,
+            [
+                preview("https://b.example", "B"),
+                preview("https://a.example", "A")
+            ]
+        );
+
+        Ok(())
+    }
+}
@@ -0,0 +1,90 @@
+use std::fmt;
+
+use sqlx::PgConnection;
+
+use crate::models::DBError;
+
+// Maintenance that must not run on two instances at once. Each one maps to a
+// Postgres advisory lock; the keys only need to differ from each other.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Lock {
+    Archive,
+    Backup,
+    Import,
+    OutboxRelay,
+    PartitionMaintenance,
+    StatsRefresh,
+}
+
+impl Lock {
+    fn key(self) -> i64 {
+        match self {
+            Lock::Archive => 0x7161_0001,
+            Lock::Import => 0x7161_0002,
+            Lock::OutboxRelay => 0x7161_0003,
+            Lock::PartitionMaintenance => 0x7161_0004,
+            Lock::StatsRefresh => 0x7161_0005,
+            Lock::Backup => 0x7161_0006,
+        }
+    }
+}
+
+impl fmt::Display for Lock {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Lock::Archive => "Archiving",
+            Lock::Backup => "A backup",
+            Lock::Import => "An import",
+            Lock::OutboxRelay => "Relaying outbox events",
+            Lock::PartitionMaintenance => "Creating answer partitions",
+            Lock::StatsRefresh => "Refreshing stats",
+        })
+    }
+}
+
+// Takes `lock` until the end of the transaction `conn` is in, unless another session
+// holds it. Outside of a transaction the lock is released right away, so call it on
+// one.
+pub async fn try_lock(conn: &mut PgConnection, lock: Lock) -> Result<bool, sqlx::Error> {
+    sqlx::query_scalar!(
+        r#"SELECT pg_try_advisory_xact_lock($1) AS "locked!""#,
+        lock.key(),
+    )
+    .fetch_one(conn)
+    .await
+}
+
+// Why an operation that found its lock taken did not run.
+pub fn lock_held(lock: Lock) -> DBError {
+    DBError::Conflict(format!("{} is already running; try again later", lock))
+}
+
+#[cfg(test)]
+mod tests {
+    use sqlx::PgPool;
+
+    use super::*;
+
+    #[sqlx::test]
+    async fn try_lock_should_exclude_other_sessions_until_commit(
+        pool: PgPool,
+    ) -> Result<(), String> {
+        let mut first = pool.begin().await.map_err(|err| err.to_string())?;
+        let mut second = pool.begin().await.map_err(|err| err.to_string())?;
+
+        let locked = try_lock(&mut first, Lock::Archive).await;
+        assert!(matches!(locked, Ok(true)));
+        let locked = try_lock(&mut second, Lock::Archive).await;
+        assert!(matches!(locked, Ok(false)));
+        let locked = try_lock(&mut second, Lock::Import).await;
+        assert!(matches!(locked, Ok(true)));
+
+        first.commit().await.map_err(|err| err.to_string())?;
+        second.rollback().await.map_err(|err| err.to_string())?;
+
+        let mut third = pool.begin().await.map_err(|err| err.to_string())?;
+        let locked = try_lock(&mut third, Lock::Archive).await;
+        assert!(matches!(locked, Ok(true)));
+        Ok(())
+    }
+}
@@ -0,0 +1,2520 @@
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap, HashSet},
+    sync::{Arc, RwLock},
+    time::Duration,
+};
+
+use async_stream::try_stream;
+use async_trait::async_trait;
+use chrono::{DateTime, NaiveDate, Utc};
+use futures::{stream, StreamExt};
+use uuid::Uuid;
+
+use super::{
+    activity::{ActivityDao, ACTIVITY_MAX_LIMIT},
+    answer_dao::AnswerDao,
+    archive::ArchiveDao,
+    attachment::AttachmentDao,
+    blocked_deletion,
+    duplicate::{DuplicateDao, SimilarAnswer},
+    embedding::EmbeddingDao,
+    ingestion::{Ingested, IngestionDao},
+    link_preview::{LinkPreview, LinkPreviewDao},
+    merge::MergeDao,
+    missed_update,
+    moderation::ModerationDao,
+    question_dao::{QuestionDao, QuestionDeletion},
+    rest_hook::RestHookDao,
+    search::{marked_html, Highlight, SearchDao, TitleMatch, MATCH_END, MATCH_START},
+    stats::StatsDao,
+    subscription::SubscriptionDao,
+    tag::{TagDao, TaggedQuestion},
+    translation::{Translation, TranslationDao},
+    unit_of_work::{UnitOfWork, UnitOfWorkFactory},
+    unknown_question,
+    user::UserDao,
+    word_filter::WordFilterDao,
+    RowStream,
+};
+use crate::{
+    embedding::cosine_distance,
+    events::OutboxEvent,
+    models::{
+        Activity, ActivityKind, Answer, AnswerDetail, AnswerSort, AnswerUuid, ArchiveSummary,
+        Attachment, AttachmentStatus, AttachmentUuid, AttachmentVariant, DBError, DailyStats,
+        Digest, DigestAnswer, DigestFrequency, DueDigest, Entity, HookUuid, Listed, MergeSummary,
+        ModerationItem, ModerationUuid, NewAttachment, NewModerationItem, ProvisionedUser,
+        PushSubscription, Question, QuestionDetail, QuestionFilter, QuestionSort,
+        QuestionTranslation, QuestionUuid, ResponseStats, RestHook, Subscribers, TrendingQuestion,
+        UserAttributes, UserUuid, Window,
+    },
+};
+
+struct Row<T> {
+    // Insertion order, so lists come back in the order a database would return them.
+    position: u64,
+    // Last edit, for finding inactive questions to archive.
+    edited: DateTime<Utc>,
+    value: T,
+}
+
+#[derive(Default)]
+struct Tables {
+    next_position: u64,
+    questions: HashMap<QuestionUuid, Row<QuestionDetail>>,
+    answers: HashMap<AnswerUuid, Row<AnswerDetail>>,
+    archived_questions: HashMap<QuestionUuid, Row<QuestionDetail>>,
+    archived_answers: HashMap<AnswerUuid, Row<AnswerDetail>>,
+    // Dropped lazily: entries of deleted questions are skipped by searches.
+    embeddings: HashMap<QuestionUuid, Vec<f32>>,
+    // In subscription order; dropped lazily like embeddings.
+    subscriptions: Vec<(QuestionUuid, String)>,
+    opted_out: HashSet<String>,
+    // Frequency and when the last digest was sent, or the address signed up.
+    digests: HashMap<String, (DigestFrequency, DateTime<Utc>)>,
+    // In subscription order, with the questions each follows; dropped lazily too.
+    push_subscriptions: Vec<(PushSubscription, Vec<QuestionUuid>)>,
+    // In creation order; dropped lazily too.
+    attachments: Vec<Attachment>,
+    // Oldest first.
+    moderation_queue: Vec<ModerationItem>,
+    // By entity, row and language; dropped lazily too.
+    translations: HashMap<(&'static str, Uuid, String), Translation>,
+    // By question and language; dropped lazily too.
+    question_translations: BTreeMap<(Uuid, String), QuestionTranslation>,
+    link_previews: HashMap<String, LinkPreview>,
+    ingested_questions: HashMap<String, QuestionUuid>,
+    filtered_words: BTreeSet<String>,
+    // Oldest first.
+    rest_hooks: Vec<RestHook>,
+    // Oldest first.
+    users: Vec<ProvisionedUser>,
+    // Merged questions and where they went.
+    redirects: HashMap<QuestionUuid, QuestionUuid>,
+}
+
+impl Tables {
+    fn insert_question(&mut self, question: QuestionDetail) {
+        self.next_position += 1;
+        let row = Row {
+            position: self.next_position,
+            edited: Utc::now(),
+            value: question,
+        };
+        self.questions.insert(row.value.question_uuid, row);
+    }
+
+    // Mirrors the answer_count and last_activity_at triggers.
+    fn insert_answer(&mut self, answer: AnswerDetail) {
+        if let Some(question) = self.questions.get_mut(&answer.question_uuid) {
+            question.value.answer_count += 1;
+        }
+        self.touch_question(&answer.question_uuid, answer.updated_at);
+
+        self.next_position += 1;
+        let row = Row {
+            position: self.next_position,
+            edited: Utc::now(),
+            value: answer,
+        };
+        self.answers.insert(row.value.answer_uuid, row);
+    }
+
+    fn touch_question(&mut self, question_uuid: &QuestionUuid, at: DateTime<Utc>) {
+        if let Some(question) = self.questions.get_mut(question_uuid) {
+            question.value.last_activity_at = question.value.last_activity_at.max(at);
+        }
+    }
+
+    // Mirrors the answer_count and last_activity_at triggers.
+    fn remove_answer(&mut self, answer_uuid: &AnswerUuid) {
+        let Some(answer) = self.answers.remove(answer_uuid) else {
+            return;
+        };
+
+        if let Some(question) = self.questions.get_mut(&answer.value.question_uuid) {
+            question.value.answer_count -= 1;
+        }
+        self.touch_question(&answer.value.question_uuid, Utc::now());
+    }
+}
+
+// Process-local storage shared by the in-memory DAOs. Everything is lost on restart.
+#[derive(Clone, Default)]
+pub struct MemoryStore {
+    tables: Arc<RwLock<Tables>>,
+}
+
+impl MemoryStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+fn new_question(question: Question) -> QuestionDetail {
+    let created_at = Utc::now();
+    QuestionDetail {
+        question_uuid: QuestionUuid::new_v4(),
+        title: question.title,
+        description: question.description,
+        metadata: question.metadata,
+        updated_at: created_at,
+        last_activity_at: created_at,
+        created_at,
+        answer_count: 0,
+        version: 1,
+    }
+}
+
+fn new_answer(question_uuid: QuestionUuid, content: String) -> AnswerDetail {
+    let created_at = Utc::now();
+    AnswerDetail {
+        answer_uuid: AnswerUuid::new_v4(),
+        question_uuid,
+        content,
+        updated_at: created_at,
+        created_at,
+        version: 1,
+    }
+}
+
+fn in_order<'a, T: Clone + 'a>(rows: impl Iterator<Item = &'a Row<T>>) -> Vec<T> {
+    let mut rows: Vec<_> = rows.collect();
+    rows.sort_by_key(|row| row.position);
+    rows.into_iter().map(|row| row.value.clone()).collect()
+}
+
+pub struct InMemoryQuestionDao {
+    store: MemoryStore,
+    deletion: QuestionDeletion,
+}
+
+impl InMemoryQuestionDao {
+    pub fn new(store: MemoryStore) -> Self {
+        Self {
+            store,
+            deletion: QuestionDeletion::Cascade,
+        }
+    }
+
+    pub fn with_deletion(mut self, deletion: QuestionDeletion) -> Self {
+        self.deletion = deletion;
+        self
+    }
+}
+
+#[async_trait]
+impl QuestionDao for InMemoryQuestionDao {
+    async fn create_question(&self, question: Question) -> Result<QuestionDetail, DBError> {
+        let question = new_question(question);
+        self.store
+            .tables
+            .write()
+            .unwrap()
+            .insert_question(question.clone());
+
+        Ok(question)
+    }
+
+    async fn delete_question(&self, question_uuid: QuestionUuid) -> Result<(), DBError> {
+        let mut tables = self.store.tables.write().unwrap();
+        if let Some(row) = tables.questions.get(&question_uuid) {
+            if self.deletion == QuestionDeletion::Restrict && row.value.answer_count > 0 {
+                return Err(blocked_deletion(
+                    &question_uuid.to_string(),
+                    row.value.answer_count,
+                ));
+            }
+        }
+        tables.questions.remove(&question_uuid);
+        // Mirrors ON DELETE CASCADE.
+        tables
+            .answers
+            .retain(|_, answer| answer.value.question_uuid != question_uuid);
+
+        Ok(())
+    }
+
+    async fn update_question(
+        &self,
+        question_uuid: QuestionUuid,
+        question: Question,
+        version: i64,
+    ) -> Result<QuestionDetail, DBError> {
+        let mut tables = self.store.tables.write().unwrap();
+        let row = match tables.questions.get_mut(&question_uuid) {
+            Some(row) if row.value.version == version => row,
+            row => {
+                let current_version = row.map(|row| row.value.version);
+                return Err(missed_update(
+                    Entity::Question,
+                    &question_uuid.to_string(),
+                    current_version,
+                    version,
+                ));
+            }
+        };
+
+        if row.value.title != question.title
+            || row.value.description != question.description
+            || row.value.metadata != question.metadata
+        {
+            row.value.title = question.title;
+            row.value.description = question.description;
+            row.value.metadata = question.metadata;
+            row.value.updated_at = Utc::now();
+            row.value.last_activity_at = row.value.updated_at;
+            row.edited = Utc::now();
+        }
+        row.value.version += 1;
+
+        Ok(row.value.clone())
+    }
+
+    async fn get_questions(&self) -> Result<Vec<QuestionDetail>, DBError> {
+        let tables = self.store.tables.read().unwrap();
+        Ok(in_order(tables.questions.values()))
+    }
+
+    async fn get_question(&self, question_uuid: QuestionUuid) -> Result<QuestionDetail, DBError> {
+        let tables = self.store.tables.read().unwrap();
+        tables
+            .questions
+            .get(&question_uuid)
+            .map(|question| question.value.clone())
+            .ok_or_else(|| unknown_question(&question_uuid))
+    }
+
+    async fn get_questions_matching(
+        &self,
+        filter: &QuestionFilter,
+    ) -> Result<Vec<QuestionDetail>, DBError> {
+        let tables = self.store.tables.read().unwrap();
+        Ok(in_order(
+            tables
+                .questions
+                .values()
+                .filter(|question| filter.matches(&question.value)),
+        ))
+    }
+
+    async fn list_questions(
+        &self,
+        filter: &QuestionFilter,
+        include_archived: bool,
+        sort: QuestionSort,
+        window: Window,
+    ) -> Result<Listed<QuestionDetail>, DBError> {
+        let tables = self.store.tables.read().unwrap();
+        let archived = tables
+            .archived_questions
+            .values()
+            .filter(|_| include_archived);
+        let mut questions = in_order(
+            tables
+                .questions
+                .values()
+                .chain(archived)
+                .filter(|question| filter.matches(&question.value)),
+        );
+        sort.sort(&mut questions);
+        Ok(window.of(questions))
+    }
+
+    async fn list_unanswered_questions(
+        &self,
+        window: Window,
+    ) -> Result<Listed<QuestionDetail>, DBError> {
+        let tables = self.store.tables.read().unwrap();
+        let mut questions = in_order(
+            tables
+                .questions
+                .values()
+                .filter(|question| question.value.answer_count == 0),
+        );
+        QuestionSort::Oldest.sort(&mut questions);
+        Ok(window.of(questions))
+    }
+
+    // The rows are in memory anyway; streaming a snapshot keeps the lock
+    // from being held at the pace of the consumer.
+    fn stream_questions(&self, filter: QuestionFilter) -> RowStream<'_, QuestionDetail> {
+        Box::pin(try_stream! {
+            for question in self.get_questions_matching(&filter).await? {
+                yield question;
+            }
+        })
+    }
+}
+
+pub struct InMemoryAnswerDao {
+    store: MemoryStore,
+}
+
+impl InMemoryAnswerDao {
+    pub fn new(store: MemoryStore) -> Self {
+        Self { store }
+    }
+}
+
+#[async_trait]
+impl AnswerDao for InMemoryAnswerDao {
+    async fn create_answer(&self, answer: Answer) -> Result<AnswerDetail, DBError> {
+        let question_uuid = answer.question_uuid;
+
+        let mut tables = self.store.tables.write().unwrap();
+        if !tables.questions.contains_key(&question_uuid) {
+            return Err(unknown_question(&question_uuid));
+        }
+
+        let answer = new_answer(question_uuid, answer.content);
+        tables.insert_answer(answer.clone());
+
+        Ok(answer)
+    }
+
+    async fn delete_answer(&self, answer_uuid: AnswerUuid) -> Result<(), DBError> {
+        self.store
+            .tables
+            .write()
+            .unwrap()
+            .remove_answer(&answer_uuid);
+
+        Ok(())
+    }
+
+    async fn update_answer(
+        &self,
+        answer_uuid: AnswerUuid,
+        content: String,
+        version: i64,
+    ) -> Result<AnswerDetail, DBError> {
+        let mut tables = self.store.tables.write().unwrap();
+        let row = match tables.answers.get_mut(&answer_uuid) {
+            Some(row) if row.value.version == version => row,
+            row => {
+                let current_version = row.map(|row| row.value.version);
+                return Err(missed_update(
+                    Entity::Answer,
+                    &answer_uuid.to_string(),
+                    current_version,
+                    version,
+                ));
+            }
+        };
+
+        let edited = row.value.content != content;
+        if edited {
+            row.value.content = content;
+            row.value.updated_at = Utc::now();
+            row.edited = Utc::now();
+        }
+        row.value.version += 1;
+
+        let answer = row.value.clone();
+        if edited {
+            tables.touch_question(&answer.question_uuid, answer.updated_at);
+        }
+        Ok(answer)
+    }
+
+    async fn get_answers(&self, question_uuid: QuestionUuid) -> Result<Vec<AnswerDetail>, DBError> {
+        let tables = self.store.tables.read().unwrap();
+        Ok(in_order(tables.answers.values().filter(|answer| {
+            answer.value.question_uuid == question_uuid
+        })))
+    }
+
+    fn stream_answers(&self, question_uuid: QuestionUuid) -> RowStream<'_, AnswerDetail> {
+        Box::pin(try_stream! {
+            for answer in self.get_answers(question_uuid).await? {
+                yield answer;
+            }
+        })
+    }
+
+    async fn list_answers(
+        &self,
+        question_uuid: QuestionUuid,
+        include_archived: bool,
+        sort: AnswerSort,
+        window: Window,
+    ) -> Result<Listed<AnswerDetail>, DBError> {
+        let tables = self.store.tables.read().unwrap();
+        let archived = tables
+            .archived_answers
+            .values()
+            .filter(|_| include_archived);
+        let mut answers = in_order(
+            tables
+                .answers
+                .values()
+                .chain(archived)
+                .filter(|answer| answer.value.question_uuid == question_uuid),
+        );
+        sort.sort(&mut answers);
+        Ok(window.of(answers))
+    }
+
+    async fn get_answers_of_questions(
+        &self,
+        question_uuids: &[QuestionUuid],
+    ) -> Result<Vec<AnswerDetail>, DBError> {
+        let tables = self.store.tables.read().unwrap();
+        Ok(in_order(tables.answers.values().filter(|answer| {
+            question_uuids.contains(&answer.value.question_uuid)
+        })))
+    }
+}
+
+pub struct InMemoryArchiveDao {
+    store: MemoryStore,
+}
+
+impl InMemoryArchiveDao {
+    pub fn new(store: MemoryStore) -> Self {
+        Self { store }
+    }
+}
+
+#[async_trait]
+impl ArchiveDao for InMemoryArchiveDao {
+    async fn archive(&self, older_than_days: u32) -> Result<ArchiveSummary, DBError> {
+        let cutoff = Utc::now() - Duration::from_secs(u64::from(older_than_days) * 86_400);
+
+        let mut tables = self.store.tables.write().unwrap();
+        let active: HashSet<QuestionUuid> = tables
+            .answers
+            .values()
+            .filter(|answer| answer.edited >= cutoff)
+            .map(|answer| answer.value.question_uuid)
+            .collect();
+        let inactive: Vec<QuestionUuid> = tables
+            .questions
+            .iter()
+            .filter(|(_, question)| {
+                question.edited < cutoff && !active.contains(&question.value.question_uuid)
+            })
+            .map(|(question_uuid, _)| *question_uuid)
+            .collect();
+
+        let mut summary = ArchiveSummary {
+            questions: 0,
+            answers: 0,
+        };
+        for question_uuid in inactive {
+            let question = tables.questions.remove(&question_uuid).unwrap();
+            tables.archived_questions.insert(question_uuid, question);
+            summary.questions += 1;
+
+            let answers: Vec<AnswerUuid> = tables
+                .answers
+                .iter()
+                .filter(|(_, answer)| answer.value.question_uuid == question_uuid)
+                .map(|(answer_uuid, _)| *answer_uuid)
+                .collect();
+            for answer_uuid in answers {
+                let answer = tables.answers.remove(&answer_uuid).unwrap();
+                tables.archived_answers.insert(answer_uuid, answer);
+                summary.answers += 1;
+            }
+        }
+
+        Ok(summary)
+    }
+
+    fn stream_archived_questions(&self, filter: QuestionFilter) -> RowStream<'_, QuestionDetail> {
+        let tables = self.store.tables.read().unwrap();
+        let questions = in_order(
+            tables
+                .archived_questions
+                .values()
+                .filter(|question| filter.matches(&question.value)),
+        );
+        stream::iter(questions.into_iter().map(Ok)).boxed()
+    }
+
+    fn stream_archived_answers(&self, question_uuid: QuestionUuid) -> RowStream<'_, AnswerDetail> {
+        let tables = self.store.tables.read().unwrap();
+        let answers = in_order(
+            tables
+                .archived_answers
+                .values()
+                .filter(|answer| answer.value.question_uuid == question_uuid),
+        );
+        stream::iter(answers.into_iter().map(Ok)).boxed()
+    }
+
+    async fn get_archived_answers_of_questions(
+        &self,
+        question_uuids: &[QuestionUuid],
+    ) -> Result<Vec<AnswerDetail>, DBError> {
+        let tables = self.store.tables.read().unwrap();
+        Ok(in_order(tables.archived_answers.values().filter(
+            |answer| question_uuids.contains(&answer.value.question_uuid),
+        )))
+    }
+}
+
+pub struct InMemoryStatsDao {
+    store: MemoryStore,
+}
+
+impl InMemoryStatsDao {
+    pub fn new(store: MemoryStore) -> Self {
+        Self { store }
+    }
+}
+
+#[async_trait]
+impl StatsDao for InMemoryStatsDao {
+    async fn get_daily_stats(&self) -> Result<Vec<DailyStats>, DBError> {
+        let tables = self.store.tables.read().unwrap();
+        let day = |created_at: DateTime<Utc>| created_at.date_naive().to_string();
+
+        let mut days: BTreeMap<String, (i64, i64)> = BTreeMap::new();
+        for question in tables
+            .questions
+            .values()
+            .chain(tables.archived_questions.values())
+        {
+            days.entry(day(question.value.created_at)).or_default().0 += 1;
+        }
+        for answer in tables
+            .answers
+            .values()
+            .chain(tables.archived_answers.values())
+        {
+            days.entry(day(answer.value.created_at)).or_default().1 += 1;
+        }
+
+        Ok(days
+            .into_iter()
+            .map(|(day, (questions, answers))| DailyStats {
+                day,
+                questions,
+                answers,
+            })
+            .collect())
+    }
+
+    async fn get_response_stats(&self, since: NaiveDate) -> Result<ResponseStats, DBError> {
+        let tables = self.store.tables.read().unwrap();
+
+        let asked: Vec<&QuestionDetail> = tables
+            .questions
+            .values()
+            .chain(tables.archived_questions.values())
+            .map(|row| &row.value)
+            .filter(|question| question.created_at.date_naive() >= since)
+            .collect();
+        let mut first_answers: HashMap<QuestionUuid, DateTime<Utc>> = HashMap::new();
+        for answer in tables
+            .answers
+            .values()
+            .chain(tables.archived_answers.values())
+        {
+            let first = first_answers
+                .entry(answer.value.question_uuid)
+                .or_insert(answer.value.created_at);
+            *first = (*first).min(answer.value.created_at);
+        }
+        let mut seconds: Vec<f64> = asked
+            .iter()
+            .filter_map(|question| {
+                let first = first_answers.get(&question.question_uuid)?;
+                Some((*first - question.created_at).num_milliseconds() as f64 / 1000.0)
+            })
+            .collect();
+        seconds.sort_by(f64::total_cmp);
+
+        let median_first_answer_secs = match seconds.len() {
+            0 => None,
+            n if n % 2 == 1 => Some(seconds[n / 2]),
+            n => Some((seconds[n / 2 - 1] + seconds[n / 2]) / 2.0),
+        };
+        Ok(ResponseStats {
+            questions: asked.len() as i64,
+            answered: seconds.len() as i64,
+            median_first_answer_secs,
+            active_users: Some(
+                tables
+                    .users
+                    .iter()
+                    .filter(|user| user.attributes.active)
+                    .count() as i64,
+            ),
+        })
+    }
+
+    async fn refresh(&self) -> Result<(), DBError> {
+        Ok(())
+    }
+}
+
+// Insertion positions stand in for audit log ids.
+pub struct InMemoryActivityDao {
+    store: MemoryStore,
+}
+
+impl InMemoryActivityDao {
+    pub fn new(store: MemoryStore) -> Self {
+        Self { store }
+    }
+}
+
+#[async_trait]
+impl ActivityDao for InMemoryActivityDao {
+    async fn recent_activity(
+        &self,
+        before_id: Option<i64>,
+        limit: u32,
+    ) -> Result<Vec<Activity>, DBError> {
+        let tables = self.store.tables.read().unwrap();
+        let question = |question_uuid| {
+            tables
+                .questions
+                .get(&question_uuid)
+                .or_else(|| tables.archived_questions.get(&question_uuid))
+        };
+
+        let questions = tables
+            .questions
+            .values()
+            .chain(tables.archived_questions.values())
+            .map(|row| Activity {
+                id: row.position as i64,
+                kind: ActivityKind::QuestionAsked,
+                question_uuid: row.value.question_uuid,
+                question_title: row.value.title.clone(),
+                answer_uuid: None,
+                content: None,
+                created_at: row.value.created_at,
+            });
+        let answers = tables
+            .answers
+            .values()
+            .chain(tables.archived_answers.values())
+            .filter_map(|row| {
+                Some(Activity {
+                    id: row.position as i64,
+                    kind: ActivityKind::AnswerPosted,
+                    question_uuid: row.value.question_uuid,
+                    question_title: question(row.value.question_uuid)?.value.title.clone(),
+                    answer_uuid: Some(row.value.answer_uuid),
+                    content: Some(row.value.content.clone()),
+                    created_at: row.value.created_at,
+                })
+            });
+        let mut activity: Vec<Activity> = questions
+            .chain(answers)
+            .filter(|entry| before_id.is_none_or(|before_id| entry.id < before_id))
+            .collect();
+        activity.sort_by_key(|entry| std::cmp::Reverse(entry.id));
+        activity.truncate(limit.clamp(1, ACTIVITY_MAX_LIMIT) as usize);
+
+        Ok(activity)
+    }
+}
+
+pub struct InMemoryMergeDao {
+    store: MemoryStore,
+}
+
+impl InMemoryMergeDao {
+    pub fn new(store: MemoryStore) -> Self {
+        Self { store }
+    }
+}
+
+#[async_trait]
+impl MergeDao for InMemoryMergeDao {
+    async fn merge_question(
+        &self,
+        source_uuid: QuestionUuid,
+        target_uuid: QuestionUuid,
+    ) -> Result<MergeSummary, DBError> {
+        let mut tables = self.store.tables.write().unwrap();
+        if let Some(missing) = [source_uuid, target_uuid]
+            .into_iter()
+            .find(|uuid| !tables.questions.contains_key(uuid))
+        {
+            return Err(DBError::NotFound(
+                Entity::Question,
+                format!("Question {} does not exist", missing.0),
+            ));
+        }
+
+        let mut answers = 0;
+        let mut latest = None;
+        for row in tables.answers.values_mut() {
+            if row.value.question_uuid == source_uuid {
+                row.value.question_uuid = target_uuid;
+                row.value.version += 1;
+                answers += 1;
+                latest = latest.max(Some(row.value.updated_at));
+            }
+        }
+        if let Some(question) = tables.questions.get_mut(&target_uuid) {
+            question.value.answer_count += answers as i64;
+        }
+        if let Some(latest) = latest {
+            tables.touch_question(&target_uuid, latest);
+        }
+
+        let mut followers = 0;
+        let emails: Vec<String> = tables
+            .subscriptions
+            .iter()
+            .filter(|(question_uuid, _)| *question_uuid == source_uuid)
+            .map(|(_, email)| email.clone())
+            .collect();
+        for email in emails {
+            let followed = (target_uuid, email);
+            if !tables.subscriptions.contains(&followed) {
+                tables.subscriptions.push(followed);
+                followers += 1;
+            }
+        }
+        for (_, question_uuids) in &mut tables.push_subscriptions {
+            if question_uuids.contains(&source_uuid) && !question_uuids.contains(&target_uuid) {
+                question_uuids.push(target_uuid);
+                followers += 1;
+            }
+        }
+
+        for attachment in &mut tables.attachments {
+            if attachment.question_uuid == source_uuid {
+                attachment.question_uuid = target_uuid;
+            }
+        }
+
+        for redirect in tables.redirects.values_mut() {
+            if *redirect == source_uuid {
+                *redirect = target_uuid;
+            }
+        }
+        tables.redirects.insert(source_uuid, target_uuid);
+        tables.questions.remove(&source_uuid);
+
+        Ok(MergeSummary {
+            source_uuid,
+            target_uuid,
+            answers,
+            followers,
+        })
+    }
+
+    async fn redirect(&self, question_uuid: QuestionUuid) -> Result<Option<QuestionUuid>, DBError> {
+        let tables = self.store.tables.read().unwrap();
+        Ok(tables.redirects.get(&question_uuid).copied())
+    }
+}
+
+pub struct InMemoryEmbeddingDao {
+    store: MemoryStore,
+}
+
+impl InMemoryEmbeddingDao {
+    pub fn new(store: MemoryStore) -> Self {
+        Self { store }
+    }
+}
+
+#[async_trait]
+impl EmbeddingDao for InMemoryEmbeddingDao {
+    async fn store_question_embedding(
+        &self,
+        question_uuid: QuestionUuid,
+        embedding: Vec<f32>,
+    ) -> Result<(), DBError> {
+        let mut tables = self.store.tables.write().unwrap();
+        if tables.questions.contains_key(&question_uuid) {
+            tables.embeddings.insert(question_uuid, embedding);
+        }
+        Ok(())
+    }
+
+    async fn nearest_questions(
+        &self,
+        embedding: Vec<f32>,
+        limit: u32,
+    ) -> Result<Vec<QuestionDetail>, DBError> {
+        let tables = self.store.tables.read().unwrap();
+        let mut questions: Vec<(f32, &QuestionDetail)> = tables
+            .embeddings
+            .iter()
+            .filter_map(|(question_uuid, stored)| {
+                let question = tables.questions.get(question_uuid)?;
+                Some((cosine_distance(&embedding, stored), &question.value))
+            })
+            .collect();
+        questions.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+        Ok(questions
+            .into_iter()
+            .take(limit as usize)
+            .map(|(_, question)| question.clone())
+            .collect())
+    }
+}
+
+pub struct InMemorySubscriptionDao {
+    store: MemoryStore,
+}
+
+impl InMemorySubscriptionDao {
+    pub fn new(store: MemoryStore) -> Self {
+        Self { store }
+    }
+}
+
+#[async_trait]
+impl SubscriptionDao for InMemorySubscriptionDao {
+    async fn subscribe(&self, question_uuid: QuestionUuid, email: String) -> Result<(), DBError> {
+        let mut tables = self.store.tables.write().unwrap();
+        if !tables.questions.contains_key(&question_uuid) {
+            return Err(DBError::NotFound(
+                Entity::Question,
+                format!("Question {} does not exist", question_uuid.0),
+            ));
+        }
+
+        let subscription = (question_uuid, email);
+        if !tables.subscriptions.contains(&subscription) {
+            tables.subscriptions.push(subscription);
+        }
+        Ok(())
+    }
+
+    async fn unsubscribe(&self, question_uuid: QuestionUuid, email: String) -> Result<(), DBError> {
+        let mut tables = self.store.tables.write().unwrap();
+        tables
+            .subscriptions
+            .retain(|subscription| *subscription != (question_uuid, email.clone()));
+        Ok(())
+    }
+
+    async fn set_opted_out(&self, email: String, opted_out: bool) -> Result<(), DBError> {
+        let mut tables = self.store.tables.write().unwrap();
+        if opted_out {
+            tables.opted_out.insert(email);
+        } else {
+            tables.opted_out.remove(&email);
+        }
+        Ok(())
+    }
+
+    async fn subscribers(
+        &self,
+        question_uuid: QuestionUuid,
+    ) -> Result<Option<Subscribers>, DBError> {
+        let tables = self.store.tables.read().unwrap();
+        let Some(question) = tables.questions.get(&question_uuid) else {
+            return Ok(None);
+        };
+
+        Ok(Some(Subscribers {
+            question_title: question.value.title.clone(),
+            emails: tables
+                .subscriptions
+                .iter()
+                .filter(|(subscribed_to, email)| {
+                    *subscribed_to == question_uuid && !tables.opted_out.contains(email)
+                })
+                .map(|(_, email)| email.clone())
+                .collect(),
+            push_subscriptions: tables
+                .push_subscriptions
+                .iter()
+                .filter(|(_, followed)| followed.contains(&question_uuid))
+                .map(|(subscription, _)| subscription.clone())
+                .collect(),
+        }))
+    }
+
+    async fn save_push_subscription(
+        &self,
+        subscription: PushSubscription,
+        question_uuids: Vec<QuestionUuid>,
+    ) -> Result<(), DBError> {
+        let mut tables = self.store.tables.write().unwrap();
+        if let Some(missing) = question_uuids
+            .iter()
+            .find(|question_uuid| !tables.questions.contains_key(question_uuid))
+        {
+            return Err(DBError::NotFound(
+                Entity::Question,
+                format!("Question {} does not exist", missing.0),
+            ));
+        }
+
+        match tables
+            .push_subscriptions
+            .iter_mut()
+            .find(|(saved, _)| saved.endpoint == subscription.endpoint)
+        {
+            Some(saved) => *saved = (subscription, question_uuids),
+            None => tables
+                .push_subscriptions
+                .push((subscription, question_uuids)),
+        }
+        Ok(())
+    }
+
+    async fn delete_push_subscription(&self, endpoint: String) -> Result<(), DBError> {
+        let mut tables = self.store.tables.write().unwrap();
+        tables
+            .push_subscriptions
+            .retain(|(subscription, _)| subscription.endpoint != endpoint);
+        Ok(())
+    }
+
+    async fn set_digest_frequency(
+        &self,
+        email: String,
+        frequency: Option<DigestFrequency>,
+    ) -> Result<(), DBError> {
+        let mut tables = self.store.tables.write().unwrap();
+        match frequency {
+            Some(frequency) => {
+                tables
+                    .digests
+                    .entry(email)
+                    .and_modify(|(current, _)| *current = frequency)
+                    .or_insert((frequency, Utc::now()));
+            }
+            None => {
+                tables.digests.remove(&email);
+            }
+        }
+        Ok(())
+    }
+
+    async fn claim_due_digests(&self, now: DateTime<Utc>) -> Result<Vec<DueDigest>, DBError> {
+        let mut tables = self.store.tables.write().unwrap();
+        let Tables {
+            digests, opted_out, ..
+        } = &mut *tables;
+
+        let mut due = vec![];
+        for (email, (frequency, last_sent_at)) in digests.iter_mut() {
+            if *last_sent_at + frequency.period() <= now && !opted_out.contains(email) {
+                due.push(DueDigest {
+                    email: email.clone(),
+                    frequency: *frequency,
+                    since: *last_sent_at,
+                    until: now,
+                });
+                *last_sent_at = now;
+            }
+        }
+        Ok(due)
+    }
+
+    async fn digest(&self, due: &DueDigest, trending_limit: u32) -> Result<Digest, DBError> {
+        let tables = self.store.tables.read().unwrap();
+        let mut new_answers: Vec<_> = tables
+            .answers
+            .values()
+            .filter(|row| row.value.created_at > due.since && row.value.created_at <= due.until)
+            .filter_map(|row| {
+                let question = tables.questions.get(&row.value.question_uuid)?;
+                Some((row, &question.value))
+            })
+            .collect();
+        new_answers.sort_by_key(|(row, _)| (row.value.created_at, row.position));
+
+        let answers = new_answers
+            .iter()
+            .filter(|(row, _)| {
+                tables
+                    .subscriptions
+                    .contains(&(row.value.question_uuid, due.email.clone()))
+            })
+            .map(|(row, question)| DigestAnswer {
+                question_uuid: question.question_uuid,
+                question_title: question.title.clone(),
+                content: row.value.content.clone(),
+            })
+            .collect();
+
+        // Each question's count and latest answer, which breaks ties like on Postgres.
+        let mut counts: HashMap<QuestionUuid, (&QuestionDetail, i64, DateTime<Utc>)> =
+            HashMap::new();
+        for (row, question) in &new_answers {
+            let entry =
+                counts
+                    .entry(question.question_uuid)
+                    .or_insert((question, 0, row.value.created_at));
+            entry.1 += 1;
+            entry.2 = entry.2.max(row.value.created_at);
+        }
+        let mut counts: Vec<_> = counts.into_values().collect();
+        counts.sort_by(|a, b| b.1.cmp(&a.1).then(b.2.cmp(&a.2)));
+
+        Ok(Digest {
+            answers,
+            trending: counts
+                .into_iter()
+                .take(trending_limit as usize)
+                .map(|(question, new_answers, _)| TrendingQuestion {
+                    question_uuid: question.question_uuid,
+                    title: question.title.clone(),
+                    new_answers,
+                })
+                .collect(),
+        })
+    }
+}
+
+pub struct InMemoryAttachmentDao {
+    store: MemoryStore,
+}
+
+impl InMemoryAttachmentDao {
+    pub fn new(store: MemoryStore) -> Self {
+        Self { store }
+    }
+}
+
+#[async_trait]
+impl AttachmentDao for InMemoryAttachmentDao {
+    async fn create_attachment(&self, attachment: NewAttachment) -> Result<Attachment, DBError> {
+        let mut tables = self.store.tables.write().unwrap();
+        if !tables.questions.contains_key(&attachment.question_uuid) {
+            return Err(DBError::NotFound(
+                Entity::Question,
+                format!("Question {} does not exist", attachment.question_uuid.0),
+            ));
+        }
+        if let Some(answer_uuid) = attachment.answer_uuid {
+            let answered = tables
+                .answers
+                .get(&answer_uuid)
+                .is_some_and(|answer| answer.value.question_uuid == attachment.question_uuid);
+            if !answered {
+                return Err(DBError::NotFound(
+                    Entity::Answer,
+                    format!(
+                        "Answer {} of question {} does not exist",
+                        answer_uuid.0, attachment.question_uuid.0
+                    ),
+                ));
+            }
+        }
+
+        let attachment = Attachment {
+            attachment_uuid: attachment.attachment_uuid,
+            question_uuid: attachment.question_uuid,
+            answer_uuid: attachment.answer_uuid,
+            filename: attachment.filename,
+            content_type: attachment.content_type,
+            size_bytes: attachment.size_bytes,
+            storage_key: attachment.storage_key,
+            status: attachment.status,
+            variants: vec![],
+            created_at: Utc::now(),
+        };
+        tables.attachments.push(attachment.clone());
+        Ok(attachment)
+    }
+
+    async fn get_attachment(
+        &self,
+        attachment_uuid: AttachmentUuid,
+    ) -> Result<Option<Attachment>, DBError> {
+        let tables = self.store.tables.read().unwrap();
+        Ok(tables
+            .attachments
+            .iter()
+            .find(|attachment| attachment.attachment_uuid == attachment_uuid)
+            .cloned())
+    }
+
+    async fn mark_uploaded(
+        &self,
+        attachment_uuid: AttachmentUuid,
+        size_bytes: i64,
+        status: AttachmentStatus,
+    ) -> Result<Attachment, DBError> {
+        let mut tables = self.store.tables.write().unwrap();
+        let Some(attachment) = tables.attachments.iter_mut().find(|attachment| {
+            attachment.attachment_uuid == attachment_uuid
+                && attachment.status == AttachmentStatus::Pending
+        }) else {
+            return Err(DBError::NotFound(
+                Entity::Attachment,
+                format!("No upload of attachment {} is pending", attachment_uuid.0),
+            ));
+        };
+
+        attachment.status = status;
+        attachment.size_bytes = size_bytes;
+        Ok(attachment.clone())
+    }
+
+    async fn finish_scan(
+        &self,
+        attachment_uuid: AttachmentUuid,
+        status: AttachmentStatus,
+    ) -> Result<Option<Attachment>, DBError> {
+        let mut tables = self.store.tables.write().unwrap();
+        Ok(tables
+            .attachments
+            .iter_mut()
+            .find(|attachment| {
+                attachment.attachment_uuid == attachment_uuid
+                    && attachment.status == AttachmentStatus::Scanning
+            })
+            .map(|attachment| {
+                attachment.status = status;
+                attachment.clone()
+            }))
+    }
+
+    async fn scanning_attachments(&self, limit: u32) -> Result<Vec<Attachment>, DBError> {
+        let tables = self.store.tables.read().unwrap();
+        Ok(tables
+            .attachments
+            .iter()
+            .filter(|attachment| attachment.status == AttachmentStatus::Scanning)
+            .take(limit as usize)
+            .cloned()
+            .collect())
+    }
+
+    async fn delete_attachment(&self, attachment_uuid: AttachmentUuid) -> Result<(), DBError> {
+        let mut tables = self.store.tables.write().unwrap();
+        tables
+            .attachments
+            .retain(|attachment| attachment.attachment_uuid != attachment_uuid);
+        Ok(())
+    }
+
+    async fn set_variants(
+        &self,
+        attachment_uuid: AttachmentUuid,
+        variants: Vec<AttachmentVariant>,
+    ) -> Result<(), DBError> {
+        let mut tables = self.store.tables.write().unwrap();
+        if let Some(attachment) = tables
+            .attachments
+            .iter_mut()
+            .find(|attachment| attachment.attachment_uuid == attachment_uuid)
+        {
+            attachment.variants = variants;
+        }
+        Ok(())
+    }
+
+    async fn attachments_of_questions(
+        &self,
+        question_uuids: &[QuestionUuid],
+    ) -> Result<Vec<Attachment>, DBError> {
+        let tables = self.store.tables.read().unwrap();
+        Ok(tables
+            .attachments
+            .iter()
+            .filter(|attachment| {
+                attachment.answer_uuid.is_none()
+                    && attachment.status == AttachmentStatus::Ready
+                    && question_uuids.contains(&attachment.question_uuid)
+            })
+            .cloned()
+            .collect())
+    }
+
+    async fn attachments_of_answers(
+        &self,
+        answer_uuids: &[AnswerUuid],
+    ) -> Result<Vec<Attachment>, DBError> {
+        let tables = self.store.tables.read().unwrap();
+        Ok(tables
+            .attachments
+            .iter()
+            .filter(|attachment| {
+                attachment.status == AttachmentStatus::Ready
+                    && attachment
+                        .answer_uuid
+                        .is_some_and(|answer_uuid| answer_uuids.contains(&answer_uuid))
+            })
+            .cloned()
+            .collect())
+    }
+}
+
+pub struct InMemoryModerationDao {
+    store: MemoryStore,
+}
+
+impl InMemoryModerationDao {
+    pub fn new(store: MemoryStore) -> Self {
+        Self { store }
+    }
+}
+
+#[async_trait]
+impl ModerationDao for InMemoryModerationDao {
+    async fn hold(&self, item: NewModerationItem) -> Result<ModerationItem, DBError> {
+        let item = ModerationItem {
+            moderation_uuid: item.moderation_uuid,
+            content: item.content,
+            score: item.score,
+            categories: item.categories,
+            spam_context: item.spam_context,
+            created_at: Utc::now(),
+        };
+        let mut tables = self.store.tables.write().unwrap();
+        tables.moderation_queue.push(item.clone());
+        Ok(item)
+    }
+
+    async fn held_items(&self, window: Window) -> Result<Listed<ModerationItem>, DBError> {
+        Ok(window.of(self.store.tables.read().unwrap().moderation_queue.clone()))
+    }
+
+    async fn take_held(
+        &self,
+        moderation_uuid: ModerationUuid,
+    ) -> Result<Option<ModerationItem>, DBError> {
+        let mut tables = self.store.tables.write().unwrap();
+        let position = tables
+            .moderation_queue
+            .iter()
+            .position(|item| item.moderation_uuid == moderation_uuid);
+        Ok(position.map(|position| tables.moderation_queue.remove(position)))
+    }
+
+    async fn take_held_many(
+        &self,
+        moderation_uuids: &[ModerationUuid],
+    ) -> Result<Vec<ModerationItem>, DBError> {
+        let mut tables = self.store.tables.write().unwrap();
+        let (taken, held) = std::mem::take(&mut tables.moderation_queue)
+            .into_iter()
+            .partition(|item| moderation_uuids.contains(&item.moderation_uuid));
+        tables.moderation_queue = held;
+        Ok(taken)
+    }
+}
+
+pub struct InMemoryTagDao {
+    store: MemoryStore,
+}
+
+impl InMemoryTagDao {
+    pub fn new(store: MemoryStore) -> Self {
+        Self { store }
+    }
+}
+
+#[async_trait]
+impl TagDao for InMemoryTagDao {
+    async fn tagged_questions(&self, limit: u32) -> Result<Vec<TaggedQuestion>, DBError> {
+        let tables = self.store.tables.read().unwrap();
+        let mut rows: Vec<&Row<QuestionDetail>> = tables.questions.values().collect();
+        rows.sort_by_key(|row| std::cmp::Reverse(row.position));
+
+        Ok(rows
+            .into_iter()
+            .filter_map(|row| {
+                TaggedQuestion::from_metadata(
+                    row.value.title.clone(),
+                    row.value.description.clone(),
+                    &row.value.metadata,
+                )
+            })
+            .take(limit as usize)
+            .collect())
+    }
+}
+
+pub struct InMemoryTranslationDao {
+    store: MemoryStore,
+}
+
+impl InMemoryTranslationDao {
+    pub fn new(store: MemoryStore) -> Self {
+        Self { store }
+    }
+}
+
+#[async_trait]
+impl TranslationDao for InMemoryTranslationDao {
+    async fn get_translation(
+        &self,
+        entity: &'static str,
+        entity_uuid: Uuid,
+        language: &str,
+    ) -> Result<Option<Translation>, DBError> {
+        let tables = self.store.tables.read().unwrap();
+        Ok(tables
+            .translations
+            .get(&(entity, entity_uuid, language.to_owned()))
+            .cloned())
+    }
+
+    async fn store_translation(&self, translation: Translation) -> Result<(), DBError> {
+        let mut tables = self.store.tables.write().unwrap();
+        let key = (
+            translation.entity,
+            translation.entity_uuid,
+            translation.language.clone(),
+        );
+        match tables.translations.get(&key) {
+            Some(stored) if stored.source_version > translation.source_version => {}
+            _ => {
+                tables.translations.insert(key, translation);
+            }
+        }
+        Ok(())
+    }
+
+    async fn question_translation(
+        &self,
+        question_uuid: QuestionUuid,
+        language: &str,
+    ) -> Result<Option<QuestionTranslation>, DBError> {
+        let tables = self.store.tables.read().unwrap();
+        if !tables.questions.contains_key(&question_uuid) {
+            return Ok(None);
+        }
+        Ok(tables
+            .question_translations
+            .get(&(question_uuid.0, language.to_owned()))
+            .cloned())
+    }
+
+    async fn question_translations(
+        &self,
+        question_uuid: QuestionUuid,
+    ) -> Result<Vec<QuestionTranslation>, DBError> {
+        let tables = self.store.tables.read().unwrap();
+        if !tables.questions.contains_key(&question_uuid) {
+            return Ok(vec![]);
+        }
+        Ok(tables
+            .question_translations
+            .range((question_uuid.0, String::new())..)
+            .take_while(|((uuid, _), _)| *uuid == question_uuid.0)
+            .map(|(_, translation)| translation.clone())
+            .collect())
+    }
+
+    async fn submit_question_translation(
+        &self,
+        question_uuid: QuestionUuid,
+        language: &str,
+        title: String,
+        description: Option<String>,
+    ) -> Result<QuestionTranslation, DBError> {
+        let mut tables = self.store.tables.write().unwrap();
+        let question_version = tables
+            .questions
+            .get(&question_uuid)
+            .map(|row| row.value.version)
+            .ok_or_else(|| {
+                DBError::NotFound(
+                    Entity::Question,
+                    format!("Question {} does not exist", question_uuid.0),
+                )
+            })?;
+
+        let translation = QuestionTranslation {
+            question_uuid,
+            language: language.to_owned(),
+            title,
+            description,
+            question_version,
+            updated_at: Utc::now(),
+        };
+        tables
+            .question_translations
+            .insert((question_uuid.0, language.to_owned()), translation.clone());
+        Ok(translation)
+    }
+
+    async fn delete_question_translation(
+        &self,
+        question_uuid: QuestionUuid,
+        language: &str,
+    ) -> Result<(), DBError> {
+        let mut tables = self.store.tables.write().unwrap();
+        let removed = tables
+            .question_translations
+            .remove(&(question_uuid.0, language.to_owned()));
+        match removed {
+            Some(_) if tables.questions.contains_key(&question_uuid) => Ok(()),
+            _ => Err(DBError::NotFound(
+                Entity::Translation,
+                format!(
+                    "Question {} has no {} translation",
+                    question_uuid.0, language
+                ),
+            )),
+        }
+    }
+}
+
+pub struct InMemoryLinkPreviewDao {
+    store: MemoryStore,
+}
+
+impl InMemoryLinkPreviewDao {
+    pub fn new(store: MemoryStore) -> Self {
+        Self { store }
+    }
+}
+
+#[async_trait]
+impl LinkPreviewDao for InMemoryLinkPreviewDao {
+    async fn get_link_previews(&self, urls: &[String]) -> Result<Vec<LinkPreview>, DBError> {
+        let tables = self.store.tables.read().unwrap();
+        Ok(urls
+            .iter()
+            .filter_map(|url| tables.link_previews.get(url).cloned())
+            .collect())
+    }
+
+    async fn store_link_preview(&self, preview: LinkPreview) -> Result<(), DBError> {
+        let mut tables = self.store.tables.write().unwrap();
+        tables.link_previews.insert(preview.url.clone(), preview);
+        Ok(())
+    }
+}
+
+pub struct InMemoryIngestionDao {
+    store: MemoryStore,
+}
+
+impl InMemoryIngestionDao {
+    pub fn new(store: MemoryStore) -> Self {
+        Self { store }
+    }
+}
+
+#[async_trait]
+impl IngestionDao for InMemoryIngestionDao {
+    async fn create_question_once(
+        &self,
+        idempotency_key: &str,
+        question: Question,
+    ) -> Result<Ingested, DBError> {
+        let mut tables = self.store.tables.write().unwrap();
+        if let Some(question_uuid) = tables.ingested_questions.get(idempotency_key) {
+            return Ok(Ingested::Duplicate(*question_uuid));
+        }
+
+        let question = new_question(question);
+        tables
+            .ingested_questions
+            .insert(idempotency_key.to_owned(), question.question_uuid);
+        tables.insert_question(question.clone());
+        Ok(Ingested::Created(question))
+    }
+}
+
+pub struct InMemoryWordFilterDao {
+    store: MemoryStore,
+}
+
+impl InMemoryWordFilterDao {
+    pub fn new(store: MemoryStore) -> Self {
+        Self { store }
+    }
+}
+
+#[async_trait]
+impl WordFilterDao for InMemoryWordFilterDao {
+    async fn filtered_words(&self) -> Result<Vec<String>, DBError> {
+        let tables = self.store.tables.read().unwrap();
+        Ok(tables.filtered_words.iter().cloned().collect())
+    }
+
+    async fn set_filtered(&self, word: String, filtered: bool) -> Result<(), DBError> {
+        let mut tables = self.store.tables.write().unwrap();
+        if filtered {
+            tables.filtered_words.insert(word);
+        } else {
+            tables.filtered_words.remove(&word);
+        }
+        Ok(())
+    }
+}
+
+// There is no outbox in memory, so hooks are kept but never called and have no recent
+// events.
+pub struct InMemoryRestHookDao {
+    store: MemoryStore,
+}
+
+impl InMemoryRestHookDao {
+    pub fn new(store: MemoryStore) -> Self {
+        Self { store }
+    }
+}
+
+#[async_trait]
+impl RestHookDao for InMemoryRestHookDao {
+    async fn create_hook(
+        &self,
+        hook_uuid: HookUuid,
+        target_url: String,
+        event_type: String,
+    ) -> Result<RestHook, DBError> {
+        let hook = RestHook {
+            hook_uuid,
+            target_url,
+            event_type,
+            created_at: Utc::now(),
+        };
+        self.store
+            .tables
+            .write()
+            .unwrap()
+            .rest_hooks
+            .push(hook.clone());
+        Ok(hook)
+    }
+
+    async fn delete_hook(&self, hook_uuid: HookUuid) -> Result<(), DBError> {
+        let mut tables = self.store.tables.write().unwrap();
+        let position = tables
+            .rest_hooks
+            .iter()
+            .position(|hook| hook.hook_uuid == hook_uuid)
+            .ok_or_else(|| {
+                DBError::NotFound(
+                    Entity::RestHook,
+                    format!("REST hook {} not found", hook_uuid),
+                )
+            })?;
+        tables.rest_hooks.remove(position);
+        Ok(())
+    }
+
+    async fn hooks_for(&self, event_type: &str) -> Result<Vec<RestHook>, DBError> {
+        let tables = self.store.tables.read().unwrap();
+        Ok(tables
+            .rest_hooks
+            .iter()
+            .filter(|hook| hook.event_type == event_type)
+            .cloned()
+            .collect())
+    }
+
+    async fn recent_events(
+        &self,
+        _event_type: &str,
+        _limit: i64,
+    ) -> Result<Vec<OutboxEvent>, DBError> {
+        Ok(vec![])
+    }
+}
+
+pub struct InMemoryUserDao {
+    store: MemoryStore,
+}
+
+impl InMemoryUserDao {
+    pub fn new(store: MemoryStore) -> Self {
+        Self { store }
+    }
+}
+
+// Like the unique index on `user_name`.
+fn check_user_name(
+    users: &[ProvisionedUser],
+    user_uuid: UserUuid,
+    user_name: &str,
+) -> Result<(), DBError> {
+    match users
+        .iter()
+        .any(|user| user.user_uuid != user_uuid && user.attributes.user_name == user_name)
+    {
+        true => Err(DBError::Conflict(format!(
+            "User name {} is already taken",
+            user_name
+        ))),
+        false => Ok(()),
+    }
+}
+
+fn user_not_found(user_uuid: UserUuid) -> DBError {
+    DBError::NotFound(Entity::User, format!("User {} not found", user_uuid))
+}
+
+#[async_trait]
+impl UserDao for InMemoryUserDao {
+    async fn create_user(
+        &self,
+        user_uuid: UserUuid,
+        attributes: UserAttributes,
+    ) -> Result<ProvisionedUser, DBError> {
+        let mut tables = self.store.tables.write().unwrap();
+        check_user_name(&tables.users, user_uuid, &attributes.user_name)?;
+        let now = Utc::now();
+        let user = ProvisionedUser {
+            user_uuid,
+            attributes,
+            created_at: now,
+            updated_at: now,
+        };
+        tables.users.push(user.clone());
+        Ok(user)
+    }
+
+    async fn get_user(&self, user_uuid: UserUuid) -> Result<ProvisionedUser, DBError> {
+        let tables = self.store.tables.read().unwrap();
+        tables
+            .users
+            .iter()
+            .find(|user| user.user_uuid == user_uuid)
+            .cloned()
+            .ok_or_else(|| user_not_found(user_uuid))
+    }
+
+    async fn users(
+        &self,
+        user_name: Option<&str>,
+        offset: i64,
+        limit: i64,
+    ) -> Result<(Vec<ProvisionedUser>, i64), DBError> {
+        let tables = self.store.tables.read().unwrap();
+        let matching: Vec<_> = tables
+            .users
+            .iter()
+            .filter(|user| user_name.is_none_or(|name| user.attributes.user_name == name))
+            .collect();
+        let total = matching.len() as i64;
+        let users = matching
+            .into_iter()
+            .skip(offset.max(0) as usize)
+            .take(limit.max(0) as usize)
+            .cloned()
+            .collect();
+        Ok((users, total))
+    }
+
+    async fn update_user(
+        &self,
+        user_uuid: UserUuid,
+        attributes: UserAttributes,
+    ) -> Result<ProvisionedUser, DBError> {
+        let mut tables = self.store.tables.write().unwrap();
+        check_user_name(&tables.users, user_uuid, &attributes.user_name)?;
+        let user = tables
+            .users
+            .iter_mut()
+            .find(|user| user.user_uuid == user_uuid)
+            .ok_or_else(|| user_not_found(user_uuid))?;
+        user.attributes = attributes;
+        user.updated_at = Utc::now();
+        Ok(user.clone())
+    }
+}
+
+// Scores answers like Postgres' pg_trgm `similarity`.
+pub struct InMemoryDuplicateDao {
+    store: MemoryStore,
+}
+
+impl InMemoryDuplicateDao {
+    pub fn new(store: MemoryStore) -> Self {
+        Self { store }
+    }
+}
+
+#[async_trait]
+impl DuplicateDao for InMemoryDuplicateDao {
+    async fn similar_answers(
+        &self,
+        question_uuid: QuestionUuid,
+        content: &str,
+        threshold: f32,
+        limit: u32,
+    ) -> Result<Vec<SimilarAnswer>, DBError> {
+        let wanted = trigrams(content);
+        let tables = self.store.tables.read().unwrap();
+        let mut similar: Vec<(u64, SimilarAnswer)> = tables
+            .answers
+            .values()
+            .filter(|row| row.value.question_uuid == question_uuid)
+            .filter_map(|row| {
+                let similarity = trigram_similarity(&wanted, &trigrams(&row.value.content));
+                (similarity >= threshold).then(|| {
+                    let answer = SimilarAnswer {
+                        answer_uuid: row.value.answer_uuid,
+                        content: row.value.content.clone(),
+                        similarity,
+                    };
+                    (row.position, answer)
+                })
+            })
+            .collect();
+        similar.sort_by(|a, b| {
+            b.1.similarity
+                .total_cmp(&a.1.similarity)
+                .then(a.0.cmp(&b.0))
+        });
+
+        Ok(similar
+            .into_iter()
+            .take(limit as usize)
+            .map(|(_, answer)| answer)
+            .collect())
+    }
+}
+
+// The trigrams of each word of `text`, lowercased and padded with two spaces in front
+// and one behind, as pg_trgm makes them.
+fn trigrams(text: &str) -> HashSet<[char; 3]> {
+    text.to_lowercase()
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .flat_map(|word| {
+            let padded: Vec<char> = "  ".chars().chain(word.chars()).chain([' ']).collect();
+            padded
+                .windows(3)
+                .map(|window| [window[0], window[1], window[2]])
+                .collect::<Vec<_>>()
+        })
+        .collect()
+}
+
+// Shared trigrams over all trigrams of either.
+fn trigram_similarity(a: &HashSet<[char; 3]>, b: &HashSet<[char; 3]>) -> f32 {
+    let union = a.union(b).count();
+    if union == 0 {
+        return 0.0;
+    }
+    a.intersection(b).count() as f32 / union as f32
+}
+
+// Matches questions containing every query word, case-insensitively, ranking title
+// matches over description ones. Words starting with `-` exclude questions instead.
+pub struct InMemorySearchDao {
+    store: MemoryStore,
+}
+
+impl InMemorySearchDao {
+    pub fn new(store: MemoryStore) -> Self {
+        Self { store }
+    }
+}
+
+// The words to look for and the ones to exclude, lowercased.
+fn query_words(query: &str) -> (Vec<String>, Vec<String>) {
+    let mut included = vec![];
+    let mut excluded = vec![];
+    for word in query.split_whitespace() {
+        let (words, word) = match word.strip_prefix('-') {
+            Some(word) => (&mut excluded, word),
+            None => (&mut included, word),
+        };
+        let word = word.trim_matches(|c: char| !c.is_alphanumeric());
+        if !word.is_empty() {
+            words.push(word.to_lowercase());
+        }
+    }
+    (included, excluded)
+}
+
+#[async_trait]
+impl SearchDao for InMemorySearchDao {
+    async fn search_questions(
+        &self,
+        query: &str,
+        limit: u32,
+    ) -> Result<Vec<QuestionDetail>, DBError> {
+        let (included, excluded) = query_words(query);
+        if included.is_empty() {
+            return Ok(vec![]);
+        }
+
+        let tables = self.store.tables.read().unwrap();
+        let mut questions: Vec<(usize, u64, &QuestionDetail)> = tables
+            .questions
+            .values()
+            .filter_map(|row| {
+                let title = row.value.title.to_lowercase();
+                let description = row
+                    .value
+                    .description
+                    .as_deref()
+                    .unwrap_or_default()
+                    .to_lowercase();
+                let count = |word: &str| {
+                    2 * title.matches(word).count() + description.matches(word).count()
+                };
+                if excluded.iter().any(|word| count(word) > 0) {
+                    return None;
+                }
+                let counts: Vec<usize> = included.iter().map(|word| count(word)).collect();
+                if counts.contains(&0) {
+                    return None;
+                }
+                Some((counts.iter().sum(), row.position, &row.value))
+            })
+            .collect();
+        questions.sort_by(|a, b| b.0.cmp(&a.0).then(b.1.cmp(&a.1)));
+
+        Ok(questions
+            .into_iter()
+            .take(limit as usize)
+            .map(|(_, _, question)| question.clone())
+            .collect())
+    }
+
+    async fn questions_by_uuid(
+        &self,
+        question_uuids: &[QuestionUuid],
+    ) -> Result<Vec<QuestionDetail>, DBError> {
+        let tables = self.store.tables.read().unwrap();
+        Ok(question_uuids
+            .iter()
+            .filter_map(|uuid| tables.questions.get(uuid))
+            .map(|row| row.value.clone())
+            .collect())
+    }
+
+    // Starting with `text` first, then the most answered and newest.
+    async fn complete_titles(&self, text: &str, limit: u32) -> Result<Vec<TitleMatch>, DBError> {
+        let text = text.to_lowercase();
+        let tables = self.store.tables.read().unwrap();
+        let mut questions: Vec<(bool, &Row<QuestionDetail>)> = tables
+            .questions
+            .values()
+            .filter_map(|row| {
+                let title = row.value.title.to_lowercase();
+                title
+                    .contains(&text)
+                    .then(|| (title.starts_with(&text), row))
+            })
+            .collect();
+        questions.sort_by(|a, b| {
+            b.0.cmp(&a.0)
+                .then(b.1.value.answer_count.cmp(&a.1.value.answer_count))
+                .then(b.1.position.cmp(&a.1.position))
+        });
+
+        Ok(questions
+            .into_iter()
+            .take(limit as usize)
+            .map(|(_, row)| TitleMatch {
+                question_uuid: row.value.question_uuid,
+                title: row.value.title.clone(),
+            })
+            .collect())
+    }
+
+    // Scores each word of `text` by its closest word of the title, which is near enough
+    // pg_trgm's `word_similarity` to tell typos apart.
+    async fn similar_titles(
+        &self,
+        text: &str,
+        threshold: f32,
+        excluded: &[QuestionUuid],
+        limit: u32,
+    ) -> Result<Vec<TitleMatch>, DBError> {
+        let wanted: Vec<_> = text.split_whitespace().map(trigrams).collect();
+        if wanted.is_empty() {
+            return Ok(vec![]);
+        }
+
+        let tables = self.store.tables.read().unwrap();
+        let mut similar: Vec<(f32, &Row<QuestionDetail>)> = tables
+            .questions
+            .values()
+            .filter(|row| !excluded.contains(&row.value.question_uuid))
+            .filter_map(|row| {
+                let words: Vec<_> = row.value.title.split_whitespace().map(trigrams).collect();
+                let closest = |word: &HashSet<[char; 3]>| {
+                    words
+                        .iter()
+                        .map(|title_word| trigram_similarity(word, title_word))
+                        .fold(0.0, f32::max)
+                };
+                let similarity = wanted.iter().map(closest).sum::<f32>() / wanted.len() as f32;
+                (similarity >= threshold).then_some((similarity, row))
+            })
+            .collect();
+        similar.sort_by(|a, b| {
+            b.0.total_cmp(&a.0)
+                .then(b.1.value.answer_count.cmp(&a.1.value.answer_count))
+                .then(b.1.position.cmp(&a.1.position))
+        });
+
+        Ok(similar
+            .into_iter()
+            .take(limit as usize)
+            .map(|(_, row)| TitleMatch {
+                question_uuid: row.value.question_uuid,
+                title: row.value.title.clone(),
+            })
+            .collect())
+    }
+
+    // Marks each word containing one looked for; descriptions are kept whole.
+    async fn highlight(
+        &self,
+        query: &str,
+        questions: &[QuestionDetail],
+    ) -> Result<Vec<Highlight>, DBError> {
+        let (included, _) = query_words(query);
+        let mark = |text: &str| {
+            let marked: Vec<String> = text
+                .split(' ')
+                .map(|word| {
+                    let lowercase = word.to_lowercase();
+                    if included.iter().any(|included| lowercase.contains(included)) {
+                        format!("{MATCH_START}{word}{MATCH_END}")
+                    } else {
+                        word.to_owned()
+                    }
+                })
+                .collect();
+            marked_html(&marked.join(" "))
+        };
+
+        Ok(questions
+            .iter()
+            .map(|question| Highlight {
+                title: mark(&question.title),
+                description: question.description.as_deref().map(mark),
+            })
+            .collect())
+    }
+}
+
+pub struct MemoryUnitOfWorkFactory {
+    store: MemoryStore,
+}
+
+impl MemoryUnitOfWorkFactory {
+    pub fn new(store: MemoryStore) -> Self {
+        Self { store }
+    }
+}
+
+#[async_trait]
+impl UnitOfWorkFactory for MemoryUnitOfWorkFactory {
+    async fn begin(&self) -> Result<Box<dyn UnitOfWork>, DBError> {
+        Ok(Box::new(MemoryUnitOfWork {
+            store: self.store.clone(),
+            questions: vec![],
+            answers: vec![],
+        }))
+    }
+}
+
+// Writes are staged and applied under a single write lock on commit.
+pub struct MemoryUnitOfWork {
+    store: MemoryStore,
+    questions: Vec<QuestionDetail>,
+    answers: Vec<AnswerDetail>,
+}
+
+impl MemoryUnitOfWork {
+    fn stages_question(&self, question_uuid: &QuestionUuid) -> bool {
+        self.questions
+            .iter()
+            .any(|question| question.question_uuid == *question_uuid)
+    }
+}
+
+#[async_trait]
+impl UnitOfWork for MemoryUnitOfWork {
+    async fn create_question(&mut self, question: Question) -> Result<QuestionDetail, DBError> {
+        let question = new_question(question);
+        self.questions.push(question.clone());
+
+        Ok(question)
+    }
+
+    async fn create_answer(&mut self, answer: Answer) -> Result<AnswerDetail, DBError> {
+        let question_uuid = answer.question_uuid;
+
+        let exists = self.stages_question(&question_uuid)
+            || self
+                .store
+                .tables
+                .read()
+                .unwrap()
+                .questions
+                .contains_key(&question_uuid);
+        if !exists {
+            return Err(unknown_question(&question_uuid));
+        }
+
+        let answer = new_answer(question_uuid, answer.content);
+        self.answers.push(answer.clone());
+
+        Ok(answer)
+    }
+
+    async fn commit(self: Box<Self>) -> Result<(), DBError> {
+        let mut tables = self.store.tables.write().unwrap();
+
+        // The question may have been deleted since the answer was staged.
+        for answer in &self.answers {
+            if !self.stages_question(&answer.question_uuid)
+                && !tables.questions.contains_key(&answer.question_uuid)
+            {
+                return Err(unknown_question(&answer.question_uuid));
+            }
+        }
+
+        for question in self.questions {
+            tables.insert_question(question);
+        }
+        for answer in self.answers {
+            tables.insert_answer(answer);
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{empty_metadata, MetadataFilter};
+    use futures::TryStreamExt;
+    use serde_json::json;
+
+    fn question(title: &str) -> Question {
+        Question {
+            title: title.to_owned(),
+            description: Some("description".to_owned()),
+            metadata: empty_metadata(),
+        }
+    }
+
+    fn answer(question_uuid: &QuestionUuid) -> Answer {
+        Answer {
+            question_uuid: *question_uuid,
+            content: "content".to_owned(),
+        }
+    }
+
+    #[test]
+    fn trigram_similarity_should_match_pg_trgm() {
+        // SELECT similarity('word', 'two words') is 4 shared trigrams of 11.
+        let similarity = trigram_similarity(&trigrams("Word"), &trigrams("two, words"));
+        assert!((similarity - 4.0 / 11.0).abs() < 1e-6);
+        assert_eq!(trigram_similarity(&trigrams(""), &trigrams("")), 0.0);
+    }
+
+    #[tokio::test]
+    async fn get_questions_should_keep_insertion_order() {
+        let dao = InMemoryQuestionDao::new(MemoryStore::new());
+
+        let mut created = vec![];
+        for title in ["first", "second", "third"] {
+            created.push(dao.create_question(question(title)).await.unwrap());
+        }
+
+        assert_eq!(dao.get_questions().await.unwrap(), created);
+    }
+
+    #[tokio::test]
+    async fn list_questions_should_page_live_and_archived_questions_together() {
+        let store = MemoryStore::new();
+        let dao = InMemoryQuestionDao::new(store.clone());
+        let mut created = vec![];
+        for title in ["first", "second", "third"] {
+            created.push(dao.create_question(question(title)).await.unwrap());
+        }
+        {
+            let mut tables = store.tables.write().unwrap();
+            let archived = tables.questions.remove(&created[0].question_uuid).unwrap();
+            tables
+                .archived_questions
+                .insert(created[0].question_uuid, archived);
+        }
+        let filter = QuestionFilter::default();
+        let list = |include_archived, offset| {
+            dao.list_questions(
+                &filter,
+                include_archived,
+                QuestionSort::Oldest,
+                Window { offset, limit: 2 },
+            )
+        };
+
+        let live = list(false, 0).await.unwrap();
+        assert_eq!(live.total, 2);
+        assert_eq!(live.items, created[1..].to_vec());
+
+        let all = list(true, 0).await.unwrap();
+        assert_eq!(all.total, 3);
+        assert_eq!(all.items, created[..2].to_vec());
+        let rest = list(true, 2).await.unwrap();
+        assert_eq!(rest.items, created[2..].to_vec());
+    }
+
+    #[tokio::test]
+    async fn search_questions_should_rank_title_matches_first() {
+        let store = MemoryStore::new();
+        let question_dao = InMemoryQuestionDao::new(store.clone());
+        let mut created = vec![];
+        for (title, description) in [
+            ("Billing address", "The invoice shows an old address"),
+            ("Duplicate invoice", "Charged twice"),
+            ("Refund for an invoice", "Charged twice"),
+        ] {
+            let question = Question {
+                description: Some(description.to_owned()),
+                ..question(title)
+            };
+            created.push(question_dao.create_question(question).await.unwrap());
+        }
+        let dao = InMemorySearchDao::new(store);
+
+        let found = dao.search_questions("Invoice", 10).await.unwrap();
+        assert_eq!(
+            found,
+            vec![created[2].clone(), created[1].clone(), created[0].clone()]
+        );
+        let found = dao.search_questions("invoice -refund", 1).await.unwrap();
+        assert_eq!(found, vec![created[1].clone()]);
+        assert!(dao
+            .search_questions("-refund", 10)
+            .await
+            .unwrap()
+            .is_empty());
+    }
+
+    #[tokio::test]
+    async fn get_questions_matching_should_match_every_key() {
+        let dao = InMemoryQuestionDao::new(MemoryStore::new());
+        let mut created = vec![];
+        for metadata in [
+            json!({ "source": "zendesk", "ticket": "42" }),
+            json!({ "source": "zendesk", "ticket": 42 }),
+            json!({ "source": "jira", "ticket": "42" }),
+        ] {
+            let question = Question {
+                metadata,
+                ..question("title")
+            };
+            created.push(dao.create_question(question).await.unwrap());
+        }
+
+        let filter = QuestionFilter {
+            metadata: MetadataFilter(
+                [("source", "zendesk"), ("ticket", "42")]
+                    .map(|(key, value)| (key.to_owned(), value.to_owned()))
+                    .into(),
+            ),
+            ..QuestionFilter::default()
+        };
+        let found = dao.get_questions_matching(&filter).await.unwrap();
+
+        assert_eq!(found, vec![created[0].clone()]);
+    }
+
+    #[tokio::test]
+    async fn create_answer_should_fail_for_unknown_question() {
+        let dao = InMemoryAnswerDao::new(MemoryStore::new());
+
+        let result = dao.create_answer(answer(&QuestionUuid::new_v4())).await;
+
+        assert!(matches!(
+            result,
+            Err(DBError::NotFound(Entity::Question, _))
+        ));
+    }
+
+    #[tokio::test]
+    async fn delete_question_should_delete_its_answers() {
+        let store = MemoryStore::new();
+        let question_dao = InMemoryQuestionDao::new(store.clone());
+        let dao = InMemoryAnswerDao::new(store);
+
+        let question = question_dao
+            .create_question(question("title"))
+            .await
+            .unwrap();
+        let created = dao
+            .create_answer(answer(&question.question_uuid))
+            .await
+            .unwrap();
+        assert_eq!(
+            dao.get_answers(question.question_uuid).await.unwrap(),
+            vec![created]
+        );
+
+        question_dao
+            .delete_question(question.question_uuid)
+            .await
+            .unwrap();
+
+        assert_eq!(
+            dao.get_answers(question.question_uuid).await.unwrap(),
+            vec![]
+        );
+    }
+
+    #[tokio::test]
+    async fn delete_question_should_be_blocked_by_answers_when_restricted() {
+        let store = MemoryStore::new();
+        let question_dao =
+            InMemoryQuestionDao::new(store.clone()).with_deletion(QuestionDeletion::Restrict);
+        let dao = InMemoryAnswerDao::new(store);
+
+        let question = question_dao
+            .create_question(question("title"))
+            .await
+            .unwrap();
+        dao.create_answer(answer(&question.question_uuid))
+            .await
+            .unwrap();
+
+        let result = question_dao.delete_question(question.question_uuid).await;
+
+        assert!(matches!(result, Err(DBError::Conflict(_))));
+        assert_eq!(
+            dao.get_answers(question.question_uuid).await.unwrap().len(),
+            1
+        );
+    }
+
+    #[tokio::test]
+    async fn answer_count_should_follow_answer_writes() {
+        let store = MemoryStore::new();
+        let question_dao = InMemoryQuestionDao::new(store.clone());
+        let dao = InMemoryAnswerDao::new(store);
+
+        let question = question_dao
+            .create_question(question("title"))
+            .await
+            .unwrap();
+        let created = dao
+            .create_answer(answer(&question.question_uuid))
+            .await
+            .unwrap();
+        assert_eq!(
+            question_dao.get_questions().await.unwrap()[0].answer_count,
+            1
+        );
+
+        dao.delete_answer(created.answer_uuid).await.unwrap();
+        assert_eq!(
+            question_dao.get_questions().await.unwrap()[0].answer_count,
+            0
+        );
+    }
+
+    #[tokio::test]
+    async fn last_activity_at_should_follow_every_write() {
+        let store = MemoryStore::new();
+        let question_dao = InMemoryQuestionDao::new(store.clone());
+        let dao = InMemoryAnswerDao::new(store);
+        let asked = question_dao
+            .create_question(question("title"))
+            .await
+            .unwrap();
+        let last_activity_at =
+            || async { question_dao.get_questions().await.unwrap()[0].last_activity_at };
+
+        let answer = dao
+            .create_answer(answer(&asked.question_uuid))
+            .await
+            .unwrap();
+        assert_eq!(last_activity_at().await, answer.created_at);
+
+        let answer = dao
+            .update_answer(answer.answer_uuid, "edited".to_owned(), answer.version)
+            .await
+            .unwrap();
+        assert_eq!(last_activity_at().await, answer.updated_at);
+
+        dao.delete_answer(answer.answer_uuid).await.unwrap();
+        let deleted_at = last_activity_at().await;
+        assert!(deleted_at > answer.updated_at);
+
+        let edited = question_dao
+            .update_question(asked.question_uuid, question("edited"), asked.version)
+            .await
+            .unwrap();
+        assert!(edited.last_activity_at > deleted_at);
+    }
+
+    #[tokio::test]
+    async fn merge_question_should_move_answers_and_followers() {
+        let store = MemoryStore::new();
+        let question_dao = InMemoryQuestionDao::new(store.clone());
+        let answer_dao = InMemoryAnswerDao::new(store.clone());
+        let subscription_dao = InMemorySubscriptionDao::new(store.clone());
+        let dao = InMemoryMergeDao::new(store);
+        let source = question_dao
+            .create_question(question("source"))
+            .await
+            .unwrap()
+            .question_uuid;
+        let target = question_dao
+            .create_question(question("target"))
+            .await
+            .unwrap()
+            .question_uuid;
+        for question_uuid in [source, source, target] {
+            answer_dao
+                .create_answer(answer(&question_uuid))
+                .await
+                .unwrap();
+        }
+        for (question_uuid, email) in [
+            (source, "shared@example.com"),
+            (source, "source@example.com"),
+            (target, "shared@example.com"),
+        ] {
+            subscription_dao
+                .subscribe(question_uuid, email.to_owned())
+                .await
+                .unwrap();
+        }
+
+        let summary = dao.merge_question(source, target).await.unwrap();
+
+        assert_eq!((summary.answers, summary.followers), (2, 1));
+        let questions = question_dao.get_questions().await.unwrap();
+        assert_eq!(questions.len(), 1);
+        assert_eq!(questions[0].answer_count, 3);
+        assert_eq!(answer_dao.get_answers(target).await.unwrap().len(), 3);
+        let subscribers = subscription_dao.subscribers(target).await.unwrap().unwrap();
+        assert_eq!(subscribers.emails.len(), 2);
+        assert_eq!(dao.redirect(source).await.unwrap(), Some(target));
+        let result = dao.merge_question(source, target).await;
+        assert!(matches!(
+            result,
+            Err(DBError::NotFound(Entity::Question, _))
+        ));
+    }
+
+    #[tokio::test]
+    async fn unit_of_work_should_only_apply_writes_on_commit() {
+        let store = MemoryStore::new();
+        let question_dao = InMemoryQuestionDao::new(store.clone());
+        let factory = MemoryUnitOfWorkFactory::new(store);
+
+        let mut work = factory.begin().await.unwrap();
+        let staged = work.create_question(question("title")).await.unwrap();
+        work.create_answer(answer(&staged.question_uuid))
+            .await
+            .unwrap();
+        drop(work);
+        assert_eq!(question_dao.get_questions().await.unwrap(), vec![]);
+
+        let mut work = factory.begin().await.unwrap();
+        let committed = work.create_question(question("title")).await.unwrap();
+        work.commit().await.unwrap();
+        assert_eq!(question_dao.get_questions().await.unwrap(), vec![committed]);
+    }
+
+    #[tokio::test]
+    async fn update_answer_should_reject_stale_versions() {
+        let store = MemoryStore::new();
+        let question_dao = InMemoryQuestionDao::new(store.clone());
+        let dao = InMemoryAnswerDao::new(store);
+
+        let question = question_dao
+            .create_question(question("title"))
+            .await
+            .unwrap();
+        let created = dao
+            .create_answer(answer(&question.question_uuid))
+            .await
+            .unwrap();
+
+        let updated = dao
+            .update_answer(created.answer_uuid, "edited".to_owned(), 1)
+            .await
+            .unwrap();
+        assert_eq!((updated.content.as_str(), updated.version), ("edited", 2));
+
+        let stale = dao
+            .update_answer(created.answer_uuid, "again".to_owned(), 1)
+            .await;
+        assert!(matches!(stale, Err(DBError::Conflict(_))));
+    }
+
+    #[tokio::test]
+    async fn archive_should_move_questions_with_their_answers() {
+        let store = MemoryStore::new();
+        let question_dao = InMemoryQuestionDao::new(store.clone());
+        let answer_dao = InMemoryAnswerDao::new(store.clone());
+        let dao = InMemoryArchiveDao::new(store);
+
+        let question = question_dao
+            .create_question(question("title"))
+            .await
+            .unwrap();
+        answer_dao
+            .create_answer(answer(&question.question_uuid))
+            .await
+            .unwrap();
+
+        let summary = dao.archive(1).await.unwrap();
+        assert_eq!(summary.questions, 0);
+
+        // Zero days makes everything edited before now inactive.
+        let summary = dao.archive(0).await.unwrap();
+        assert_eq!(
+            summary,
+            ArchiveSummary {
+                questions: 1,
+                answers: 1
+            }
+        );
+        assert_eq!(question_dao.get_questions().await.unwrap(), vec![]);
+        let archived: Vec<_> = dao
+            .stream_archived_questions(QuestionFilter::default())
+            .try_collect()
+            .await
+            .unwrap();
+        assert_eq!(archived.len(), 1);
+        let answers: Vec<_> = dao
+            .stream_archived_answers(question.question_uuid)
+            .try_collect()
+            .await
+            .unwrap();
+        assert_eq!(answers.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn nearest_questions_should_skip_deleted_questions() {
+        let store = MemoryStore::new();
+        let question_dao = InMemoryQuestionDao::new(store.clone());
+        let dao = InMemoryEmbeddingDao::new(store);
+
+        let near = question_dao
+            .create_question(question("near"))
+            .await
+            .unwrap();
+        let far = question_dao.create_question(question("far")).await.unwrap();
+        let deleted = question_dao
+            .create_question(question("deleted"))
+            .await
+            .unwrap();
+        for (question, embedding) in [
+            (&near, [1.0, 0.1]),
+            (&far, [0.0, 1.0]),
+            (&deleted, [1.0, 0.0]),
+        ] {
+            dao.store_question_embedding(question.question_uuid, embedding.to_vec())
+                .await
+                .unwrap();
+        }
+        question_dao
+            .delete_question(deleted.question_uuid)
+            .await
+            .unwrap();
+
+        let nearest = dao.nearest_questions(vec![1.0, 0.0], 10).await.unwrap();
+
+        assert_eq!(nearest, vec![near, far]);
+    }
+}
@@ -0,0 +1,452 @@
+use async_trait::async_trait;
+use sqlx::{types::Uuid, PgPool};
+
+use super::{
+    acquire,
+    audit::set_actor,
+    invalidation::{CACHE_INVALIDATION_CHANNEL, INVALIDATE_ALL},
+};
+use crate::models::{DBError, Entity, MergeSummary, QuestionUuid};
+
+// Consolidates duplicate questions: the source's answers, attachments and followers move
+// to the target, and the source is deleted, leaving a redirect to the target behind.
+#[async_trait]
+pub trait MergeDao {
+    // All or nothing. Fails with `DBError::NotFound` for `Entity::Question` if either
+    // question does not exist. Redirects to the source are pointed at the target.
+    async fn merge_question(
+        &self,
+        source_uuid: QuestionUuid,
+        target_uuid: QuestionUuid,
+    ) -> Result<MergeSummary, DBError>;
+    // Where a merged question went; None for questions never merged.
+    async fn redirect(&self, question_uuid: QuestionUuid) -> Result<Option<QuestionUuid>, DBError>;
+}
+
+pub struct PgMergeDao {
+    db: PgPool,
+}
+
+impl PgMergeDao {
+    pub fn new(db: PgPool) -> Self {
+        Self { db }
+    }
+}
+
+#[async_trait]
+impl MergeDao for PgMergeDao {
+    async fn merge_question(
+        &self,
+        source_uuid: QuestionUuid,
+        target_uuid: QuestionUuid,
+    ) -> Result<MergeSummary, DBError> {
+        let other = |err: sqlx::Error| DBError::Other(Box::new(err));
+        let (source, target) = (source_uuid.0, target_uuid.0);
+
+        let mut tx = self.db.begin().await.map_err(other)?;
+        set_actor(&mut tx).await.map_err(other)?;
+        // One notification for the whole merge, as for archiving.
+        sqlx::query("SET LOCAL app.skip_cache_invalidation = 'on'")
+            .execute(&mut tx)
+            .await
+            .map_err(other)?;
+
+        // Locking both makes concurrent answers to the source wait for this transaction
+        // and then fail their foreign key check.
+        let found = sqlx::query_scalar!(
+            r#"
+                SELECT question_uuid FROM questions
+                WHERE question_uuid = $1 OR question_uuid = $2
+                ORDER BY question_uuid
+                FOR UPDATE
+            "#,
+            source,
+            target,
+        )
+        .fetch_all(&mut tx)
+        .await
+        .map_err(other)?;
+        if let Some(missing) = [source, target]
+            .into_iter()
+            .find(|uuid| !found.contains(uuid))
+        {
+            return Err(DBError::NotFound(
+                Entity::Question,
+                format!("Question {} does not exist", missing),
+            ));
+        }
+
+        // A new version, so the audit log and outbox record the move.
+        let answers = sqlx::query!(
+            r#"
+                UPDATE answers SET question_uuid = $2, version = version + 1
+                WHERE question_uuid = $1
+            "#,
+            source,
+            target,
+        )
+        .execute(&mut tx)
+        .await
+        .map_err(other)?
+        .rows_affected();
+
+        // The answer_count triggers only follow inserts and deletes. Answers keep their
+        // creation time, so answer reads are told how far back the moved ones go.
+        sqlx::query!(
+            r#"
+                UPDATE questions AS target
+                SET answer_count = target.answer_count + $3,
+                    answers_since = CASE
+                        WHEN $3 > 0 AND LEAST(source.created_at, source.answers_since)
+                            < LEAST(target.created_at, target.answers_since)
+                        THEN LEAST(source.created_at, source.answers_since)
+                        ELSE target.answers_since
+                    END
+                FROM questions AS source
+                WHERE target.question_uuid = $1 AND source.question_uuid = $2
+            "#,
+            target,
+            source,
+            answers as i64,
+        )
+        .execute(&mut tx)
+        .await
+        .map_err(other)?;
+
+        let subscriptions = sqlx::query!(
+            r#"
+                INSERT INTO question_subscriptions ( question_uuid, email, created_at )
+                SELECT $2, email, created_at FROM question_subscriptions WHERE question_uuid = $1
+                ON CONFLICT DO NOTHING
+            "#,
+            source,
+            target,
+        )
+        .execute(&mut tx)
+        .await
+        .map_err(other)?
+        .rows_affected();
+
+        let push_subscriptions = sqlx::query!(
+            r#"
+                INSERT INTO push_subscription_questions ( endpoint, question_uuid )
+                SELECT endpoint, $2 FROM push_subscription_questions WHERE question_uuid = $1
+                ON CONFLICT DO NOTHING
+            "#,
+            source,
+            target,
+        )
+        .execute(&mut tx)
+        .await
+        .map_err(other)?
+        .rows_affected();
+
+        sqlx::query!(
+            "UPDATE question_redirects SET target_uuid = $2 WHERE target_uuid = $1",
+            source,
+            target,
+        )
+        .execute(&mut tx)
+        .await
+        .map_err(other)?;
+
+        sqlx::query!(
+            r#"
+                INSERT INTO question_redirects ( source_uuid, target_uuid ) VALUES ( $1, $2 )
+                ON CONFLICT (source_uuid) DO UPDATE SET target_uuid = $2, merged_at = LOCALTIMESTAMP
+            "#,
+            source,
+            target,
+        )
+        .execute(&mut tx)
+        .await
+        .map_err(other)?;
+
+        // Those of the moved answers as well as the source's own; their objects keep their
+        // storage keys, which name only the attachment.
+        sqlx::query!(
+            "UPDATE attachments SET question_uuid = $2 WHERE question_uuid = $1",
+            source,
+            target,
+        )
+        .execute(&mut tx)
+        .await
+        .map_err(other)?;
+
+        // Its subscriptions go with it.
+        sqlx::query!("DELETE FROM questions WHERE question_uuid = $1", source)
+            .execute(&mut tx)
+            .await
+            .map_err(other)?;
+
+        sqlx::query("SELECT pg_notify($1, $2)")
+            .bind(CACHE_INVALIDATION_CHANNEL)
+            .bind(INVALIDATE_ALL)
+            .execute(&mut tx)
+            .await
+            .map_err(other)?;
+        tx.commit().await.map_err(other)?;
+
+        Ok(MergeSummary {
+            source_uuid,
+            target_uuid,
+            answers,
+            followers: subscriptions + push_subscriptions,
+        })
+    }
+
+    async fn redirect(&self, question_uuid: QuestionUuid) -> Result<Option<QuestionUuid>, DBError> {
+        let mut conn = acquire(&self.db).await?;
+
+        let target: Option<Uuid> = sqlx::query_scalar!(
+            "SELECT target_uuid FROM question_redirects WHERE source_uuid = $1",
+            question_uuid.0,
+        )
+        .fetch_optional(&mut conn)
+        .await
+        .map_err(|err| DBError::Other(Box::new(err)))?;
+
+        Ok(target.map(QuestionUuid))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        models::{
+            empty_metadata, Answer, AnswerSort, AttachmentStatus, AttachmentUuid, NewAttachment,
+            Question, Window,
+        },
+        persistence::{
+            answer_dao::{AnswerDao, AnswerDaoImpl},
+            attachment::{AttachmentDao, PgAttachmentDao},
+            question_dao::{QuestionDao, QuestionDaoImpl},
+            subscription::{PgSubscriptionDao, SubscriptionDao},
+        },
+    };
+    use futures::TryStreamExt;
+
+    async fn create_question(pool: &PgPool, title: &str) -> QuestionUuid {
+        QuestionDaoImpl::new(pool.clone())
+            .create_question(Question {
+                title: title.to_owned(),
+                description: None,
+                metadata: empty_metadata(),
+            })
+            .await
+            .unwrap()
+            .question_uuid
+    }
+
+    #[sqlx::test]
+    async fn merge_question_should_move_answers_and_followers(pool: PgPool) -> Result<(), String> {
+        let dao = PgMergeDao::new(pool.clone());
+        let answer_dao = AnswerDaoImpl::new(pool.clone());
+        let subscription_dao = PgSubscriptionDao::new(pool.clone());
+        let source = create_question(&pool, "source").await;
+        let target = create_question(&pool, "target").await;
+        for question_uuid in [source, source, target] {
+            answer_dao
+                .create_answer(Answer {
+                    question_uuid,
+                    content: "content".to_owned(),
+                })
+                .await
+                .map_err(|err| err.to_string())?;
+        }
+        for (question_uuid, email) in [
+            (source, "shared@example.com"),
+            (source, "source@example.com"),
+            (target, "shared@example.com"),
+        ] {
+            subscription_dao
+                .subscribe(question_uuid, email.to_owned())
+                .await
+                .map_err(|err| err.to_string())?;
+        }
+
+        let summary = dao
+            .merge_question(source, target)
+            .await
+            .map_err(|err| err.to_string())?;
+        assert_eq!(
+            summary,
+            MergeSummary {
+                source_uuid: source,
+                target_uuid: target,
+                answers: 2,
+                followers: 1,
+            }
+        );
+
+        let questions = QuestionDaoImpl::new(pool.clone())
+            .get_questions()
+            .await
+            .map_err(|err| err.to_string())?;
+        assert_eq!(questions.len(), 1);
+        assert_eq!(questions[0].answer_count, 3);
+        let answers = answer_dao
+            .get_answers(target)
+            .await
+            .map_err(|err| err.to_string())?;
+        assert_eq!(answers.len(), 3);
+        let subscribers = subscription_dao
+            .subscribers(target)
+            .await
+            .map_err(|err| err.to_string())?
+            .unwrap();
+        assert_eq!(subscribers.emails.len(), 2);
+        assert_eq!(dao.redirect(source).await.unwrap(), Some(target));
+        assert_eq!(dao.redirect(target).await.unwrap(), None);
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn merge_question_should_move_attachments(pool: PgPool) -> Result<(), String> {
+        let dao = PgMergeDao::new(pool.clone());
+        let attachment_dao = PgAttachmentDao::new(pool.clone());
+        let source = create_question(&pool, "source").await;
+        let target = create_question(&pool, "target").await;
+        let answer = AnswerDaoImpl::new(pool.clone())
+            .create_answer(Answer {
+                question_uuid: source,
+                content: "content".to_owned(),
+            })
+            .await
+            .map_err(|err| err.to_string())?;
+        let mut attachment_uuids = vec![];
+        for answer_uuid in [None, Some(answer.answer_uuid)] {
+            let attachment_uuid = AttachmentUuid::new_v4();
+            attachment_dao
+                .create_attachment(NewAttachment {
+                    attachment_uuid,
+                    question_uuid: source,
+                    answer_uuid,
+                    filename: "photo.png".to_owned(),
+                    content_type: "image/png".to_owned(),
+                    size_bytes: 0,
+                    storage_key: format!("attachments/{}", attachment_uuid),
+                    status: AttachmentStatus::Ready,
+                })
+                .await
+                .map_err(|err| err.to_string())?;
+            attachment_uuids.push(attachment_uuid);
+        }
+
+        dao.merge_question(source, target)
+            .await
+            .map_err(|err| err.to_string())?;
+
+        for attachment_uuid in attachment_uuids {
+            let attachment = attachment_dao
+                .get_attachment(attachment_uuid)
+                .await
+                .map_err(|err| err.to_string())?
+                .ok_or("attachment went with the source")?;
+            assert_eq!(attachment.question_uuid, target);
+        }
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn merged_answers_older_than_the_target_should_still_be_listed(
+        pool: PgPool,
+    ) -> Result<(), String> {
+        let dao = PgMergeDao::new(pool.clone());
+        let answer_dao = AnswerDaoImpl::new(pool.clone());
+        let source = create_question(&pool, "source").await;
+        for question_uuid in [source, source] {
+            answer_dao
+                .create_answer(Answer {
+                    question_uuid,
+                    content: "content".to_owned(),
+                })
+                .await
+                .map_err(|err| err.to_string())?;
+        }
+        let target = create_question(&pool, "target").await;
+        let later = create_question(&pool, "later").await;
+        // Both were asked after the source's answers were posted.
+        sqlx::query(
+            "UPDATE questions SET created_at = created_at + INTERVAL '1 day' WHERE question_uuid = ANY($1)",
+        )
+        .bind(vec![target.0, later.0])
+        .execute(&pool)
+        .await
+        .map_err(|err| err.to_string())?;
+
+        dao.merge_question(source, target)
+            .await
+            .map_err(|err| err.to_string())?;
+        dao.merge_question(target, later)
+            .await
+            .map_err(|err| err.to_string())?;
+
+        let question = QuestionDaoImpl::new(pool.clone())
+            .get_question(later)
+            .await
+            .map_err(|err| err.to_string())?;
+        assert_eq!(question.answer_count, 2);
+        let answers = answer_dao
+            .get_answers(later)
+            .await
+            .map_err(|err| err.to_string())?;
+        assert_eq!(answers.len(), 2);
+        let streamed: Vec<_> = answer_dao
+            .stream_answers(later)
+            .try_collect()
+            .await
+            .map_err(|err| err.to_string())?;
+        assert_eq!(streamed.len(), 2);
+        let listed = answer_dao
+            .list_answers(
+                later,
+                false,
+                AnswerSort::Oldest,
+                Window {
+                    offset: 0,
+                    limit: 10,
+                },
+            )
+            .await
+            .map_err(|err| err.to_string())?;
+        assert_eq!((listed.items.len(), listed.total), (2, 2));
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn merge_question_should_change_nothing_for_unknown_targets(
+        pool: PgPool,
+    ) -> Result<(), String> {
+        let dao = PgMergeDao::new(pool.clone());
+        let source = create_question(&pool, "source").await;
+
+        let result = dao.merge_question(source, QuestionUuid::new_v4()).await;
+        assert!(matches!(
+            result,
+            Err(DBError::NotFound(Entity::Question, _))
+        ));
+        assert_eq!(dao.redirect(source).await.unwrap(), None);
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn redirects_should_follow_later_merges(pool: PgPool) -> Result<(), String> {
+        let dao = PgMergeDao::new(pool.clone());
+        let first = create_question(&pool, "first").await;
+        let second = create_question(&pool, "second").await;
+        let third = create_question(&pool, "third").await;
+
+        dao.merge_question(first, second)
+            .await
+            .map_err(|err| err.to_string())?;
+        dao.merge_question(second, third)
+            .await
+            .map_err(|err| err.to_string())?;
+
+        assert_eq!(dao.redirect(first).await.unwrap(), Some(third));
+        assert_eq!(dao.redirect(second).await.unwrap(), Some(third));
+        Ok(())
+    }
+}
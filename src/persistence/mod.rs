@@ -1,2 +1,245 @@
+use std::{
+    sync::atomic::{AtomicU64, Ordering},
+    time::{Duration, Instant},
+};
+
+use futures::stream::BoxStream;
+use log::warn;
+use sqlx::{migrate::Migrator, pool::PoolConnection, Database, PgPool, Pool, Postgres};
+
+use self::replica::ReadReplica;
+use crate::{
+    metrics,
+    models::{DBError, Entity, QuestionUuid},
+};
+
+pub mod activity;
 pub mod answer_dao;
+pub mod archive;
+pub mod attachment;
+pub mod audit;
+pub mod backup;
+pub mod cache;
+#[cfg(feature = "chaos")]
+pub mod chaos;
+pub mod circuit_breaker;
+pub mod duplicate;
+pub mod embedding;
+pub mod export;
+pub mod import;
+pub mod ingestion;
+pub mod instrumented;
+pub mod invalidation;
+pub mod link_preview;
+pub mod locks;
+pub mod memory;
+pub mod merge;
+pub mod moderation;
+#[cfg(feature = "mysql")]
+pub mod mysql;
+pub mod outbox;
+pub mod partition;
 pub mod question_dao;
+pub mod replica;
+pub mod rest_hook;
+pub mod retry;
+#[cfg(any(feature = "sqlite", feature = "mysql"))]
+mod row;
+pub mod search;
+pub mod shedding;
+#[cfg(feature = "sqlite")]
+pub mod sqlite;
+#[cfg(any(feature = "sqlite", feature = "mysql"))]
+mod statements;
+pub mod stats;
+pub mod subscription;
+pub mod tag;
+pub mod translation;
+pub mod unit_of_work;
+pub mod user;
+pub mod word_filter;
+
+pub const PRIMARY_POOL: &str = "primary";
+
+// The Postgres schema, applied with `sqlx migrate run` before the API starts.
+static MIGRATOR: Migrator = sqlx::migrate!();
+
+// Rows read one at a time from an open cursor, for results too large to hold in memory.
+pub type RowStream<'a, T> = BoxStream<'a, Result<T, DBError>>;
+
+static ACQUIRE_WARN_THRESHOLD_MS: AtomicU64 = AtomicU64::new(500);
+
+pub fn set_acquire_warn_threshold(threshold: Duration) {
+    ACQUIRE_WARN_THRESHOLD_MS.store(threshold.as_millis() as u64, Ordering::Relaxed);
+}
+
+// The primary pool of whichever backend `DATABASE_URL` selected.
+#[derive(Clone)]
+pub enum DatabasePool {
+    Postgres(PgPool),
+    #[cfg(feature = "sqlite")]
+    Sqlite(sqlx::SqlitePool),
+    #[cfg(feature = "mysql")]
+    MySql(sqlx::MySqlPool),
+}
+
+impl DatabasePool {
+    pub fn record_state(&self) {
+        match self {
+            Self::Postgres(pool) => metrics::record_pool_state(PRIMARY_POOL, pool),
+            #[cfg(feature = "sqlite")]
+            Self::Sqlite(pool) => metrics::record_pool_state(PRIMARY_POOL, pool),
+            #[cfg(feature = "mysql")]
+            Self::MySql(pool) => metrics::record_pool_state(PRIMARY_POOL, pool),
+        }
+    }
+}
+
+pub async fn acquire<DB: Database>(pool: &Pool<DB>) -> Result<PoolConnection<DB>, DBError> {
+    acquire_from(pool, PRIMARY_POOL).await
+}
+
+// Reads go to the replica when one is configured and reachable.
+pub async fn acquire_read(
+    primary: &PgPool,
+    replica: Option<&ReadReplica>,
+) -> Result<PoolConnection<Postgres>, DBError> {
+    match replica {
+        Some(replica) => replica.acquire_or_primary(primary).await,
+        None => acquire(primary).await,
+    }
+}
+
+// Checks out a connection while recording how long the caller had to wait for it.
+pub async fn acquire_from<DB: Database>(
+    pool: &Pool<DB>,
+    name: &str,
+) -> Result<PoolConnection<DB>, DBError> {
+    let started = Instant::now();
+    let waiting = (name == PRIMARY_POOL).then(shedding::wait);
+    let connection = pool.acquire().await;
+    drop(waiting);
+    let waited = started.elapsed();
+
+    metrics::POOL_ACQUIRE_WAIT
+        .with_label_values(&[name])
+        .observe(waited.as_secs_f64());
+
+    let threshold = Duration::from_millis(ACQUIRE_WARN_THRESHOLD_MS.load(Ordering::Relaxed));
+    if waited > threshold {
+        warn!(
+            "Waited {:?} for a {} pool connection (size: {}, idle: {}); consider raising DATABASE_MAX_CONNECTIONS.",
+            waited,
+            name,
+            pool.size(),
+            pool.num_idle()
+        );
+    }
+
+    connection.map_err(|err| DBError::Other(Box::new(err)))
+}
+
+// Tells apart why an update guarded by a version matched no row: someone else
+// updated the row first, or it does not exist.
+pub fn missed_update(
+    entity: Entity,
+    uuid: &str,
+    current_version: Option<i64>,
+    version: i64,
+) -> DBError {
+    match current_version {
+        Some(current) => DBError::Conflict(format!(
+            "{} {} is at version {}, not {}",
+            entity, uuid, current, version
+        )),
+        None => DBError::NotFound(entity, format!("{} {} does not exist", entity, uuid)),
+    }
+}
+
+pub fn unknown_question(question_uuid: &QuestionUuid) -> DBError {
+    DBError::NotFound(
+        Entity::Question,
+        format!("Question {} does not exist", question_uuid),
+    )
+}
+
+// Why a question under `QuestionDeletion::Restrict` was not deleted.
+pub fn blocked_deletion(question_uuid: &str, answer_count: i64) -> DBError {
+    DBError::Conflict(format!(
+        "Question {} still has {} answers; delete them first",
+        question_uuid, answer_count
+    ))
+}
+
+// The Postgres migrations this build expects but the database has not recorded as
+// applied, e.g. `20231001160000_version`.
+pub async fn pending_migrations(pool: &PgPool) -> Result<Vec<String>, sqlx::Error> {
+    let applied: Vec<i64> =
+        sqlx::query_scalar("SELECT version FROM _sqlx_migrations WHERE success")
+            .fetch_all(pool)
+            .await
+            .or_else(|err| match &err {
+                // Nothing was ever applied with sqlx.
+                sqlx::Error::Database(db_err) if db_err.code().as_deref() == Some("42P01") => {
+                    Ok(vec![])
+                }
+                _ => Err(err),
+            })?;
+
+    Ok(MIGRATOR
+        .iter()
+        .filter(|migration| !migration.migration_type.is_down_migration())
+        .filter(|migration| !applied.contains(&migration.version))
+        .map(|migration| format!("{}_{}", migration.version, migration.description))
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[sqlx::test]
+    async fn pending_migrations_should_list_what_is_not_applied(
+        pool: PgPool,
+    ) -> Result<(), String> {
+        let pending = pending_migrations(&pool)
+            .await
+            .map_err(|err| err.to_string())?;
+        assert_eq!(pending, Vec::<String>::new());
+
+        sqlx::query("DELETE FROM _sqlx_migrations WHERE version = 20231001160000")
+            .execute(&pool)
+            .await
+            .map_err(|err| err.to_string())?;
+
+        let pending = pending_migrations(&pool)
+            .await
+            .map_err(|err| err.to_string())?;
+        assert_eq!(pending, vec!["20231001160000_version".to_owned()]);
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn acquire_should_record_wait_time(pool: PgPool) -> Result<(), String> {
+        let histogram = metrics::POOL_ACQUIRE_WAIT.with_label_values(&[PRIMARY_POOL]);
+        let before = histogram.get_sample_count();
+
+        acquire(&pool)
+            .await
+            .map_err(|err| format!("Expected Ok but got: {}", err))?;
+
+        assert!(histogram.get_sample_count() > before);
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn acquire_should_fail_on_closed_pool(pool: PgPool) -> Result<(), String> {
+        pool.close().await;
+
+        match acquire(&pool).await {
+            Err(DBError::Other(_)) => Ok(()),
+            Err(err) => Err(format!("Expected Other but got: {}", err)),
+            Ok(_) => Err("Expected Err but got Ok".to_owned()),
+        }
+    }
+}
@@ -0,0 +1,316 @@
+use async_trait::async_trait;
+use serde_json::Value;
+use sqlx::{types::chrono::NaiveDateTime, PgPool};
+use uuid::Uuid;
+
+use super::acquire;
+use crate::models::{DBError, Listed, ModerationItem, ModerationUuid, NewModerationItem, Window};
+
+// The moderation queue: creates held for a moderator, oldest first.
+#[async_trait]
+pub trait ModerationDao {
+    async fn hold(&self, item: NewModerationItem) -> Result<ModerationItem, DBError>;
+    async fn held_items(&self, window: Window) -> Result<Listed<ModerationItem>, DBError>;
+    // Removes the item and returns it, so two moderators cannot both act on it. None for
+    // unknown items.
+    async fn take_held(
+        &self,
+        moderation_uuid: ModerationUuid,
+    ) -> Result<Option<ModerationItem>, DBError>;
+    // Like `take_held` for all the items at once; unknown ones are left out.
+    async fn take_held_many(
+        &self,
+        moderation_uuids: &[ModerationUuid],
+    ) -> Result<Vec<ModerationItem>, DBError>;
+}
+
+struct ModerationRow {
+    moderation_uuid: Uuid,
+    content: Value,
+    score: f32,
+    categories: Vec<String>,
+    created_at: NaiveDateTime,
+    spam_context: Option<Value>,
+}
+
+impl TryFrom<ModerationRow> for ModerationItem {
+    type Error = DBError;
+
+    fn try_from(row: ModerationRow) -> Result<Self, Self::Error> {
+        Ok(ModerationItem {
+            moderation_uuid: ModerationUuid(row.moderation_uuid),
+            content: serde_json::from_value(row.content)
+                .map_err(|err| DBError::Other(Box::new(err)))?,
+            score: row.score,
+            categories: row.categories,
+            spam_context: row
+                .spam_context
+                .map(serde_json::from_value)
+                .transpose()
+                .map_err(|err| DBError::Other(Box::new(err)))?,
+            created_at: row.created_at.and_utc(),
+        })
+    }
+}
+
+pub struct PgModerationDao {
+    db: PgPool,
+}
+
+impl PgModerationDao {
+    pub fn new(db: PgPool) -> Self {
+        Self { db }
+    }
+}
+
+#[async_trait]
+impl ModerationDao for PgModerationDao {
+    async fn hold(&self, item: NewModerationItem) -> Result<ModerationItem, DBError> {
+        let mut conn = acquire(&self.db).await?;
+        let content =
+            serde_json::to_value(&item.content).map_err(|err| DBError::Other(Box::new(err)))?;
+        let spam_context = item
+            .spam_context
+            .as_ref()
+            .map(serde_json::to_value)
+            .transpose()
+            .map_err(|err| DBError::Other(Box::new(err)))?;
+
+        let row = sqlx::query_as!(
+            ModerationRow,
+            r#"
+                INSERT INTO moderation_queue
+                    ( moderation_uuid, content, score, categories, spam_context )
+                VALUES ( $1, $2, $3, $4, $5 )
+                RETURNING *
+            "#,
+            item.moderation_uuid.0,
+            content,
+            item.score,
+            &item.categories,
+            spam_context,
+        )
+        .fetch_one(&mut conn)
+        .await
+        .map_err(|err| DBError::Other(Box::new(err)))?;
+
+        row.try_into()
+    }
+
+    async fn held_items(&self, window: Window) -> Result<Listed<ModerationItem>, DBError> {
+        let mut conn = acquire(&self.db).await?;
+
+        let rows = sqlx::query_as!(
+            ModerationRow,
+            r#"
+                SELECT * FROM moderation_queue
+                ORDER BY created_at, moderation_uuid
+                OFFSET $1
+                LIMIT $2
+            "#,
+            window.offset as i64,
+            window.limit as i64,
+        )
+        .fetch_all(&mut conn)
+        .await
+        .map_err(|err| DBError::Other(Box::new(err)))?;
+
+        let total = sqlx::query_scalar!(r#"SELECT COUNT(*) AS "total!" FROM moderation_queue"#)
+            .fetch_one(&mut conn)
+            .await
+            .map_err(|err| DBError::Other(Box::new(err)))?;
+
+        Ok(Listed {
+            items: rows
+                .into_iter()
+                .map(ModerationItem::try_from)
+                .collect::<Result<_, _>>()?,
+            total: total as u64,
+        })
+    }
+
+    async fn take_held(
+        &self,
+        moderation_uuid: ModerationUuid,
+    ) -> Result<Option<ModerationItem>, DBError> {
+        let mut conn = acquire(&self.db).await?;
+
+        let row = sqlx::query_as!(
+            ModerationRow,
+            "DELETE FROM moderation_queue WHERE moderation_uuid = $1 RETURNING *",
+            moderation_uuid.0,
+        )
+        .fetch_optional(&mut conn)
+        .await
+        .map_err(|err| DBError::Other(Box::new(err)))?;
+
+        row.map(ModerationItem::try_from).transpose()
+    }
+
+    async fn take_held_many(
+        &self,
+        moderation_uuids: &[ModerationUuid],
+    ) -> Result<Vec<ModerationItem>, DBError> {
+        let moderation_uuids: Vec<Uuid> = moderation_uuids.iter().map(|uuid| uuid.0).collect();
+
+        let mut conn = acquire(&self.db).await?;
+
+        let rows = sqlx::query_as!(
+            ModerationRow,
+            "DELETE FROM moderation_queue WHERE moderation_uuid = ANY($1) RETURNING *",
+            &moderation_uuids,
+        )
+        .fetch_all(&mut conn)
+        .await
+        .map_err(|err| DBError::Other(Box::new(err)))?;
+
+        rows.into_iter().map(ModerationItem::try_from).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{empty_metadata, HeldContent, Question, SpamContext};
+
+    #[sqlx::test]
+    async fn held_items_should_be_taken_once(pool: PgPool) -> Result<(), String> {
+        let dao = PgModerationDao::new(pool);
+        let item = NewModerationItem {
+            moderation_uuid: ModerationUuid::new_v4(),
+            content: HeldContent::Question {
+                question: Question {
+                    title: "title".to_owned(),
+                    description: None,
+                    metadata: empty_metadata(),
+                },
+                author_email: Some("author@example.com".to_owned()),
+            },
+            score: 1.0,
+            categories: vec!["spam".to_owned()],
+            spam_context: Some(SpamContext {
+                user_ip: Some("203.0.113.7".to_owned()),
+                user_agent: Some("curl/8.0".to_owned()),
+                referrer: None,
+            }),
+        };
+
+        let held = dao.hold(item.clone()).await.unwrap();
+        assert_eq!(held.content, item.content);
+        assert_eq!(held.categories, item.categories);
+        assert_eq!(held.spam_context, item.spam_context);
+        assert_eq!(
+            dao.held_items(Window {
+                offset: 0,
+                limit: 10
+            })
+            .await
+            .unwrap()
+            .items,
+            std::slice::from_ref(&held)
+        );
+
+        assert_eq!(
+            dao.take_held(item.moderation_uuid).await.unwrap(),
+            Some(held)
+        );
+        assert_eq!(dao.take_held(item.moderation_uuid).await.unwrap(), None);
+        assert_eq!(
+            dao.held_items(Window {
+                offset: 0,
+                limit: 10
+            })
+            .await
+            .unwrap()
+            .total,
+            0
+        );
+
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn held_items_should_be_taken_together(pool: PgPool) -> Result<(), String> {
+        let dao = PgModerationDao::new(pool);
+        let mut held = vec![];
+        for title in ["first", "second", "third"] {
+            let item = dao
+                .hold(NewModerationItem {
+                    moderation_uuid: ModerationUuid::new_v4(),
+                    content: HeldContent::Question {
+                        question: Question {
+                            title: title.to_owned(),
+                            description: None,
+                            metadata: empty_metadata(),
+                        },
+                        author_email: None,
+                    },
+                    score: 1.0,
+                    categories: vec![],
+                    spam_context: None,
+                })
+                .await
+                .map_err(|err| err.to_string())?;
+            held.push(item.moderation_uuid);
+        }
+
+        let taken = dao
+            .take_held_many(&[held[0], held[2], ModerationUuid::new_v4()])
+            .await
+            .map_err(|err| err.to_string())?;
+        let mut taken: Vec<_> = taken.iter().map(|item| item.moderation_uuid).collect();
+        taken.sort_by_key(|uuid| uuid.0);
+        let mut expected = vec![held[0], held[2]];
+        expected.sort_by_key(|uuid| uuid.0);
+        assert_eq!(taken, expected);
+
+        let left = dao
+            .held_items(Window {
+                offset: 0,
+                limit: 10,
+            })
+            .await
+            .map_err(|err| err.to_string())?;
+        assert_eq!(left.total, 1);
+        assert_eq!(left.items[0].moderation_uuid, held[1]);
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn held_items_should_be_paged_oldest_first(pool: PgPool) -> Result<(), String> {
+        let dao = PgModerationDao::new(pool);
+        let mut held = vec![];
+        for title in ["first", "second", "third"] {
+            let item = dao
+                .hold(NewModerationItem {
+                    moderation_uuid: ModerationUuid::new_v4(),
+                    content: HeldContent::Question {
+                        question: Question {
+                            title: title.to_owned(),
+                            description: None,
+                            metadata: empty_metadata(),
+                        },
+                        author_email: None,
+                    },
+                    score: 1.0,
+                    categories: vec![],
+                    spam_context: None,
+                })
+                .await
+                .map_err(|err| err.to_string())?;
+            held.push(item.moderation_uuid);
+        }
+
+        let page = dao
+            .held_items(Window {
+                offset: 1,
+                limit: 1,
+            })
+            .await
+            .map_err(|err| err.to_string())?;
+        assert_eq!(page.total, 3);
+        assert_eq!(page.items.len(), 1);
+        assert_eq!(page.items[0].moderation_uuid, held[1]);
+        Ok(())
+    }
+}
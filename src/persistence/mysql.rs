@@ -0,0 +1,784 @@
+use std::time::Duration;
+
+use async_stream::try_stream;
+use async_trait::async_trait;
+use chrono::NaiveDate;
+use futures::TryStreamExt;
+use sqlx::{
+    migrate::{MigrateError, Migrator},
+    mysql::{MySqlDatabaseError, MySqlPoolOptions},
+    types::{Json, Uuid},
+    MySql, MySqlConnection, MySqlPool, Transaction,
+};
+
+use super::{
+    acquire,
+    answer_dao::AnswerDao,
+    archive::ArchiveDao,
+    blocked_deletion, missed_update,
+    question_dao::{QuestionDao, QuestionDeletion},
+    row::{AnswerRow, QuestionRow},
+    statements,
+    stats::StatsDao,
+    unit_of_work::{UnitOfWork, UnitOfWorkFactory},
+    unknown_question, RowStream,
+};
+use crate::models::{
+    mysql_error_number, Answer, AnswerDetail, AnswerSort, AnswerUuid, ArchiveSummary, DBError,
+    DailyStats, Entity, Listed, Question, QuestionDetail, QuestionFilter, QuestionSort,
+    QuestionUuid, ResponseStats, Window,
+};
+
+static MIGRATOR: Migrator = sqlx::migrate!("./migrations/mysql");
+
+pub async fn connect(
+    url: &str,
+    max_connections: u32,
+    acquire_timeout: Duration,
+) -> Result<MySqlPool, MigrateError> {
+    let pool = MySqlPoolOptions::new()
+        .max_connections(max_connections)
+        .acquire_timeout(acquire_timeout)
+        .connect(url)
+        .await?;
+
+    MIGRATOR.run(&pool).await?;
+    Ok(pool)
+}
+
+pub struct MySqlQuestionDao {
+    db: MySqlPool,
+    deletion: QuestionDeletion,
+}
+
+impl MySqlQuestionDao {
+    pub fn new(db: MySqlPool) -> Self {
+        Self {
+            db,
+            deletion: QuestionDeletion::Cascade,
+        }
+    }
+
+    pub fn with_deletion(mut self, deletion: QuestionDeletion) -> Self {
+        self.deletion = deletion;
+        self
+    }
+}
+
+#[async_trait]
+impl QuestionDao for MySqlQuestionDao {
+    async fn create_question(&self, question: Question) -> Result<QuestionDetail, DBError> {
+        let mut conn = acquire(&self.db).await?;
+        insert_question(&mut conn, question).await
+    }
+
+    async fn delete_question(&self, question_uuid: QuestionUuid) -> Result<(), DBError> {
+        let question_uuid = question_uuid.to_string();
+
+        let mut conn = acquire(&self.db).await?;
+        let restrict = self.deletion == QuestionDeletion::Restrict;
+
+        let deleted = sqlx::query(statements::DELETE_QUESTION)
+            .bind(&question_uuid)
+            .bind(restrict)
+            .execute(&mut conn)
+            .await
+            .map_err(|err| DBError::Other(Box::new(err)))?
+            .rows_affected();
+
+        if deleted == 0 && restrict {
+            let answer_count = sqlx::query_scalar::<_, i64>(statements::QUESTION_ANSWER_COUNT)
+                .bind(&question_uuid)
+                .fetch_optional(&mut conn)
+                .await
+                .map_err(|err| DBError::Other(Box::new(err)))?;
+
+            if let Some(answer_count) = answer_count {
+                return Err(blocked_deletion(&question_uuid, answer_count));
+            }
+        }
+
+        Ok(())
+    }
+
+    async fn update_question(
+        &self,
+        question_uuid: QuestionUuid,
+        question: Question,
+        version: i64,
+    ) -> Result<QuestionDetail, DBError> {
+        let question_uuid = question_uuid.to_string();
+
+        let mut conn = acquire(&self.db).await?;
+
+        // Read back separately so changes made by the updated_at trigger are included.
+        let updated = sqlx::query(statements::UPDATE_QUESTION)
+            .bind(&question.title)
+            .bind(&question.description)
+            .bind(Json(&question.metadata))
+            .bind(&question_uuid)
+            .bind(version)
+            .execute(&mut conn)
+            .await
+            .map_err(|err| DBError::Other(Box::new(err)))?
+            .rows_affected();
+
+        if updated == 0 {
+            let current_version = sqlx::query_scalar::<_, i64>(statements::QUESTION_VERSION)
+                .bind(&question_uuid)
+                .fetch_optional(&mut conn)
+                .await
+                .map_err(|err| DBError::Other(Box::new(err)))?;
+
+            return Err(missed_update(
+                Entity::Question,
+                &question_uuid,
+                current_version,
+                version,
+            ));
+        }
+
+        let result = sqlx::query_as::<_, QuestionRow>(statements::SELECT_QUESTION)
+            .bind(&question_uuid)
+            .fetch_one(&mut conn)
+            .await
+            .map_err(|err| DBError::Other(Box::new(err)))?;
+
+        Ok(result.into())
+    }
+
+    async fn get_questions(&self) -> Result<Vec<QuestionDetail>, DBError> {
+        let mut conn = acquire(&self.db).await?;
+
+        let result = sqlx::query_as::<_, QuestionRow>(statements::SELECT_QUESTIONS)
+            .fetch_all(&mut conn)
+            .await
+            .map_err(|err| DBError::Other(Box::new(err)))?;
+
+        Ok(result.into_iter().map(QuestionDetail::from).collect())
+    }
+
+    async fn get_question(&self, question_uuid: QuestionUuid) -> Result<QuestionDetail, DBError> {
+        let mut conn = acquire(&self.db).await?;
+
+        let result = sqlx::query_as::<_, QuestionRow>(statements::SELECT_QUESTION)
+            .bind(question_uuid.to_string())
+            .fetch_optional(&mut conn)
+            .await
+            .map_err(|err| DBError::Other(Box::new(err)))?;
+
+        result
+            .map(QuestionDetail::from)
+            .ok_or_else(|| unknown_question(&question_uuid))
+    }
+
+    async fn get_questions_matching(
+        &self,
+        filter: &QuestionFilter,
+    ) -> Result<Vec<QuestionDetail>, DBError> {
+        matching_questions(&self.db, "questions", filter.clone())
+            .try_collect()
+            .await
+    }
+
+    async fn list_questions(
+        &self,
+        filter: &QuestionFilter,
+        include_archived: bool,
+        sort: QuestionSort,
+        window: Window,
+    ) -> Result<Listed<QuestionDetail>, DBError> {
+        let mut conn = acquire(&self.db).await?;
+        let after = filter.created.after.map(|at| at.naive_utc());
+        let before = filter.created.before.map(|at| at.naive_utc());
+
+        // The conditions of `get_questions_matching`, over archived questions too.
+        let matching = format!(
+            r#"
+                FROM {}
+                WHERE JSON_CONTAINS(metadata, CAST(? AS JSON))
+                AND (? IS NULL OR created_at >= ?)
+                AND (? IS NULL OR created_at < ?)
+            "#,
+            statements::QUESTIONS_AND_ARCHIVED
+        );
+
+        let items = sqlx::query_as::<_, QuestionRow>(&format!(
+            "SELECT * {} ORDER BY {} LIMIT ? OFFSET ?",
+            matching,
+            statements::question_order(sort)
+        ))
+        .bind(include_archived)
+        .bind(Json(filter.metadata.to_json()))
+        .bind(after)
+        .bind(after)
+        .bind(before)
+        .bind(before)
+        .bind(window.limit as i64)
+        .bind(window.offset as i64)
+        .fetch_all(&mut conn)
+        .await
+        .map_err(|err| DBError::Other(Box::new(err)))?;
+
+        let total: i64 = sqlx::query_scalar(&format!("SELECT COUNT(*) {}", matching))
+            .bind(include_archived)
+            .bind(Json(filter.metadata.to_json()))
+            .bind(after)
+            .bind(after)
+            .bind(before)
+            .bind(before)
+            .fetch_one(&mut conn)
+            .await
+            .map_err(|err| DBError::Other(Box::new(err)))?;
+
+        Ok(Listed {
+            items: items.into_iter().map(QuestionDetail::from).collect(),
+            total: total as u64,
+        })
+    }
+
+    async fn list_unanswered_questions(
+        &self,
+        window: Window,
+    ) -> Result<Listed<QuestionDetail>, DBError> {
+        let mut conn = acquire(&self.db).await?;
+
+        let items = sqlx::query_as::<_, QuestionRow>(statements::UNANSWERED_QUESTIONS)
+            .bind(window.limit as i64)
+            .bind(window.offset as i64)
+            .fetch_all(&mut conn)
+            .await
+            .map_err(|err| DBError::Other(Box::new(err)))?;
+        let total: i64 = sqlx::query_scalar(statements::UNANSWERED_QUESTION_COUNT)
+            .fetch_one(&mut conn)
+            .await
+            .map_err(|err| DBError::Other(Box::new(err)))?;
+
+        Ok(Listed {
+            items: items.into_iter().map(QuestionDetail::from).collect(),
+            total: total as u64,
+        })
+    }
+
+    fn stream_questions(&self, filter: QuestionFilter) -> RowStream<'_, QuestionDetail> {
+        matching_questions(&self.db, "questions", filter)
+    }
+}
+
+// The questions of `table` that `filter` matches, fetched as they are consumed.
+fn matching_questions<'a>(
+    db: &'a MySqlPool,
+    table: &'static str,
+    filter: QuestionFilter,
+) -> RowStream<'a, QuestionDetail> {
+    Box::pin(try_stream! {
+        let mut conn = acquire(db).await?;
+
+        let sql = format!(
+            r#"
+                SELECT question_uuid, title, description, metadata, created_at, updated_at, last_activity_at, answer_count, version
+                FROM {}
+                WHERE JSON_CONTAINS(metadata, CAST(? AS JSON))
+                AND (? IS NULL OR created_at >= ?)
+                AND (? IS NULL OR created_at < ?)
+            "#,
+            table
+        );
+        let after = filter.created.after.map(|at| at.naive_utc());
+        let before = filter.created.before.map(|at| at.naive_utc());
+        let mut rows = sqlx::query_as::<_, QuestionRow>(&sql)
+            .bind(Json(filter.metadata.to_json()))
+            .bind(after)
+            .bind(after)
+            .bind(before)
+            .bind(before)
+            .fetch(&mut conn);
+
+        while let Some(row) = rows
+            .try_next()
+            .await
+            .map_err(|err| DBError::Other(Box::new(err)))?
+        {
+            yield row.into();
+        }
+    })
+}
+
+pub struct MySqlAnswerDao {
+    db: MySqlPool,
+}
+
+impl MySqlAnswerDao {
+    pub fn new(db: MySqlPool) -> Self {
+        Self { db }
+    }
+}
+
+#[async_trait]
+impl AnswerDao for MySqlAnswerDao {
+    async fn create_answer(&self, answer: Answer) -> Result<AnswerDetail, DBError> {
+        let mut conn = acquire(&self.db).await?;
+        insert_answer(&mut conn, answer).await
+    }
+
+    async fn delete_answer(&self, answer_uuid: AnswerUuid) -> Result<(), DBError> {
+        let answer_uuid = answer_uuid.to_string();
+
+        let mut conn = acquire(&self.db).await?;
+
+        sqlx::query(statements::DELETE_ANSWER)
+            .bind(answer_uuid)
+            .execute(&mut conn)
+            .await
+            .map_err(|err| DBError::Other(Box::new(err)))?;
+
+        Ok(())
+    }
+
+    async fn update_answer(
+        &self,
+        answer_uuid: AnswerUuid,
+        content: String,
+        version: i64,
+    ) -> Result<AnswerDetail, DBError> {
+        let answer_uuid = answer_uuid.to_string();
+
+        let mut conn = acquire(&self.db).await?;
+
+        let updated = sqlx::query(statements::UPDATE_ANSWER)
+            .bind(&content)
+            .bind(&answer_uuid)
+            .bind(version)
+            .execute(&mut conn)
+            .await
+            .map_err(|err| DBError::Other(Box::new(err)))?
+            .rows_affected();
+
+        if updated == 0 {
+            let current_version = sqlx::query_scalar::<_, i64>(statements::ANSWER_VERSION)
+                .bind(&answer_uuid)
+                .fetch_optional(&mut conn)
+                .await
+                .map_err(|err| DBError::Other(Box::new(err)))?;
+
+            return Err(missed_update(
+                Entity::Answer,
+                &answer_uuid,
+                current_version,
+                version,
+            ));
+        }
+
+        let result = sqlx::query_as::<_, AnswerRow>(statements::SELECT_ANSWER)
+            .bind(&answer_uuid)
+            .fetch_one(&mut conn)
+            .await
+            .map_err(|err| DBError::Other(Box::new(err)))?;
+
+        Ok(result.into())
+    }
+
+    async fn get_answers(&self, question_uuid: QuestionUuid) -> Result<Vec<AnswerDetail>, DBError> {
+        let question_uuid = question_uuid.to_string();
+
+        let mut conn = acquire(&self.db).await?;
+
+        let result = sqlx::query_as::<_, AnswerRow>(statements::SELECT_ANSWERS)
+            .bind(question_uuid)
+            .fetch_all(&mut conn)
+            .await
+            .map_err(|err| DBError::Other(Box::new(err)))?;
+
+        Ok(result.into_iter().map(AnswerDetail::from).collect())
+    }
+
+    async fn list_answers(
+        &self,
+        question_uuid: QuestionUuid,
+        include_archived: bool,
+        sort: AnswerSort,
+        window: Window,
+    ) -> Result<Listed<AnswerDetail>, DBError> {
+        let question_uuid = question_uuid.to_string();
+
+        let mut conn = acquire(&self.db).await?;
+
+        let items = sqlx::query_as::<_, AnswerRow>(&format!(
+            "SELECT * FROM {} ORDER BY {} LIMIT ? OFFSET ?",
+            statements::ANSWERS_AND_ARCHIVED,
+            statements::answer_order(sort)
+        ))
+        .bind(&question_uuid)
+        .bind(include_archived)
+        .bind(&question_uuid)
+        .bind(window.limit as i64)
+        .bind(window.offset as i64)
+        .fetch_all(&mut conn)
+        .await
+        .map_err(|err| DBError::Other(Box::new(err)))?;
+
+        let total: i64 = sqlx::query_scalar(&format!(
+            "SELECT COUNT(*) FROM {}",
+            statements::ANSWERS_AND_ARCHIVED
+        ))
+        .bind(&question_uuid)
+        .bind(include_archived)
+        .bind(&question_uuid)
+        .fetch_one(&mut conn)
+        .await
+        .map_err(|err| DBError::Other(Box::new(err)))?;
+
+        Ok(Listed {
+            items: items.into_iter().map(AnswerDetail::from).collect(),
+            total: total as u64,
+        })
+    }
+
+    fn stream_answers(&self, question_uuid: QuestionUuid) -> RowStream<'_, AnswerDetail> {
+        Box::pin(try_stream! {
+            let question_uuid = question_uuid.to_string();
+
+            let mut conn = acquire(&self.db).await?;
+
+            let mut rows = sqlx::query_as::<_, AnswerRow>(
+                statements::SELECT_ANSWERS,
+            )
+            .bind(question_uuid)
+            .fetch(&mut conn);
+
+            while let Some(row) = rows
+                .try_next()
+                .await
+                .map_err(|err| DBError::Other(Box::new(err)))?
+            {
+                yield row.into();
+            }
+        })
+    }
+
+    async fn get_answers_of_questions(
+        &self,
+        question_uuids: &[QuestionUuid],
+    ) -> Result<Vec<AnswerDetail>, DBError> {
+        if question_uuids.is_empty() {
+            return Ok(vec![]);
+        }
+        let sql = statements::select_answers_of_questions("answers", question_uuids.len());
+
+        let mut conn = acquire(&self.db).await?;
+
+        let mut query = sqlx::query_as::<_, AnswerRow>(&sql);
+        for question_uuid in question_uuids {
+            query = query.bind(question_uuid.to_string());
+        }
+        let result = query
+            .fetch_all(&mut conn)
+            .await
+            .map_err(|err| DBError::Other(Box::new(err)))?;
+
+        Ok(result.into_iter().map(AnswerDetail::from).collect())
+    }
+}
+
+pub struct MySqlArchiveDao {
+    db: MySqlPool,
+}
+
+impl MySqlArchiveDao {
+    pub fn new(db: MySqlPool) -> Self {
+        Self { db }
+    }
+
+    async fn move_inactive(&self, older_than_days: u32) -> Result<ArchiveSummary, sqlx::Error> {
+        let mut tx = self.db.begin().await?;
+        // Locking the questions makes concurrent answer inserts wait for this
+        // transaction and then fail their foreign key check.
+        sqlx::query(
+            r#"
+                SELECT question_uuid FROM questions
+                WHERE updated_at < CURRENT_TIMESTAMP(6) - INTERVAL ? DAY
+                AND NOT EXISTS (
+                    SELECT 1 FROM answers
+                    WHERE answers.question_uuid = questions.question_uuid
+                    AND answers.updated_at >= CURRENT_TIMESTAMP(6) - INTERVAL ? DAY
+                )
+                FOR UPDATE
+            "#,
+        )
+        .bind(older_than_days)
+        .bind(older_than_days)
+        .execute(&mut tx)
+        .await?;
+
+        let questions = sqlx::query(
+            r#"
+                INSERT INTO questions_archive
+                    ( question_uuid, title, description, metadata, created_at, updated_at, last_activity_at, answer_count, version )
+                SELECT question_uuid, title, description, metadata, created_at, updated_at, last_activity_at, answer_count, version
+                FROM questions
+                WHERE updated_at < CURRENT_TIMESTAMP(6) - INTERVAL ? DAY
+                AND NOT EXISTS (
+                    SELECT 1 FROM answers
+                    WHERE answers.question_uuid = questions.question_uuid
+                    AND answers.updated_at >= CURRENT_TIMESTAMP(6) - INTERVAL ? DAY
+                )
+            "#,
+        )
+        .bind(older_than_days)
+        .bind(older_than_days)
+        .execute(&mut tx)
+        .await?
+        .rows_affected();
+
+        let answers = sqlx::query(statements::ARCHIVE_ANSWERS)
+            .execute(&mut tx)
+            .await?
+            .rows_affected();
+
+        sqlx::query(statements::DELETE_ARCHIVED_QUESTIONS)
+            .execute(&mut tx)
+            .await?;
+        tx.commit().await?;
+
+        Ok(ArchiveSummary { questions, answers })
+    }
+}
+
+#[async_trait]
+impl ArchiveDao for MySqlArchiveDao {
+    async fn archive(&self, older_than_days: u32) -> Result<ArchiveSummary, DBError> {
+        self.move_inactive(older_than_days)
+            .await
+            .map_err(|err| DBError::Other(Box::new(err)))
+    }
+
+    fn stream_archived_questions(&self, filter: QuestionFilter) -> RowStream<'_, QuestionDetail> {
+        matching_questions(&self.db, "questions_archive", filter)
+    }
+
+    fn stream_archived_answers(&self, question_uuid: QuestionUuid) -> RowStream<'_, AnswerDetail> {
+        Box::pin(try_stream! {
+            let mut conn = acquire(&self.db).await?;
+
+            let mut rows = sqlx::query_as::<_, AnswerRow>(statements::SELECT_ARCHIVED_ANSWERS)
+                .bind(question_uuid.to_string())
+                .fetch(&mut conn);
+
+            while let Some(row) = rows
+                .try_next()
+                .await
+                .map_err(|err| DBError::Other(Box::new(err)))?
+            {
+                yield row.into();
+            }
+        })
+    }
+
+    async fn get_archived_answers_of_questions(
+        &self,
+        question_uuids: &[QuestionUuid],
+    ) -> Result<Vec<AnswerDetail>, DBError> {
+        if question_uuids.is_empty() {
+            return Ok(vec![]);
+        }
+        let sql = statements::select_answers_of_questions("answers_archive", question_uuids.len());
+
+        let mut conn = acquire(&self.db).await?;
+
+        let mut query = sqlx::query_as::<_, AnswerRow>(&sql);
+        for question_uuid in question_uuids {
+            query = query.bind(question_uuid.to_string());
+        }
+        let result = query
+            .fetch_all(&mut conn)
+            .await
+            .map_err(|err| DBError::Other(Box::new(err)))?;
+
+        Ok(result.into_iter().map(AnswerDetail::from).collect())
+    }
+}
+
+pub struct MySqlStatsDao {
+    db: MySqlPool,
+}
+
+impl MySqlStatsDao {
+    pub fn new(db: MySqlPool) -> Self {
+        Self { db }
+    }
+}
+
+#[async_trait]
+impl StatsDao for MySqlStatsDao {
+    async fn get_daily_stats(&self) -> Result<Vec<DailyStats>, DBError> {
+        let mut conn = acquire(&self.db).await?;
+
+        let result = sqlx::query_as::<_, (String, i64, i64)>(
+            "SELECT DATE_FORMAT(day, '%Y-%m-%d'), questions, answers FROM daily_stats ORDER BY day",
+        )
+        .fetch_all(&mut conn)
+        .await
+        .map_err(|err| DBError::Other(Box::new(err)))?;
+
+        Ok(result
+            .into_iter()
+            .map(|(day, questions, answers)| DailyStats {
+                day,
+                questions,
+                answers,
+            })
+            .collect())
+    }
+
+    async fn get_response_stats(&self, since: NaiveDate) -> Result<ResponseStats, DBError> {
+        let mut conn = acquire(&self.db).await?;
+        let since = since.to_string();
+
+        // The median is the middle one of the ordered times, or the mean of the middle two.
+        let (questions, answered, median_first_answer_secs) =
+            sqlx::query_as::<_, (i64, i64, Option<f64>)>(
+                r#"
+                    SELECT
+                        (SELECT CAST(COALESCE(SUM(questions), 0) AS SIGNED) FROM daily_stats WHERE day >= ?),
+                        (SELECT COUNT(*) FROM first_answers WHERE day >= ?),
+                        (
+                            SELECT AVG(seconds)
+                            FROM (
+                                SELECT seconds,
+                                    ROW_NUMBER() OVER (ORDER BY seconds) AS position,
+                                    COUNT(*) OVER () AS n
+                                FROM first_answers
+                                WHERE day >= ?
+                            ) AS ordered
+                            WHERE position IN ((n + 1) DIV 2, (n + 2) DIV 2)
+                        )
+                "#,
+            )
+            .bind(&since)
+            .bind(&since)
+            .bind(&since)
+            .fetch_one(&mut conn)
+            .await
+            .map_err(|err| DBError::Other(Box::new(err)))?;
+
+        // There is no user directory on this backend.
+        Ok(ResponseStats {
+            questions,
+            answered,
+            median_first_answer_secs,
+            active_users: None,
+        })
+    }
+
+    // The view is not materialized here.
+    async fn refresh(&self) -> Result<(), DBError> {
+        Ok(())
+    }
+}
+
+// Shared by the DAOs and the unit of work.
+async fn insert_question(
+    conn: &mut MySqlConnection,
+    question: Question,
+) -> Result<QuestionDetail, DBError> {
+    let question_uuid = Uuid::new_v4().to_string();
+
+    // MySQL has no RETURNING, the row is read back for its created_at.
+    sqlx::query(
+        "INSERT INTO questions ( question_uuid, title, description, metadata ) VALUES ( ?, ?, ?, ? )",
+    )
+        .bind(&question_uuid)
+        .bind(&question.title)
+        .bind(&question.description)
+            .bind(Json(&question.metadata))
+        .execute(&mut *conn)
+        .await
+        .map_err(|err| DBError::Other(Box::new(err)))?;
+
+    let result = sqlx::query_as::<_, QuestionRow>(statements::SELECT_QUESTION)
+        .bind(&question_uuid)
+        .fetch_one(&mut *conn)
+        .await
+        .map_err(|err| DBError::Other(Box::new(err)))?;
+
+    Ok(result.into())
+}
+
+async fn insert_answer(
+    conn: &mut MySqlConnection,
+    answer: Answer,
+) -> Result<AnswerDetail, DBError> {
+    let question_uuid = answer.question_uuid.to_string();
+    let answer_uuid = Uuid::new_v4().to_string();
+
+    sqlx::query("INSERT INTO answers ( answer_uuid, question_uuid, content ) VALUES ( ?, ?, ? )")
+        .bind(&answer_uuid)
+        .bind(question_uuid)
+        .bind(&answer.content)
+        .execute(&mut *conn)
+        .await
+        .map_err(|err| match err {
+            sqlx::Error::Database(err)
+                if err
+                    .try_downcast_ref::<MySqlDatabaseError>()
+                    .map(|err| err.number())
+                    == Some(mysql_error_number::NO_REFERENCED_ROW) =>
+            {
+                DBError::NotFound(Entity::Question, err.to_string())
+            }
+            err => DBError::Other(Box::new(err)),
+        })?;
+
+    let result = sqlx::query_as::<_, AnswerRow>(statements::SELECT_ANSWER)
+        .bind(&answer_uuid)
+        .fetch_one(&mut *conn)
+        .await
+        .map_err(|err| DBError::Other(Box::new(err)))?;
+
+    Ok(result.into())
+}
+
+pub struct MySqlUnitOfWorkFactory {
+    db: MySqlPool,
+}
+
+impl MySqlUnitOfWorkFactory {
+    pub fn new(db: MySqlPool) -> Self {
+        Self { db }
+    }
+}
+
+#[async_trait]
+impl UnitOfWorkFactory for MySqlUnitOfWorkFactory {
+    async fn begin(&self) -> Result<Box<dyn UnitOfWork>, DBError> {
+        let tx = self
+            .db
+            .begin()
+            .await
+            .map_err(|err| DBError::Other(Box::new(err)))?;
+
+        Ok(Box::new(MySqlUnitOfWork { tx }))
+    }
+}
+
+pub struct MySqlUnitOfWork {
+    tx: Transaction<'static, MySql>,
+}
+
+#[async_trait]
+impl UnitOfWork for MySqlUnitOfWork {
+    async fn create_question(&mut self, question: Question) -> Result<QuestionDetail, DBError> {
+        insert_question(&mut self.tx, question).await
+    }
+
+    async fn create_answer(&mut self, answer: Answer) -> Result<AnswerDetail, DBError> {
+        insert_answer(&mut self.tx, answer).await
+    }
+
+    async fn commit(self: Box<Self>) -> Result<(), DBError> {
+        self.tx
+            .commit()
+            .await
+            .map_err(|err| DBError::Other(Box::new(err)))
+    }
+}
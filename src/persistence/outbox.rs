@@ -0,0 +1,239 @@
+use std::time::{Duration, Instant};
+
+use chrono::Utc;
+use log::{info, warn};
+use sqlx::PgPool;
+use tokio::task::JoinHandle;
+
+use super::locks::{self, Lock};
+use crate::{
+    backpressure,
+    config::AppConfig,
+    events::{EventPublisher, OutboxEvent},
+    metrics,
+};
+
+const CLEANUP_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+// Publishes the events the triggers of the outbox migration write. Publishing is at
+// least once: an event is only marked as published after the publisher accepted it.
+pub fn spawn_outbox_relay(
+    pool: PgPool,
+    publisher: Box<dyn EventPublisher + Send + Sync>,
+    config: &AppConfig,
+) -> JoinHandle<()> {
+    let poll_interval = config.outbox_poll_interval.max(Duration::from_millis(10));
+    let batch_size = config.outbox_batch_size.max(1);
+    let retention_days = config.outbox_retention_days;
+
+    tokio::spawn(async move {
+        let mut cleaned_at: Option<Instant> = None;
+
+        loop {
+            match relay_batch(&pool, publisher.as_ref(), batch_size).await {
+                // More may be waiting.
+                Ok(relayed) if relayed == batch_size => continue,
+                Ok(_) => {}
+                Err(sqlx::Error::PoolClosed) => return,
+                Err(err) => warn!("Relaying outbox events failed: {}", err),
+            }
+
+            let cleanup_due = cleaned_at.is_none_or(|at| at.elapsed() >= CLEANUP_INTERVAL);
+            if retention_days > 0 && cleanup_due {
+                match delete_published(&pool, retention_days).await {
+                    Ok(0) => {}
+                    Ok(deleted) => info!("Deleted {} published outbox events.", deleted),
+                    Err(err) => warn!("Deleting published outbox events failed: {}", err),
+                }
+                cleaned_at = Some(Instant::now());
+            }
+
+            tokio::time::sleep(poll_interval).await;
+        }
+    })
+}
+
+// Publishes up to `batch_size` pending events in `id` order and returns how many went
+// out. A failed event stops the batch, so nothing is delivered ahead of it.
+async fn relay_batch(
+    pool: &PgPool,
+    publisher: &(dyn EventPublisher + Send + Sync),
+    batch_size: u32,
+) -> Result<u32, sqlx::Error> {
+    let mut tx = pool.begin().await?;
+    // The instance holding the lock relays; the others skip their turn.
+    if !locks::try_lock(&mut tx, Lock::OutboxRelay).await? {
+        return Ok(0);
+    }
+
+    let rows = sqlx::query!(
+        r#"
+            SELECT id, event_type, aggregate_uuid, payload::TEXT AS "payload!", created_at
+            FROM outbox
+            WHERE published_at IS NULL
+            ORDER BY id
+            LIMIT $1
+        "#,
+        i64::from(batch_size),
+    )
+    .fetch_all(&mut tx)
+    .await?;
+
+    let mut published = vec![];
+    for row in rows {
+        let event = OutboxEvent {
+            id: row.id,
+            event_type: row.event_type,
+            aggregate_uuid: row.aggregate_uuid.to_string(),
+            payload: serde_json::from_str(&row.payload)
+                .map_err(|err| sqlx::Error::Decode(Box::new(err)))?,
+            created_at: row.created_at.and_utc(),
+        };
+
+        if let Err(err) = publisher.publish(&event).await {
+            warn!("Event {} not published: {}", event.id, err);
+            metrics::OUTBOX_EVENTS.with_label_values(&["failed"]).inc();
+
+            sqlx::query!(
+                "UPDATE outbox SET attempts = attempts + 1, last_error = $2 WHERE id = $1",
+                event.id,
+                err.to_string(),
+            )
+            .execute(&mut tx)
+            .await?;
+            break;
+        }
+        let lag = Utc::now() - event.created_at;
+        backpressure::record_delivery_lag(lag.to_std().unwrap_or_default());
+        published.push(event.id);
+    }
+
+    sqlx::query!(
+        "UPDATE outbox SET published_at = LOCALTIMESTAMP WHERE id = ANY($1)",
+        &published,
+    )
+    .execute(&mut tx)
+    .await?;
+    tx.commit().await?;
+
+    metrics::OUTBOX_EVENTS
+        .with_label_values(&["published"])
+        .inc_by(published.len() as u64);
+    Ok(published.len() as u32)
+}
+
+async fn delete_published(pool: &PgPool, retention_days: u32) -> Result<u64, sqlx::Error> {
+    let retention_days = i32::try_from(retention_days).unwrap_or(i32::MAX);
+
+    let deleted = sqlx::query!(
+        "DELETE FROM outbox WHERE published_at < LOCALTIMESTAMP - make_interval(days => $1)",
+        retention_days,
+    )
+    .execute(pool)
+    .await?
+    .rows_affected();
+
+    Ok(deleted)
+}
+
+#[cfg(test)]
+mod tests {
+    use async_trait::async_trait;
+    use tokio::sync::Mutex;
+
+    use super::*;
+    use crate::{
+        events::PublishError,
+        models::{empty_metadata, Answer, Question},
+        persistence::{
+            answer_dao::{AnswerDao, AnswerDaoImpl},
+            question_dao::{QuestionDao, QuestionDaoImpl},
+        },
+    };
+
+    #[derive(Default)]
+    struct RecordingPublisher {
+        events: Mutex<Vec<OutboxEvent>>,
+        fail: bool,
+    }
+
+    #[async_trait]
+    impl EventPublisher for RecordingPublisher {
+        async fn publish(&self, event: &OutboxEvent) -> Result<(), PublishError> {
+            if self.fail {
+                return Err(PublishError("subscriber down".to_owned()));
+            }
+            self.events.lock().await.push(event.clone());
+            Ok(())
+        }
+    }
+
+    async fn question_with_answer(pool: &PgPool) -> Result<(), String> {
+        let question = QuestionDaoImpl::new(pool.clone())
+            .create_question(Question {
+                title: "title".to_owned(),
+                description: Some("description".to_owned()),
+                metadata: empty_metadata(),
+            })
+            .await
+            .map_err(|err| err.to_string())?;
+        AnswerDaoImpl::new(pool.clone())
+            .create_answer(Answer {
+                question_uuid: question.question_uuid,
+                content: "content".to_owned(),
+            })
+            .await
+            .map_err(|err| err.to_string())?;
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn relay_should_publish_each_event_once_in_order(pool: PgPool) -> Result<(), String> {
+        question_with_answer(&pool).await?;
+        let publisher = RecordingPublisher::default();
+
+        let relayed = relay_batch(&pool, &publisher, 10)
+            .await
+            .map_err(|err| err.to_string())?;
+        assert_eq!(relayed, 2);
+        let relayed = relay_batch(&pool, &publisher, 10)
+            .await
+            .map_err(|err| err.to_string())?;
+        assert_eq!(relayed, 0);
+
+        let events = publisher.events.lock().await;
+        let types: Vec<_> = events
+            .iter()
+            .map(|event| event.event_type.as_str())
+            .collect();
+        assert_eq!(types, ["question_created", "answer_created"]);
+        assert_eq!(events[1].payload["content"], "content");
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn relay_should_keep_events_the_publisher_rejected(pool: PgPool) -> Result<(), String> {
+        question_with_answer(&pool).await?;
+        let publisher = RecordingPublisher {
+            fail: true,
+            ..Default::default()
+        };
+
+        let relayed = relay_batch(&pool, &publisher, 10)
+            .await
+            .map_err(|err| err.to_string())?;
+        assert_eq!(relayed, 0);
+
+        let pending = sqlx::query!(
+            r#"SELECT attempts, last_error FROM outbox WHERE published_at IS NULL ORDER BY id"#
+        )
+        .fetch_all(&pool)
+        .await
+        .map_err(|err| err.to_string())?;
+        assert_eq!(pending.len(), 2);
+        assert_eq!(pending[0].attempts, 1);
+        assert_eq!(pending[1].attempts, 0);
+        assert!(pending[0].last_error.is_some());
+        Ok(())
+    }
+}
@@ -0,0 +1,71 @@
+use std::time::Duration;
+
+use log::{info, warn};
+use sqlx::PgPool;
+use tokio::task::JoinHandle;
+
+use super::locks::{self, Lock};
+
+// Partitions are created this many months before they are needed.
+const MONTHS_AHEAD: i32 = 2;
+
+const CHECK_INTERVAL: Duration = Duration::from_secs(24 * 60 * 60);
+
+// Keeps monthly partitions of `answers` ahead of the clock, see the partition_answers
+// migration. Runs once at startup, so a server down over a month boundary catches up.
+pub fn spawn_partition_maintainer(pool: PgPool) -> JoinHandle<()> {
+    tokio::spawn(async move {
+        loop {
+            match create_answer_partitions(&pool).await {
+                Ok(0) => {}
+                Ok(created) => info!("Created {} answer partitions.", created),
+                Err(sqlx::Error::PoolClosed) => return,
+                Err(err) => warn!("Creating answer partitions failed: {}", err),
+            }
+
+            tokio::time::sleep(CHECK_INTERVAL).await;
+        }
+    })
+}
+
+async fn create_answer_partitions(pool: &PgPool) -> Result<i32, sqlx::Error> {
+    let mut tx = pool.begin().await?;
+    // Instances starting together would race to create the same partitions.
+    if !locks::try_lock(&mut tx, Lock::PartitionMaintenance).await? {
+        return Ok(0);
+    }
+
+    let created = sqlx::query_scalar!(
+        "SELECT create_answer_partitions(LOCALTIMESTAMP, $1)",
+        MONTHS_AHEAD,
+    )
+    .fetch_one(&mut tx)
+    .await?;
+    tx.commit().await?;
+
+    Ok(created.unwrap_or(0))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[sqlx::test]
+    async fn create_answer_partitions_should_be_idempotent(pool: PgPool) -> Result<(), String> {
+        // The migration already created the partitions up to MONTHS_AHEAD.
+        let created = create_answer_partitions(&pool)
+            .await
+            .map_err(|err| err.to_string())?;
+        assert_eq!(created, 0);
+
+        let created = sqlx::query_scalar!(
+            "SELECT create_answer_partitions(LOCALTIMESTAMP, $1)",
+            MONTHS_AHEAD + 1,
+        )
+        .fetch_one(&pool)
+        .await
+        .map_err(|err| err.to_string())?;
+        assert_eq!(created, Some(1));
+        Ok(())
+    }
+}
@@ -1,105 +1,501 @@
-use async_trait::async_trait;
-use sqlx::{types::Uuid, PgPool};
-
-use crate::models::{DBError, Question, QuestionDetail};
+use std::str::FromStr;
 
+use async_stream::try_stream;
+use async_trait::async_trait;
+use futures::TryStreamExt;
+use serde_json::Value;
+use sqlx::{
+    types::{
+        chrono::{DateTime, Utc},
+        Uuid,
+    },
+    FromRow, PgConnection, PgPool,
+};
+
+use super::{
+    acquire_read, audit::acquire_as_actor, blocked_deletion, missed_update, replica::ReadReplica,
+    unknown_question, RowStream,
+};
+use crate::models::{
+    DBError, Entity, Listed, Question, QuestionDetail, QuestionFilter, QuestionSort, QuestionUuid,
+    Window,
+};
+
+// Questions as stored, for the routes and for services that embed this crate. Inputs
+// are taken as they are: trimming and length checks happen in the handlers, so
+// embedders validate their own. Every backend and decorator keeps these semantics.
+//
+// `DBError::Unavailable` means the database could not be reached and the call may be
+// retried; `DBError::Other` is any other failure.
 #[async_trait]
 pub trait QuestionDao {
+    // The new question starts at version 1 with no answers.
     async fn create_question(&self, question: Question) -> Result<QuestionDetail, DBError>;
-    async fn delete_question(&self, question_uuid: String) -> Result<(), DBError>;
+    // Succeeds for unknown questions, so a retried delete does not fail. Its answers
+    // are deleted with it, or under `QuestionDeletion::Restrict` it fails with
+    // `DBError::Conflict` while it has any.
+    async fn delete_question(&self, question_uuid: QuestionUuid) -> Result<(), DBError>;
+    // Fails with `DBError::Conflict` unless the stored question is still at `version`,
+    // and with `DBError::NotFound` if there is none. The version goes up by one.
+    async fn update_question(
+        &self,
+        question_uuid: QuestionUuid,
+        question: Question,
+        version: i64,
+    ) -> Result<QuestionDetail, DBError>;
+    // Every question, in no particular order. May be served by a read replica, so a
+    // question just written is not always listed yet.
     async fn get_questions(&self) -> Result<Vec<QuestionDetail>, DBError>;
+    // One question by its UUID, NotFound if there is none. Read like `get_questions`.
+    async fn get_question(&self, question_uuid: QuestionUuid) -> Result<QuestionDetail, DBError>;
+    // The questions `filter` matches, otherwise like `get_questions`. Backends that can
+    // query their metadata and created_at columns do, so only the matches are read.
+    async fn get_questions_matching(
+        &self,
+        filter: &QuestionFilter,
+    ) -> Result<Vec<QuestionDetail>, DBError>;
+    // Same rows as `get_questions_matching`, fetched as they are consumed. An empty
+    // filter streams every question.
+    fn stream_questions(&self, filter: QuestionFilter) -> RowStream<'_, QuestionDetail>;
+    // A window of the questions `filter` matches, in `sort` order, and how many match
+    // in all. With `include_archived`, archived questions are listed among the live
+    // ones. Backends order, count and cut the list in their queries, so only the
+    // window is read.
+    async fn list_questions(
+        &self,
+        filter: &QuestionFilter,
+        include_archived: bool,
+        sort: QuestionSort,
+        window: Window,
+    ) -> Result<Listed<QuestionDetail>, DBError>;
+    // A window of the live questions without answers, oldest first, and how many there
+    // are in all. Read like `get_questions`; backends select by the `answer_count`
+    // column, so only the window is read.
+    async fn list_unanswered_questions(
+        &self,
+        window: Window,
+    ) -> Result<Listed<QuestionDetail>, DBError>;
+}
+
+// A `questions` row as the queries below select it. Columns are always listed, so
+// adding one to the table does not change what is fetched.
+#[derive(FromRow)]
+pub(super) struct QuestionRow {
+    pub question_uuid: Uuid,
+    pub title: String,
+    pub description: Option<String>,
+    pub metadata: Value,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+    pub last_activity_at: DateTime<Utc>,
+    pub answer_count: i64,
+    pub version: i64,
+}
+
+impl From<QuestionRow> for QuestionDetail {
+    fn from(row: QuestionRow) -> Self {
+        QuestionDetail {
+            question_uuid: QuestionUuid(row.question_uuid),
+            title: row.title,
+            description: row.description,
+            metadata: row.metadata,
+            created_at: row.created_at,
+            updated_at: row.updated_at,
+            last_activity_at: row.last_activity_at,
+            answer_count: row.answer_count,
+            version: row.version,
+        }
+    }
+}
+
+// What deleting a question does to its answers.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum QuestionDeletion {
+    // The answers are deleted with it, by `ON DELETE CASCADE`.
+    Cascade,
+    // Fails with `DBError::Conflict` while the question has answers.
+    Restrict,
+}
+
+impl FromStr for QuestionDeletion {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.to_ascii_lowercase().as_str() {
+            "cascade" => Ok(Self::Cascade),
+            "restrict" => Ok(Self::Restrict),
+            _ => Err(format!("Unknown question deletion: {}", value)),
+        }
+    }
 }
 
 pub struct QuestionDaoImpl {
     db: PgPool,
+    read_replica: Option<ReadReplica>,
+    deletion: QuestionDeletion,
 }
 
 impl QuestionDaoImpl {
     pub fn new(db: PgPool) -> Self {
-        Self { db }
+        Self {
+            db,
+            read_replica: None,
+            deletion: QuestionDeletion::Cascade,
+        }
+    }
+
+    pub fn with_read_replica(mut self, read_replica: ReadReplica) -> Self {
+        self.read_replica = Some(read_replica);
+        self
+    }
+
+    pub fn with_deletion(mut self, deletion: QuestionDeletion) -> Self {
+        self.deletion = deletion;
+        self
     }
 }
 
 #[async_trait]
 impl QuestionDao for QuestionDaoImpl {
     async fn create_question(&self, question: Question) -> Result<QuestionDetail, DBError> {
-        let result = sqlx::query!(
-            r#"
-                INSERT INTO questions ( title, description )
-                VALUES ( $1, $2 )
-                RETURNING *
-            "#,
-            &question.title,
-            &question.description
-        )
-        .fetch_one(&self.db)
-        .await;
-
-        let Ok(result) = result else {
-            return Err(DBError::Other(Box::new(result.err().unwrap())));
-        };
-
-        Ok(QuestionDetail {
-            question_uuid: result.question_uuid.to_string(),
-            title: result.title,
-            description: result.description,
-            created_at: result.created_at.to_string(),
-        })
+        let mut conn = acquire_as_actor(&self.db).await?;
+        insert_question(&mut conn, question).await
     }
 
-    async fn delete_question(&self, question_uuid: String) -> Result<(), DBError> {
-        let question_uuid = Uuid::parse_str(&question_uuid)
-            .map_err(|error| DBError::InvalidUUID(error.to_string()))?;
+    async fn delete_question(&self, question_uuid: QuestionUuid) -> Result<(), DBError> {
+        let question_uuid = question_uuid.0;
+
+        let mut conn = acquire_as_actor(&self.db).await?;
+        let restrict = self.deletion == QuestionDeletion::Restrict;
 
-        let result = sqlx::query!(
+        // answer_count is updated by the same transaction that adds an answer, and
+        // that update makes this statement wait and re-check the row.
+        let deleted = sqlx::query!(
             r#"
                 DELETE from questions
                 WHERE question_uuid = $1
+                AND (NOT $2 OR answer_count = 0)
             "#,
             question_uuid,
+            restrict,
         )
-        .execute(&self.db)
-        .await;
+        .execute(&mut conn)
+        .await
+        .map_err(|err| DBError::Other(Box::new(err)))?
+        .rows_affected();
+
+        if deleted == 0 && restrict {
+            let answer_count = sqlx::query_scalar!(
+                "SELECT answer_count FROM questions WHERE question_uuid = $1",
+                question_uuid,
+            )
+            .fetch_optional(&mut conn)
+            .await
+            .map_err(|err| DBError::Other(Box::new(err)))?;
 
-        if let Err(err) = result {
-            return Err(DBError::Other(Box::new(err)));
+            if let Some(answer_count) = answer_count {
+                return Err(blocked_deletion(&question_uuid.to_string(), answer_count));
+            }
         }
 
         Ok(())
     }
 
+    async fn update_question(
+        &self,
+        question_uuid: QuestionUuid,
+        question: Question,
+        version: i64,
+    ) -> Result<QuestionDetail, DBError> {
+        let question_uuid = question_uuid.0;
+
+        let mut conn = acquire_as_actor(&self.db).await?;
+
+        let result = sqlx::query_as!(
+            QuestionRow,
+            r#"
+                UPDATE questions
+                SET title = $1, description = $2, metadata = $3, version = version + 1
+                WHERE question_uuid = $4 AND version = $5
+                RETURNING question_uuid, title, description, metadata, created_at, updated_at, last_activity_at, answer_count, version
+            "#,
+            &question.title,
+            question.description.as_deref(),
+            &question.metadata,
+            question_uuid,
+            version,
+        )
+        .fetch_optional(&mut conn)
+        .await
+        .map_err(|err| DBError::Other(Box::new(err)))?;
+
+        let Some(result) = result else {
+            let current_version = sqlx::query_scalar!(
+                "SELECT version FROM questions WHERE question_uuid = $1",
+                question_uuid,
+            )
+            .fetch_optional(&mut conn)
+            .await
+            .map_err(|err| DBError::Other(Box::new(err)))?;
+
+            return Err(missed_update(
+                Entity::Question,
+                &question_uuid.to_string(),
+                current_version,
+                version,
+            ));
+        };
+
+        Ok(result.into())
+    }
+
     async fn get_questions(&self) -> Result<Vec<QuestionDetail>, DBError> {
-        let result = sqlx::query!(
+        let mut conn = acquire_read(&self.db, self.read_replica.as_ref()).await?;
+
+        let result = sqlx::query_as!(
+            QuestionRow,
             r#"
-                SELECT question_uuid, title, description, created_at
+                SELECT question_uuid, title, description, metadata, created_at, updated_at, last_activity_at, answer_count, version
                 FROM questions
             "#
         )
-        .fetch_all(&self.db)
-        .await;
-
-        match result {
-            Ok(result) => {
-                let questions = result
-                    .iter()
-                    .map(|val| QuestionDetail {
-                        question_uuid: val.question_uuid.to_string(),
-                        title: val.title.clone(),
-                        description: val.description.clone(),
-                        created_at: val.created_at.to_string(),
-                    })
-                    .collect();
-                Ok(questions)
+        .fetch_all(&mut conn)
+        .await
+        .map_err(|err| DBError::Other(Box::new(err)))?;
+
+        Ok(result.into_iter().map(QuestionDetail::from).collect())
+    }
+
+    async fn get_question(&self, question_uuid: QuestionUuid) -> Result<QuestionDetail, DBError> {
+        let mut conn = acquire_read(&self.db, self.read_replica.as_ref()).await?;
+
+        let result = sqlx::query_as!(
+            QuestionRow,
+            r#"
+                SELECT question_uuid, title, description, metadata, created_at, updated_at, last_activity_at, answer_count, version
+                FROM questions
+                WHERE question_uuid = $1
+            "#,
+            question_uuid.0,
+        )
+        .fetch_optional(&mut conn)
+        .await
+        .map_err(|err| DBError::Other(Box::new(err)))?;
+
+        result
+            .map(QuestionDetail::from)
+            .ok_or_else(|| unknown_question(&question_uuid))
+    }
+
+    async fn get_questions_matching(
+        &self,
+        filter: &QuestionFilter,
+    ) -> Result<Vec<QuestionDetail>, DBError> {
+        let mut conn = acquire_read(&self.db, self.read_replica.as_ref()).await?;
+
+        let result = sqlx::query_as!(
+            QuestionRow,
+            r#"
+                SELECT question_uuid, title, description, metadata, created_at, updated_at, last_activity_at, answer_count, version
+                FROM questions
+                WHERE metadata @> $1
+                AND ($2::TIMESTAMPTZ IS NULL OR created_at >= $2)
+                AND ($3::TIMESTAMPTZ IS NULL OR created_at < $3)
+            "#,
+            filter.metadata.to_json(),
+            filter.created.after,
+            filter.created.before,
+        )
+        .fetch_all(&mut conn)
+        .await
+        .map_err(|err| DBError::Other(Box::new(err)))?;
+
+        Ok(result.into_iter().map(QuestionDetail::from).collect())
+    }
+
+    fn stream_questions(&self, filter: QuestionFilter) -> RowStream<'_, QuestionDetail> {
+        Box::pin(try_stream! {
+            let mut conn = acquire_read(&self.db, self.read_replica.as_ref()).await?;
+
+            let mut rows = sqlx::query_as!(
+                QuestionRow,
+                r#"
+                    SELECT question_uuid, title, description, metadata, created_at, updated_at, last_activity_at, answer_count, version
+                    FROM questions
+                    WHERE metadata @> $1
+                    AND ($2::TIMESTAMPTZ IS NULL OR created_at >= $2)
+                    AND ($3::TIMESTAMPTZ IS NULL OR created_at < $3)
+                "#,
+                filter.metadata.to_json(),
+                filter.created.after,
+                filter.created.before,
+            )
+            .fetch(&mut conn);
+
+            while let Some(row) = rows
+                .try_next()
+                .await
+                .map_err(|err| DBError::Other(Box::new(err)))?
+            {
+                yield row.into();
             }
-            Err(e) => Err(DBError::Other(Box::new(e))),
-        }
+        })
+    }
+
+    async fn list_questions(
+        &self,
+        filter: &QuestionFilter,
+        include_archived: bool,
+        sort: QuestionSort,
+        window: Window,
+    ) -> Result<Listed<QuestionDetail>, DBError> {
+        let mut conn = acquire_read(&self.db, self.read_replica.as_ref()).await?;
+
+        // One statement per sort would each need their own copy of the filter; the
+        // CASEs leave every key but the chosen one NULL.
+        let items = sqlx::query_as!(
+            QuestionRow,
+            r#"
+                SELECT
+                    question_uuid AS "question_uuid!",
+                    title AS "title!",
+                    description,
+                    metadata AS "metadata!",
+                    created_at AS "created_at!",
+                    updated_at AS "updated_at!",
+                    last_activity_at AS "last_activity_at!",
+                    answer_count AS "answer_count!",
+                    version AS "version!"
+                FROM (
+                    SELECT question_uuid, title, description, metadata, created_at, updated_at, last_activity_at, answer_count, version
+                    FROM questions
+                    UNION ALL
+                    SELECT question_uuid, title, description, metadata, created_at, updated_at, last_activity_at, answer_count, version
+                    FROM questions_archive
+                    WHERE $4
+                ) AS listed
+                WHERE metadata @> $1
+                AND ($2::TIMESTAMPTZ IS NULL OR created_at >= $2)
+                AND ($3::TIMESTAMPTZ IS NULL OR created_at < $3)
+                ORDER BY
+                    CASE WHEN $5 = 'oldest' THEN created_at END,
+                    CASE WHEN $5 = 'newest' THEN created_at END DESC,
+                    CASE WHEN $5 = 'updated' THEN updated_at END DESC,
+                    CASE WHEN $5 = 'answers' THEN answer_count END DESC,
+                    CASE WHEN $5 = 'activity' THEN last_activity_at END DESC,
+                    CASE WHEN $5 IN ('oldest', 'answers') THEN question_uuid END,
+                    question_uuid DESC
+                OFFSET $6
+                LIMIT $7
+            "#,
+            filter.metadata.to_json(),
+            filter.created.after,
+            filter.created.before,
+            include_archived,
+            sort.as_str(),
+            window.offset as i64,
+            window.limit as i64,
+        )
+        .fetch_all(&mut conn)
+        .await
+        .map_err(|err| DBError::Other(Box::new(err)))?;
+
+        let total = sqlx::query_scalar!(
+            r#"
+                SELECT COUNT(*) AS "total!"
+                FROM (
+                    SELECT metadata, created_at FROM questions
+                    UNION ALL
+                    SELECT metadata, created_at FROM questions_archive WHERE $4
+                ) AS listed
+                WHERE metadata @> $1
+                AND ($2::TIMESTAMPTZ IS NULL OR created_at >= $2)
+                AND ($3::TIMESTAMPTZ IS NULL OR created_at < $3)
+            "#,
+            filter.metadata.to_json(),
+            filter.created.after,
+            filter.created.before,
+            include_archived,
+        )
+        .fetch_one(&mut conn)
+        .await
+        .map_err(|err| DBError::Other(Box::new(err)))?;
+
+        Ok(Listed {
+            items: items.into_iter().map(QuestionDetail::from).collect(),
+            total: total as u64,
+        })
     }
+
+    async fn list_unanswered_questions(
+        &self,
+        window: Window,
+    ) -> Result<Listed<QuestionDetail>, DBError> {
+        let mut conn = acquire_read(&self.db, self.read_replica.as_ref()).await?;
+
+        let items = sqlx::query_as!(
+            QuestionRow,
+            r#"
+                SELECT question_uuid, title, description, metadata, created_at, updated_at, last_activity_at, answer_count, version
+                FROM questions
+                WHERE answer_count = 0
+                ORDER BY created_at, question_uuid
+                OFFSET $1
+                LIMIT $2
+            "#,
+            window.offset as i64,
+            window.limit as i64,
+        )
+        .fetch_all(&mut conn)
+        .await
+        .map_err(|err| DBError::Other(Box::new(err)))?;
+
+        let total = sqlx::query_scalar!(
+            r#"SELECT COUNT(*) AS "total!" FROM questions WHERE answer_count = 0"#
+        )
+        .fetch_one(&mut conn)
+        .await
+        .map_err(|err| DBError::Other(Box::new(err)))?;
+
+        Ok(Listed {
+            items: items.into_iter().map(QuestionDetail::from).collect(),
+            total: total as u64,
+        })
+    }
+}
+
+// Shared with `PgUnitOfWork` so the statement is the same in and out of a transaction.
+pub(super) async fn insert_question(
+    conn: &mut PgConnection,
+    question: Question,
+) -> Result<QuestionDetail, DBError> {
+    let result = sqlx::query_as!(
+        QuestionRow,
+        r#"
+            INSERT INTO questions ( title, description, metadata )
+            VALUES ( $1, $2, $3 )
+            RETURNING question_uuid, title, description, metadata, created_at, updated_at, last_activity_at, answer_count, version
+        "#,
+        &question.title,
+        question.description.as_deref(),
+        &question.metadata
+    )
+    .fetch_one(conn)
+    .await
+    .map_err(|err| DBError::Other(Box::new(err)))?;
+
+    Ok(result.into())
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::models::{DBError, Question};
+    use crate::{
+        models::{empty_metadata, Answer, DBError, DateRange, MetadataFilter, Question},
+        persistence::answer_dao::{AnswerDao, AnswerDaoImpl},
+    };
+    use chrono::TimeZone;
+    use serde_json::json;
     use sqlx::PgPool;
 
     #[sqlx::test]
@@ -112,7 +508,8 @@ mod tests {
         let result = dao
             .create_question(Question {
                 title: "some_title".to_owned(),
-                description: "some_desc".to_owned(),
+                description: Some("some_desc".to_owned()),
+                metadata: empty_metadata(),
             })
             .await;
 
@@ -124,7 +521,7 @@ mod tests {
             return Ok(());
         }
 
-        return Err(format!("Expected other error but got: {:?} ", result.err()));
+        Err(format!("Expected other error but got: {:?} ", result.err()))
     }
 
     #[sqlx::test]
@@ -134,33 +531,19 @@ mod tests {
         let result = dao
             .create_question(Question {
                 title: "some_title".to_owned(),
-                description: "some_desc".to_owned(),
+                description: Some("some_desc".to_owned()),
+                metadata: empty_metadata(),
             })
             .await
             .map_err(|e| format!("An not expected error ocourred: {:?}", e))?;
 
         assert_eq!(result.title, "some_title".to_owned());
-        assert_eq!(result.description, "some_desc".to_owned());
+        assert_eq!(result.description.as_deref(), Some("some_desc"));
 
         pool.close().await;
         Ok(())
     }
 
-    #[sqlx::test]
-    async fn delete_question_should_fail_on_malformed_uuid(pool: PgPool) -> Result<(), String> {
-        let dao = QuestionDaoImpl::new(pool);
-        let err = dao
-            .delete_question("invalid_uui".to_owned())
-            .await
-            .unwrap_err();
-
-        if let DBError::InvalidUUID(_) = err {
-            return Ok(());
-        }
-
-        Err(format!("Expected InvalidUUID but got: {}", err))
-    }
-
     #[sqlx::test]
     async fn delete_question_should_fail_if_database_error_occours(
         pool: PgPool,
@@ -168,7 +551,10 @@ mod tests {
         let dao = QuestionDaoImpl::new(pool.clone());
         pool.close().await;
         let some_uuid = "a22abcd2-22ab-2222-a22b-2abc2a2b22cc";
-        let err = dao.delete_question(some_uuid.to_owned()).await.unwrap_err();
+        let err = dao
+            .delete_question(some_uuid.parse().unwrap())
+            .await
+            .unwrap_err();
 
         match err {
             DBError::Other(_) => Ok(()),
@@ -180,12 +566,92 @@ mod tests {
     async fn delete_question_should_succeed(pool: PgPool) -> Result<(), String> {
         let valid_uuid = "a22abcd2-22ab-2222-a22b-2abc2a2b22cc";
         let dao = QuestionDaoImpl::new(pool);
-        let result = dao
-            .delete_question(valid_uuid.to_owned())
+        dao.delete_question(valid_uuid.parse().unwrap())
             .await
             .map_err(|err| format!("Expected Ok but got: {}", err))?;
 
-        assert_eq!(result, ());
+        Ok(())
+    }
+
+    async fn question_with_answer(pool: &PgPool) -> Result<QuestionDetail, String> {
+        let question = QuestionDaoImpl::new(pool.clone())
+            .create_question(Question {
+                title: "some_title".to_owned(),
+                description: Some("some_desc".to_owned()),
+                metadata: empty_metadata(),
+            })
+            .await
+            .map_err(|err| err.to_string())?;
+        AnswerDaoImpl::new(pool.clone())
+            .create_answer(Answer {
+                question_uuid: question.question_uuid,
+                content: "some_content".to_owned(),
+            })
+            .await
+            .map_err(|err| err.to_string())?;
+        Ok(question)
+    }
+
+    #[sqlx::test]
+    async fn delete_question_should_cascade_to_answers(pool: PgPool) -> Result<(), String> {
+        let question = question_with_answer(&pool).await?;
+        let dao = QuestionDaoImpl::new(pool.clone()).with_deletion(QuestionDeletion::Cascade);
+
+        dao.delete_question(question.question_uuid)
+            .await
+            .map_err(|err| format!("Expected Ok but got: {}", err))?;
+
+        let answers = AnswerDaoImpl::new(pool)
+            .get_answers(question.question_uuid)
+            .await
+            .map_err(|err| err.to_string())?;
+        assert!(answers.is_empty());
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn delete_question_should_be_blocked_by_answers_when_restricted(
+        pool: PgPool,
+    ) -> Result<(), String> {
+        let question = question_with_answer(&pool).await?;
+        let dao = QuestionDaoImpl::new(pool.clone()).with_deletion(QuestionDeletion::Restrict);
+
+        let err = dao
+            .delete_question(question.question_uuid)
+            .await
+            .unwrap_err();
+        if !matches!(err, DBError::Conflict(_)) {
+            return Err(format!("Expected Conflict but got: {}", err));
+        }
+
+        let answers = AnswerDaoImpl::new(pool)
+            .get_answers(question.question_uuid)
+            .await
+            .map_err(|err| err.to_string())?;
+        assert_eq!(answers.len(), 1);
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn delete_question_should_delete_unanswered_question_when_restricted(
+        pool: PgPool,
+    ) -> Result<(), String> {
+        let dao = QuestionDaoImpl::new(pool.clone()).with_deletion(QuestionDeletion::Restrict);
+        let question = dao
+            .create_question(Question {
+                title: "some_title".to_owned(),
+                description: Some("some_desc".to_owned()),
+                metadata: empty_metadata(),
+            })
+            .await
+            .map_err(|err| err.to_string())?;
+
+        dao.delete_question(question.question_uuid)
+            .await
+            .map_err(|err| format!("Expected Ok but got: {}", err))?;
+
+        let questions = dao.get_questions().await.map_err(|err| err.to_string())?;
+        assert!(questions.is_empty());
         Ok(())
     }
 
@@ -201,20 +667,141 @@ mod tests {
         }
     }
 
+    #[sqlx::test]
+    async fn descriptions_should_be_nullable(pool: PgPool) -> Result<(), String> {
+        let dao = QuestionDaoImpl::new(pool);
+        let question = dao
+            .create_question(Question {
+                title: "some_title".to_owned(),
+                description: None,
+                metadata: empty_metadata(),
+            })
+            .await
+            .map_err(|err| err.to_string())?;
+        assert_eq!(question.description, None);
+
+        let question = dao
+            .update_question(
+                question.question_uuid,
+                Question {
+                    title: "some_title".to_owned(),
+                    description: Some("some_desc".to_owned()),
+                    metadata: empty_metadata(),
+                },
+                question.version,
+            )
+            .await
+            .map_err(|err| err.to_string())?;
+        assert_eq!(question.description.as_deref(), Some("some_desc"));
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn get_questions_matching_should_match_contained_values(
+        pool: PgPool,
+    ) -> Result<(), String> {
+        let dao = QuestionDaoImpl::new(pool);
+        let mut created = vec![];
+        for metadata in [
+            json!({ "source": "zendesk", "ticket": "42", "tags": ["billing"] }),
+            json!({ "source": "zendesk", "ticket": 42 }),
+            json!({ "source": "jira", "ticket": "42" }),
+        ] {
+            let question = dao
+                .create_question(Question {
+                    title: "some_title".to_owned(),
+                    description: None,
+                    metadata,
+                })
+                .await
+                .map_err(|err| err.to_string())?;
+            created.push(question);
+        }
+
+        let filter = QuestionFilter {
+            metadata: MetadataFilter(
+                [("source", "zendesk"), ("ticket", "42")]
+                    .map(|(key, value)| (key.to_owned(), value.to_owned()))
+                    .into(),
+            ),
+            ..QuestionFilter::default()
+        };
+        let found = dao
+            .get_questions_matching(&filter)
+            .await
+            .map_err(|err| err.to_string())?;
+
+        assert_eq!(found, vec![created[0].clone()]);
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn get_questions_matching_should_compare_creation_instants(
+        pool: PgPool,
+    ) -> Result<(), String> {
+        let dao = QuestionDaoImpl::new(pool.clone());
+        let mut titles = vec![];
+        for created_at in [
+            "2023-10-01 21:59:59+00",
+            "2023-10-01 22:00:00+00",
+            "2023-10-02 23:00:00+02",
+        ] {
+            let question = dao
+                .create_question(Question {
+                    title: created_at.to_owned(),
+                    description: None,
+                    metadata: empty_metadata(),
+                })
+                .await
+                .map_err(|err| err.to_string())?;
+            sqlx::query(
+                "UPDATE questions SET created_at = $1::TEXT::TIMESTAMPTZ WHERE question_uuid = $2",
+            )
+            .bind(created_at)
+            .bind(question.question_uuid.0)
+            .execute(&pool)
+            .await
+            .map_err(|err| err.to_string())?;
+            titles.push(created_at);
+        }
+
+        // October 2 in Berlin.
+        let filter = QuestionFilter {
+            created: DateRange {
+                after: Some(Utc.with_ymd_and_hms(2023, 10, 1, 22, 0, 0).unwrap()),
+                before: Some(Utc.with_ymd_and_hms(2023, 10, 2, 21, 0, 0).unwrap()),
+            },
+            ..QuestionFilter::default()
+        };
+        let found = dao
+            .get_questions_matching(&filter)
+            .await
+            .map_err(|err| err.to_string())?;
+
+        let found: Vec<_> = found
+            .iter()
+            .map(|question| question.title.as_str())
+            .collect();
+        assert_eq!(found, vec![titles[1]]);
+        Ok(())
+    }
+
     #[sqlx::test]
     async fn get_questions_should_succeed(pool: PgPool) -> Result<(), String> {
         let dao = QuestionDaoImpl::new(pool.clone());
         let question1 = dao
             .create_question(Question {
                 title: "some_title".to_owned(),
-                description: "some_desc".to_owned(),
+                description: Some("some_desc".to_owned()),
+                metadata: empty_metadata(),
             })
             .await
             .unwrap();
         let question2 = dao
             .create_question(Question {
                 title: "some_title".to_owned(),
-                description: "some_desc".to_owned(),
+                description: Some("some_desc".to_owned()),
+                metadata: empty_metadata(),
             })
             .await
             .unwrap();
@@ -227,4 +814,182 @@ mod tests {
         assert_eq!(result, vec![question1, question2]);
         Ok(())
     }
+
+    #[sqlx::test]
+    async fn get_question_should_find_only_that_question(pool: PgPool) -> Result<(), String> {
+        let dao = QuestionDaoImpl::new(pool);
+        let mut created = vec![];
+        for title in ["first", "second"] {
+            let question = dao
+                .create_question(Question {
+                    title: title.to_owned(),
+                    description: None,
+                    metadata: empty_metadata(),
+                })
+                .await
+                .map_err(|e| e.to_string())?;
+            created.push(question);
+        }
+
+        let found = dao
+            .get_question(created[1].question_uuid)
+            .await
+            .map_err(|e| e.to_string())?;
+        assert_eq!(found, created[1]);
+
+        let missing = dao.get_question(QuestionUuid::new_v4()).await;
+        assert!(matches!(
+            missing,
+            Err(DBError::NotFound(Entity::Question, _))
+        ));
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn updated_at_should_only_move_on_edits(pool: PgPool) -> Result<(), String> {
+        let dao = QuestionDaoImpl::new(pool.clone());
+        let question = dao
+            .create_question(Question {
+                title: "some_title".to_owned(),
+                description: Some("some_desc".to_owned()),
+                metadata: empty_metadata(),
+            })
+            .await
+            .map_err(|e| e.to_string())?;
+        assert_eq!(question.updated_at, question.created_at);
+        let question_uuid = question.question_uuid.0;
+
+        sqlx::query("UPDATE questions SET answer_count = 5 WHERE question_uuid = $1")
+            .bind(question_uuid)
+            .execute(&pool)
+            .await
+            .map_err(|e| e.to_string())?;
+        let counted = dao.get_questions().await.map_err(|e| e.to_string())?;
+        assert_eq!(counted[0].updated_at, question.updated_at);
+
+        // Make sure the edit lands on a later timestamp.
+        tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+        sqlx::query("UPDATE questions SET title = 'edited' WHERE question_uuid = $1")
+            .bind(question_uuid)
+            .execute(&pool)
+            .await
+            .map_err(|e| e.to_string())?;
+        let edited = dao.get_questions().await.map_err(|e| e.to_string())?;
+        assert_ne!(edited[0].updated_at, question.updated_at);
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn update_question_should_reject_stale_versions(pool: PgPool) -> Result<(), String> {
+        let dao = QuestionDaoImpl::new(pool.clone());
+        let question = dao
+            .create_question(Question {
+                title: "some_title".to_owned(),
+                description: Some("some_desc".to_owned()),
+                metadata: empty_metadata(),
+            })
+            .await
+            .map_err(|e| e.to_string())?;
+
+        let edit = || Question {
+            title: "edited".to_owned(),
+            description: Some("some_desc".to_owned()),
+            metadata: empty_metadata(),
+        };
+        let updated = dao
+            .update_question(question.question_uuid, edit(), question.version)
+            .await
+            .map_err(|e| e.to_string())?;
+        assert_eq!(updated.title, "edited");
+        assert_eq!(updated.version, question.version + 1);
+
+        let stale = dao
+            .update_question(question.question_uuid, edit(), question.version)
+            .await;
+        assert!(matches!(stale, Err(DBError::Conflict(_))));
+
+        let missing = dao.update_question(QuestionUuid::new_v4(), edit(), 1).await;
+        assert!(matches!(
+            missing,
+            Err(DBError::NotFound(Entity::Question, _))
+        ));
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn list_questions_should_order_and_page_in_the_query(pool: PgPool) -> Result<(), String> {
+        let dao = QuestionDaoImpl::new(pool.clone());
+        let mut created = vec![];
+        for (title, answer_count) in [("first", 1), ("second", 3), ("third", 2)] {
+            let question = dao
+                .create_question(Question {
+                    title: title.to_owned(),
+                    description: None,
+                    metadata: empty_metadata(),
+                })
+                .await
+                .map_err(|e| e.to_string())?;
+            sqlx::query("UPDATE questions SET answer_count = $1 WHERE question_uuid = $2")
+                .bind(answer_count)
+                .bind(question.question_uuid.0)
+                .execute(&pool)
+                .await
+                .map_err(|e| e.to_string())?;
+            created.push(question);
+        }
+
+        let listed = dao
+            .list_questions(
+                &QuestionFilter::default(),
+                true,
+                QuestionSort::Answers,
+                Window {
+                    offset: 1,
+                    limit: 1,
+                },
+            )
+            .await
+            .map_err(|e| e.to_string())?;
+
+        assert_eq!(listed.total, 3);
+        let titles: Vec<&str> = listed.items.iter().map(|q| q.title.as_str()).collect();
+        assert_eq!(titles, vec!["third"]);
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn list_unanswered_questions_should_page_the_oldest_first(
+        pool: PgPool,
+    ) -> Result<(), String> {
+        let dao = QuestionDaoImpl::new(pool.clone());
+        for (title, answer_count) in [("first", 0), ("second", 2), ("third", 0), ("fourth", 0)] {
+            let question = dao
+                .create_question(Question {
+                    title: title.to_owned(),
+                    description: None,
+                    metadata: empty_metadata(),
+                })
+                .await
+                .map_err(|e| e.to_string())?;
+            sqlx::query("UPDATE questions SET answer_count = $1 WHERE question_uuid = $2")
+                .bind(answer_count)
+                .bind(question.question_uuid.0)
+                .execute(&pool)
+                .await
+                .map_err(|e| e.to_string())?;
+        }
+
+        let listed = dao
+            .list_unanswered_questions(Window {
+                offset: 1,
+                limit: 1,
+            })
+            .await
+            .map_err(|e| e.to_string())?;
+
+        assert_eq!(listed.total, 3);
+        let titles: Vec<&str> = listed.items.iter().map(|q| q.title.as_str()).collect();
+        assert_eq!(titles, vec!["third"]);
+        Ok(())
+    }
 }
@@ -0,0 +1,112 @@
+use std::{
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use log::warn;
+use sqlx::{pool::PoolConnection, PgPool, Postgres};
+
+use super::{acquire, acquire_from, PRIMARY_POOL};
+use crate::{metrics, models::DBError};
+
+pub const REPLICA_POOL: &str = "replica";
+
+// A read-only pool that is skipped for `cooldown` after it fails, sending reads to the primary.
+#[derive(Clone)]
+pub struct ReadReplica {
+    pool: PgPool,
+    cooldown: Duration,
+    down_until: Arc<Mutex<Option<Instant>>>,
+}
+
+impl ReadReplica {
+    pub fn new(pool: PgPool, cooldown: Duration) -> Self {
+        Self {
+            pool,
+            cooldown,
+            down_until: Arc::new(Mutex::new(None)),
+        }
+    }
+
+    pub fn pool(&self) -> &PgPool {
+        &self.pool
+    }
+
+    pub async fn acquire_or_primary(
+        &self,
+        primary: &PgPool,
+    ) -> Result<PoolConnection<Postgres>, DBError> {
+        if self.is_down() {
+            return acquire(primary).await;
+        }
+
+        match acquire_from(&self.pool, REPLICA_POOL).await {
+            Ok(connection) => Ok(connection),
+            Err(err) => {
+                warn!(
+                    "Read replica unavailable, using the {} pool for {:?}: {}",
+                    PRIMARY_POOL, self.cooldown, err
+                );
+                *self.down_until.lock().unwrap() = Some(Instant::now() + self.cooldown);
+                metrics::REPLICA_FALLBACKS.inc();
+
+                acquire(primary).await
+            }
+        }
+    }
+
+    fn is_down(&self) -> bool {
+        let mut down_until = self.down_until.lock().unwrap();
+
+        match *down_until {
+            Some(until) if Instant::now() < until => true,
+            Some(_) => {
+                *down_until = None;
+                false
+            }
+            None => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sqlx::{postgres::PgPoolOptions, Connection};
+
+    #[sqlx::test]
+    async fn acquire_or_primary_should_use_replica_when_healthy(
+        pool: PgPool,
+    ) -> Result<(), String> {
+        let replica_pool = PgPoolOptions::new().connect_lazy_with(pool.connect_options().clone());
+        let replica = ReadReplica::new(replica_pool, Duration::from_secs(10));
+        pool.close().await;
+
+        let mut connection = replica
+            .acquire_or_primary(&pool)
+            .await
+            .map_err(|err| format!("Expected Ok but got: {}", err))?;
+
+        connection.ping().await.map_err(|err| err.to_string())
+    }
+
+    #[sqlx::test]
+    async fn acquire_or_primary_should_fall_back_when_replica_is_down(
+        pool: PgPool,
+    ) -> Result<(), String> {
+        let replica_pool = PgPoolOptions::new()
+            .acquire_timeout(Duration::from_millis(200))
+            .connect_lazy("postgres://nobody@127.0.0.1:1/none")
+            .unwrap();
+        let replica = ReadReplica::new(replica_pool, Duration::from_secs(10));
+
+        let mut connection = replica
+            .acquire_or_primary(&pool)
+            .await
+            .map_err(|err| format!("Expected Ok but got: {}", err))?;
+        connection.ping().await.map_err(|err| err.to_string())?;
+
+        assert!(replica.is_down());
+        Ok(())
+    }
+}
@@ -0,0 +1,194 @@
+use async_trait::async_trait;
+use sqlx::{types::chrono::NaiveDateTime, PgPool};
+use uuid::Uuid;
+
+use super::acquire;
+use crate::{
+    events::OutboxEvent,
+    models::{DBError, Entity, HookUuid, RestHook},
+};
+
+// REST hook subscriptions, and the outbox events they are sent.
+#[async_trait]
+pub trait RestHookDao {
+    async fn create_hook(
+        &self,
+        hook_uuid: HookUuid,
+        target_url: String,
+        event_type: String,
+    ) -> Result<RestHook, DBError>;
+    // NotFound for unknown hooks.
+    async fn delete_hook(&self, hook_uuid: HookUuid) -> Result<(), DBError>;
+    // Oldest first.
+    async fn hooks_for(&self, event_type: &str) -> Result<Vec<RestHook>, DBError>;
+    // The latest `limit` events of `event_type` still in the outbox, newest first.
+    async fn recent_events(
+        &self,
+        event_type: &str,
+        limit: i64,
+    ) -> Result<Vec<OutboxEvent>, DBError>;
+}
+
+struct RestHookRow {
+    hook_uuid: Uuid,
+    target_url: String,
+    event_type: String,
+    created_at: NaiveDateTime,
+}
+
+impl From<RestHookRow> for RestHook {
+    fn from(row: RestHookRow) -> Self {
+        RestHook {
+            hook_uuid: HookUuid(row.hook_uuid),
+            target_url: row.target_url,
+            event_type: row.event_type,
+            created_at: row.created_at.and_utc(),
+        }
+    }
+}
+
+pub struct PgRestHookDao {
+    db: PgPool,
+}
+
+impl PgRestHookDao {
+    pub fn new(db: PgPool) -> Self {
+        Self { db }
+    }
+}
+
+#[async_trait]
+impl RestHookDao for PgRestHookDao {
+    async fn create_hook(
+        &self,
+        hook_uuid: HookUuid,
+        target_url: String,
+        event_type: String,
+    ) -> Result<RestHook, DBError> {
+        let mut conn = acquire(&self.db).await?;
+
+        let row = sqlx::query_as!(
+            RestHookRow,
+            r#"
+                INSERT INTO rest_hooks ( hook_uuid, target_url, event_type )
+                VALUES ( $1, $2, $3 )
+                RETURNING *
+            "#,
+            hook_uuid.0,
+            target_url,
+            event_type,
+        )
+        .fetch_one(&mut conn)
+        .await
+        .map_err(|err| DBError::Other(Box::new(err)))?;
+
+        Ok(row.into())
+    }
+
+    async fn delete_hook(&self, hook_uuid: HookUuid) -> Result<(), DBError> {
+        let mut conn = acquire(&self.db).await?;
+
+        let result = sqlx::query!("DELETE FROM rest_hooks WHERE hook_uuid = $1", hook_uuid.0)
+            .execute(&mut conn)
+            .await
+            .map_err(|err| DBError::Other(Box::new(err)))?;
+
+        if result.rows_affected() == 0 {
+            return Err(DBError::NotFound(
+                Entity::RestHook,
+                format!("REST hook {} not found", hook_uuid),
+            ));
+        }
+        Ok(())
+    }
+
+    async fn hooks_for(&self, event_type: &str) -> Result<Vec<RestHook>, DBError> {
+        let mut conn = acquire(&self.db).await?;
+
+        let rows = sqlx::query_as!(
+            RestHookRow,
+            "SELECT * FROM rest_hooks WHERE event_type = $1 ORDER BY created_at, hook_uuid",
+            event_type,
+        )
+        .fetch_all(&mut conn)
+        .await
+        .map_err(|err| DBError::Other(Box::new(err)))?;
+
+        Ok(rows.into_iter().map(RestHook::from).collect())
+    }
+
+    async fn recent_events(
+        &self,
+        event_type: &str,
+        limit: i64,
+    ) -> Result<Vec<OutboxEvent>, DBError> {
+        let mut conn = acquire(&self.db).await?;
+
+        let rows = sqlx::query!(
+            r#"
+                SELECT id, event_type, aggregate_uuid, payload::TEXT AS "payload!", created_at
+                FROM outbox
+                WHERE event_type = $1
+                ORDER BY id DESC
+                LIMIT $2
+            "#,
+            event_type,
+            limit,
+        )
+        .fetch_all(&mut conn)
+        .await
+        .map_err(|err| DBError::Other(Box::new(err)))?;
+
+        rows.into_iter()
+            .map(|row| {
+                Ok(OutboxEvent {
+                    id: row.id,
+                    event_type: row.event_type,
+                    aggregate_uuid: row.aggregate_uuid.to_string(),
+                    payload: serde_json::from_str(&row.payload)
+                        .map_err(|err| DBError::Other(Box::new(err)))?,
+                    created_at: row.created_at.and_utc(),
+                })
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[sqlx::test]
+    async fn hooks_should_be_listed_by_event_and_deleted_once(pool: PgPool) -> Result<(), String> {
+        let dao = PgRestHookDao::new(pool);
+
+        let hook = dao
+            .create_hook(
+                HookUuid::new_v4(),
+                "https://hooks.example.com/1".to_owned(),
+                "question_created".to_owned(),
+            )
+            .await
+            .unwrap();
+        dao.create_hook(
+            HookUuid::new_v4(),
+            "https://hooks.example.com/2".to_owned(),
+            "answer_created".to_owned(),
+        )
+        .await
+        .unwrap();
+
+        let hooks = dao.hooks_for("question_created").await.unwrap();
+        assert_eq!(hooks.len(), 1);
+        assert_eq!(hooks[0], hook);
+
+        dao.delete_hook(hook.hook_uuid).await.unwrap();
+        assert!(dao.hooks_for("question_created").await.unwrap().is_empty());
+        assert!(matches!(
+            dao.delete_hook(hook.hook_uuid).await,
+            Err(DBError::NotFound(Entity::RestHook, _))
+        ));
+
+        Ok(())
+    }
+}
@@ -0,0 +1,326 @@
+use std::{future::Future, time::Duration};
+
+use async_trait::async_trait;
+use log::warn;
+use rand::Rng;
+
+use super::{answer_dao::AnswerDao, question_dao::QuestionDao, RowStream};
+use crate::{
+    metrics,
+    models::{
+        postgres_error_code, Answer, AnswerDetail, AnswerSort, AnswerUuid, DBError, Listed,
+        Question, QuestionDetail, QuestionFilter, QuestionSort, QuestionUuid, Window,
+    },
+};
+
+#[derive(Debug, Clone, Copy)]
+pub struct RetryPolicy {
+    pub max_attempts: u32,
+    pub base_delay: Duration,
+    pub max_delay: Duration,
+}
+
+impl RetryPolicy {
+    pub async fn run<T, F, Fut>(&self, operation: &'static str, mut call: F) -> Result<T, DBError>
+    where
+        F: FnMut() -> Fut,
+        Fut: Future<Output = Result<T, DBError>>,
+    {
+        let mut attempt = 1;
+
+        loop {
+            match call().await {
+                Err(err) if attempt < self.max_attempts && is_transient(&err) => {
+                    let delay = self.backoff(attempt);
+                    warn!(
+                        "Transient error on {} (attempt {}/{}), retrying in {:?}: {}",
+                        operation, attempt, self.max_attempts, delay, err
+                    );
+                    metrics::DB_RETRIES.with_label_values(&[operation]).inc();
+
+                    tokio::time::sleep(delay).await;
+                    attempt += 1;
+                }
+                result => return result,
+            }
+        }
+    }
+
+    // Exponential backoff with full jitter.
+    fn backoff(&self, attempt: u32) -> Duration {
+        let ceiling = self
+            .base_delay
+            .saturating_mul(2u32.saturating_pow(attempt - 1))
+            .min(self.max_delay);
+
+        rand::thread_rng().gen_range(Duration::ZERO..=ceiling)
+    }
+}
+
+pub fn is_transient(err: &DBError) -> bool {
+    let DBError::Other(err) = err else {
+        return false;
+    };
+    let Some(err) = err.downcast_ref::<sqlx::Error>() else {
+        return false;
+    };
+
+    match err {
+        sqlx::Error::Io(_) => true,
+        sqlx::Error::Database(err) => err.code().is_some_and(|code| {
+            code == postgres_error_code::SERIALIZATION_FAILURE
+                || code == postgres_error_code::DEADLOCK_DETECTED
+                || code.starts_with(postgres_error_code::CONNECTION_EXCEPTION_CLASS)
+        }),
+        _ => false,
+    }
+}
+
+// Only idempotent operations are retried; a failed insert may still have committed.
+pub struct RetryQuestionDao<T> {
+    inner: T,
+    policy: RetryPolicy,
+}
+
+impl<T> RetryQuestionDao<T> {
+    pub fn new(inner: T, policy: RetryPolicy) -> Self {
+        Self { inner, policy }
+    }
+}
+
+#[async_trait]
+impl<T: QuestionDao + Send + Sync> QuestionDao for RetryQuestionDao<T> {
+    async fn create_question(&self, question: Question) -> Result<QuestionDetail, DBError> {
+        self.inner.create_question(question).await
+    }
+
+    async fn delete_question(&self, question_uuid: QuestionUuid) -> Result<(), DBError> {
+        // Not retried: if the first attempt committed, the retry would report the row as missing.
+        self.inner.delete_question(question_uuid).await
+    }
+
+    async fn update_question(
+        &self,
+        question_uuid: QuestionUuid,
+        question: Question,
+        version: i64,
+    ) -> Result<QuestionDetail, DBError> {
+        // Not retried: if the first attempt committed, the retry would report a conflict.
+        self.inner
+            .update_question(question_uuid, question, version)
+            .await
+    }
+
+    async fn get_questions(&self) -> Result<Vec<QuestionDetail>, DBError> {
+        self.policy
+            .run("get_questions", || self.inner.get_questions())
+            .await
+    }
+
+    async fn get_question(&self, question_uuid: QuestionUuid) -> Result<QuestionDetail, DBError> {
+        self.policy
+            .run("get_question", || self.inner.get_question(question_uuid))
+            .await
+    }
+
+    async fn get_questions_matching(
+        &self,
+        filter: &QuestionFilter,
+    ) -> Result<Vec<QuestionDetail>, DBError> {
+        self.policy
+            .run("get_questions_matching", || {
+                self.inner.get_questions_matching(filter)
+            })
+            .await
+    }
+
+    // Not retried: part of the rows may already be on their way to the client.
+    fn stream_questions(&self, filter: QuestionFilter) -> RowStream<'_, QuestionDetail> {
+        self.inner.stream_questions(filter)
+    }
+
+    async fn list_questions(
+        &self,
+        filter: &QuestionFilter,
+        include_archived: bool,
+        sort: QuestionSort,
+        window: Window,
+    ) -> Result<Listed<QuestionDetail>, DBError> {
+        self.policy
+            .run("list_questions", || {
+                self.inner
+                    .list_questions(filter, include_archived, sort, window)
+            })
+            .await
+    }
+
+    async fn list_unanswered_questions(
+        &self,
+        window: Window,
+    ) -> Result<Listed<QuestionDetail>, DBError> {
+        self.policy
+            .run("list_unanswered_questions", || {
+                self.inner.list_unanswered_questions(window)
+            })
+            .await
+    }
+}
+
+pub struct RetryAnswerDao<T> {
+    inner: T,
+    policy: RetryPolicy,
+}
+
+impl<T> RetryAnswerDao<T> {
+    pub fn new(inner: T, policy: RetryPolicy) -> Self {
+        Self { inner, policy }
+    }
+}
+
+#[async_trait]
+impl<T: AnswerDao + Send + Sync> AnswerDao for RetryAnswerDao<T> {
+    async fn create_answer(&self, answer: Answer) -> Result<AnswerDetail, DBError> {
+        self.inner.create_answer(answer).await
+    }
+
+    async fn delete_answer(&self, answer_uuid: AnswerUuid) -> Result<(), DBError> {
+        // Not retried, like `delete_question`.
+        self.inner.delete_answer(answer_uuid).await
+    }
+
+    async fn update_answer(
+        &self,
+        answer_uuid: AnswerUuid,
+        content: String,
+        version: i64,
+    ) -> Result<AnswerDetail, DBError> {
+        self.inner
+            .update_answer(answer_uuid, content, version)
+            .await
+    }
+
+    async fn get_answers(&self, question_uuid: QuestionUuid) -> Result<Vec<AnswerDetail>, DBError> {
+        self.policy
+            .run("get_answers", || self.inner.get_answers(question_uuid))
+            .await
+    }
+
+    fn stream_answers(&self, question_uuid: QuestionUuid) -> RowStream<'_, AnswerDetail> {
+        self.inner.stream_answers(question_uuid)
+    }
+
+    async fn get_answers_of_questions(
+        &self,
+        question_uuids: &[QuestionUuid],
+    ) -> Result<Vec<AnswerDetail>, DBError> {
+        self.policy
+            .run("get_answers_of_questions", || {
+                self.inner.get_answers_of_questions(question_uuids)
+            })
+            .await
+    }
+
+    async fn list_answers(
+        &self,
+        question_uuid: QuestionUuid,
+        include_archived: bool,
+        sort: AnswerSort,
+        window: Window,
+    ) -> Result<Listed<AnswerDetail>, DBError> {
+        self.policy
+            .run("list_answers", || {
+                self.inner
+                    .list_answers(question_uuid, include_archived, sort, window)
+            })
+            .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    use super::*;
+
+    fn policy() -> RetryPolicy {
+        RetryPolicy {
+            max_attempts: 3,
+            base_delay: Duration::from_millis(1),
+            max_delay: Duration::from_millis(5),
+        }
+    }
+
+    fn transient_error() -> DBError {
+        DBError::Other(Box::new(sqlx::Error::Io(std::io::Error::new(
+            std::io::ErrorKind::ConnectionReset,
+            "reset",
+        ))))
+    }
+
+    #[test]
+    fn is_transient_should_classify_errors() {
+        assert!(is_transient(&transient_error()));
+        assert!(!is_transient(&DBError::InvalidUUID("".to_owned())));
+        assert!(!is_transient(&DBError::Other(Box::new(
+            sqlx::Error::RowNotFound
+        ))));
+        assert!(!is_transient(&DBError::Other(Box::new(
+            std::io::Error::other("not sqlx")
+        ))));
+    }
+
+    #[test]
+    fn backoff_should_stay_below_max_delay() {
+        let policy = policy();
+        for attempt in 1..10 {
+            assert!(policy.backoff(attempt) <= policy.max_delay);
+        }
+    }
+
+    #[tokio::test]
+    async fn run_should_retry_transient_errors_until_success() {
+        let attempts = AtomicU32::new(0);
+
+        let result = policy()
+            .run("test", || async {
+                if attempts.fetch_add(1, Ordering::SeqCst) == 0 {
+                    return Err(transient_error());
+                }
+                Ok(())
+            })
+            .await;
+
+        assert!(result.is_ok());
+        assert_eq!(attempts.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn run_should_give_up_after_max_attempts() {
+        let attempts = AtomicU32::new(0);
+
+        let result: Result<(), _> = policy()
+            .run("test", || async {
+                attempts.fetch_add(1, Ordering::SeqCst);
+                Err(transient_error())
+            })
+            .await;
+
+        assert!(matches!(result, Err(DBError::Other(_))));
+        assert_eq!(attempts.load(Ordering::SeqCst), 3);
+    }
+
+    #[tokio::test]
+    async fn run_should_not_retry_permanent_errors() {
+        let attempts = AtomicU32::new(0);
+
+        let result: Result<(), _> = policy()
+            .run("test", || async {
+                attempts.fetch_add(1, Ordering::SeqCst);
+                Err(DBError::InvalidUUID("".to_owned()))
+            })
+            .await;
+
+        assert!(matches!(result, Err(DBError::InvalidUUID(_))));
+        assert_eq!(attempts.load(Ordering::SeqCst), 1);
+    }
+}
@@ -0,0 +1,66 @@
+use serde_json::Value;
+use sqlx::{
+    types::{chrono::NaiveDateTime, uuid::fmt::Hyphenated, Json},
+    FromRow,
+};
+
+use crate::models::{AnswerDetail, AnswerUuid, QuestionDetail, QuestionUuid};
+
+// Backends without a native uuid type store them as hyphenated text, so the rows decode
+// them from that and bound uuids are passed with `to_string`. Metadata is decoded from
+// JSON text the same way, and bound wrapped in `Json`.
+
+#[derive(FromRow)]
+pub struct QuestionRow {
+    pub question_uuid: Hyphenated,
+    pub title: String,
+    pub description: Option<String>,
+    pub metadata: Json<Value>,
+    pub created_at: NaiveDateTime,
+    pub updated_at: NaiveDateTime,
+    pub last_activity_at: NaiveDateTime,
+    pub answer_count: i64,
+    pub version: i64,
+}
+
+impl From<QuestionRow> for QuestionDetail {
+    fn from(row: QuestionRow) -> Self {
+        QuestionDetail {
+            question_uuid: QuestionUuid(row.question_uuid.into_uuid()),
+            title: row.title,
+            // SQLite stores a missing description as ''.
+            description: row
+                .description
+                .filter(|description| !description.is_empty()),
+            metadata: row.metadata.0,
+            created_at: row.created_at.and_utc(),
+            updated_at: row.updated_at.and_utc(),
+            last_activity_at: row.last_activity_at.and_utc(),
+            answer_count: row.answer_count,
+            version: row.version,
+        }
+    }
+}
+
+#[derive(FromRow)]
+pub struct AnswerRow {
+    pub answer_uuid: Hyphenated,
+    pub question_uuid: Hyphenated,
+    pub content: String,
+    pub created_at: NaiveDateTime,
+    pub updated_at: NaiveDateTime,
+    pub version: i64,
+}
+
+impl From<AnswerRow> for AnswerDetail {
+    fn from(row: AnswerRow) -> Self {
+        AnswerDetail {
+            answer_uuid: AnswerUuid(row.answer_uuid.into_uuid()),
+            question_uuid: QuestionUuid(row.question_uuid.into_uuid()),
+            content: row.content,
+            created_at: row.created_at.and_utc(),
+            updated_at: row.updated_at.and_utc(),
+            version: row.version,
+        }
+    }
+}
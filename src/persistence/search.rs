@@ -0,0 +1,456 @@
+use async_trait::async_trait;
+use sqlx::{Connection, PgPool};
+use uuid::Uuid;
+
+use super::{acquire, question_dao::QuestionRow};
+use crate::models::{DBError, QuestionDetail, QuestionUuid};
+
+// A title completing what is being typed, kept small for as-you-type requests.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TitleMatch {
+    pub question_uuid: QuestionUuid,
+    pub title: String,
+}
+
+// A question's title and description with the words that made it match marked, as HTML.
+// The description is cut to the passages around them.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Highlight {
+    pub title: String,
+    pub description: Option<String>,
+}
+
+// Delimit matches until `marked_html` turns them into `<mark>`. Private use characters,
+// so no text contains them and escaping leaves them alone.
+pub const MATCH_START: char = '\u{E000}';
+pub const MATCH_END: char = '\u{E001}';
+
+// The text HTML escaped, with the marked matches in `<mark>`.
+pub fn marked_html(text: &str) -> String {
+    let mut html = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            MATCH_START => html.push_str("<mark>"),
+            MATCH_END => html.push_str("</mark>"),
+            '&' => html.push_str("&amp;"),
+            '<' => html.push_str("&lt;"),
+            '>' => html.push_str("&gt;"),
+            '"' => html.push_str("&quot;"),
+            '\'' => html.push_str("&#39;"),
+            c => html.push(c),
+        }
+    }
+    html
+}
+
+// Full text search over question titles and descriptions.
+#[async_trait]
+pub trait SearchDao {
+    // Best match first. `query` takes web search syntax: quoted phrases, `or` and `-word`.
+    async fn search_questions(
+        &self,
+        query: &str,
+        limit: u32,
+    ) -> Result<Vec<QuestionDetail>, DBError>;
+    // The questions that still exist of `question_uuids`, in no particular order, for
+    // search engines that only return ids.
+    async fn questions_by_uuid(
+        &self,
+        question_uuids: &[QuestionUuid],
+    ) -> Result<Vec<QuestionDetail>, DBError>;
+    // Titles containing `text`, case-insensitively: those starting with it first, then
+    // the closest and most answered.
+    async fn complete_titles(&self, text: &str, limit: u32) -> Result<Vec<TitleMatch>, DBError>;
+    // Titles alike to `text` from `threshold` on, by trigram word similarity from 0 to 1,
+    // closest first, for suggesting what a misspelled query was after. `excluded` were
+    // found already.
+    async fn similar_titles(
+        &self,
+        text: &str,
+        threshold: f32,
+        excluded: &[QuestionUuid],
+        limit: u32,
+    ) -> Result<Vec<TitleMatch>, DBError>;
+    // What of `questions` matched `query`, one per question in the same order. Works on
+    // any of them, so results of another search engine can be highlighted too.
+    async fn highlight(
+        &self,
+        query: &str,
+        questions: &[QuestionDetail],
+    ) -> Result<Vec<Highlight>, DBError>;
+}
+
+pub struct PgSearchDao {
+    db: PgPool,
+}
+
+impl PgSearchDao {
+    pub fn new(db: PgPool) -> Self {
+        Self { db }
+    }
+}
+
+#[async_trait]
+impl SearchDao for PgSearchDao {
+    async fn search_questions(
+        &self,
+        query: &str,
+        limit: u32,
+    ) -> Result<Vec<QuestionDetail>, DBError> {
+        let mut conn = acquire(&self.db).await?;
+
+        let rows = sqlx::query_as!(
+            QuestionRow,
+            r#"
+                SELECT question_uuid, title, description, metadata, created_at, updated_at, last_activity_at, answer_count, version
+                FROM questions
+                WHERE to_tsvector('english', title || ' ' || coalesce(description, ''))
+                    @@ websearch_to_tsquery('english', $1)
+                ORDER BY ts_rank(
+                    to_tsvector('english', title || ' ' || coalesce(description, '')),
+                    websearch_to_tsquery('english', $1)
+                ) DESC, created_at DESC
+                LIMIT $2
+            "#,
+            query,
+            i64::from(limit),
+        )
+        .fetch_all(&mut conn)
+        .await
+        .map_err(|err| DBError::Other(Box::new(err)))?;
+
+        Ok(rows.into_iter().map(QuestionDetail::from).collect())
+    }
+
+    async fn questions_by_uuid(
+        &self,
+        question_uuids: &[QuestionUuid],
+    ) -> Result<Vec<QuestionDetail>, DBError> {
+        let mut conn = acquire(&self.db).await?;
+        let question_uuids: Vec<Uuid> = question_uuids.iter().map(|uuid| uuid.0).collect();
+
+        let rows = sqlx::query_as!(
+            QuestionRow,
+            r#"
+                SELECT question_uuid, title, description, metadata, created_at, updated_at, last_activity_at, answer_count, version
+                FROM questions
+                WHERE question_uuid = ANY($1)
+            "#,
+            &question_uuids,
+        )
+        .fetch_all(&mut conn)
+        .await
+        .map_err(|err| DBError::Other(Box::new(err)))?;
+
+        Ok(rows.into_iter().map(QuestionDetail::from).collect())
+    }
+
+    async fn complete_titles(&self, text: &str, limit: u32) -> Result<Vec<TitleMatch>, DBError> {
+        let mut conn = acquire(&self.db).await?;
+        let text = text.to_lowercase();
+        // Typed `%` and `_` are matched as they are, not as wildcards.
+        let escaped = text
+            .replace('\\', "\\\\")
+            .replace('%', "\\%")
+            .replace('_', "\\_");
+
+        let rows = sqlx::query!(
+            r#"
+                SELECT question_uuid, title
+                FROM questions
+                WHERE lower(title) LIKE '%' || $1 || '%'
+                ORDER BY lower(title) LIKE $1 || '%' DESC,
+                    word_similarity($2, lower(title)) DESC,
+                    answer_count DESC,
+                    created_at DESC
+                LIMIT $3
+            "#,
+            escaped,
+            text,
+            i64::from(limit),
+        )
+        .fetch_all(&mut conn)
+        .await
+        .map_err(|err| DBError::Other(Box::new(err)))?;
+
+        Ok(rows
+            .into_iter()
+            .map(|row| TitleMatch {
+                question_uuid: QuestionUuid(row.question_uuid),
+                title: row.title,
+            })
+            .collect())
+    }
+
+    async fn similar_titles(
+        &self,
+        text: &str,
+        threshold: f32,
+        excluded: &[QuestionUuid],
+        limit: u32,
+    ) -> Result<Vec<TitleMatch>, DBError> {
+        let mut conn = acquire(&self.db).await?;
+        let excluded: Vec<Uuid> = excluded.iter().map(|uuid| uuid.0).collect();
+
+        async {
+            let mut tx = conn.begin().await?;
+            // `<%` compares against this, which lets the trigram index on titles serve it.
+            sqlx::query("SELECT set_config('pg_trgm.word_similarity_threshold', $1, true)")
+                .bind(threshold.to_string())
+                .execute(&mut tx)
+                .await?;
+            let rows = sqlx::query!(
+                r#"
+                    SELECT question_uuid, title
+                    FROM questions
+                    WHERE lower($1) <% lower(title) AND NOT question_uuid = ANY($2)
+                    ORDER BY word_similarity(lower($1), lower(title)) DESC,
+                        answer_count DESC,
+                        created_at DESC
+                    LIMIT $3
+                "#,
+                text,
+                &excluded,
+                i64::from(limit),
+            )
+            .fetch_all(&mut tx)
+            .await?;
+            tx.commit().await?;
+
+            Ok(rows
+                .into_iter()
+                .map(|row| TitleMatch {
+                    question_uuid: QuestionUuid(row.question_uuid),
+                    title: row.title,
+                })
+                .collect())
+        }
+        .await
+        .map_err(|err: sqlx::Error| DBError::Other(Box::new(err)))
+    }
+
+    async fn highlight(
+        &self,
+        query: &str,
+        questions: &[QuestionDetail],
+    ) -> Result<Vec<Highlight>, DBError> {
+        let mut conn = acquire(&self.db).await?;
+        let titles: Vec<&str> = questions.iter().map(|q| q.title.as_str()).collect();
+        let descriptions: Vec<&str> = questions
+            .iter()
+            .map(|q| q.description.as_deref().unwrap_or_default())
+            .collect();
+        let selection = format!("StartSel={MATCH_START}, StopSel={MATCH_END}");
+
+        let rows = sqlx::query!(
+            r#"
+                SELECT
+                    ts_headline('english', texts.title, query, $4 || ', HighlightAll=true') AS "title!",
+                    ts_headline(
+                        'english', texts.description, query,
+                        $4 || ', MaxFragments=2, MinWords=10, MaxWords=30, FragmentDelimiter=" … "'
+                    ) AS "description!"
+                FROM unnest($2::text[], $3::text[]) WITH ORDINALITY AS texts(title, description, position),
+                    websearch_to_tsquery('english', $1) AS query
+                ORDER BY texts.position
+            "#,
+            query,
+            &titles as &[&str],
+            &descriptions as &[&str],
+            selection,
+        )
+        .fetch_all(&mut conn)
+        .await
+        .map_err(|err| DBError::Other(Box::new(err)))?;
+
+        Ok(rows
+            .into_iter()
+            .zip(questions)
+            .map(|(row, question)| Highlight {
+                title: marked_html(&row.title),
+                description: question
+                    .description
+                    .as_ref()
+                    .map(|_| marked_html(&row.description)),
+            })
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::DateTime;
+
+    use super::*;
+    use crate::{
+        models::{empty_metadata, Question},
+        persistence::question_dao::{QuestionDao, QuestionDaoImpl},
+    };
+
+    #[sqlx::test]
+    async fn search_questions_should_match_stemmed_words(pool: PgPool) -> Result<(), String> {
+        let question_dao = QuestionDaoImpl::new(pool.clone());
+        let mut created = vec![];
+        for (title, description) in [
+            ("Refunds for duplicate invoices", None),
+            ("Billing address", Some("The invoice shows an old address")),
+            ("API tokens", None),
+        ] {
+            created.push(
+                question_dao
+                    .create_question(Question {
+                        title: title.to_owned(),
+                        description: description.map(str::to_owned),
+                        metadata: empty_metadata(),
+                    })
+                    .await
+                    .unwrap(),
+            );
+        }
+        let dao = PgSearchDao::new(pool);
+
+        let found = dao.search_questions("invoice", 10).await.unwrap();
+        let mut titles: Vec<&str> = found.iter().map(|q| q.title.as_str()).collect();
+        titles.sort();
+        assert_eq!(
+            titles,
+            ["Billing address", "Refunds for duplicate invoices"]
+        );
+        let found = dao.search_questions("invoice -refund", 10).await.unwrap();
+        assert_eq!(found, vec![created[1].clone()]);
+
+        let found = dao
+            .questions_by_uuid(&[created[2].question_uuid, QuestionUuid::new_v4()])
+            .await
+            .unwrap();
+        assert_eq!(found, vec![created[2].clone()]);
+
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn similar_titles_should_suggest_close_spellings(pool: PgPool) -> Result<(), String> {
+        let question_dao = QuestionDaoImpl::new(pool.clone());
+        let mut created = vec![];
+        for title in [
+            "Refunds for duplicate invoices",
+            "Invoice address",
+            "API tokens",
+        ] {
+            created.push(
+                question_dao
+                    .create_question(Question {
+                        title: title.to_owned(),
+                        description: None,
+                        metadata: empty_metadata(),
+                    })
+                    .await
+                    .unwrap(),
+            );
+        }
+        let dao = PgSearchDao::new(pool);
+
+        let titles = |matches: Vec<TitleMatch>| -> Vec<String> {
+            matches.into_iter().map(|found| found.title).collect()
+        };
+        let mut similar = titles(dao.similar_titles("Invoise", 0.3, &[], 10).await.unwrap());
+        similar.sort();
+        assert_eq!(
+            similar,
+            ["Invoice address", "Refunds for duplicate invoices"]
+        );
+        assert_eq!(
+            titles(
+                dao.similar_titles("invoise", 0.3, &[created[1].question_uuid], 10)
+                    .await
+                    .unwrap()
+            ),
+            ["Refunds for duplicate invoices"]
+        );
+        assert!(dao
+            .similar_titles("invoise", 0.9, &[], 10)
+            .await
+            .unwrap()
+            .is_empty());
+
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn highlight_should_mark_stemmed_matches(pool: PgPool) -> Result<(), String> {
+        let question = |title: &str, description: Option<&str>| QuestionDetail {
+            question_uuid: QuestionUuid::new_v4(),
+            title: title.to_owned(),
+            description: description.map(str::to_owned),
+            metadata: empty_metadata(),
+            created_at: DateTime::UNIX_EPOCH,
+            updated_at: DateTime::UNIX_EPOCH,
+            last_activity_at: DateTime::UNIX_EPOCH,
+            answer_count: 0,
+            version: 1,
+        };
+        let dao = PgSearchDao::new(pool);
+
+        let highlights = dao
+            .highlight(
+                "invoice",
+                &[
+                    question("Refunds for <duplicate> invoices", None),
+                    question("Billing address", Some("The invoice shows an old address")),
+                ],
+            )
+            .await
+            .unwrap();
+        assert_eq!(
+            highlights,
+            [
+                Highlight {
+                    title: "Refunds for &lt;duplicate&gt; <mark>invoices</mark>".to_owned(),
+                    description: None,
+                },
+                // Passages start at a match.
+                Highlight {
+                    title: "Billing address".to_owned(),
+                    description: Some("<mark>invoice</mark> shows an old address".to_owned()),
+                },
+            ]
+        );
+
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn complete_titles_should_put_prefix_matches_first(pool: PgPool) -> Result<(), String> {
+        let question_dao = QuestionDaoImpl::new(pool.clone());
+        for title in [
+            "Refunds for duplicate invoices",
+            "Invoice address",
+            "100% off_sale",
+        ] {
+            question_dao
+                .create_question(Question {
+                    title: title.to_owned(),
+                    description: None,
+                    metadata: empty_metadata(),
+                })
+                .await
+                .unwrap();
+        }
+        let dao = PgSearchDao::new(pool);
+
+        let titles = |matches: Vec<TitleMatch>| -> Vec<String> {
+            matches.into_iter().map(|found| found.title).collect()
+        };
+        assert_eq!(
+            titles(dao.complete_titles("INVOIC", 10).await.unwrap()),
+            ["Invoice address", "Refunds for duplicate invoices"]
+        );
+        assert_eq!(
+            titles(dao.complete_titles("0% off_", 10).await.unwrap()),
+            ["100% off_sale"]
+        );
+        assert!(dao.complete_titles("0%_", 10).await.unwrap().is_empty());
+
+        Ok(())
+    }
+}
@@ -0,0 +1,144 @@
+use std::{
+    collections::VecDeque,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use crate::metrics;
+
+// Callers waiting for a primary pool connection, and when each started to. Requests are
+// shed while that queue is too deep or its oldest caller has waited too long, instead of
+// all of them queueing into their deadlines.
+static QUEUE: Mutex<Queue> = Mutex::new(Queue {
+    next_ticket: 0,
+    waiters: VecDeque::new(),
+    max_wait: Duration::ZERO,
+    max_depth: 0,
+});
+
+struct Queue {
+    next_ticket: u64,
+    // By ticket, so the front is the caller waiting longest. Callers can leave from
+    // anywhere in the queue when their wait is cancelled.
+    waiters: VecDeque<(u64, Instant)>,
+    // Zero disables either check.
+    max_wait: Duration,
+    max_depth: usize,
+}
+
+impl Queue {
+    fn overloaded(&self, now: Instant) -> bool {
+        let too_deep = self.max_depth > 0 && self.waiters.len() >= self.max_depth;
+        let too_long = !self.max_wait.is_zero()
+            && self
+                .waiters
+                .front()
+                .is_some_and(|(_, since)| now.duration_since(*since) >= self.max_wait);
+        too_deep || too_long
+    }
+
+    fn join(&mut self, now: Instant) -> u64 {
+        let ticket = self.next_ticket;
+        self.next_ticket += 1;
+        self.waiters.push_back((ticket, now));
+        ticket
+    }
+
+    fn leave(&mut self, ticket: u64) {
+        if let Ok(index) = self
+            .waiters
+            .binary_search_by_key(&ticket, |(ticket, _)| *ticket)
+        {
+            self.waiters.remove(index);
+        }
+    }
+}
+
+pub fn set_limits(max_wait: Duration, max_depth: usize) {
+    let mut queue = QUEUE.lock().unwrap();
+    queue.max_wait = max_wait;
+    queue.max_depth = max_depth;
+}
+
+// Whether a new request should be turned away; counted in the shed metric when it is.
+pub fn should_shed() -> bool {
+    let overloaded = QUEUE.lock().unwrap().overloaded(Instant::now());
+    if overloaded {
+        metrics::REQUESTS_SHED.inc();
+    }
+    overloaded
+}
+
+// Held while waiting for a connection; dropping it, also when the wait is cancelled,
+// leaves the queue.
+pub struct Waiting(u64);
+
+pub fn wait() -> Waiting {
+    let mut queue = QUEUE.lock().unwrap();
+    let ticket = queue.join(Instant::now());
+    metrics::POOL_WAITING.set(queue.waiters.len() as i64);
+    Waiting(ticket)
+}
+
+impl Drop for Waiting {
+    fn drop(&mut self) {
+        let mut queue = QUEUE.lock().unwrap();
+        queue.leave(self.0);
+        metrics::POOL_WAITING.set(queue.waiters.len() as i64);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn queue(started: &[Instant]) -> Queue {
+        let mut queue = Queue {
+            next_ticket: 0,
+            waiters: VecDeque::new(),
+            max_wait: Duration::from_millis(200),
+            max_depth: 10,
+        };
+        for at in started {
+            queue.join(*at);
+        }
+        queue
+    }
+
+    #[test]
+    fn queue_should_be_overloaded_when_too_deep_or_too_slow() {
+        let now = Instant::now();
+        let ago = |millis| now - Duration::from_millis(millis);
+
+        assert!(!queue(&[]).overloaded(now));
+        assert!(!queue(&[ago(100); 9]).overloaded(now));
+        assert!(queue(&[ago(100); 10]).overloaded(now));
+        assert!(queue(&[ago(200)]).overloaded(now));
+
+        let disabled = Queue {
+            max_wait: Duration::ZERO,
+            max_depth: 0,
+            ..queue(&[ago(10_000); 100])
+        };
+        assert!(!disabled.overloaded(now));
+    }
+
+    #[test]
+    fn wait_should_be_timed_from_the_oldest_caller_still_waiting() {
+        let now = Instant::now();
+        let ago = |millis| now - Duration::from_millis(millis);
+        let mut queue = queue(&[]);
+
+        // Callers keep overlapping, so the queue never drains.
+        let first = queue.join(ago(300));
+        let second = queue.join(ago(100));
+        assert!(queue.overloaded(now));
+        queue.leave(first);
+        assert!(!queue.overloaded(now));
+        let third = queue.join(ago(50));
+        queue.leave(second);
+        assert!(!queue.overloaded(now));
+        queue.leave(third);
+        assert!(queue.waiters.is_empty());
+    }
+}
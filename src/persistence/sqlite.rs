@@ -0,0 +1,1263 @@
+use std::{str::FromStr, time::Duration};
+
+use async_stream::try_stream;
+use async_trait::async_trait;
+use chrono::NaiveDate;
+use futures::TryStreamExt;
+use sqlx::{
+    migrate::{MigrateError, Migrator},
+    sqlite::{SqliteConnectOptions, SqlitePoolOptions},
+    types::{Json, Uuid},
+    Sqlite, SqliteConnection, SqlitePool, Transaction,
+};
+
+use super::{
+    acquire,
+    answer_dao::AnswerDao,
+    archive::ArchiveDao,
+    blocked_deletion, missed_update,
+    question_dao::{QuestionDao, QuestionDeletion},
+    row::{AnswerRow, QuestionRow},
+    statements,
+    stats::StatsDao,
+    unit_of_work::{UnitOfWork, UnitOfWorkFactory},
+    unknown_question, RowStream,
+};
+use crate::models::{
+    sqlite_error_code, Answer, AnswerDetail, AnswerSort, AnswerUuid, ArchiveSummary, DBError,
+    DailyStats, Entity, Listed, Question, QuestionDetail, QuestionFilter, QuestionSort,
+    QuestionUuid, ResponseStats, Window,
+};
+
+static MIGRATOR: Migrator = sqlx::migrate!("./migrations/sqlite");
+
+// Opens (creating it if needed) and migrates the database file, so no setup is required.
+pub async fn connect(
+    url: &str,
+    max_connections: u32,
+    acquire_timeout: Duration,
+) -> Result<SqlitePool, MigrateError> {
+    let options = SqliteConnectOptions::from_str(url)?.create_if_missing(true);
+    let pool = SqlitePoolOptions::new()
+        .max_connections(max_connections)
+        .acquire_timeout(acquire_timeout)
+        .connect_with(options)
+        .await?;
+
+    MIGRATOR.run(&pool).await?;
+    Ok(pool)
+}
+
+pub struct SqliteQuestionDao {
+    db: SqlitePool,
+    deletion: QuestionDeletion,
+}
+
+impl SqliteQuestionDao {
+    pub fn new(db: SqlitePool) -> Self {
+        Self {
+            db,
+            deletion: QuestionDeletion::Cascade,
+        }
+    }
+
+    pub fn with_deletion(mut self, deletion: QuestionDeletion) -> Self {
+        self.deletion = deletion;
+        self
+    }
+}
+
+#[async_trait]
+impl QuestionDao for SqliteQuestionDao {
+    async fn create_question(&self, question: Question) -> Result<QuestionDetail, DBError> {
+        let mut conn = acquire(&self.db).await?;
+        insert_question(&mut conn, question).await
+    }
+
+    async fn delete_question(&self, question_uuid: QuestionUuid) -> Result<(), DBError> {
+        let question_uuid = question_uuid.to_string();
+
+        let mut conn = acquire(&self.db).await?;
+        let restrict = self.deletion == QuestionDeletion::Restrict;
+
+        let deleted = sqlx::query(statements::DELETE_QUESTION)
+            .bind(&question_uuid)
+            .bind(restrict)
+            .execute(&mut conn)
+            .await
+            .map_err(|err| DBError::Other(Box::new(err)))?
+            .rows_affected();
+
+        if deleted == 0 && restrict {
+            let answer_count = sqlx::query_scalar::<_, i64>(statements::QUESTION_ANSWER_COUNT)
+                .bind(&question_uuid)
+                .fetch_optional(&mut conn)
+                .await
+                .map_err(|err| DBError::Other(Box::new(err)))?;
+
+            if let Some(answer_count) = answer_count {
+                return Err(blocked_deletion(&question_uuid, answer_count));
+            }
+        }
+
+        Ok(())
+    }
+
+    async fn update_question(
+        &self,
+        question_uuid: QuestionUuid,
+        question: Question,
+        version: i64,
+    ) -> Result<QuestionDetail, DBError> {
+        let question_uuid = question_uuid.to_string();
+
+        let mut conn = acquire(&self.db).await?;
+
+        // Read back separately so changes made by the updated_at trigger are included.
+        let updated = sqlx::query(statements::UPDATE_QUESTION)
+            .bind(&question.title)
+            .bind(stored_description(&question))
+            .bind(Json(&question.metadata))
+            .bind(&question_uuid)
+            .bind(version)
+            .execute(&mut conn)
+            .await
+            .map_err(|err| DBError::Other(Box::new(err)))?
+            .rows_affected();
+
+        if updated == 0 {
+            let current_version = sqlx::query_scalar::<_, i64>(statements::QUESTION_VERSION)
+                .bind(&question_uuid)
+                .fetch_optional(&mut conn)
+                .await
+                .map_err(|err| DBError::Other(Box::new(err)))?;
+
+            return Err(missed_update(
+                Entity::Question,
+                &question_uuid,
+                current_version,
+                version,
+            ));
+        }
+
+        let result = sqlx::query_as::<_, QuestionRow>(statements::SELECT_QUESTION)
+            .bind(&question_uuid)
+            .fetch_one(&mut conn)
+            .await
+            .map_err(|err| DBError::Other(Box::new(err)))?;
+
+        Ok(result.into())
+    }
+
+    async fn get_questions(&self) -> Result<Vec<QuestionDetail>, DBError> {
+        let mut conn = acquire(&self.db).await?;
+
+        let result = sqlx::query_as::<_, QuestionRow>(statements::SELECT_QUESTIONS)
+            .fetch_all(&mut conn)
+            .await
+            .map_err(|err| DBError::Other(Box::new(err)))?;
+
+        Ok(result.into_iter().map(QuestionDetail::from).collect())
+    }
+
+    async fn get_question(&self, question_uuid: QuestionUuid) -> Result<QuestionDetail, DBError> {
+        let mut conn = acquire(&self.db).await?;
+
+        let result = sqlx::query_as::<_, QuestionRow>(statements::SELECT_QUESTION)
+            .bind(question_uuid.to_string())
+            .fetch_optional(&mut conn)
+            .await
+            .map_err(|err| DBError::Other(Box::new(err)))?;
+
+        result
+            .map(QuestionDetail::from)
+            .ok_or_else(|| unknown_question(&question_uuid))
+    }
+
+    async fn get_questions_matching(
+        &self,
+        filter: &QuestionFilter,
+    ) -> Result<Vec<QuestionDetail>, DBError> {
+        matching_questions(&self.db, "questions", filter.clone())
+            .try_collect()
+            .await
+    }
+
+    async fn list_questions(
+        &self,
+        filter: &QuestionFilter,
+        include_archived: bool,
+        sort: QuestionSort,
+        window: Window,
+    ) -> Result<Listed<QuestionDetail>, DBError> {
+        let mut conn = acquire(&self.db).await?;
+        let after = filter.created.after.map(|at| at.naive_utc());
+        let before = filter.created.before.map(|at| at.naive_utc());
+
+        // The conditions of `get_questions_matching`, over archived questions too.
+        let matching = format!(
+            r#"
+                FROM {}
+                WHERE NOT EXISTS (
+                    SELECT 1 FROM json_each(?) AS wanted
+                    WHERE json_type(questions.metadata, '$.' || json_quote(wanted.key)) IS NOT 'text'
+                    OR json_extract(questions.metadata, '$.' || json_quote(wanted.key)) IS NOT wanted.value
+                )
+                AND (? IS NULL OR julianday(created_at) >= julianday(?))
+                AND (? IS NULL OR julianday(created_at) < julianday(?))
+            "#,
+            statements::QUESTIONS_AND_ARCHIVED
+        );
+
+        let items = sqlx::query_as::<_, QuestionRow>(&format!(
+            "SELECT * {} ORDER BY {} LIMIT ? OFFSET ?",
+            matching,
+            statements::question_order(sort)
+        ))
+        .bind(include_archived)
+        .bind(Json(filter.metadata.to_json()))
+        .bind(after)
+        .bind(after)
+        .bind(before)
+        .bind(before)
+        .bind(window.limit as i64)
+        .bind(window.offset as i64)
+        .fetch_all(&mut conn)
+        .await
+        .map_err(|err| DBError::Other(Box::new(err)))?;
+
+        let total: i64 = sqlx::query_scalar(&format!("SELECT COUNT(*) {}", matching))
+            .bind(include_archived)
+            .bind(Json(filter.metadata.to_json()))
+            .bind(after)
+            .bind(after)
+            .bind(before)
+            .bind(before)
+            .fetch_one(&mut conn)
+            .await
+            .map_err(|err| DBError::Other(Box::new(err)))?;
+
+        Ok(Listed {
+            items: items.into_iter().map(QuestionDetail::from).collect(),
+            total: total as u64,
+        })
+    }
+
+    async fn list_unanswered_questions(
+        &self,
+        window: Window,
+    ) -> Result<Listed<QuestionDetail>, DBError> {
+        let mut conn = acquire(&self.db).await?;
+
+        let items = sqlx::query_as::<_, QuestionRow>(statements::UNANSWERED_QUESTIONS)
+            .bind(window.limit as i64)
+            .bind(window.offset as i64)
+            .fetch_all(&mut conn)
+            .await
+            .map_err(|err| DBError::Other(Box::new(err)))?;
+        let total: i64 = sqlx::query_scalar(statements::UNANSWERED_QUESTION_COUNT)
+            .fetch_one(&mut conn)
+            .await
+            .map_err(|err| DBError::Other(Box::new(err)))?;
+
+        Ok(Listed {
+            items: items.into_iter().map(QuestionDetail::from).collect(),
+            total: total as u64,
+        })
+    }
+
+    fn stream_questions(&self, filter: QuestionFilter) -> RowStream<'_, QuestionDetail> {
+        matching_questions(&self.db, "questions", filter)
+    }
+}
+
+// The questions of `table` that `filter` matches, fetched as they are consumed. A
+// question matches unless one of the wanted keys is missing or holds anything but that
+// string. Timestamps are text, compared as Julian days so fractions of a second count.
+fn matching_questions<'a>(
+    db: &'a SqlitePool,
+    table: &'static str,
+    filter: QuestionFilter,
+) -> RowStream<'a, QuestionDetail> {
+    Box::pin(try_stream! {
+        let mut conn = acquire(db).await?;
+
+        let sql = format!(
+            r#"
+                SELECT question_uuid, title, description, metadata, created_at, updated_at, last_activity_at, answer_count, version
+                FROM {} AS questions
+                WHERE NOT EXISTS (
+                    SELECT 1 FROM json_each(?) AS wanted
+                    WHERE json_type(questions.metadata, '$.' || json_quote(wanted.key)) IS NOT 'text'
+                    OR json_extract(questions.metadata, '$.' || json_quote(wanted.key)) IS NOT wanted.value
+                )
+                AND (? IS NULL OR julianday(created_at) >= julianday(?))
+                AND (? IS NULL OR julianday(created_at) < julianday(?))
+            "#,
+            table
+        );
+        let after = filter.created.after.map(|at| at.naive_utc());
+        let before = filter.created.before.map(|at| at.naive_utc());
+        let mut rows = sqlx::query_as::<_, QuestionRow>(&sql)
+            .bind(Json(filter.metadata.to_json()))
+            .bind(after)
+            .bind(after)
+            .bind(before)
+            .bind(before)
+            .fetch(&mut conn);
+
+        while let Some(row) = rows
+            .try_next()
+            .await
+            .map_err(|err| DBError::Other(Box::new(err)))?
+        {
+            yield row.into();
+        }
+    })
+}
+
+pub struct SqliteAnswerDao {
+    db: SqlitePool,
+}
+
+impl SqliteAnswerDao {
+    pub fn new(db: SqlitePool) -> Self {
+        Self { db }
+    }
+}
+
+#[async_trait]
+impl AnswerDao for SqliteAnswerDao {
+    async fn create_answer(&self, answer: Answer) -> Result<AnswerDetail, DBError> {
+        let mut conn = acquire(&self.db).await?;
+        insert_answer(&mut conn, answer).await
+    }
+
+    async fn delete_answer(&self, answer_uuid: AnswerUuid) -> Result<(), DBError> {
+        let answer_uuid = answer_uuid.to_string();
+
+        let mut conn = acquire(&self.db).await?;
+
+        sqlx::query(statements::DELETE_ANSWER)
+            .bind(answer_uuid)
+            .execute(&mut conn)
+            .await
+            .map_err(|err| DBError::Other(Box::new(err)))?;
+
+        Ok(())
+    }
+
+    async fn update_answer(
+        &self,
+        answer_uuid: AnswerUuid,
+        content: String,
+        version: i64,
+    ) -> Result<AnswerDetail, DBError> {
+        let answer_uuid = answer_uuid.to_string();
+
+        let mut conn = acquire(&self.db).await?;
+
+        let updated = sqlx::query(statements::UPDATE_ANSWER)
+            .bind(&content)
+            .bind(&answer_uuid)
+            .bind(version)
+            .execute(&mut conn)
+            .await
+            .map_err(|err| DBError::Other(Box::new(err)))?
+            .rows_affected();
+
+        if updated == 0 {
+            let current_version = sqlx::query_scalar::<_, i64>(statements::ANSWER_VERSION)
+                .bind(&answer_uuid)
+                .fetch_optional(&mut conn)
+                .await
+                .map_err(|err| DBError::Other(Box::new(err)))?;
+
+            return Err(missed_update(
+                Entity::Answer,
+                &answer_uuid,
+                current_version,
+                version,
+            ));
+        }
+
+        let result = sqlx::query_as::<_, AnswerRow>(statements::SELECT_ANSWER)
+            .bind(&answer_uuid)
+            .fetch_one(&mut conn)
+            .await
+            .map_err(|err| DBError::Other(Box::new(err)))?;
+
+        Ok(result.into())
+    }
+
+    async fn get_answers(&self, question_uuid: QuestionUuid) -> Result<Vec<AnswerDetail>, DBError> {
+        let question_uuid = question_uuid.to_string();
+
+        let mut conn = acquire(&self.db).await?;
+
+        let result = sqlx::query_as::<_, AnswerRow>(statements::SELECT_ANSWERS)
+            .bind(question_uuid)
+            .fetch_all(&mut conn)
+            .await
+            .map_err(|err| DBError::Other(Box::new(err)))?;
+
+        Ok(result.into_iter().map(AnswerDetail::from).collect())
+    }
+
+    async fn list_answers(
+        &self,
+        question_uuid: QuestionUuid,
+        include_archived: bool,
+        sort: AnswerSort,
+        window: Window,
+    ) -> Result<Listed<AnswerDetail>, DBError> {
+        let question_uuid = question_uuid.to_string();
+
+        let mut conn = acquire(&self.db).await?;
+
+        let items = sqlx::query_as::<_, AnswerRow>(&format!(
+            "SELECT * FROM {} ORDER BY {} LIMIT ? OFFSET ?",
+            statements::ANSWERS_AND_ARCHIVED,
+            statements::answer_order(sort)
+        ))
+        .bind(&question_uuid)
+        .bind(include_archived)
+        .bind(&question_uuid)
+        .bind(window.limit as i64)
+        .bind(window.offset as i64)
+        .fetch_all(&mut conn)
+        .await
+        .map_err(|err| DBError::Other(Box::new(err)))?;
+
+        let total: i64 = sqlx::query_scalar(&format!(
+            "SELECT COUNT(*) FROM {}",
+            statements::ANSWERS_AND_ARCHIVED
+        ))
+        .bind(&question_uuid)
+        .bind(include_archived)
+        .bind(&question_uuid)
+        .fetch_one(&mut conn)
+        .await
+        .map_err(|err| DBError::Other(Box::new(err)))?;
+
+        Ok(Listed {
+            items: items.into_iter().map(AnswerDetail::from).collect(),
+            total: total as u64,
+        })
+    }
+
+    fn stream_answers(&self, question_uuid: QuestionUuid) -> RowStream<'_, AnswerDetail> {
+        Box::pin(try_stream! {
+            let question_uuid = question_uuid.to_string();
+
+            let mut conn = acquire(&self.db).await?;
+
+            let mut rows = sqlx::query_as::<_, AnswerRow>(
+                statements::SELECT_ANSWERS,
+            )
+            .bind(question_uuid)
+            .fetch(&mut conn);
+
+            while let Some(row) = rows
+                .try_next()
+                .await
+                .map_err(|err| DBError::Other(Box::new(err)))?
+            {
+                yield row.into();
+            }
+        })
+    }
+
+    async fn get_answers_of_questions(
+        &self,
+        question_uuids: &[QuestionUuid],
+    ) -> Result<Vec<AnswerDetail>, DBError> {
+        if question_uuids.is_empty() {
+            return Ok(vec![]);
+        }
+        let sql = statements::select_answers_of_questions("answers", question_uuids.len());
+
+        let mut conn = acquire(&self.db).await?;
+
+        let mut query = sqlx::query_as::<_, AnswerRow>(&sql);
+        for question_uuid in question_uuids {
+            query = query.bind(question_uuid.to_string());
+        }
+        let result = query
+            .fetch_all(&mut conn)
+            .await
+            .map_err(|err| DBError::Other(Box::new(err)))?;
+
+        Ok(result.into_iter().map(AnswerDetail::from).collect())
+    }
+}
+
+pub struct SqliteArchiveDao {
+    db: SqlitePool,
+}
+
+impl SqliteArchiveDao {
+    pub fn new(db: SqlitePool) -> Self {
+        Self { db }
+    }
+
+    async fn move_inactive(&self, older_than_days: u32) -> Result<ArchiveSummary, sqlx::Error> {
+        let mut tx = self.db.begin().await?;
+
+        let questions = sqlx::query(
+            r#"
+                INSERT INTO questions_archive
+                    ( question_uuid, title, description, metadata, created_at, updated_at, last_activity_at, answer_count, version )
+                SELECT question_uuid, title, description, metadata, created_at, updated_at, last_activity_at, answer_count, version
+                FROM questions
+                WHERE updated_at < datetime('now', ?)
+                AND NOT EXISTS (
+                    SELECT 1 FROM answers
+                    WHERE answers.question_uuid = questions.question_uuid
+                    AND answers.updated_at >= datetime('now', ?)
+                )
+            "#,
+        )
+        .bind(format!("-{} days", older_than_days))
+        .bind(format!("-{} days", older_than_days))
+        .execute(&mut tx)
+        .await?
+        .rows_affected();
+
+        let answers = sqlx::query(statements::ARCHIVE_ANSWERS)
+            .execute(&mut tx)
+            .await?
+            .rows_affected();
+
+        sqlx::query(statements::DELETE_ARCHIVED_QUESTIONS)
+            .execute(&mut tx)
+            .await?;
+        tx.commit().await?;
+
+        Ok(ArchiveSummary { questions, answers })
+    }
+}
+
+#[async_trait]
+impl ArchiveDao for SqliteArchiveDao {
+    async fn archive(&self, older_than_days: u32) -> Result<ArchiveSummary, DBError> {
+        self.move_inactive(older_than_days)
+            .await
+            .map_err(|err| DBError::Other(Box::new(err)))
+    }
+
+    fn stream_archived_questions(&self, filter: QuestionFilter) -> RowStream<'_, QuestionDetail> {
+        matching_questions(&self.db, "questions_archive", filter)
+    }
+
+    fn stream_archived_answers(&self, question_uuid: QuestionUuid) -> RowStream<'_, AnswerDetail> {
+        Box::pin(try_stream! {
+            let mut conn = acquire(&self.db).await?;
+
+            let mut rows = sqlx::query_as::<_, AnswerRow>(statements::SELECT_ARCHIVED_ANSWERS)
+                .bind(question_uuid.to_string())
+                .fetch(&mut conn);
+
+            while let Some(row) = rows
+                .try_next()
+                .await
+                .map_err(|err| DBError::Other(Box::new(err)))?
+            {
+                yield row.into();
+            }
+        })
+    }
+
+    async fn get_archived_answers_of_questions(
+        &self,
+        question_uuids: &[QuestionUuid],
+    ) -> Result<Vec<AnswerDetail>, DBError> {
+        if question_uuids.is_empty() {
+            return Ok(vec![]);
+        }
+        let sql = statements::select_answers_of_questions("answers_archive", question_uuids.len());
+
+        let mut conn = acquire(&self.db).await?;
+
+        let mut query = sqlx::query_as::<_, AnswerRow>(&sql);
+        for question_uuid in question_uuids {
+            query = query.bind(question_uuid.to_string());
+        }
+        let result = query
+            .fetch_all(&mut conn)
+            .await
+            .map_err(|err| DBError::Other(Box::new(err)))?;
+
+        Ok(result.into_iter().map(AnswerDetail::from).collect())
+    }
+}
+
+pub struct SqliteStatsDao {
+    db: SqlitePool,
+}
+
+impl SqliteStatsDao {
+    pub fn new(db: SqlitePool) -> Self {
+        Self { db }
+    }
+}
+
+#[async_trait]
+impl StatsDao for SqliteStatsDao {
+    async fn get_daily_stats(&self) -> Result<Vec<DailyStats>, DBError> {
+        let mut conn = acquire(&self.db).await?;
+
+        let result = sqlx::query_as::<_, (String, i64, i64)>(
+            "SELECT day, questions, answers FROM daily_stats ORDER BY day",
+        )
+        .fetch_all(&mut conn)
+        .await
+        .map_err(|err| DBError::Other(Box::new(err)))?;
+
+        Ok(result
+            .into_iter()
+            .map(|(day, questions, answers)| DailyStats {
+                day,
+                questions,
+                answers,
+            })
+            .collect())
+    }
+
+    async fn get_response_stats(&self, since: NaiveDate) -> Result<ResponseStats, DBError> {
+        let mut conn = acquire(&self.db).await?;
+        let since = since.to_string();
+
+        // The median is the middle one of the ordered times, or the mean of the middle two.
+        let (questions, answered, median_first_answer_secs) =
+            sqlx::query_as::<_, (i64, i64, Option<f64>)>(
+                r#"
+                    SELECT
+                        (SELECT CAST(COALESCE(SUM(questions), 0) AS INTEGER) FROM daily_stats WHERE day >= ?),
+                        (SELECT COUNT(*) FROM first_answers WHERE day >= ?),
+                        (
+                            SELECT AVG(seconds)
+                            FROM (
+                                SELECT seconds,
+                                    ROW_NUMBER() OVER (ORDER BY seconds) AS position,
+                                    COUNT(*) OVER () AS n
+                                FROM first_answers
+                                WHERE day >= ?
+                            ) AS ordered
+                            WHERE position IN ((n + 1) / 2, (n + 2) / 2)
+                        )
+                "#,
+            )
+            .bind(&since)
+            .bind(&since)
+            .bind(&since)
+            .fetch_one(&mut conn)
+            .await
+            .map_err(|err| DBError::Other(Box::new(err)))?;
+
+        // There is no user directory on this backend.
+        Ok(ResponseStats {
+            questions,
+            answered,
+            median_first_answer_secs,
+            active_users: None,
+        })
+    }
+
+    // The view is not materialized here.
+    async fn refresh(&self) -> Result<(), DBError> {
+        Ok(())
+    }
+}
+
+// The column cannot be made nullable without rebuilding `questions`, which would
+// cascade to the answers, so a missing description is stored as ''.
+fn stored_description(question: &Question) -> &str {
+    question.description.as_deref().unwrap_or_default()
+}
+
+// Shared by the DAOs and the unit of work.
+async fn insert_question(
+    conn: &mut SqliteConnection,
+    question: Question,
+) -> Result<QuestionDetail, DBError> {
+    let result = sqlx::query_as::<_, QuestionRow>(
+        r#"
+            INSERT INTO questions ( question_uuid, title, description, metadata, updated_at, last_activity_at )
+            VALUES ( ?, ?, ?, ?, CURRENT_TIMESTAMP, CURRENT_TIMESTAMP )
+            RETURNING question_uuid, title, description, metadata, created_at, updated_at, last_activity_at, answer_count, version
+        "#,
+    )
+    .bind(Uuid::new_v4().to_string())
+    .bind(&question.title)
+    .bind(stored_description(&question))
+            .bind(Json(&question.metadata))
+    .fetch_one(&mut *conn)
+    .await
+    .map_err(|err| DBError::Other(Box::new(err)))?;
+
+    Ok(result.into())
+}
+
+async fn insert_answer(
+    conn: &mut SqliteConnection,
+    answer: Answer,
+) -> Result<AnswerDetail, DBError> {
+    let question_uuid = answer.question_uuid.to_string();
+    let answer_uuid = Uuid::new_v4().to_string();
+
+    // A failed foreign key check only reports its extended code without RETURNING.
+    sqlx::query(
+        r#"
+            INSERT INTO answers ( answer_uuid, question_uuid, content, updated_at )
+            VALUES ( ?, ?, ?, CURRENT_TIMESTAMP )
+        "#,
+    )
+    .bind(&answer_uuid)
+    .bind(question_uuid)
+    .bind(&answer.content)
+    .execute(&mut *conn)
+    .await
+    .map_err(|err| match err {
+        sqlx::Error::Database(err)
+            if err.code().as_deref() == Some(sqlite_error_code::FOREIGN_KEY_VIOLATION) =>
+        {
+            DBError::NotFound(Entity::Question, err.to_string())
+        }
+        err => DBError::Other(Box::new(err)),
+    })?;
+
+    let result = sqlx::query_as::<_, AnswerRow>(statements::SELECT_ANSWER)
+        .bind(&answer_uuid)
+        .fetch_one(&mut *conn)
+        .await
+        .map_err(|err| DBError::Other(Box::new(err)))?;
+
+    Ok(result.into())
+}
+
+pub struct SqliteUnitOfWorkFactory {
+    db: SqlitePool,
+}
+
+impl SqliteUnitOfWorkFactory {
+    pub fn new(db: SqlitePool) -> Self {
+        Self { db }
+    }
+}
+
+#[async_trait]
+impl UnitOfWorkFactory for SqliteUnitOfWorkFactory {
+    async fn begin(&self) -> Result<Box<dyn UnitOfWork>, DBError> {
+        let tx = self
+            .db
+            .begin()
+            .await
+            .map_err(|err| DBError::Other(Box::new(err)))?;
+
+        Ok(Box::new(SqliteUnitOfWork { tx }))
+    }
+}
+
+pub struct SqliteUnitOfWork {
+    tx: Transaction<'static, Sqlite>,
+}
+
+#[async_trait]
+impl UnitOfWork for SqliteUnitOfWork {
+    async fn create_question(&mut self, question: Question) -> Result<QuestionDetail, DBError> {
+        insert_question(&mut self.tx, question).await
+    }
+
+    async fn create_answer(&mut self, answer: Answer) -> Result<AnswerDetail, DBError> {
+        insert_answer(&mut self.tx, answer).await
+    }
+
+    async fn commit(self: Box<Self>) -> Result<(), DBError> {
+        self.tx
+            .commit()
+            .await
+            .map_err(|err| DBError::Other(Box::new(err)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{empty_metadata, DateRange, MetadataFilter};
+    use serde_json::json;
+
+    async fn pool() -> SqlitePool {
+        // Every in-memory connection is its own database, so keep a single one.
+        let options = SqliteConnectOptions::from_str("sqlite::memory:").unwrap();
+        let pool = SqlitePoolOptions::new()
+            .max_connections(1)
+            .connect_with(options)
+            .await
+            .unwrap();
+        MIGRATOR.run(&pool).await.unwrap();
+        pool
+    }
+
+    fn question() -> Question {
+        Question {
+            title: "title".to_owned(),
+            description: Some("description".to_owned()),
+            metadata: empty_metadata(),
+        }
+    }
+
+    #[tokio::test]
+    async fn questions_should_round_trip() {
+        let dao = SqliteQuestionDao::new(pool().await);
+
+        let created = dao.create_question(question()).await.unwrap();
+        assert_eq!(dao.get_questions().await.unwrap(), vec![created.clone()]);
+
+        dao.delete_question(created.question_uuid).await.unwrap();
+        assert_eq!(dao.get_questions().await.unwrap(), vec![]);
+    }
+
+    #[tokio::test]
+    async fn missing_descriptions_should_read_back_as_missing() {
+        let dao = SqliteQuestionDao::new(pool().await);
+
+        let created = dao
+            .create_question(Question {
+                title: "title".to_owned(),
+                description: None,
+                metadata: empty_metadata(),
+            })
+            .await
+            .unwrap();
+
+        assert_eq!(created.description, None);
+        assert_eq!(dao.get_questions().await.unwrap(), vec![created]);
+    }
+
+    #[tokio::test]
+    async fn metadata_filter_should_match_string_values() {
+        let dao = SqliteQuestionDao::new(pool().await);
+        let mut created = vec![];
+        for metadata in [
+            json!({ "source": "zendesk", "ticket": "42", "a.b": "c" }),
+            json!({ "source": "zendesk", "ticket": 42 }),
+            json!({ "source": "jira" }),
+            empty_metadata(),
+        ] {
+            let question = Question {
+                metadata,
+                ..question()
+            };
+            created.push(dao.create_question(question).await.unwrap());
+        }
+
+        let filter = QuestionFilter {
+            metadata: MetadataFilter(
+                [("source", "zendesk"), ("ticket", "42"), ("a.b", "c")]
+                    .map(|(key, value)| (key.to_owned(), value.to_owned()))
+                    .into(),
+            ),
+            ..QuestionFilter::default()
+        };
+        let found = dao.get_questions_matching(&filter).await.unwrap();
+
+        assert_eq!(found, vec![created[0].clone()]);
+    }
+
+    #[tokio::test]
+    async fn created_range_should_count_fractions_of_a_second() {
+        let pool = pool().await;
+        let dao = SqliteQuestionDao::new(pool.clone());
+        let created = dao.create_question(question()).await.unwrap();
+        sqlx::query("UPDATE questions SET created_at = '2023-10-01 22:00:00.500'")
+            .execute(&pool)
+            .await
+            .unwrap();
+        let range = |after: &str, before: &str| QuestionFilter {
+            created: DateRange {
+                after: Some(after.parse().unwrap()),
+                before: Some(before.parse().unwrap()),
+            },
+            ..QuestionFilter::default()
+        };
+
+        let found = dao
+            .get_questions_matching(&range("2023-10-01T22:00:00.5Z", "2023-10-02T00:00:00Z"))
+            .await
+            .unwrap();
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].question_uuid, created.question_uuid);
+
+        let found = dao
+            .get_questions_matching(&range("2023-10-01T21:00:00Z", "2023-10-01T22:00:00.5Z"))
+            .await
+            .unwrap();
+        assert_eq!(found, vec![]);
+    }
+
+    #[tokio::test]
+    async fn get_answers_of_questions_should_only_return_theirs() {
+        let pool = pool().await;
+        let question_dao = SqliteQuestionDao::new(pool.clone());
+        let dao = SqliteAnswerDao::new(pool);
+        let mut answers = vec![];
+        for content in ["first", "second", "third"] {
+            let question = question_dao.create_question(question()).await.unwrap();
+            let answer = dao
+                .create_answer(Answer {
+                    question_uuid: question.question_uuid,
+                    content: content.to_owned(),
+                })
+                .await
+                .unwrap();
+            answers.push(answer);
+        }
+
+        let mut found = dao
+            .get_answers_of_questions(&[answers[0].question_uuid, answers[2].question_uuid])
+            .await
+            .unwrap();
+        found.sort_by(|a, b| a.content.cmp(&b.content));
+
+        assert_eq!(found, vec![answers[0].clone(), answers[2].clone()]);
+        assert_eq!(dao.get_answers_of_questions(&[]).await.unwrap(), vec![]);
+    }
+
+    #[tokio::test]
+    async fn answers_should_be_deleted_with_their_question() {
+        let pool = pool().await;
+        let question_dao = SqliteQuestionDao::new(pool.clone());
+        let dao = SqliteAnswerDao::new(pool);
+
+        let question = question_dao.create_question(question()).await.unwrap();
+        let answer = dao
+            .create_answer(Answer {
+                question_uuid: question.question_uuid,
+                content: "content".to_owned(),
+            })
+            .await
+            .unwrap();
+        assert_eq!(
+            dao.get_answers(question.question_uuid).await.unwrap(),
+            vec![answer]
+        );
+
+        question_dao
+            .delete_question(question.question_uuid)
+            .await
+            .unwrap();
+        assert_eq!(
+            dao.get_answers(question.question_uuid).await.unwrap(),
+            vec![]
+        );
+    }
+
+    #[tokio::test]
+    async fn delete_question_should_be_blocked_by_answers_when_restricted() {
+        let pool = pool().await;
+        let question_dao =
+            SqliteQuestionDao::new(pool.clone()).with_deletion(QuestionDeletion::Restrict);
+        let dao = SqliteAnswerDao::new(pool);
+
+        let question = question_dao.create_question(question()).await.unwrap();
+        let answer = dao
+            .create_answer(Answer {
+                question_uuid: question.question_uuid,
+                content: "content".to_owned(),
+            })
+            .await
+            .unwrap();
+
+        let result = question_dao.delete_question(question.question_uuid).await;
+        assert!(matches!(result, Err(DBError::Conflict(_))));
+
+        dao.delete_answer(answer.answer_uuid).await.unwrap();
+        question_dao
+            .delete_question(question.question_uuid)
+            .await
+            .unwrap();
+        assert_eq!(question_dao.get_questions().await.unwrap(), vec![]);
+    }
+
+    #[tokio::test]
+    async fn answer_count_should_follow_answer_writes() {
+        let pool = pool().await;
+        let question_dao = SqliteQuestionDao::new(pool.clone());
+        let dao = SqliteAnswerDao::new(pool);
+
+        let question = question_dao.create_question(question()).await.unwrap();
+        let answer = dao
+            .create_answer(Answer {
+                question_uuid: question.question_uuid,
+                content: "content".to_owned(),
+            })
+            .await
+            .unwrap();
+        assert_eq!(
+            question_dao.get_questions().await.unwrap()[0].answer_count,
+            1
+        );
+
+        dao.delete_answer(answer.answer_uuid).await.unwrap();
+        assert_eq!(
+            question_dao.get_questions().await.unwrap()[0].answer_count,
+            0
+        );
+    }
+
+    #[tokio::test]
+    async fn last_activity_at_should_follow_every_write() {
+        let pool = pool().await;
+        let question_dao = SqliteQuestionDao::new(pool.clone());
+        let dao = SqliteAnswerDao::new(pool.clone());
+        let asked = question_dao.create_question(question()).await.unwrap();
+        assert_eq!(asked.last_activity_at, asked.created_at);
+        // Rewinds the question's activity, runs a write, then checks it moved again.
+        let rewind = || async {
+            sqlx::query("UPDATE questions SET last_activity_at = '2000-01-01 00:00:00'")
+                .execute(&pool)
+                .await
+                .unwrap();
+        };
+        let moved = || async {
+            question_dao.get_questions().await.unwrap()[0].last_activity_at >= asked.created_at
+        };
+
+        rewind().await;
+        let answer = dao
+            .create_answer(Answer {
+                question_uuid: asked.question_uuid,
+                content: "content".to_owned(),
+            })
+            .await
+            .unwrap();
+        assert!(moved().await, "creating an answer");
+
+        rewind().await;
+        let answer = dao
+            .update_answer(answer.answer_uuid, "edited".to_owned(), answer.version)
+            .await
+            .unwrap();
+        assert!(moved().await, "editing an answer");
+
+        rewind().await;
+        dao.delete_answer(answer.answer_uuid).await.unwrap();
+        assert!(moved().await, "deleting an answer");
+
+        rewind().await;
+        question_dao
+            .update_question(
+                asked.question_uuid,
+                Question {
+                    title: "edited".to_owned(),
+                    ..question()
+                },
+                asked.version,
+            )
+            .await
+            .unwrap();
+        assert!(moved().await, "editing the question");
+    }
+
+    #[tokio::test]
+    async fn create_answer_should_fail_for_unknown_question() {
+        let dao = SqliteAnswerDao::new(pool().await);
+
+        let result = dao
+            .create_answer(Answer {
+                question_uuid: QuestionUuid::new_v4(),
+                content: "content".to_owned(),
+            })
+            .await;
+
+        assert!(matches!(
+            result,
+            Err(DBError::NotFound(Entity::Question, _))
+        ));
+    }
+
+    #[tokio::test]
+    async fn unit_of_work_should_roll_back_when_not_committed() {
+        let pool = pool().await;
+        let question_dao = SqliteQuestionDao::new(pool.clone());
+        let factory = SqliteUnitOfWorkFactory::new(pool);
+
+        let mut work = factory.begin().await.unwrap();
+        work.create_question(question()).await.unwrap();
+        drop(work);
+        assert_eq!(question_dao.get_questions().await.unwrap(), vec![]);
+
+        let mut work = factory.begin().await.unwrap();
+        let created = work.create_question(question()).await.unwrap();
+        work.commit().await.unwrap();
+        assert_eq!(question_dao.get_questions().await.unwrap(), vec![created]);
+    }
+
+    #[tokio::test]
+    async fn update_question_should_reject_stale_versions() {
+        let dao = SqliteQuestionDao::new(pool().await);
+
+        let created = dao.create_question(question()).await.unwrap();
+        let updated = dao
+            .update_question(created.question_uuid, question(), created.version)
+            .await
+            .unwrap();
+        assert_eq!(updated.version, created.version + 1);
+        // Nothing changed, so this is not an edit.
+        assert_eq!(updated.updated_at, created.updated_at);
+
+        let stale = dao
+            .update_question(created.question_uuid, question(), created.version)
+            .await;
+        assert!(matches!(stale, Err(DBError::Conflict(_))));
+    }
+
+    #[tokio::test]
+    async fn archive_should_move_inactive_questions_with_their_answers() {
+        let pool = pool().await;
+        let question_dao = SqliteQuestionDao::new(pool.clone());
+        let answer_dao = SqliteAnswerDao::new(pool.clone());
+        let dao = SqliteArchiveDao::new(pool.clone());
+
+        let inactive = question_dao.create_question(question()).await.unwrap();
+        answer_dao
+            .create_answer(Answer {
+                question_uuid: inactive.question_uuid,
+                content: "content".to_owned(),
+            })
+            .await
+            .unwrap();
+        let active = question_dao.create_question(question()).await.unwrap();
+        for table in ["questions", "answers"] {
+            sqlx::query(&format!(
+                "UPDATE {} SET updated_at = datetime(updated_at, '-100 days') WHERE question_uuid = ?",
+                table
+            ))
+            .bind(inactive.question_uuid.to_string())
+            .execute(&pool)
+            .await
+            .unwrap();
+        }
+
+        let summary = dao.archive(30).await.unwrap();
+
+        assert_eq!(
+            summary,
+            ArchiveSummary {
+                questions: 1,
+                answers: 1
+            }
+        );
+        assert_eq!(question_dao.get_questions().await.unwrap(), vec![active]);
+        let archived: Vec<_> = dao
+            .stream_archived_questions(QuestionFilter::default())
+            .try_collect()
+            .await
+            .unwrap();
+        assert_eq!(archived.len(), 1);
+        assert_eq!(archived[0].question_uuid, inactive.question_uuid);
+        let answers: Vec<_> = dao
+            .stream_archived_answers(inactive.question_uuid)
+            .try_collect()
+            .await
+            .unwrap();
+        assert_eq!(answers.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn list_questions_should_page_archived_questions_too() {
+        let pool = pool().await;
+        let question_dao = SqliteQuestionDao::new(pool.clone());
+        let answer_dao = SqliteAnswerDao::new(pool.clone());
+
+        let archived = question_dao.create_question(question()).await.unwrap();
+        answer_dao
+            .create_answer(Answer {
+                question_uuid: archived.question_uuid,
+                content: "content".to_owned(),
+            })
+            .await
+            .unwrap();
+        for table in ["questions", "answers"] {
+            sqlx::query(&format!(
+                "UPDATE {} SET updated_at = datetime(updated_at, '-100 days')",
+                table
+            ))
+            .execute(&pool)
+            .await
+            .unwrap();
+        }
+        SqliteArchiveDao::new(pool.clone())
+            .archive(30)
+            .await
+            .unwrap();
+        let live = question_dao.create_question(question()).await.unwrap();
+        let filter = QuestionFilter::default();
+        let list = |include_archived| {
+            question_dao.list_questions(
+                &filter,
+                include_archived,
+                QuestionSort::Updated,
+                Window {
+                    offset: 0,
+                    limit: 1,
+                },
+            )
+        };
+
+        let listed = list(true).await.unwrap();
+        assert_eq!((listed.items, listed.total), (vec![live.clone()], 2));
+        let listed = list(false).await.unwrap();
+        assert_eq!((listed.items, listed.total), (vec![live], 1));
+
+        let answers = answer_dao
+            .list_answers(
+                archived.question_uuid,
+                true,
+                AnswerSort::Oldest,
+                Window {
+                    offset: 0,
+                    limit: 10,
+                },
+            )
+            .await
+            .unwrap();
+        assert_eq!(answers.total, 1);
+    }
+
+    #[tokio::test]
+    async fn daily_stats_should_count_questions_and_answers() {
+        let pool = pool().await;
+        let question = SqliteQuestionDao::new(pool.clone())
+            .create_question(question())
+            .await
+            .unwrap();
+        SqliteAnswerDao::new(pool.clone())
+            .create_answer(Answer {
+                question_uuid: question.question_uuid,
+                content: "content".to_owned(),
+            })
+            .await
+            .unwrap();
+
+        let dao = SqliteStatsDao::new(pool);
+        let stats = dao.get_daily_stats().await.unwrap();
+
+        assert_eq!(
+            stats,
+            vec![DailyStats {
+                day: question.created_at.date_naive().to_string(),
+                questions: 1,
+                answers: 1
+            }]
+        );
+        let response = dao
+            .get_response_stats(question.created_at.date_naive())
+            .await
+            .unwrap();
+        assert_eq!((response.questions, response.answered), (1, 1));
+        assert!(response.median_first_answer_secs.unwrap() >= 0.0);
+        assert_eq!(response.active_users, None);
+    }
+}
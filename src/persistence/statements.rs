@@ -0,0 +1,133 @@
+use crate::models::{AnswerSort, QuestionSort};
+
+// SQL the SQLite and MySQL backends share. Both take `?` placeholders and store
+// UUIDs as text, so only inserts, archiving, stats and metadata filters differ between
+// them; those stay in their modules. Postgres keeps its compile-time checked `query!` calls.
+
+pub const SELECT_QUESTION: &str = r#"
+    SELECT question_uuid, title, description, metadata, created_at, updated_at, last_activity_at, answer_count, version
+    FROM questions
+    WHERE question_uuid = ?
+"#;
+
+pub const SELECT_QUESTIONS: &str =
+    "SELECT question_uuid, title, description, metadata, created_at, updated_at, last_activity_at, answer_count, version FROM questions";
+
+// Live questions, then archived ones when the bound flag is set, to filter, order and
+// page as one list.
+pub const QUESTIONS_AND_ARCHIVED: &str = r#"
+    (
+        SELECT question_uuid, title, description, metadata, created_at, updated_at, last_activity_at, answer_count, version
+        FROM questions
+        UNION ALL
+        SELECT question_uuid, title, description, metadata, created_at, updated_at, last_activity_at, answer_count, version
+        FROM questions_archive
+        WHERE ?
+    ) AS questions
+"#;
+
+// Ties are broken by UUID, as Postgres does, so pages do not overlap.
+pub fn question_order(sort: QuestionSort) -> &'static str {
+    match sort {
+        QuestionSort::Oldest => "created_at, question_uuid",
+        QuestionSort::Newest => "created_at DESC, question_uuid DESC",
+        QuestionSort::Updated => "updated_at DESC, question_uuid DESC",
+        QuestionSort::Answers => "answer_count DESC, question_uuid",
+        QuestionSort::Activity => "last_activity_at DESC, question_uuid DESC",
+    }
+}
+
+// Binds the limit, then the offset.
+pub const UNANSWERED_QUESTIONS: &str = r#"
+    SELECT question_uuid, title, description, metadata, created_at, updated_at, last_activity_at, answer_count, version
+    FROM questions
+    WHERE answer_count = 0
+    ORDER BY created_at, question_uuid
+    LIMIT ? OFFSET ?
+"#;
+
+pub const UNANSWERED_QUESTION_COUNT: &str = "SELECT COUNT(*) FROM questions WHERE answer_count = 0";
+
+pub const UPDATE_QUESTION: &str = r#"
+    UPDATE questions
+    SET title = ?, description = ?, metadata = ?, version = version + 1
+    WHERE question_uuid = ? AND version = ?
+"#;
+
+pub const QUESTION_VERSION: &str = "SELECT version FROM questions WHERE question_uuid = ?";
+
+// Binds the UUID, then whether answered questions must be kept.
+pub const DELETE_QUESTION: &str =
+    "DELETE FROM questions WHERE question_uuid = ? AND (NOT ? OR answer_count = 0)";
+
+pub const QUESTION_ANSWER_COUNT: &str =
+    "SELECT answer_count FROM questions WHERE question_uuid = ?";
+
+pub const SELECT_ANSWER: &str = r#"
+    SELECT answer_uuid, question_uuid, content, created_at, updated_at, version
+    FROM answers
+    WHERE answer_uuid = ?
+"#;
+
+pub const SELECT_ANSWERS: &str = r#"
+    SELECT answer_uuid, question_uuid, content, created_at, updated_at, version
+    FROM answers
+    WHERE question_uuid = ?
+"#;
+
+// The answers of a question, then its archived ones when the flag is set. Binds the
+// UUID, the flag and the UUID again.
+pub const ANSWERS_AND_ARCHIVED: &str = r#"
+    (
+        SELECT answer_uuid, question_uuid, content, created_at, updated_at, version
+        FROM answers
+        WHERE question_uuid = ?
+        UNION ALL
+        SELECT answer_uuid, question_uuid, content, created_at, updated_at, version
+        FROM answers_archive
+        WHERE ? AND question_uuid = ?
+    ) AS answers
+"#;
+
+pub fn answer_order(sort: AnswerSort) -> &'static str {
+    match sort {
+        AnswerSort::Oldest => "created_at, answer_uuid",
+        AnswerSort::Newest => "created_at DESC, answer_uuid DESC",
+        AnswerSort::Updated => "updated_at DESC, answer_uuid DESC",
+    }
+}
+
+pub const UPDATE_ANSWER: &str =
+    "UPDATE answers SET content = ?, version = version + 1 WHERE answer_uuid = ? AND version = ?";
+
+pub const ANSWER_VERSION: &str = "SELECT version FROM answers WHERE answer_uuid = ?";
+
+pub const DELETE_ANSWER: &str = "DELETE FROM answers WHERE answer_uuid = ?";
+
+pub const SELECT_ARCHIVED_ANSWERS: &str = r#"
+    SELECT answer_uuid, question_uuid, content, created_at, updated_at, version
+    FROM answers_archive
+    WHERE question_uuid = ?
+"#;
+
+// The answers in `table` of `count` questions, taking one `?` per question.
+pub fn select_answers_of_questions(table: &str, count: usize) -> String {
+    format!(
+        "SELECT answer_uuid, question_uuid, content, created_at, updated_at, version FROM {} WHERE question_uuid IN ({})",
+        table,
+        vec!["?"; count].join(", ")
+    )
+}
+
+// Run after the questions were copied: live and archived questions are disjoint, so
+// the ones in both were just copied.
+pub const ARCHIVE_ANSWERS: &str = r#"
+    INSERT INTO answers_archive
+        ( answer_uuid, question_uuid, content, created_at, updated_at, version )
+    SELECT answer_uuid, question_uuid, content, created_at, updated_at, version
+    FROM answers
+    WHERE question_uuid IN ( SELECT question_uuid FROM questions_archive )
+"#;
+
+pub const DELETE_ARCHIVED_QUESTIONS: &str =
+    "DELETE FROM questions WHERE question_uuid IN ( SELECT question_uuid FROM questions_archive )";
@@ -0,0 +1,187 @@
+use std::{sync::Arc, time::Duration};
+
+use async_trait::async_trait;
+use chrono::NaiveDate;
+use log::warn;
+use sqlx::PgPool;
+use tokio::task::JoinHandle;
+
+use super::{
+    acquire,
+    locks::{self, Lock},
+};
+use crate::{
+    config::LiveConfig,
+    models::{DBError, DailyStats, ResponseStats},
+};
+
+// Reads the `daily_stats` and `first_answers` views. Only Postgres materializes them; on
+// the other backends they are computed on every read and refreshing does nothing.
+#[async_trait]
+pub trait StatsDao {
+    async fn get_daily_stats(&self) -> Result<Vec<DailyStats>, DBError>;
+    // Over the questions asked on `since` or later.
+    async fn get_response_stats(&self, since: NaiveDate) -> Result<ResponseStats, DBError>;
+    async fn refresh(&self) -> Result<(), DBError>;
+}
+
+pub struct PgStatsDao {
+    db: PgPool,
+}
+
+impl PgStatsDao {
+    pub fn new(db: PgPool) -> Self {
+        Self { db }
+    }
+}
+
+#[async_trait]
+impl StatsDao for PgStatsDao {
+    async fn get_daily_stats(&self) -> Result<Vec<DailyStats>, DBError> {
+        let mut conn = acquire(&self.db).await?;
+
+        let result = sqlx::query!(
+            r#"
+                SELECT day::TEXT AS "day!", questions AS "questions!", answers AS "answers!"
+                FROM daily_stats
+                ORDER BY day
+            "#
+        )
+        .fetch_all(&mut conn)
+        .await
+        .map_err(|err| DBError::Other(Box::new(err)))?;
+
+        Ok(result
+            .into_iter()
+            .map(|val| DailyStats {
+                day: val.day,
+                questions: val.questions,
+                answers: val.answers,
+            })
+            .collect())
+    }
+
+    async fn get_response_stats(&self, since: NaiveDate) -> Result<ResponseStats, DBError> {
+        let mut conn = acquire(&self.db).await?;
+
+        let result = sqlx::query!(
+            r#"
+                SELECT
+                    (SELECT COALESCE(SUM(questions), 0)::BIGINT FROM daily_stats WHERE day >= $1)
+                        AS "questions!",
+                    COUNT(*) AS "answered!",
+                    PERCENTILE_CONT(0.5) WITHIN GROUP (ORDER BY seconds) AS median_first_answer_secs,
+                    (SELECT COUNT(*) FROM users WHERE active) AS "active_users!"
+                FROM first_answers
+                WHERE day >= $1
+            "#,
+            since
+        )
+        .fetch_one(&mut conn)
+        .await
+        .map_err(|err| DBError::Other(Box::new(err)))?;
+
+        Ok(ResponseStats {
+            questions: result.questions,
+            answered: result.answered,
+            median_first_answer_secs: result.median_first_answer_secs,
+            active_users: Some(result.active_users),
+        })
+    }
+
+    async fn refresh(&self) -> Result<(), DBError> {
+        async {
+            let mut tx = self.db.begin().await?;
+            // Another instance is refreshing, which does the same.
+            if !locks::try_lock(&mut tx, Lock::StatsRefresh).await? {
+                return Ok(());
+            }
+
+            sqlx::query("REFRESH MATERIALIZED VIEW CONCURRENTLY daily_stats")
+                .execute(&mut tx)
+                .await?;
+            sqlx::query("REFRESH MATERIALIZED VIEW CONCURRENTLY first_answers")
+                .execute(&mut tx)
+                .await?;
+            tx.commit().await
+        }
+        .await
+        .map_err(|err: sqlx::Error| DBError::Other(Box::new(err)))
+    }
+}
+
+// Refreshes periodically; `STATS_REFRESH_SECS=0` pauses it. Follows config reloads.
+pub fn spawn_stats_refresher(
+    stats_dao: Arc<dyn StatsDao + Send + Sync>,
+    config: LiveConfig,
+) -> JoinHandle<()> {
+    tokio::spawn(async move {
+        loop {
+            let interval = config.load().stats_refresh_interval;
+            if interval.is_zero() {
+                tokio::time::sleep(Duration::from_secs(1)).await;
+                continue;
+            }
+            tokio::time::sleep(interval).await;
+
+            if let Err(err) = stats_dao.refresh().await {
+                warn!("Refreshing stats failed: {:?}", err);
+            }
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        models::{empty_metadata, Answer, Question},
+        persistence::{
+            answer_dao::{AnswerDao, AnswerDaoImpl},
+            question_dao::{QuestionDao, QuestionDaoImpl},
+        },
+    };
+
+    #[sqlx::test]
+    async fn refresh_should_count_new_rows(pool: PgPool) -> Result<(), String> {
+        let dao = PgStatsDao::new(pool.clone());
+        let question = QuestionDaoImpl::new(pool.clone())
+            .create_question(Question {
+                title: "title".to_owned(),
+                description: Some("description".to_owned()),
+                metadata: empty_metadata(),
+            })
+            .await
+            .map_err(|err| err.to_string())?;
+        AnswerDaoImpl::new(pool)
+            .create_answer(Answer {
+                question_uuid: question.question_uuid,
+                content: "content".to_owned(),
+            })
+            .await
+            .map_err(|err| err.to_string())?;
+
+        let stale = dao.get_daily_stats().await.map_err(|err| err.to_string())?;
+        assert_eq!(stale, vec![]);
+
+        dao.refresh().await.map_err(|err| err.to_string())?;
+        let stats = dao.get_daily_stats().await.map_err(|err| err.to_string())?;
+        assert_eq!(stats.len(), 1);
+        assert_eq!((stats[0].questions, stats[0].answers), (1, 1));
+
+        let since = question.created_at.date_naive();
+        let response = dao
+            .get_response_stats(since)
+            .await
+            .map_err(|err| err.to_string())?;
+        assert_eq!((response.questions, response.answered), (1, 1));
+        assert!(response.median_first_answer_secs.unwrap() >= 0.0);
+        assert_eq!(response.active_users, Some(0));
+        let later = dao
+            .get_response_stats(since.succ_opt().unwrap())
+            .await
+            .map_err(|err| err.to_string())?;
+        assert_eq!((later.questions, later.median_first_answer_secs), (0, None));
+        Ok(())
+    }
+}
@@ -0,0 +1,593 @@
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use sqlx::{types::chrono::NaiveDateTime, PgPool};
+
+use super::acquire;
+use crate::models::{
+    postgres_error_code, DBError, Digest, DigestAnswer, DigestFrequency, DueDigest, Entity,
+    PushSubscription, QuestionUuid, Subscribers, TrendingQuestion,
+};
+
+// Who hears about new answers. There are no user accounts, so a subscriber is an email
+// address, and opting out stops every email to that address whatever it subscribed to.
+// Addresses are stored as the caller passes them; the handlers trim and lowercase them.
+// Browsers subscribe to pushes the same way, identified by their push endpoint.
+#[async_trait]
+pub trait SubscriptionDao {
+    // Fails with `DBError::NotFound` for `Entity::Question` if the question does not exist.
+    // Subscribing again changes nothing.
+    async fn subscribe(&self, question_uuid: QuestionUuid, email: String) -> Result<(), DBError>;
+    // Succeeds for unknown subscriptions.
+    async fn unsubscribe(&self, question_uuid: QuestionUuid, email: String) -> Result<(), DBError>;
+    // Subscriptions are kept while opted out, so opting back in restores them.
+    async fn set_opted_out(&self, email: String, opted_out: bool) -> Result<(), DBError>;
+    // The question's subscribers who did not opt out and the push subscriptions
+    // following it, oldest first; none for an unknown question.
+    async fn subscribers(
+        &self,
+        question_uuid: QuestionUuid,
+    ) -> Result<Option<Subscribers>, DBError>;
+    // Saves the subscription under its endpoint, following exactly `question_uuids`.
+    // Fails with `DBError::NotFound` for `Entity::Question`, changing nothing, if one of
+    // them does not exist.
+    async fn save_push_subscription(
+        &self,
+        subscription: PushSubscription,
+        question_uuids: Vec<QuestionUuid>,
+    ) -> Result<(), DBError>;
+    // Succeeds for unknown endpoints.
+    async fn delete_push_subscription(&self, endpoint: String) -> Result<(), DBError>;
+    // None stops the address's digests. The first digest of a new address covers what
+    // comes after this call; changing the frequency keeps the current period's start.
+    async fn set_digest_frequency(
+        &self,
+        email: String,
+        frequency: Option<DigestFrequency>,
+    ) -> Result<(), DBError>;
+    // Marks every digest whose period had passed by `now` as sent at `now` and returns
+    // them, leaving out opted-out addresses. Instances claiming at the same time never
+    // get the same digest.
+    async fn claim_due_digests(&self, now: DateTime<Utc>) -> Result<Vec<DueDigest>, DBError>;
+    // At most `trending_limit` trending questions, most answered first.
+    async fn digest(&self, due: &DueDigest, trending_limit: u32) -> Result<Digest, DBError>;
+}
+
+// TIMESTAMP columns hold UTC.
+fn timestamp(at: DateTime<Utc>) -> NaiveDateTime {
+    at.naive_utc()
+}
+
+pub struct PgSubscriptionDao {
+    db: PgPool,
+}
+
+impl PgSubscriptionDao {
+    pub fn new(db: PgPool) -> Self {
+        Self { db }
+    }
+}
+
+#[async_trait]
+impl SubscriptionDao for PgSubscriptionDao {
+    async fn subscribe(&self, question_uuid: QuestionUuid, email: String) -> Result<(), DBError> {
+        let mut conn = acquire(&self.db).await?;
+
+        sqlx::query!(
+            r#"
+                INSERT INTO question_subscriptions ( question_uuid, email )
+                VALUES ( $1, $2 )
+                ON CONFLICT DO NOTHING
+            "#,
+            question_uuid.0,
+            email,
+        )
+        .execute(&mut conn)
+        .await
+        .map_err(|err: sqlx::Error| match err {
+            sqlx::Error::Database(err)
+                if err
+                    .code()
+                    .is_some_and(|code| code == postgres_error_code::FOREIGN_KEY_VIOLATION) =>
+            {
+                DBError::NotFound(
+                    Entity::Question,
+                    format!("Question {} does not exist", question_uuid.0),
+                )
+            }
+            err => DBError::Other(Box::new(err)),
+        })?;
+
+        Ok(())
+    }
+
+    async fn unsubscribe(&self, question_uuid: QuestionUuid, email: String) -> Result<(), DBError> {
+        let mut conn = acquire(&self.db).await?;
+
+        sqlx::query!(
+            "DELETE FROM question_subscriptions WHERE question_uuid = $1 AND email = $2",
+            question_uuid.0,
+            email,
+        )
+        .execute(&mut conn)
+        .await
+        .map_err(|err| DBError::Other(Box::new(err)))?;
+
+        Ok(())
+    }
+
+    async fn set_opted_out(&self, email: String, opted_out: bool) -> Result<(), DBError> {
+        let mut conn = acquire(&self.db).await?;
+
+        let result = if opted_out {
+            sqlx::query!(
+                "INSERT INTO email_opt_outs ( email ) VALUES ( $1 ) ON CONFLICT DO NOTHING",
+                email,
+            )
+            .execute(&mut conn)
+            .await
+        } else {
+            sqlx::query!("DELETE FROM email_opt_outs WHERE email = $1", email)
+                .execute(&mut conn)
+                .await
+        };
+        result.map_err(|err| DBError::Other(Box::new(err)))?;
+
+        Ok(())
+    }
+
+    async fn subscribers(
+        &self,
+        question_uuid: QuestionUuid,
+    ) -> Result<Option<Subscribers>, DBError> {
+        let mut conn = acquire(&self.db).await?;
+
+        // One row for a question without subscribers, with a null email.
+        let rows = sqlx::query!(
+            r#"
+                SELECT q.title, s.email AS "email?"
+                FROM questions q
+                LEFT JOIN question_subscriptions s
+                    ON s.question_uuid = q.question_uuid
+                    AND NOT EXISTS (SELECT 1 FROM email_opt_outs o WHERE o.email = s.email)
+                WHERE q.question_uuid = $1
+                ORDER BY s.created_at, s.email
+            "#,
+            question_uuid.0,
+        )
+        .fetch_all(&mut conn)
+        .await
+        .map_err(|err| DBError::Other(Box::new(err)))?;
+
+        let Some(first) = rows.first() else {
+            return Ok(None);
+        };
+        let question_title = first.title.clone();
+
+        let push_subscriptions = sqlx::query_as!(
+            PushSubscription,
+            r#"
+                SELECT p.endpoint, p.p256dh, p.auth
+                FROM push_subscriptions p
+                JOIN push_subscription_questions f ON f.endpoint = p.endpoint
+                WHERE f.question_uuid = $1
+                ORDER BY p.created_at, p.endpoint
+            "#,
+            question_uuid.0,
+        )
+        .fetch_all(&mut conn)
+        .await
+        .map_err(|err| DBError::Other(Box::new(err)))?;
+
+        Ok(Some(Subscribers {
+            question_title,
+            emails: rows.into_iter().filter_map(|row| row.email).collect(),
+            push_subscriptions,
+        }))
+    }
+
+    async fn save_push_subscription(
+        &self,
+        subscription: PushSubscription,
+        question_uuids: Vec<QuestionUuid>,
+    ) -> Result<(), DBError> {
+        let db_error = |err: sqlx::Error| DBError::Other(Box::new(err));
+        let mut tx = self.db.begin().await.map_err(db_error)?;
+
+        // Keys change when a browser subscribes again at the same endpoint.
+        sqlx::query!(
+            r#"
+                INSERT INTO push_subscriptions ( endpoint, p256dh, auth )
+                VALUES ( $1, $2, $3 )
+                ON CONFLICT (endpoint) DO UPDATE
+                SET p256dh = EXCLUDED.p256dh, auth = EXCLUDED.auth
+            "#,
+            subscription.endpoint,
+            subscription.p256dh,
+            subscription.auth,
+        )
+        .execute(&mut tx)
+        .await
+        .map_err(db_error)?;
+
+        sqlx::query!(
+            "DELETE FROM push_subscription_questions WHERE endpoint = $1",
+            subscription.endpoint,
+        )
+        .execute(&mut tx)
+        .await
+        .map_err(db_error)?;
+
+        for question_uuid in question_uuids {
+            sqlx::query!(
+                r#"
+                    INSERT INTO push_subscription_questions ( endpoint, question_uuid )
+                    VALUES ( $1, $2 )
+                    ON CONFLICT DO NOTHING
+                "#,
+                subscription.endpoint,
+                question_uuid.0,
+            )
+            .execute(&mut tx)
+            .await
+            .map_err(|err: sqlx::Error| match err {
+                sqlx::Error::Database(err)
+                    if err
+                        .code()
+                        .is_some_and(|code| code == postgres_error_code::FOREIGN_KEY_VIOLATION) =>
+                {
+                    DBError::NotFound(
+                        Entity::Question,
+                        format!("Question {} does not exist", question_uuid.0),
+                    )
+                }
+                err => DBError::Other(Box::new(err)),
+            })?;
+        }
+
+        tx.commit().await.map_err(db_error)?;
+        Ok(())
+    }
+
+    async fn delete_push_subscription(&self, endpoint: String) -> Result<(), DBError> {
+        let mut conn = acquire(&self.db).await?;
+
+        sqlx::query!(
+            "DELETE FROM push_subscriptions WHERE endpoint = $1",
+            endpoint
+        )
+        .execute(&mut conn)
+        .await
+        .map_err(|err| DBError::Other(Box::new(err)))?;
+
+        Ok(())
+    }
+
+    async fn set_digest_frequency(
+        &self,
+        email: String,
+        frequency: Option<DigestFrequency>,
+    ) -> Result<(), DBError> {
+        let mut conn = acquire(&self.db).await?;
+
+        let result = match frequency {
+            Some(frequency) => {
+                sqlx::query!(
+                    r#"
+                        INSERT INTO digest_preferences ( email, frequency )
+                        VALUES ( $1, $2 )
+                        ON CONFLICT (email) DO UPDATE SET frequency = EXCLUDED.frequency
+                    "#,
+                    email,
+                    frequency.as_str(),
+                )
+                .execute(&mut conn)
+                .await
+            }
+            None => {
+                sqlx::query!("DELETE FROM digest_preferences WHERE email = $1", email)
+                    .execute(&mut conn)
+                    .await
+            }
+        };
+        result.map_err(|err| DBError::Other(Box::new(err)))?;
+
+        Ok(())
+    }
+
+    async fn claim_due_digests(&self, now: DateTime<Utc>) -> Result<Vec<DueDigest>, DBError> {
+        let mut conn = acquire(&self.db).await?;
+
+        // Rows another instance is claiming are skipped rather than waited for; that
+        // instance sends them.
+        let rows = sqlx::query!(
+            r#"
+                WITH due AS (
+                    SELECT email, last_sent_at
+                    FROM digest_preferences d
+                    WHERE d.last_sent_at <= CASE d.frequency WHEN 'daily' THEN $2::TIMESTAMP ELSE $3::TIMESTAMP END
+                    AND NOT EXISTS (SELECT 1 FROM email_opt_outs o WHERE o.email = d.email)
+                    FOR UPDATE SKIP LOCKED
+                )
+                UPDATE digest_preferences p
+                SET last_sent_at = $1
+                FROM due
+                WHERE p.email = due.email
+                RETURNING p.email, p.frequency, due.last_sent_at AS since
+            "#,
+            timestamp(now),
+            timestamp(now - DigestFrequency::Daily.period()),
+            timestamp(now - DigestFrequency::Weekly.period()),
+        )
+        .fetch_all(&mut conn)
+        .await
+        .map_err(|err| DBError::Other(Box::new(err)))?;
+
+        rows.into_iter()
+            .map(|row| {
+                Ok(DueDigest {
+                    email: row.email,
+                    frequency: row
+                        .frequency
+                        .parse()
+                        .map_err(|err: String| DBError::Other(err.into()))?,
+                    since: row.since.and_utc(),
+                    until: now,
+                })
+            })
+            .collect()
+    }
+
+    async fn digest(&self, due: &DueDigest, trending_limit: u32) -> Result<Digest, DBError> {
+        let mut conn = acquire(&self.db).await?;
+        let (since, until) = (timestamp(due.since), timestamp(due.until));
+
+        let answers = sqlx::query!(
+            r#"
+                SELECT a.question_uuid, q.title, a.content
+                FROM answers a
+                JOIN questions q ON q.question_uuid = a.question_uuid
+                JOIN question_subscriptions s
+                    ON s.question_uuid = a.question_uuid AND s.email = $1
+                WHERE a.created_at > $2 AND a.created_at <= $3
+                ORDER BY a.created_at, a.answer_uuid
+            "#,
+            due.email,
+            since,
+            until,
+        )
+        .fetch_all(&mut conn)
+        .await
+        .map_err(|err| DBError::Other(Box::new(err)))?;
+
+        let trending = sqlx::query!(
+            r#"
+                SELECT q.question_uuid, q.title, COUNT(*) AS "new_answers!"
+                FROM answers a
+                JOIN questions q ON q.question_uuid = a.question_uuid
+                WHERE a.created_at > $1 AND a.created_at <= $2
+                GROUP BY q.question_uuid, q.title
+                ORDER BY COUNT(*) DESC, MAX(a.created_at) DESC
+                LIMIT $3
+            "#,
+            since,
+            until,
+            i64::from(trending_limit),
+        )
+        .fetch_all(&mut conn)
+        .await
+        .map_err(|err| DBError::Other(Box::new(err)))?;
+
+        Ok(Digest {
+            answers: answers
+                .into_iter()
+                .map(|row| DigestAnswer {
+                    question_uuid: QuestionUuid(row.question_uuid),
+                    question_title: row.title,
+                    content: row.content,
+                })
+                .collect(),
+            trending: trending
+                .into_iter()
+                .map(|row| TrendingQuestion {
+                    question_uuid: QuestionUuid(row.question_uuid),
+                    title: row.title,
+                    new_answers: row.new_answers,
+                })
+                .collect(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        models::{empty_metadata, Answer, Question},
+        persistence::{
+            answer_dao::{AnswerDao, AnswerDaoImpl},
+            question_dao::{QuestionDao, QuestionDaoImpl},
+        },
+    };
+
+    #[sqlx::test]
+    async fn subscribers_should_leave_out_opted_out_addresses(pool: PgPool) -> Result<(), String> {
+        let question_dao = QuestionDaoImpl::new(pool.clone());
+        let dao = PgSubscriptionDao::new(pool);
+        let question = question_dao
+            .create_question(Question {
+                title: "title".to_owned(),
+                description: None,
+                metadata: empty_metadata(),
+            })
+            .await
+            .unwrap();
+        let question_uuid = question.question_uuid;
+
+        for email in ["a@example.com", "b@example.com", "a@example.com"] {
+            dao.subscribe(question_uuid, email.to_owned())
+                .await
+                .unwrap();
+        }
+        dao.set_opted_out("b@example.com".to_owned(), true)
+            .await
+            .unwrap();
+
+        let subscribers = dao.subscribers(question_uuid).await.unwrap().unwrap();
+        assert_eq!(subscribers.question_title, "title");
+        assert_eq!(subscribers.emails, ["a@example.com"]);
+
+        dao.set_opted_out("b@example.com".to_owned(), false)
+            .await
+            .unwrap();
+        dao.unsubscribe(question_uuid, "a@example.com".to_owned())
+            .await
+            .unwrap();
+        let subscribers = dao.subscribers(question_uuid).await.unwrap().unwrap();
+        assert_eq!(subscribers.emails, ["b@example.com"]);
+
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn push_subscriptions_should_follow_the_saved_questions(
+        pool: PgPool,
+    ) -> Result<(), String> {
+        let question_dao = QuestionDaoImpl::new(pool.clone());
+        let dao = PgSubscriptionDao::new(pool);
+        let mut question_uuids = vec![];
+        for title in ["first", "second"] {
+            let question = question_dao
+                .create_question(Question {
+                    title: title.to_owned(),
+                    description: None,
+                    metadata: empty_metadata(),
+                })
+                .await
+                .unwrap();
+            question_uuids.push(question.question_uuid);
+        }
+        let subscription = |auth: &str| PushSubscription {
+            endpoint: "https://push.example.net/1".to_owned(),
+            p256dh: "key".to_owned(),
+            auth: auth.to_owned(),
+        };
+
+        dao.save_push_subscription(subscription("old"), vec![question_uuids[0]])
+            .await
+            .unwrap();
+        dao.save_push_subscription(subscription("new"), vec![question_uuids[1]])
+            .await
+            .unwrap();
+        let result = dao
+            .save_push_subscription(
+                subscription("newest"),
+                vec![question_uuids[0], QuestionUuid::new_v4()],
+            )
+            .await;
+        assert!(matches!(
+            result,
+            Err(DBError::NotFound(Entity::Question, _))
+        ));
+
+        let followers = |question_uuid| {
+            let dao = &dao;
+            async move {
+                dao.subscribers(question_uuid)
+                    .await
+                    .unwrap()
+                    .unwrap()
+                    .push_subscriptions
+            }
+        };
+        assert!(followers(question_uuids[0]).await.is_empty());
+        assert_eq!(followers(question_uuids[1]).await, [subscription("new")]);
+
+        dao.delete_push_subscription("https://push.example.net/1".to_owned())
+            .await
+            .unwrap();
+        assert!(followers(question_uuids[1]).await.is_empty());
+
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn due_digests_should_be_claimed_once_per_period(pool: PgPool) -> Result<(), String> {
+        let question_dao = QuestionDaoImpl::new(pool.clone());
+        let dao = PgSubscriptionDao::new(pool.clone());
+        let mut questions = vec![];
+        for title in ["followed", "popular"] {
+            questions.push(
+                question_dao
+                    .create_question(Question {
+                        title: title.to_owned(),
+                        description: None,
+                        metadata: empty_metadata(),
+                    })
+                    .await
+                    .unwrap(),
+            );
+        }
+        dao.subscribe(questions[0].question_uuid, "a@example.com".to_owned())
+            .await
+            .unwrap();
+        for (email, frequency) in [
+            ("a@example.com", DigestFrequency::Daily),
+            ("b@example.com", DigestFrequency::Weekly),
+        ] {
+            dao.set_digest_frequency(email.to_owned(), Some(frequency))
+                .await
+                .unwrap();
+        }
+        let answer_dao = AnswerDaoImpl::new(pool);
+        for question in [&questions[0], &questions[1], &questions[1]] {
+            answer_dao
+                .create_answer(Answer {
+                    question_uuid: question.question_uuid,
+                    content: format!("answer to {}", question.title),
+                })
+                .await
+                .unwrap();
+        }
+
+        let now = Utc::now() + chrono::Duration::days(2);
+        let due = dao.claim_due_digests(now).await.unwrap();
+        assert_eq!(due.len(), 1);
+        assert_eq!(due[0].email, "a@example.com");
+        assert_eq!(due[0].frequency, DigestFrequency::Daily);
+        assert!(dao.claim_due_digests(now).await.unwrap().is_empty());
+
+        let digest = dao.digest(&due[0], 1).await.unwrap();
+        assert_eq!(
+            digest.answers,
+            [DigestAnswer {
+                question_uuid: questions[0].question_uuid,
+                question_title: "followed".to_owned(),
+                content: "answer to followed".to_owned(),
+            }]
+        );
+        assert_eq!(
+            digest.trending,
+            [TrendingQuestion {
+                question_uuid: questions[1].question_uuid,
+                title: "popular".to_owned(),
+                new_answers: 2,
+            }]
+        );
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn subscribe_should_fail_for_unknown_question(pool: PgPool) -> Result<(), String> {
+        let dao = PgSubscriptionDao::new(pool);
+        let question_uuid = QuestionUuid::new_v4();
+
+        let err = dao
+            .subscribe(question_uuid, "a@example.com".to_owned())
+            .await
+            .unwrap_err();
+
+        assert!(matches!(err, DBError::NotFound(Entity::Question, _)));
+        assert_eq!(dao.subscribers(question_uuid).await.unwrap(), None);
+        Ok(())
+    }
+}
@@ -0,0 +1,123 @@
+use async_trait::async_trait;
+use serde_json::Value;
+use sqlx::PgPool;
+
+use super::acquire;
+use crate::models::{metadata_tags, DBError};
+
+// A question that carries tags, as tag suggestions learn from it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TaggedQuestion {
+    pub title: String,
+    pub description: Option<String>,
+    pub tags: Vec<String>,
+}
+
+impl TaggedQuestion {
+    // None for questions without tags.
+    pub fn from_metadata(
+        title: String,
+        description: Option<String>,
+        metadata: &Value,
+    ) -> Option<Self> {
+        let tags: Vec<String> = metadata_tags(metadata)
+            .into_iter()
+            .map(str::to_owned)
+            .collect();
+        (!tags.is_empty()).then_some(Self {
+            title,
+            description,
+            tags,
+        })
+    }
+}
+
+// Reads the questions tag suggestions are built from.
+#[async_trait]
+pub trait TagDao {
+    // Newest first.
+    async fn tagged_questions(&self, limit: u32) -> Result<Vec<TaggedQuestion>, DBError>;
+}
+
+pub struct PgTagDao {
+    db: PgPool,
+}
+
+impl PgTagDao {
+    pub fn new(db: PgPool) -> Self {
+        Self { db }
+    }
+}
+
+#[async_trait]
+impl TagDao for PgTagDao {
+    async fn tagged_questions(&self, limit: u32) -> Result<Vec<TaggedQuestion>, DBError> {
+        let mut conn = acquire(&self.db).await?;
+
+        let rows = sqlx::query!(
+            r#"
+                SELECT title, description, metadata FROM questions
+                WHERE metadata ? 'tags'
+                ORDER BY created_at DESC
+                LIMIT $1
+            "#,
+            i64::from(limit),
+        )
+        .fetch_all(&mut conn)
+        .await
+        .map_err(|err| DBError::Other(Box::new(err)))?;
+
+        Ok(rows
+            .into_iter()
+            .filter_map(|row| {
+                TaggedQuestion::from_metadata(row.title, row.description, &row.metadata)
+            })
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::{
+        models::{empty_metadata, Question},
+        persistence::question_dao::{QuestionDao, QuestionDaoImpl},
+    };
+
+    #[sqlx::test]
+    async fn tagged_questions_should_skip_untagged_ones(pool: PgPool) -> Result<(), String> {
+        let question_dao = QuestionDaoImpl::new(pool.clone());
+        for (title, metadata) in [
+            ("first", json!({ "tags": ["billing", "refunds"] })),
+            ("untagged", empty_metadata()),
+            ("second", json!({ "tags": "api" })),
+        ] {
+            question_dao
+                .create_question(Question {
+                    title: title.to_owned(),
+                    description: None,
+                    metadata,
+                })
+                .await
+                .unwrap();
+        }
+
+        let tagged = PgTagDao::new(pool).tagged_questions(10).await.unwrap();
+
+        let tags: Vec<(&str, Vec<String>)> = tagged
+            .iter()
+            .map(|question| (question.title.as_str(), question.tags.clone()))
+            .collect();
+        assert_eq!(
+            tags,
+            [
+                ("second", vec!["api".to_owned()]),
+                ("first", vec!["billing".to_owned(), "refunds".to_owned()]),
+            ]
+        );
+
+        Ok(())
+    }
+}
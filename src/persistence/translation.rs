@@ -0,0 +1,371 @@
+use std::collections::BTreeMap;
+
+use async_trait::async_trait;
+use sqlx::{types::chrono::NaiveDateTime, PgPool};
+use uuid::Uuid;
+
+use super::acquire;
+use crate::models::{DBError, Entity, QuestionTranslation, QuestionUuid};
+
+// A translated row, keyed like the `translations` table.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Translation {
+    pub entity: &'static str,
+    pub entity_uuid: Uuid,
+    pub language: String,
+    // The version of the row that was translated.
+    pub source_version: i64,
+    pub fields: BTreeMap<String, String>,
+}
+
+// Caches machine translations by entity, row and language, and keeps the translations
+// of questions people submitted.
+#[async_trait]
+pub trait TranslationDao {
+    async fn get_translation(
+        &self,
+        entity: &'static str,
+        entity_uuid: Uuid,
+        language: &str,
+    ) -> Result<Option<Translation>, DBError>;
+    // Replaces the translation of an older version.
+    async fn store_translation(&self, translation: Translation) -> Result<(), DBError>;
+    async fn question_translation(
+        &self,
+        question_uuid: QuestionUuid,
+        language: &str,
+    ) -> Result<Option<QuestionTranslation>, DBError>;
+    // By language.
+    async fn question_translations(
+        &self,
+        question_uuid: QuestionUuid,
+    ) -> Result<Vec<QuestionTranslation>, DBError>;
+    // Replaces the question's translation into `language`, recording the version of the
+    // question it was made from. NotFound for unknown questions.
+    async fn submit_question_translation(
+        &self,
+        question_uuid: QuestionUuid,
+        language: &str,
+        title: String,
+        description: Option<String>,
+    ) -> Result<QuestionTranslation, DBError>;
+    // NotFound when the question has no translation into `language`.
+    async fn delete_question_translation(
+        &self,
+        question_uuid: QuestionUuid,
+        language: &str,
+    ) -> Result<(), DBError>;
+}
+
+struct QuestionTranslationRow {
+    question_uuid: Uuid,
+    language: String,
+    title: String,
+    description: Option<String>,
+    question_version: i64,
+    updated_at: NaiveDateTime,
+}
+
+impl From<QuestionTranslationRow> for QuestionTranslation {
+    fn from(row: QuestionTranslationRow) -> Self {
+        QuestionTranslation {
+            question_uuid: QuestionUuid(row.question_uuid),
+            language: row.language,
+            title: row.title,
+            description: row.description,
+            question_version: row.question_version,
+            updated_at: row.updated_at.and_utc(),
+        }
+    }
+}
+
+pub struct PgTranslationDao {
+    db: PgPool,
+}
+
+impl PgTranslationDao {
+    pub fn new(db: PgPool) -> Self {
+        Self { db }
+    }
+}
+
+#[async_trait]
+impl TranslationDao for PgTranslationDao {
+    async fn get_translation(
+        &self,
+        entity: &'static str,
+        entity_uuid: Uuid,
+        language: &str,
+    ) -> Result<Option<Translation>, DBError> {
+        let mut conn = acquire(&self.db).await?;
+
+        let row = sqlx::query!(
+            r#"
+                SELECT source_version, fields FROM translations
+                WHERE entity = $1 AND entity_uuid = $2 AND language = $3
+            "#,
+            entity,
+            entity_uuid,
+            language,
+        )
+        .fetch_optional(&mut conn)
+        .await
+        .map_err(|err| DBError::Other(Box::new(err)))?;
+
+        row.map(|row| {
+            Ok(Translation {
+                entity,
+                entity_uuid,
+                language: language.to_owned(),
+                source_version: row.source_version,
+                fields: serde_json::from_value(row.fields)
+                    .map_err(|err| DBError::Other(Box::new(err)))?,
+            })
+        })
+        .transpose()
+    }
+
+    async fn store_translation(&self, translation: Translation) -> Result<(), DBError> {
+        let mut conn = acquire(&self.db).await?;
+        let fields = serde_json::to_value(&translation.fields)
+            .map_err(|err| DBError::Other(Box::new(err)))?;
+
+        sqlx::query!(
+            r#"
+                INSERT INTO translations ( entity, entity_uuid, language, source_version, fields )
+                VALUES ( $1, $2, $3, $4, $5 )
+                ON CONFLICT ( entity, entity_uuid, language ) DO UPDATE
+                SET source_version = EXCLUDED.source_version, fields = EXCLUDED.fields,
+                    created_at = LOCALTIMESTAMP
+                WHERE translations.source_version <= EXCLUDED.source_version
+            "#,
+            translation.entity,
+            translation.entity_uuid,
+            translation.language,
+            translation.source_version,
+            fields,
+        )
+        .execute(&mut conn)
+        .await
+        .map_err(|err| DBError::Other(Box::new(err)))?;
+
+        Ok(())
+    }
+
+    async fn question_translation(
+        &self,
+        question_uuid: QuestionUuid,
+        language: &str,
+    ) -> Result<Option<QuestionTranslation>, DBError> {
+        let mut conn = acquire(&self.db).await?;
+
+        let row = sqlx::query_as!(
+            QuestionTranslationRow,
+            r#"
+                SELECT question_uuid, language, title, description, question_version, updated_at
+                FROM question_translations
+                WHERE question_uuid = $1 AND language = $2
+            "#,
+            question_uuid.0,
+            language,
+        )
+        .fetch_optional(&mut conn)
+        .await
+        .map_err(|err| DBError::Other(Box::new(err)))?;
+
+        Ok(row.map(QuestionTranslation::from))
+    }
+
+    async fn question_translations(
+        &self,
+        question_uuid: QuestionUuid,
+    ) -> Result<Vec<QuestionTranslation>, DBError> {
+        let mut conn = acquire(&self.db).await?;
+
+        let rows = sqlx::query_as!(
+            QuestionTranslationRow,
+            r#"
+                SELECT question_uuid, language, title, description, question_version, updated_at
+                FROM question_translations
+                WHERE question_uuid = $1
+                ORDER BY language
+            "#,
+            question_uuid.0,
+        )
+        .fetch_all(&mut conn)
+        .await
+        .map_err(|err| DBError::Other(Box::new(err)))?;
+
+        Ok(rows.into_iter().map(QuestionTranslation::from).collect())
+    }
+
+    async fn submit_question_translation(
+        &self,
+        question_uuid: QuestionUuid,
+        language: &str,
+        title: String,
+        description: Option<String>,
+    ) -> Result<QuestionTranslation, DBError> {
+        let mut conn = acquire(&self.db).await?;
+
+        let row = sqlx::query_as!(
+            QuestionTranslationRow,
+            r#"
+                INSERT INTO question_translations
+                    ( question_uuid, language, title, description, question_version )
+                SELECT question_uuid, $2, $3, $4, version FROM questions WHERE question_uuid = $1
+                ON CONFLICT ( question_uuid, language ) DO UPDATE
+                SET title = EXCLUDED.title, description = EXCLUDED.description,
+                    question_version = EXCLUDED.question_version, updated_at = LOCALTIMESTAMP
+                RETURNING question_uuid, language, title, description, question_version, updated_at
+            "#,
+            question_uuid.0,
+            language,
+            title,
+            description,
+        )
+        .fetch_optional(&mut conn)
+        .await
+        .map_err(|err| DBError::Other(Box::new(err)))?;
+
+        row.map(QuestionTranslation::from).ok_or_else(|| {
+            DBError::NotFound(
+                Entity::Question,
+                format!("Question {} does not exist", question_uuid.0),
+            )
+        })
+    }
+
+    async fn delete_question_translation(
+        &self,
+        question_uuid: QuestionUuid,
+        language: &str,
+    ) -> Result<(), DBError> {
+        let mut conn = acquire(&self.db).await?;
+
+        let deleted = sqlx::query!(
+            "DELETE FROM question_translations WHERE question_uuid = $1 AND language = $2",
+            question_uuid.0,
+            language,
+        )
+        .execute(&mut conn)
+        .await
+        .map_err(|err| DBError::Other(Box::new(err)))?
+        .rows_affected();
+
+        if deleted == 0 {
+            return Err(DBError::NotFound(
+                Entity::Translation,
+                format!(
+                    "Question {} has no {} translation",
+                    question_uuid.0, language
+                ),
+            ));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        models::{empty_metadata, Question},
+        persistence::question_dao::{QuestionDao, QuestionDaoImpl},
+    };
+
+    fn translation(source_version: i64, title: &str) -> Translation {
+        Translation {
+            entity: "question",
+            entity_uuid: Uuid::nil(),
+            language: "es".to_owned(),
+            source_version,
+            fields: BTreeMap::from([("title".to_owned(), title.to_owned())]),
+        }
+    }
+
+    #[sqlx::test]
+    async fn translations_should_keep_the_newest_version(pool: PgPool) -> Result<(), String> {
+        let dao = PgTranslationDao::new(pool);
+        assert_eq!(
+            dao.get_translation("question", Uuid::nil(), "es")
+                .await
+                .unwrap(),
+            None
+        );
+
+        dao.store_translation(translation(2, "dos")).await.unwrap();
+        dao.store_translation(translation(1, "uno")).await.unwrap();
+
+        assert_eq!(
+            dao.get_translation("question", Uuid::nil(), "es")
+                .await
+                .unwrap(),
+            Some(translation(2, "dos"))
+        );
+        assert_eq!(
+            dao.get_translation("question", Uuid::nil(), "fr")
+                .await
+                .unwrap(),
+            None
+        );
+
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn submitted_translations_should_replace_each_other(pool: PgPool) -> Result<(), String> {
+        let question = QuestionDaoImpl::new(pool.clone())
+            .create_question(Question {
+                title: "title".to_owned(),
+                description: None,
+                metadata: empty_metadata(),
+            })
+            .await
+            .map_err(|err| err.to_string())?;
+        let question_uuid = question.question_uuid;
+        let dao = PgTranslationDao::new(pool);
+
+        dao.submit_question_translation(question_uuid, "pt", "titulo".to_owned(), None)
+            .await
+            .map_err(|err| err.to_string())?;
+        let submitted = dao
+            .submit_question_translation(
+                question_uuid,
+                "pt",
+                "título".to_owned(),
+                Some("descrição".to_owned()),
+            )
+            .await
+            .map_err(|err| err.to_string())?;
+        assert_eq!(submitted.question_version, question.version);
+        assert_eq!(
+            dao.question_translation(question_uuid, "pt")
+                .await
+                .map_err(|err| err.to_string())?,
+            Some(submitted.clone())
+        );
+        assert_eq!(
+            dao.question_translations(question_uuid)
+                .await
+                .map_err(|err| err.to_string())?,
+            [submitted]
+        );
+
+        let unknown = QuestionUuid(Uuid::new_v4());
+        assert!(matches!(
+            dao.submit_question_translation(unknown, "pt", "título".to_owned(), None)
+                .await,
+            Err(DBError::NotFound(Entity::Question, _))
+        ));
+
+        dao.delete_question_translation(question_uuid, "pt")
+            .await
+            .map_err(|err| err.to_string())?;
+        assert!(matches!(
+            dao.delete_question_translation(question_uuid, "pt").await,
+            Err(DBError::NotFound(Entity::Translation, _))
+        ));
+        Ok(())
+    }
+}
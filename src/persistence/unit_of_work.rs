@@ -0,0 +1,152 @@
+use async_trait::async_trait;
+use sqlx::{PgPool, Postgres, Transaction};
+
+use super::{answer_dao::insert_answer, audit::set_actor, question_dao::insert_question};
+use crate::models::{Answer, AnswerDetail, DBError, Question, QuestionDetail};
+
+// Writes that become visible together on `commit`. Dropping a unit of work
+// without committing it discards everything it did.
+//
+// Deleting a question needs no unit of work, its answers go with it through
+// ON DELETE CASCADE.
+#[async_trait]
+pub trait UnitOfWork: Send {
+    async fn create_question(&mut self, question: Question) -> Result<QuestionDetail, DBError>;
+    async fn create_answer(&mut self, answer: Answer) -> Result<AnswerDetail, DBError>;
+    async fn commit(self: Box<Self>) -> Result<(), DBError>;
+}
+
+#[async_trait]
+pub trait UnitOfWorkFactory {
+    async fn begin(&self) -> Result<Box<dyn UnitOfWork>, DBError>;
+}
+
+pub struct PgUnitOfWorkFactory {
+    db: PgPool,
+}
+
+impl PgUnitOfWorkFactory {
+    pub fn new(db: PgPool) -> Self {
+        Self { db }
+    }
+}
+
+#[async_trait]
+impl UnitOfWorkFactory for PgUnitOfWorkFactory {
+    async fn begin(&self) -> Result<Box<dyn UnitOfWork>, DBError> {
+        let mut tx = self
+            .db
+            .begin()
+            .await
+            .map_err(|err| DBError::Other(Box::new(err)))?;
+        set_actor(&mut tx)
+            .await
+            .map_err(|err| DBError::Other(Box::new(err)))?;
+
+        Ok(Box::new(PgUnitOfWork { tx }))
+    }
+}
+
+pub struct PgUnitOfWork {
+    tx: Transaction<'static, Postgres>,
+}
+
+#[async_trait]
+impl UnitOfWork for PgUnitOfWork {
+    async fn create_question(&mut self, question: Question) -> Result<QuestionDetail, DBError> {
+        insert_question(&mut self.tx, question).await
+    }
+
+    async fn create_answer(&mut self, answer: Answer) -> Result<AnswerDetail, DBError> {
+        insert_answer(&mut self.tx, answer).await
+    }
+
+    async fn commit(self: Box<Self>) -> Result<(), DBError> {
+        self.tx
+            .commit()
+            .await
+            .map_err(|err| DBError::Other(Box::new(err)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        models::{empty_metadata, Entity, QuestionUuid},
+        persistence::question_dao::{QuestionDao, QuestionDaoImpl},
+    };
+
+    fn question() -> Question {
+        Question {
+            title: "title".to_owned(),
+            description: Some("description".to_owned()),
+            metadata: empty_metadata(),
+        }
+    }
+
+    #[sqlx::test]
+    async fn commit_should_persist_all_writes(pool: PgPool) -> Result<(), String> {
+        let mut work = PgUnitOfWorkFactory::new(pool.clone())
+            .begin()
+            .await
+            .map_err(|err| err.to_string())?;
+
+        let question = work
+            .create_question(question())
+            .await
+            .map_err(|err| err.to_string())?;
+        let answer = work
+            .create_answer(Answer {
+                question_uuid: question.question_uuid,
+                content: "content".to_owned(),
+            })
+            .await
+            .map_err(|err| err.to_string())?;
+        work.commit().await.map_err(|err| err.to_string())?;
+
+        let questions = QuestionDaoImpl::new(pool)
+            .get_questions()
+            .await
+            .map_err(|err| err.to_string())?;
+        assert_eq!(
+            questions,
+            vec![QuestionDetail {
+                answer_count: 1,
+                last_activity_at: answer.created_at,
+                ..question
+            }]
+        );
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn dropping_without_commit_should_roll_back(pool: PgPool) -> Result<(), String> {
+        let mut work = PgUnitOfWorkFactory::new(pool.clone())
+            .begin()
+            .await
+            .map_err(|err| err.to_string())?;
+
+        work.create_question(question())
+            .await
+            .map_err(|err| err.to_string())?;
+        let result = work
+            .create_answer(Answer {
+                question_uuid: QuestionUuid::new_v4(),
+                content: "content".to_owned(),
+            })
+            .await;
+        assert!(matches!(
+            result,
+            Err(DBError::NotFound(Entity::Question, _))
+        ));
+        drop(work);
+
+        let questions = QuestionDaoImpl::new(pool)
+            .get_questions()
+            .await
+            .map_err(|err| err.to_string())?;
+        assert_eq!(questions, vec![]);
+        Ok(())
+    }
+}
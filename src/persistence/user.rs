@@ -0,0 +1,280 @@
+use async_trait::async_trait;
+use sqlx::{types::chrono::NaiveDateTime, PgPool};
+use uuid::Uuid;
+
+use super::acquire;
+use crate::models::{
+    postgres_error_code, DBError, Entity, ProvisionedUser, UserAttributes, UserUuid,
+};
+
+// Accounts provisioned over SCIM. User names are unique; taking one that is in use is
+// a Conflict.
+#[async_trait]
+pub trait UserDao {
+    async fn create_user(
+        &self,
+        user_uuid: UserUuid,
+        attributes: UserAttributes,
+    ) -> Result<ProvisionedUser, DBError>;
+    // NotFound for unknown users.
+    async fn get_user(&self, user_uuid: UserUuid) -> Result<ProvisionedUser, DBError>;
+    // Oldest first, only the one named `user_name` when given, with how many there are
+    // in all.
+    async fn users(
+        &self,
+        user_name: Option<&str>,
+        offset: i64,
+        limit: i64,
+    ) -> Result<(Vec<ProvisionedUser>, i64), DBError>;
+    // Replaces every attribute; NotFound for unknown users.
+    async fn update_user(
+        &self,
+        user_uuid: UserUuid,
+        attributes: UserAttributes,
+    ) -> Result<ProvisionedUser, DBError>;
+}
+
+struct UserRow {
+    user_uuid: Uuid,
+    user_name: String,
+    external_id: Option<String>,
+    display_name: Option<String>,
+    active: bool,
+    created_at: NaiveDateTime,
+    updated_at: NaiveDateTime,
+}
+
+impl From<UserRow> for ProvisionedUser {
+    fn from(row: UserRow) -> Self {
+        ProvisionedUser {
+            user_uuid: UserUuid(row.user_uuid),
+            attributes: UserAttributes {
+                user_name: row.user_name,
+                external_id: row.external_id,
+                display_name: row.display_name,
+                active: row.active,
+            },
+            created_at: row.created_at.and_utc(),
+            updated_at: row.updated_at.and_utc(),
+        }
+    }
+}
+
+fn write_error(err: sqlx::Error, user_name: &str) -> DBError {
+    match err {
+        sqlx::Error::Database(err)
+            if err
+                .code()
+                .is_some_and(|code| code == postgres_error_code::UNIQUE_VIOLATION) =>
+        {
+            DBError::Conflict(format!("User name {} is already taken", user_name))
+        }
+        err => DBError::Other(Box::new(err)),
+    }
+}
+
+fn not_found(user_uuid: UserUuid) -> DBError {
+    DBError::NotFound(Entity::User, format!("User {} not found", user_uuid))
+}
+
+pub struct PgUserDao {
+    db: PgPool,
+}
+
+impl PgUserDao {
+    pub fn new(db: PgPool) -> Self {
+        Self { db }
+    }
+}
+
+#[async_trait]
+impl UserDao for PgUserDao {
+    async fn create_user(
+        &self,
+        user_uuid: UserUuid,
+        attributes: UserAttributes,
+    ) -> Result<ProvisionedUser, DBError> {
+        let mut conn = acquire(&self.db).await?;
+
+        let row = sqlx::query_as!(
+            UserRow,
+            r#"
+                INSERT INTO users ( user_uuid, user_name, external_id, display_name, active )
+                VALUES ( $1, $2, $3, $4, $5 )
+                RETURNING *
+            "#,
+            user_uuid.0,
+            attributes.user_name,
+            attributes.external_id,
+            attributes.display_name,
+            attributes.active,
+        )
+        .fetch_one(&mut conn)
+        .await
+        .map_err(|err| write_error(err, &attributes.user_name))?;
+
+        Ok(row.into())
+    }
+
+    async fn get_user(&self, user_uuid: UserUuid) -> Result<ProvisionedUser, DBError> {
+        let mut conn = acquire(&self.db).await?;
+
+        let row = sqlx::query_as!(
+            UserRow,
+            "SELECT * FROM users WHERE user_uuid = $1",
+            user_uuid.0
+        )
+        .fetch_optional(&mut conn)
+        .await
+        .map_err(|err| DBError::Other(Box::new(err)))?;
+
+        row.map(ProvisionedUser::from)
+            .ok_or_else(|| not_found(user_uuid))
+    }
+
+    async fn users(
+        &self,
+        user_name: Option<&str>,
+        offset: i64,
+        limit: i64,
+    ) -> Result<(Vec<ProvisionedUser>, i64), DBError> {
+        let mut conn = acquire(&self.db).await?;
+
+        let rows = sqlx::query!(
+            r#"
+                SELECT *, COUNT(*) OVER () AS "total!"
+                FROM users
+                WHERE $1::TEXT IS NULL OR user_name = $1
+                ORDER BY created_at, user_uuid
+                OFFSET $2
+                LIMIT $3
+            "#,
+            user_name,
+            offset,
+            limit,
+        )
+        .fetch_all(&mut conn)
+        .await
+        .map_err(|err| DBError::Other(Box::new(err)))?;
+
+        // Past the last page no row carries the total, so it is counted on its own.
+        let total = match rows.first() {
+            Some(row) => row.total,
+            None => sqlx::query_scalar!(
+                r#"SELECT COUNT(*) AS "total!" FROM users WHERE $1::TEXT IS NULL OR user_name = $1"#,
+                user_name,
+            )
+            .fetch_one(&mut conn)
+            .await
+            .map_err(|err| DBError::Other(Box::new(err)))?,
+        };
+        let users = rows
+            .into_iter()
+            .map(|row| {
+                UserRow {
+                    user_uuid: row.user_uuid,
+                    user_name: row.user_name,
+                    external_id: row.external_id,
+                    display_name: row.display_name,
+                    active: row.active,
+                    created_at: row.created_at,
+                    updated_at: row.updated_at,
+                }
+                .into()
+            })
+            .collect();
+        Ok((users, total))
+    }
+
+    async fn update_user(
+        &self,
+        user_uuid: UserUuid,
+        attributes: UserAttributes,
+    ) -> Result<ProvisionedUser, DBError> {
+        let mut conn = acquire(&self.db).await?;
+
+        let row = sqlx::query_as!(
+            UserRow,
+            r#"
+                UPDATE users
+                SET user_name = $2, external_id = $3, display_name = $4, active = $5,
+                    updated_at = LOCALTIMESTAMP
+                WHERE user_uuid = $1
+                RETURNING *
+            "#,
+            user_uuid.0,
+            attributes.user_name,
+            attributes.external_id,
+            attributes.display_name,
+            attributes.active,
+        )
+        .fetch_optional(&mut conn)
+        .await
+        .map_err(|err| write_error(err, &attributes.user_name))?;
+
+        row.map(ProvisionedUser::from)
+            .ok_or_else(|| not_found(user_uuid))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn attributes(user_name: &str) -> UserAttributes {
+        UserAttributes {
+            user_name: user_name.to_owned(),
+            external_id: Some("00u1".to_owned()),
+            display_name: None,
+            active: true,
+        }
+    }
+
+    #[sqlx::test]
+    async fn user_names_should_be_unique_and_found_by_name(pool: PgPool) -> Result<(), String> {
+        let dao = PgUserDao::new(pool);
+
+        let ada = dao
+            .create_user(UserUuid::new_v4(), attributes("ada@example.com"))
+            .await
+            .unwrap();
+        let bob = dao
+            .create_user(UserUuid::new_v4(), attributes("bob@example.com"))
+            .await
+            .unwrap();
+        assert!(matches!(
+            dao.create_user(UserUuid::new_v4(), attributes("ada@example.com"))
+                .await,
+            Err(DBError::Conflict(_))
+        ));
+        assert!(matches!(
+            dao.update_user(bob.user_uuid, attributes("ada@example.com"))
+                .await,
+            Err(DBError::Conflict(_))
+        ));
+
+        let (users, total) = dao.users(Some("ada@example.com"), 0, 10).await.unwrap();
+        assert_eq!((users, total), (vec![ada.clone()], 1));
+        let (users, total) = dao.users(None, 5, 10).await.unwrap();
+        assert_eq!((users.len(), total), (0, 2));
+
+        let deactivated = dao
+            .update_user(
+                ada.user_uuid,
+                UserAttributes {
+                    active: false,
+                    ..ada.attributes.clone()
+                },
+            )
+            .await
+            .unwrap();
+        assert!(!deactivated.attributes.active);
+        assert_eq!(dao.get_user(ada.user_uuid).await.unwrap(), deactivated);
+        assert!(matches!(
+            dao.get_user(UserUuid::new_v4()).await,
+            Err(DBError::NotFound(Entity::User, _))
+        ));
+
+        Ok(())
+    }
+}
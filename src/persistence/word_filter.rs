@@ -0,0 +1,79 @@
+use async_trait::async_trait;
+use sqlx::PgPool;
+
+use super::acquire;
+use crate::models::DBError;
+
+// The word filter's list, lowercased.
+#[async_trait]
+pub trait WordFilterDao {
+    // Alphabetical.
+    async fn filtered_words(&self) -> Result<Vec<String>, DBError>;
+    // Adding a listed word or removing an unlisted one is a no-op.
+    async fn set_filtered(&self, word: String, filtered: bool) -> Result<(), DBError>;
+}
+
+pub struct PgWordFilterDao {
+    db: PgPool,
+}
+
+impl PgWordFilterDao {
+    pub fn new(db: PgPool) -> Self {
+        Self { db }
+    }
+}
+
+#[async_trait]
+impl WordFilterDao for PgWordFilterDao {
+    async fn filtered_words(&self) -> Result<Vec<String>, DBError> {
+        let mut conn = acquire(&self.db).await?;
+
+        sqlx::query_scalar!("SELECT word FROM filtered_words ORDER BY word")
+            .fetch_all(&mut conn)
+            .await
+            .map_err(|err| DBError::Other(Box::new(err)))
+    }
+
+    async fn set_filtered(&self, word: String, filtered: bool) -> Result<(), DBError> {
+        let mut conn = acquire(&self.db).await?;
+
+        let result = if filtered {
+            sqlx::query!(
+                "INSERT INTO filtered_words ( word ) VALUES ( $1 ) ON CONFLICT DO NOTHING",
+                word,
+            )
+            .execute(&mut conn)
+            .await
+        } else {
+            sqlx::query!("DELETE FROM filtered_words WHERE word = $1", word)
+                .execute(&mut conn)
+                .await
+        };
+        result.map_err(|err| DBError::Other(Box::new(err)))?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[sqlx::test]
+    async fn filtered_words_should_be_added_once_and_removed(pool: PgPool) -> Result<(), String> {
+        let dao = PgWordFilterDao::new(pool);
+
+        for word in ["scam", "darn", "scam"] {
+            dao.set_filtered(word.to_owned(), true).await.unwrap();
+        }
+        assert_eq!(dao.filtered_words().await.unwrap(), ["darn", "scam"]);
+
+        dao.set_filtered("darn".to_owned(), false).await.unwrap();
+        dao.set_filtered("unlisted".to_owned(), false)
+            .await
+            .unwrap();
+        assert_eq!(dao.filtered_words().await.unwrap(), ["scam"]);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,472 @@
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use serde_json::json;
+use thiserror::Error;
+
+use crate::{
+    chat::excerpt,
+    config::AppConfig,
+    models::{AnswerDetail, PushSubscription},
+    startup::StartupError,
+};
+
+#[derive(Error, Debug)]
+pub enum PushError {
+    // The push service dropped the subscription, e.g. because the user revoked the
+    // permission; it should be deleted.
+    #[error("The push subscription is gone")]
+    Gone,
+    #[error("Invalid push key: {0}")]
+    InvalidKey(String),
+    #[error("Pushing failed: {0}")]
+    Failed(String),
+}
+
+#[async_trait]
+pub trait Pusher {
+    async fn push(&self, subscription: &PushSubscription, payload: &[u8]) -> Result<(), PushError>;
+}
+
+// None while no VAPID keys are configured, as push services refuse unsigned pushes.
+pub fn pusher_from_config(
+    config: &AppConfig,
+) -> Result<Option<Arc<dyn Pusher + Send + Sync>>, StartupError> {
+    match (&config.vapid_private_key, &config.vapid_public_key) {
+        #[cfg(feature = "webpush")]
+        (Some(private_key), Some(public_key)) => {
+            match WebPusher::new(private_key, public_key, &config.vapid_subject) {
+                Ok(pusher) => Ok(Some(Arc::new(pusher))),
+                Err(err) => Err(StartupError::InvalidSetting {
+                    setting: "VAPID_PRIVATE_KEY, VAPID_PUBLIC_KEY or VAPID_SUBJECT",
+                    expected:
+                        "a P-256 key pair in unpadded base64url and a mailto: or https: subject",
+                    reason: err.to_string(),
+                }),
+            }
+        }
+        #[cfg(not(feature = "webpush"))]
+        (Some(_), Some(_)) => Err(StartupError::MissingFeature {
+            setting: "VAPID_PRIVATE_KEY",
+            feature: "webpush",
+        }),
+        (Some(_), None) => Err(StartupError::MissingSetting {
+            setting: "VAPID_PRIVATE_KEY",
+            needed: "VAPID_PUBLIC_KEY",
+        }),
+        (None, Some(_)) => Err(StartupError::MissingSetting {
+            setting: "VAPID_PUBLIC_KEY",
+            needed: "VAPID_PRIVATE_KEY",
+        }),
+        (None, None) => Ok(None),
+    }
+}
+
+// What the page's service worker receives, to show as a notification.
+pub fn answer_payload(question_title: &str, answer: &AnswerDetail) -> Vec<u8> {
+    json!({
+        "type": "answer.created",
+        "question_uuid": answer.question_uuid.0,
+        "question_title": question_title,
+        "answer_uuid": answer.answer_uuid.0,
+        "excerpt": excerpt(&answer.content),
+    })
+    .to_string()
+    .into_bytes()
+}
+
+#[cfg(feature = "webpush")]
+pub use web_push::*;
+
+// Web Push by hand with ring: VAPID (RFC 8292) to sign requests and aes128gcm
+// (RFC 8291) to encrypt payloads for the browser.
+#[cfg(feature = "webpush")]
+mod web_push {
+    use async_trait::async_trait;
+    use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+    use chrono::{DateTime, Duration, Utc};
+    use ring::{
+        aead, agreement, hkdf,
+        rand::{SecureRandom, SystemRandom},
+        signature::{EcdsaKeyPair, ECDSA_P256_SHA256_FIXED_SIGNING},
+    };
+    use serde_json::json;
+
+    use super::{PushError, Pusher};
+    use crate::models::PushSubscription;
+
+    // How long push services keep a push for a browser that is offline.
+    const TTL_SECS: u32 = 24 * 60 * 60;
+    // Payloads are sent as a single record of at most this many bytes.
+    const RECORD_SIZE: u32 = 4096;
+    // The padding delimiter and the AES-GCM tag.
+    const RECORD_OVERHEAD: usize = 1 + 16;
+    // Push services reject tokens valid for more than a day.
+    const TOKEN_LIFETIME: Duration = Duration::hours(12);
+
+    pub struct WebPusher {
+        client: reqwest::Client,
+        key_pair: EcdsaKeyPair,
+        public_key: String,
+        subject: String,
+    }
+
+    impl WebPusher {
+        pub fn new(private_key: &str, public_key: &str, subject: &str) -> Result<Self, PushError> {
+            if !subject.starts_with("mailto:") && !subject.starts_with("https:") {
+                return Err(PushError::InvalidKey(format!(
+                    "the subject {} is not a mailto: or https: URL",
+                    subject
+                )));
+            }
+            let key_pair = EcdsaKeyPair::from_private_key_and_public_key(
+                &ECDSA_P256_SHA256_FIXED_SIGNING,
+                &decode(private_key)?,
+                &decode(public_key)?,
+                &SystemRandom::new(),
+            )
+            .map_err(|err| PushError::InvalidKey(format!("not a P-256 key pair: {}", err)))?;
+            let client = reqwest::Client::builder()
+                .timeout(std::time::Duration::from_secs(10))
+                .build()
+                .expect("HTTP client should build.");
+
+            Ok(Self {
+                client,
+                key_pair,
+                public_key: public_key.trim_end_matches('=').to_owned(),
+                subject: subject.to_owned(),
+            })
+        }
+
+        // The Authorization header for a push to `endpoint`: a JWT for the push
+        // service's origin, signed with the VAPID key.
+        pub fn authorization(
+            &self,
+            endpoint: &str,
+            now: DateTime<Utc>,
+        ) -> Result<String, PushError> {
+            let endpoint = reqwest::Url::parse(endpoint)
+                .map_err(|err| PushError::Failed(format!("{} is not a URL: {}", endpoint, err)))?;
+            let header = json!({ "typ": "JWT", "alg": "ES256" });
+            let claims = json!({
+                "aud": endpoint.origin().ascii_serialization(),
+                "exp": (now + TOKEN_LIFETIME).timestamp(),
+                "sub": self.subject,
+            });
+            let unsigned = format!(
+                "{}.{}",
+                URL_SAFE_NO_PAD.encode(header.to_string()),
+                URL_SAFE_NO_PAD.encode(claims.to_string())
+            );
+            let signature = self
+                .key_pair
+                .sign(&SystemRandom::new(), unsigned.as_bytes())
+                .map_err(|_| PushError::Failed("signing the VAPID token failed".to_owned()))?;
+
+            Ok(format!(
+                "vapid t={}.{}, k={}",
+                unsigned,
+                URL_SAFE_NO_PAD.encode(signature.as_ref()),
+                self.public_key
+            ))
+        }
+    }
+
+    #[async_trait]
+    impl Pusher for WebPusher {
+        async fn push(
+            &self,
+            subscription: &PushSubscription,
+            payload: &[u8],
+        ) -> Result<(), PushError> {
+            let body = encrypt(payload, &subscription.p256dh, &subscription.auth)?;
+            let authorization = self.authorization(&subscription.endpoint, Utc::now())?;
+
+            let response = self
+                .client
+                .post(&subscription.endpoint)
+                .header("TTL", TTL_SECS)
+                .header("Content-Encoding", "aes128gcm")
+                .header("Content-Type", "application/octet-stream")
+                .header("Authorization", authorization)
+                .body(body)
+                .send()
+                .await
+                .map_err(|err| PushError::Failed(err.to_string()))?;
+
+            match response.status().as_u16() {
+                404 | 410 => Err(PushError::Gone),
+                _ => response
+                    .error_for_status()
+                    .map(|_| ())
+                    .map_err(|err| PushError::Failed(err.to_string())),
+            }
+        }
+    }
+
+    // Encrypts `payload` for a subscription's keys as one aes128gcm record, with a new
+    // key pair and salt each time.
+    pub fn encrypt(payload: &[u8], p256dh: &str, auth: &str) -> Result<Vec<u8>, PushError> {
+        if payload.len() + RECORD_OVERHEAD > RECORD_SIZE as usize {
+            return Err(PushError::Failed(format!(
+                "a payload of {} bytes does not fit in one record",
+                payload.len()
+            )));
+        }
+        let ua_public = decode(p256dh)?;
+        let auth_secret = decode(auth)?;
+
+        let rng = SystemRandom::new();
+        let mut salt = [0; 16];
+        rng.fill(&mut salt)
+            .map_err(|_| PushError::Failed("no randomness for the salt".to_owned()))?;
+        let as_private = agreement::EphemeralPrivateKey::generate(&agreement::ECDH_P256, &rng)
+            .map_err(|_| PushError::Failed("generating a key pair failed".to_owned()))?;
+        let as_public = as_private
+            .compute_public_key()
+            .map_err(|_| PushError::Failed("generating a key pair failed".to_owned()))?;
+        let (cek, nonce) = agreement::agree_ephemeral(
+            as_private,
+            &agreement::UnparsedPublicKey::new(&agreement::ECDH_P256, &ua_public),
+            |ecdh_secret| {
+                content_keys(
+                    ecdh_secret,
+                    &auth_secret,
+                    &ua_public,
+                    as_public.as_ref(),
+                    &salt,
+                )
+            },
+        )
+        .map_err(|_| PushError::InvalidKey("p256dh is not a P-256 public key".to_owned()))?;
+
+        seal(payload, cek, nonce, &salt, as_public.as_ref())
+    }
+
+    // The body of an aes128gcm push: the header naming the salt and the server's public
+    // key, then the payload as its only record.
+    fn seal(
+        payload: &[u8],
+        cek: [u8; 16],
+        nonce: [u8; 12],
+        salt: &[u8],
+        as_public: &[u8],
+    ) -> Result<Vec<u8>, PushError> {
+        // The only record is also the last, which its padding delimiter says.
+        let mut record = payload.to_vec();
+        record.push(2);
+        aead::LessSafeKey::new(
+            aead::UnboundKey::new(&aead::AES_128_GCM, &cek).expect("The key should be 16 bytes."),
+        )
+        .seal_in_place_append_tag(
+            aead::Nonce::assume_unique_for_key(nonce),
+            aead::Aad::empty(),
+            &mut record,
+        )
+        .map_err(|_| PushError::Failed("encrypting the payload failed".to_owned()))?;
+
+        let mut body = Vec::with_capacity(salt.len() + 5 + as_public.len() + record.len());
+        body.extend_from_slice(salt);
+        body.extend_from_slice(&RECORD_SIZE.to_be_bytes());
+        body.push(as_public.len() as u8);
+        body.extend_from_slice(as_public);
+        body.extend_from_slice(&record);
+        Ok(body)
+    }
+
+    // The content encryption key and nonce, derived from the shared secret as both the
+    // server and the browser do.
+    pub fn content_keys(
+        ecdh_secret: &[u8],
+        auth_secret: &[u8],
+        ua_public: &[u8],
+        as_public: &[u8],
+        salt: &[u8],
+    ) -> ([u8; 16], [u8; 12]) {
+        let mut ikm = [0; 32];
+        hkdf_sha256(
+            auth_secret,
+            ecdh_secret,
+            &[b"WebPush: info\0", ua_public, as_public],
+            &mut ikm,
+        );
+        let mut cek = [0; 16];
+        hkdf_sha256(salt, &ikm, &[b"Content-Encoding: aes128gcm\0"], &mut cek);
+        let mut nonce = [0; 12];
+        hkdf_sha256(salt, &ikm, &[b"Content-Encoding: nonce\0"], &mut nonce);
+        (cek, nonce)
+    }
+
+    struct OutputLen(usize);
+
+    impl hkdf::KeyType for OutputLen {
+        fn len(&self) -> usize {
+            self.0
+        }
+    }
+
+    fn hkdf_sha256(salt: &[u8], ikm: &[u8], info: &[&[u8]], out: &mut [u8]) {
+        hkdf::Salt::new(hkdf::HKDF_SHA256, salt)
+            .extract(ikm)
+            .expand(info, OutputLen(out.len()))
+            .and_then(|okm| okm.fill(out))
+            .expect("The output should be short enough for HKDF.");
+    }
+
+    // Browsers send keys as base64url, with or without padding.
+    fn decode(key: &str) -> Result<Vec<u8>, PushError> {
+        URL_SAFE_NO_PAD
+            .decode(key.trim_end_matches('='))
+            .map_err(|err| PushError::InvalidKey(format!("not base64url: {}", err)))
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use ring::signature::{UnparsedPublicKey, ECDSA_P256_SHA256_FIXED};
+
+        use super::*;
+
+        // The application server's key pair from RFC 8291's example.
+        const PRIVATE_KEY: &str = "yfWPiYE-n46HLnH0KqZOF1fJJU3MYrct3AELtAQ-oRw";
+        const PUBLIC_KEY: &str =
+            "BP4z9KsN6nGRTbVYI_c7VJSPQTBtkgcy27mlmlMoZIIgDll6e3vCYLocInmYWAmS6TlzAC8wEqKK6PBru3jl7A8";
+
+        // The example of RFC 8291, section 5: the browser's keys, the shared secret the
+        // server's key pair above agrees with them, and the push it sends.
+        const UA_PUBLIC_KEY: &str =
+            "BCVxsr7N_eNgVRqvHtD0zTZsEc6-VV-JvLexhqUzORcxaOzi6-AYWXvTBHm4bjyPjs7Vd8pZGH6SRpkNtoIAiw4";
+        const AUTH_SECRET: &str = "BTBZMqHH6r4Tts7J_aSIgg";
+        const SALT: &str = "DGv6ra1nlYgDCS1FRnbzlw";
+        const ECDH_SECRET: &str = "kyrL1jIIOHEzg3sM2ZWRHDRB62YACZhhSlknJ672kSs";
+        const MESSAGE: &str = "DGv6ra1nlYgDCS1FRnbzlwAAEABBBP4z9KsN6nGRTbVYI_c7VJSPQTBtkgcy27mlmlMoZIIgDll6e3vCYLocInmYWAmS6TlzAC8wEqKK6PBru3jl7A_yl95bQpu6cVPTpK4Mqgkf1CXztLVBSt2Ks3oZwbuwXPXLWyouBWLVWGNWQexSgSxsj_Qulcy4a-fN";
+
+        #[test]
+        fn pushes_should_match_the_rfc_8291_example() {
+            let salt = decode(SALT).unwrap();
+            let as_public = decode(PUBLIC_KEY).unwrap();
+
+            let (cek, nonce) = content_keys(
+                &decode(ECDH_SECRET).unwrap(),
+                &decode(AUTH_SECRET).unwrap(),
+                &decode(UA_PUBLIC_KEY).unwrap(),
+                &as_public,
+                &salt,
+            );
+            assert_eq!(URL_SAFE_NO_PAD.encode(cek), "oIhVW04MRdy2XN9CiKLxTg");
+            assert_eq!(URL_SAFE_NO_PAD.encode(nonce), "4h_95klXJ5E_qnoN");
+
+            let body = seal(
+                b"When I grow up, I want to be a watermelon",
+                cek,
+                nonce,
+                &salt,
+                &as_public,
+            )
+            .unwrap();
+            assert_eq!(URL_SAFE_NO_PAD.encode(body), MESSAGE);
+        }
+
+        // With new keys each time, as the browser derives them from its side of the exchange.
+        #[test]
+        fn browsers_should_be_able_to_decrypt_pushes() {
+            let rng = SystemRandom::new();
+            let ua_private =
+                agreement::EphemeralPrivateKey::generate(&agreement::ECDH_P256, &rng).unwrap();
+            let ua_public = ua_private.compute_public_key().unwrap();
+            let auth_secret = [7; 16];
+
+            let body = encrypt(
+                b"{\"type\":\"answer.created\"}",
+                &URL_SAFE_NO_PAD.encode(ua_public.as_ref()),
+                &URL_SAFE_NO_PAD.encode(auth_secret),
+            )
+            .unwrap();
+
+            let (salt, rest) = body.split_at(16);
+            assert_eq!(rest[..4], RECORD_SIZE.to_be_bytes());
+            assert_eq!(rest[4], 65);
+            let (as_public, record) = rest[5..].split_at(65);
+            let (cek, nonce) = agreement::agree_ephemeral(
+                ua_private,
+                &agreement::UnparsedPublicKey::new(&agreement::ECDH_P256, as_public),
+                |ecdh_secret| {
+                    content_keys(
+                        ecdh_secret,
+                        &auth_secret,
+                        ua_public.as_ref(),
+                        as_public,
+                        salt,
+                    )
+                },
+            )
+            .unwrap();
+            let mut record = record.to_vec();
+            let plaintext =
+                aead::LessSafeKey::new(aead::UnboundKey::new(&aead::AES_128_GCM, &cek).unwrap())
+                    .open_in_place(
+                        aead::Nonce::assume_unique_for_key(nonce),
+                        aead::Aad::empty(),
+                        &mut record,
+                    )
+                    .unwrap();
+            assert_eq!(plaintext, b"{\"type\":\"answer.created\"}\x02");
+        }
+
+        #[test]
+        fn vapid_tokens_should_be_signed_for_the_push_service() {
+            let pusher =
+                WebPusher::new(PRIVATE_KEY, PUBLIC_KEY, "mailto:admin@example.com").unwrap();
+            let now = DateTime::from_timestamp(1_700_000_000, 0).unwrap();
+
+            let authorization = pusher
+                .authorization("https://push.example.net:8443/send/abc?x=1", now)
+                .unwrap();
+
+            let (token, key) = authorization
+                .strip_prefix("vapid t=")
+                .unwrap()
+                .split_once(", k=")
+                .unwrap();
+            assert_eq!(key, PUBLIC_KEY);
+            let (unsigned, signature) = token.rsplit_once('.').unwrap();
+            UnparsedPublicKey::new(&ECDSA_P256_SHA256_FIXED, decode(PUBLIC_KEY).unwrap())
+                .verify(unsigned.as_bytes(), &decode(signature).unwrap())
+                .unwrap();
+            let claims: serde_json::Value =
+                serde_json::from_slice(&decode(unsigned.split_once('.').unwrap().1).unwrap())
+                    .unwrap();
+            assert_eq!(
+                claims,
+                json!({
+                    "aud": "https://push.example.net:8443",
+                    "exp": 1_700_043_200,
+                    "sub": "mailto:admin@example.com",
+                })
+            );
+        }
+
+        #[test]
+        fn mismatched_vapid_keys_should_be_rejected() {
+            let other_public = URL_SAFE_NO_PAD.encode(
+                agreement::EphemeralPrivateKey::generate(
+                    &agreement::ECDH_P256,
+                    &SystemRandom::new(),
+                )
+                .unwrap()
+                .compute_public_key()
+                .unwrap()
+                .as_ref(),
+            );
+
+            assert!(matches!(
+                WebPusher::new(PRIVATE_KEY, &other_public, "mailto:admin@example.com"),
+                Err(PushError::InvalidKey(_))
+            ));
+            assert!(matches!(
+                WebPusher::new(PRIVATE_KEY, PUBLIC_KEY, "admin@example.com"),
+                Err(PushError::InvalidKey(_))
+            ));
+        }
+    }
+}
@@ -0,0 +1,182 @@
+use std::{
+    collections::HashMap,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use rocket::{
+    fairing::{Fairing, Info, Kind},
+    http::Header,
+    request::{self, FromRequest},
+    Request, Response,
+};
+
+// Past this many keys, expired windows are dropped before a new one is added.
+const PRUNE_AT: usize = 10_000;
+
+// Counts requests per key, e.g. a client IP, in fixed windows. Kept in process, so each
+// instance enforces its own limit.
+pub struct RateLimiter {
+    limit: u32,
+    window: Duration,
+    windows: Mutex<HashMap<String, (Instant, u32)>>,
+}
+
+impl RateLimiter {
+    // A zero `limit` lets everything through.
+    pub fn new(limit: u32, window: Duration) -> Self {
+        Self {
+            limit,
+            window,
+            windows: Mutex::new(HashMap::new()),
+        }
+    }
+
+    // Counts a request of `key`, or refuses it once the window is used up. Either way
+    // returns where the window stands; None while everything is let through.
+    pub fn check(&self, key: &str) -> Result<Option<RateLimit>, RateLimit> {
+        self.check_at(key, Instant::now())
+    }
+
+    fn check_at(&self, key: &str, now: Instant) -> Result<Option<RateLimit>, RateLimit> {
+        if self.limit == 0 {
+            return Ok(None);
+        }
+        let mut windows = self.windows.lock().unwrap();
+        if windows.len() >= PRUNE_AT {
+            windows.retain(|_, (start, _)| now.duration_since(*start) < self.window);
+        }
+
+        let (start, count) = windows.entry(key.to_owned()).or_insert((now, 0));
+        let mut elapsed = now.duration_since(*start);
+        if elapsed >= self.window {
+            *start = now;
+            *count = 0;
+            elapsed = Duration::ZERO;
+        }
+        let mut limit = RateLimit {
+            limit: self.limit,
+            remaining: self.limit - *count,
+            window: self.window,
+            reset: self.window - elapsed,
+        };
+        if limit.remaining == 0 {
+            return Err(limit);
+        }
+        *count += 1;
+        limit.remaining -= 1;
+        Ok(Some(limit))
+    }
+}
+
+// Where a client's window stood after its request; `reset` is how long until it starts
+// over, which is also how long a refused client has to wait.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RateLimit {
+    pub limit: u32,
+    pub remaining: u32,
+    pub window: Duration,
+    pub reset: Duration,
+}
+
+impl RateLimit {
+    // Whole seconds, rounded up so a client waiting that long is let through.
+    pub fn reset_secs(&self) -> u64 {
+        self.reset.as_secs() + u64::from(self.reset.subsec_nanos() > 0)
+    }
+}
+
+// The limit a route checked the request against, for `RateLimitHeaders` to report.
+#[derive(Default)]
+pub struct RateLimitSlot(Mutex<Option<RateLimit>>);
+
+impl RateLimitSlot {
+    pub fn record(&self, limit: RateLimit) {
+        *self.0.lock().unwrap() = Some(limit);
+    }
+}
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for &'r RateLimitSlot {
+    type Error = ();
+
+    async fn from_request(req: &'r Request<'_>) -> request::Outcome<Self, Self::Error> {
+        request::Outcome::Success(req.local_cache(RateLimitSlot::default))
+    }
+}
+
+// Tells clients of rate limited routes where they stand, on successes and 429s alike, so
+// they can slow down before being refused. Other routes have no limit to report.
+pub struct RateLimitHeaders;
+
+#[rocket::async_trait]
+impl Fairing for RateLimitHeaders {
+    fn info(&self) -> Info {
+        Info {
+            name: "Report rate limits in X-RateLimit headers",
+            kind: Kind::Response,
+        }
+    }
+
+    async fn on_response<'r>(&self, request: &'r Request<'_>, response: &mut Response<'r>) {
+        let Some(limit) = *request
+            .local_cache(RateLimitSlot::default)
+            .0
+            .lock()
+            .unwrap()
+        else {
+            return;
+        };
+        response.set_header(Header::new("X-RateLimit-Limit", limit.limit.to_string()));
+        response.set_header(Header::new(
+            "X-RateLimit-Remaining",
+            limit.remaining.to_string(),
+        ));
+        response.set_header(Header::new(
+            "X-RateLimit-Reset",
+            limit.reset_secs().to_string(),
+        ));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rate_limiter_should_count_each_key_per_window() {
+        let limiter = RateLimiter::new(2, Duration::from_secs(60));
+        let start = Instant::now();
+
+        assert!(limiter.check_at("a", start).is_ok());
+        assert_eq!(
+            limiter.check_at("a", start + Duration::from_secs(10)),
+            Ok(Some(RateLimit {
+                limit: 2,
+                remaining: 0,
+                window: Duration::from_secs(60),
+                reset: Duration::from_secs(50),
+            }))
+        );
+        assert_eq!(
+            limiter
+                .check_at("a", start + Duration::from_secs(15))
+                .map_err(|limit| limit.reset),
+            Err(Duration::from_secs(45))
+        );
+        assert!(
+            limiter.check_at("b", start).is_ok(),
+            "keys are counted apart"
+        );
+        assert!(
+            limiter
+                .check_at("a", start + Duration::from_secs(60))
+                .is_ok(),
+            "a new window starts over"
+        );
+        assert_eq!(
+            RateLimiter::new(0, Duration::from_secs(60)).check_at("a", start),
+            Ok(None)
+        );
+    }
+}
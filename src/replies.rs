@@ -0,0 +1,175 @@
+use sha2::{Digest, Sha256};
+use uuid::Uuid;
+
+use crate::{
+    config::AppConfig, handlers::admin::tokens_match, models::QuestionUuid, startup::StartupError,
+};
+
+// Enough hex of the signature that guessing an address for a question takes 2^64 tries.
+const SIGNATURE_CHARS: usize = 16;
+
+// Reply-To addresses of answer notifications, `reply+<question>.<signature>@<domain>`.
+// The signature keeps anyone from answering by email with a made-up address, so the
+// inbound webhook needs no secret of its own.
+#[derive(Clone)]
+pub struct ReplyAddresses {
+    domain: String,
+    secret: String,
+}
+
+pub fn addresses_from_config(config: &AppConfig) -> Result<Option<ReplyAddresses>, StartupError> {
+    match (&config.reply_email_domain, &config.reply_email_secret) {
+        (Some(domain), Some(secret)) => Ok(Some(ReplyAddresses::new(domain, secret))),
+        (Some(_), None) => Err(StartupError::MissingSetting {
+            setting: "REPLY_EMAIL_DOMAIN",
+            needed: "REPLY_EMAIL_SECRET",
+        }),
+        (None, _) => Ok(None),
+    }
+}
+
+impl ReplyAddresses {
+    pub fn new(domain: &str, secret: &str) -> Self {
+        Self {
+            domain: domain.to_lowercase(),
+            secret: secret.to_owned(),
+        }
+    }
+
+    pub fn address(&self, question_uuid: QuestionUuid) -> String {
+        format!(
+            "reply+{}.{}@{}",
+            question_uuid.0.simple(),
+            self.signature(question_uuid),
+            self.domain
+        )
+    }
+
+    // The question of the first reply address among `recipients`, which is a header
+    // value such as `"Q&A" <reply+...@example.com>, someone@example.com`.
+    pub fn question_for(&self, recipients: &str) -> Option<QuestionUuid> {
+        recipients.split(',').find_map(|recipient| {
+            let address = match recipient.split_once('<') {
+                Some((_, rest)) => rest.split('>').next().unwrap_or_default(),
+                None => recipient,
+            };
+            let (local, domain) = address.trim().rsplit_once('@')?;
+            if !domain.eq_ignore_ascii_case(&self.domain) {
+                return None;
+            }
+            let (question, signature) = local
+                .strip_prefix("reply+")
+                .or_else(|| local.strip_prefix("REPLY+"))?
+                .split_once('.')?;
+            let question_uuid = QuestionUuid(Uuid::try_parse(question).ok()?);
+
+            tokens_match(&signature.to_lowercase(), &self.signature(question_uuid))
+                .then_some(question_uuid)
+        })
+    }
+
+    fn signature(&self, question_uuid: QuestionUuid) -> String {
+        hmac_sha256(self.secret.as_bytes(), question_uuid.0.as_bytes())
+            .iter()
+            .map(|byte| format!("{:02x}", byte))
+            .collect::<String>()[..SIGNATURE_CHARS]
+            .to_owned()
+    }
+}
+
+pub(crate) fn hmac_sha256(key: &[u8], message: &[u8]) -> [u8; 32] {
+    let mut block = [0u8; 64];
+    if key.len() > block.len() {
+        block[..32].copy_from_slice(&Sha256::digest(key));
+    } else {
+        block[..key.len()].copy_from_slice(key);
+    }
+    let inner = Sha256::new()
+        .chain_update(block.map(|byte| byte ^ 0x36))
+        .chain_update(message)
+        .finalize();
+    Sha256::new()
+        .chain_update(block.map(|byte| byte ^ 0x5c))
+        .chain_update(inner)
+        .finalize()
+        .into()
+}
+
+// What the sender wrote above the quoted notification: quoted lines, the headers mail
+// clients put above a quote, and signatures are dropped.
+pub fn reply_text(body: &str) -> String {
+    let body = body.replace("\r\n", "\n");
+    let lines: Vec<&str> = body.lines().collect();
+
+    let mut kept = vec![];
+    for (i, line) in lines.iter().enumerate() {
+        let next = lines.get(i + 1).map(|line| line.trim()).unwrap_or_default();
+        let trimmed = line.trim();
+        let quote_header = (trimmed.starts_with("On ")
+            && (trimmed.ends_with("wrote:") || next.ends_with("wrote:")))
+            || (trimmed.starts_with("From:")
+                && (next.starts_with("Sent:") || next.starts_with("Date:")))
+            || trimmed.starts_with("-----Original Message-----")
+            || trimmed.starts_with("________________________________");
+        if quote_header || *line == "-- " || *line == "--" {
+            break;
+        }
+        if !trimmed.starts_with('>') {
+            kept.push(line.trim_end());
+        }
+    }
+    kept.join("\n").trim().to_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hmac_sha256_should_match_rfc_4231() {
+        let mac = hmac_sha256(b"Jefe", b"what do ya want for nothing?");
+        let hex: String = mac.iter().map(|byte| format!("{:02x}", byte)).collect();
+        assert_eq!(
+            hex,
+            "5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843"
+        );
+    }
+
+    #[test]
+    fn question_for_should_only_accept_signed_addresses() {
+        let replies = ReplyAddresses::new("Reply.Example.com", "secret");
+        let question_uuid = QuestionUuid::new_v4();
+        let address = replies.address(question_uuid);
+
+        assert_eq!(replies.question_for(&address), Some(question_uuid));
+        assert_eq!(
+            replies.question_for(&format!(
+                "someone@example.com, \"Q&A\" <{}>",
+                address.to_uppercase()
+            )),
+            Some(question_uuid)
+        );
+        let other = ReplyAddresses::new("reply.example.com", "other");
+        assert_eq!(other.question_for(&address), None);
+        let forged = address.replace(
+            &question_uuid.0.simple().to_string(),
+            &Uuid::new_v4().simple().to_string(),
+        );
+        assert_eq!(replies.question_for(&forged), None);
+        assert_eq!(
+            replies.question_for(&address.replace("reply.example.com", "example.com")),
+            None
+        );
+    }
+
+    #[test]
+    fn reply_text_should_drop_quotes_and_signatures() {
+        let gmail = "Restart it.\r\n\r\nOn Tue, 3 Oct 2023 at 10:00, Q&A <reply+x@example.com>\r\nwrote:\r\n> \"How?\" has a new answer:\r\n";
+        assert_eq!(reply_text(gmail), "Restart it.");
+        let outlook = "Restart it.\nTwice.\n\nFrom: Q&A\nSent: Tuesday\nSubject: New answer\n";
+        assert_eq!(reply_text(outlook), "Restart it.\nTwice.");
+        let inline = "> Did you restart it?\nYes, twice.  \n-- \nSam";
+        assert_eq!(reply_text(inline), "Yes, twice.");
+        assert_eq!(reply_text("> only a quote\n"), "");
+    }
+}
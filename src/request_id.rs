@@ -0,0 +1,83 @@
+use rocket::fairing::{Fairing, Info, Kind};
+use rocket::http::Header;
+use rocket::request::{self, FromRequest};
+use rocket::{Data, Request, Response};
+use uuid::Uuid;
+
+pub const REQUEST_ID_HEADER: &str = "X-Request-Id";
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct RequestId(pub String);
+
+impl RequestId {
+    pub fn generate() -> Self {
+        RequestId(Uuid::new_v4().to_string())
+    }
+
+    // Reuses the caller's id (e.g. from a load balancer) when it looks sane.
+    fn from_header(value: Option<&str>) -> Self {
+        match value {
+            Some(id) if !id.is_empty() && id.len() <= 128 && id.is_ascii() => {
+                RequestId(id.to_owned())
+            }
+            _ => RequestId::generate(),
+        }
+    }
+}
+
+impl std::fmt::Display for RequestId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for &'r RequestId {
+    type Error = ();
+
+    async fn from_request(req: &'r Request<'_>) -> request::Outcome<Self, Self::Error> {
+        request::Outcome::Success(req.local_cache(RequestId::generate))
+    }
+}
+
+pub struct RequestIdFairing;
+
+#[rocket::async_trait]
+impl Fairing for RequestIdFairing {
+    fn info(&self) -> Info {
+        Info {
+            name: "Assign a request id to every request",
+            kind: Kind::Request | Kind::Response,
+        }
+    }
+
+    async fn on_request(&self, request: &mut Request<'_>, _: &mut Data<'_>) {
+        let id = RequestId::from_header(request.headers().get_one(REQUEST_ID_HEADER));
+        request.local_cache(|| id);
+    }
+
+    async fn on_response<'r>(&self, request: &'r Request<'_>, response: &mut Response<'r>) {
+        let id = request.local_cache(RequestId::generate);
+        response.set_header(Header::new(REQUEST_ID_HEADER, id.0.clone()));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn from_header_should_reuse_valid_id() {
+        let id = RequestId::from_header(Some("abc-123"));
+        assert_eq!(id, RequestId("abc-123".to_owned()));
+    }
+
+    #[test]
+    fn from_header_should_generate_id_when_missing_or_invalid() {
+        assert!(Uuid::parse_str(&RequestId::from_header(None).0).is_ok());
+        assert!(Uuid::parse_str(&RequestId::from_header(Some("")).0).is_ok());
+
+        let too_long = "a".repeat(129);
+        assert!(Uuid::parse_str(&RequestId::from_header(Some(&too_long)).0).is_ok());
+    }
+}
@@ -0,0 +1,606 @@
+use std::{sync::Arc, time::Duration};
+
+use async_trait::async_trait;
+use log::warn;
+use thiserror::Error;
+use tokio::{
+    io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader},
+    net::TcpStream,
+    sync::Notify,
+};
+
+use crate::{
+    attachments::{ObjectStore, StoreError},
+    config::{AppConfig, ScannerKind},
+    models::{Attachment, AttachmentStatus, DBError},
+    notifications::{self, Mailer},
+    persistence::attachment::AttachmentDao,
+    startup::StartupError,
+    thumbnails::Thumbnails,
+};
+
+// Attachments scanned per look at the database.
+const BATCH_SIZE: u32 = 20;
+
+// Uploads wake the worker at once; this is for ones other instances took, and for
+// retrying while the scanner is unreachable.
+const POLL_INTERVAL: Duration = Duration::from_secs(30);
+
+// clamd reads INSTREAM data in chunks of at most this many bytes.
+const CLAMD_CHUNK: usize = 64 * 1024;
+
+// Longest scanner reply that is read, so a misbehaving server cannot exhaust memory.
+const MAX_REPLY: u64 = 64 * 1024;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Verdict {
+    Clean,
+    // Why the scanner objects, e.g. the signature it found.
+    Flagged(String),
+}
+
+#[derive(Error, Debug)]
+pub enum ScanError {
+    // The scanner could not be asked; the attachment is scanned again later.
+    #[error("Content scanner error: {0}")]
+    Scanner(String),
+    #[error(transparent)]
+    Store(#[from] StoreError),
+    #[error(transparent)]
+    Database(#[from] DBError),
+}
+
+// Checks uploaded bytes, e.g. for malware, before they are served.
+#[async_trait]
+pub trait ContentScanner {
+    async fn scan(&self, bytes: &[u8]) -> Result<Verdict, ScanError>;
+}
+
+// None while CONTENT_SCANNER is unset, so uploads are ready at once.
+pub fn scanner_from_config(
+    config: &AppConfig,
+) -> Result<Option<Arc<dyn ContentScanner + Send + Sync>>, StartupError> {
+    match config.content_scanner {
+        ScannerKind::None => Ok(None),
+        ScannerKind::Noop => Ok(Some(Arc::new(NoopScanner))),
+        ScannerKind::ClamAv => Ok(Some(Arc::new(ClamAvScanner::new(
+            config.clamav_address.clone(),
+            config.scan_timeout,
+        )))),
+        ScannerKind::Icap => {
+            let url = config
+                .icap_url
+                .as_deref()
+                .ok_or(StartupError::MissingSetting {
+                    setting: "CONTENT_SCANNER=icap",
+                    needed: "ICAP_URL",
+                })?;
+            let scanner = IcapScanner::new(url, config.scan_timeout).map_err(|reason| {
+                StartupError::InvalidSetting {
+                    setting: "ICAP_URL",
+                    expected: "icap://host[:port]/service",
+                    reason,
+                }
+            })?;
+            Ok(Some(Arc::new(scanner)))
+        }
+    }
+}
+
+// Scans attachments waiting in the `scanning` status in the background. The queue is
+// the attachments table, so nothing is lost on restarts; attachments stay unserved
+// while the scanner is unreachable.
+#[derive(Clone)]
+pub struct Scans {
+    wake: Arc<Notify>,
+}
+
+impl Scans {
+    // Starts the worker, which scans one attachment at a time. Clean images are passed
+    // on to `thumbnails`.
+    pub fn spawn(
+        attachment_dao: Arc<dyn AttachmentDao + Send + Sync>,
+        object_store: Arc<dyn ObjectStore + Send + Sync>,
+        scanner: Arc<dyn ContentScanner + Send + Sync>,
+        mailer: Arc<dyn Mailer + Send + Sync>,
+        moderator_emails: Vec<String>,
+        thumbnails: Option<Thumbnails>,
+    ) -> Self {
+        let wake = Arc::new(Notify::new());
+        let worker_wake = wake.clone();
+
+        tokio::spawn(async move {
+            loop {
+                let scanned = scan_batch(
+                    attachment_dao.as_ref(),
+                    object_store.as_ref(),
+                    scanner.as_ref(),
+                    mailer.as_ref(),
+                    &moderator_emails,
+                    thumbnails.as_ref(),
+                )
+                .await;
+                match scanned {
+                    Ok(count) if count == BATCH_SIZE as usize => continue,
+                    Ok(_) => {}
+                    Err(err) => warn!("Scanning attachments failed: {}", err),
+                }
+                tokio::select! {
+                    _ = worker_wake.notified() => {}
+                    _ = tokio::time::sleep(POLL_INTERVAL) => {}
+                }
+            }
+        });
+
+        Self { wake }
+    }
+
+    // Called after an upload is marked as scanning.
+    pub fn wake(&self) {
+        self.wake.notify_one();
+    }
+}
+
+// Stops at the first failure, which is most likely the scanner being down.
+async fn scan_batch(
+    attachment_dao: &(dyn AttachmentDao + Send + Sync),
+    object_store: &(dyn ObjectStore + Send + Sync),
+    scanner: &(dyn ContentScanner + Send + Sync),
+    mailer: &(dyn Mailer + Send + Sync),
+    moderator_emails: &[String],
+    thumbnails: Option<&Thumbnails>,
+) -> Result<usize, ScanError> {
+    let batch = attachment_dao.scanning_attachments(BATCH_SIZE).await?;
+    for attachment in &batch {
+        let scanned = scan_attachment(
+            attachment,
+            scanner,
+            attachment_dao,
+            object_store,
+            mailer,
+            moderator_emails,
+        )
+        .await?;
+        if let (Some(scanned), Some(thumbnails)) = (scanned, thumbnails) {
+            if scanned.status == AttachmentStatus::Ready {
+                thumbnails.enqueue(scanned);
+            }
+        }
+    }
+    Ok(batch.len())
+}
+
+// Scans a scanning attachment and makes it ready, or quarantines it and mails the
+// moderators. None if it was no longer scanning.
+pub async fn scan_attachment(
+    attachment: &Attachment,
+    scanner: &(dyn ContentScanner + Send + Sync),
+    attachment_dao: &(dyn AttachmentDao + Send + Sync),
+    object_store: &(dyn ObjectStore + Send + Sync),
+    mailer: &(dyn Mailer + Send + Sync),
+    moderator_emails: &[String],
+) -> Result<Option<Attachment>, ScanError> {
+    let verdict = match object_store.get(&attachment.storage_key).await {
+        Ok(bytes) => scanner.scan(&bytes).await?,
+        // It could never be scanned, so it must not become servable either.
+        Err(StoreError::NotFound(_)) => {
+            Verdict::Flagged("Its bytes are missing from the object store.".to_owned())
+        }
+        Err(err) => return Err(err.into()),
+    };
+    let status = match verdict {
+        Verdict::Clean => AttachmentStatus::Ready,
+        Verdict::Flagged(_) => AttachmentStatus::Quarantined,
+    };
+    let Some(scanned) = attachment_dao
+        .finish_scan(attachment.attachment_uuid, status)
+        .await?
+    else {
+        return Ok(None);
+    };
+
+    if let Verdict::Flagged(reason) = verdict {
+        warn!(
+            "Quarantined attachment {}: {}",
+            scanned.attachment_uuid, reason
+        );
+        for to in moderator_emails {
+            let email = notifications::quarantine_email(to, &scanned, &reason);
+            if let Err(err) = mailer.send(&email).await {
+                warn!("Emailing {} failed: {}", email.to, err);
+            }
+        }
+    }
+    Ok(Some(scanned))
+}
+
+// Passes everything.
+pub struct NoopScanner;
+
+#[async_trait]
+impl ContentScanner for NoopScanner {
+    async fn scan(&self, _bytes: &[u8]) -> Result<Verdict, ScanError> {
+        Ok(Verdict::Clean)
+    }
+}
+
+fn scanner_unreachable(err: std::io::Error) -> ScanError {
+    ScanError::Scanner(err.to_string())
+}
+
+// A clamd daemon, sent the bytes with the INSTREAM command over TCP.
+pub struct ClamAvScanner {
+    address: String,
+    timeout: Duration,
+}
+
+impl ClamAvScanner {
+    pub fn new(address: String, timeout: Duration) -> Self {
+        Self { address, timeout }
+    }
+
+    async fn instream(&self, bytes: &[u8]) -> Result<Verdict, ScanError> {
+        let mut stream = TcpStream::connect(&self.address)
+            .await
+            .map_err(scanner_unreachable)?;
+        let sent = async {
+            stream.write_all(b"zINSTREAM\0").await?;
+            for chunk in bytes.chunks(CLAMD_CHUNK) {
+                stream
+                    .write_all(&(chunk.len() as u32).to_be_bytes())
+                    .await?;
+                stream.write_all(chunk).await?;
+            }
+            stream.write_all(&0u32.to_be_bytes()).await
+        }
+        .await;
+
+        // clamd replies and hangs up even when it stops reading early, e.g. past its
+        // StreamMaxLength, so the reply is read before a failed write is reported.
+        let mut reply = vec![];
+        (&mut stream)
+            .take(MAX_REPLY)
+            .read_to_end(&mut reply)
+            .await
+            .map_err(scanner_unreachable)?;
+        if reply.is_empty() {
+            sent.map_err(scanner_unreachable)?;
+        }
+        clamd_verdict(&String::from_utf8_lossy(&reply))
+    }
+}
+
+#[async_trait]
+impl ContentScanner for ClamAvScanner {
+    async fn scan(&self, bytes: &[u8]) -> Result<Verdict, ScanError> {
+        tokio::time::timeout(self.timeout, self.instream(bytes))
+            .await
+            .map_err(|_| ScanError::Scanner("clamd did not reply in time".to_owned()))?
+    }
+}
+
+// `stream: OK`, `stream: <signature> FOUND` or `<problem> ERROR`. Files clamd cannot
+// scan, e.g. ones past its size limit, are flagged rather than retried forever.
+fn clamd_verdict(reply: &str) -> Result<Verdict, ScanError> {
+    let reply = reply.trim_end_matches(['\0', '\n']);
+    let result = reply.strip_prefix("stream: ").unwrap_or(reply);
+    if result == "OK" {
+        Ok(Verdict::Clean)
+    } else if let Some(signature) = result.strip_suffix(" FOUND") {
+        Ok(Verdict::Flagged(format!("ClamAV found {}.", signature)))
+    } else if let Some(problem) = result.strip_suffix(" ERROR") {
+        Ok(Verdict::Flagged(format!(
+            "ClamAV could not scan it: {}.",
+            problem
+        )))
+    } else {
+        Err(ScanError::Scanner(format!(
+            "Unexpected clamd reply: {:?}",
+            reply
+        )))
+    }
+}
+
+// An ICAP server (RFC 3507), sent the bytes as the body of an HTTP response to modify.
+// It answers 204 for clean content, and 200 with a replacement for anything it blocks.
+pub struct IcapScanner {
+    // `host:port`, to connect to and for the Host header.
+    authority: String,
+    url: String,
+    timeout: Duration,
+}
+
+impl IcapScanner {
+    // `url` is `icap://host[:port]/service`; the port defaults to 1344.
+    pub fn new(url: &str, timeout: Duration) -> Result<Self, String> {
+        let rest = url
+            .strip_prefix("icap://")
+            .ok_or_else(|| format!("{} is not an icap:// URL", url))?;
+        let (authority, _) = rest.split_once('/').unwrap_or((rest, ""));
+        if authority.is_empty() {
+            return Err(format!("{} has no host", url));
+        }
+        let authority = if authority.rsplit_once(':').is_some() {
+            authority.to_owned()
+        } else {
+            format!("{}:1344", authority)
+        };
+        Ok(Self {
+            authority,
+            url: url.to_owned(),
+            timeout,
+        })
+    }
+
+    async fn respmod(&self, bytes: &[u8]) -> Result<Verdict, ScanError> {
+        let mut stream = TcpStream::connect(&self.authority)
+            .await
+            .map_err(scanner_unreachable)?;
+        let http_head = format!(
+            "HTTP/1.1 200 OK\r\nContent-Type: application/octet-stream\r\nContent-Length: {}\r\n\r\n",
+            bytes.len()
+        );
+        let icap_head = format!(
+            "RESPMOD {} ICAP/1.0\r\nHost: {}\r\nAllow: 204\r\nEncapsulated: res-hdr=0, res-body={}\r\n\r\n",
+            self.url,
+            self.authority,
+            http_head.len()
+        );
+        let mut request = [icap_head.into_bytes(), http_head.into_bytes()].concat();
+        if !bytes.is_empty() {
+            request.extend_from_slice(format!("{:x}\r\n", bytes.len()).as_bytes());
+            request.extend_from_slice(bytes);
+            request.extend_from_slice(b"\r\n");
+        }
+        request.extend_from_slice(b"0\r\n\r\n");
+        stream
+            .write_all(&request)
+            .await
+            .map_err(scanner_unreachable)?;
+
+        // Only the ICAP head matters; a replacement body is not read.
+        let mut reader = BufReader::new(stream).take(MAX_REPLY);
+        let mut head = vec![];
+        loop {
+            let mut line = String::new();
+            if reader
+                .read_line(&mut line)
+                .await
+                .map_err(scanner_unreachable)?
+                == 0
+            {
+                break;
+            }
+            let line = line.trim_end().to_owned();
+            if line.is_empty() {
+                break;
+            }
+            head.push(line);
+        }
+        icap_verdict(&head)
+    }
+}
+
+#[async_trait]
+impl ContentScanner for IcapScanner {
+    async fn scan(&self, bytes: &[u8]) -> Result<Verdict, ScanError> {
+        tokio::time::timeout(self.timeout, self.respmod(bytes))
+            .await
+            .map_err(|_| ScanError::Scanner("The ICAP server did not reply in time".to_owned()))?
+    }
+}
+
+fn icap_verdict(head: &[String]) -> Result<Verdict, ScanError> {
+    let status = head.first().map(String::as_str).unwrap_or_default();
+    match status.split(' ').nth(1) {
+        Some("204") => Ok(Verdict::Clean),
+        Some("200") => {
+            // c-icap and most AV services name what they found in one of these.
+            let found = head.iter().find_map(|line| {
+                let (name, value) = line.split_once(':')?;
+                ["X-Infection-Found", "X-Violations-Found", "X-Virus-ID"]
+                    .iter()
+                    .any(|header| name.eq_ignore_ascii_case(header))
+                    .then(|| value.trim().to_owned())
+            });
+            Ok(Verdict::Flagged(match found {
+                Some(found) => format!("The ICAP server blocked it: {}.", found),
+                None => "The ICAP server blocked it.".to_owned(),
+            }))
+        }
+        _ => Err(ScanError::Scanner(format!(
+            "Unexpected ICAP reply: {:?}",
+            status
+        ))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio::{net::TcpListener, sync::Mutex};
+
+    use super::*;
+    use crate::{
+        attachments::{self, LocalObjectStore},
+        models::{empty_metadata, AttachmentUuid, NewAttachment, Question},
+        notifications::{Email, MailError},
+        persistence::{
+            memory::{InMemoryAttachmentDao, InMemoryQuestionDao, MemoryStore},
+            question_dao::QuestionDao,
+        },
+    };
+
+    #[test]
+    fn scanner_replies_should_be_read_as_verdicts() {
+        assert_eq!(clamd_verdict("stream: OK\0").unwrap(), Verdict::Clean);
+        assert_eq!(
+            clamd_verdict("stream: Eicar-Test-Signature FOUND\0").unwrap(),
+            Verdict::Flagged("ClamAV found Eicar-Test-Signature.".to_owned())
+        );
+        assert!(matches!(
+            clamd_verdict("INSTREAM size limit exceeded. ERROR\0").unwrap(),
+            Verdict::Flagged(_)
+        ));
+        assert!(clamd_verdict("").is_err());
+
+        let head = |lines: &[&str]| {
+            lines
+                .iter()
+                .map(|line| line.to_string())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            icap_verdict(&head(&["ICAP/1.0 204 No Content"])).unwrap(),
+            Verdict::Clean
+        );
+        assert_eq!(
+            icap_verdict(&head(&[
+                "ICAP/1.0 200 OK",
+                "X-Infection-Found: Type=0; Threat=EICAR;"
+            ]))
+            .unwrap(),
+            Verdict::Flagged("The ICAP server blocked it: Type=0; Threat=EICAR;.".to_owned())
+        );
+        assert!(icap_verdict(&head(&["ICAP/1.0 500 Server Error"])).is_err());
+    }
+
+    #[tokio::test]
+    async fn clamd_should_be_sent_the_bytes_in_chunks() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap().to_string();
+        let server = tokio::spawn(async move {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let mut command = [0; 10];
+            socket.read_exact(&mut command).await.unwrap();
+            assert_eq!(&command, b"zINSTREAM\0");
+            let mut received = vec![];
+            loop {
+                let length = socket.read_u32().await.unwrap() as usize;
+                if length == 0 {
+                    break;
+                }
+                let mut chunk = vec![0; length];
+                socket.read_exact(&mut chunk).await.unwrap();
+                received.extend(chunk);
+            }
+            socket
+                .write_all(b"stream: Eicar-Test-Signature FOUND\0")
+                .await
+                .unwrap();
+            received
+        });
+
+        let bytes = vec![7; CLAMD_CHUNK + 1];
+        let verdict = ClamAvScanner::new(address, Duration::from_secs(5))
+            .scan(&bytes)
+            .await
+            .unwrap();
+
+        assert!(matches!(verdict, Verdict::Flagged(_)));
+        assert_eq!(server.await.unwrap(), bytes);
+    }
+
+    struct FlaggingScanner;
+
+    #[async_trait]
+    impl ContentScanner for FlaggingScanner {
+        async fn scan(&self, bytes: &[u8]) -> Result<Verdict, ScanError> {
+            Ok(match bytes.starts_with(b"X5O!") {
+                true => Verdict::Flagged("ClamAV found Eicar-Test-Signature.".to_owned()),
+                false => Verdict::Clean,
+            })
+        }
+    }
+
+    #[derive(Default)]
+    struct RecordingMailer(Mutex<Vec<Email>>);
+
+    #[async_trait]
+    impl Mailer for RecordingMailer {
+        async fn send(&self, email: &Email) -> Result<(), MailError> {
+            self.0.lock().await.push(email.clone());
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn flagged_attachments_should_be_quarantined_and_reported() {
+        let store = MemoryStore::new();
+        let question = InMemoryQuestionDao::new(store.clone())
+            .create_question(Question {
+                title: "title".to_owned(),
+                description: None,
+                metadata: empty_metadata(),
+            })
+            .await
+            .unwrap();
+        let attachment_dao = InMemoryAttachmentDao::new(store);
+        let root = std::env::temp_dir().join(format!("qa-scanning-{}", AttachmentUuid::new_v4()));
+        let object_store = LocalObjectStore::new(root.clone());
+        let mailer = RecordingMailer::default();
+        let moderators = ["mod@example.com".to_owned()];
+
+        let mut scanned = vec![];
+        for bytes in [&b"X5O!P%@AP"[..], &b"hello"[..]] {
+            let attachment_uuid = AttachmentUuid::new_v4();
+            let attachment = attachment_dao
+                .create_attachment(NewAttachment {
+                    attachment_uuid,
+                    question_uuid: question.question_uuid,
+                    answer_uuid: None,
+                    filename: "file.txt".to_owned(),
+                    content_type: "text/plain".to_owned(),
+                    size_bytes: bytes.len() as i64,
+                    storage_key: attachments::storage_key(attachment_uuid),
+                    status: AttachmentStatus::Scanning,
+                })
+                .await
+                .unwrap();
+            object_store
+                .put(&attachment.storage_key, "text/plain", bytes.to_vec())
+                .await
+                .unwrap();
+            let result = scan_attachment(
+                &attachment,
+                &FlaggingScanner,
+                &attachment_dao,
+                &object_store,
+                &mailer,
+                &moderators,
+            )
+            .await
+            .unwrap()
+            .unwrap();
+            scanned.push(result);
+        }
+
+        assert_eq!(scanned[0].status, AttachmentStatus::Quarantined);
+        assert_eq!(scanned[1].status, AttachmentStatus::Ready);
+        assert!(attachment_dao
+            .scanning_attachments(10)
+            .await
+            .unwrap()
+            .is_empty());
+        let emails = mailer.0.lock().await;
+        assert_eq!(emails.len(), 1);
+        assert_eq!(emails[0].to, "mod@example.com");
+        assert_eq!(emails[0].subject, "Attachment \"file.txt\" quarantined");
+        assert!(emails[0].body.contains("Eicar-Test-Signature"));
+        assert_eq!(
+            scan_attachment(
+                &scanned[0],
+                &FlaggingScanner,
+                &attachment_dao,
+                &object_store,
+                &mailer,
+                &moderators,
+            )
+            .await
+            .unwrap(),
+            None,
+            "attachments are scanned once"
+        );
+
+        tokio::fs::remove_dir_all(root).await.unwrap();
+    }
+}
@@ -0,0 +1,401 @@
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use log::warn;
+use thiserror::Error;
+
+use crate::{
+    config::{AppConfig, SearchBackend},
+    models::{DBError, QuestionDetail, QuestionUuid},
+    persistence::search::{Highlight, SearchDao, TitleMatch},
+    startup::StartupError,
+};
+
+#[derive(Error, Debug)]
+pub enum SearchError {
+    #[error(transparent)]
+    Database(#[from] DBError),
+    #[cfg(feature = "elasticsearch")]
+    #[error("Search request failed: {0}")]
+    Request(String),
+}
+
+#[async_trait]
+pub trait SearchProvider {
+    // Best match first.
+    async fn search(&self, query: &str, limit: u32) -> Result<Vec<QuestionDetail>, SearchError>;
+}
+
+// Postgres full text search, or word matching in memory.
+pub struct DatabaseSearch(pub Arc<dyn SearchDao + Send + Sync>);
+
+#[async_trait]
+impl SearchProvider for DatabaseSearch {
+    async fn search(&self, query: &str, limit: u32) -> Result<Vec<QuestionDetail>, SearchError> {
+        Ok(self.0.search_questions(query, limit).await?)
+    }
+}
+
+// Managed as `Option<Search>`; None while the backend cannot search.
+pub struct Search {
+    pub provider: Box<dyn SearchProvider + Send + Sync>,
+    // Asked while `provider` fails; None when `provider` is the database already.
+    pub fallback: Option<Box<dyn SearchProvider + Send + Sync>>,
+    // Completes titles itself whatever the provider, as an index kept from the outbox
+    // lags behind what was just asked.
+    pub search_dao: Arc<dyn SearchDao + Send + Sync>,
+}
+
+impl Search {
+    pub async fn search(
+        &self,
+        query: &str,
+        limit: u32,
+    ) -> Result<Vec<QuestionDetail>, SearchError> {
+        match (self.provider.search(query, limit).await, &self.fallback) {
+            (Err(err), Some(fallback)) => {
+                warn!("Search failed, falling back to the database: {}", err);
+                fallback.search(query, limit).await
+            }
+            (result, _) => result,
+        }
+    }
+
+    pub async fn complete_titles(
+        &self,
+        text: &str,
+        limit: u32,
+    ) -> Result<Vec<TitleMatch>, DBError> {
+        self.search_dao.complete_titles(text, limit).await
+    }
+
+    pub async fn similar_titles(
+        &self,
+        text: &str,
+        threshold: f32,
+        excluded: &[QuestionUuid],
+        limit: u32,
+    ) -> Result<Vec<TitleMatch>, DBError> {
+        self.search_dao
+            .similar_titles(text, threshold, excluded, limit)
+            .await
+    }
+
+    // Postgres marks the matches whatever the provider, so they follow its stemming.
+    pub async fn highlight(
+        &self,
+        query: &str,
+        questions: &[QuestionDetail],
+    ) -> Result<Vec<Highlight>, DBError> {
+        self.search_dao.highlight(query, questions).await
+    }
+}
+
+pub fn search_from_config(
+    config: &AppConfig,
+    search_dao: Arc<dyn SearchDao + Send + Sync>,
+) -> Result<Search, StartupError> {
+    match config.search_backend {
+        SearchBackend::Database => Ok(Search {
+            provider: Box::new(DatabaseSearch(search_dao.clone())),
+            fallback: None,
+            search_dao,
+        }),
+        #[cfg(feature = "elasticsearch")]
+        SearchBackend::Elasticsearch => Ok(Search {
+            provider: Box::new(ElasticsearchSearch {
+                elasticsearch: Elasticsearch::from_config(config),
+                search_dao: search_dao.clone(),
+            }),
+            fallback: Some(Box::new(DatabaseSearch(search_dao.clone()))),
+            search_dao,
+        }),
+        #[cfg(not(feature = "elasticsearch"))]
+        SearchBackend::Elasticsearch => Err(StartupError::MissingFeature {
+            setting: "SEARCH_BACKEND=elasticsearch",
+            feature: "elasticsearch",
+        }),
+    }
+}
+
+// What a question is indexed as, from its row in the outbox or the database.
+#[cfg(feature = "elasticsearch")]
+fn document(
+    title: &str,
+    description: Option<&str>,
+    metadata: &serde_json::Value,
+) -> serde_json::Value {
+    serde_json::json!({
+        "title": title,
+        "description": description,
+        "tags": crate::models::metadata_tags(metadata),
+    })
+}
+
+// One index of an Elasticsearch or OpenSearch cluster, holding a document per live
+// question with its uuid as id.
+#[cfg(feature = "elasticsearch")]
+#[derive(Clone)]
+pub struct Elasticsearch {
+    client: reqwest::Client,
+    index_url: String,
+    api_key: Option<String>,
+}
+
+#[cfg(feature = "elasticsearch")]
+impl Elasticsearch {
+    pub fn from_config(config: &AppConfig) -> Self {
+        let client = reqwest::Client::builder()
+            .timeout(std::time::Duration::from_secs(10))
+            .build()
+            .expect("HTTP client should build.");
+
+        Self {
+            client,
+            index_url: format!(
+                "{}/{}",
+                config.elasticsearch_url.trim_end_matches('/'),
+                config.elasticsearch_index
+            ),
+            api_key: config.elasticsearch_api_key.clone(),
+        }
+    }
+
+    fn request(&self, method: reqwest::Method, path: &str) -> reqwest::RequestBuilder {
+        let request = self
+            .client
+            .request(method, format!("{}/{}", self.index_url, path));
+        match &self.api_key {
+            Some(api_key) => request.header("Authorization", format!("ApiKey {}", api_key)),
+            None => request,
+        }
+    }
+
+    async fn send(request: reqwest::RequestBuilder) -> Result<reqwest::Response, SearchError> {
+        request
+            .send()
+            .await
+            .and_then(|response| response.error_for_status())
+            .map_err(|err| SearchError::Request(err.to_string()))
+    }
+
+    pub async fn index_question(
+        &self,
+        question_uuid: &str,
+        document: &serde_json::Value,
+    ) -> Result<(), SearchError> {
+        let request = self
+            .request(reqwest::Method::PUT, &format!("_doc/{}", question_uuid))
+            .json(document);
+        Self::send(request).await?;
+        Ok(())
+    }
+
+    // Deleting a question that was never indexed is fine.
+    pub async fn delete_question(&self, question_uuid: &str) -> Result<(), SearchError> {
+        let response = self
+            .request(reqwest::Method::DELETE, &format!("_doc/{}", question_uuid))
+            .send()
+            .await
+            .map_err(|err| SearchError::Request(err.to_string()))?;
+        if response.status() != reqwest::StatusCode::NOT_FOUND {
+            response
+                .error_for_status()
+                .map_err(|err| SearchError::Request(err.to_string()))?;
+        }
+        Ok(())
+    }
+
+    // Indexes the questions in one `_bulk` request, overwriting existing documents.
+    pub async fn index_questions(&self, questions: &[QuestionDetail]) -> Result<(), SearchError> {
+        #[derive(serde::Deserialize)]
+        struct Response {
+            errors: bool,
+        }
+
+        let mut body = String::new();
+        for question in questions {
+            let action = serde_json::json!({ "index": { "_id": question.question_uuid } });
+            let document = document(
+                &question.title,
+                question.description.as_deref(),
+                &question.metadata,
+            );
+            body.push_str(&format!("{}\n{}\n", action, document));
+        }
+
+        let request = self
+            .request(reqwest::Method::POST, "_bulk")
+            .header("Content-Type", "application/x-ndjson")
+            .body(body);
+        let response: Response = Self::send(request)
+            .await?
+            .json()
+            .await
+            .map_err(|err| SearchError::Request(err.to_string()))?;
+        if response.errors {
+            return Err(SearchError::Request(
+                "Some questions could not be indexed".to_owned(),
+            ));
+        }
+        Ok(())
+    }
+
+    // Ids of the best matching documents, best first.
+    async fn search_ids(&self, query: &str, limit: u32) -> Result<Vec<String>, SearchError> {
+        #[derive(serde::Deserialize)]
+        struct Response {
+            hits: Hits,
+        }
+
+        #[derive(serde::Deserialize)]
+        struct Hits {
+            hits: Vec<Hit>,
+        }
+
+        #[derive(serde::Deserialize)]
+        struct Hit {
+            #[serde(rename = "_id")]
+            id: String,
+        }
+
+        let request = self
+            .request(reqwest::Method::POST, "_search")
+            .json(&serde_json::json!({
+                "size": limit,
+                "_source": false,
+                "query": {
+                    "multi_match": {
+                        "query": query,
+                        "fields": ["title^2", "description", "tags"],
+                    },
+                },
+            }));
+        let response: Response = Self::send(request)
+            .await?
+            .json()
+            .await
+            .map_err(|err| SearchError::Request(err.to_string()))?;
+
+        Ok(response.hits.hits.into_iter().map(|hit| hit.id).collect())
+    }
+}
+
+// Ranks with the search engine and loads the questions from the database, so results
+// are never staler than the index lags behind; questions deleted since drop out.
+#[cfg(feature = "elasticsearch")]
+pub struct ElasticsearchSearch {
+    pub elasticsearch: Elasticsearch,
+    pub search_dao: Arc<dyn SearchDao + Send + Sync>,
+}
+
+#[cfg(feature = "elasticsearch")]
+#[async_trait]
+impl SearchProvider for ElasticsearchSearch {
+    async fn search(&self, query: &str, limit: u32) -> Result<Vec<QuestionDetail>, SearchError> {
+        let question_uuids: Vec<crate::models::QuestionUuid> = self
+            .elasticsearch
+            .search_ids(query, limit)
+            .await?
+            .iter()
+            .filter_map(|id| id.parse().ok())
+            .collect();
+
+        let mut questions = self.search_dao.questions_by_uuid(&question_uuids).await?;
+        questions.sort_by_key(|question| {
+            question_uuids
+                .iter()
+                .position(|uuid| *uuid == question.question_uuid)
+        });
+        Ok(questions)
+    }
+}
+
+// Keeps the index in sync from the outbox: created and updated questions are
+// (re)indexed, deleted and archived ones removed. Answers are not indexed.
+#[cfg(feature = "elasticsearch")]
+pub struct ElasticsearchIndexer(pub Elasticsearch);
+
+#[cfg(feature = "elasticsearch")]
+#[async_trait]
+impl crate::events::EventPublisher for ElasticsearchIndexer {
+    async fn publish(
+        &self,
+        event: &crate::events::OutboxEvent,
+    ) -> Result<(), crate::events::PublishError> {
+        let payload = &event.payload;
+        let result = match event.event_type.as_str() {
+            "question_created" | "question_updated" => {
+                let document = document(
+                    payload["title"].as_str().unwrap_or_default(),
+                    payload["description"].as_str(),
+                    &payload["metadata"],
+                );
+                self.0
+                    .index_question(&event.aggregate_uuid, &document)
+                    .await
+            }
+            "question_deleted" | "question_archived" => {
+                self.0.delete_question(&event.aggregate_uuid).await
+            }
+            _ => Ok(()),
+        };
+        result.map_err(|err| crate::events::PublishError(err.to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        models::{empty_metadata, QuestionUuid},
+        persistence::memory::{InMemorySearchDao, MemoryStore},
+    };
+    use chrono::DateTime;
+
+    struct FailingSearch;
+
+    #[async_trait]
+    impl SearchProvider for FailingSearch {
+        async fn search(&self, _: &str, _: u32) -> Result<Vec<QuestionDetail>, SearchError> {
+            Err(DBError::Other("unreachable".into()).into())
+        }
+    }
+
+    struct FixedSearch(Vec<QuestionDetail>);
+
+    #[async_trait]
+    impl SearchProvider for FixedSearch {
+        async fn search(&self, _: &str, _: u32) -> Result<Vec<QuestionDetail>, SearchError> {
+            Ok(self.0.clone())
+        }
+    }
+
+    #[tokio::test]
+    async fn search_should_fall_back_while_the_provider_fails() {
+        let question = QuestionDetail {
+            question_uuid: QuestionUuid::new_v4(),
+            title: "title".to_owned(),
+            description: None,
+            metadata: empty_metadata(),
+            created_at: DateTime::UNIX_EPOCH,
+            updated_at: DateTime::UNIX_EPOCH,
+            last_activity_at: DateTime::UNIX_EPOCH,
+            answer_count: 0,
+            version: 1,
+        };
+        let search = Search {
+            provider: Box::new(FailingSearch),
+            fallback: Some(Box::new(FixedSearch(vec![question.clone()]))),
+            search_dao: Arc::new(InMemorySearchDao::new(MemoryStore::new())),
+        };
+        assert_eq!(search.search("title", 10).await.unwrap(), vec![question]);
+
+        let search = Search {
+            provider: Box::new(FailingSearch),
+            fallback: None,
+            search_dao: Arc::new(InMemorySearchDao::new(MemoryStore::new())),
+        };
+        assert!(search.search("title", 10).await.is_err());
+    }
+}
@@ -0,0 +1,167 @@
+use async_trait::async_trait;
+use thiserror::Error;
+
+use crate::{
+    config::{AppConfig, SpamCheckerKind},
+    models::{HeldContent, SpamContext},
+    startup::StartupError,
+};
+
+// The moderation category of content held as spam.
+pub const SPAM_CATEGORY: &str = "spam";
+
+// Only remote checkers can fail.
+#[derive(Error, Debug)]
+pub enum SpamError {
+    #[cfg(feature = "akismet")]
+    #[error("Spam check failed: {0}")]
+    Request(String),
+}
+
+#[async_trait]
+pub trait SpamChecker {
+    async fn is_spam(
+        &self,
+        content: &HeldContent,
+        context: &SpamContext,
+    ) -> Result<bool, SpamError>;
+    // Teaches the checker a moderator's decision on content it flagged: `spam` when it was
+    // rejected, ham when it was approved.
+    async fn report(
+        &self,
+        content: &HeldContent,
+        context: &SpamContext,
+        spam: bool,
+    ) -> Result<(), SpamError>;
+}
+
+pub fn checker_from_config(
+    config: &AppConfig,
+) -> Result<Option<Box<dyn SpamChecker + Send + Sync>>, StartupError> {
+    match config.spam_checker {
+        SpamCheckerKind::None => Ok(None),
+        #[cfg(feature = "akismet")]
+        SpamCheckerKind::Akismet => Ok(Some(Box::new(AkismetChecker::new(
+            &config.akismet_api_url,
+            config
+                .akismet_api_key
+                .clone()
+                .ok_or(StartupError::MissingSetting {
+                    setting: "SPAM_CHECKER=akismet",
+                    needed: "AKISMET_API_KEY",
+                })?,
+            config
+                .public_url
+                .clone()
+                .ok_or(StartupError::MissingSetting {
+                    setting: "SPAM_CHECKER=akismet",
+                    needed: "PUBLIC_URL",
+                })?,
+        )))),
+        #[cfg(not(feature = "akismet"))]
+        SpamCheckerKind::Akismet => Err(StartupError::MissingFeature {
+            setting: "SPAM_CHECKER=akismet",
+            feature: "akismet",
+        }),
+    }
+}
+
+// Akismet's REST API, which other spam services also speak. `site_url` is the `blog` the
+// key was registered for.
+#[cfg(feature = "akismet")]
+pub struct AkismetChecker {
+    client: reqwest::Client,
+    url: String,
+    api_key: String,
+    site_url: String,
+}
+
+#[cfg(feature = "akismet")]
+impl AkismetChecker {
+    pub fn new(base_url: &str, api_key: String, site_url: String) -> Self {
+        let client = reqwest::Client::builder()
+            .timeout(std::time::Duration::from_secs(10))
+            .build()
+            .expect("HTTP client should build.");
+
+        Self {
+            client,
+            url: format!("{}/1.1", base_url.trim_end_matches('/')),
+            api_key,
+            site_url,
+        }
+    }
+
+    async fn post(
+        &self,
+        method: &str,
+        content: &HeldContent,
+        context: &SpamContext,
+    ) -> Result<String, SpamError> {
+        let comment_type = match content {
+            HeldContent::Answer { .. } => "reply",
+            _ => "forum-post",
+        };
+        let form = [
+            ("api_key", Some(self.api_key.as_str())),
+            ("blog", Some(self.site_url.as_str())),
+            ("user_ip", context.user_ip.as_deref()),
+            ("user_agent", context.user_agent.as_deref()),
+            ("referrer", context.referrer.as_deref()),
+            ("comment_type", Some(comment_type)),
+            ("comment_content", Some(&content.text())),
+        ];
+        let form: Vec<(&str, &str)> = form
+            .iter()
+            .filter_map(|(key, value)| value.map(|value| (*key, value)))
+            .collect();
+
+        self.client
+            .post(format!("{}/{}", self.url, method))
+            .form(&form)
+            .send()
+            .await
+            .and_then(|response| response.error_for_status())
+            .map_err(|err| SpamError::Request(err.to_string()))?
+            .text()
+            .await
+            .map_err(|err| SpamError::Request(err.to_string()))
+    }
+}
+
+#[cfg(feature = "akismet")]
+#[async_trait]
+impl SpamChecker for AkismetChecker {
+    async fn is_spam(
+        &self,
+        content: &HeldContent,
+        context: &SpamContext,
+    ) -> Result<bool, SpamError> {
+        // Akismet needs the sender's address; creates without one, e.g. from background
+        // jobs, are not checked.
+        if context.user_ip.is_none() {
+            return Ok(false);
+        }
+
+        match self.post("comment-check", content, context).await?.trim() {
+            "true" => Ok(true),
+            "false" => Ok(false),
+            // `invalid` for a bad key, with the reason in a debug header.
+            other => Err(SpamError::Request(format!(
+                "Unexpected response: {}",
+                other
+            ))),
+        }
+    }
+
+    async fn report(
+        &self,
+        content: &HeldContent,
+        context: &SpamContext,
+        spam: bool,
+    ) -> Result<(), SpamError> {
+        let method = if spam { "submit-spam" } else { "submit-ham" };
+        self.post(method, content, context).await?;
+        Ok(())
+    }
+}
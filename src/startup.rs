@@ -0,0 +1,1035 @@
+use std::{
+    env, error::Error as StdError, fmt::Display, future::Future, path::PathBuf, sync::Arc,
+    time::Duration,
+};
+
+use log::{error, warn};
+use rocket::fairing::AdHoc;
+use sqlx::{postgres::PgPoolOptions, PgPool};
+use thiserror::Error;
+
+#[cfg(feature = "chaos")]
+use crate::persistence::chaos::ChaosDao;
+#[cfg(feature = "mysql")]
+use crate::persistence::mysql::{
+    MySqlAnswerDao, MySqlArchiveDao, MySqlQuestionDao, MySqlStatsDao, MySqlUnitOfWorkFactory,
+};
+#[cfg(feature = "sqlite")]
+use crate::persistence::sqlite::{
+    SqliteAnswerDao, SqliteArchiveDao, SqliteQuestionDao, SqliteStatsDao, SqliteUnitOfWorkFactory,
+};
+use crate::{
+    analytics::{self, Analytics},
+    answerer::{self, AnswerSuggestions},
+    attachments::{self, Attachments},
+    backpressure, captcha,
+    chat::{self, ChatNotifiers},
+    config::{
+        AppConfig, DuplicateAnswerPolicy, LiveConfig, SearchBackend, Storage, WordFilterPolicy,
+    },
+    confirmations,
+    duplicates::DuplicateAnswers,
+    embedding::{self, SemanticSearch},
+    events, ingestion,
+    link_previews::{self, LinkPreviews},
+    metrics,
+    moderation::{self, Moderation},
+    notifications::{self, Notifications},
+    persistence::{
+        self,
+        activity::{ActivityDao, PgActivityDao},
+        answer_dao::{AnswerDao, AnswerDaoImpl},
+        archive::{self, ArchiveDao, PgArchiveDao},
+        attachment::{AttachmentDao, PgAttachmentDao},
+        audit::{AuditDao, PgAuditDao},
+        backup,
+        cache::{
+            CachedAnswerDao, CachedArchiveDao, CachedImportDao, CachedQuestionDao,
+            CachedUnitOfWorkFactory, QueryCache,
+        },
+        circuit_breaker::{CircuitBreaker, CircuitBreakerAnswerDao, CircuitBreakerQuestionDao},
+        duplicate::{DuplicateDao, PgDuplicateDao},
+        embedding::{EmbeddingDao, PgEmbeddingDao},
+        import::{ImportDao, PgCopyImportDao, UnitOfWorkImportDao},
+        ingestion::{IngestionDao, PgIngestionDao},
+        instrumented::{InstrumentedAnswerDao, InstrumentedQuestionDao},
+        invalidation,
+        link_preview::{LinkPreviewDao, PgLinkPreviewDao},
+        memory::{
+            InMemoryActivityDao, InMemoryAnswerDao, InMemoryArchiveDao, InMemoryAttachmentDao,
+            InMemoryDuplicateDao, InMemoryEmbeddingDao, InMemoryIngestionDao,
+            InMemoryLinkPreviewDao, InMemoryMergeDao, InMemoryModerationDao, InMemoryQuestionDao,
+            InMemorySearchDao, InMemoryStatsDao, InMemorySubscriptionDao, InMemoryTagDao,
+            InMemoryTranslationDao, InMemoryUserDao, InMemoryWordFilterDao, MemoryStore,
+            MemoryUnitOfWorkFactory,
+        },
+        merge::{MergeDao, PgMergeDao},
+        moderation::{ModerationDao, PgModerationDao},
+        outbox, partition,
+        question_dao::{QuestionDao, QuestionDaoImpl},
+        replica::{ReadReplica, REPLICA_POOL},
+        rest_hook::{PgRestHookDao, RestHookDao},
+        retry::{RetryAnswerDao, RetryQuestionDao},
+        search::{PgSearchDao, SearchDao},
+        shedding,
+        stats::{self, PgStatsDao, StatsDao},
+        subscription::{PgSubscriptionDao, SubscriptionDao},
+        tag::{PgTagDao, TagDao},
+        translation::{PgTranslationDao, TranslationDao},
+        unit_of_work::{PgUnitOfWorkFactory, UnitOfWorkFactory},
+        user::{PgUserDao, UserDao},
+        word_filter::{PgWordFilterDao, WordFilterDao},
+        DatabasePool, PRIMARY_POOL,
+    },
+    push,
+    rate_limit::RateLimiter,
+    replies,
+    scanning::{self, Scans},
+    search::{self, Search},
+    spam,
+    tagging::{self, TagSuggestions},
+    thumbnails::Thumbnails,
+    translation::{self, Translations},
+    word_filter::WordFilter,
+    AppState,
+};
+
+type Daos = (
+    Arc<dyn QuestionDao + Send + Sync>,
+    Arc<dyn AnswerDao + Send + Sync>,
+    Arc<dyn UnitOfWorkFactory + Send + Sync>,
+    Arc<dyn ImportDao + Send + Sync>,
+    Arc<dyn ArchiveDao + Send + Sync>,
+);
+
+// Everything built from the storage selected at startup.
+struct Backend {
+    pool: Option<DatabasePool>,
+    read_replica: Option<ReadReplica>,
+    daos: Daos,
+    // Read as is, so left out of `decorate`.
+    stats_dao: Arc<dyn StatsDao + Send + Sync>,
+    // None when the backend cannot store embeddings.
+    embedding_dao: Option<Arc<dyn EmbeddingDao + Send + Sync>>,
+    // None when the backend keeps no audit log.
+    audit_dao: Option<Arc<dyn AuditDao + Send + Sync>>,
+    // None when the backend cannot store subscriptions, which turns notifications off.
+    subscription_dao: Option<Arc<dyn SubscriptionDao + Send + Sync>>,
+    // None when the backend cannot store attachment rows, which turns attachments off.
+    attachment_dao: Option<Arc<dyn AttachmentDao + Send + Sync>>,
+    // None when the backend has no moderation queue, which turns moderation off.
+    moderation_dao: Option<Arc<dyn ModerationDao + Send + Sync>>,
+    // None when the backend cannot list tagged questions, which turns tag suggestions off.
+    tag_dao: Option<Arc<dyn TagDao + Send + Sync>>,
+    // None when the backend cannot cache translations, which turns translation off.
+    translation_dao: Option<Arc<dyn TranslationDao + Send + Sync>>,
+    // None when the backend cannot store link previews, which turns them off.
+    link_preview_dao: Option<Arc<dyn LinkPreviewDao + Send + Sync>>,
+    // None when the backend has no full text search, which turns search off.
+    search_dao: Option<Arc<dyn SearchDao + Send + Sync>>,
+    // None when the backend cannot remember idempotency keys, which turns ingestion off.
+    ingestion_dao: Option<Arc<dyn IngestionDao + Send + Sync>>,
+    // None when the backend cannot keep the word filter's list, which turns it off.
+    word_filter_dao: Option<Arc<dyn WordFilterDao + Send + Sync>>,
+    // None when the backend has no outbox to call hooks from, which turns REST hooks off.
+    rest_hook_dao: Option<Arc<dyn RestHookDao + Send + Sync>>,
+    // None when the backend cannot compare answers, which turns duplicate checks off.
+    duplicate_dao: Option<Arc<dyn DuplicateDao + Send + Sync>>,
+    // None when the backend cannot keep provisioned users, which turns SCIM off.
+    user_dao: Option<Arc<dyn UserDao + Send + Sync>>,
+    // None when the backend keeps no history of writes, which turns the activity feed off.
+    activity_dao: Option<Arc<dyn ActivityDao + Send + Sync>>,
+    // None when the backend cannot merge questions in one transaction, which turns
+    // merging off.
+    merge_dao: Option<Arc<dyn MergeDao + Send + Sync>>,
+}
+
+// Why the server could not start, worded to say what to fix.
+#[derive(Error, Debug)]
+pub enum StartupError {
+    #[error("DATABASE_URL is not set. Point it at a postgres:, sqlite: or mysql: database, or set STORAGE=memory.")]
+    MissingDatabaseUrl,
+    #[error("DATABASE_URL has the unsupported scheme {0:?}. Use postgres:, or build with the sqlite or mysql cargo feature.")]
+    UnsupportedScheme(String),
+    #[error("{name} is not a valid connection string: {source}")]
+    InvalidUrl {
+        name: &'static str,
+        source: sqlx::Error,
+    },
+    #[error("Could not reach the database after {attempts} attempts: {source}. Check DATABASE_URL and that the server is up.")]
+    Unreachable {
+        attempts: u32,
+        source: Box<dyn StdError + Send + Sync>,
+    },
+    #[error("The database is missing migrations {0}. Apply them with `sqlx migrate run`.")]
+    PendingMigrations(String),
+    #[error("Could not inspect the database schema: {0}")]
+    Schema(sqlx::Error),
+    #[error("{setting} needs the {feature} cargo feature. Build with `--features {feature}` or unset {setting}.")]
+    MissingFeature {
+        setting: &'static str,
+        feature: &'static str,
+    },
+    #[error("{setting} needs {needed} to be set as well.")]
+    MissingSetting {
+        setting: &'static str,
+        needed: &'static str,
+    },
+    #[error("{setting} is invalid: {reason}. Expected {expected}.")]
+    InvalidSetting {
+        setting: &'static str,
+        expected: &'static str,
+        reason: String,
+    },
+}
+
+// Builds the state while Rocket ignites, so a failure is logged as a `StartupError`
+// and stops the launch instead of panicking.
+pub fn database(env_file: Option<PathBuf>) -> AdHoc {
+    AdHoc::try_on_ignite("Database", |rocket| async move {
+        match state_from_env(env_file).await {
+            Ok(state) => Ok(rocket.manage(state)),
+            Err(err) => {
+                error!("{}", err);
+                Err(rocket)
+            }
+        }
+    })
+}
+
+// Connects to the configured storage, starts the background jobs and returns what
+// `build_rocket` serves.
+pub async fn state_from_env(env_file: Option<PathBuf>) -> Result<AppState, StartupError> {
+    let config = AppConfig::from_env();
+    check_database_timeouts(&config)?;
+    backpressure::set_warn_thresholds(&config);
+
+    let Backend {
+        pool,
+        read_replica,
+        daos: (question_dao, answer_dao, unit_of_work, import_dao, archive_dao),
+        stats_dao,
+        embedding_dao,
+        audit_dao,
+        subscription_dao,
+        attachment_dao,
+        moderation_dao,
+        tag_dao,
+        translation_dao,
+        link_preview_dao,
+        search_dao,
+        ingestion_dao,
+        word_filter_dao,
+        rest_hook_dao,
+        duplicate_dao,
+        user_dao,
+        activity_dao,
+        merge_dao,
+    } = match config.storage {
+        Storage::Database => {
+            let database_url =
+                env::var("DATABASE_URL").map_err(|_| StartupError::MissingDatabaseUrl)?;
+            connect_database(&config, &database_url).await?
+        }
+        Storage::Memory => {
+            warn!("STORAGE=memory: data is kept in this process and lost on restart.");
+            if config.search_backend != SearchBackend::Database {
+                warn!("SEARCH_BACKEND only gets changes from the Postgres outbox; the index is not kept in sync.");
+            }
+            memory_backend(&config)
+        }
+    };
+
+    let semantic_search = match (embedding::embedder_from_config(&config)?, embedding_dao) {
+        (Some(embedder), Some(embedding_dao)) => Some(SemanticSearch {
+            embedder,
+            embedding_dao,
+        }),
+        (Some(_), None) => {
+            warn!("EMBEDDER is set but the storage cannot hold embeddings (Postgres needs pgvector); semantic search is disabled.");
+            None
+        }
+        (None, _) => None,
+    };
+    let captcha = captcha::captcha_from_config(&config)?;
+    let analytics = analytics::sink_from_config(&config)?
+        .map(|sink| Analytics::spawn(sink, config.analytics_sample_rate));
+    let suggestions = answerer::answerer_from_config(&config)?.map(|answerer| AnswerSuggestions {
+        answerer,
+        rate_limiter: RateLimiter::new(config.suggestions_per_hour, Duration::from_secs(3600)),
+    });
+
+    let moderation = match (
+        moderation::provider_from_config(&config)?,
+        spam::checker_from_config(&config)?,
+        moderation_dao,
+    ) {
+        (None, None, _) => None,
+        (provider, spam_checker, Some(moderation_dao)) => Some(Moderation {
+            provider,
+            spam_checker,
+            moderation_dao,
+            threshold: config.moderation_threshold,
+        }),
+        (_, _, None) => {
+            warn!("MODERATION_PROVIDER or SPAM_CHECKER is set but the storage has no moderation queue; new content is published unmoderated.");
+            None
+        }
+    };
+    let word_filter = match (config.word_filter, word_filter_dao) {
+        (WordFilterPolicy::Off, _) => None,
+        (policy, Some(word_filter_dao)) => Some(WordFilter::new(word_filter_dao, policy)),
+        (_, None) => {
+            warn!("WORD_FILTER is set but the storage cannot keep the word list; content is not filtered.");
+            None
+        }
+    };
+    // Hooks are called by the outbox relay, which needs the webhooks feature for that.
+    let rest_hook_dao = rest_hook_dao.filter(|_| cfg!(feature = "webhooks"));
+    let duplicate_answers = duplicate_answers_from_config(duplicate_dao, &config);
+    let replies = replies::addresses_from_config(&config)?;
+    let tag_suggestions = tag_suggestions_from_config(tag_dao, &config)?;
+    let search = search_from_config(search_dao, &config)?;
+    let translations = match (
+        translation::translator_from_config(&config)?,
+        translation_dao.clone(),
+    ) {
+        (Some(translator), Some(translation_dao)) => Some(Translations {
+            translator,
+            translation_dao,
+        }),
+        (Some(_), None) => {
+            warn!("TRANSLATOR is set but the storage cannot cache translations; translation is disabled.");
+            None
+        }
+        (None, _) => None,
+    };
+    let link_previews = match (
+        link_previews::fetcher_from_config(&config)?,
+        link_preview_dao,
+    ) {
+        (Some(fetcher), Some(link_preview_dao)) => {
+            Some(LinkPreviews::spawn(fetcher, link_preview_dao))
+        }
+        (Some(_), None) => {
+            warn!("LINK_PREVIEWS is set but the storage cannot keep link previews; links get no previews.");
+            None
+        }
+        (None, _) => None,
+    };
+
+    match ingestion_dao {
+        Some(ingestion_dao) => {
+            ingestion::spawn_consumer(&config, ingestion_dao);
+        }
+        None if config.ingestion_url.is_some() => {
+            warn!("INGESTION_URL is set but the storage cannot remember idempotency keys; questions are not consumed.");
+        }
+        None => {}
+    }
+
+    // Everything left that can fail is built before the background jobs start.
+    let mailer = notifications::mailer_from_config(&config)?;
+    let tokens = confirmations::tokens_from_config(&config)?;
+    let pusher = push::pusher_from_config(&config)?;
+    let notifiers = chat::notifiers_from_config(&config)?;
+    let scanner = scanning::scanner_from_config(&config)?;
+    let mut attachments = attachments_from_config(attachment_dao, &config)?;
+
+    let live_config = LiveConfig::new(config, env_file);
+    live_config.spawn_watcher(live_config.load().config_watch_interval);
+    archive::spawn_archiver(archive_dao.clone(), live_config.clone());
+    stats::spawn_stats_refresher(stats_dao.clone(), live_config.clone());
+    let notifications = subscription_dao.map(|dao| {
+        notifications::spawn_digest_sender(
+            dao.clone(),
+            mailer.clone(),
+            tokens.clone(),
+            live_config.clone(),
+        );
+        Notifications::spawn(dao, mailer.clone(), pusher, replies.clone(), tokens)
+    });
+    let chat = (!notifiers.is_empty()).then(|| ChatNotifiers::spawn(notifiers));
+    if let Some(attachments) = &mut attachments {
+        let config = live_config.load();
+        let sizes = config.thumbnail_sizes.clone();
+        attachments.thumbnails = (!sizes.is_empty()).then(|| {
+            Thumbnails::spawn(
+                attachments.attachment_dao.clone(),
+                attachments.object_store.clone(),
+                sizes,
+            )
+        });
+        attachments.scans = scanner.map(|scanner| {
+            Scans::spawn(
+                attachments.attachment_dao.clone(),
+                attachments.object_store.clone(),
+                scanner,
+                mailer,
+                config.moderator_emails.clone(),
+                attachments.thumbnails.clone(),
+            )
+        });
+    }
+
+    Ok(AppState {
+        live_config,
+        pool,
+        question_dao,
+        answer_dao,
+        unit_of_work,
+        import_dao,
+        archive_dao,
+        stats_dao,
+        read_replica,
+        semantic_search,
+        search,
+        analytics,
+        suggestions,
+        captcha,
+        moderation,
+        word_filter,
+        duplicate_answers,
+        rest_hook_dao,
+        user_dao,
+        activity_dao,
+        merge_dao,
+        tag_suggestions,
+        translations,
+        translation_dao,
+        link_previews,
+        audit_dao,
+        notifications,
+        replies,
+        chat,
+        attachments,
+    })
+}
+
+// Storage kept in this process, with no background jobs or config watcher, e.g. to
+// serve `build_rocket` in tests.
+pub fn in_memory(config: AppConfig) -> Result<AppState, StartupError> {
+    let Backend {
+        daos: (question_dao, answer_dao, unit_of_work, import_dao, archive_dao),
+        stats_dao,
+        attachment_dao,
+        tag_dao,
+        search_dao,
+        duplicate_dao,
+        user_dao,
+        activity_dao,
+        merge_dao,
+        translation_dao,
+        ..
+    } = memory_backend(&config);
+    let attachments = attachments_from_config(attachment_dao, &config)?;
+    let duplicate_answers = duplicate_answers_from_config(duplicate_dao, &config);
+    let replies = replies::addresses_from_config(&config)?;
+    let tag_suggestions = tag_suggestions_from_config(tag_dao, &config)?;
+    let search = search_from_config(search_dao, &config)?;
+
+    Ok(AppState {
+        live_config: LiveConfig::new(config, None),
+        pool: None,
+        question_dao,
+        answer_dao,
+        unit_of_work,
+        import_dao,
+        archive_dao,
+        stats_dao,
+        read_replica: None,
+        semantic_search: None,
+        search,
+        analytics: None,
+        suggestions: None,
+        captcha: None,
+        moderation: None,
+        word_filter: None,
+        duplicate_answers,
+        rest_hook_dao: None,
+        user_dao,
+        activity_dao,
+        merge_dao,
+        tag_suggestions,
+        translations: None,
+        translation_dao,
+        link_previews: None,
+        audit_dao: None,
+        notifications: None,
+        replies,
+        chat: None,
+        attachments,
+    })
+}
+
+fn attachments_from_config(
+    attachment_dao: Option<Arc<dyn AttachmentDao + Send + Sync>>,
+    config: &AppConfig,
+) -> Result<Option<Attachments>, StartupError> {
+    attachment_dao
+        .map(|attachment_dao| {
+            Ok(Attachments {
+                attachment_dao,
+                object_store: attachments::object_store_from_config(config)?,
+                thumbnails: None,
+                scans: None,
+            })
+        })
+        .transpose()
+}
+
+fn duplicate_answers_from_config(
+    duplicate_dao: Option<Arc<dyn DuplicateDao + Send + Sync>>,
+    config: &AppConfig,
+) -> Option<DuplicateAnswers> {
+    match (config.duplicate_answers, duplicate_dao) {
+        (DuplicateAnswerPolicy::Off, _) => None,
+        (policy, Some(duplicate_dao)) => Some(DuplicateAnswers {
+            duplicate_dao,
+            policy,
+            threshold: config.duplicate_answer_threshold,
+        }),
+        // Only worth a warning when asked for; `warn` is the default.
+        (DuplicateAnswerPolicy::Reject, None) => {
+            warn!("DUPLICATE_ANSWERS=reject but the storage cannot compare answers; duplicates are not checked.");
+            None
+        }
+        (DuplicateAnswerPolicy::Warn, None) => None,
+    }
+}
+
+fn tag_suggestions_from_config(
+    tag_dao: Option<Arc<dyn TagDao + Send + Sync>>,
+    config: &AppConfig,
+) -> Result<Option<TagSuggestions>, StartupError> {
+    Ok(match (tagging::suggester_from_config(config)?, tag_dao) {
+        (Some(suggester), Some(tag_dao)) => Some(TagSuggestions::new(suggester, tag_dao, config)),
+        (Some(_), None) => {
+            warn!("TAG_SUGGESTER is set but the storage cannot list tagged questions; tag suggestions are disabled.");
+            None
+        }
+        (None, _) => None,
+    })
+}
+
+fn search_from_config(
+    search_dao: Option<Arc<dyn SearchDao + Send + Sync>>,
+    config: &AppConfig,
+) -> Result<Option<Search>, StartupError> {
+    match search_dao {
+        Some(search_dao) => search::search_from_config(config, search_dao).map(Some),
+        None => {
+            if config.search_backend != SearchBackend::Database {
+                warn!("SEARCH_BACKEND is set but the storage has no full text search to fall back to; search is disabled.");
+            }
+            Ok(None)
+        }
+    }
+}
+
+fn memory_backend(config: &AppConfig) -> Backend {
+    let store = MemoryStore::new();
+    Backend {
+        pool: None,
+        read_replica: None,
+        daos: (
+            Arc::new(InstrumentedQuestionDao::new(
+                InMemoryQuestionDao::new(store.clone()).with_deletion(config.question_deletion),
+            )),
+            Arc::new(InstrumentedAnswerDao::new(InMemoryAnswerDao::new(
+                store.clone(),
+            ))),
+            Arc::new(MemoryUnitOfWorkFactory::new(store.clone())),
+            Arc::new(UnitOfWorkImportDao::new(MemoryUnitOfWorkFactory::new(
+                store.clone(),
+            ))),
+            Arc::new(InMemoryArchiveDao::new(store.clone())),
+        ),
+        stats_dao: Arc::new(InMemoryStatsDao::new(store.clone())),
+        embedding_dao: Some(Arc::new(InMemoryEmbeddingDao::new(store.clone()))),
+        audit_dao: None,
+        subscription_dao: Some(Arc::new(InMemorySubscriptionDao::new(store.clone()))),
+        attachment_dao: Some(Arc::new(InMemoryAttachmentDao::new(store.clone()))),
+        moderation_dao: Some(Arc::new(InMemoryModerationDao::new(store.clone()))),
+        tag_dao: Some(Arc::new(InMemoryTagDao::new(store.clone()))),
+        translation_dao: Some(Arc::new(InMemoryTranslationDao::new(store.clone()))),
+        link_preview_dao: Some(Arc::new(InMemoryLinkPreviewDao::new(store.clone()))),
+        search_dao: Some(Arc::new(InMemorySearchDao::new(store.clone()))),
+        ingestion_dao: Some(Arc::new(InMemoryIngestionDao::new(store.clone()))),
+        word_filter_dao: Some(Arc::new(InMemoryWordFilterDao::new(store.clone()))),
+        rest_hook_dao: None,
+        duplicate_dao: Some(Arc::new(InMemoryDuplicateDao::new(store.clone()))),
+        user_dao: Some(Arc::new(InMemoryUserDao::new(store.clone()))),
+        activity_dao: Some(Arc::new(InMemoryActivityDao::new(store.clone()))),
+        merge_dao: Some(Arc::new(InMemoryMergeDao::new(store))),
+    }
+}
+
+// A Postgres database that is already migrated, with no background jobs or config
+// watcher, e.g. to serve a `#[sqlx::test]` pool in the integration tests.
+pub async fn postgres(pool: PgPool, config: AppConfig) -> Result<AppState, StartupError> {
+    let Backend {
+        pool,
+        daos: (question_dao, answer_dao, unit_of_work, import_dao, archive_dao),
+        stats_dao,
+        audit_dao,
+        attachment_dao,
+        tag_dao,
+        search_dao,
+        duplicate_dao,
+        user_dao,
+        activity_dao,
+        merge_dao,
+        translation_dao,
+        ..
+    } = postgres_backend(pool, None, &config, None).await?;
+    let attachments = attachments_from_config(attachment_dao, &config)?;
+    let duplicate_answers = duplicate_answers_from_config(duplicate_dao, &config);
+    let replies = replies::addresses_from_config(&config)?;
+    let tag_suggestions = tag_suggestions_from_config(tag_dao, &config)?;
+    let search = search_from_config(search_dao, &config)?;
+
+    Ok(AppState {
+        live_config: LiveConfig::new(config, None),
+        pool,
+        question_dao,
+        answer_dao,
+        unit_of_work,
+        import_dao,
+        archive_dao,
+        stats_dao,
+        read_replica: None,
+        semantic_search: None,
+        search,
+        analytics: None,
+        suggestions: None,
+        captcha: None,
+        moderation: None,
+        word_filter: None,
+        duplicate_answers,
+        rest_hook_dao: None,
+        user_dao,
+        activity_dao,
+        merge_dao,
+        tag_suggestions,
+        translations: None,
+        translation_dao,
+        link_previews: None,
+        audit_dao,
+        notifications: None,
+        replies,
+        chat: None,
+        attachments,
+    })
+}
+
+// A database wait that outlives the request deadline ends as a 504 the breaker never sees.
+fn check_database_timeouts(config: &AppConfig) -> Result<(), StartupError> {
+    for (setting, timeout) in [
+        ("DATABASE_ACQUIRE_TIMEOUT_MS", config.db_acquire_timeout),
+        ("BREAKER_CALL_TIMEOUT_MS", config.breaker_call_timeout),
+    ] {
+        if timeout.is_zero() || timeout >= config.request_timeout {
+            return Err(StartupError::InvalidSetting {
+                setting,
+                expected: "a non-zero duration below REQUEST_TIMEOUT_SECS",
+                reason: format!(
+                    "{timeout:?} is not below the {:?} request deadline",
+                    config.request_timeout
+                ),
+            });
+        }
+    }
+    Ok(())
+}
+
+// Connects to the backend selected by the DATABASE_URL scheme.
+async fn connect_database(config: &AppConfig, database_url: &str) -> Result<Backend, StartupError> {
+    let cache = (!config.cache_ttl.is_zero()).then(|| Arc::new(QueryCache::new(config.cache_ttl)));
+
+    persistence::set_acquire_warn_threshold(config.pool_acquire_warn_threshold);
+    shedding::set_limits(config.shed_acquire_wait, config.shed_queue_depth);
+    metrics::record_pool_max_size(PRIMARY_POOL, config.db_max_connections);
+
+    match database_url.split(':').next() {
+        Some("postgres" | "postgresql") => {
+            let pool = PgPoolOptions::new()
+                .max_connections(config.db_max_connections)
+                .acquire_timeout(config.db_acquire_timeout)
+                .connect_lazy(database_url)
+                .map_err(|source| StartupError::InvalidUrl {
+                    name: "DATABASE_URL",
+                    source,
+                })?;
+            with_retries(config, || async { pool.acquire().await.map(drop) }).await?;
+
+            let pending = persistence::pending_migrations(&pool)
+                .await
+                .map_err(StartupError::Schema)?;
+            if !pending.is_empty() {
+                return Err(StartupError::PendingMigrations(pending.join(", ")));
+            }
+
+            let read_replica = match &config.database_read_url {
+                Some(url) => {
+                    let replica_pool = PgPoolOptions::new()
+                        .max_connections(config.db_max_connections)
+                        .acquire_timeout(config.replica_acquire_timeout)
+                        .connect_lazy(url)
+                        .map_err(|source| StartupError::InvalidUrl {
+                            name: "DATABASE_READ_URL",
+                            source,
+                        })?;
+                    metrics::record_pool_max_size(REPLICA_POOL, config.db_max_connections);
+
+                    Some(ReadReplica::new(replica_pool, config.replica_cooldown))
+                }
+                None => None,
+            };
+
+            let publisher =
+                events::publisher_from_config(config, Arc::new(PgRestHookDao::new(pool.clone())))?;
+            if let Some(cache) = &cache {
+                invalidation::spawn_listener(pool.clone(), cache.clone());
+            }
+            partition::spawn_partition_maintainer(pool.clone());
+            outbox::spawn_outbox_relay(pool.clone(), publisher, config);
+            if !config.backup_interval.is_zero() {
+                backup::spawn_backup_scheduler(pool.clone(), config);
+            }
+
+            postgres_backend(pool, read_replica, config, cache).await
+        }
+        #[cfg(feature = "sqlite")]
+        Some("sqlite") => {
+            let pool = with_retries(config, || {
+                persistence::sqlite::connect(
+                    database_url,
+                    config.db_max_connections,
+                    config.db_acquire_timeout,
+                )
+            })
+            .await?;
+
+            let daos = decorate(
+                SqliteQuestionDao::new(pool.clone()).with_deletion(config.question_deletion),
+                SqliteAnswerDao::new(pool.clone()),
+                SqliteUnitOfWorkFactory::new(pool.clone()),
+                UnitOfWorkImportDao::new(SqliteUnitOfWorkFactory::new(pool.clone())),
+                SqliteArchiveDao::new(pool.clone()),
+                config,
+                cache,
+            );
+            Ok(Backend {
+                stats_dao: Arc::new(SqliteStatsDao::new(pool.clone())),
+                embedding_dao: None,
+                audit_dao: None,
+                subscription_dao: None,
+                attachment_dao: None,
+                moderation_dao: None,
+                tag_dao: None,
+                translation_dao: None,
+                link_preview_dao: None,
+                search_dao: None,
+                ingestion_dao: None,
+                word_filter_dao: None,
+                rest_hook_dao: None,
+                duplicate_dao: None,
+                user_dao: None,
+                activity_dao: None,
+                merge_dao: None,
+                pool: Some(DatabasePool::Sqlite(pool)),
+                read_replica: None,
+                daos,
+            })
+        }
+        #[cfg(feature = "mysql")]
+        Some("mysql") => {
+            let pool = with_retries(config, || {
+                persistence::mysql::connect(
+                    database_url,
+                    config.db_max_connections,
+                    config.db_acquire_timeout,
+                )
+            })
+            .await?;
+
+            let daos = decorate(
+                MySqlQuestionDao::new(pool.clone()).with_deletion(config.question_deletion),
+                MySqlAnswerDao::new(pool.clone()),
+                MySqlUnitOfWorkFactory::new(pool.clone()),
+                UnitOfWorkImportDao::new(MySqlUnitOfWorkFactory::new(pool.clone())),
+                MySqlArchiveDao::new(pool.clone()),
+                config,
+                cache,
+            );
+            Ok(Backend {
+                stats_dao: Arc::new(MySqlStatsDao::new(pool.clone())),
+                embedding_dao: None,
+                audit_dao: None,
+                subscription_dao: None,
+                attachment_dao: None,
+                moderation_dao: None,
+                tag_dao: None,
+                translation_dao: None,
+                link_preview_dao: None,
+                search_dao: None,
+                ingestion_dao: None,
+                word_filter_dao: None,
+                rest_hook_dao: None,
+                duplicate_dao: None,
+                user_dao: None,
+                activity_dao: None,
+                merge_dao: None,
+                pool: Some(DatabasePool::MySql(pool)),
+                read_replica: None,
+                daos,
+            })
+        }
+        scheme => Err(StartupError::UnsupportedScheme(
+            scheme.unwrap_or_default().to_owned(),
+        )),
+    }
+}
+
+async fn postgres_backend(
+    pool: PgPool,
+    read_replica: Option<ReadReplica>,
+    config: &AppConfig,
+    cache: Option<Arc<QueryCache>>,
+) -> Result<Backend, StartupError> {
+    let mut question_dao_impl =
+        QuestionDaoImpl::new(pool.clone()).with_deletion(config.question_deletion);
+    let mut answer_dao_impl = AnswerDaoImpl::new(pool.clone());
+    if let Some(read_replica) = &read_replica {
+        question_dao_impl = question_dao_impl.with_read_replica(read_replica.clone());
+        answer_dao_impl = answer_dao_impl.with_read_replica(read_replica.clone());
+    }
+
+    let daos = decorate(
+        question_dao_impl,
+        answer_dao_impl,
+        PgUnitOfWorkFactory::new(pool.clone()),
+        PgCopyImportDao::new(pool.clone()),
+        PgArchiveDao::new(pool.clone()),
+        config,
+        cache,
+    );
+    let embedding_dao = PgEmbeddingDao::connect(pool.clone())
+        .await
+        .map_err(StartupError::Schema)?;
+    Ok(Backend {
+        stats_dao: Arc::new(PgStatsDao::new(pool.clone())),
+        embedding_dao: embedding_dao
+            .map(|dao| Arc::new(dao) as Arc<dyn EmbeddingDao + Send + Sync>),
+        audit_dao: Some(Arc::new(PgAuditDao::new(pool.clone()))),
+        subscription_dao: Some(Arc::new(PgSubscriptionDao::new(pool.clone()))),
+        attachment_dao: Some(Arc::new(PgAttachmentDao::new(pool.clone()))),
+        moderation_dao: Some(Arc::new(PgModerationDao::new(pool.clone()))),
+        tag_dao: Some(Arc::new(PgTagDao::new(pool.clone()))),
+        translation_dao: Some(Arc::new(PgTranslationDao::new(pool.clone()))),
+        link_preview_dao: Some(Arc::new(PgLinkPreviewDao::new(pool.clone()))),
+        search_dao: Some(Arc::new(PgSearchDao::new(pool.clone()))),
+        ingestion_dao: Some(Arc::new(PgIngestionDao::new(pool.clone()))),
+        word_filter_dao: Some(Arc::new(PgWordFilterDao::new(pool.clone()))),
+        rest_hook_dao: Some(Arc::new(PgRestHookDao::new(pool.clone()))),
+        duplicate_dao: Some(Arc::new(PgDuplicateDao::new(pool.clone()))),
+        user_dao: Some(Arc::new(PgUserDao::new(pool.clone()))),
+        activity_dao: Some(Arc::new(PgActivityDao::new(pool.clone()))),
+        merge_dao: Some(Arc::new(PgMergeDao::new(pool.clone()))),
+        pool: Some(DatabasePool::Postgres(pool)),
+        read_replica,
+        daos,
+    })
+}
+
+// The database often comes up alongside the API, e.g. in docker compose, so the first
+// connection is retried before startup gives up.
+async fn with_retries<T, E, F, Fut>(config: &AppConfig, mut connect: F) -> Result<T, StartupError>
+where
+    E: StdError + Display + Send + Sync + 'static,
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<T, E>>,
+{
+    let attempts = config.db_connect_attempts.max(1);
+    let mut attempt = 1;
+    loop {
+        match connect().await {
+            Ok(value) => return Ok(value),
+            Err(err) if attempt < attempts => {
+                warn!(
+                    "Could not reach the database (attempt {} of {}): {}. Retrying in {:?}.",
+                    attempt, attempts, err, config.db_connect_retry_delay
+                );
+                tokio::time::sleep(config.db_connect_retry_delay).await;
+                attempt += 1;
+            }
+            Err(err) => {
+                return Err(StartupError::Unreachable {
+                    attempts,
+                    source: Box::new(err),
+                })
+            }
+        }
+    }
+}
+
+// Stacks the retry, circuit breaker, instrumentation and cache layers on top of the backend DAOs.
+fn decorate<Q, A, U, I, R>(
+    question_dao: Q,
+    answer_dao: A,
+    unit_of_work: U,
+    import_dao: I,
+    archive_dao: R,
+    config: &AppConfig,
+    cache: Option<Arc<QueryCache>>,
+) -> Daos
+where
+    Q: QuestionDao + Send + Sync + 'static,
+    A: AnswerDao + Send + Sync + 'static,
+    U: UnitOfWorkFactory + Send + Sync + 'static,
+    I: ImportDao + Send + Sync + 'static,
+    R: ArchiveDao + Send + Sync + 'static,
+{
+    let breaker = Arc::new(
+        CircuitBreaker::new(
+            config.breaker_failure_threshold,
+            config.breaker_open_duration,
+        )
+        .with_call_timeout(config.breaker_call_timeout),
+    );
+    // Under the retries, where the backend's own failures come from.
+    #[cfg(feature = "chaos")]
+    let (question_dao, answer_dao) = {
+        if config.chaos.is_enabled() {
+            warn!(
+                "Injecting {:?} of latency and a {} failure rate into database calls.",
+                config.chaos.latency, config.chaos.failure_rate
+            );
+        }
+        (
+            ChaosDao::new(question_dao, config.chaos),
+            ChaosDao::new(answer_dao, config.chaos),
+        )
+    };
+    // Instrumented inside the cache, so only calls that reach the backend are measured.
+    let question_dao = InstrumentedQuestionDao::new(CircuitBreakerQuestionDao::new(
+        RetryQuestionDao::new(question_dao, config.retry_policy),
+        breaker.clone(),
+    ));
+    let answer_dao = InstrumentedAnswerDao::new(CircuitBreakerAnswerDao::new(
+        RetryAnswerDao::new(answer_dao, config.retry_policy),
+        breaker,
+    ));
+
+    match cache {
+        Some(cache) => (
+            Arc::new(CachedQuestionDao::new(question_dao, cache.clone())),
+            Arc::new(CachedAnswerDao::new(answer_dao, cache.clone())),
+            Arc::new(CachedUnitOfWorkFactory::new(unit_of_work, cache.clone())),
+            Arc::new(CachedImportDao::new(import_dao, cache.clone())),
+            Arc::new(CachedArchiveDao::new(archive_dao, cache)),
+        ),
+        None => (
+            Arc::new(question_dao),
+            Arc::new(answer_dao),
+            Arc::new(unit_of_work),
+            Arc::new(import_dao),
+            Arc::new(archive_dao),
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io;
+
+    use super::*;
+
+    fn config(attempts: u32) -> AppConfig {
+        AppConfig {
+            db_connect_attempts: attempts,
+            db_connect_retry_delay: Duration::ZERO,
+            ..AppConfig::from_env()
+        }
+    }
+
+    #[tokio::test]
+    async fn with_retries_should_give_up_after_the_configured_attempts() {
+        let mut calls = 0;
+        let result: Result<(), _> = with_retries(&config(3), || {
+            calls += 1;
+            async { Err(io::Error::from(io::ErrorKind::ConnectionRefused)) }
+        })
+        .await;
+
+        assert!(matches!(
+            result,
+            Err(StartupError::Unreachable { attempts: 3, .. })
+        ));
+        assert_eq!(calls, 3);
+    }
+
+    #[tokio::test]
+    async fn with_retries_should_stop_at_the_first_success() {
+        let mut calls = 0;
+        let result = with_retries(&config(3), || {
+            calls += 1;
+            let succeed = calls == 2;
+            async move {
+                if succeed {
+                    Ok(calls)
+                } else {
+                    Err(io::Error::from(io::ErrorKind::ConnectionRefused))
+                }
+            }
+        })
+        .await;
+
+        assert_eq!(result.unwrap(), 2);
+    }
+
+    #[tokio::test]
+    async fn unknown_schemes_should_be_reported() {
+        let result = connect_database(&config(1), "redis://localhost").await;
+
+        assert!(matches!(
+            result,
+            Err(StartupError::UnsupportedScheme(scheme)) if scheme == "redis"
+        ));
+    }
+
+    #[test]
+    fn incomplete_settings_should_be_reported() {
+        let result = in_memory(AppConfig {
+            reply_email_domain: Some("replies.example.com".to_owned()),
+            reply_email_secret: None,
+            ..config(1)
+        });
+
+        let Err(err) = result else {
+            panic!("REPLY_EMAIL_DOMAIN without a secret should not start");
+        };
+        assert_eq!(
+            err.to_string(),
+            "REPLY_EMAIL_DOMAIN needs REPLY_EMAIL_SECRET to be set as well."
+        );
+    }
+
+    #[test]
+    fn database_timeouts_should_stay_below_the_request_deadline() {
+        let result = check_database_timeouts(&AppConfig {
+            request_timeout: Duration::from_secs(4),
+            breaker_call_timeout: Duration::from_secs(5),
+            ..config(1)
+        });
+
+        assert!(matches!(
+            result,
+            Err(StartupError::InvalidSetting {
+                setting: "BREAKER_CALL_TIMEOUT_MS",
+                ..
+            })
+        ));
+    }
+}
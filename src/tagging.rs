@@ -0,0 +1,395 @@
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use async_trait::async_trait;
+use thiserror::Error;
+
+use crate::{
+    config::{AppConfig, TaggerKind},
+    embedding::question_text,
+    models::DBError,
+    persistence::tag::{TagDao, TaggedQuestion},
+    startup::StartupError,
+};
+
+// The newest tagged questions suggestions are learned from.
+const CORPUS_SIZE: u32 = 5000;
+// Weaker matches are noise rather than suggestions.
+const MIN_SCORE: f32 = 0.1;
+// Existing tags a model is shown, most used first.
+#[cfg(feature = "openai")]
+const KNOWN_TAGS: usize = 100;
+
+const STOP_WORDS: [&str; 32] = [
+    "and", "are", "but", "can", "did", "does", "for", "from", "get", "has", "have", "how", "its",
+    "not", "one", "should", "that", "the", "them", "then", "there", "this", "use", "using", "was",
+    "what", "when", "where", "which", "who", "why", "with",
+];
+
+#[derive(Error, Debug)]
+pub enum TaggingError {
+    #[error(transparent)]
+    Database(#[from] DBError),
+    #[cfg(feature = "openai")]
+    #[error("Tag request failed: {0}")]
+    Request(String),
+}
+
+// Picks tags for a question's text; `index` holds what the existing questions are tagged.
+#[async_trait]
+pub trait TagSuggester {
+    // Best first, at most `limit`.
+    async fn suggest(
+        &self,
+        text: &str,
+        index: &TagIndex,
+        limit: usize,
+    ) -> Result<Vec<String>, TaggingError>;
+}
+
+// Managed as `Option<TagSuggestions>`; None while no suggester is configured or the
+// backend cannot list tagged questions.
+pub struct TagSuggestions {
+    pub suggester: Box<dyn TagSuggester + Send + Sync>,
+    pub tag_dao: Arc<dyn TagDao + Send + Sync>,
+    pub limit: usize,
+    // Whether questions created without tags get the suggested ones.
+    pub auto_apply: bool,
+    refresh: Duration,
+    index: Mutex<Option<(Instant, Arc<TagIndex>)>>,
+}
+
+impl TagSuggestions {
+    pub fn new(
+        suggester: Box<dyn TagSuggester + Send + Sync>,
+        tag_dao: Arc<dyn TagDao + Send + Sync>,
+        config: &AppConfig,
+    ) -> Self {
+        Self {
+            suggester,
+            tag_dao,
+            limit: config.max_suggested_tags,
+            auto_apply: config.auto_tag_questions,
+            refresh: config.tag_index_refresh,
+            index: Mutex::new(None),
+        }
+    }
+
+    pub async fn suggest(
+        &self,
+        title: &str,
+        description: Option<&str>,
+    ) -> Result<Vec<String>, TaggingError> {
+        let index = self.index().await?;
+        self.suggester
+            .suggest(&question_text(title, description), &index, self.limit)
+            .await
+    }
+
+    // Rebuilt once it is older than TAG_INDEX_REFRESH_SECS, so new tags are picked up
+    // without reading every tagged question per request.
+    async fn index(&self) -> Result<Arc<TagIndex>, DBError> {
+        if let Some((built, index)) = self.index.lock().unwrap().as_ref() {
+            if built.elapsed() < self.refresh {
+                return Ok(index.clone());
+            }
+        }
+
+        let questions = self.tag_dao.tagged_questions(CORPUS_SIZE).await?;
+        let index = Arc::new(TagIndex::build(&questions));
+        *self.index.lock().unwrap() = Some((Instant::now(), index.clone()));
+        Ok(index)
+    }
+}
+
+pub fn suggester_from_config(
+    config: &AppConfig,
+) -> Result<Option<Box<dyn TagSuggester + Send + Sync>>, StartupError> {
+    match config.tagger {
+        TaggerKind::None => Ok(None),
+        TaggerKind::TfIdf => Ok(Some(Box::new(TfIdfSuggester))),
+        #[cfg(feature = "openai")]
+        TaggerKind::OpenAi => Ok(Some(Box::new(OpenAiSuggester::new(
+            &config.tagger_api_url,
+            config.tagger_api_key.clone(),
+            config.tagger_model.clone(),
+        )))),
+        #[cfg(not(feature = "openai"))]
+        TaggerKind::OpenAi => Err(StartupError::MissingFeature {
+            setting: "TAG_SUGGESTER=openai",
+            feature: "openai",
+        }),
+    }
+}
+
+// Lowercased words of at least three characters, without the most common English ones.
+fn words(text: &str) -> impl Iterator<Item = String> + '_ {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|word| word.chars().count() >= 3)
+        .map(str::to_lowercase)
+        .filter(|word| !STOP_WORDS.contains(&word.as_str()))
+}
+
+struct TagProfile {
+    tag: String,
+    questions: usize,
+    weights: HashMap<String, f32>,
+    norm: f32,
+}
+
+// Each tag as the TF-IDF weighted words of the questions carrying it.
+pub struct TagIndex {
+    idf: HashMap<String, f32>,
+    tags: Vec<TagProfile>,
+}
+
+impl TagIndex {
+    pub fn build(questions: &[TaggedQuestion]) -> Self {
+        let mut document_frequency: HashMap<String, usize> = HashMap::new();
+        let mut term_counts: HashMap<&str, (usize, HashMap<String, f32>)> = HashMap::new();
+        for question in questions {
+            let text = question_text(&question.title, question.description.as_deref());
+            let mut counts: HashMap<String, f32> = HashMap::new();
+            for word in words(&text) {
+                *counts.entry(word).or_default() += 1.0;
+            }
+            for word in counts.keys() {
+                *document_frequency.entry(word.clone()).or_default() += 1;
+            }
+            for tag in &question.tags {
+                let (tagged, tag_counts) = term_counts.entry(tag.as_str()).or_default();
+                *tagged += 1;
+                for (word, count) in &counts {
+                    *tag_counts.entry(word.clone()).or_default() += count;
+                }
+            }
+        }
+
+        // Smoothed, so words in every question still count a little.
+        let total = questions.len() as f32;
+        let idf: HashMap<String, f32> = document_frequency
+            .into_iter()
+            .map(|(word, frequency)| {
+                let idf = ((1.0 + total) / (1.0 + frequency as f32)).ln() + 1.0;
+                (word, idf)
+            })
+            .collect();
+        let tags = term_counts
+            .into_iter()
+            .map(|(tag, (questions, counts))| {
+                let weights: HashMap<String, f32> = counts
+                    .into_iter()
+                    .map(|(word, count)| {
+                        let weight = count * idf[&word];
+                        (word, weight)
+                    })
+                    .collect();
+                let norm = weights
+                    .values()
+                    .map(|weight| weight * weight)
+                    .sum::<f32>()
+                    .sqrt();
+                TagProfile {
+                    tag: tag.to_owned(),
+                    questions,
+                    weights,
+                    norm,
+                }
+            })
+            .collect();
+
+        Self { idf, tags }
+    }
+
+    // Tags by cosine similarity to `text`, best first, leaving out those below MIN_SCORE.
+    pub fn score(&self, text: &str) -> Vec<(String, f32)> {
+        let mut query: HashMap<String, f32> = HashMap::new();
+        for word in words(text) {
+            if let Some(idf) = self.idf.get(&word) {
+                *query.entry(word).or_default() += idf;
+            }
+        }
+        let query_norm = query
+            .values()
+            .map(|weight| weight * weight)
+            .sum::<f32>()
+            .sqrt();
+        if query_norm == 0.0 {
+            return vec![];
+        }
+
+        let mut scores: Vec<(String, f32)> = self
+            .tags
+            .iter()
+            .filter(|profile| profile.norm > 0.0)
+            .map(|profile| {
+                let dot: f32 = query
+                    .iter()
+                    .filter_map(|(word, weight)| Some(weight * profile.weights.get(word)?))
+                    .sum();
+                (profile.tag.clone(), dot / (query_norm * profile.norm))
+            })
+            .filter(|(_, score)| *score >= MIN_SCORE)
+            .collect();
+        scores.sort_by(|a, b| b.1.total_cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        scores
+    }
+
+    // Most used first.
+    pub fn known_tags(&self) -> Vec<&str> {
+        let mut tags: Vec<&TagProfile> = self.tags.iter().collect();
+        tags.sort_by(|a, b| {
+            b.questions
+                .cmp(&a.questions)
+                .then_with(|| a.tag.cmp(&b.tag))
+        });
+        tags.into_iter()
+            .map(|profile| profile.tag.as_str())
+            .collect()
+    }
+}
+
+// Only ever suggests tags already in use.
+pub struct TfIdfSuggester;
+
+#[async_trait]
+impl TagSuggester for TfIdfSuggester {
+    async fn suggest(
+        &self,
+        text: &str,
+        index: &TagIndex,
+        limit: usize,
+    ) -> Result<Vec<String>, TaggingError> {
+        Ok(index
+            .score(text)
+            .into_iter()
+            .take(limit)
+            .map(|(tag, _)| tag)
+            .collect())
+    }
+}
+
+// Any server implementing OpenAI's `/chat/completions` endpoint. The model is shown the
+// tags in use so it reuses them, but may also come up with new ones.
+#[cfg(feature = "openai")]
+pub struct OpenAiSuggester {
+    client: reqwest::Client,
+    url: String,
+    api_key: Option<String>,
+    model: String,
+}
+
+#[cfg(feature = "openai")]
+impl OpenAiSuggester {
+    pub fn new(base_url: &str, api_key: Option<String>, model: String) -> Self {
+        Self {
+            client: reqwest::Client::new(),
+            url: format!("{}/chat/completions", base_url.trim_end_matches('/')),
+            api_key,
+            model,
+        }
+    }
+}
+
+#[cfg(feature = "openai")]
+#[async_trait]
+impl TagSuggester for OpenAiSuggester {
+    async fn suggest(
+        &self,
+        text: &str,
+        index: &TagIndex,
+        limit: usize,
+    ) -> Result<Vec<String>, TaggingError> {
+        #[derive(serde::Deserialize)]
+        struct Response {
+            choices: Vec<Choice>,
+        }
+
+        #[derive(serde::Deserialize)]
+        struct Choice {
+            message: Message,
+        }
+
+        #[derive(serde::Deserialize)]
+        struct Message {
+            content: Option<String>,
+        }
+
+        let known: Vec<&str> = index.known_tags().into_iter().take(KNOWN_TAGS).collect();
+        let instructions = format!(
+            "You tag questions on a question and answer site. Reply with up to {} short \
+             lowercase tags for the question below, separated by commas, and nothing else. \
+             Prefer these tags already in use: {}.",
+            limit,
+            known.join(", ")
+        );
+        let mut request = self.client.post(&self.url).json(&serde_json::json!({
+            "model": self.model,
+            "messages": [
+                { "role": "system", "content": instructions },
+                { "role": "user", "content": text },
+            ],
+        }));
+        if let Some(api_key) = &self.api_key {
+            request = request.bearer_auth(api_key);
+        }
+
+        let response: Response = request
+            .send()
+            .await
+            .and_then(|response| response.error_for_status())
+            .map_err(|err| TaggingError::Request(err.to_string()))?
+            .json()
+            .await
+            .map_err(|err| TaggingError::Request(err.to_string()))?;
+
+        let content = response
+            .choices
+            .into_iter()
+            .next()
+            .and_then(|choice| choice.message.content)
+            .unwrap_or_default();
+        let mut tags: Vec<String> = Vec::new();
+        for tag in content.split([',', '\n']) {
+            let tag = tag.trim().trim_start_matches('#').to_lowercase();
+            if !tag.is_empty() && !tags.contains(&tag) {
+                tags.push(tag);
+            }
+        }
+        tags.truncate(limit);
+        Ok(tags)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tagged(title: &str, tags: &[&str]) -> TaggedQuestion {
+        TaggedQuestion {
+            title: title.to_owned(),
+            description: None,
+            tags: tags.iter().map(|tag| tag.to_string()).collect(),
+        }
+    }
+
+    #[test]
+    fn tag_index_should_rank_tags_of_similar_questions() {
+        let index = TagIndex::build(&[
+            tagged("Refund for a duplicate invoice", &["billing", "refunds"]),
+            tagged("Invoice shows the wrong address", &["billing"]),
+            tagged("API token expired after rotation", &["api"]),
+            tagged("Rate limits of the API", &["api"]),
+        ]);
+
+        let scores = index.score("How do I get a refund for my invoice?");
+
+        let tags: Vec<&str> = scores.iter().map(|(tag, _)| tag.as_str()).collect();
+        assert_eq!(tags, ["refunds", "billing"]);
+        assert!(index.score("Something else entirely").is_empty());
+        assert_eq!(index.known_tags(), ["api", "billing", "refunds"]);
+    }
+}
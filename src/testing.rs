@@ -0,0 +1,238 @@
+// Mock DAOs for unit tests here and for integration tests and other crates, behind the
+// `test-support` feature. Each call returns the response mocked for it once; a call
+// nothing was mocked for panics, so unexpected DAO use fails the test.
+
+use async_stream::try_stream;
+use async_trait::async_trait;
+use tokio::sync::Mutex;
+
+use crate::{
+    models::{
+        Answer, AnswerDetail, AnswerSort, AnswerUuid, DBError, Listed, Question, QuestionDetail,
+        QuestionFilter, QuestionSort, QuestionUuid, Window,
+    },
+    persistence::{answer_dao::AnswerDao, question_dao::QuestionDao, unknown_question, RowStream},
+};
+
+#[derive(Default)]
+pub struct QuestionDaoMock {
+    create_question_response: Mutex<Option<Result<QuestionDetail, DBError>>>,
+    delete_question_response: Mutex<Option<Result<(), DBError>>>,
+    update_question_response: Mutex<Option<Result<QuestionDetail, DBError>>>,
+    get_questions_response: Mutex<Option<Result<Vec<QuestionDetail>, DBError>>>,
+    get_questions_matching_response: Mutex<Option<Result<Vec<QuestionDetail>, DBError>>>,
+}
+
+impl QuestionDaoMock {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn mock_create_question_response(&mut self, response: Result<QuestionDetail, DBError>) {
+        self.create_question_response = Mutex::new(Some(response));
+    }
+
+    pub fn mock_delete_question_response(&mut self, response: Result<(), DBError>) {
+        self.delete_question_response = Mutex::new(Some(response));
+    }
+
+    pub fn mock_update_question_response(&mut self, response: Result<QuestionDetail, DBError>) {
+        self.update_question_response = Mutex::new(Some(response));
+    }
+
+    pub fn mock_get_questions_response(&mut self, response: Result<Vec<QuestionDetail>, DBError>) {
+        self.get_questions_response = Mutex::new(Some(response));
+    }
+
+    pub fn mock_get_questions_matching_response(
+        &mut self,
+        response: Result<Vec<QuestionDetail>, DBError>,
+    ) {
+        self.get_questions_matching_response = Mutex::new(Some(response));
+    }
+}
+
+#[async_trait]
+impl QuestionDao for QuestionDaoMock {
+    async fn create_question(&self, _: Question) -> Result<QuestionDetail, DBError> {
+        self.create_question_response
+            .lock()
+            .await
+            .take()
+            .expect("create_question_response should not be None.")
+    }
+
+    async fn delete_question(&self, _: QuestionUuid) -> Result<(), DBError> {
+        self.delete_question_response
+            .lock()
+            .await
+            .take()
+            .expect("delete_question_response should not be None.")
+    }
+
+    async fn update_question(
+        &self,
+        _: QuestionUuid,
+        _: Question,
+        _: i64,
+    ) -> Result<QuestionDetail, DBError> {
+        self.update_question_response
+            .lock()
+            .await
+            .take()
+            .expect("update_question_response should not be None.")
+    }
+
+    async fn get_questions(&self) -> Result<Vec<QuestionDetail>, DBError> {
+        self.get_questions_response
+            .lock()
+            .await
+            .take()
+            .expect("get_questions_response should not be None.")
+    }
+
+    // Serves the `get_questions` response, looking the question up in it.
+    async fn get_question(&self, question_uuid: QuestionUuid) -> Result<QuestionDetail, DBError> {
+        self.get_questions()
+            .await?
+            .into_iter()
+            .find(|question| question.question_uuid == question_uuid)
+            .ok_or_else(|| unknown_question(&question_uuid))
+    }
+
+    async fn get_questions_matching(
+        &self,
+        _: &QuestionFilter,
+    ) -> Result<Vec<QuestionDetail>, DBError> {
+        self.get_questions_matching_response
+            .lock()
+            .await
+            .take()
+            .expect("get_questions_matching_response should not be None.")
+    }
+
+    // Serves the `get_questions` response as it is mocked, cut to the window.
+    async fn list_questions(
+        &self,
+        _: &QuestionFilter,
+        _: bool,
+        _: QuestionSort,
+        window: Window,
+    ) -> Result<Listed<QuestionDetail>, DBError> {
+        Ok(window.of(self.get_questions().await?))
+    }
+
+    async fn list_unanswered_questions(
+        &self,
+        window: Window,
+    ) -> Result<Listed<QuestionDetail>, DBError> {
+        let mut questions = self.get_questions().await?;
+        questions.retain(|question| question.answer_count == 0);
+        Ok(window.of(questions))
+    }
+
+    fn stream_questions(&self, filter: QuestionFilter) -> RowStream<'_, QuestionDetail> {
+        Box::pin(try_stream! {
+            for question in self.get_questions().await? {
+                if filter.matches(&question) {
+                    yield question;
+                }
+            }
+        })
+    }
+}
+
+#[derive(Default)]
+pub struct AnswerDaoMock {
+    create_answer_response: Mutex<Option<Result<AnswerDetail, DBError>>>,
+    delete_answer_response: Mutex<Option<Result<(), DBError>>>,
+    update_answer_response: Mutex<Option<Result<AnswerDetail, DBError>>>,
+    get_answers_response: Mutex<Option<Result<Vec<AnswerDetail>, DBError>>>,
+}
+
+impl AnswerDaoMock {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn mock_create_answer(&mut self, response: Result<AnswerDetail, DBError>) {
+        self.create_answer_response = Mutex::new(Some(response));
+    }
+
+    pub fn mock_delete_answer(&mut self, response: Result<(), DBError>) {
+        self.delete_answer_response = Mutex::new(Some(response));
+    }
+
+    pub fn mock_update_answer(&mut self, response: Result<AnswerDetail, DBError>) {
+        self.update_answer_response = Mutex::new(Some(response));
+    }
+
+    pub fn mock_get_answers(&mut self, response: Result<Vec<AnswerDetail>, DBError>) {
+        self.get_answers_response = Mutex::new(Some(response));
+    }
+}
+
+#[async_trait]
+impl AnswerDao for AnswerDaoMock {
+    async fn create_answer(&self, _: Answer) -> Result<AnswerDetail, DBError> {
+        self.create_answer_response
+            .lock()
+            .await
+            .take()
+            .expect("create_answer_response should not be None.")
+    }
+    async fn delete_answer(&self, _: AnswerUuid) -> Result<(), DBError> {
+        self.delete_answer_response
+            .lock()
+            .await
+            .take()
+            .expect("delete_answer_response should not be None.")
+    }
+    async fn update_answer(
+        &self,
+        _: AnswerUuid,
+        _: String,
+        _: i64,
+    ) -> Result<AnswerDetail, DBError> {
+        self.update_answer_response
+            .lock()
+            .await
+            .take()
+            .expect("update_answer_response should not be None.")
+    }
+    async fn get_answers(&self, _: QuestionUuid) -> Result<Vec<AnswerDetail>, DBError> {
+        self.get_answers_response
+            .lock()
+            .await
+            .take()
+            .expect("get_answers_response should not be None.")
+    }
+    fn stream_answers(&self, question_uuid: QuestionUuid) -> RowStream<'_, AnswerDetail> {
+        Box::pin(try_stream! {
+            for answer in self.get_answers(question_uuid).await? {
+                yield answer;
+            }
+        })
+    }
+    // Serves the `get_answers` response as it is mocked, cut to the window.
+    async fn list_answers(
+        &self,
+        question_uuid: QuestionUuid,
+        _: bool,
+        _: AnswerSort,
+        window: Window,
+    ) -> Result<Listed<AnswerDetail>, DBError> {
+        Ok(window.of(self.get_answers(question_uuid).await?))
+    }
+    // Serves the `get_answers` response, whichever questions are asked for.
+    async fn get_answers_of_questions(
+        &self,
+        _: &[QuestionUuid],
+    ) -> Result<Vec<AnswerDetail>, DBError> {
+        self.get_answers_response
+            .lock()
+            .await
+            .take()
+            .expect("get_answers_response should not be None.")
+    }
+}
@@ -0,0 +1,309 @@
+use std::{io::Cursor, sync::Arc};
+
+use image::{imageops::FilterType, DynamicImage, ImageFormat, ImageReader};
+use log::warn;
+use thiserror::Error;
+use tokio::sync::mpsc;
+
+use crate::{
+    attachments::{ObjectStore, StoreError},
+    backpressure,
+    models::{Attachment, AttachmentVariant, DBError},
+    persistence::attachment::AttachmentDao,
+};
+
+// Images waiting for thumbnails. Past this, new ones are dropped with a warning and
+// only have their full size download.
+const QUEUE_CAPACITY: usize = 100;
+
+// Larger images are not decoded, so a small file that inflates to a huge bitmap cannot
+// exhaust memory.
+const MAX_PIXELS: u64 = 40_000_000;
+
+const THUMBNAIL_TYPE: &str = "image/png";
+
+#[derive(Error, Debug)]
+pub enum ThumbnailError {
+    #[error("Cannot make thumbnails: {0}")]
+    Image(String),
+    #[error(transparent)]
+    Store(#[from] StoreError),
+    #[error(transparent)]
+    Database(#[from] DBError),
+}
+
+// Other images, e.g. SVG, keep just their full size download.
+pub fn is_thumbnailable(content_type: &str) -> bool {
+    matches!(
+        content_type,
+        "image/png" | "image/jpeg" | "image/gif" | "image/webp"
+    )
+}
+
+// Makes the thumbnails of image attachments from a queue drained in the background.
+// Best effort like notifications: failures are logged and nothing is retried.
+#[derive(Clone)]
+pub struct Thumbnails {
+    queue: mpsc::Sender<Attachment>,
+}
+
+impl Thumbnails {
+    // Starts the worker, which handles one image at a time.
+    pub fn spawn(
+        attachment_dao: Arc<dyn AttachmentDao + Send + Sync>,
+        object_store: Arc<dyn ObjectStore + Send + Sync>,
+        sizes: Vec<u32>,
+    ) -> Self {
+        let (queue, mut jobs) = mpsc::channel::<Attachment>(QUEUE_CAPACITY);
+
+        tokio::spawn(async move {
+            while let Some(attachment) = jobs.recv().await {
+                backpressure::record_queue_depth("thumbnails", jobs.len(), QUEUE_CAPACITY);
+                let made = make_thumbnails(
+                    &attachment,
+                    &sizes,
+                    attachment_dao.as_ref(),
+                    object_store.as_ref(),
+                )
+                .await;
+                if let Err(err) = made {
+                    warn!(
+                        "Thumbnails of attachment {} failed: {}",
+                        attachment.attachment_uuid, err
+                    );
+                }
+            }
+        });
+
+        Self { queue }
+    }
+
+    // Never waits for the queue; attachments that are not images are skipped.
+    pub fn enqueue(&self, attachment: Attachment) {
+        if !is_thumbnailable(&attachment.content_type) {
+            return;
+        }
+        if let Err(err) = self.queue.try_send(attachment) {
+            warn!("Dropped a thumbnail job: {}", err);
+        }
+        backpressure::record_queue("thumbnails", &self.queue);
+    }
+}
+
+// Stores a PNG thumbnail for each of `sizes`, the longest edge in pixels, that is
+// smaller than the image, next to it in the store, and records them as its variants.
+pub async fn make_thumbnails(
+    attachment: &Attachment,
+    sizes: &[u32],
+    attachment_dao: &(dyn AttachmentDao + Send + Sync),
+    object_store: &(dyn ObjectStore + Send + Sync),
+) -> Result<Vec<AttachmentVariant>, ThumbnailError> {
+    let bytes = object_store.get(&attachment.storage_key).await?;
+    let sizes = sizes.to_vec();
+    // Decoding and resizing are CPU bound, so they stay off the async workers.
+    let thumbnails = tokio::task::spawn_blocking(move || thumbnails(&bytes, &sizes))
+        .await
+        .map_err(|err| ThumbnailError::Image(err.to_string()))??;
+
+    let mut variants = vec![];
+    for thumbnail in thumbnails {
+        let name = format!("thumbnail-{}", thumbnail.size);
+        let storage_key = format!("{}.{}", attachment.storage_key, name);
+        let size_bytes = thumbnail.png.len() as i64;
+        object_store
+            .put(&storage_key, THUMBNAIL_TYPE, thumbnail.png)
+            .await?;
+        variants.push(AttachmentVariant {
+            name,
+            width: thumbnail.width,
+            height: thumbnail.height,
+            content_type: THUMBNAIL_TYPE.to_owned(),
+            size_bytes,
+            storage_key,
+        });
+    }
+
+    attachment_dao
+        .set_variants(attachment.attachment_uuid, variants.clone())
+        .await?;
+    Ok(variants)
+}
+
+struct Thumbnail {
+    size: u32,
+    png: Vec<u8>,
+    width: u32,
+    height: u32,
+}
+
+// One for each of `sizes` smaller than the image. Animated images are thumbnailed by
+// their first frame.
+fn thumbnails(bytes: &[u8], sizes: &[u32]) -> Result<Vec<Thumbnail>, ThumbnailError> {
+    let reader = || {
+        ImageReader::new(Cursor::new(bytes))
+            .with_guessed_format()
+            .map_err(|err| ThumbnailError::Image(err.to_string()))
+    };
+    let (width, height) = reader()?
+        .into_dimensions()
+        .map_err(|err| ThumbnailError::Image(err.to_string()))?;
+    if width as u64 * height as u64 > MAX_PIXELS {
+        return Err(ThumbnailError::Image(format!(
+            "{}x{} is more than {} pixels",
+            width, height, MAX_PIXELS
+        )));
+    }
+    let image = reader()?
+        .decode()
+        .map_err(|err| ThumbnailError::Image(err.to_string()))?;
+
+    sizes
+        .iter()
+        .filter(|&&size| size < width.max(height))
+        .map(|&size| {
+            // Blends the pixels each one covers, so thin lines do not vanish.
+            let thumbnail =
+                DynamicImage::from(image.resize(size, size, FilterType::Triangle).to_rgba8());
+            let mut encoded = Cursor::new(vec![]);
+            thumbnail
+                .write_to(&mut encoded, ImageFormat::Png)
+                .map_err(|err| ThumbnailError::Image(err.to_string()))?;
+            Ok(Thumbnail {
+                size,
+                png: encoded.into_inner(),
+                width: thumbnail.width(),
+                height: thumbnail.height(),
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        attachments::{self, LocalObjectStore},
+        models::{empty_metadata, AttachmentStatus, AttachmentUuid, NewAttachment, Question},
+        persistence::{
+            memory::{InMemoryAttachmentDao, InMemoryQuestionDao, MemoryStore},
+            question_dao::QuestionDao,
+        },
+    };
+
+    fn encoded(width: u32, height: u32, format: ImageFormat) -> Vec<u8> {
+        let image = image::RgbaImage::from_fn(width, height, |x, y| {
+            image::Rgba([(x % 256) as u8, (y % 256) as u8, 200, 255])
+        });
+        let mut encoded = Cursor::new(vec![]);
+        DynamicImage::from(image)
+            .write_to(&mut encoded, format)
+            .unwrap();
+        encoded.into_inner()
+    }
+
+    #[test]
+    fn jpeg_gif_and_webp_should_be_thumbnailed_as_png() {
+        for (format, content_type) in [
+            (ImageFormat::Jpeg, "image/jpeg"),
+            (ImageFormat::Gif, "image/gif"),
+            (ImageFormat::WebP, "image/webp"),
+        ] {
+            assert!(is_thumbnailable(content_type));
+
+            let made = thumbnails(&encoded(200, 300, format), &[100]).unwrap();
+
+            assert_eq!(made.len(), 1, "{}", content_type);
+            assert_eq!(
+                (made[0].width, made[0].height),
+                (67, 100),
+                "{}",
+                content_type
+            );
+            let thumbnail = image::load_from_memory_with_format(&made[0].png, ImageFormat::Png);
+            assert_eq!(thumbnail.unwrap().height(), 100, "{}", content_type);
+        }
+        assert!(!is_thumbnailable("image/svg+xml"));
+    }
+
+    #[test]
+    fn images_with_too_many_pixels_should_not_be_decoded() {
+        let mut huge = encoded(1, 1, ImageFormat::Png);
+        // The IHDR chunk's width and height, then its CRC.
+        huge[16..20].copy_from_slice(&100_000u32.to_be_bytes());
+        huge[20..24].copy_from_slice(&100_000u32.to_be_bytes());
+        let mut crc = flate2::Crc::new();
+        crc.update(&huge[12..29]);
+        huge[29..33].copy_from_slice(&crc.sum().to_be_bytes());
+
+        let Err(ThumbnailError::Image(message)) = thumbnails(&huge, &[160]) else {
+            panic!("Expected an image error");
+        };
+        assert_eq!(message, "100000x100000 is more than 40000000 pixels");
+    }
+
+    #[tokio::test]
+    async fn thumbnails_should_be_stored_and_recorded_as_variants() {
+        let store = MemoryStore::new();
+        let question = InMemoryQuestionDao::new(store.clone())
+            .create_question(Question {
+                title: "title".to_owned(),
+                description: None,
+                metadata: empty_metadata(),
+            })
+            .await
+            .unwrap();
+        let attachment_dao = InMemoryAttachmentDao::new(store);
+        let root = std::env::temp_dir().join(format!("qa-thumbnails-{}", AttachmentUuid::new_v4()));
+        let object_store = LocalObjectStore::new(root.clone());
+
+        let attachment_uuid = AttachmentUuid::new_v4();
+        let attachment = attachment_dao
+            .create_attachment(NewAttachment {
+                attachment_uuid,
+                question_uuid: question.question_uuid,
+                answer_uuid: None,
+                filename: "photo.png".to_owned(),
+                content_type: "image/png".to_owned(),
+                size_bytes: 0,
+                storage_key: attachments::storage_key(attachment_uuid),
+                status: AttachmentStatus::Ready,
+            })
+            .await
+            .unwrap();
+        object_store
+            .put(
+                &attachment.storage_key,
+                "image/png",
+                encoded(300, 200, ImageFormat::Png),
+            )
+            .await
+            .unwrap();
+
+        let variants = make_thumbnails(&attachment, &[160, 640], &attachment_dao, &object_store)
+            .await
+            .unwrap();
+
+        assert_eq!(variants.len(), 1, "no thumbnail is larger than the image");
+        assert_eq!(variants[0].name, "thumbnail-160");
+        assert_eq!((variants[0].width, variants[0].height), (160, 107));
+        let stored = object_store.get(&variants[0].storage_key).await.unwrap();
+        assert_eq!(stored.len() as i64, variants[0].size_bytes);
+        assert_eq!(
+            image::load_from_memory_with_format(&stored, ImageFormat::Png)
+                .unwrap()
+                .width(),
+            160
+        );
+        assert_eq!(
+            attachment_dao
+                .get_attachment(attachment_uuid)
+                .await
+                .unwrap()
+                .unwrap()
+                .variants,
+            variants
+        );
+
+        std::fs::remove_dir_all(root).unwrap();
+    }
+}
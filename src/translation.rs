@@ -0,0 +1,378 @@
+use std::{collections::BTreeMap, sync::Arc};
+
+use async_trait::async_trait;
+use log::warn;
+use thiserror::Error;
+
+use crate::{
+    config::{AppConfig, TranslatorKind},
+    models::{DBError, QuestionDetail},
+    persistence::translation::{Translation, TranslationDao},
+    startup::StartupError,
+};
+
+// The `entity` of translated questions in the `translations` table.
+pub const QUESTION: &str = "question";
+
+#[derive(Error, Debug)]
+pub enum TranslationError {
+    #[error(transparent)]
+    Database(#[from] DBError),
+    #[cfg(feature = "translation")]
+    #[error("Translation request failed: {0}")]
+    Request(String),
+}
+
+// Translates texts into a language, detecting the language they are written in.
+#[async_trait]
+pub trait Translator {
+    // One translation per text, in order. `language` is a lowercase code such as `es` or
+    // `pt-br`.
+    async fn translate(
+        &self,
+        texts: &[String],
+        language: &str,
+    ) -> Result<Vec<String>, TranslationError>;
+}
+
+// Managed as `Option<Translations>`; None while no translator is configured or the backend
+// cannot cache translations.
+pub struct Translations {
+    pub translator: Box<dyn Translator + Send + Sync>,
+    pub translation_dao: Arc<dyn TranslationDao + Send + Sync>,
+}
+
+impl Translations {
+    // The question with its title and description in `language`. Translations are cached
+    // until the question is edited; failing to cache one only costs a new one next time.
+    pub async fn translate_question(
+        &self,
+        mut question: QuestionDetail,
+        language: &str,
+    ) -> Result<QuestionDetail, TranslationError> {
+        let mut fields = BTreeMap::from([("title".to_owned(), question.title.clone())]);
+        if let Some(description) = &question.description {
+            fields.insert("description".to_owned(), description.clone());
+        }
+
+        let mut translated = self
+            .translate_fields(
+                QUESTION,
+                question.question_uuid.0,
+                question.version,
+                language,
+                fields,
+            )
+            .await?;
+        if let Some(title) = translated.remove("title") {
+            question.title = title;
+        }
+        if question.description.is_some() {
+            question.description = translated.remove("description");
+        }
+        Ok(question)
+    }
+
+    async fn translate_fields(
+        &self,
+        entity: &'static str,
+        entity_uuid: uuid::Uuid,
+        version: i64,
+        language: &str,
+        fields: BTreeMap<String, String>,
+    ) -> Result<BTreeMap<String, String>, TranslationError> {
+        let cached = self
+            .translation_dao
+            .get_translation(entity, entity_uuid, language)
+            .await?;
+        if let Some(cached) = cached.filter(|cached| cached.source_version == version) {
+            return Ok(cached.fields);
+        }
+
+        let (names, texts): (Vec<String>, Vec<String>) = fields.into_iter().unzip();
+        let translations = self.translator.translate(&texts, language).await?;
+        let fields: BTreeMap<String, String> = names.into_iter().zip(translations).collect();
+
+        let stored = self
+            .translation_dao
+            .store_translation(Translation {
+                entity,
+                entity_uuid,
+                language: language.to_owned(),
+                source_version: version,
+                fields: fields.clone(),
+            })
+            .await;
+        if let Err(err) = stored {
+            warn!(
+                "Error on caching a translation of {}: {:?}",
+                entity_uuid, err
+            );
+        }
+        Ok(fields)
+    }
+}
+
+// A language tag such as `es` or `pt-BR`, lowercased; None for anything else.
+pub fn parse_language(language: &str) -> Option<String> {
+    let mut parts = language.split('-');
+    let primary = parts.next()?;
+    let region = parts.next();
+    let valid = (2..=3).contains(&primary.len())
+        && primary.chars().all(|c| c.is_ascii_alphabetic())
+        && region.is_none_or(|region| {
+            (2..=4).contains(&region.len()) && region.chars().all(|c| c.is_ascii_alphanumeric())
+        })
+        && parts.next().is_none();
+    valid.then(|| language.to_ascii_lowercase())
+}
+
+pub fn translator_from_config(
+    config: &AppConfig,
+) -> Result<Option<Box<dyn Translator + Send + Sync>>, StartupError> {
+    #[cfg(feature = "translation")]
+    let (url, api_key) = (
+        config.translator_api_url.clone(),
+        config.translator_api_key.clone(),
+    );
+    match config.translator {
+        TranslatorKind::None => Ok(None),
+        #[cfg(feature = "translation")]
+        TranslatorKind::DeepL => Ok(Some(Box::new(DeepLTranslator::new(
+            url.as_deref().unwrap_or("https://api-free.deepl.com"),
+            api_key.ok_or(StartupError::MissingSetting {
+                setting: "TRANSLATOR=deepl",
+                needed: "TRANSLATOR_API_KEY",
+            })?,
+        )))),
+        #[cfg(feature = "translation")]
+        TranslatorKind::Google => Ok(Some(Box::new(GoogleTranslator::new(
+            url.as_deref()
+                .unwrap_or("https://translation.googleapis.com/language/translate/v2"),
+            api_key.ok_or(StartupError::MissingSetting {
+                setting: "TRANSLATOR=google",
+                needed: "TRANSLATOR_API_KEY",
+            })?,
+        )))),
+        #[cfg(feature = "translation")]
+        TranslatorKind::LibreTranslate => Ok(Some(Box::new(LibreTranslator::new(
+            url.as_deref().unwrap_or("https://libretranslate.com"),
+            api_key,
+        )))),
+        #[cfg(not(feature = "translation"))]
+        _ => Err(StartupError::MissingFeature {
+            setting: "TRANSLATOR",
+            feature: "translation",
+        }),
+    }
+}
+
+#[cfg(feature = "translation")]
+async fn post_json<T: serde::de::DeserializeOwned>(
+    request: reqwest::RequestBuilder,
+) -> Result<T, TranslationError> {
+    request
+        .send()
+        .await
+        .and_then(|response| response.error_for_status())
+        .map_err(|err| TranslationError::Request(err.to_string()))?
+        .json()
+        .await
+        .map_err(|err| TranslationError::Request(err.to_string()))
+}
+
+#[cfg(feature = "translation")]
+fn one_per_text(
+    texts: &[String],
+    translations: Vec<String>,
+) -> Result<Vec<String>, TranslationError> {
+    if translations.len() != texts.len() {
+        return Err(TranslationError::Request(format!(
+            "Expected {} translations but got {}",
+            texts.len(),
+            translations.len()
+        )));
+    }
+    Ok(translations)
+}
+
+// DeepL's `/v2/translate`; the free and the paid API only differ in their URL.
+#[cfg(feature = "translation")]
+pub struct DeepLTranslator {
+    client: reqwest::Client,
+    url: String,
+    api_key: String,
+}
+
+#[cfg(feature = "translation")]
+impl DeepLTranslator {
+    pub fn new(base_url: &str, api_key: String) -> Self {
+        Self {
+            client: reqwest::Client::new(),
+            url: format!("{}/v2/translate", base_url.trim_end_matches('/')),
+            api_key,
+        }
+    }
+}
+
+#[cfg(feature = "translation")]
+#[async_trait]
+impl Translator for DeepLTranslator {
+    async fn translate(
+        &self,
+        texts: &[String],
+        language: &str,
+    ) -> Result<Vec<String>, TranslationError> {
+        #[derive(serde::Deserialize)]
+        struct Response {
+            translations: Vec<Text>,
+        }
+
+        #[derive(serde::Deserialize)]
+        struct Text {
+            text: String,
+        }
+
+        let request = self
+            .client
+            .post(&self.url)
+            .header("Authorization", format!("DeepL-Auth-Key {}", self.api_key))
+            .json(&serde_json::json!({
+                "text": texts,
+                "target_lang": language.to_ascii_uppercase(),
+            }));
+        let response: Response = post_json(request).await?;
+
+        let translations = response.translations.into_iter().map(|t| t.text).collect();
+        one_per_text(texts, translations)
+    }
+}
+
+// Google Cloud Translation's basic v2 API, with an API key.
+#[cfg(feature = "translation")]
+pub struct GoogleTranslator {
+    client: reqwest::Client,
+    url: String,
+    api_key: String,
+}
+
+#[cfg(feature = "translation")]
+impl GoogleTranslator {
+    pub fn new(url: &str, api_key: String) -> Self {
+        Self {
+            client: reqwest::Client::new(),
+            url: url.to_owned(),
+            api_key,
+        }
+    }
+}
+
+#[cfg(feature = "translation")]
+#[async_trait]
+impl Translator for GoogleTranslator {
+    async fn translate(
+        &self,
+        texts: &[String],
+        language: &str,
+    ) -> Result<Vec<String>, TranslationError> {
+        #[derive(serde::Deserialize)]
+        struct Response {
+            data: Data,
+        }
+
+        #[derive(serde::Deserialize)]
+        struct Data {
+            translations: Vec<Text>,
+        }
+
+        #[derive(serde::Deserialize)]
+        #[serde(rename_all = "camelCase")]
+        struct Text {
+            translated_text: String,
+        }
+
+        let request = self
+            .client
+            .post(&self.url)
+            .query(&[("key", &self.api_key)])
+            .json(&serde_json::json!({
+                "q": texts,
+                "target": language,
+                "format": "text",
+            }));
+        let response: Response = post_json(request).await?;
+
+        let translations = response
+            .data
+            .translations
+            .into_iter()
+            .map(|t| t.translated_text)
+            .collect();
+        one_per_text(texts, translations)
+    }
+}
+
+// A LibreTranslate server, which can be self-hosted; public ones need an API key.
+#[cfg(feature = "translation")]
+pub struct LibreTranslator {
+    client: reqwest::Client,
+    url: String,
+    api_key: Option<String>,
+}
+
+#[cfg(feature = "translation")]
+impl LibreTranslator {
+    pub fn new(base_url: &str, api_key: Option<String>) -> Self {
+        Self {
+            client: reqwest::Client::new(),
+            url: format!("{}/translate", base_url.trim_end_matches('/')),
+            api_key,
+        }
+    }
+}
+
+#[cfg(feature = "translation")]
+#[async_trait]
+impl Translator for LibreTranslator {
+    async fn translate(
+        &self,
+        texts: &[String],
+        language: &str,
+    ) -> Result<Vec<String>, TranslationError> {
+        #[derive(serde::Deserialize)]
+        #[serde(rename_all = "camelCase")]
+        struct Response {
+            translated_text: Vec<String>,
+        }
+
+        // It only knows the primary language, e.g. `pt` for `pt-br`.
+        let target = language.split('-').next().unwrap_or(language);
+        let request = self.client.post(&self.url).json(&serde_json::json!({
+            "q": texts,
+            "source": "auto",
+            "target": target,
+            "format": "text",
+            "api_key": self.api_key,
+        }));
+        let response: Response = post_json(request).await?;
+
+        one_per_text(texts, response.translated_text)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_language_should_accept_language_tags() {
+        assert_eq!(parse_language("es"), Some("es".to_owned()));
+        assert_eq!(parse_language("pt-BR"), Some("pt-br".to_owned()));
+        assert_eq!(parse_language("zh-Hant"), Some("zh-hant".to_owned()));
+        assert_eq!(parse_language("e"), None);
+        assert_eq!(parse_language("spanish"), None);
+        assert_eq!(parse_language("es-"), None);
+        assert_eq!(parse_language("es-ES-x"), None);
+        assert_eq!(parse_language("e1"), None);
+    }
+}
@@ -0,0 +1,157 @@
+use std::{
+    collections::HashSet,
+    sync::{Arc, RwLock},
+    time::{Duration, Instant},
+};
+
+use crate::{config::WordFilterPolicy, models::DBError, persistence::word_filter::WordFilterDao};
+
+// Other instances pick up changes made through them within this long.
+const REFRESH_INTERVAL: Duration = Duration::from_secs(30);
+
+// Managed as `Option<WordFilter>`; None while WORD_FILTER is off.
+pub struct WordFilter {
+    word_filter_dao: Arc<dyn WordFilterDao + Send + Sync>,
+    pub policy: WordFilterPolicy,
+    cache: RwLock<Option<(Instant, Arc<HashSet<String>>)>>,
+}
+
+impl WordFilter {
+    pub fn new(
+        word_filter_dao: Arc<dyn WordFilterDao + Send + Sync>,
+        policy: WordFilterPolicy,
+    ) -> Self {
+        Self {
+            word_filter_dao,
+            policy,
+            cache: RwLock::new(None),
+        }
+    }
+
+    pub async fn words(&self) -> Result<Vec<String>, DBError> {
+        self.word_filter_dao.filtered_words().await
+    }
+
+    pub async fn set_filtered(&self, word: String, filtered: bool) -> Result<(), DBError> {
+        self.word_filter_dao.set_filtered(word, filtered).await?;
+        *self.cache.write().unwrap() = None;
+        Ok(())
+    }
+
+    async fn cached_words(&self) -> Result<Arc<HashSet<String>>, DBError> {
+        if let Some((loaded, words)) = self.cache.read().unwrap().as_ref() {
+            if loaded.elapsed() < REFRESH_INTERVAL {
+                return Ok(words.clone());
+            }
+        }
+
+        let words = Arc::new(
+            self.word_filter_dao
+                .filtered_words()
+                .await?
+                .into_iter()
+                .collect::<HashSet<_>>(),
+        );
+        *self.cache.write().unwrap() = Some((Instant::now(), words.clone()));
+        Ok(words)
+    }
+
+    // Returns the names of the fields containing a filtered word. Under `Mask` those
+    // fields are masked in place.
+    pub async fn apply(
+        &self,
+        fields: Vec<(&'static str, &mut String)>,
+    ) -> Result<Vec<&'static str>, DBError> {
+        let words = self.cached_words().await?;
+        if words.is_empty() {
+            return Ok(vec![]);
+        }
+
+        let mut filtered = vec![];
+        for (name, text) in fields {
+            if let Some(masked) = mask(text, &words) {
+                if self.policy == WordFilterPolicy::Mask {
+                    *text = masked;
+                }
+                filtered.push(name);
+            }
+        }
+        Ok(filtered)
+    }
+}
+
+// `text` with the letters of each filtered word replaced by `*`, or None when it has
+// none. Words are runs of letters and digits, matched whole and case insensitively.
+fn mask(text: &str, words: &HashSet<String>) -> Option<String> {
+    let mut masked = String::with_capacity(text.len());
+    let mut found = false;
+    let mut rest = text;
+    while let Some(start) = rest.find(char::is_alphanumeric) {
+        masked.push_str(&rest[..start]);
+        rest = &rest[start..];
+        let end = rest
+            .find(|c: char| !c.is_alphanumeric())
+            .unwrap_or(rest.len());
+        let word = &rest[..end];
+        if words.contains(&word.to_lowercase()) {
+            found = true;
+            masked.extend(word.chars().map(|_| '*'));
+        } else {
+            masked.push_str(word);
+        }
+        rest = &rest[end..];
+    }
+    masked.push_str(rest);
+
+    found.then_some(masked)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::persistence::memory::{InMemoryWordFilterDao, MemoryStore};
+
+    fn words() -> HashSet<String> {
+        HashSet::from(["darn".to_owned(), "scam".to_owned()])
+    }
+
+    #[test]
+    fn mask_should_replace_whole_words_only() {
+        assert_eq!(
+            mask("Darn, this SCAM! (darn)", &words()).as_deref(),
+            Some("****, this ****! (****)")
+        );
+        assert_eq!(mask("darned scammers", &words()), None);
+        assert_eq!(mask("", &words()), None);
+    }
+
+    #[tokio::test]
+    async fn apply_should_follow_the_policy_and_list_changes() {
+        let dao = Arc::new(InMemoryWordFilterDao::new(MemoryStore::new()));
+        let reject = WordFilter::new(dao.clone(), WordFilterPolicy::Reject);
+        let mask = WordFilter::new(dao, WordFilterPolicy::Mask);
+        let mut title = "a scam".to_owned();
+        let mut description = "fine".to_owned();
+
+        assert!(reject
+            .apply(vec![("title", &mut title)])
+            .await
+            .unwrap()
+            .is_empty());
+
+        reject.set_filtered("scam".to_owned(), true).await.unwrap();
+        let filtered = reject
+            .apply(vec![
+                ("title", &mut title),
+                ("description", &mut description),
+            ])
+            .await
+            .unwrap();
+        assert_eq!(filtered, ["title"]);
+        assert_eq!(title, "a scam");
+
+        let filtered = mask.apply(vec![("title", &mut title)]).await.unwrap();
+        assert_eq!(filtered, ["title"]);
+        assert_eq!(title, "a ****");
+    }
+}
@@ -0,0 +1,2427 @@
+use std::{
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, Mutex,
+    },
+    time::Duration,
+};
+
+use chrono::{DateTime, FixedOffset};
+use question_answer_api_rust::{
+    analytics::{Analytics, AnalyticsError, AnalyticsEvent, AnalyticsSink},
+    answerer::{self, AiAnswerer, AnswerSuggestions, AnswererError, Suggestion},
+    build_rocket,
+    captcha::{Captcha, CaptchaError, CaptchaVerifier},
+    config::{AppConfig, DuplicateAnswerPolicy, TaggerKind, WordFilterPolicy},
+    dto::{
+        AdminStatsResponse, AnswerResponse, AttachmentResponse, BulkModerationResult,
+        HeldContentResponse, LinkPreviewResponse, ModerationItemResponse, Paginated,
+        PublishedResponse, QuestionResponse, RestHookResponse, SearchResponse,
+        SuggestedAnswerResponse, SuggestedTagsResponse, TranslationResponse,
+    },
+    link_previews::{LinkPreviews, PreviewError, PreviewFetcher},
+    models::{
+        Activity, ActivityKind, AnswerDetail, DBError, HeldContent, MergeSummary, QuestionDetail,
+        SpamContext,
+    },
+    moderation::{HeuristicModerator, Moderation},
+    persistence::{
+        link_preview::LinkPreview,
+        memory::{
+            InMemoryLinkPreviewDao, InMemoryModerationDao, InMemoryRestHookDao,
+            InMemoryTranslationDao, InMemoryWordFilterDao, MemoryStore,
+        },
+    },
+    rate_limit::RateLimiter,
+    replies::ReplyAddresses,
+    spam::{SpamChecker, SpamError},
+    startup,
+    testing::QuestionDaoMock,
+    translation::{TranslationError, Translations, Translator},
+    word_filter::WordFilter,
+    AppState,
+};
+use rocket::{
+    http::{ContentType, Header, Status},
+    local::asynchronous::{Client, LocalResponse},
+};
+use serde_json::{json, Value};
+use uuid::Uuid;
+
+async fn client() -> Client {
+    let rocket = build_rocket(startup::in_memory(AppConfig::from_env()).unwrap());
+    Client::tracked(rocket).await.unwrap()
+}
+
+#[rocket::async_test]
+async fn questions_and_answers_should_round_trip() {
+    let client = client().await;
+
+    let response = client
+        .post("/question")
+        .json(&json!({ "title": "title", "description": "description" }))
+        .dispatch()
+        .await;
+    assert_eq!(response.status(), Status::Ok);
+    let question: QuestionResponse = response.into_json().await.unwrap();
+
+    let response = client
+        .post("/answer")
+        .json(&json!({ "question_uuid": question.question_uuid, "content": "content" }))
+        .dispatch()
+        .await;
+    assert_eq!(response.status(), Status::Ok);
+    let answer: AnswerResponse = response.into_json().await.unwrap();
+
+    let questions: Paginated<QuestionResponse> = client
+        .get("/questions")
+        .dispatch()
+        .await
+        .into_json()
+        .await
+        .unwrap();
+    assert_eq!(questions.items.len(), 1);
+    assert_eq!(questions.items[0].question_uuid, question.question_uuid);
+    assert_eq!(questions.items[0].answer_count, 1);
+
+    let answers: Paginated<AnswerResponse> = client
+        .get(format!("/answers/{}", question.question_uuid))
+        .dispatch()
+        .await
+        .into_json()
+        .await
+        .unwrap();
+    assert_eq!(answers.items, vec![answer]);
+}
+
+#[rocket::async_test]
+async fn question_etags_should_change_when_an_answer_is_edited() {
+    let client = client().await;
+
+    let response = client
+        .post("/question")
+        .json(&json!({ "title": "title", "description": "description" }))
+        .dispatch()
+        .await;
+    let question: QuestionResponse = response.into_json().await.unwrap();
+    let response = client
+        .post("/answer")
+        .json(&json!({ "question_uuid": question.question_uuid, "content": "content" }))
+        .dispatch()
+        .await;
+    let answer: AnswerResponse = response.into_json().await.unwrap();
+
+    let response = client.get("/questions").dispatch().await;
+    let etag = response.headers().get_one("ETag").unwrap().to_owned();
+    let response = client
+        .get("/questions")
+        .header(Header::new("If-None-Match", etag.clone()))
+        .dispatch()
+        .await;
+    assert_eq!(response.status(), Status::NotModified);
+
+    let response = client
+        .patch(format!("/answer/{}", answer.answer_uuid))
+        .json(&json!({ "content": "edited", "version": answer.version }))
+        .dispatch()
+        .await;
+    assert_eq!(response.status(), Status::Ok);
+
+    let response = client
+        .get("/questions")
+        .header(Header::new("If-None-Match", etag.clone()))
+        .dispatch()
+        .await;
+    assert_eq!(response.status(), Status::Ok);
+    assert_ne!(response.headers().get_one("ETag"), Some(etag.as_str()));
+}
+
+#[rocket::async_test]
+async fn timestamps_should_be_rfc3339_in_utc() {
+    let client = client().await;
+
+    let question: Value = client
+        .post("/question")
+        .json(&json!({ "title": "title", "description": "description" }))
+        .dispatch()
+        .await
+        .into_json()
+        .await
+        .unwrap();
+
+    for field in ["created_at", "updated_at"] {
+        let timestamp = question[field].as_str().unwrap();
+        assert!(timestamp.ends_with('Z'), "{} is {}", field, timestamp);
+        DateTime::parse_from_rfc3339(timestamp).unwrap();
+    }
+}
+
+#[rocket::async_test]
+async fn questions_should_be_paged_oldest_first() {
+    let client = client().await;
+    for title in ["first", "second", "third"] {
+        client
+            .post("/question")
+            .json(&json!({ "title": title, "description": "description" }))
+            .dispatch()
+            .await;
+    }
+
+    let first: Paginated<QuestionResponse> = client
+        .get("/questions?per_page=2")
+        .dispatch()
+        .await
+        .into_json()
+        .await
+        .unwrap();
+    assert_eq!(first.total, Some(3));
+    assert_eq!(first.items[0].title, "first");
+
+    let second: Paginated<QuestionResponse> = client
+        .get(format!("/questions?cursor={}", first.next_cursor.unwrap()))
+        .dispatch()
+        .await
+        .into_json()
+        .await
+        .unwrap();
+    assert_eq!(second.page, 2);
+    assert_eq!(second.items.len(), 1);
+    assert_eq!(second.items[0].title, "third");
+    assert_eq!(second.next_cursor, None);
+}
+
+#[rocket::async_test]
+async fn answered_questions_should_sort_first_by_activity() {
+    let client = client().await;
+    let mut questions = vec![];
+    for title in ["first", "second"] {
+        let question: QuestionResponse = client
+            .post("/question")
+            .json(&json!({ "title": title }))
+            .dispatch()
+            .await
+            .into_json()
+            .await
+            .unwrap();
+        questions.push(question);
+    }
+    client
+        .post("/answer")
+        .json(&json!({ "question_uuid": questions[0].question_uuid, "content": "content" }))
+        .dispatch()
+        .await;
+
+    let listed: Paginated<QuestionResponse> = client
+        .get("/questions?sort=activity")
+        .dispatch()
+        .await
+        .into_json()
+        .await
+        .unwrap();
+    let titles: Vec<_> = listed.items.iter().map(|q| q.title.as_str()).collect();
+    assert_eq!(titles, ["first", "second"]);
+    assert!(listed.items[0].last_activity_at > questions[0].last_activity_at);
+}
+
+#[cfg(feature = "camel-case")]
+#[rocket::async_test]
+async fn fields_should_be_camel_cased_in_camel_case_builds() {
+    let client = client().await;
+
+    let question: Value = client
+        .post("/question")
+        .json(&json!({ "title": "title", "description": "description" }))
+        .dispatch()
+        .await
+        .into_json()
+        .await
+        .unwrap();
+    assert!(question["questionUuid"].is_string());
+    assert_eq!(question["answerCount"], 0);
+    assert!(question.get("question_uuid").is_none());
+
+    let response = client
+        .post("/answer")
+        .json(&json!({ "questionUuid": question["questionUuid"], "content": "content" }))
+        .dispatch()
+        .await;
+    let answer: Value = response.into_json().await.unwrap();
+    assert_eq!(answer["questionUuid"], question["questionUuid"]);
+}
+
+// The problem+json error envelope, checked against the request id header.
+async fn error_body(response: LocalResponse<'_>, status: Status) -> Value {
+    assert_eq!(response.status(), status);
+    assert_eq!(
+        response.content_type(),
+        Some(ContentType::new("application", "problem+json"))
+    );
+    let request_id = response
+        .headers()
+        .get_one("X-Request-Id")
+        .unwrap()
+        .to_owned();
+
+    let body: Value = response.into_json().await.unwrap();
+    assert_eq!(body["type"], "about:blank");
+    assert_eq!(body["title"], status.reason_lossy());
+    assert_eq!(body["status"], status.code);
+    assert_eq!(body["request_id"], request_id);
+    body
+}
+
+#[rocket::async_test]
+async fn answer_for_unknown_question_should_be_rejected() {
+    let client = client().await;
+
+    let response = client
+        .post("/answer")
+        .json(&json!({ "question_uuid": Uuid::new_v4(), "content": "content" }))
+        .dispatch()
+        .await;
+    let body = error_body(response, Status::BadRequest).await;
+    assert_eq!(body["code"], "QUESTION_NOT_FOUND");
+
+    let response = client
+        .post("/answer")
+        .json(&json!({ "question_uuid": "not a uuid", "content": "content" }))
+        .dispatch()
+        .await;
+    error_body(response, Status::UnprocessableEntity).await;
+}
+
+#[rocket::async_test]
+async fn malformed_path_uuids_should_be_bad_requests() {
+    let client = client().await;
+
+    for request in [
+        client.delete("/question/not-a-uuid"),
+        client.delete("/answer/not-a-uuid"),
+        client.get("/answers/not-a-uuid"),
+    ] {
+        let body = error_body(request.dispatch().await, Status::BadRequest).await;
+        assert_eq!(body["code"], "INVALID_UUID");
+        assert!(body["detail"]
+            .as_str()
+            .unwrap()
+            .starts_with("Malformed UUID"));
+    }
+}
+
+#[rocket::async_test]
+async fn unmatched_routes_and_bad_bodies_should_get_json_errors() {
+    let client = client().await;
+
+    let response = client.get("/nothing-here").dispatch().await;
+    let body = error_body(response, Status::NotFound).await;
+    assert_eq!(body["code"], "ROUTE_NOT_FOUND");
+    assert_eq!(body["detail"], "No route matches GET /nothing-here.");
+    assert_eq!(body["instance"], "/nothing-here");
+
+    let response = client
+        .post("/question")
+        .header(ContentType::JSON)
+        .body("{ not json")
+        .dispatch()
+        .await;
+    let body = error_body(response, Status::BadRequest).await;
+    assert_eq!(body["code"], "MALFORMED_BODY");
+
+    let response = client
+        .post("/question")
+        .json(&json!({ "description": "description" }))
+        .dispatch()
+        .await;
+    let body = error_body(response, Status::UnprocessableEntity).await;
+    assert!(body["detail"]
+        .as_str()
+        .unwrap()
+        .contains("missing field `title`"));
+}
+
+#[rocket::async_test]
+async fn bodies_should_have_a_json_content_type() {
+    let client = client().await;
+    let body = json!({ "title": "title" }).to_string();
+
+    let response = client.post("/question").body(&body).dispatch().await;
+    let error = error_body(response, Status::UnsupportedMediaType).await;
+    assert_eq!(error["code"], "UNSUPPORTED_MEDIA_TYPE");
+    assert_eq!(
+        error["detail"],
+        "Content-Type is missing; send one of: application/json."
+    );
+
+    let response = client
+        .post("/question")
+        .header(ContentType::Plain)
+        .body(&body)
+        .dispatch()
+        .await;
+    let error = error_body(response, Status::UnsupportedMediaType).await;
+    assert_eq!(
+        error["detail"],
+        "Content-Type text/plain is not supported; send one of: application/json."
+    );
+
+    let response = client
+        .post("/question")
+        .header(ContentType::new("application", "json").with_params(("charset", "utf-8")))
+        .body(&body)
+        .dispatch()
+        .await;
+    assert_eq!(response.status(), Status::Ok);
+}
+
+#[rocket::async_test]
+async fn unknown_sorts_and_out_of_range_pages_should_be_listed() {
+    let client = client().await;
+
+    let response = client
+        .get("/questions?sort=popular&per_page=1000")
+        .dispatch()
+        .await;
+    let body = error_body(response, Status::BadRequest).await;
+    assert_eq!(body["code"], "INVALID_QUERY");
+    let fields: Vec<_> = body["errors"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|error| error["field"].as_str().unwrap())
+        .collect();
+    assert_eq!(fields, ["sort", "per_page"]);
+
+    let response = client.get("/questions?sort=newest").dispatch().await;
+    assert_eq!(response.status(), Status::Ok);
+}
+
+#[rocket::async_test]
+async fn blank_or_long_fields_should_be_listed() {
+    let client = client().await;
+
+    let response = client
+        .post("/question")
+        .json(&json!({ "title": " ", "description": "d".repeat(256) }))
+        .dispatch()
+        .await;
+    let body = error_body(response, Status::UnprocessableEntity).await;
+    assert_eq!(body["code"], "INVALID_FIELDS");
+    assert_eq!(
+        body["errors"],
+        json!([
+            { "field": "title", "message": "must not be empty" },
+            { "field": "description", "message": "must be at most 255 characters" },
+        ])
+    );
+
+    let questions: Paginated<QuestionResponse> = client
+        .get("/questions")
+        .dispatch()
+        .await
+        .into_json()
+        .await
+        .unwrap();
+    assert!(questions.items.is_empty());
+}
+
+#[rocket::async_test]
+async fn questions_should_not_need_a_description() {
+    let client = client().await;
+
+    for body in [
+        json!({ "title": "no description" }),
+        json!({ "title": "null description", "description": null }),
+        json!({ "title": "blank description", "description": "  " }),
+    ] {
+        let question: Value = client
+            .post("/question")
+            .json(&body)
+            .dispatch()
+            .await
+            .into_json()
+            .await
+            .unwrap();
+        assert_eq!(question["description"], Value::Null);
+    }
+
+    let questions: Paginated<QuestionResponse> = client
+        .get("/questions")
+        .dispatch()
+        .await
+        .into_json()
+        .await
+        .unwrap();
+    assert!(questions
+        .items
+        .iter()
+        .all(|question| question.description.is_none()));
+}
+
+#[rocket::async_test]
+async fn questions_should_be_filtered_by_metadata() {
+    let client = client().await;
+
+    for (title, metadata) in [
+        ("zendesk", json!({ "source": "zendesk", "ticket": "42" })),
+        (
+            "zendesk too",
+            json!({ "source": "zendesk", "ticket": "43" }),
+        ),
+        ("no metadata", Value::Null),
+    ] {
+        let question: Value = client
+            .post("/question")
+            .json(&json!({ "title": title, "metadata": metadata }))
+            .dispatch()
+            .await
+            .into_json()
+            .await
+            .unwrap();
+        assert_eq!(
+            question["metadata"],
+            if metadata.is_null() {
+                json!({})
+            } else {
+                metadata
+            }
+        );
+    }
+
+    let titles = |questions: Paginated<QuestionResponse>| -> Vec<String> {
+        questions
+            .items
+            .into_iter()
+            .map(|question| question.title)
+            .collect()
+    };
+    let zendesk: Paginated<QuestionResponse> = client
+        .get("/questions?metadata.source=zendesk")
+        .dispatch()
+        .await
+        .into_json()
+        .await
+        .unwrap();
+    assert_eq!(titles(zendesk), ["zendesk", "zendesk too"]);
+
+    let ticket: Paginated<QuestionResponse> = client
+        .get("/questions?metadata.source=zendesk&metadata.ticket=43")
+        .dispatch()
+        .await
+        .into_json()
+        .await
+        .unwrap();
+    assert_eq!(titles(ticket), ["zendesk too"]);
+}
+
+#[rocket::async_test]
+async fn questions_should_be_filtered_by_date_in_the_callers_timezone() {
+    let client = client().await;
+    client
+        .post("/question")
+        .json(&json!({ "title": "title" }))
+        .dispatch()
+        .await;
+
+    let client = &client;
+    let list = |uri: &'static str| async move {
+        let response = client.get(uri).dispatch().await;
+        assert_eq!(response.status(), Status::Ok);
+        response
+            .into_json::<Paginated<QuestionResponse>>()
+            .await
+            .unwrap()
+            .items
+    };
+    // However far ahead Kiritimati is, its midnight today has already passed.
+    let today = list("/questions?created_after=today&tz=Pacific/Kiritimati").await;
+    assert_eq!(today.len(), 1);
+    assert_eq!(
+        today[0].created_at.offset(),
+        &FixedOffset::east_opt(14 * 3600).unwrap()
+    );
+    assert!(list("/questions?created_before=2023-01-01&tz=Asia/Tokyo")
+        .await
+        .is_empty());
+
+    let response = client
+        .get("/questions?created_after=this_week")
+        .header(Header::new("X-Timezone", "Asia/Kolkata"))
+        .dispatch()
+        .await;
+    let listed: Paginated<QuestionResponse> = response.into_json().await.unwrap();
+    assert_eq!(
+        listed.items[0].created_at.offset(),
+        &FixedOffset::east_opt(5 * 3600 + 30 * 60).unwrap()
+    );
+
+    let response = client
+        .get("/questions?created_after=tomorrow&tz=Mars/Olympus")
+        .dispatch()
+        .await;
+    assert_eq!(response.status(), Status::BadRequest);
+    let body: Value = response.into_json().await.unwrap();
+    assert_eq!(body["code"], "INVALID_QUERY");
+}
+
+#[rocket::async_test]
+async fn answers_should_be_included_on_request() {
+    let client = client().await;
+
+    let mut questions = vec![];
+    for title in ["answered", "unanswered"] {
+        let question: QuestionResponse = client
+            .post("/question")
+            .json(&json!({ "title": title }))
+            .dispatch()
+            .await
+            .into_json()
+            .await
+            .unwrap();
+        questions.push(question);
+    }
+    for content in ["first", "second"] {
+        let response = client
+            .post("/answer")
+            .json(&json!({ "question_uuid": questions[0].question_uuid, "content": content }))
+            .dispatch()
+            .await;
+        assert_eq!(response.status(), Status::Ok);
+    }
+
+    let page: Paginated<QuestionResponse> = client
+        .get("/questions")
+        .dispatch()
+        .await
+        .into_json()
+        .await
+        .unwrap();
+    assert!(page.items.iter().all(|question| question.answers.is_none()));
+
+    let page: Paginated<QuestionResponse> = client
+        .get("/questions?include=answers")
+        .dispatch()
+        .await
+        .into_json()
+        .await
+        .unwrap();
+    let contents = page
+        .items
+        .iter()
+        .map(|question| {
+            question
+                .answers
+                .as_ref()
+                .unwrap()
+                .iter()
+                .map(|answer| answer.content.as_str())
+                .collect::<Vec<_>>()
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(contents, [vec!["first", "second"], vec![]]);
+
+    let response = client
+        .get("/questions?include=answers,tags")
+        .dispatch()
+        .await;
+    let body = error_body(response, Status::BadRequest).await;
+    assert_eq!(body["code"], "INVALID_QUERY");
+    assert_eq!(
+        body["errors"],
+        json!([{
+            "field": "include",
+            "message": "`tags` is not one of: answers, attachments, answers.attachments"
+        }])
+    );
+}
+
+#[rocket::async_test]
+async fn metadata_should_be_a_small_object() {
+    let client = client().await;
+
+    for (metadata, message) in [
+        (json!(["zendesk"]), "must be an object"),
+        (
+            json!({ "notes": "n".repeat(4096) }),
+            "must be at most 4096 bytes",
+        ),
+    ] {
+        let response = client
+            .post("/question")
+            .json(&json!({ "title": "title", "metadata": metadata }))
+            .dispatch()
+            .await;
+        let body = error_body(response, Status::UnprocessableEntity).await;
+        assert_eq!(
+            body["errors"],
+            json!([{ "field": "metadata", "message": message }])
+        );
+    }
+}
+
+#[rocket::async_test]
+async fn unknown_fields_should_be_rejected_when_strict() {
+    let client = client().await;
+    let typo = json!({ "titel": "title", "title": "title" });
+
+    let response = client.post("/question").json(&typo).dispatch().await;
+    assert_eq!(response.status(), Status::Ok);
+
+    let config = AppConfig {
+        strict_json: true,
+        ..AppConfig::from_env()
+    };
+    let client = Client::tracked(build_rocket(startup::in_memory(config).unwrap()))
+        .await
+        .unwrap();
+
+    let response = client.post("/question").json(&typo).dispatch().await;
+    let body = error_body(response, Status::UnprocessableEntity).await;
+    assert_eq!(
+        body["detail"],
+        "Request body does not match what this route expects: unknown field `titel`"
+    );
+
+    let response = client
+        .post("/question")
+        .json(&json!({ "title": "title", "metadata": { "anything": "goes" } }))
+        .dispatch()
+        .await;
+    let question: QuestionResponse = response.into_json().await.unwrap();
+
+    let response = client
+        .post("/answer")
+        .json(&json!({ "question_uuid": question.question_uuid, "content": "content" }))
+        .dispatch()
+        .await;
+    assert_eq!(response.status(), Status::Ok);
+
+    let response = client
+        .post("/question/with-answer")
+        .json(&json!({
+            "question": { "title": "title", "descriptoin": "description" },
+            "answer": "content",
+        }))
+        .dispatch()
+        .await;
+    let body = error_body(response, Status::UnprocessableEntity).await;
+    assert!(body["detail"]
+        .as_str()
+        .unwrap()
+        .ends_with("unknown field `question.descriptoin`"));
+}
+
+#[rocket::async_test]
+async fn unavailable_database_should_get_503() {
+    let mut question_dao = QuestionDaoMock::new();
+    question_dao.mock_get_questions_response(Err(DBError::Unavailable));
+    let state = AppState {
+        question_dao: Arc::new(question_dao),
+        ..startup::in_memory(AppConfig::from_env()).unwrap()
+    };
+    let client = Client::tracked(build_rocket(state)).await.unwrap();
+
+    let response = client.get("/questions").dispatch().await;
+
+    let body = error_body(response, Status::ServiceUnavailable).await;
+    assert_eq!(body["code"], "DATABASE_UNAVAILABLE");
+}
+
+#[rocket::async_test]
+async fn subscriptions_should_need_notifications() {
+    let client = client().await;
+
+    let response = client
+        .put(format!(
+            "/question/{}/subscriptions/author@example.com",
+            Uuid::new_v4()
+        ))
+        .dispatch()
+        .await;
+
+    let body = error_body(response, Status::NotImplemented).await;
+    assert_eq!(body["code"], "NOT_ENABLED");
+}
+
+#[rocket::async_test]
+async fn confirmation_links_should_show_the_change_before_making_it() {
+    let client = client().await;
+    let url = format!(
+        "/notifications/confirm?email=%3Cb%3E%40example.com&change=unsubscribe&question={}&token=1.ab",
+        Uuid::nil()
+    );
+
+    let response = client.get(&url).dispatch().await;
+    assert_eq!(response.status(), Status::Ok);
+    assert_eq!(response.content_type(), Some(ContentType::HTML));
+    let page = response.into_string().await.unwrap();
+    assert!(page.contains(&format!(
+        "stop the emails about new answers to question {} at &lt;b&gt;@example.com",
+        Uuid::nil()
+    )));
+    assert!(page.contains("<form method=\"post\">"));
+
+    let response = client.post(&url).dispatch().await;
+    let body = error_body(response, Status::NotImplemented).await;
+    assert_eq!(body["code"], "NOT_ENABLED");
+
+    let response = client
+        .get("/notifications/confirm?email=a%40example.com&change=unsubscribe&token=1.ab")
+        .dispatch()
+        .await;
+    let body = error_body(response, Status::BadRequest).await;
+    assert_eq!(body["errors"][0]["field"], "change");
+}
+
+#[rocket::async_test]
+async fn telegram_should_list_unanswered_questions() {
+    let config = AppConfig {
+        telegram_webhook_secret: Some("secret".to_owned()),
+        ..AppConfig::from_env()
+    };
+    let client = Client::tracked(build_rocket(startup::in_memory(config).unwrap()))
+        .await
+        .unwrap();
+    for title in ["first", "second"] {
+        client
+            .post("/question")
+            .header(ContentType::JSON)
+            .body(json!({ "title": title }).to_string())
+            .dispatch()
+            .await;
+    }
+    let update = |text: &str| {
+        json!({
+            "update_id": 1,
+            "message": { "message_id": 1, "chat": { "id": 42 }, "text": text },
+        })
+        .to_string()
+    };
+
+    let response = client
+        .post("/telegram/updates")
+        .header(ContentType::JSON)
+        .body(update("/unanswered"))
+        .dispatch()
+        .await;
+    assert_eq!(response.status(), Status::Unauthorized);
+
+    let response = client
+        .post("/telegram/updates")
+        .header(ContentType::JSON)
+        .header(Header::new("X-Telegram-Bot-Api-Secret-Token", "secret"))
+        .body(update("/unanswered@QaBot"))
+        .dispatch()
+        .await;
+    assert_eq!(response.status(), Status::Ok);
+    let reply: Value = response.into_json().await.unwrap();
+    assert_eq!(reply["method"], "sendMessage");
+    assert_eq!(reply["chat_id"], 42);
+    let text = reply["text"].as_str().unwrap();
+    assert!(text.starts_with("Waiting for an answer, oldest first:\n- first\n  GET /answers/"));
+    assert!(text.contains("\n- second\n"));
+
+    let response = client
+        .post("/telegram/updates")
+        .header(ContentType::JSON)
+        .header(Header::new("X-Telegram-Bot-Api-Secret-Token", "secret"))
+        .body(update("hello"))
+        .dispatch()
+        .await;
+    assert_eq!(response.status(), Status::Ok);
+    assert_eq!(response.into_string().await, None);
+}
+
+#[rocket::async_test]
+async fn attachments_should_be_uploaded_listed_and_downloaded() {
+    let object_store_dir = std::env::temp_dir().join(format!("qa-api-objects-{}", Uuid::new_v4()));
+    let config = AppConfig {
+        object_store_dir: object_store_dir.clone(),
+        ..AppConfig::from_env()
+    };
+    let client = Client::tracked(build_rocket(startup::in_memory(config).unwrap()))
+        .await
+        .unwrap();
+    let question: QuestionResponse = client
+        .post("/question")
+        .json(&json!({ "title": "title" }))
+        .dispatch()
+        .await
+        .into_json()
+        .await
+        .unwrap();
+
+    let body = concat!(
+        "--boundary\r\n",
+        "Content-Disposition: form-data; name=\"file\"; filename=\"notes.txt\"\r\n",
+        "Content-Type: text/plain\r\n",
+        "\r\n",
+        "hello\r\n",
+        "--boundary--\r\n",
+    );
+    let response = client
+        .post(format!("/question/{}/attachments", question.question_uuid))
+        .header(ContentType::new("multipart", "form-data").with_params(("boundary", "boundary")))
+        .body(body)
+        .dispatch()
+        .await;
+    assert_eq!(response.status(), Status::Ok);
+    let attachment: AttachmentResponse = response.into_json().await.unwrap();
+    assert_eq!(attachment.filename, "notes.txt");
+    assert_eq!(attachment.content_type, "text/plain");
+    assert_eq!(attachment.size_bytes, 5);
+    assert_eq!(attachment.status, "ready");
+
+    let page: Paginated<QuestionResponse> = client
+        .get("/questions?include=attachments")
+        .dispatch()
+        .await
+        .into_json()
+        .await
+        .unwrap();
+    assert_eq!(page.items[0].attachments, Some(vec![attachment.clone()]));
+
+    let response = client
+        .get(format!("/attachments/{}", attachment.attachment_uuid))
+        .dispatch()
+        .await;
+    assert_eq!(response.status(), Status::Ok);
+    assert_eq!(response.content_type(), Some(ContentType::Plain));
+    assert_eq!(
+        response.headers().get_one("Content-Disposition"),
+        Some("attachment; filename=\"notes.txt\"; filename*=UTF-8''notes.txt")
+    );
+    assert_eq!(response.into_string().await.unwrap(), "hello");
+
+    // The local store cannot pre-sign uploads.
+    let response = client
+        .post(format!("/question/{}/attachments", question.question_uuid))
+        .json(&json!({ "filename": "big.bin", "content_type": "application/octet-stream", "size_bytes": 1 }))
+        .dispatch()
+        .await;
+    let body = error_body(response, Status::NotImplemented).await;
+    assert_eq!(body["code"], "NOT_ENABLED");
+
+    let response = client
+        .get(format!("/attachments/{}", Uuid::new_v4()))
+        .dispatch()
+        .await;
+    let body = error_body(response, Status::NotFound).await;
+    assert_eq!(body["code"], "ATTACHMENT_NOT_FOUND");
+
+    std::fs::remove_dir_all(object_store_dir).unwrap();
+}
+
+struct EchoAnswerer;
+
+#[async_trait::async_trait]
+impl AiAnswerer for EchoAnswerer {
+    async fn suggest(
+        &self,
+        question: &QuestionDetail,
+        answers: &[AnswerDetail],
+    ) -> Result<Suggestion, AnswererError> {
+        Ok(Suggestion {
+            content: format!("About {}, after {} answers.", question.title, answers.len()),
+            model: "echo".to_owned(),
+        })
+    }
+}
+
+#[rocket::async_test]
+async fn avatars_should_resolve_uploads_then_gravatar_then_identicons() {
+    let object_store_dir = std::env::temp_dir().join(format!("qa-api-objects-{}", Uuid::new_v4()));
+    let config = AppConfig {
+        object_store_dir: object_store_dir.clone(),
+        admin_token: Some("secret".to_owned()),
+        public_url: Some("https://qa.example.com".to_owned()),
+        ..AppConfig::from_env()
+    };
+    let client = Client::tracked(build_rocket(startup::in_memory(config).unwrap()))
+        .await
+        .unwrap();
+    let url = "/users/Ada@Example.com/avatar";
+
+    let response = client.get(url).dispatch().await;
+    assert_eq!(response.status(), Status::Found);
+    let location = response.headers().get_one("Location").unwrap();
+    assert!(location.starts_with("https://www.gravatar.com/avatar/"));
+    assert!(location.contains(
+        "d=https%3A%2F%2Fqa.example.com%2Fusers%2Fada%2540example.com%2Favatar%3Fgravatar%3Dfalse"
+    ));
+
+    let response = client
+        .get(format!("{}?gravatar=false", url))
+        .dispatch()
+        .await;
+    assert_eq!(response.status(), Status::Ok);
+    assert_eq!(response.content_type(), Some(ContentType::SVG));
+    assert_eq!(
+        response.headers().get_one("Cache-Control"),
+        Some("public, max-age=3600")
+    );
+
+    let png = b"\x89PNG\r\n\x1a\nnot really".to_vec();
+    let response = client.put(url).body(png.clone()).dispatch().await;
+    assert_eq!(response.status(), Status::Unauthorized);
+    let response = client
+        .put(url)
+        .header(Header::new("Authorization", "Bearer secret"))
+        .body("<svg onload=alert(1)>")
+        .dispatch()
+        .await;
+    error_body(response, Status::BadRequest).await;
+    let response = client
+        .put(url)
+        .header(Header::new("Authorization", "Bearer secret"))
+        .body(png.clone())
+        .dispatch()
+        .await;
+    assert_eq!(response.status(), Status::Ok);
+
+    let response = client.get("/users/ada@example.com/avatar").dispatch().await;
+    assert_eq!(response.status(), Status::Ok);
+    assert_eq!(response.content_type(), Some(ContentType::PNG));
+    let etag = response.headers().get_one("ETag").unwrap().to_owned();
+    assert_eq!(response.into_bytes().await.unwrap(), png);
+    let response = client
+        .get(url)
+        .header(Header::new("If-None-Match", etag))
+        .dispatch()
+        .await;
+    assert_eq!(response.status(), Status::NotModified);
+
+    let response = client
+        .delete(url)
+        .header(Header::new("Authorization", "Bearer secret"))
+        .dispatch()
+        .await;
+    assert_eq!(response.status(), Status::Ok);
+    assert_eq!(client.get(url).dispatch().await.status(), Status::Found);
+
+    std::fs::remove_dir_all(object_store_dir).unwrap();
+}
+
+#[rocket::async_test]
+async fn suggested_answers_should_be_drafts_behind_a_flag_and_a_limit() {
+    let mut config = AppConfig::from_env();
+    config
+        .feature_flags
+        .insert(answerer::FEATURE_FLAG.to_owned());
+    let state = AppState {
+        suggestions: Some(AnswerSuggestions {
+            answerer: Box::new(EchoAnswerer),
+            rate_limiter: RateLimiter::new(1, Duration::from_secs(3600)),
+        }),
+        ..startup::in_memory(config).unwrap()
+    };
+    let client = Client::tracked(build_rocket(state)).await.unwrap();
+    let question: QuestionResponse = client
+        .post("/question")
+        .json(&json!({ "title": "borrowing" }))
+        .dispatch()
+        .await
+        .into_json()
+        .await
+        .unwrap();
+
+    let response = client
+        .post(format!("/question/{}/suggest-answer", Uuid::new_v4()))
+        .dispatch()
+        .await;
+    assert_eq!(response.headers().get_one("X-RateLimit-Limit"), None);
+    let body = error_body(response, Status::NotFound).await;
+    assert_eq!(body["code"], "QUESTION_NOT_FOUND");
+
+    let url = format!("/question/{}/suggest-answer", question.question_uuid);
+    let response = client.post(&url).dispatch().await;
+    assert_eq!(response.status(), Status::Ok);
+    let headers = response.headers();
+    assert_eq!(headers.get_one("X-RateLimit-Limit"), Some("1"));
+    assert_eq!(headers.get_one("X-RateLimit-Remaining"), Some("0"));
+    assert_eq!(headers.get_one("X-RateLimit-Reset"), Some("3600"));
+    let suggestion: SuggestedAnswerResponse = response.into_json().await.unwrap();
+    assert_eq!(suggestion.content, "About borrowing, after 0 answers.");
+    assert!(suggestion.draft && suggestion.ai_generated);
+    assert_eq!(suggestion.model, "echo");
+
+    let answers: Paginated<AnswerResponse> = client
+        .get(format!("/answers/{}", question.question_uuid))
+        .dispatch()
+        .await
+        .into_json()
+        .await
+        .unwrap();
+    assert!(answers.items.is_empty(), "drafts are not posted");
+
+    let response = client.post(&url).dispatch().await;
+    assert_eq!(
+        response.headers().get_one("X-RateLimit-Remaining"),
+        Some("0")
+    );
+    let reset: u64 = response
+        .headers()
+        .get_one("X-RateLimit-Reset")
+        .unwrap()
+        .parse()
+        .unwrap();
+    assert!((1..=3600).contains(&reset));
+    assert_eq!(
+        response.headers().get_one("Retry-After"),
+        Some(reset.to_string().as_str())
+    );
+    let body = error_body(response, Status::TooManyRequests).await;
+    assert_eq!(body["code"], "RATE_LIMITED");
+    assert_eq!(
+        (
+            &body["limit"],
+            &body["window_secs"],
+            &body["retry_after_secs"]
+        ),
+        (&json!(1), &json!(3600), &json!(reset))
+    );
+}
+
+#[rocket::async_test]
+async fn suggested_answers_should_need_the_feature_flag() {
+    let state = AppState {
+        suggestions: Some(AnswerSuggestions {
+            answerer: Box::new(EchoAnswerer),
+            rate_limiter: RateLimiter::new(0, Duration::from_secs(3600)),
+        }),
+        ..startup::in_memory(AppConfig::from_env()).unwrap()
+    };
+    let client = Client::tracked(build_rocket(state)).await.unwrap();
+
+    let response = client
+        .post(format!("/question/{}/suggest-answer", Uuid::new_v4()))
+        .dispatch()
+        .await;
+
+    let body = error_body(response, Status::NotImplemented).await;
+    assert_eq!(body["code"], "NOT_ENABLED");
+}
+
+// Flags "pills" and records the decisions reported to it.
+#[derive(Clone, Default)]
+struct PillsSpamChecker(Arc<Mutex<Vec<(SpamContext, bool)>>>);
+
+#[rocket::async_trait]
+impl SpamChecker for PillsSpamChecker {
+    async fn is_spam(&self, content: &HeldContent, _: &SpamContext) -> Result<bool, SpamError> {
+        Ok(content.text().contains("pills"))
+    }
+
+    async fn report(
+        &self,
+        _: &HeldContent,
+        context: &SpamContext,
+        spam: bool,
+    ) -> Result<(), SpamError> {
+        self.0.lock().unwrap().push((context.clone(), spam));
+        Ok(())
+    }
+}
+
+#[rocket::async_test]
+async fn spam_should_be_held_and_decisions_reported() {
+    let mut config = AppConfig::from_env();
+    config.admin_token = Some("secret".to_owned());
+    let spam_checker = PillsSpamChecker::default();
+    let state = AppState {
+        moderation: Some(Moderation {
+            provider: None,
+            spam_checker: Some(Box::new(spam_checker.clone())),
+            moderation_dao: Arc::new(InMemoryModerationDao::new(MemoryStore::new())),
+            threshold: 0.8,
+        }),
+        ..startup::in_memory(config).unwrap()
+    };
+    let client = Client::tracked(build_rocket(state)).await.unwrap();
+
+    let response = client
+        .post("/question")
+        .header(Header::new("User-Agent", "spambot/1.0"))
+        .json(&json!({ "title": "Cheap pills" }))
+        .dispatch()
+        .await;
+    assert_eq!(response.status(), Status::Accepted);
+    let held: HeldContentResponse = response.into_json().await.unwrap();
+
+    let response = client
+        .delete(format!("/moderation/queue/{}", held.moderation_uuid))
+        .header(Header::new("Authorization", "Bearer secret"))
+        .dispatch()
+        .await;
+    assert_eq!(response.status(), Status::Ok);
+    let reports = spam_checker.0.lock().unwrap().clone();
+    assert_eq!(reports.len(), 1);
+    assert_eq!(reports[0].0.user_agent.as_deref(), Some("spambot/1.0"));
+    assert!(reports[0].1, "rejected content is reported as spam");
+}
+
+#[rocket::async_test]
+async fn flagged_content_should_wait_for_a_moderator() {
+    let mut config = AppConfig::from_env();
+    config.admin_token = Some("secret".to_owned());
+    let state = AppState {
+        moderation: Some(Moderation {
+            provider: Some(Box::new(HeuristicModerator::new(vec!["scam".to_owned()]))),
+            spam_checker: None,
+            moderation_dao: Arc::new(InMemoryModerationDao::new(MemoryStore::new())),
+            threshold: 0.8,
+        }),
+        ..startup::in_memory(config).unwrap()
+    };
+    let client = Client::tracked(build_rocket(state)).await.unwrap();
+    let admin = || Header::new("Authorization", "Bearer secret");
+
+    let response = client
+        .post("/question")
+        .json(&json!({ "title": "An honest question" }))
+        .dispatch()
+        .await;
+    assert_eq!(response.status(), Status::Ok);
+
+    let response = client
+        .post("/question")
+        .json(&json!({ "title": "Join this scam" }))
+        .dispatch()
+        .await;
+    assert_eq!(response.status(), Status::Accepted);
+    let held: HeldContentResponse = response.into_json().await.unwrap();
+
+    let questions: Paginated<QuestionResponse> = client
+        .get("/questions")
+        .dispatch()
+        .await
+        .into_json()
+        .await
+        .unwrap();
+    assert_eq!(questions.items.len(), 1, "held content is not published");
+
+    let response = client.get("/moderation/queue").dispatch().await;
+    assert_eq!(response.status(), Status::Unauthorized);
+    let queue: Paginated<ModerationItemResponse> = client
+        .get("/moderation/queue")
+        .header(admin())
+        .dispatch()
+        .await
+        .into_json()
+        .await
+        .unwrap();
+    assert_eq!(queue.total, Some(1));
+    assert_eq!(queue.items[0].moderation_uuid, held.moderation_uuid);
+    assert_eq!(queue.items[0].categories, ["blocklist"]);
+
+    let url = format!("/moderation/queue/{}/approve", held.moderation_uuid);
+    let response = client.post(&url).header(admin()).dispatch().await;
+    assert_eq!(response.status(), Status::Ok);
+    let question: QuestionResponse = response.into_json().await.unwrap();
+    assert_eq!(question.title, "Join this scam");
+
+    let response = client.post(&url).header(admin()).dispatch().await;
+    let body = error_body(response, Status::NotFound).await;
+    assert_eq!(body["code"], "MODERATION_ITEM_NOT_FOUND");
+
+    let questions: Paginated<QuestionResponse> = client
+        .get("/questions")
+        .dispatch()
+        .await
+        .into_json()
+        .await
+        .unwrap();
+    assert_eq!(questions.items.len(), 2);
+}
+
+#[rocket::async_test]
+async fn held_content_should_be_moderated_in_bulk() {
+    let mut config = AppConfig::from_env();
+    config.admin_token = Some("secret".to_owned());
+    let state = AppState {
+        moderation: Some(Moderation {
+            provider: Some(Box::new(HeuristicModerator::new(vec!["scam".to_owned()]))),
+            spam_checker: None,
+            moderation_dao: Arc::new(InMemoryModerationDao::new(MemoryStore::new())),
+            threshold: 0.8,
+        }),
+        ..startup::in_memory(config).unwrap()
+    };
+    let client = Client::tracked(build_rocket(state)).await.unwrap();
+    let admin = || Header::new("Authorization", "Bearer secret");
+    let mut held = vec![];
+    for title in ["Join this scam", "Another scam"] {
+        let response = client
+            .post("/question")
+            .json(&json!({ "title": title }))
+            .dispatch()
+            .await;
+        assert_eq!(response.status(), Status::Accepted);
+        let item: HeldContentResponse = response.into_json().await.unwrap();
+        held.push(item.moderation_uuid);
+    }
+    let unknown = Uuid::new_v4();
+
+    let queue: Paginated<ModerationItemResponse> = client
+        .get("/moderation/queue?per_page=1")
+        .header(admin())
+        .dispatch()
+        .await
+        .into_json()
+        .await
+        .unwrap();
+    assert_eq!(queue.total, Some(2));
+    assert_eq!(queue.items.len(), 1);
+    assert_eq!(queue.items[0].moderation_uuid, held[0]);
+    let queue: Paginated<ModerationItemResponse> = client
+        .get(format!(
+            "/moderation/queue?cursor={}",
+            queue.next_cursor.unwrap()
+        ))
+        .header(admin())
+        .dispatch()
+        .await
+        .into_json()
+        .await
+        .unwrap();
+    assert_eq!(queue.items[0].moderation_uuid, held[1]);
+    assert_eq!(queue.next_cursor, None);
+
+    let results: Vec<BulkModerationResult> = client
+        .post("/moderation/bulk")
+        .header(admin())
+        .json(&json!({ "moderation_uuids": [held[0], unknown], "action": "approve" }))
+        .dispatch()
+        .await
+        .into_json()
+        .await
+        .unwrap();
+    assert_eq!(results.len(), 2);
+    assert_eq!(results[0].status, 200);
+    assert!(matches!(
+        &results[0].published,
+        Some(PublishedResponse::Question(question)) if question.title == "Join this scam"
+    ));
+    assert_eq!(results[1].moderation_uuid.0, unknown);
+    assert_eq!(results[1].status, 400);
+    assert_eq!(
+        serde_json::to_value(results[1].code).unwrap(),
+        "MODERATION_ITEM_NOT_FOUND"
+    );
+
+    let results: Vec<BulkModerationResult> = client
+        .post("/moderation/bulk")
+        .header(admin())
+        .json(&json!({ "moderation_uuids": [held[1]], "action": "delete" }))
+        .dispatch()
+        .await
+        .into_json()
+        .await
+        .unwrap();
+    assert_eq!((results[0].status, &results[0].published), (200, &None));
+    let queue: Paginated<ModerationItemResponse> = client
+        .get("/moderation/queue")
+        .header(admin())
+        .dispatch()
+        .await
+        .into_json()
+        .await
+        .unwrap();
+    assert!(queue.items.is_empty());
+
+    let response = client
+        .post("/moderation/bulk")
+        .header(admin())
+        .json(&json!({ "moderation_uuids": [], "action": "delete" }))
+        .dispatch()
+        .await;
+    let body = error_body(response, Status::UnprocessableEntity).await;
+    assert_eq!(body["errors"][0]["field"], "moderation_uuids");
+}
+
+#[rocket::async_test]
+async fn tags_should_be_suggested_from_tagged_questions() {
+    let mut config = AppConfig::from_env();
+    config.tagger = TaggerKind::TfIdf;
+    config.auto_tag_questions = true;
+    let client = Client::tracked(build_rocket(startup::in_memory(config).unwrap()))
+        .await
+        .unwrap();
+    for (title, tags) in [
+        (
+            "Refund for a duplicate invoice",
+            json!(["billing", "refunds"]),
+        ),
+        ("API token expired", json!(["api"])),
+    ] {
+        let response = client
+            .post("/question")
+            .json(&json!({ "title": title, "metadata": { "tags": tags } }))
+            .dispatch()
+            .await;
+        assert_eq!(response.status(), Status::Ok);
+    }
+
+    let response = client
+        .post("/questions/suggest-tags")
+        .json(&json!({ "title": "Where is my refund?", "description": "For the invoice" }))
+        .dispatch()
+        .await;
+    assert_eq!(response.status(), Status::Ok);
+    let suggested: SuggestedTagsResponse = response.into_json().await.unwrap();
+    assert_eq!(suggested.tags, ["billing", "refunds"]);
+
+    let question: QuestionResponse = client
+        .post("/question")
+        .json(&json!({ "title": "Expired API token" }))
+        .dispatch()
+        .await
+        .into_json()
+        .await
+        .unwrap();
+    assert_eq!(question.metadata, json!({ "tags": ["api"] }));
+
+    let question: QuestionResponse = client
+        .post("/question")
+        .json(&json!({ "title": "Expired API token", "metadata": { "tags": [] } }))
+        .dispatch()
+        .await
+        .into_json()
+        .await
+        .unwrap();
+    assert_eq!(
+        question.metadata,
+        json!({ "tags": [] }),
+        "tags of the author are kept"
+    );
+}
+
+// Prefixes each text with the language, counting the calls.
+struct TaggingTranslator(Arc<AtomicUsize>);
+
+#[async_trait::async_trait]
+impl Translator for TaggingTranslator {
+    async fn translate(
+        &self,
+        texts: &[String],
+        language: &str,
+    ) -> Result<Vec<String>, TranslationError> {
+        self.0.fetch_add(1, Ordering::SeqCst);
+        Ok(texts
+            .iter()
+            .map(|text| format!("[{}] {}", language, text))
+            .collect())
+    }
+}
+
+#[rocket::async_test]
+async fn questions_should_be_translated_once_per_version() {
+    let calls = Arc::new(AtomicUsize::new(0));
+    let store = MemoryStore::new();
+    let state = AppState {
+        translations: Some(Translations {
+            translator: Box::new(TaggingTranslator(calls.clone())),
+            translation_dao: Arc::new(InMemoryTranslationDao::new(store)),
+        }),
+        ..startup::in_memory(AppConfig::from_env()).unwrap()
+    };
+    let client = Client::tracked(build_rocket(state)).await.unwrap();
+    let question: QuestionResponse = client
+        .post("/question")
+        .json(&json!({ "title": "title", "description": "description" }))
+        .dispatch()
+        .await
+        .into_json()
+        .await
+        .unwrap();
+    let url = format!("/question/{}", question.question_uuid);
+
+    let untranslated: QuestionResponse =
+        client.get(&url).dispatch().await.into_json().await.unwrap();
+    assert_eq!(untranslated, question);
+
+    for _ in 0..2 {
+        let response = client.get(format!("{}?lang=ES", url)).dispatch().await;
+        assert_eq!(response.status(), Status::Ok);
+        let translated: QuestionResponse = response.into_json().await.unwrap();
+        assert_eq!(translated.title, "[es] title");
+        assert_eq!(translated.description.as_deref(), Some("[es] description"));
+        assert_eq!(translated.language.as_deref(), Some("es"));
+    }
+    assert_eq!(calls.load(Ordering::SeqCst), 1, "the translation is cached");
+
+    let response = client
+        .patch(&url)
+        .json(&json!({ "title": "edited", "version": question.version }))
+        .dispatch()
+        .await;
+    assert_eq!(response.status(), Status::Ok);
+    let translated: QuestionResponse = client
+        .get(format!("{}?lang=es", url))
+        .dispatch()
+        .await
+        .into_json()
+        .await
+        .unwrap();
+    assert_eq!(translated.title, "[es] edited");
+    assert_eq!(translated.description, None);
+    assert_eq!(calls.load(Ordering::SeqCst), 2);
+
+    let response = client.get(format!("{}?lang=spanish", url)).dispatch().await;
+    let body = error_body(response, Status::BadRequest).await;
+    assert_eq!(body["errors"][0]["field"], "lang");
+}
+
+#[rocket::async_test]
+async fn translation_should_fall_back_to_the_original() {
+    let client = client().await;
+    let question: QuestionResponse = client
+        .post("/question")
+        .json(&json!({ "title": "title" }))
+        .dispatch()
+        .await
+        .into_json()
+        .await
+        .unwrap();
+
+    let response = client
+        .get(format!("/question/{}?lang=es", question.question_uuid))
+        .dispatch()
+        .await;
+
+    assert_eq!(response.status(), Status::Ok);
+    let untranslated: QuestionResponse = response.into_json().await.unwrap();
+    assert_eq!(untranslated, question);
+}
+
+#[rocket::async_test]
+async fn submitted_translations_should_come_before_machine_ones() {
+    let calls = Arc::new(AtomicUsize::new(0));
+    let mut config = AppConfig::from_env();
+    config.admin_token = Some("secret".to_owned());
+    let state = AppState {
+        translations: Some(Translations {
+            translator: Box::new(TaggingTranslator(calls.clone())),
+            translation_dao: Arc::new(InMemoryTranslationDao::new(MemoryStore::new())),
+        }),
+        ..startup::in_memory(config).unwrap()
+    };
+    let client = Client::tracked(build_rocket(state)).await.unwrap();
+    let question: QuestionResponse = client
+        .post("/question")
+        .json(&json!({ "title": "title", "description": "description" }))
+        .dispatch()
+        .await
+        .into_json()
+        .await
+        .unwrap();
+    let url = format!("/question/{}", question.question_uuid);
+
+    let response = client
+        .put(format!("{}/translations/PT", url))
+        .json(&json!({ "title": "  título  " }))
+        .dispatch()
+        .await;
+    assert_eq!(response.status(), Status::Ok);
+    let submitted: TranslationResponse = response.into_json().await.unwrap();
+    assert_eq!(submitted.language, "pt");
+    assert_eq!(submitted.title, "título");
+    assert_eq!(submitted.question_version, question.version);
+
+    // `pt-BR` falls back to the `pt` translation.
+    let translated: QuestionResponse = client
+        .get(format!("{}?lang=pt-BR", url))
+        .dispatch()
+        .await
+        .into_json()
+        .await
+        .unwrap();
+    assert_eq!(translated.title, "título");
+    assert_eq!(translated.description, None);
+    assert_eq!(translated.language.as_deref(), Some("pt"));
+    let translated: QuestionResponse = client
+        .get(format!("{}?lang=es", url))
+        .dispatch()
+        .await
+        .into_json()
+        .await
+        .unwrap();
+    assert_eq!(translated.title, "[es] title");
+    assert_eq!(
+        calls.load(Ordering::SeqCst),
+        1,
+        "only es was machine translated"
+    );
+
+    let translations: Vec<TranslationResponse> = client
+        .get(format!("{}/translations", url))
+        .dispatch()
+        .await
+        .into_json()
+        .await
+        .unwrap();
+    assert_eq!(translations, [submitted]);
+
+    let response = client
+        .put(format!("{}/translations/pt", url))
+        .json(&json!({ "title": " " }))
+        .dispatch()
+        .await;
+    let body = error_body(response, Status::UnprocessableEntity).await;
+    assert_eq!(body["errors"][0]["field"], "title");
+    let response = client
+        .put(format!("{}/translations/portuguese", url))
+        .json(&json!({ "title": "título" }))
+        .dispatch()
+        .await;
+    assert_eq!(response.status(), Status::BadRequest);
+    let response = client
+        .put(format!("/question/{}/translations/pt", Uuid::new_v4()))
+        .json(&json!({ "title": "título" }))
+        .dispatch()
+        .await;
+    let body = error_body(response, Status::NotFound).await;
+    assert_eq!(body["code"], "QUESTION_NOT_FOUND");
+
+    let response = client
+        .delete(format!("{}/translations/pt", url))
+        .dispatch()
+        .await;
+    assert_eq!(response.status(), Status::Unauthorized);
+    let admin = || Header::new("Authorization", "Bearer secret");
+    let response = client
+        .delete(format!("{}/translations/pt", url))
+        .header(admin())
+        .dispatch()
+        .await;
+    assert_eq!(response.status(), Status::Ok);
+    let response = client
+        .delete(format!("{}/translations/pt", url))
+        .header(admin())
+        .dispatch()
+        .await;
+    let body = error_body(response, Status::NotFound).await;
+    assert_eq!(body["code"], "TRANSLATION_NOT_FOUND");
+}
+
+// Titles each page after its URL; `.pdf` links are not HTML.
+struct EchoFetcher;
+
+#[async_trait::async_trait]
+impl PreviewFetcher for EchoFetcher {
+    async fn fetch(&self, url: &str) -> Result<Option<LinkPreview>, PreviewError> {
+        Ok((!url.ends_with(".pdf")).then(|| LinkPreview {
+            url: url.to_owned(),
+            title: Some(format!("Page at {}", url)),
+            description: None,
+            image_url: None,
+            site_name: Some("Example".to_owned()),
+        }))
+    }
+}
+
+#[rocket::async_test]
+async fn links_in_questions_should_get_previews() {
+    let state = AppState {
+        link_previews: Some(LinkPreviews::spawn(
+            Box::new(EchoFetcher),
+            Arc::new(InMemoryLinkPreviewDao::new(MemoryStore::new())),
+        )),
+        ..startup::in_memory(AppConfig::from_env()).unwrap()
+    };
+    let client = Client::tracked(build_rocket(state)).await.unwrap();
+    let question: QuestionResponse = client
+        .post("/question")
+        .json(&json!({
+            "title": "Broken link",
+            "description": "https://example.com/docs and https://example.com/manual.pdf.",
+        }))
+        .dispatch()
+        .await
+        .into_json()
+        .await
+        .unwrap();
+    assert_eq!(question.link_previews, None, "only the detail has previews");
+
+    // Fetched in the background.
+    let url = format!("/question/{}", question.question_uuid);
+    let mut previews = vec![];
+    for _ in 0..50 {
+        let detail: QuestionResponse = client.get(&url).dispatch().await.into_json().await.unwrap();
+        previews = detail.link_previews.unwrap();
+        if !previews.is_empty() {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(10)).await;
+    }
+    assert_eq!(
+        previews,
+        [LinkPreviewResponse {
+            url: "https://example.com/docs".to_owned(),
+            title: Some("Page at https://example.com/docs".to_owned()),
+            description: None,
+            image_url: None,
+            site_name: Some("Example".to_owned()),
+        }]
+    );
+}
+
+#[rocket::async_test]
+async fn questions_should_be_searchable() {
+    let client = client().await;
+    for (title, description) in [
+        ("Refund for a duplicate invoice", "Charged twice"),
+        ("Billing address", "The invoice shows an old address"),
+        ("API tokens", "How do I rotate them?"),
+    ] {
+        let response = client
+            .post("/question")
+            .json(&json!({ "title": title, "description": description }))
+            .dispatch()
+            .await;
+        assert_eq!(response.status(), Status::Ok);
+    }
+
+    let found: Paginated<QuestionResponse> = client
+        .get("/questions/search?q=invoice")
+        .dispatch()
+        .await
+        .into_json()
+        .await
+        .unwrap();
+    let titles: Vec<&str> = found.items.iter().map(|q| q.title.as_str()).collect();
+    assert_eq!(
+        titles,
+        ["Refund for a duplicate invoice", "Billing address"]
+    );
+    let highlight = found.items[1].highlight.as_ref().unwrap();
+    assert_eq!(highlight.title, "Billing address");
+    assert_eq!(
+        highlight.description.as_deref(),
+        Some("The <mark>invoice</mark> shows an old address")
+    );
+
+    let found: Paginated<QuestionResponse> = client
+        .get("/questions/search?q=invoice%20-refund")
+        .dispatch()
+        .await
+        .into_json()
+        .await
+        .unwrap();
+    assert_eq!(found.items.len(), 1);
+    assert_eq!(found.items[0].title, "Billing address");
+
+    let found: SearchResponse = client
+        .get("/questions/search?q=invoise")
+        .dispatch()
+        .await
+        .into_json()
+        .await
+        .unwrap();
+    assert!(found.page.items.is_empty());
+    let suggested: Vec<String> = found
+        .suggestions
+        .unwrap()
+        .into_iter()
+        .map(|suggestion| suggestion.title)
+        .collect();
+    assert_eq!(suggested, ["Refund for a duplicate invoice"]);
+
+    let response = client.get("/questions/search?q=%20").dispatch().await;
+    assert_eq!(response.status(), Status::BadRequest);
+}
+
+#[rocket::async_test]
+async fn titles_should_autocomplete_prefixes_first() {
+    let client = client().await;
+    for title in [
+        "Refund for a duplicate invoice",
+        "Invoices by email",
+        "API tokens",
+    ] {
+        let response = client
+            .post("/question")
+            .json(&json!({ "title": title }))
+            .dispatch()
+            .await;
+        assert_eq!(response.status(), Status::Ok);
+    }
+    let client = &client;
+    let complete = |query: &'static str| async move {
+        let found: Vec<Value> = client
+            .get(format!("/questions/autocomplete?{}", query))
+            .dispatch()
+            .await
+            .into_json()
+            .await
+            .unwrap();
+        found
+    };
+
+    let found = complete("q=INVOI").await;
+    assert_eq!(
+        found,
+        [
+            json!({ "question_uuid": found[0]["question_uuid"], "title": "Invoices by email" }),
+            json!({ "question_uuid": found[1]["question_uuid"], "title": "Refund for a duplicate invoice" }),
+        ]
+    );
+    assert_eq!(complete("q=invoi&limit=1").await.len(), 1);
+    assert!(complete("q=in").await.is_empty());
+}
+
+#[derive(Clone, Default)]
+struct CapturingSink(Arc<Mutex<Vec<AnalyticsEvent>>>);
+
+#[rocket::async_trait]
+impl AnalyticsSink for CapturingSink {
+    async fn send(&self, events: &[AnalyticsEvent]) -> Result<(), AnalyticsError> {
+        self.0.lock().unwrap().extend_from_slice(events);
+        Ok(())
+    }
+}
+
+#[rocket::async_test]
+async fn successful_requests_should_emit_analytics_events() {
+    let sink = CapturingSink::default();
+    let state = AppState {
+        analytics: Some(Analytics::spawn(Box::new(sink.clone()), 1.0)),
+        ..startup::in_memory(AppConfig::from_env()).unwrap()
+    };
+    let client = Client::tracked(build_rocket(state)).await.unwrap();
+    let question: QuestionResponse = client
+        .post("/question")
+        .json(&json!({ "title": "title" }))
+        .dispatch()
+        .await
+        .into_json()
+        .await
+        .unwrap();
+
+    let response = client
+        .get(format!("/question/{}", question.question_uuid))
+        .header(Header::new("X-Anonymous-Id", "visitor-1"))
+        .dispatch()
+        .await;
+    assert_eq!(response.status(), Status::Ok);
+    let response = client
+        .get(format!("/question/{}", Uuid::new_v4()))
+        .dispatch()
+        .await;
+    assert_eq!(response.status(), Status::NotFound);
+
+    // Sent in the background.
+    for _ in 0..50 {
+        if !sink.0.lock().unwrap().is_empty() {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(10)).await;
+    }
+    let events = sink.0.lock().unwrap().clone();
+    assert_eq!(events.len(), 1, "failed requests should not be tracked");
+    assert_eq!(events[0].event, "question_viewed");
+    assert_eq!(events[0].anonymous_id, "visitor-1");
+    assert_eq!(
+        events[0].properties,
+        json!({ "question_uuid": question.question_uuid, "language": null })
+    );
+}
+
+// Accepts the token "solved" only.
+struct FixedCaptcha;
+
+#[rocket::async_trait]
+impl CaptchaVerifier for FixedCaptcha {
+    async fn verify(&self, token: &str, _: Option<&str>) -> Result<bool, CaptchaError> {
+        Ok(token == "solved")
+    }
+}
+
+#[rocket::async_test]
+async fn filtered_words_should_be_masked_or_rejected() {
+    let word_filter_dao = Arc::new(InMemoryWordFilterDao::new(MemoryStore::new()));
+    let client = |policy| {
+        let mut config = AppConfig::from_env();
+        config.admin_token = Some("secret".to_owned());
+        let state = AppState {
+            word_filter: Some(WordFilter::new(word_filter_dao.clone(), policy)),
+            ..startup::in_memory(config).unwrap()
+        };
+        Client::tracked(build_rocket(state))
+    };
+    let admin = || Header::new("Authorization", "Bearer secret");
+
+    let masking = client(WordFilterPolicy::Mask).await.unwrap();
+    let response = masking.put("/moderation/words/Scam").dispatch().await;
+    assert_eq!(response.status(), Status::Unauthorized);
+    let response = masking
+        .put("/moderation/words/Scam")
+        .header(admin())
+        .dispatch()
+        .await;
+    assert_eq!(response.status(), Status::Ok);
+    let response = masking
+        .put("/moderation/words/two%20words")
+        .header(admin())
+        .dispatch()
+        .await;
+    error_body(response, Status::BadRequest).await;
+    let words: Vec<String> = masking
+        .get("/moderation/words")
+        .header(admin())
+        .dispatch()
+        .await
+        .into_json()
+        .await
+        .unwrap();
+    assert_eq!(words, ["scam"]);
+
+    let question: QuestionResponse = masking
+        .post("/question")
+        .json(&json!({ "title": "Is this a SCAM?", "description": "scammers" }))
+        .dispatch()
+        .await
+        .into_json()
+        .await
+        .unwrap();
+    assert_eq!(question.title, "Is this a ****?");
+    assert_eq!(question.description.as_deref(), Some("scammers"));
+
+    let rejecting = client(WordFilterPolicy::Reject).await.unwrap();
+    let response = rejecting
+        .post("/question/with-answer")
+        .json(&json!({ "question": { "title": "title" }, "answer": "a scam" }))
+        .dispatch()
+        .await;
+    let body = error_body(response, Status::UnprocessableEntity).await;
+    assert_eq!(
+        body["errors"],
+        json!([{ "field": "answer", "message": "contains a blocked word" }])
+    );
+
+    let response = rejecting
+        .delete("/moderation/words/scam")
+        .header(admin())
+        .dispatch()
+        .await;
+    assert_eq!(response.status(), Status::Ok);
+    let response = rejecting
+        .post("/question")
+        .json(&json!({ "title": "a scam" }))
+        .dispatch()
+        .await;
+    assert_eq!(response.status(), Status::Ok);
+}
+
+#[rocket::async_test]
+async fn similar_answers_should_be_listed_or_rejected() {
+    let client = |policy| {
+        let mut config = AppConfig::from_env();
+        config.duplicate_answers = policy;
+        Client::tracked(build_rocket(startup::in_memory(config).unwrap()))
+    };
+    let answer =
+        |question_uuid| json!({ "question_uuid": question_uuid, "content": "Restart the router." });
+
+    let warning = client(DuplicateAnswerPolicy::Warn).await.unwrap();
+    let question: QuestionResponse = warning
+        .post("/question")
+        .json(&json!({ "title": "title" }))
+        .dispatch()
+        .await
+        .into_json()
+        .await
+        .unwrap();
+    let first: AnswerResponse = warning
+        .post("/answer")
+        .json(&answer(question.question_uuid))
+        .dispatch()
+        .await
+        .into_json()
+        .await
+        .unwrap();
+    assert_eq!(first.similar_answers, None);
+    let second: AnswerResponse = warning
+        .post("/answer")
+        .json(&answer(question.question_uuid))
+        .dispatch()
+        .await
+        .into_json()
+        .await
+        .unwrap();
+    let similar = second.similar_answers.unwrap();
+    assert_eq!(similar.len(), 1);
+    assert_eq!(similar[0].answer_uuid, first.answer_uuid);
+    assert_eq!(similar[0].similarity, 1.0);
+
+    let rejecting = client(DuplicateAnswerPolicy::Reject).await.unwrap();
+    let question: QuestionResponse = rejecting
+        .post("/question")
+        .json(&json!({ "title": "title" }))
+        .dispatch()
+        .await
+        .into_json()
+        .await
+        .unwrap();
+    let first: AnswerResponse = rejecting
+        .post("/answer")
+        .json(&answer(question.question_uuid))
+        .dispatch()
+        .await
+        .into_json()
+        .await
+        .unwrap();
+    let response = rejecting
+        .post("/answer")
+        .json(&answer(question.question_uuid))
+        .dispatch()
+        .await;
+    let body = error_body(response, Status::Conflict).await;
+    assert_eq!(body["code"], "DUPLICATE_ANSWER");
+    assert_eq!(
+        body["errors"],
+        json!([{
+            "field": "content",
+            "message": format!("is 100% alike answer {}", first.answer_uuid)
+        }])
+    );
+    let response = rejecting
+        .post("/answer?allow_duplicate=true")
+        .json(&answer(question.question_uuid))
+        .dispatch()
+        .await;
+    assert_eq!(response.status(), Status::Ok);
+}
+
+#[rocket::async_test]
+async fn email_replies_should_answer_the_question_they_reply_about() {
+    let disabled = client().await;
+    let response = disabled
+        .post("/answer/email")
+        .header(ContentType::Form)
+        .body("recipient=reply%2Bx.y%40reply.example.com&body-plain=Yes")
+        .dispatch()
+        .await;
+    error_body(response, Status::NotImplemented).await;
+
+    let mut config = AppConfig::from_env();
+    config.reply_email_domain = Some("reply.example.com".to_owned());
+    config.reply_email_secret = Some("secret".to_owned());
+    let client = Client::tracked(build_rocket(startup::in_memory(config).unwrap()))
+        .await
+        .unwrap();
+    let question: QuestionResponse = client
+        .post("/question")
+        .json(&json!({ "title": "title" }))
+        .dispatch()
+        .await
+        .into_json()
+        .await
+        .unwrap();
+    let address =
+        ReplyAddresses::new("reply.example.com", "secret").address(question.question_uuid);
+
+    let answer: AnswerResponse = client
+        .post("/answer/email")
+        .header(ContentType::Form)
+        .body(format!(
+            "to=Q%26A+%3C{}%3E&text={}",
+            address.replace('+', "%2B").replace('@', "%40"),
+            "Restart+it.%0D%0A%0D%0A%3E+%22title%22+has+a+new+answer%3A%0D%0A"
+        ))
+        .dispatch()
+        .await
+        .into_json()
+        .await
+        .unwrap();
+    assert_eq!(answer.question_uuid, question.question_uuid);
+    assert_eq!(answer.content, "Restart it.");
+
+    let forged = ReplyAddresses::new("reply.example.com", "guess").address(question.question_uuid);
+    let response = client
+        .post("/answer/email")
+        .header(ContentType::Form)
+        .body(format!(
+            "recipient={}&body-plain=Spam",
+            forged.replace('+', "%2B").replace('@', "%40")
+        ))
+        .dispatch()
+        .await;
+    let body = error_body(response, Status::UnprocessableEntity).await;
+    assert_eq!(body["errors"][0]["field"], "recipient");
+}
+
+#[rocket::async_test]
+async fn scim_should_provision_and_deactivate_users() {
+    let disabled = client().await;
+    let response = disabled.get("/scim/v2/Users").dispatch().await;
+    error_body(response, Status::Forbidden).await;
+
+    let mut config = AppConfig::from_env();
+    config.scim_token = Some("idp".to_owned());
+    let client = Client::tracked(build_rocket(startup::in_memory(config).unwrap()))
+        .await
+        .unwrap();
+    let idp = || Header::new("Authorization", "Bearer idp");
+    let scim = ContentType::new("application", "scim+json");
+    let ada = json!({
+        "schemas": ["urn:ietf:params:scim:schemas:core:2.0:User"],
+        "userName": "Ada@Example.com",
+        "externalId": "00u1",
+        "name": { "givenName": "Ada" },
+        "active": true
+    });
+
+    let response = client
+        .post("/scim/v2/Users")
+        .header(scim.clone())
+        .json(&ada)
+        .dispatch()
+        .await;
+    assert_eq!(response.status(), Status::Unauthorized);
+    let response = client
+        .post("/scim/v2/Users")
+        .header(idp())
+        .header(scim.clone())
+        .body(ada.to_string())
+        .dispatch()
+        .await;
+    assert_eq!(response.status(), Status::Created);
+    assert_eq!(response.content_type(), Some(scim.clone()));
+    let location = response.headers().get_one("Location").unwrap().to_owned();
+    let user: Value = response.into_json().await.unwrap();
+    assert_eq!(user["userName"], "ada@example.com");
+    assert_eq!(user["externalId"], "00u1");
+    assert_eq!(user["meta"]["location"], location);
+
+    let response = client
+        .post("/scim/v2/Users")
+        .header(idp())
+        .json(&ada)
+        .dispatch()
+        .await;
+    error_body(response, Status::Conflict).await;
+
+    let list: Value = client
+        .get("/scim/v2/Users?filter=userName%20eq%20%22ada%40example.com%22")
+        .header(idp())
+        .dispatch()
+        .await
+        .into_json()
+        .await
+        .unwrap();
+    assert_eq!(list["totalResults"], 1);
+    assert_eq!(list["Resources"][0]["id"], user["id"]);
+    let response = client
+        .get("/scim/v2/Users?filter=emails%20co%20%22ada%22")
+        .header(idp())
+        .dispatch()
+        .await;
+    error_body(response, Status::BadRequest).await;
+
+    let deactivated: Value = client
+        .patch(location.clone())
+        .header(idp())
+        .json(&json!({
+            "schemas": ["urn:ietf:params:scim:api:messages:2.0:PatchOp"],
+            "Operations": [{ "op": "replace", "path": "active", "value": false }]
+        }))
+        .dispatch()
+        .await
+        .into_json()
+        .await
+        .unwrap();
+    assert_eq!(deactivated["active"], false);
+    let replaced: Value = client
+        .put(location.clone())
+        .header(idp())
+        .json(&json!({ "userName": "ada@example.com", "displayName": "Ada L." }))
+        .dispatch()
+        .await
+        .into_json()
+        .await
+        .unwrap();
+    assert_eq!(replaced["active"], true);
+    assert_eq!(replaced["displayName"], "Ada L.");
+    assert!(replaced.get("externalId").is_none());
+}
+
+#[rocket::async_test]
+async fn rest_hooks_should_be_subscribed_sampled_and_unsubscribed() {
+    let mut config = AppConfig::from_env();
+    config.admin_token = Some("secret".to_owned());
+    let state = AppState {
+        rest_hook_dao: Some(Arc::new(InMemoryRestHookDao::new(MemoryStore::new()))),
+        ..startup::in_memory(config).unwrap()
+    };
+    let client = Client::tracked(build_rocket(state)).await.unwrap();
+    let admin = || Header::new("Authorization", "Bearer secret");
+
+    let response = client
+        .post("/hooks")
+        .json(&json!({ "target_url": "https://hooks.example.com/1", "event": "answer_created" }))
+        .dispatch()
+        .await;
+    assert_eq!(response.status(), Status::Unauthorized);
+    let response = client
+        .post("/hooks")
+        .header(admin())
+        .json(&json!({ "target_url": "http://hooks.example.com/1", "event": "answer_liked" }))
+        .dispatch()
+        .await;
+    let body = error_body(response, Status::UnprocessableEntity).await;
+    let fields: Vec<&str> = body["errors"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|error| error["field"].as_str().unwrap())
+        .collect();
+    assert_eq!(fields, ["target_url", "event"]);
+
+    let hook: RestHookResponse = client
+        .post("/hooks")
+        .header(admin())
+        .json(&json!({ "target_url": "https://hooks.example.com/1", "event": "answer_created" }))
+        .dispatch()
+        .await
+        .into_json()
+        .await
+        .unwrap();
+    assert_eq!(hook.event, "answer_created");
+
+    let samples: Vec<Value> = client
+        .get("/hooks/samples/answer_created")
+        .header(admin())
+        .dispatch()
+        .await
+        .into_json()
+        .await
+        .unwrap();
+    assert_eq!(samples.len(), 1);
+    assert_eq!(samples[0]["event_type"], "answer_created");
+    assert!(samples[0]["payload"]["content"].is_string());
+    let response = client
+        .get("/hooks/samples/answer_liked")
+        .header(admin())
+        .dispatch()
+        .await;
+    error_body(response, Status::BadRequest).await;
+
+    let unsubscribe = || client.delete(format!("/hooks/{}", hook.id)).header(admin());
+    assert_eq!(unsubscribe().dispatch().await.status(), Status::Ok);
+    let body = error_body(unsubscribe().dispatch().await, Status::NotFound).await;
+    assert_eq!(body["code"], "HOOK_NOT_FOUND");
+}
+
+#[rocket::async_test]
+async fn anonymous_creates_should_need_a_captcha_on_protected_routes() {
+    let mut config = AppConfig::from_env();
+    config.admin_token = Some("secret".to_owned());
+    let state = AppState {
+        captcha: Some(Captcha {
+            verifier: Box::new(FixedCaptcha),
+            routes: vec!["/question".to_owned()],
+        }),
+        ..startup::in_memory(config).unwrap()
+    };
+    let client = Client::tracked(build_rocket(state)).await.unwrap();
+    let create = |token: Option<&'static str>| {
+        let mut request = client.post("/question").json(&json!({ "title": "title" }));
+        if let Some(token) = token {
+            request = request.header(Header::new("X-Captcha-Token", token));
+        }
+        request.dispatch()
+    };
+
+    let body = error_body(create(None).await, Status::Forbidden).await;
+    assert_eq!(body["code"], "CAPTCHA_REQUIRED");
+    error_body(create(Some("forged")).await, Status::Forbidden).await;
+    let response = create(Some("solved")).await;
+    assert_eq!(response.status(), Status::Ok);
+    let question: QuestionResponse = response.into_json().await.unwrap();
+
+    let response = client
+        .post("/question")
+        .header(Header::new("Authorization", "Bearer secret"))
+        .json(&json!({ "title": "title" }))
+        .dispatch()
+        .await;
+    assert_eq!(response.status(), Status::Ok, "admins need no captcha");
+    let response = client
+        .post("/answer")
+        .json(&json!({ "question_uuid": question.question_uuid, "content": "content" }))
+        .dispatch()
+        .await;
+    assert_eq!(response.status(), Status::Ok, "/answer is not protected");
+}
+
+#[rocket::async_test]
+async fn admin_stats_should_summarize_recent_activity() {
+    let mut config = AppConfig::from_env();
+    config.admin_token = Some("secret".to_owned());
+    let client = Client::tracked(build_rocket(startup::in_memory(config).unwrap()))
+        .await
+        .unwrap();
+    let admin = || Header::new("Authorization", "Bearer secret");
+
+    let response = client.get("/admin/stats").dispatch().await;
+    assert_eq!(response.status(), Status::Unauthorized);
+
+    for title in ["Answered", "Unanswered"] {
+        let response = client
+            .post("/question")
+            .json(&json!({ "title": title }))
+            .dispatch()
+            .await;
+        assert_eq!(response.status(), Status::Ok);
+        if title == "Answered" {
+            let question: QuestionResponse = response.into_json().await.unwrap();
+            let response = client
+                .post("/answer")
+                .json(&json!({ "question_uuid": question.question_uuid, "content": "Yes" }))
+                .dispatch()
+                .await;
+            assert_eq!(response.status(), Status::Ok);
+        }
+    }
+
+    let stats: AdminStatsResponse = client
+        .get("/admin/stats?days=7")
+        .header(admin())
+        .dispatch()
+        .await
+        .into_json()
+        .await
+        .unwrap();
+    assert_eq!(stats.daily.len(), 1);
+    assert_eq!((stats.daily[0].questions, stats.daily[0].answers), (2, 1));
+    assert_eq!(stats.answer_rate, Some(0.5));
+    assert!(stats.median_first_answer_secs.unwrap() >= 0.0);
+    assert_eq!(stats.active_users, Some(0));
+
+    let response = client
+        .get("/admin/stats?days=0")
+        .header(admin())
+        .dispatch()
+        .await;
+    assert_eq!(response.status(), Status::BadRequest);
+}
+
+#[rocket::async_test]
+async fn merged_questions_should_redirect_to_their_target() {
+    let mut config = AppConfig::from_env();
+    config.admin_token = Some("secret".to_owned());
+    let client = Client::tracked(build_rocket(startup::in_memory(config).unwrap()))
+        .await
+        .unwrap();
+    let admin = || Header::new("Authorization", "Bearer secret");
+    let mut questions = vec![];
+    for title in ["How do I reset my password?", "Password reset"] {
+        let question: QuestionResponse = client
+            .post("/question")
+            .json(&json!({ "title": title }))
+            .dispatch()
+            .await
+            .into_json()
+            .await
+            .unwrap();
+        questions.push(question.question_uuid);
+    }
+    let (source, target) = (questions[1], questions[0]);
+    let response = client
+        .post("/answer")
+        .json(&json!({ "question_uuid": source, "content": "Use the link" }))
+        .dispatch()
+        .await;
+    assert_eq!(response.status(), Status::Ok);
+
+    let merge = format!("/admin/questions/{}/merge-into/{}", source, target);
+    let response = client.post(&merge).dispatch().await;
+    assert_eq!(response.status(), Status::Unauthorized);
+    let summary: MergeSummary = client
+        .post(&merge)
+        .header(admin())
+        .dispatch()
+        .await
+        .into_json()
+        .await
+        .unwrap();
+    assert_eq!((summary.answers, summary.followers), (1, 0));
+
+    let response = client.get(format!("/question/{}", source)).dispatch().await;
+    assert_eq!(response.status(), Status::PermanentRedirect);
+    assert_eq!(
+        response.headers().get_one("Location"),
+        Some(format!("/question/{}", target).as_str())
+    );
+    let answers: Paginated<AnswerResponse> = client
+        .get(format!("/answers/{}", target))
+        .dispatch()
+        .await
+        .into_json()
+        .await
+        .unwrap();
+    assert_eq!(answers.items.len(), 1);
+
+    let response = client.post(&merge).header(admin()).dispatch().await;
+    let body = error_body(response, Status::NotFound).await;
+    assert_eq!(body["code"], "QUESTION_NOT_FOUND");
+}
+
+#[rocket::async_test]
+async fn activity_should_list_recent_questions_and_answers() {
+    let client = client().await;
+
+    let question: QuestionResponse = client
+        .post("/question")
+        .json(&json!({ "title": "How do I reset my password?" }))
+        .dispatch()
+        .await
+        .into_json()
+        .await
+        .unwrap();
+    let response = client
+        .post("/answer")
+        .json(&json!({ "question_uuid": question.question_uuid, "content": "Use the link" }))
+        .dispatch()
+        .await;
+    assert_eq!(response.status(), Status::Ok);
+
+    let activity: Paginated<Activity> = client
+        .get("/activity?limit=1")
+        .dispatch()
+        .await
+        .into_json()
+        .await
+        .unwrap();
+    assert_eq!(activity.items.len(), 1);
+    assert_eq!(activity.items[0].kind, ActivityKind::AnswerPosted);
+    assert_eq!(
+        activity.items[0].question_title,
+        "How do I reset my password?"
+    );
+    assert_eq!(activity.items[0].content.as_deref(), Some("Use the link"));
+
+    let older: Paginated<Activity> = client
+        .get(format!(
+            "/activity?cursor={}",
+            activity.next_cursor.unwrap()
+        ))
+        .dispatch()
+        .await
+        .into_json()
+        .await
+        .unwrap();
+    assert_eq!((older.page, older.per_page), (2, 1));
+    assert_eq!(older.items.len(), 1);
+    assert_eq!(older.items[0].kind, ActivityKind::QuestionAsked);
+
+    let older: Paginated<Activity> = client
+        .get(format!("/activity?before={}", activity.items[0].id))
+        .dispatch()
+        .await
+        .into_json()
+        .await
+        .unwrap();
+    assert_eq!(older.items.len(), 1);
+    assert_eq!(older.next_cursor, None);
+}
@@ -0,0 +1,42 @@
+use question_answer_api_rust::{
+    empty_metadata,
+    persistence::memory::{InMemoryAnswerDao, InMemoryQuestionDao, MemoryStore},
+    Answer, AnswerDao, DBError, Entity, Question, QuestionDao, QuestionUuid,
+};
+
+#[rocket::async_test]
+async fn daos_should_be_usable_without_the_api() {
+    let store = MemoryStore::new();
+    let questions: Box<dyn QuestionDao + Send + Sync> =
+        Box::new(InMemoryQuestionDao::new(store.clone()));
+    let answers: Box<dyn AnswerDao + Send + Sync> = Box::new(InMemoryAnswerDao::new(store));
+
+    let question = questions
+        .create_question(Question {
+            title: "title".to_owned(),
+            description: Some("description".to_owned()),
+            metadata: empty_metadata(),
+        })
+        .await
+        .unwrap();
+    answers
+        .create_answer(Answer {
+            question_uuid: question.question_uuid,
+            content: "content".to_owned(),
+        })
+        .await
+        .unwrap();
+
+    assert_eq!(questions.get_questions().await.unwrap()[0].answer_count, 1);
+
+    let unknown = answers
+        .create_answer(Answer {
+            question_uuid: QuestionUuid::new_v4(),
+            content: "content".to_owned(),
+        })
+        .await;
+    assert!(matches!(
+        unknown,
+        Err(DBError::NotFound(Entity::Question, _))
+    ));
+}
@@ -0,0 +1,163 @@
+// The full app on a fresh, migrated Postgres database per test, as `#[sqlx::test]`
+// provides from `DATABASE_URL`.
+
+use question_answer_api_rust::{
+    build_rocket,
+    config::AppConfig,
+    dto::{AnswerResponse, Paginated, QuestionResponse},
+    startup,
+};
+use rocket::{
+    http::{Header, Status},
+    local::asynchronous::Client,
+};
+use serde_json::{json, Value};
+use sqlx::PgPool;
+
+async fn client(pool: PgPool) -> Client {
+    let state = startup::postgres(pool, AppConfig::from_env())
+        .await
+        .unwrap();
+    Client::tracked(build_rocket(state)).await.unwrap()
+}
+
+#[sqlx::test]
+async fn questions_should_go_from_creation_to_deletion(pool: PgPool) {
+    let client = client(pool).await;
+
+    let question: QuestionResponse = client
+        .post("/question")
+        .json(&json!({ "title": "title", "description": "description" }))
+        .dispatch()
+        .await
+        .into_json()
+        .await
+        .unwrap();
+    let answer: AnswerResponse = client
+        .post("/answer")
+        .json(&json!({ "question_uuid": question.question_uuid, "content": "content" }))
+        .dispatch()
+        .await
+        .into_json()
+        .await
+        .unwrap();
+
+    let questions: Paginated<QuestionResponse> = client
+        .get("/questions")
+        .dispatch()
+        .await
+        .into_json()
+        .await
+        .unwrap();
+    assert_eq!(questions.total, Some(1));
+    assert_eq!(questions.items[0].answer_count, 1);
+
+    let edited: AnswerResponse = client
+        .patch(format!("/answer/{}", answer.answer_uuid))
+        .header(Header::new("If-Match", format!("\"{}\"", answer.version)))
+        .json(&json!({ "content": "edited" }))
+        .dispatch()
+        .await
+        .into_json()
+        .await
+        .unwrap();
+    assert_eq!(edited.content, "edited");
+    assert_eq!(edited.version, answer.version + 1);
+
+    let response = client
+        .delete(format!("/question/{}", question.question_uuid))
+        .dispatch()
+        .await;
+    assert_eq!(response.status(), Status::Ok);
+
+    // The answers went with the question.
+    let answers: Paginated<AnswerResponse> = client
+        .get(format!("/answers/{}", question.question_uuid))
+        .dispatch()
+        .await
+        .into_json()
+        .await
+        .unwrap();
+    assert!(answers.items.is_empty());
+}
+
+#[sqlx::test]
+async fn database_errors_should_get_their_codes(pool: PgPool) {
+    let client = client(pool).await;
+
+    let response = client
+        .post("/answer")
+        .json(&json!({ "question_uuid": uuid::Uuid::new_v4(), "content": "content" }))
+        .dispatch()
+        .await;
+    assert_eq!(response.status(), Status::BadRequest);
+    let body: Value = response.into_json().await.unwrap();
+    assert_eq!(body["code"], "QUESTION_NOT_FOUND");
+
+    let question: QuestionResponse = client
+        .post("/question")
+        .json(&json!({ "title": "title", "description": "description" }))
+        .dispatch()
+        .await
+        .into_json()
+        .await
+        .unwrap();
+    let stale = json!({ "title": "new", "description": "new", "version": question.version + 1 });
+    let response = client
+        .patch(format!("/question/{}", question.question_uuid))
+        .json(&stale)
+        .dispatch()
+        .await;
+    assert_eq!(response.status(), Status::Conflict);
+    let body: Value = response.into_json().await.unwrap();
+    assert_eq!(body["code"], "CONFLICT");
+
+    let response = client
+        .patch(format!("/answer/{}", uuid::Uuid::new_v4()))
+        .json(&json!({ "content": "content", "version": 1 }))
+        .dispatch()
+        .await;
+    let body: Value = response.into_json().await.unwrap();
+    assert_eq!(body["code"], "ANSWER_NOT_FOUND");
+}
+
+#[sqlx::test]
+async fn cors_preflights_should_be_answered(pool: PgPool) {
+    let client = client(pool).await;
+
+    let response = client
+        .options("/question")
+        .header(Header::new("Origin", "https://example.com"))
+        .header(Header::new("Access-Control-Request-Method", "POST"))
+        .dispatch()
+        .await;
+
+    assert_eq!(response.status(), Status::Ok);
+    assert_eq!(
+        response.headers().get_one("Access-Control-Allow-Origin"),
+        Some("*")
+    );
+    let methods = response
+        .headers()
+        .get_one("Access-Control-Allow-Methods")
+        .unwrap();
+    for method in ["POST", "PUT", "PATCH", "DELETE"] {
+        assert!(methods.contains(method), "{} is not allowed", method);
+    }
+    assert_eq!(
+        response.headers().get_one("Access-Control-Allow-Headers"),
+        Some("*")
+    );
+    let exposed = response
+        .headers()
+        .get_one("Access-Control-Expose-Headers")
+        .unwrap();
+    for header in [
+        "ETag",
+        "X-RateLimit-Remaining",
+        "Retry-After",
+        "X-Request-Id",
+    ] {
+        assert!(exposed.contains(header), "{} is not exposed", header);
+    }
+}